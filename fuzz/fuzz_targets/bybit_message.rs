@@ -0,0 +1,31 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tokio::sync::{broadcast, watch};
+
+use rust_arb_bot::adapters::bybit::parse_message;
+use rust_arb_bot::adapters::feed::CexFeed;
+
+// one message per line, so halts and recoveries get exercised too
+fuzz_target!(|data: &[u8]| {
+    let (tx, rx) = watch::channel(None);
+    let (risk_tx, _risk_rx) = broadcast::channel(64);
+    let mut feed = CexFeed::new("bybit", "HYPEUSDT");
+
+    for line in data.split(|b| *b == b'\n') {
+        let Ok(text) = std::str::from_utf8(line) else {
+            continue;
+        };
+        let Some(price) = parse_message(text) else {
+            continue;
+        };
+        assert!(price.bid.is_finite() && price.bid >= 0.0);
+        assert!(price.ask.is_finite() && price.ask >= 0.0);
+
+        feed.update(price, &tx, &risk_tx);
+        match &*rx.borrow() {
+            Some(price) => assert!(!feed.is_halted() && price.bid > 0.0 && price.ask > 0.0),
+            None => assert!(feed.is_halted()),
+        }
+    }
+});
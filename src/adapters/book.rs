@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::adapters::feed::parse_level;
+use crate::arbitrage::{PriceData, QuoteSource};
+
+/// A price level: price and size in base.
+pub type Level = (f64, f64);
+
+/// Local copy of a CEX order book kept from a snapshot and the deltas after
+/// it, for depth subscriptions whose updates only carry changed levels.
+/// Walked for the price a given size executes at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBook {
+    // keyed by the bits of the price, which sort like the price itself for
+    // the positive finite prices that get in
+    bids: BTreeMap<u64, Level>,
+    asks: BTreeMap<u64, Level>,
+    // id of the last update applied, for venues numbering them
+    update_id: Option<u64>,
+    // when the exchange sent the last update, see `stamp`
+    source: Option<QuoteSource>,
+}
+
+impl OrderBook {
+    /// The book in a REST snapshot whose `bids` and `asks` keys hold
+    /// `[price, size]` string pairs.
+    pub fn from_json(data: &Value, bids: &str, asks: &str) -> Self {
+        let levels = |side: &str| -> Vec<Level> {
+            data[side]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|level| level.as_array())
+                .map(|level| (parse_level(level.first()), parse_level(level.get(1))))
+                .collect()
+        };
+        let mut book = Self::default();
+        book.apply_snapshot(&levels(bids), &levels(asks));
+        book
+    }
+
+    /// Dates the book to the exchange timestamp of the last update applied,
+    /// carried by the prices read off it.
+    pub fn stamp(&mut self, source: Option<QuoteSource>) {
+        self.source = source;
+    }
+
+    /// Replaces the whole book.
+    pub fn apply_snapshot(&mut self, bids: &[Level], asks: &[Level]) {
+        self.bids.clear();
+        self.asks.clear();
+        self.apply_delta(bids, asks);
+    }
+
+    /// Sets the given levels, a size of 0 removing the level.
+    pub fn apply_delta(&mut self, bids: &[Level], asks: &[Level]) {
+        for (side, levels) in [(&mut self.bids, bids), (&mut self.asks, asks)] {
+            for &(px, sz) in levels.iter().filter(|(px, _)| px.is_finite() && *px > 0.0) {
+                if sz > 0.0 && sz.is_finite() {
+                    side.insert(px.to_bits(), (px, sz));
+                } else {
+                    side.remove(&px.to_bits());
+                }
+            }
+        }
+    }
+
+    /// Applies a snapshot or delta numbered `update_id`. Deltas must follow
+    /// the last update without a gap, as a missed one leaves the book wrong
+    /// until the next snapshot; the venue has to be resubscribed then.
+    pub fn apply_update(&mut self, snapshot: bool, update_id: Option<u64>, bids: &[Level], asks: &[Level]) -> Result<()> {
+        if snapshot {
+            self.apply_snapshot(bids, asks);
+        } else {
+            match (self.update_id, update_id) {
+                (Some(last), Some(id)) if id != last + 1 => bail!("book update {} does not follow {}", id, last),
+                (None, Some(id)) => bail!("book delta {} before any snapshot", id),
+                _ => {}
+            }
+            self.apply_delta(bids, asks);
+        }
+        self.update_id = update_id;
+        Ok(())
+    }
+
+    /// Average price of taking `size` base off the asks (buying) or the
+    /// bids, `None` for a side too thin to fill it.
+    pub fn vwap(&self, is_buy: bool, size: f64) -> Option<f64> {
+        self.walk(is_buy, size).filter(|(_, filled)| *filled).map(|(px, _)| px)
+    }
+
+    /// What selling and buying `size` base would average, 0 for an empty
+    /// side. At the top of book for a size within the best levels. A side
+    /// too thin for `size` prices what it has at its deepest level and
+    /// marks the price thin, not to be traded on.
+    pub fn executable(&self, size: f64) -> PriceData {
+        let (bid, bid_filled) = self.walk(false, size).unwrap_or((0.0, true));
+        let (ask, ask_filled) = self.walk(true, size).unwrap_or((0.0, true));
+        PriceData { bid, ask, source: self.source, thin: !bid_filled || !ask_filled }
+    }
+
+    // average price of taking `size` off a side, the rest beyond the book
+    // at its deepest level, and whether the side had enough for it
+    fn walk(&self, is_buy: bool, size: f64) -> Option<(f64, bool)> {
+        let levels: Box<dyn Iterator<Item = &Level>> = if is_buy { Box::new(self.asks()) } else { Box::new(self.bids()) };
+        let (mut left, mut cost, mut last) = (size, 0.0, None);
+        for &(px, sz) in levels {
+            let take = sz.min(left);
+            cost += take * px;
+            left -= take;
+            last = Some(px);
+            if left <= 0.0 {
+                break;
+            }
+        }
+        let last = last?;
+        // rounding leaves dust of a size the levels add up to
+        let filled = left <= size * 1e-9;
+        Some((if size > 0.0 { (cost + left.max(0.0) * last) / size } else { last }, filled))
+    }
+
+    /// Bids from the best down.
+    pub fn bids(&self) -> impl Iterator<Item = &Level> {
+        self.bids.values().rev()
+    }
+
+    /// Asks from the best up.
+    pub fn asks(&self) -> impl Iterator<Item = &Level> {
+        self.asks.values()
+    }
+
+    /// Best bid and ask, 0 for an empty side.
+    pub fn top(&self) -> PriceData {
+        PriceData {
+            bid: self.bids().next().map_or(0.0, |(px, _)| *px),
+            ask: self.asks().next().map_or(0.0, |(px, _)| *px),
+            source: self.source,
+            thin: false,
+        }
+    }
+}
@@ -1,100 +1,232 @@
-use std::time::Duration;
-
-use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
-use log::{error, info};
+use anyhow::{Result, anyhow, bail};
 use serde_json::{Value, json};
-use tokio::sync::watch::Sender;
-use tokio::time::sleep;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
-
-use crate::arbitrage::PriceData;
-use crate::settings;
-
-pub async fn run_bybit_listener(tx: Sender<Option<PriceData>>) {
-    loop {
-        match connect_and_subscribe(tx.clone()).await {
-            Ok(_) => info!("bybit ws connection closed normally"),
-            Err(e) => error!("bybit ws connection error: {}", e),
-        }
-
-        info!("reconnecting in 5 seconds...");
-        sleep(Duration::from_secs(5)).await;
+
+use crate::adapters::book::{Level, OrderBook};
+use crate::adapters::feed::{exchange_ts, parse_level};
+use crate::adapters::{Decoder, ExchangeAdapter};
+use crate::arbitrage::{PriceData, QuoteSource};
+use crate::markets::{MarketMeta, step_precision};
+use crate::settings::{self, Settings};
+use crate::venue_status::MaintenanceWindow;
+
+/// Bybit v5 public spot stream, keeping an `orderbook.{depth}` book of
+/// `bybit_depth` levels and pricing `exec_base_size` off it.
+pub struct Bybit;
+
+impl ExchangeAdapter for Bybit {
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+
+    fn ticker(&self, cfg: &Settings) -> String {
+        cfg.bybit_ticker.clone()
+    }
+
+    fn endpoint(&self, cfg: &Settings) -> String {
+        cfg.bybit_ws_endpoint.clone()
+    }
+
+    fn subscribe(&self, cfg: &Settings, ticker: &str) -> Vec<Value> {
+        vec![json!({
+            "op": "subscribe",
+            "args": [format!("orderbook.{}.{}", cfg.bybit_depth(ticker), ticker)]
+        })]
+    }
+
+    fn decoder(&self, cfg: &Settings, ticker: &str) -> Decoder {
+        let mut book = OrderBook::default();
+        // an inverse symbol's base is the pair's quote, it is priced at the
+        // top of book
+        let size = if cfg.is_inverse(ticker) { 0.0 } else { cfg.exec_base_size };
+        Box::new(move |text| {
+            let Some(update) = parse_book_update(text) else {
+                return Ok(None);
+            };
+            book.apply_update(update.snapshot, update.update_id, &update.bids, &update.asks)?;
+            book.stamp(update.source);
+            Ok(Some(book.executable(size)))
+        })
     }
 }
 
-async fn connect_and_subscribe(tx: Sender<Option<PriceData>>) -> Result<()> {
-    let cfg = settings::Settings::load()?;
-
-    let (ws_stream, _) = connect_async(&cfg.bybit_ws_endpoint).await?;
-    info!("connected to bybit webSocket: {}", cfg.bybit_ws_endpoint);
-
-    let (mut write, mut read) = ws_stream.split();
-
-    let subscribe_msg = json!({
-        "op": "subscribe",
-        "args": [format!("orderbook.1.{}", cfg.bybit_ticker)]
-    });
-
-    write.send(Message::Text(subscribe_msg.to_string())).await?;
-    info!("subscribed to {} orderbook", cfg.bybit_ticker);
-
-    // Track previous price to avoid duplicate updates
-    let mut last_price: Option<PriceData> = None;
-
-    while let Some(msg) = read.next().await {
-        match msg? {
-            Message::Text(text) => {
-                if let Ok(data) = serde_json::from_str::<Value>(&text) {
-                    // skip subscription confirmations
-                    if data.get("op").is_some() {
-                        continue;
-                    }
-
-                    let Some(orderbook_data) = data.get("data") else {
-                        continue;
-                    };
-
-                    let bid = orderbook_data
-                        .get("b")
-                        .and_then(|b| b.as_array())
-                        .and_then(|bids| bids.first())
-                        .and_then(|bid| bid.as_array())
-                        .and_then(|bid| bid.first())
-                        .and_then(|p| p.as_str())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
-
-                    let ask = orderbook_data
-                        .get("a")
-                        .and_then(|a| a.as_array())
-                        .and_then(|asks| asks.first())
-                        .and_then(|ask| ask.as_array())
-                        .and_then(|ask| ask.first())
-                        .and_then(|p| p.as_str())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
-
-                    let price_data = PriceData { bid, ask };
-
-                    // Only send update if price has changed
-                    if last_price.as_ref() != Some(&price_data) {
-                        if let Err(e) = tx.send(Some(price_data.clone())) {
-                            error!("failed to send CEX price update: {}", e);
-                        }
-
-                        info!("⚠️ BYBIT {}: bid ${:.2} ask ${:.2}", cfg.bybit_ticker, bid, ask);
-                        last_price = Some(price_data);
-                    }
-                }
-            }
-            Message::Ping(ping) => write.send(Message::Pong(ping)).await?,
-            Message::Close(_) => {
-                break;
-            }
-            _ => {}
-        }
+/// Top of book from one bybit `orderbook.1` message, `None` for anything
+/// that isn't a book update. An empty side reads as a price of 0.
+pub fn parse_message(text: &str) -> Option<PriceData> {
+    let data = serde_json::from_str::<Value>(text).ok()?;
+
+    // skip subscription confirmations and anything off the orderbook topic
+    if data.get("op").is_some() {
+        return None;
+    }
+    if !data.get("topic").and_then(|t| t.as_str()).is_some_and(|t| t.starts_with("orderbook.")) {
+        return None;
+    }
+
+    Some(top_of_book(data.get("data")?).with_source(exchange_ts(data.get("ts"))))
+}
+
+/// One message of an `orderbook.{depth}` stream. Level 1 streams only send
+/// snapshots; deeper ones send a snapshot, then deltas carrying the changed
+/// levels with a size of 0 for removed ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookUpdate {
+    pub snapshot: bool,
+    // `u`, consecutive from the snapshot on
+    pub update_id: Option<u64>,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+    // the message's `ts`
+    pub source: Option<QuoteSource>,
+}
+
+/// The book update in one bybit message, `None` for anything that isn't
+/// one.
+pub fn parse_book_update(text: &str) -> Option<BookUpdate> {
+    let data = serde_json::from_str::<Value>(text).ok()?;
+    if !data.get("topic").and_then(|t| t.as_str()).is_some_and(|t| t.starts_with("orderbook.")) {
+        return None;
     }
 
-    Ok(())
+    let book = data.get("data")?;
+    let levels = |side: &str| -> Vec<Level> {
+        book.get(side)
+            .and_then(|levels| levels.as_array())
+            .map(|levels| {
+                levels
+                    .iter()
+                    .filter_map(|level| level.as_array())
+                    .map(|level| (parse_level(level.first()), parse_level(level.get(1))))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Some(BookUpdate {
+        snapshot: data.get("type").and_then(|t| t.as_str()) != Some("delta"),
+        update_id: book.get("u").and_then(|u| u.as_u64()),
+        bids: levels("b"),
+        asks: levels("a"),
+        source: exchange_ts(data.get("ts")),
+    })
+}
+
+/// One top-of-book snapshot from the REST API, for one-shot tools.
+pub async fn fetch_snapshot(cfg: &settings::Settings) -> Result<PriceData> {
+    let book = rest_get(cfg, "/v5/market/orderbook", &[("limit", "1")]).await?;
+    let top = top_of_book(&book);
+    Ok(if cfg.is_inverse(&cfg.bybit_ticker) { top.inverted() } else { top })
+}
+
+/// `limit` levels a side of the book from the REST API, for sizing trades
+/// against its depth.
+pub async fn fetch_book(cfg: &settings::Settings, limit: u32) -> Result<OrderBook> {
+    let data = rest_get(cfg, "/v5/market/orderbook", &[("limit", limit.to_string().as_str())]).await?;
+    Ok(OrderBook::from_json(&data, "b", "a"))
+}
+
+/// Trading status of `bybit_ticker`, e.g. "Trading".
+pub async fn fetch_symbol_status(cfg: &settings::Settings) -> Result<String> {
+    let info = rest_get(cfg, "/v5/market/instruments-info", &[]).await?;
+    let instrument = info["list"]
+        .as_array()
+        .and_then(|list| list.first())
+        .ok_or_else(|| anyhow!("no bybit spot symbol {}", cfg.bybit_ticker))?;
+
+    Ok(instrument["status"].as_str().unwrap_or("unknown").to_string())
+}
+
+/// Maintenance bybit has announced and not finished yet.
+pub async fn fetch_maintenance(cfg: &settings::Settings) -> Result<Vec<MaintenanceWindow>> {
+    let status = rest_get(cfg, "/v5/system/status", &[]).await?;
+    Ok(maintenance_windows(&status))
+}
+
+/// The scheduled and ongoing windows of a `/v5/system/status` result,
+/// whose begin and end are unix ms strings.
+pub fn maintenance_windows(status: &Value) -> Vec<MaintenanceWindow> {
+    let ms = |entry: &Value, key: &str| entry[key].as_str().and_then(|v| v.parse::<u64>().ok());
+    status["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|entry| matches!(entry["state"].as_str(), Some("scheduled" | "ongoing")))
+        .filter_map(|entry| {
+            Some(MaintenanceWindow {
+                title: entry["title"].as_str().unwrap_or("maintenance").to_string(),
+                begin_ms: ms(entry, "begin")?,
+                end_ms: ms(entry, "end"),
+            })
+        })
+        .collect()
+}
+
+/// Tick size, lot size and minimums of `bybit_ticker`.
+pub async fn fetch_market(cfg: &settings::Settings) -> Result<MarketMeta> {
+    let info = rest_get(cfg, "/v5/market/instruments-info", &[]).await?;
+    let instrument = info["list"]
+        .as_array()
+        .and_then(|list| list.first())
+        .ok_or_else(|| anyhow!("no bybit spot symbol {}", cfg.bybit_ticker))?;
+    market_meta(instrument)
+}
+
+/// Market metadata of a v5 spot instrument.
+pub fn market_meta(instrument: &Value) -> Result<MarketMeta> {
+    let field = |section: &str, key: &str| {
+        instrument[section][key]
+            .as_str()
+            .ok_or_else(|| anyhow!("bybit instrument has no {}.{}", section, key))
+    };
+    let number = |section: &str, key: &str| -> Result<f64> { Ok(field(section, key)?.parse()?) };
+
+    let tick_size = field("priceFilter", "tickSize")?;
+    let lot_size = field("lotSizeFilter", "basePrecision")?;
+    Ok(MarketMeta {
+        symbol: instrument["symbol"].as_str().unwrap_or_default().to_string(),
+        tick_size: tick_size.parse()?,
+        lot_size: lot_size.parse()?,
+        min_qty: number("lotSizeFilter", "minOrderQty")?,
+        min_notional: number("lotSizeFilter", "minOrderAmt")?,
+        price_precision: step_precision(tick_size),
+        qty_precision: step_precision(lot_size),
+        sig_figs: None,
+    })
+}
+
+// public v5 spot endpoint for `bybit_ticker`, returning its `result`
+async fn rest_get(cfg: &settings::Settings, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+    let body: Value = reqwest::Client::new()
+        .get(format!("{}{}", cfg.bybit_rest_endpoint, path))
+        .query(&[("category", "spot"), ("symbol", cfg.bybit_ticker.as_str())])
+        .query(query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if let Some(code) = body.get("retCode").and_then(|c| c.as_i64())
+        && code != 0
+    {
+        bail!("bybit {} {}: {} ({})", path, cfg.bybit_ticker, body["retMsg"], code);
+    }
+
+    body.get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("bybit {} response has no result", path))
+}
+
+// best levels of a book in bybit's `b`/`a` layout, shared by WS and REST
+fn top_of_book(book: &Value) -> PriceData {
+    let best = |side: &str| {
+        let level = book
+            .get(side)
+            .and_then(|levels| levels.as_array())
+            .and_then(|levels| levels.first())
+            .and_then(|level| level.as_array())
+            .and_then(|level| level.first());
+        parse_level(level)
+    };
+
+    PriceData::new(best("b"), best("a"))
 }
@@ -0,0 +1,205 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow, bail};
+use log::error;
+use serde_json::Value;
+
+use crate::adapters::feed::exchange_ts;
+use crate::adapters::{Decoder, ExchangeAdapter};
+use crate::arbitrage::PriceData;
+use crate::settings::{Settings, WsVenue};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(usize),
+}
+
+/// Where a value sits in a json message, JSONPath-like: fields joined by
+/// dots and array elements in brackets, e.g. `data[0].bids[0][0]`, with an
+/// optional leading `$`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPath {
+    steps: Vec<Step>,
+}
+
+impl FieldPath {
+    pub fn parse(path: &str) -> Result<Self> {
+        let rest = path.strip_prefix('$').unwrap_or(path);
+        let rest = rest.strip_prefix('.').unwrap_or(rest);
+        if rest.is_empty() {
+            bail!("empty field path {:?}", path);
+        }
+
+        let mut steps = Vec::new();
+        for part in rest.split('.') {
+            let (field, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if field.is_empty() && indices.is_empty() {
+                bail!("empty field in path {:?}", path);
+            }
+            if !field.is_empty() {
+                steps.push(Step::Field(field.to_string()));
+            }
+            while !indices.is_empty() {
+                let (index, next) = indices
+                    .strip_prefix('[')
+                    .and_then(|i| i.split_once(']'))
+                    .ok_or_else(|| anyhow!("unclosed index in path {:?}", path))?;
+                let index = index.parse().map_err(|_| anyhow!("bad index {:?} in path {:?}", index, path))?;
+                steps.push(Step::Index(index));
+                indices = next;
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// The value at this path in `message`, if there is one.
+    pub fn get<'a>(&self, message: &'a Value) -> Option<&'a Value> {
+        self.steps.iter().try_fold(message, |value, step| match step {
+            Step::Field(field) => value.get(field),
+            Step::Index(index) => value.get(index),
+        })
+    }
+}
+
+/// A `path=value` test on a message, met when the value at `path` reads as
+/// `value`: strings as they are, anything else as json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    path: FieldPath,
+    expected: String,
+}
+
+impl Condition {
+    pub fn parse(condition: &str) -> Result<Self> {
+        let (path, expected) = condition
+            .split_once('=')
+            .ok_or_else(|| anyhow!("condition {:?} is not path=value", condition))?;
+        Ok(Self { path: FieldPath::parse(path.trim())?, expected: expected.trim().to_string() })
+    }
+
+    pub fn matches(&self, message: &Value) -> bool {
+        match self.path.get(message) {
+            Some(Value::String(s)) => *s == self.expected,
+            Some(value) => self.expected.parse::<Value>().is_ok_and(|expected| expected == *value),
+            None => false,
+        }
+    }
+
+    fn for_ticker(&self, ticker: &str) -> Self {
+        Self { path: self.path.clone(), expected: self.expected.replace("{ticker}", ticker) }
+    }
+}
+
+/// A venue read the way its `WsVenue` config describes, for the long tail
+/// of exchanges whose stream is one subscription and a flat ticker
+/// message. Top of book only: venues worth pricing by depth get an adapter
+/// of their own.
+pub struct DeclarativeAdapter {
+    venue: &'static str,
+    spec: WsVenue,
+    only_when: Vec<Condition>,
+    error_when: Vec<Condition>,
+    bid: FieldPath,
+    ask: FieldPath,
+    timestamp: Option<FieldPath>,
+    keepalive: Option<(Duration, &'static str)>,
+}
+
+impl DeclarativeAdapter {
+    /// Checks every path, condition and subscription template of `spec`.
+    pub fn new(spec: &WsVenue) -> Result<Self> {
+        let conditions = |conditions: &[String]| conditions.iter().map(|c| Condition::parse(c)).collect::<Result<Vec<_>>>();
+        for template in &spec.subscribe {
+            serde_json::from_str::<Value>(&fill(template, &spec.ticker))
+                .map_err(|e| anyhow!("{} subscription {:?} is not json: {}", spec.name, template, e))?;
+        }
+        // adapters live as long as the bot, and the trait hands out
+        // static names
+        let leak = |s: &str| -> &'static str { Box::leak(s.to_string().into_boxed_str()) };
+        let keepalive_message = spec.keepalive_message.as_deref().unwrap_or("ping");
+        Ok(Self {
+            venue: leak(&spec.name),
+            spec: spec.clone(),
+            only_when: conditions(&spec.only_when)?,
+            error_when: conditions(&spec.error_when)?,
+            bid: FieldPath::parse(&spec.bid)?,
+            ask: FieldPath::parse(&spec.ask)?,
+            timestamp: spec.timestamp.as_deref().map(FieldPath::parse).transpose()?,
+            keepalive: spec.keepalive_secs.map(|secs| (Duration::from_secs(secs), leak(keepalive_message))),
+        })
+    }
+}
+
+impl ExchangeAdapter for DeclarativeAdapter {
+    fn venue(&self) -> &'static str {
+        self.venue
+    }
+
+    fn ticker(&self, _cfg: &Settings) -> String {
+        self.spec.ticker.clone()
+    }
+
+    fn endpoint(&self, _cfg: &Settings) -> String {
+        self.spec.endpoint.clone()
+    }
+
+    fn subscribe(&self, _cfg: &Settings, ticker: &str) -> Vec<Value> {
+        self.spec
+            .subscribe
+            .iter()
+            .filter_map(|template| match serde_json::from_str(&fill(template, ticker)) {
+                Ok(msg) => Some(msg),
+                Err(e) => {
+                    error!("{} subscription for {} is not json: {}", self.venue, ticker, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// A message meeting `only_when` and holding either price is a quote,
+    /// a side it lacks or that isn't a positive price reading as empty.
+    /// Anything else, including text that isn't json, is skipped.
+    fn decoder(&self, _cfg: &Settings, ticker: &str) -> Decoder {
+        let venue = self.venue;
+        let only_when: Vec<_> = self.only_when.iter().map(|c| c.for_ticker(ticker)).collect();
+        let error_when: Vec<_> = self.error_when.iter().map(|c| c.for_ticker(ticker)).collect();
+        let (bid, ask, timestamp) = (self.bid.clone(), self.ask.clone(), self.timestamp.clone());
+        Box::new(move |text| {
+            let Ok(message) = serde_json::from_str::<Value>(text) else {
+                return Ok(None);
+            };
+            if !error_when.is_empty() && error_when.iter().all(|c| c.matches(&message)) {
+                bail!("{} sent an error: {}", venue, text);
+            }
+            if !only_when.iter().all(|c| c.matches(&message)) {
+                return Ok(None);
+            }
+            let (bid, ask) = (bid.get(&message), ask.get(&message));
+            if bid.is_none() && ask.is_none() {
+                return Ok(None);
+            }
+            let source = timestamp.as_ref().and_then(|path| exchange_ts(path.get(&message)));
+            Ok(Some(PriceData::new(price(bid), price(ask)).with_source(source)))
+        })
+    }
+
+    fn keepalive(&self) -> Option<(Duration, &'static str)> {
+        self.keepalive
+    }
+}
+
+// fills `{ticker}` and `{time}`, unix seconds, into a subscription
+fn fill(template: &str, ticker: &str) -> String {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    template.replace("{ticker}", ticker).replace("{time}", &time.to_string())
+}
+
+// venues send prices as numbers or decimal strings
+fn price(value: Option<&Value>) -> f64 {
+    value
+        .and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok()))
+        .filter(|p| p.is_finite() && *p > 0.0)
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,96 @@
+use log::{error, info};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio::sync::watch::Sender;
+
+use crate::arbitrage::{PriceData, QuoteSource};
+use crate::risk::RiskEvent;
+
+/// Publishes the top of book of one CEX venue. Repeated prices are dropped,
+/// and while the book is empty the price is withdrawn and the venue
+/// reported halted under its label.
+pub struct CexFeed {
+    venue: String,
+    ticker: String,
+    last_price: Option<PriceData>,
+    halted: bool,
+    inverted: bool,
+}
+
+impl CexFeed {
+    pub fn new(venue: &str, ticker: &str) -> Self {
+        Self {
+            venue: venue.to_string(),
+            ticker: ticker.to_string(),
+            last_price: None,
+            halted: false,
+            inverted: false,
+        }
+    }
+
+    /// Flips every price of a symbol quoted the other way round than the
+    /// pair, see `inverse_tickers`.
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn update(
+        &mut self,
+        price: PriceData,
+        tx: &Sender<Option<PriceData>>,
+        risk_tx: &broadcast::Sender<RiskEvent>,
+    ) {
+        let price = if self.inverted { price.inverted() } else { price };
+        // an emptied book means the pair stopped trading
+        if (price.bid == 0.0 || price.ask == 0.0) != self.halted {
+            self.halted = !self.halted;
+            let _ = risk_tx.send(RiskEvent::CexHalt { venue: self.venue.clone(), halted: self.halted });
+        }
+        if self.halted {
+            tx.send_if_modified(|price| price.take().is_some());
+            self.last_price = None;
+            return;
+        }
+
+        // Only send update if price has changed; a repeat still freshens
+        // the quote's timestamp, without waking the engines
+        if self.last_price.as_ref().is_some_and(|last| last.same_quote(&price)) {
+            tx.send_if_modified(|last| {
+                if let Some(last) = last {
+                    last.source = price.source;
+                }
+                false
+            });
+        } else {
+            if let Err(e) = tx.send(Some(price.clone())) {
+                error!("failed to send CEX price update: {}", e);
+            }
+
+            info!("⚠️ {} {}: bid ${:.2} ask ${:.2}", self.venue.to_uppercase(), self.ticker, price.bid, price.ask);
+            self.last_price = Some(price);
+        }
+    }
+}
+
+/// A price level as exchanges send it, a decimal string. Anything else,
+/// including "NaN", "inf" and non-positive prices, reads as an empty level.
+pub fn parse_level(value: Option<&Value>) -> f64 {
+    value
+        .and_then(|p| p.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|p| p.is_finite() && *p > 0.0)
+        .unwrap_or(0.0)
+}
+
+/// An exchange's unix ms timestamp of a message, a number or a decimal
+/// string depending on the venue.
+pub fn exchange_ts(value: Option<&Value>) -> Option<QuoteSource> {
+    let value = value?;
+    let ms = value.as_u64().or_else(|| value.as_str()?.parse().ok())?;
+    Some(QuoteSource::Exchange(ms))
+}
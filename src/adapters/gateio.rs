@@ -1,111 +1,145 @@
-use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
-use log::{error, info};
+use anyhow::{Result, anyhow, bail};
 use serde_json::{Value, json};
-use tokio::sync::watch::Sender;
-use tokio::time::sleep;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
+use crate::adapters::book::OrderBook;
+use crate::adapters::feed::{exchange_ts, parse_level};
+use crate::adapters::{Decoder, ExchangeAdapter};
 use crate::arbitrage::PriceData;
-use crate::settings;
+use crate::markets::MarketMeta;
+use crate::settings::{self, Settings};
 
-pub async fn run_gateio_listener(tx: Sender<Option<PriceData>>) {
-    loop {
-        match connect_and_subscribe(tx.clone()).await {
-            Ok(_) => info!("gateio ws connection closed normally"),
-            Err(e) => error!("gateio ws connection error: {}", e),
-        }
+/// Gate.io v4 `spot.tickers` stream.
+pub struct Gateio;
+
+impl ExchangeAdapter for Gateio {
+    fn venue(&self) -> &'static str {
+        "gateio"
+    }
+
+    fn ticker(&self, cfg: &Settings) -> String {
+        cfg.gateio_ticker.clone()
+    }
+
+    fn endpoint(&self, cfg: &Settings) -> String {
+        cfg.gateio_ws_endpoint.clone()
+    }
 
-        info!("reconnecting in 5 seconds...");
-        sleep(Duration::from_secs(5)).await;
+    fn subscribe(&self, _cfg: &Settings, ticker: &str) -> Vec<Value> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        vec![json!({
+            "time": time,
+            "channel": "spot.tickers",
+            "event": "subscribe",
+            "payload": [ticker]
+        })]
+    }
+
+    fn decoder(&self, _cfg: &Settings, _ticker: &str) -> Decoder {
+        Box::new(|text| Ok(parse_message(text)))
     }
 }
 
-async fn connect_and_subscribe(tx: Sender<Option<PriceData>>) -> Result<()> {
-    let cfg = settings::Settings::load()?;
-
-    let (ws_stream, _) = connect_async(&cfg.gateio_ws_endpoint).await?;
-    info!("connected to gateio webSocket: {}", cfg.gateio_ws_endpoint);
-
-    let (mut write, mut read) = ws_stream.split();
-
-    // Subscribe to ticker updates using Gate.io WebSocket v4 format
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-
-    let subscribe_msg = json!({
-        "time": current_time,
-        "channel": "spot.tickers",
-        "event": "subscribe",
-        "payload": [cfg.gateio_ticker.clone()]
-    });
-
-    write.send(Message::Text(subscribe_msg.to_string())).await?;
-    info!("subscribed to {} ticker", cfg.gateio_ticker);
-
-    // Track previous price to avoid duplicate updates
-    let mut last_price: Option<PriceData> = None;
-
-    while let Some(msg) = read.next().await {
-        match msg? {
-            Message::Text(text) => {
-                if let Ok(data) = serde_json::from_str::<Value>(&text) {
-                    // Skip subscription confirmations but allow update events
-                    if let Some(event) = data.get("event").and_then(|e| e.as_str()) {
-                        if event == "subscribe" || event == "unsubscribe" {
-                            continue;
-                        }
-                    }
-
-                    // Handle ping messages
-                    if let Some(channel) = data.get("channel").and_then(|c| c.as_str()) {
-                        if channel == "spot.ping" {
-                            continue;
-                        }
-                    }
-
-                    // Parse ticker data from update events
-                    let Some(result) = data.get("result") else {
-                        continue;
-                    };
-
-                    // Extract bid and ask from ticker data
-                    let bid = result
-                        .get("highest_bid")
-                        .and_then(|p| p.as_str())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
-
-                    let ask = result
-                        .get("lowest_ask")
-                        .and_then(|p| p.as_str())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
-
-                    let price_data = PriceData { bid, ask };
-
-                    // Only send update if price has changed
-                    if last_price.as_ref() != Some(&price_data) {
-                        if let Err(e) = tx.send(Some(price_data.clone())) {
-                            error!("failed to send CEX price update: {}", e);
-                        }
-
-                        info!("⚠️ GATEIO {}: bid ${:.2} ask ${:.2}", cfg.gateio_ticker, bid, ask);
-                        last_price = Some(price_data);
-                    }
-                }
-            }
-            Message::Ping(ping) => write.send(Message::Pong(ping)).await?,
-            Message::Close(_) => {
-                break;
-            }
-            _ => {}
+/// Best bid and ask from one gateio `spot.tickers` message, `None` for
+/// anything that isn't a ticker update. An empty side reads as a price of 0.
+pub fn parse_message(text: &str) -> Option<PriceData> {
+    let data = serde_json::from_str::<Value>(text).ok()?;
+
+    // only ticker updates; subscription acks, pongs and errors carry a
+    // `result` too
+    let channel = data.get("channel").and_then(|c| c.as_str());
+    let event = data.get("event").and_then(|e| e.as_str());
+    if channel != Some("spot.tickers") || event != Some("update") {
+        return None;
+    }
+
+    // Parse ticker data from update events
+    Some(ticker_prices(data.get("result")?).with_source(exchange_ts(data.get("time_ms"))))
+}
+
+/// One ticker snapshot from the REST API, for one-shot tools.
+pub async fn fetch_snapshot(cfg: &settings::Settings) -> Result<PriceData> {
+    let tickers = rest_get(cfg, "/api/v4/spot/tickers", &[("currency_pair", cfg.gateio_ticker.as_str())]).await?;
+    let ticker = tickers
+        .as_array()
+        .and_then(|tickers| tickers.first())
+        .ok_or_else(|| anyhow!("gateio returned no ticker for {}", cfg.gateio_ticker))?;
+
+    let top = ticker_prices(ticker);
+    Ok(if cfg.is_inverse(&cfg.gateio_ticker) { top.inverted() } else { top })
+}
+
+/// `limit` levels a side of the book from the REST API, for sizing trades
+/// against its depth.
+pub async fn fetch_book(cfg: &settings::Settings, limit: u32) -> Result<OrderBook> {
+    // gate serves at most 100
+    let limit = limit.min(100).to_string();
+    let query = [("currency_pair", cfg.gateio_ticker.as_str()), ("limit", limit.as_str())];
+    let book = rest_get(cfg, "/api/v4/spot/order_book", &query).await?;
+    Ok(OrderBook::from_json(&book, "bids", "asks"))
+}
+
+/// Trading status of `gateio_ticker`, e.g. "tradable".
+pub async fn fetch_pair_status(cfg: &settings::Settings) -> Result<String> {
+    let pair = rest_get(cfg, &format!("/api/v4/spot/currency_pairs/{}", cfg.gateio_ticker), &[]).await?;
+    Ok(pair["trade_status"].as_str().unwrap_or("unknown").to_string())
+}
+
+/// Tick size, lot size and minimums of `gateio_ticker`.
+pub async fn fetch_market(cfg: &settings::Settings) -> Result<MarketMeta> {
+    let pair = rest_get(cfg, &format!("/api/v4/spot/currency_pairs/{}", cfg.gateio_ticker), &[]).await?;
+    market_meta(&pair)
+}
+
+/// Market metadata of a v4 currency pair, whose steps are given as decimal
+/// places.
+pub fn market_meta(pair: &Value) -> Result<MarketMeta> {
+    let decimals = |key: &str| {
+        pair[key]
+            .as_u64()
+            .map(|d| d as u32)
+            .ok_or_else(|| anyhow!("gateio pair has no {}", key))
+    };
+    // minimums are absent on pairs without one
+    let minimum = |key: &str| -> Result<f64> {
+        match pair[key].as_str() {
+            Some(v) => Ok(v.parse()?),
+            None => Ok(0.0),
         }
+    };
+
+    let (price_precision, qty_precision) = (decimals("precision")?, decimals("amount_precision")?);
+    Ok(MarketMeta {
+        symbol: pair["id"].as_str().unwrap_or_default().to_string(),
+        tick_size: 10f64.powi(-(price_precision as i32)),
+        lot_size: 10f64.powi(-(qty_precision as i32)),
+        min_qty: minimum("min_base_amount")?,
+        min_notional: minimum("min_quote_amount")?,
+        price_precision,
+        qty_precision,
+        sig_figs: None,
+    })
+}
+
+// public v4 endpoint; errors come back as `{label, message}` bodies
+async fn rest_get(cfg: &settings::Settings, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+    let response = reqwest::Client::new()
+        .get(format!("{}{}", cfg.gateio_rest_endpoint, path))
+        .query(query)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body: Value = response.json().await?;
+    if !status.is_success() {
+        bail!("gateio {}: {} {} ({})", path, body["label"], body["message"], status);
     }
 
-    Ok(())
-} 
\ No newline at end of file
+    Ok(body)
+}
+
+// best bid and ask of a ticker object, shared by WS and REST
+fn ticker_prices(ticker: &Value) -> PriceData {
+    PriceData::new(parse_level(ticker.get("highest_bid")), parse_level(ticker.get("lowest_ask")))
+}
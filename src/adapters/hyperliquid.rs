@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+
+use crate::adapters::book::{Level, OrderBook};
+use crate::adapters::feed::{exchange_ts, parse_level};
+use crate::adapters::{Decoder, ExchangeAdapter};
+use crate::markets::MarketMeta;
+use crate::settings::Settings;
+
+// hyperliquid closes connections without a message for 60s
+const PING_AFTER: Duration = Duration::from_secs(50);
+
+// spot prices carry at most 5 significant figures and 8 decimals less the
+// base token's szDecimals
+const PRICE_SIG_FIGS: u32 = 5;
+const SPOT_MAX_DECIMALS: u32 = 8;
+// orders worth less than $10 are rejected
+const MIN_ORDER_VALUE: f64 = 10.0;
+
+/// Hyperliquid's own spot book, HyperCore rather than HyperEVM: the
+/// `l2Book` stream of spot pair `mm_spot_pair_index`, each push a full
+/// snapshot priced at `exec_base_size` like the bybit book. Kept alive with
+/// `{"method":"ping"}` messages answered on the `pong` channel.
+pub struct Hyperliquid;
+
+impl ExchangeAdapter for Hyperliquid {
+    fn venue(&self) -> &'static str {
+        "hyperliquid"
+    }
+
+    // spot pairs other than PURR/USDC go by their index
+    fn ticker(&self, cfg: &Settings) -> String {
+        format!("@{}", cfg.mm_spot_pair_index)
+    }
+
+    fn endpoint(&self, cfg: &Settings) -> String {
+        cfg.hyperliquid_ws_endpoint.clone()
+    }
+
+    fn subscribe(&self, _cfg: &Settings, ticker: &str) -> Vec<Value> {
+        vec![json!({
+            "method": "subscribe",
+            "subscription": { "type": "l2Book", "coin": ticker }
+        })]
+    }
+
+    fn decoder(&self, cfg: &Settings, ticker: &str) -> Decoder {
+        let size = if cfg.is_inverse(ticker) { 0.0 } else { cfg.exec_base_size };
+        let coin = ticker.to_string();
+        Box::new(move |text| Ok(parse_l2_book(text, &coin)?.map(|book| book.executable(size))))
+    }
+
+    fn keepalive(&self) -> Option<(Duration, &'static str)> {
+        Some((PING_AFTER, r#"{"method":"ping"}"#))
+    }
+}
+
+/// The book of `coin` in one hyperliquid `l2Book` push, `None` for pongs,
+/// subscription acks, other coins and anything else without one. An
+/// `error` message is an error.
+pub fn parse_l2_book(text: &str, coin: &str) -> Result<Option<OrderBook>> {
+    let Ok(message) = serde_json::from_str::<Value>(text) else {
+        return Ok(None);
+    };
+    if message["channel"] == "error" {
+        bail!("hyperliquid {}", message["data"]);
+    }
+    let data = &message["data"];
+    if message["channel"] != "l2Book" || data["coin"] != coin {
+        return Ok(None);
+    }
+    // levels are [bids, asks] of {px, sz, n}
+    let side = |i: usize| -> Vec<Level> {
+        data["levels"][i]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|level| (parse_level(level.get("px")), parse_level(level.get("sz"))))
+            .collect()
+    };
+    let mut book = OrderBook::default();
+    book.apply_snapshot(&side(0), &side(1));
+    book.stamp(exchange_ts(data.get("time")));
+    Ok(Some(book))
+}
+
+/// Tick, lot and minimum rules of spot pair `mm_spot_pair_index`, from
+/// the public `spotMeta` info request.
+pub async fn fetch_market(cfg: &Settings) -> Result<MarketMeta> {
+    let spot_meta: Value = reqwest::Client::new()
+        .post(format!("{}/info", cfg.hyperliquid_api_url.trim_end_matches('/')))
+        .json(&json!({ "type": "spotMeta" }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    market_meta(&spot_meta, cfg.mm_spot_pair_index)
+}
+
+/// Market metadata of spot pair `pair_index` in a `spotMeta` response.
+/// Sizes step by the szDecimals of its base token, prices by 5
+/// significant figures.
+pub fn market_meta(spot_meta: &Value, pair_index: u32) -> Result<MarketMeta> {
+    let pair = spot_meta["universe"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|pair| pair["index"] == pair_index)
+        .ok_or_else(|| anyhow!("hyperliquid has no spot pair {}", pair_index))?;
+    let base = &pair["tokens"][0];
+    let sz_decimals = spot_meta["tokens"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|token| token["index"] == *base)
+        .and_then(|token| token["szDecimals"].as_u64())
+        .ok_or_else(|| anyhow!("hyperliquid has no szDecimals of token {}", base))? as u32;
+
+    let price_precision = SPOT_MAX_DECIMALS.saturating_sub(sz_decimals);
+    Ok(MarketMeta {
+        symbol: pair["name"].as_str().unwrap_or_default().to_string(),
+        tick_size: 10f64.powi(-(price_precision as i32)),
+        lot_size: 10f64.powi(-(sz_decimals as i32)),
+        min_qty: 0.0,
+        min_notional: MIN_ORDER_VALUE,
+        price_precision,
+        qty_precision: sz_decimals,
+        sig_figs: Some(PRICE_SIG_FIGS),
+    })
+}
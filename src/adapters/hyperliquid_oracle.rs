@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, address},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol_types::SolValue,
+};
+use anyhow::Result;
+use log::{error, info};
+use tokio::sync::watch::Sender;
+use tokio::time::{Instant, sleep};
+
+use crate::arbitrage::PriceData;
+use crate::helpers::provider::connect_provider;
+use crate::settings::{self, Settings};
+
+// HyperEVM read precompiles exposing HyperCore state
+pub const MARK_PX_PRECOMPILE: Address = address!("0x0000000000000000000000000000000000000806");
+pub const ORACLE_PX_PRECOMPILE: Address = address!("0x0000000000000000000000000000000000000807");
+
+/// Polls the HyperCore perp oracle price straight from HyperEVM, as a
+/// reference price that doesn't depend on any CEX feed. Published with
+/// bid == ask.
+pub async fn run_oracle_listener(tx: Sender<Option<PriceData>>) -> Result<()> {
+    let cfg = settings::Settings::load()?;
+
+    let provider = connect_provider(cfg.quote_rpc()).await?;
+    let provider = Arc::new(provider);
+
+    loop {
+        match fetch_oracle_price(&cfg, &provider, ORACLE_PX_PRECOMPILE).await {
+            Ok(price) => {
+                let price_data = PriceData::new(price, price);
+                tx.send_if_modified(|current| {
+                    let changed = current.as_ref() != Some(&price_data);
+                    if changed {
+                        *current = Some(price_data.clone());
+                    }
+                    changed
+                });
+            }
+            Err(e) => error!("oracle price fetch error: {}", e),
+        }
+
+        sleep(Duration::from_millis(1000)).await;
+    }
+}
+
+/// Reads a perp price precompile. Perp prices carry `6 - szDecimals`
+/// decimals.
+pub async fn fetch_oracle_price(
+    cfg: &Settings,
+    provider: &Arc<impl Provider>,
+    precompile: Address,
+) -> Result<f64> {
+    let start = Instant::now();
+
+    let tx = TransactionRequest::default()
+        .to(precompile)
+        .with_input(Bytes::from(cfg.oracle_perp_index.abi_encode()));
+    let response = provider.call(tx).await?;
+
+    let raw = u64::abi_decode(&response)?;
+    let price = raw as f64 / 10f64.powi(6 - cfg.oracle_sz_decimals as i32);
+
+    info!("⚠️ HYPERCORE ORACLE perp {}: ${:.4} (took {:.2}ms)", cfg.oracle_perp_index, price, start.elapsed().as_millis());
+
+    Ok(price)
+}
+
+/// Whether a DEX quote's mid lies within `oracle_band_bps` of the oracle.
+pub fn within_oracle_band(cfg: &Settings, quote: &PriceData, oracle: &PriceData) -> bool {
+    let quote_mid = (quote.bid + quote.ask) / 2.0;
+    let oracle_mid = (oracle.bid + oracle.ask) / 2.0;
+    if oracle_mid <= 0.0 {
+        return true;
+    }
+
+    (quote_mid - oracle_mid).abs() / oracle_mid * 10000.0 <= cfg.oracle_band_bps as f64
+}
@@ -3,152 +3,741 @@ use std::time::Duration;
 
 use alloy::{
     network::Ethereum,
-    primitives::{Bytes, U256},
-    providers::{Provider, ProviderBuilder},
+    primitives::{Address, Bytes, I256, U256},
+    providers::Provider,
+    rpc::types::{Filter, Header},
 };
+use futures_util::StreamExt;
 
 use revm::{
     database::{AlloyDB, CacheDB, WrapDatabaseAsync},
     state::Bytecode,
 };
 
-use anyhow::Result;
-use log::{error, info};
-use tokio::time::{sleep, Instant};
-use tokio::sync::watch;
+use anyhow::{Result, anyhow, bail};
+use log::{error, info, warn};
+use tokio::time::{sleep, sleep_until, Instant};
+use tokio::sync::{broadcast, mpsc, watch};
 
-use crate::settings;
-use crate::arbitrage::{PriceData};
-use crate::helpers::revm::{init_cache_db, init_account_with_bytecode, insert_mapping_storage_slot, hydrate_pool_state, revm_call};
-use crate::helpers::abi::{ONE_ETHER, quote_calldata, decode_quote_response, quote_exact_output_calldata, decode_quote_output_response, build_tx};
+use crate::settings::{self, QuoterKind};
+use crate::adapters::hyperliquid_oracle::within_oracle_band;
+use crate::arbitrage::{PriceData, QuoteSource};
+use crate::dex::cadence::RefreshCadence;
+use crate::dex::univ3_math;
+use crate::dex::pool_events::{PoolEvent, PoolLog, apply_pool_event};
+use crate::execution::split::PoolRoute;
+use crate::normalize::Normalizer;
+use crate::risk::RiskEvent;
+use crate::helpers::profiler::revm_call_profiled;
+use crate::helpers::provider::connect_provider;
+use crate::helpers::revm::{init_cache_db, init_account_with_bytecode, insert_mapping_storage_slot, hydrate_pool_state, revm_call, BlockTracker, CachedTicks, invalidate_storage, warm_cache_db, pool_hot_slots, tick_spacing_for_fee, access_list_slots};
+use crate::helpers::abi::{quote_calldata_for, decode_quote_response_for, quote_exact_output_calldata_for, decode_quote_output_response_for, decode_amount_response, multicall3_calldata, decode_multicall3_response, get_basefee_calldata, call_tx};
 
-pub async fn run_hyperswap_listener(tx: watch::Sender<Option<PriceData>>) -> Result<()> {
+/// Pools quoted besides the configured pair, added and removed at runtime.
+/// `cfg` is the bot config with the pair's tokens, pool and fee tier.
+pub enum PoolCommand {
+    Add {
+        name: String,
+        cfg: Box<settings::Settings>,
+        tx: watch::Sender<Option<PriceData>>,
+    },
+    Remove {
+        name: String,
+    },
+    /// Stop (`true`) or resume quoting every pool, e.g. for maintenance.
+    Idle(bool),
+}
+
+/// Quotes the configured pool, and every pool added through `pool_rx`,
+/// from one shared REVM cache. With `dex_new_heads` they are quoted once per
+/// new block; while no head arrives, as often as `RefreshCadence` says given
+/// `cex_feeds` and the pools' events.
+pub async fn run_hyperswap_listener(
+    tx: watch::Sender<Option<PriceData>>,
+    risk_tx: broadcast::Sender<RiskEvent>,
+    oracle_rx: watch::Receiver<Option<PriceData>>,
+    cex_feeds: Vec<watch::Receiver<Option<PriceData>>>,
+    mut pool_rx: mpsc::UnboundedReceiver<PoolCommand>,
+) -> Result<()> {
     let cfg: settings::Settings = settings::Settings::load()?;
 
-    let provider = ProviderBuilder::new().connect_http(cfg.rpc_url.parse()?);
+    let provider = connect_provider(cfg.quote_rpc()).await?;
     let provider = Arc::new(provider);
 
     // initialize cache_db
     let mut cache_db = init_cache_db(provider.clone());
 
-    // mock ERC‑20s with generic_erc20 bytecode
-    let mocked_erc20 = include_str!("../bytecode/generic_erc20.hex");
-    let mocked_erc20 = mocked_erc20.parse::<Bytes>().unwrap();
-    let mocked_erc20 = Bytecode::new_raw(mocked_erc20);
-    init_account_with_bytecode(cfg.weth_addr, mocked_erc20.clone(), &mut cache_db).await?;
-    // init_account_with_bytecode(cfg.usdt_addr, mocked_erc20.clone(), &mut cache_db).await?;
+    // preload hot contracts so the first quotes don't wait on cold fetches
+    let start = Instant::now();
+    let mut pools = vec![(cfg.pool_addr, cfg.dex_fee_tier)];
+    pools.extend(cfg.extra_pools.iter().map(|p| (p.pool_addr, p.fee_tier)));
+
+    let mut accounts = vec![cfg.quoter_v2_addr, cfg.router_addr, cfg.weth_addr, cfg.usdt_addr];
+    let mut storage = Vec::new();
+    for (pool, fee) in pools {
+        accounts.push(pool);
+        for slot in pool_hot_slots(&provider, pool, tick_spacing_for_fee(fee)).await? {
+            storage.push((pool, slot));
+        }
+    }
+
+    if !cfg.mock_erc20 {
+        // real token code is already loaded, pull in whatever slots the
+        // quotes actually touch (pool balances, token hooks, ...)
+        let volume = Normalizer::new(&cfg).one_base();
+        let quotes = [
+            quote_calldata_for(cfg.quoter_kind, cfg.weth_addr, cfg.usdt_addr, volume, cfg.dex_fee_tier, cfg.self_addr),
+            quote_exact_output_calldata_for(cfg.quoter_kind, cfg.usdt_addr, cfg.weth_addr, volume, cfg.dex_fee_tier, cfg.self_addr),
+        ];
+        for calldata in quotes {
+            let (touched, slots) = access_list_slots(&provider, cfg.self_addr, cfg.quoter_v2_addr, calldata).await?;
+            for account in touched {
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+            storage.extend(slots);
+        }
+    }
+
+    warm_cache_db(&mut cache_db, &provider, &accounts, &storage).await?;
+    info!("warmed revm cache with {} accounts and {} slots in {}ms", accounts.len(), storage.len(), start.elapsed().as_millis());
+
+    if cfg.mock_erc20 {
+        // fast mode: mock ERC‑20s with generic_erc20 bytecode
+        let mocked_erc20 = include_str!("../bytecode/generic_erc20.hex");
+        let mocked_erc20 = mocked_erc20.parse::<Bytes>().unwrap();
+        let mocked_erc20 = Bytecode::new_raw(mocked_erc20);
+        init_account_with_bytecode(cfg.weth_addr, mocked_erc20.clone(), &mut cache_db).await?;
+        // init_account_with_bytecode(cfg.usdt_addr, mocked_erc20.clone(), &mut cache_db).await?;
+
+        // mock pool state balances
+        let big = U256::MAX / U256::from(2);
+        insert_mapping_storage_slot(cfg.weth_addr, U256::ZERO, cfg.pool_addr, big, &mut cache_db).await?;
+        insert_mapping_storage_slot(cfg.usdt_addr, U256::ZERO, cfg.pool_addr, big, &mut cache_db).await?;
+        for pool in &cfg.extra_pools {
+            insert_mapping_storage_slot(cfg.weth_addr, U256::ZERO, pool.pool_addr, big, &mut cache_db).await?;
+            insert_mapping_storage_slot(cfg.usdt_addr, U256::ZERO, pool.pool_addr, big, &mut cache_db).await?;
+        }
+    }
+
+    let mut pool_addrs = vec![cfg.pool_addr];
+    pool_addrs.extend(cfg.extra_pools.iter().map(|p| p.pool_addr));
+    let mut tracker = BlockTracker::new(pool_addrs.clone())
+        .with_max_lag(cfg.max_chain_lag_secs)
+        .with_risk_events(risk_tx);
+    if cfg.mock_erc20 {
+        tracker = tracker.with_keep(vec![cfg.weth_addr, cfg.usdt_addr]);
+    }
+
+    // pool swaps are written into the cache, other pool/token events mark
+    // their cached storage dirty and cut the wait short
+    let (logs_tx, mut logs_rx) = mpsc::unbounded_channel::<PoolLog>();
+    tokio::spawn(run_log_watcher(cfg.clone(), logs_tx));
+
+    // new heads drive the quotes, the cadence only stands in while they don't come
+    let (heads_tx, mut heads_rx) = mpsc::unbounded_channel::<Header>();
+    if cfg.dex_new_heads {
+        tokio::spawn(run_head_watcher(cfg.clone(), heads_tx));
+    }
+    let head_timeout = Duration::from_millis(cfg.dex_refresh_slow_ms);
+    let mut last_head: Option<Instant> = None;
 
-    // mock pool state balances
-    let big = U256::MAX / U256::from(2);
-    insert_mapping_storage_slot(cfg.weth_addr, U256::ZERO, cfg.pool_addr, big, &mut cache_db).await?;
-    insert_mapping_storage_slot(cfg.usdt_addr, U256::ZERO, cfg.pool_addr, big, &mut cache_db).await?;
+    let mut extra: Vec<(String, settings::Settings, watch::Sender<Option<PriceData>>)> = Vec::new();
+    let mut idle = false;
+    let mut quote = true;
+    let mut cadence = RefreshCadence::from_config(&cfg).with_cex_feeds(cex_feeds);
 
     loop {
         // match fetch_quote(&cfg, &provider, &tx).await {
             // Ok(_) => {},
             // Err(e) => error!("DEX price fetch error: {}", e),
         // }
-        match fetch_quote_revm(&cfg, provider.clone(), &tx, &mut cache_db).await {
-            Ok(_) => {},
-            Err(e) => error!("DEX price fetch error: {}", e),
+        let oracle = oracle_rx.borrow().clone();
+        if !idle && quote {
+            let before = published(&tx, &extra);
+            match fetch_quote_cached(&cfg, provider.clone(), &tx, &mut cache_db, &mut tracker, oracle.as_ref()).await {
+                Ok(_) => {},
+                Err(e) => error!("DEX price fetch error: {}", e),
+            }
+            // the oracle only prices the configured base
+            for (name, pair_cfg, pair_tx) in &extra {
+                if let Err(e) = fetch_quote_cached(pair_cfg, provider.clone(), pair_tx, &mut cache_db, &mut tracker, None).await {
+                    error!("{} DEX price fetch error: {}", name, e);
+                }
+            }
+            cadence.refreshed(published(&tx, &extra) != before);
+        }
+
+        // fetch DEX prices once per new head; without heads when the cadence
+        // says, or right away on a relevant event
+        let heads_live = last_head.is_some_and(|at| at.elapsed() < head_timeout);
+        quote = true;
+        tokio::select! {
+            _ = cadence.tick(), if !heads_live => {}
+            _ = sleep_until(last_head.unwrap_or_else(Instant::now) + head_timeout), if heads_live => {
+                warn!("no new head for {}ms, polling the DEX until heads resume", head_timeout.as_millis());
+                tracker.unannounce();
+                cadence.wake();
+            }
+            Some(mut head) = heads_rx.recv() => {
+                while let Ok(next) = heads_rx.try_recv() {
+                    head = next;
+                }
+                last_head = Some(Instant::now());
+                // a polled quote may have caught this block already
+                quote = tracker.recent.back() != Some(&(head.number, head.hash));
+                tracker.announce(head);
+            }
+            Some(command) = pool_rx.recv() => match command {
+                PoolCommand::Add { name, cfg: pair_cfg, tx: pair_tx } => {
+                    match hydrate_pair(&pair_cfg, &provider, &mut cache_db).await {
+                        Ok(()) => {
+                            info!("quoting {} pool {}", name, pair_cfg.pool_addr);
+                            tracker.volatile.push(pair_cfg.pool_addr);
+                            extra.retain(|(n, _, _)| *n != name);
+                            extra.push((name, *pair_cfg, pair_tx));
+                        }
+                        // dropping the sender tells the engines the pair is gone
+                        Err(e) => error!("failed to hydrate {} pool {}: {:#}", name, pair_cfg.pool_addr, e),
+                    }
+                }
+                PoolCommand::Remove { name } => {
+                    if let Some(i) = extra.iter().position(|(n, _, _)| *n == name) {
+                        let (_, pair_cfg, _) = extra.remove(i);
+                        if !pool_addrs.contains(&pair_cfg.pool_addr) {
+                            tracker.volatile.retain(|a| *a != pair_cfg.pool_addr);
+                        }
+                        info!("stopped quoting {} pool {}", name, pair_cfg.pool_addr);
+                    }
+                }
+                PoolCommand::Idle(on) => {
+                    idle = on;
+                    info!("DEX quoting {}", if on { "idle" } else { "resumed" });
+                }
+            },
+            Some(log) = logs_rx.recv() => {
+                let mut logs = vec![log];
+                while let Ok(log) = logs_rx.try_recv() {
+                    logs.push(log);
+                }
+                let (dirty, applied) = on_pool_logs(logs, &pool_addrs, &mut cache_db, &mut tracker);
+
+                // mocked token balances live in token storage, keep them
+                let dirty: Vec<Address> = dirty.into_iter().filter(|a| !cfg.mock_erc20 || pool_addrs.contains(a)).collect();
+
+                invalidate_storage(&mut cache_db, &dirty);
+                // a swap written into the cache is quoted without a single
+                // RPC call; anything else waits for its block's head
+                quote = applied || !heads_live;
+                cadence.wake();
+            }
+        }
+    }
+}
+
+// a quote off the shared cache, by the quoter or by `dex_local_math`
+async fn fetch_quote_cached<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    provider: Arc<P>,
+    price_tx: &watch::Sender<Option<PriceData>>,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+    oracle: Option<&PriceData>,
+) -> Result<()> {
+    if cfg.dex_local_math {
+        fetch_quote_math(cfg, provider, price_tx, cache_db, tracker, oracle).await
+    } else {
+        fetch_quote_revm(cfg, provider, price_tx, cache_db, tracker, oracle).await
+    }
+}
+
+// applies pool swaps to the cache and returns the addresses of every other
+// log, and whether any swap was applied
+fn on_pool_logs<P: Provider + Clone>(
+    logs: Vec<PoolLog>,
+    pools: &[Address],
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+) -> (Vec<Address>, bool) {
+    let mut dirty = Vec::new();
+    let mut applied = false;
+    for log in logs {
+        match log {
+            PoolLog::Following(true) => {
+                // events only carry changes, start them off a fresh read
+                tracker.followed = pools.to_vec();
+                invalidate_storage(cache_db, pools);
+                for pool in pools {
+                    if !tracker.recheck.contains(pool) {
+                        tracker.recheck.push(*pool);
+                    }
+                }
+                info!("following {} pools from their events", pools.len());
+            }
+            PoolLog::Following(false) => {
+                if !tracker.followed.is_empty() {
+                    warn!("pool events missed, re-reading pools every block");
+                    invalidate_storage(cache_db, &std::mem::take(&mut tracker.followed));
+                }
+            }
+            PoolLog::Log(log) => match PoolEvent::decode(&log).filter(|e| !log.removed && tracker.follows(e.pool())) {
+                Some(event) => applied |= apply_pool_event(cache_db, tracker, &event),
+                None => {
+                    if !dirty.contains(&log.address()) {
+                        dirty.push(log.address());
+                    }
+                }
+            },
+        }
+    }
+    (dirty, applied)
+}
+
+// the prices last sent for the configured pool and each added one
+fn published<C>(tx: &watch::Sender<Option<PriceData>>, extra: &[(String, C, watch::Sender<Option<PriceData>>)]) -> Vec<Option<PriceData>> {
+    std::iter::once(tx).chain(extra.iter().map(|(_, _, tx)| tx)).map(|tx| tx.borrow().clone()).collect()
+}
+
+// loads a pool added at runtime into the cache, as the startup warm-up does
+// for the configured ones; its events are not watched, the per-block
+// refresh keeps it current
+async fn hydrate_pair<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    provider: &Arc<P>,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<()> {
+    let start = Instant::now();
+    let accounts = [cfg.pool_addr, cfg.weth_addr, cfg.usdt_addr];
+    let storage: Vec<_> = pool_hot_slots(provider, cfg.pool_addr, tick_spacing_for_fee(cfg.dex_fee_tier))
+        .await?
+        .into_iter()
+        .map(|slot| (cfg.pool_addr, slot))
+        .collect();
+
+    warm_cache_db(cache_db, provider, &accounts, &storage).await?;
+    info!("warmed revm cache with pool {} ({} slots) in {}ms", cfg.pool_addr, storage.len(), start.elapsed().as_millis());
+    Ok(())
+}
+
+/// Streams logs of the pools and both tokens. Uses `eth_subscribe` where
+/// the transport supports it and falls back to a polled log filter over
+/// HTTP. `PoolLog::Following` brackets each stream.
+pub async fn run_log_watcher(cfg: settings::Settings, logs_tx: mpsc::UnboundedSender<PoolLog>) {
+    loop {
+        match watch_logs(&cfg, &logs_tx).await {
+            Ok(_) => info!("log subscription closed"),
+            Err(e) => error!("log subscription error: {}", e),
         }
-        
-        // fetch DEX prices every 1 seconds
-        sleep(Duration::from_millis(1000)).await;
+
+        // events may go missed from here on
+        if logs_tx.send(PoolLog::Following(false)).is_err() {
+            return;
+        }
+
+        info!("resubscribing to pool logs in 5 seconds...");
+        sleep(Duration::from_secs(5)).await;
     }
 }
 
+async fn watch_logs(cfg: &settings::Settings, logs_tx: &mpsc::UnboundedSender<PoolLog>) -> Result<()> {
+    let provider = connect_provider(cfg.subscribe_rpc()).await?;
+
+    let mut addresses = vec![cfg.pool_addr, cfg.weth_addr, cfg.usdt_addr];
+    addresses.extend(cfg.extra_pools.iter().map(|p| p.pool_addr));
+    let filter = Filter::new().address(addresses);
+
+    let mut stream = match provider.subscribe_logs(&filter).await {
+        Ok(sub) => sub.into_stream().map(|log| vec![log]).boxed(),
+        Err(e) => {
+            warn!("log subscription unavailable ({}), polling a log filter instead", e);
+            provider.watch_logs(&filter).await?.into_stream().boxed()
+        }
+    };
+    info!("watching logs of pools and tokens on {}", cfg.subscribe_rpc());
+    if logs_tx.send(PoolLog::Following(true)).is_err() {
+        return Ok(());
+    }
+
+    while let Some(logs) = stream.next().await {
+        for log in logs {
+            if logs_tx.send(PoolLog::Log(Box::new(log))).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Streams new chain heads, from `eth_subscribe("newHeads")` where the
+/// transport supports it and from a polled block filter over HTTP
+/// otherwise. Only the latest of a batch of polled blocks is forwarded.
+pub async fn run_head_watcher(cfg: settings::Settings, heads_tx: mpsc::UnboundedSender<Header>) {
+    loop {
+        match watch_heads(&cfg, &heads_tx).await {
+            Ok(_) => info!("head subscription closed"),
+            Err(e) => error!("head subscription error: {}", e),
+        }
+
+        if heads_tx.is_closed() {
+            return;
+        }
+
+        info!("resubscribing to new heads in 5 seconds...");
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn watch_heads(cfg: &settings::Settings, heads_tx: &mpsc::UnboundedSender<Header>) -> Result<()> {
+    let provider = connect_provider(cfg.subscribe_rpc()).await?;
+
+    match provider.subscribe_blocks().await {
+        Ok(sub) => {
+            info!("subscribed to new heads on {}", cfg.subscribe_rpc());
+            let mut stream = sub.into_stream();
+            while let Some(head) = stream.next().await {
+                if heads_tx.send(head).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(e) => {
+            warn!("head subscription unavailable ({}), polling a block filter instead", e);
+            let mut stream = provider
+                .watch_blocks()
+                .await?
+                .with_poll_interval(Duration::from_millis(cfg.dex_refresh_fast_ms))
+                .into_stream();
+            while let Some(hashes) = stream.next().await {
+                let Some(hash) = hashes.last() else {
+                    continue;
+                };
+                let Some(block) = provider.get_block_by_hash(*hash).await? else {
+                    continue;
+                };
+                if heads_tx.send(block.header).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Plain RPC quoting: both directions and the base fee are batched through
+// Multicall3 so a refresh costs a single eth_call. Router-kind quoting can't
+// go through here, since Multicall3 would become the paying swapper.
 pub async fn fetch_quote(
     cfg: &settings::Settings,
     provider: &Arc<impl Provider>, 
     price_tx: &watch::Sender<Option<PriceData>>, 
 ) -> Result<()> {
-    let volume = ONE_ETHER;
-    
-    let start = Instant::now();
+    if cfg.quoter_kind == QuoterKind::Router {
+        bail!("router quoting is only supported in revm mode");
+    }
+
+    let norm = Normalizer::new(cfg);
+    let volume = norm.one_base();
 
-    let base_fee = provider.get_gas_price().await?;
+    let start = Instant::now();
 
-    let sell_weth_calldata = quote_calldata(
+    let sell_weth_calldata = quote_calldata_for(
+        cfg.quoter_kind,
         cfg.weth_addr, 
         cfg.usdt_addr, 
         volume, 
-        cfg.dex_fee_tier
+        cfg.dex_fee_tier,
+        cfg.self_addr,
     );
-    let sell_response = provider.call(build_tx(
-        cfg.quoter_v2_addr, 
-        cfg.self_addr, 
-        sell_weth_calldata, 
-        base_fee
-    )).await?;
 
-    let buy_weth_calldata = quote_exact_output_calldata(
+    let buy_weth_calldata = quote_exact_output_calldata_for(
+        cfg.quoter_kind,
         cfg.usdt_addr, 
         cfg.weth_addr, 
         volume, 
-        cfg.dex_fee_tier
+        cfg.dex_fee_tier,
+        cfg.self_addr,
     );
-    let buy_response = provider.call(build_tx(
-        cfg.quoter_v2_addr, 
+
+    let batch_calldata = multicall3_calldata(vec![
+        (cfg.quoter_v2_addr, sell_weth_calldata),
+        (cfg.quoter_v2_addr, buy_weth_calldata),
+        (cfg.multicall3_addr, get_basefee_calldata()),
+    ]);
+    let batch_response = provider.call(call_tx(
+        cfg.multicall3_addr, 
         cfg.self_addr, 
-        buy_weth_calldata, 
-        base_fee
+        batch_calldata, 
     )).await?;
 
-    let price_data = PriceData {
-        bid: decode_quote_response(sell_response)? as f64 / 1e6,
-        ask: decode_quote_output_response(buy_response)? as f64 / 1e6
-    };
+    let [sell_response, buy_response, base_fee_response]: [Bytes; 3] =
+        decode_multicall3_response(batch_response)?
+            .try_into()
+            .map_err(|r: Vec<Bytes>| anyhow!("multicall returned {} results, expected 3", r.len()))?;
+
+    let price_data = norm.dex_book(
+        decode_quote_response_for(cfg.quoter_kind, sell_response)?,
+        decode_quote_output_response_for(cfg.quoter_kind, buy_response)?,
+        volume,
+    );
+    let base_fee = decode_amount_response(base_fee_response)?;
 
     if let Err(e) = price_tx.send(Some(price_data.clone())) {
         error!("failed to send DEX price update: {}", e);
     }
 
-    info!("⚠️ HYPERSWAP WHYPE/USDT: bid ${:.2} ask ${:.2} (took {:.2}ms eth_call)", price_data.bid, price_data.ask, start.elapsed().as_millis());
+    info!("⚠️ HYPERSWAP {}: bid ${:.2} ask ${:.2} base fee {:.2} gwei (took {:.2}ms eth_call)", norm.denomination, price_data.bid, price_data.ask, base_fee as f64 / 1e9, start.elapsed().as_millis());
 
     Ok(())
 }
 
+/// Bid and ask per base unit for trading `base_amount` through one pool,
+/// by plain `eth_call`s against the latest block.
+pub async fn quote_route(
+    cfg: &settings::Settings,
+    provider: &(impl Provider + ?Sized),
+    route: &PoolRoute,
+    base_amount: U256,
+) -> Result<PriceData> {
+    let sell_calldata = quote_calldata_for(
+        route.quoter_kind,
+        cfg.weth_addr,
+        cfg.usdt_addr,
+        base_amount,
+        route.fee_tier,
+        cfg.self_addr,
+    );
+    let buy_calldata = quote_exact_output_calldata_for(
+        route.quoter_kind,
+        cfg.usdt_addr,
+        cfg.weth_addr,
+        base_amount,
+        route.fee_tier,
+        cfg.self_addr,
+    );
+
+    let (sell_response, buy_response) = tokio::try_join!(
+        provider.call(call_tx(route.quoter_addr, cfg.self_addr, sell_calldata)).into_future(),
+        provider.call(call_tx(route.quoter_addr, cfg.self_addr, buy_calldata)).into_future(),
+    )?;
+
+    Ok(Normalizer::new(cfg).dex_book(
+        decode_quote_response_for(route.quoter_kind, sell_response)?,
+        decode_quote_output_response_for(route.quoter_kind, buy_response)?,
+        base_amount,
+    ))
+}
+
+/// `quote_route` simulated in REVM on the cached state of the head block,
+/// refreshing the pool's storage first unless its events keep it current.
+pub async fn quote_route_revm<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    provider: &Arc<P>,
+    route: &PoolRoute,
+    base_amount: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+) -> Result<PriceData> {
+    let block_number = tracker.advance(cache_db, provider).await?;
+    if !tracker.follows(route.pool_addr) {
+        hydrate_pool_state(cache_db, provider, route.pool_addr, block_number).await?;
+    }
+
+    let sell_calldata = quote_calldata_for(
+        route.quoter_kind,
+        cfg.weth_addr,
+        cfg.usdt_addr,
+        base_amount,
+        route.fee_tier,
+        cfg.self_addr,
+    );
+    let buy_calldata = quote_exact_output_calldata_for(
+        route.quoter_kind,
+        cfg.usdt_addr,
+        cfg.weth_addr,
+        base_amount,
+        route.fee_tier,
+        cfg.self_addr,
+    );
+    let sell_response = revm_call(cfg.self_addr, route.quoter_addr, sell_calldata, cache_db)?;
+    let buy_response = revm_call(cfg.self_addr, route.quoter_addr, buy_calldata, cache_db)?;
+
+    let book = Normalizer::new(cfg).dex_book(
+        decode_quote_response_for(route.quoter_kind, sell_response)?,
+        decode_quote_output_response_for(route.quoter_kind, buy_response)?,
+        base_amount,
+    );
+    Ok(book.with_source(Some(QuoteSource::Block(block_number))))
+}
+
+/// `quote_route` computed with local V3 math on the cached state of the
+/// head block, without running the quoter.
+pub async fn quote_route_math<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    provider: &Arc<P>,
+    route: &PoolRoute,
+    base_amount: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+) -> Result<PriceData> {
+    let block_number = tracker.advance(cache_db, provider).await?;
+    if !tracker.follows(route.pool_addr) {
+        hydrate_pool_state(cache_db, provider, route.pool_addr, block_number).await?;
+    }
+    Ok(math_book(cfg, route, base_amount, cache_db)?.with_source(Some(QuoteSource::Block(block_number))))
+}
+
+// selling and buying `base_amount` through the pool, swapped locally
+fn math_book<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    route: &PoolRoute,
+    base_amount: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<PriceData> {
+    let mut ticks = CachedTicks { cache_db, pool: route.pool_addr };
+    let state = ticks.state()?;
+    let spacing = tick_spacing_for_fee(route.fee_tier);
+    // the pool's token0 is the lower address
+    let base_is_token0 = cfg.weth_addr < cfg.usdt_addr;
+    let amount = I256::try_from(base_amount)?;
+
+    let sold = univ3_math::swap(state, &mut ticks, spacing, route.fee_tier, base_is_token0, amount)?;
+    let bought = univ3_math::swap(state, &mut ticks, spacing, route.fee_tier, !base_is_token0, -amount)?;
+
+    Ok(Normalizer::new(cfg).dex_book(sold.amount_out.saturating_to(), bought.amount_in.saturating_to(), base_amount))
+}
+
 // REVM-based quote fetching for better performance
 pub async fn fetch_quote_revm<P: Provider + Clone>(
     cfg: &settings::Settings,
     provider: Arc<P>,
     price_tx: &watch::Sender<Option<PriceData>>, 
     cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+    oracle: Option<&PriceData>,
 ) -> Result<()> {
-    let volume = ONE_ETHER;
+    let norm = Normalizer::new(cfg);
+    let volume = norm.one_base();
 
     let start = Instant::now();
 
-    // ensure pool state is up to date
-    hydrate_pool_state(cache_db, &provider, cfg.pool_addr).await?;
+    // pin both directions and the pool hydration to the same block
+    let block_number = tracker.advance(cache_db, &provider).await?;
+
+    // a stale chain against a live CEX only yields phantom arbs, withdraw
+    // the price until blocks are fresh again
+    if tracker.is_lagging() {
+        price_tx.send_if_modified(|price| price.take().is_some());
+        return Ok(());
+    }
+
+    // ensure pool state is up to date, a followed pool is kept so by its events
+    if !tracker.follows(cfg.pool_addr) {
+        hydrate_pool_state(cache_db, &provider, cfg.pool_addr, block_number).await?;
+    }
 
-    let sell_weth_calldata = quote_calldata(
+    let sell_weth_calldata = quote_calldata_for(
+        cfg.quoter_kind,
         cfg.weth_addr, 
         cfg.usdt_addr, 
         volume, 
-        cfg.dex_fee_tier
+        cfg.dex_fee_tier,
+        cfg.self_addr,
     );
-    let sell_response = revm_call(cfg.self_addr, cfg.quoter_v2_addr, sell_weth_calldata, cache_db)?;
+    let sell_response = quote_call(cfg, "sell", sell_weth_calldata, cache_db)?;
 
-    let buy_weth_calldata = quote_exact_output_calldata(
+    let buy_weth_calldata = quote_exact_output_calldata_for(
+        cfg.quoter_kind,
         cfg.usdt_addr, 
         cfg.weth_addr, 
         volume, 
-        cfg.dex_fee_tier
+        cfg.dex_fee_tier,
+        cfg.self_addr,
     );
-    let ask_response = revm_call(cfg.self_addr, cfg.quoter_v2_addr, buy_weth_calldata, cache_db)?;
+    let ask_response = quote_call(cfg, "buy", buy_weth_calldata, cache_db)?;
 
-    let price_data = PriceData {
-        bid: decode_quote_response(sell_response)? as f64 / 1e6,
-        ask: decode_quote_output_response(ask_response)? as f64 / 1e6,
-    };
+    let price_data = norm
+        .dex_book(
+            decode_quote_response_for(cfg.quoter_kind, sell_response)?,
+            decode_quote_output_response_for(cfg.quoter_kind, ask_response)?,
+            volume,
+        )
+        .with_source(Some(QuoteSource::Block(block_number)));
+
+    publish_quote(cfg, price_tx, &price_data, oracle)?;
+
+    info!("⚠️ HYPERSWAP {}: bid ${:.2} ask ${:.2} @ block {} (took {:.2}ms revm_call)", norm.denomination, price_data.bid, price_data.ask, block_number, start.elapsed().as_millis());
+
+    Ok(())
+}
+
+/// `fetch_quote_revm` with both directions computed by `univ3_math::swap`
+/// on the cached pool state rather than by simulating the quoter.
+pub async fn fetch_quote_math<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    provider: Arc<P>,
+    price_tx: &watch::Sender<Option<PriceData>>,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+    oracle: Option<&PriceData>,
+) -> Result<()> {
+    let norm = Normalizer::new(cfg);
+    let start = Instant::now();
+
+    let block_number = tracker.advance(cache_db, &provider).await?;
+    if tracker.is_lagging() {
+        price_tx.send_if_modified(|price| price.take().is_some());
+        return Ok(());
+    }
+    if !tracker.follows(cfg.pool_addr) {
+        hydrate_pool_state(cache_db, &provider, cfg.pool_addr, block_number).await?;
+    }
+
+    let route = PoolRoute::all(cfg).remove(0);
+    let price_data = math_book(cfg, &route, norm.one_base(), cache_db)?.with_source(Some(QuoteSource::Block(block_number)));
+    publish_quote(cfg, price_tx, &price_data, oracle)?;
+
+    info!("⚠️ HYPERSWAP {}: bid ${:.2} ask ${:.2} @ block {} (took {:.2}ms v3 math)", norm.denomination, price_data.bid, price_data.ask, block_number, start.elapsed().as_millis());
+
+    Ok(())
+}
+
+// sends a quote on unless it is far off the HyperCore oracle, which is more
+// likely broken than an arb
+fn publish_quote(
+    cfg: &settings::Settings,
+    price_tx: &watch::Sender<Option<PriceData>>,
+    price_data: &PriceData,
+    oracle: Option<&PriceData>,
+) -> Result<()> {
+    if let Some(oracle) = oracle
+        && !within_oracle_band(cfg, price_data, oracle)
+    {
+        price_tx.send_if_modified(|price| price.take().is_some());
+        bail!("quote bid ${:.4} ask ${:.4} outside oracle band around ${:.4}", price_data.bid, price_data.ask, oracle.bid);
+    }
 
     if let Err(e) = price_tx.send(Some(price_data.clone())) {
         error!("failed to send DEX price update: {}", e);
     }
+    Ok(())
+}
 
-    info!("⚠️ HYPERSWAP WHYPE/USDT: bid ${:.2} ask ${:.2} (took {:.2}ms revm_call)", price_data.bid, price_data.ask, start.elapsed().as_millis());
+// quoter call, dumping a per-frame gas profile when `revm_profile` is on
+fn quote_call<P: Provider + Clone>(
+    cfg: &settings::Settings,
+    label: &str,
+    calldata: Bytes,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<Bytes> {
+    if !cfg.revm_profile {
+        return revm_call(cfg.self_addr, cfg.quoter_v2_addr, calldata, cache_db);
+    }
 
-    Ok(())
+    let (response, profile) = revm_call_profiled(cfg.self_addr, cfg.quoter_v2_addr, calldata, cache_db)?;
+    info!("⛽ {} quote gas profile:\n{}", label, profile.report());
+
+    Ok(response)
 }
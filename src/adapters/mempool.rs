@@ -0,0 +1,232 @@
+use std::time::Duration;
+
+use alloy::{
+    consensus::Transaction,
+    network::TransactionResponse,
+    primitives::{Address, TxHash, U256},
+    providers::Provider,
+    sol_types::SolCall,
+};
+use anyhow::{Result, anyhow};
+use futures_util::StreamExt;
+use log::{error, info};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+use crate::arbitrage::PriceData;
+use crate::dex::univ3_math::PoolPrice;
+use crate::helpers::abi::{exactInputSingleCall, exactOutputSingleCall};
+use crate::helpers::provider::connect_provider;
+use crate::helpers::revm::{POOL_LIQUIDITY, POOL_SLOT0};
+use crate::settings::Settings;
+
+/// A router swap through the primary pool seen in the mempool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSwap {
+    pub zero_for_one: bool,
+    pub exact_input: bool,
+    // input for exact-input swaps, output otherwise
+    pub amount: U256,
+}
+
+struct Pending {
+    hash: TxHash,
+    from: Address,
+    nonce: u64,
+    seen: u64,
+    swap: PendingSwap,
+}
+
+/// The router swaps through the primary pool still pending. A swap leaves
+/// once its sender's nonce is used up, by it or by whatever replaced it,
+/// or once it has been pending `max_age` blocks.
+pub struct PendingSwaps {
+    max_age: u64,
+    swaps: Vec<Pending>,
+}
+
+impl PendingSwaps {
+    pub fn new(max_age: u64) -> Self {
+        Self { max_age, swaps: Vec::new() }
+    }
+
+    /// Adds `swap`, seen while `block` was the latest, in place of the
+    /// pending one with the same sender and nonce.
+    pub fn insert(&mut self, hash: TxHash, from: Address, nonce: u64, swap: PendingSwap, block: u64) {
+        if self.swaps.iter().any(|p| p.hash == hash) {
+            return;
+        }
+        self.swaps.retain(|p| p.from != from || p.nonce != nonce);
+        self.swaps.push(Pending { hash, from, nonce, seen: block, swap });
+    }
+
+    /// Drops what block `number` made stale, given the sender and nonce of
+    /// each transaction it included.
+    pub fn on_block(&mut self, number: u64, included: &[(Address, u64)]) {
+        let max_age = self.max_age;
+        self.swaps.retain(|p| {
+            let used = included.iter().any(|(from, nonce)| p.from == *from && p.nonce <= *nonce);
+            !used && number.saturating_sub(p.seen) < max_age
+        });
+    }
+
+    pub fn swaps(&self) -> impl Iterator<Item = &PendingSwap> {
+        self.swaps.iter().map(|p| &p.swap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.swaps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.swaps.is_empty()
+    }
+}
+
+/// The swap `input` makes through the primary pool when sent to the
+/// router, if it is a single-pool swap of the pair at its fee tier.
+pub fn decode_pending_swap(cfg: &Settings, input: &[u8]) -> Option<PendingSwap> {
+    let (token_in, token_out, fee, exact_input, amount) =
+        if let Ok(call) = exactInputSingleCall::abi_decode(input) {
+            let p = call.params;
+            (p.tokenIn, p.tokenOut, p.fee, true, p.amountIn)
+        } else if let Ok(call) = exactOutputSingleCall::abi_decode(input) {
+            let p = call.params;
+            (p.tokenIn, p.tokenOut, p.fee, false, p.amountOut)
+        } else {
+            return None;
+        };
+
+    let pair = [cfg.weth_addr, cfg.usdt_addr];
+    if !pair.contains(&token_in) || !pair.contains(&token_out) || token_in == token_out {
+        return None;
+    }
+    if fee.to::<u32>() != cfg.dex_fee_tier {
+        return None;
+    }
+
+    Some(PendingSwap { zero_for_one: token_in < token_out, exact_input, amount })
+}
+
+/// `dex` moved by the pool price change from `before` to `after`, in base
+/// priced in quote.
+pub fn predict_price(dex: &PriceData, before: &PoolPrice, after: &PoolPrice, base_is_token0: bool) -> PriceData {
+    let ratio = after.price() / before.price();
+    let ratio = if base_is_token0 { ratio } else { 1.0 / ratio };
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return dex.clone();
+    }
+
+    PriceData::new(dex.bid * ratio, dex.ask * ratio).with_source(dex.source)
+}
+
+/// Publishes the DEX price as it will be once the pending swaps through the
+/// primary pool land, applied to the pool with local V3 math, see
+/// `PendingSwaps` for when a swap stops counting. Falls back to forwarding the
+/// last-block price while the mempool can't be watched.
+pub async fn run_mempool_monitor(
+    cfg: Settings,
+    mut dex_rx: watch::Receiver<Option<PriceData>>,
+    predicted_tx: watch::Sender<Option<PriceData>>,
+) {
+    loop {
+        match watch_mempool(&cfg, &mut dex_rx, &predicted_tx).await {
+            Ok(_) => info!("mempool subscription closed"),
+            Err(e) => error!("mempool monitor error: {}", e),
+        }
+        if predicted_tx.is_closed() {
+            return;
+        }
+
+        info!("resubscribing to the mempool in 5 seconds...");
+        let retry = sleep(Duration::from_secs(5));
+        tokio::pin!(retry);
+        loop {
+            let _ = predicted_tx.send(dex_rx.borrow().clone());
+            tokio::select! {
+                _ = &mut retry => break,
+                result = dex_rx.changed() => {
+                    if result.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn watch_mempool(
+    cfg: &Settings,
+    dex_rx: &mut watch::Receiver<Option<PriceData>>,
+    predicted_tx: &watch::Sender<Option<PriceData>>,
+) -> Result<()> {
+    let provider = connect_provider(cfg.subscribe_rpc()).await?;
+    let mut transactions = provider.subscribe_full_pending_transactions().await?.into_stream();
+    let mut blocks = provider.subscribe_blocks().await?.into_stream();
+    info!("watching pending swaps of {} on {}", cfg.pool_addr, cfg.subscribe_rpc());
+
+    let base_is_token0 = cfg.weth_addr < cfg.usdt_addr;
+    let mut pool = read_pool(&provider, cfg.pool_addr).await?;
+    let mut latest = provider.get_block_number().await?;
+    let mut pending = PendingSwaps::new(cfg.pending_swap_max_blocks);
+
+    loop {
+        tokio::select! {
+            Some(tx) = transactions.next() => {
+                if tx.to() != Some(cfg.router_addr) {
+                    continue;
+                }
+                let Some(swap) = decode_pending_swap(cfg, tx.input()) else {
+                    continue;
+                };
+                pending.insert(tx.tx_hash(), tx.from(), tx.nonce(), swap, latest);
+            }
+            Some(header) = blocks.next() => {
+                let block = provider
+                    .get_block_by_hash(header.hash)
+                    .full()
+                    .await?
+                    .ok_or_else(|| anyhow!("block {} not found", header.hash))?;
+                let included: Vec<(Address, u64)> = block.transactions.txns().map(|tx| (tx.from(), tx.nonce())).collect();
+                latest = header.number;
+                pending.on_block(latest, &included);
+                pool = read_pool(&provider, cfg.pool_addr).await?;
+            }
+            result = dex_rx.changed() => result?,
+            else => return Ok(()),
+        }
+
+        let Some(dex) = dex_rx.borrow().clone() else {
+            continue;
+        };
+        let after = pending
+            .swaps()
+            .fold(pool, |p, s| p.after_swap(s.zero_for_one, s.exact_input, s.amount, cfg.dex_fee_tier));
+        let predicted = predict_price(&dex, &pool, &after, base_is_token0);
+
+        if !pending.is_empty() {
+            info!(
+                "🔮 {} pending swaps move HYPERSWAP to bid ${:.4} ask ${:.4} (now ${:.4}/${:.4})",
+                pending.len(),
+                predicted.bid,
+                predicted.ask,
+                dex.bid,
+                dex.ask
+            );
+        }
+        predicted_tx.send_if_modified(|current| {
+            let changed = current.as_ref() != Some(&predicted);
+            *current = Some(predicted);
+            changed
+        });
+    }
+}
+
+async fn read_pool(provider: &impl Provider, pool: Address) -> Result<PoolPrice> {
+    let (slot0, liquidity) = tokio::try_join!(
+        provider.get_storage_at(pool, U256::from(POOL_SLOT0)).into_future(),
+        provider.get_storage_at(pool, U256::from(POOL_LIQUIDITY)).into_future(),
+    )?;
+
+    Ok(PoolPrice::from_storage(slot0, liquidity))
+}
@@ -1,3 +1,206 @@
+pub mod book;
 pub mod bybit;
+pub mod declarative;
+pub mod feed;
 pub mod gateio;
+pub mod hyperliquid;
+pub mod hyperliquid_oracle;
 pub mod hyperswap;
+pub mod mempool;
+pub mod okx;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio::sync::watch::Sender;
+use tokio::time::sleep;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+use crate::adapters::declarative::DeclarativeAdapter;
+use crate::adapters::feed::CexFeed;
+use crate::arbitrage::PriceData;
+use crate::risk::RiskEvent;
+use crate::settings::Settings;
+
+/// Reads the messages of one connection into prices, `None` for messages
+/// that carry none. Fresh per connection, so state such as an order book
+/// starts over on reconnect; an error, e.g. a book out of sequence, ends
+/// the connection for that.
+pub type Decoder = Box<dyn FnMut(&str) -> Result<Option<PriceData>> + Send>;
+
+/// A CEX venue streaming top of book over a websocket: where to connect,
+/// how to subscribe to a symbol and how to read what comes back.
+/// `run_listener` does the rest, so a new venue is one implementation
+/// plus an entry in `AdapterRegistry::builtin`, or no code at all for a
+/// venue `DeclarativeAdapter` can read.
+pub trait ExchangeAdapter: Send + Sync {
+    /// Name in `cex_venues`, engine names and logs.
+    fn venue(&self) -> &'static str;
+
+    /// Symbol of the configured pair on this venue.
+    fn ticker(&self, cfg: &Settings) -> String;
+
+    fn endpoint(&self, cfg: &Settings) -> String;
+
+    /// Messages to send once connected to stream `ticker`.
+    fn subscribe(&self, cfg: &Settings, ticker: &str) -> Vec<Value>;
+
+    fn decoder(&self, cfg: &Settings, ticker: &str) -> Decoder;
+
+    /// Text message to send after this long without one from the venue,
+    /// for venues keeping connections alive at the application level
+    /// rather than with ping frames. Silence after it ends the connection.
+    fn keepalive(&self) -> Option<(Duration, &'static str)> {
+        None
+    }
+}
+
+/// The venues the bot can stream, by name.
+#[derive(Clone)]
+pub struct AdapterRegistry {
+    adapters: BTreeMap<&'static str, Arc<dyn ExchangeAdapter>>,
+}
+
+impl AdapterRegistry {
+    pub fn empty() -> Self {
+        Self { adapters: BTreeMap::new() }
+    }
+
+    /// Every venue this build supports.
+    pub fn builtin() -> Self {
+        Self::empty().with(bybit::Bybit).with(gateio::Gateio).with(hyperliquid::Hyperliquid).with(okx::Okx)
+    }
+
+    /// Every builtin venue plus those described in `ws_venues`, whose names
+    /// must be new.
+    pub fn configured(cfg: &Settings) -> Result<Self> {
+        let mut registry = Self::builtin();
+        for spec in &cfg.ws_venues {
+            if registry.adapters.contains_key(spec.name.as_str()) {
+                bail!("ws venue {} is already defined", spec.name);
+            }
+            registry = registry.with(DeclarativeAdapter::new(spec)?);
+        }
+        Ok(registry)
+    }
+
+    pub fn with(mut self, adapter: impl ExchangeAdapter + 'static) -> Self {
+        self.adapters.insert(adapter.venue(), Arc::new(adapter));
+        self
+    }
+
+    pub fn get(&self, venue: &str) -> Result<Arc<dyn ExchangeAdapter>> {
+        self.adapters.get(venue).cloned().ok_or_else(|| {
+            let known: Vec<_> = self.adapters.keys().copied().collect();
+            anyhow!("no exchange adapter for {:?}, known: {}", venue, known.join(", "))
+        })
+    }
+
+    /// Adapters of `cex_venues`, in the configured order.
+    pub fn from_config(&self, cfg: &Settings) -> Result<Vec<Arc<dyn ExchangeAdapter>>> {
+        cfg.cex_venues.iter().map(|venue| self.get(venue)).collect()
+    }
+}
+
+/// Streams the top of book of `ticker` on `adapter`'s venue into `tx`,
+/// reconnecting whenever the connection drops.
+pub async fn run_listener(
+    adapter: Arc<dyn ExchangeAdapter>,
+    cfg: Settings,
+    ticker: String,
+    tx: Sender<Option<PriceData>>,
+    risk_tx: broadcast::Sender<RiskEvent>,
+) {
+    let venue = adapter.venue().to_string();
+    run_labeled_listener(adapter, venue, cfg, ticker, tx, risk_tx).await
+}
+
+/// As `run_listener`, but logs and reports halts as `label`, so a second
+/// feed on the same venue neither raises nor clears the main feed's halt.
+pub async fn run_labeled_listener(
+    adapter: Arc<dyn ExchangeAdapter>,
+    label: String,
+    cfg: Settings,
+    ticker: String,
+    tx: Sender<Option<PriceData>>,
+    risk_tx: broadcast::Sender<RiskEvent>,
+) {
+    let venue = label.as_str();
+    loop {
+        match connect_and_subscribe(&*adapter, venue, &cfg, &ticker, &tx, &risk_tx).await {
+            Ok(_) => info!("{} ws connection closed normally", venue),
+            Err(e) => error!("{} ws connection error: {}", venue, e),
+        }
+
+        info!("reconnecting in 5 seconds...");
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn connect_and_subscribe(
+    adapter: &dyn ExchangeAdapter,
+    venue: &str,
+    cfg: &Settings,
+    ticker: &str,
+    tx: &Sender<Option<PriceData>>,
+    risk_tx: &broadcast::Sender<RiskEvent>,
+) -> Result<()> {
+    let endpoint = adapter.endpoint(cfg);
+    let (ws_stream, _) = connect_async(&endpoint).await?;
+    info!("connected to {} webSocket: {}", venue, endpoint);
+
+    let (mut write, mut read) = ws_stream.split();
+    for msg in adapter.subscribe(cfg, ticker) {
+        write.send(Message::Text(msg.to_string())).await?;
+    }
+    info!("subscribed to {} {}", venue, ticker);
+
+    let mut feed = CexFeed::new(venue, ticker).inverted(cfg.is_inverse(ticker));
+    let mut decode = adapter.decoder(cfg, ticker);
+    let keepalive = adapter.keepalive();
+    let mut pinged = false;
+
+    loop {
+        let silence = async {
+            match keepalive {
+                Some((after, _)) => sleep(after).await,
+                None => std::future::pending().await,
+            }
+        };
+        let msg = tokio::select! {
+            msg = read.next() => msg,
+            _ = silence => {
+                let Some((after, ping)) = keepalive else { continue };
+                if pinged {
+                    bail!("{} silent for {:?} after a keepalive", venue, after);
+                }
+                write.send(Message::Text(ping.to_string())).await?;
+                pinged = true;
+                continue;
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+        pinged = false;
+
+        match msg? {
+            Message::Text(text) => {
+                if let Some(price) = decode(&text)? {
+                    feed.update(price, tx, risk_tx);
+                }
+            }
+            Message::Ping(ping) => write.send(Message::Pong(ping)).await?,
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
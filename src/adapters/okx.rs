@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+
+use crate::adapters::book::OrderBook;
+use crate::adapters::feed::exchange_ts;
+use crate::adapters::{Decoder, ExchangeAdapter};
+use crate::settings::Settings;
+
+// okx drops connections silent for 30s; the client pings, not the server
+const PING_AFTER: Duration = Duration::from_secs(25);
+
+/// OKX v5 public `books5` stream: five levels a side, each push a full
+/// snapshot, priced at `exec_base_size` like the bybit book. Kept alive
+/// with text "ping" messages answered by "pong" rather than ping frames.
+pub struct Okx;
+
+impl ExchangeAdapter for Okx {
+    fn venue(&self) -> &'static str {
+        "okx"
+    }
+
+    fn ticker(&self, cfg: &Settings) -> String {
+        cfg.okx_ticker.clone()
+    }
+
+    fn endpoint(&self, cfg: &Settings) -> String {
+        cfg.okx_ws_endpoint.clone()
+    }
+
+    fn subscribe(&self, _cfg: &Settings, ticker: &str) -> Vec<Value> {
+        vec![json!({
+            "op": "subscribe",
+            "args": [{ "channel": "books5", "instId": ticker }]
+        })]
+    }
+
+    fn decoder(&self, cfg: &Settings, ticker: &str) -> Decoder {
+        let size = if cfg.is_inverse(ticker) { 0.0 } else { cfg.exec_base_size };
+        Box::new(move |text| Ok(parse_books5(text)?.map(|book| book.executable(size))))
+    }
+
+    fn keepalive(&self) -> Option<(Duration, &'static str)> {
+        Some((PING_AFTER, "ping"))
+    }
+}
+
+/// The book in one okx `books5` push, `None` for pongs, subscription acks
+/// and anything else without one. An error event is an error.
+pub fn parse_books5(text: &str) -> Result<Option<OrderBook>> {
+    let Ok(message) = serde_json::from_str::<Value>(text) else {
+        return Ok(None);
+    };
+    if message["event"] == "error" {
+        bail!("okx {} ({})", message["msg"], message["code"]);
+    }
+    if message["arg"]["channel"] != "books5" {
+        return Ok(None);
+    }
+    // levels are [price, size, deprecated, orders]
+    Ok(message["data"].get(0).map(|book| {
+        let mut parsed = OrderBook::from_json(book, "bids", "asks");
+        parsed.stamp(exchange_ts(book.get("ts")));
+        parsed
+    }))
+}
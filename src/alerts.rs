@@ -0,0 +1,156 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use anyhow::Result;
+use log::{info, warn};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::notify::{Notification, QUEUE_CAPACITY, http_client};
+use crate::settings::{Secret, Settings};
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// A chat alerts are pushed to.
+#[derive(Debug, Clone)]
+pub enum ChatTarget {
+    Telegram { api_url: String, token: Secret, chat_id: String },
+    Discord { url: Secret },
+}
+
+impl ChatTarget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChatTarget::Telegram { .. } => "telegram",
+            ChatTarget::Discord { .. } => "discord",
+        }
+    }
+}
+
+/// Lets an alert per key out at most once per `cooldown` and at most
+/// `per_hour` alerts overall, so a persistent spread does not flood the
+/// chat. Counts what it held back per key until the key's next alert.
+#[derive(Debug)]
+pub struct AlertLimiter {
+    cooldown: Duration,
+    per_hour: Option<u32>,
+    last: HashMap<String, Instant>,
+    sent: VecDeque<Instant>,
+    held_back: HashMap<String, u32>,
+}
+
+impl AlertLimiter {
+    pub fn new(cooldown: Duration, per_hour: Option<u32>) -> Self {
+        Self { cooldown, per_hour, last: HashMap::new(), sent: VecDeque::new(), held_back: HashMap::new() }
+    }
+
+    /// Whether an alert for `key` may go out at `now`, with how many were
+    /// held back for it since its last one.
+    pub fn admit(&mut self, key: &str, now: Instant) -> Option<u32> {
+        while self.sent.front().is_some_and(|t| now.duration_since(*t) >= HOUR) {
+            self.sent.pop_front();
+        }
+        let cooling = self.last.get(key).is_some_and(|t| now.duration_since(*t) < self.cooldown);
+        let capped = self.per_hour.is_some_and(|max| self.sent.len() as u32 >= max);
+        if cooling || capped {
+            *self.held_back.entry(key.to_string()).or_default() += 1;
+            return None;
+        }
+
+        self.last.insert(key.to_string(), now);
+        self.sent.push_back(now);
+        Some(self.held_back.remove(key).unwrap_or_default())
+    }
+}
+
+/// What alerts are rate-limited by: venue, pair and direction of an
+/// opportunity. `None` for other notifications.
+pub fn alert_key(notification: &Notification) -> Option<String> {
+    let Notification::Opportunity { venue, direction, details, .. } = notification else {
+        return None;
+    };
+    Some(format!("{}/{}/{:?}", venue, details.pair, direction))
+}
+
+/// The chat message of an opportunity, `None` for other notifications.
+pub fn format_alert(notification: &Notification, held_back: u32) -> Option<String> {
+    let Notification::Opportunity { id, venue, direction, net, details, .. } = notification else {
+        return None;
+    };
+    let mut text = format!(
+        "💡 {} {:?} on {}\nsize {:.4}, net ${:.4} (${:.4}/unit)\ncex {:.6}, dex {:.6}\n[{}]",
+        details.pair, direction, venue, details.size, details.expected_net, net, details.cex_price, details.dex_price, id
+    );
+    if held_back > 0 {
+        text.push_str(&format!("\n{} more alerts held back", held_back));
+    }
+    Some(text)
+}
+
+/// Pushes opportunity alerts to Telegram and/or Discord through
+/// `AlertLimiter`. Failed deliveries are logged and dropped.
+pub struct ChatAlerts {
+    http: reqwest::Client,
+    targets: Vec<ChatTarget>,
+    limiter: AlertLimiter,
+    rx: mpsc::Receiver<Notification>,
+}
+
+impl ChatAlerts {
+    pub fn new(targets: Vec<ChatTarget>, limiter: AlertLimiter, rx: mpsc::Receiver<Notification>) -> Self {
+        Self { http: http_client(), targets, limiter, rx }
+    }
+
+    /// The configured chats and the sender feeding them, see
+    /// `Notifier::with_chat`. `None` when no chat is configured.
+    pub fn from_config(cfg: &Settings) -> Option<(mpsc::Sender<Notification>, Self)> {
+        let mut targets = Vec::new();
+        if let (Some(token), Some(chat_id)) = (&cfg.telegram_bot_token, &cfg.telegram_chat_id) {
+            let api_url = cfg.telegram_api_url.trim_end_matches('/').to_string();
+            targets.push(ChatTarget::Telegram { api_url, token: token.clone(), chat_id: chat_id.clone() });
+        }
+        if let Some(url) = &cfg.discord_webhook_url {
+            targets.push(ChatTarget::Discord { url: url.clone() });
+        }
+        if targets.is_empty() {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let limiter = AlertLimiter::new(Duration::from_secs(cfg.chat_alert_cooldown_secs), cfg.chat_max_alerts_per_hour);
+        Some((tx, Self::new(targets, limiter, rx)))
+    }
+
+    pub async fn run(mut self) {
+        let names: Vec<_> = self.targets.iter().map(|t| t.name()).collect();
+        info!("pushing opportunity alerts to {}", names.join(" and "));
+        while let Some(notification) = self.rx.recv().await {
+            let Some(key) = alert_key(&notification) else {
+                continue;
+            };
+            let Some(text) = self.limiter.admit(&key, Instant::now()).and_then(|held_back| format_alert(&notification, held_back))
+            else {
+                continue;
+            };
+            for target in &self.targets {
+                if let Err(e) = self.deliver(target, &text).await {
+                    warn!("{} alert failed: {:#}", target.name(), e);
+                }
+            }
+        }
+    }
+
+    async fn deliver(&self, target: &ChatTarget, text: &str) -> Result<()> {
+        let request = match target {
+            ChatTarget::Telegram { api_url, token, chat_id } => self
+                .http
+                .post(format!("{}/bot{}/sendMessage", api_url, token.expose()))
+                .json(&json!({ "chat_id": chat_id, "text": text })),
+            ChatTarget::Discord { url } => self.http.post(url.expose()).json(&json!({ "content": text })),
+        };
+        // both urls hold a secret, keep it out of the error
+        request.send().await.map_err(|e| e.without_url())?.error_for_status().map_err(|e| e.without_url())?;
+        Ok(())
+    }
+}
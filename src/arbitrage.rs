@@ -1,125 +1,936 @@
+use crate::audit::Decision;
+use crate::borrow::BorrowCost;
+use crate::clock::{Clock, SystemClock};
+use crate::execution::executor::expected_net;
+use crate::notify::{Notifier, OpportunityDetails};
+use crate::opportunity::Opportunity;
+use crate::pnl::TradeRecord;
+use crate::risk::{RiskEvent, RiskHandle};
+use crate::scanner::DEFAULT_PAIR;
 use crate::settings::Settings;
+use crate::sizing::Sizer;
+use crate::strategy::{self, Strategy};
+use crate::telemetry::{Span, Tracer};
 use alloy::providers::Provider;
 use anyhow::Result;
-use log::{info};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::watch;
+use tokio::time::{Instant, sleep};
 
+/// When a quote was taken, for telling how old it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteSource {
+    /// a DEX quote on the state of this block
+    Block(u64),
+    /// a CEX book or ticker the exchange stamped at this unix ms
+    Exchange(u64),
+}
+
+/// Best bid and ask of base in quote, see `Denomination`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct PriceData {
     pub bid: f64,
     pub ask: f64,
+    // `None` when the feed does not say, aged from its arrival then
+    pub source: Option<QuoteSource>,
+    // a side of the book could not fill the size this is priced at, see
+    // `OrderBook::executable`
+    pub thin: bool,
+}
+
+impl PriceData {
+    pub fn new(bid: f64, ask: f64) -> Self {
+        Self { bid, ask, source: None, thin: false }
+    }
+
+    pub fn with_source(mut self, source: Option<QuoteSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_thin(mut self, thin: bool) -> Self {
+        self.thin = thin;
+        self
+    }
+
+    /// The same book quoted the other way round: bidding for base is
+    /// offering quote. An empty side stays 0.
+    pub fn inverted(&self) -> Self {
+        let flip = |p: f64| if p > 0.0 { 1.0 / p } else { 0.0 };
+        Self { bid: flip(self.ask), ask: flip(self.bid), source: self.source, thin: self.thin }
+    }
+
+    /// Whether `other` quotes the same bid and ask, however old.
+    pub fn same_quote(&self, other: &PriceData) -> bool {
+        self.bid == other.bid && self.ask == other.ask && self.thin == other.thin
+    }
+}
+
+/// What prices are quoted in: units of `quote` per unit of `base`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denomination {
+    pub base: String,
+    pub quote: String,
+}
+
+impl Denomination {
+    pub fn inverted(&self) -> Self {
+        Self { base: self.quote.clone(), quote: self.base.clone() }
+    }
+
+    /// Which side of the pair gas is paid in, `None` when neither.
+    pub fn gas_token(&self, native: &str) -> Option<GasToken> {
+        if self.base == native {
+            Some(GasToken::Base)
+        } else if self.quote == native {
+            Some(GasToken::Quote)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for Denomination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+/// The side of the pair that is the chain's gas token, which decides how
+/// gas is priced in quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GasToken {
+    // e.g. HYPE/USDT: gas costs the base price per token
+    #[default]
+    Base,
+    // an inverse pair such as USDT/HYPE: gas is already in quote
+    Quote,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ArbDirection {
     BuyCex,
     BuyDex,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leg {
+    Cex,
+    Dex,
+}
+
+/// Both legs as evaluated in one tick, the DEX side already bridged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickPrices {
+    pub cex: PriceData,
+    pub dex: PriceData,
+    // USD per quote token, with `with_quote_peg`
+    pub peg: Option<f64>,
+    // what is left of the edge given how far apart the legs were quoted,
+    // see `age_weight`; with `quote_age_half_life_ms`
+    pub age_weight: Option<f64>,
+}
+
+impl TickPrices {
+    /// The CEX and DEX sides the legs of `direction` take.
+    pub fn legs(&self, direction: &ArbDirection) -> (f64, f64) {
+        match direction {
+            ArbDirection::BuyCex => (self.cex.ask, self.dex.bid),
+            ArbDirection::BuyDex => (self.cex.bid, self.dex.ask),
+        }
+    }
+}
+
+/// One leg's feed went quiet: the engine only monitors until it is back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Degraded {
+    pub leg: Leg,
+    pub since: Instant,
+    // last time the spread against the live leg was logged
+    logged_at: Instant,
+}
+
+/// Feeds an engine needs before its first evaluation. A feed is warm once
+/// it delivered a valid price after the engine started, so neither a price
+/// restored from `state_file` nor an empty or crossed book counts. The DEX
+/// feed doubles as the REVM cache check: the pool listener publishes only
+/// once its cache is hydrated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warmup {
+    waiting: Vec<&'static str>,
+    since: Instant,
+    // last time the wait was logged
+    logged_at: Option<Instant>,
+}
+
+impl Warmup {
+    pub fn new(feeds: &[&'static str]) -> Self {
+        Self { waiting: feeds.to_vec(), since: Instant::now(), logged_at: None }
+    }
+
+    pub fn require(&mut self, feed: &'static str) {
+        if !self.waiting.contains(&feed) {
+            self.waiting.push(feed);
+        }
+    }
+
+    /// Marks `feed` warm when it delivered a valid `price`.
+    pub fn delivered(&mut self, feed: &str, price: Option<&PriceData>) {
+        if price.is_some_and(is_valid_price) {
+            self.waiting.retain(|f| *f != feed);
+        }
+    }
+
+    /// The feeds still to deliver a price.
+    pub fn waiting(&self) -> &[&'static str] {
+        &self.waiting
+    }
+
+    pub fn is_warm(&self) -> bool {
+        self.waiting.is_empty()
+    }
+}
+
+/// Both sides positive and finite, the bid not above the ask.
+pub fn is_valid_price(price: &PriceData) -> bool {
+    let ok = |p: f64| p.is_finite() && p > 0.0;
+    ok(price.bid) && ok(price.ask) && price.bid <= price.ask
+}
+
+/// A price feed by the kind of venue behind it, as CEX books and DEX
+/// quotes are traded differently.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VenueId {
+    Cex(String),
+    Dex(String),
+}
+
+impl VenueId {
+    pub fn name(&self) -> &str {
+        match self {
+            VenueId::Cex(name) | VenueId::Dex(name) => name,
+        }
+    }
+
+    pub fn is_dex(&self) -> bool {
+        matches!(self, VenueId::Dex(_))
+    }
+}
+
+/// The DEX venue engines trade against unless told otherwise, and the venue
+/// name of recorded DEX ticks.
+pub const DEX_VENUE: &str = "hyperswap";
+
+// a CEX on the other leg pays the taker fee too, and no gas
+fn cross_cex_settings(cfg: &Settings) -> Settings {
+    Settings { cex_fee_bps: cfg.cex_fee_bps * 2, dex_gas_used: 0, ..cfg.clone() }
+}
+
 pub struct ArbEngine {
     pub config: Settings,
+    pub venue: String,
+    // the venue of the other leg, priced from `dex_rx`: a DEX unless two
+    // CEXes are compared, see `across`
+    pub counter: VenueId,
     pub cex_rx: watch::Receiver<Option<PriceData>>,
     pub dex_rx: watch::Receiver<Option<PriceData>>,
     pub provider: Arc<dyn Provider>,
+    pub risk: RiskHandle,
+    // converts DEX prices into the CEX quote currency, see `with_dex_bridge`
+    pub bridge_rx: Option<watch::Receiver<Option<PriceData>>>,
+    // re-denominates profits in USD, see `with_quote_peg`
+    pub peg_rx: Option<watch::Receiver<Option<PriceData>>>,
+    pub notifier: Notifier,
+    pub tracer: Tracer,
+    // sizes the best opportunities, see `with_sizer`
+    sizer: Option<Arc<Sizer>>,
+    strategy: Box<dyn Strategy>,
+    borrow: BorrowCost,
+    spread_out_of_bounds: bool,
+    feed_stale: Duration,
+    cex_updated_at: Option<Instant>,
+    dex_updated_at: Option<Instant>,
+    // the block of the latest DEX quote and when it was first seen
+    dex_block: Option<(u64, Instant)>,
+    degraded: Option<Degraded>,
+    // until every feed is warm, see `Warmup`
+    warmup: Option<Warmup>,
+    // what the last tick evaluated, for notifications
+    last_prices: Option<TickPrices>,
+    // staleness and warmup are timed on this, see `with_clock`
+    clock: Arc<dyn Clock>,
 }
 
 impl ArbEngine {
     pub fn new(
         config: Settings,
+        venue: &str,
         cex_rx: watch::Receiver<Option<PriceData>>,
         dex_rx: watch::Receiver<Option<PriceData>>,
         provider: Arc<dyn Provider>,
+        risk: RiskHandle,
     ) -> Self {
+        let strategy = strategy::from_config(&config);
+        info!("{} engine running strategy {}", venue, strategy.name());
+
         Self {
-            config,
+            venue: venue.to_string(),
+            counter: VenueId::Dex(DEX_VENUE.to_string()),
             cex_rx,
             dex_rx,
             provider,
+            risk,
+            bridge_rx: None,
+            peg_rx: None,
+            notifier: Notifier::default(),
+            tracer: Tracer::default(),
+            sizer: None,
+            strategy,
+            borrow: BorrowCost::new(&config, venue),
+            spread_out_of_bounds: false,
+            feed_stale: Duration::from_secs(config.feed_stale_secs),
+            cex_updated_at: None,
+            dex_updated_at: None,
+            dex_block: None,
+            degraded: None,
+            warmup: Some(Warmup::new(&["cex", "dex"])),
+            last_prices: None,
+            clock: Arc::new(SystemClock),
+            config,
+        }
+    }
+
+    /// One engine for every pair of `feeds` worth comparing: each CEX
+    /// against each DEX and, with `scan_cross_cex`, each two CEXes once,
+    /// both directions being evaluated anyway. The CEX listed first is the
+    /// engine's `venue`, the other venue its `counter`.
+    pub fn across(
+        config: &Settings,
+        feeds: &[(VenueId, watch::Receiver<Option<PriceData>>)],
+        provider: Arc<dyn Provider>,
+        risk: RiskHandle,
+    ) -> Vec<Self> {
+        let mut engines = Vec::new();
+        for (i, (venue, cex_rx)) in feeds.iter().enumerate() {
+            let VenueId::Cex(name) = venue else {
+                continue;
+            };
+            for (j, (counter, counter_rx)) in feeds.iter().enumerate() {
+                let cfg = match counter {
+                    VenueId::Dex(_) => config.clone(),
+                    VenueId::Cex(_) if config.scan_cross_cex && j > i => cross_cex_settings(config),
+                    VenueId::Cex(_) => continue,
+                };
+                let engine = Self::new(cfg, name, cex_rx.clone(), counter_rx.clone(), provider.clone(), risk.clone());
+                engines.push(engine.with_counter(counter.clone()));
+            }
+        }
+        engines
+    }
+
+    /// Prices the other leg as `counter`, `DEX_VENUE` by default.
+    pub fn with_counter(mut self, counter: VenueId) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Cross-pair mode: the DEX pool quotes base in an intermediate asset
+    /// and `bridge_rx` prices that asset in the CEX quote currency, e.g.
+    /// HYPE/USDC on the DEX with USDC/USDT from a CEX against HYPE/USDT.
+    pub fn with_dex_bridge(mut self, bridge_rx: watch::Receiver<Option<PriceData>>) -> Self {
+        self.bridge_rx = Some(bridge_rx);
+        self.require("bridge");
+        self
+    }
+
+    /// Counts profits in USD through a peg feed of the quote token (see
+    /// `quote_peg`) rather than in the quote token itself.
+    pub fn with_quote_peg(mut self, peg_rx: watch::Receiver<Option<PriceData>>) -> Self {
+        self.peg_rx = Some(peg_rx);
+        self.require("peg");
+        self
+    }
+
+    /// Reports opportunities and trades to the webhook behind `notifier`.
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// Traces each tick through the gas price read and the strategy.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Times feed staleness, warmup and the strategy on `clock` rather than
+    /// the wall clock, e.g. a `SimClock` replaying recorded ticks.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        if let Some(warmup) = &mut self.warmup {
+            warmup.since = clock.now();
         }
+        self.strategy.set_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Searches the size netting the most for the best opportunities;
+    /// without it they trade `exec_base_size`.
+    pub fn with_sizer(mut self, sizer: Arc<Sizer>) -> Self {
+        self.sizer = Some(sizer);
+        self
+    }
+
+    /// The sizer of this engine's opportunities, none for a cross-pair
+    /// engine as its DEX quotes are in another asset, nor against a CEX.
+    pub fn sizer(&self) -> Option<Arc<Sizer>> {
+        self.sizer.clone().filter(|_| self.bridge_rx.is_none() && self.counter.is_dex())
     }
 
     pub async fn run(&mut self) -> Result<()> {
         info!("starting arbitrage engine...");
 
         loop {
-            tokio::select! {
-                _ = self.cex_rx.changed() => {
-                    self.check_for_opportunity().await?;
+            let bridge_changed = async {
+                match self.bridge_rx.as_mut() {
+                    Some(rx) => rx.changed().await,
+                    None => std::future::pending().await,
                 }
-                _ = self.dex_rx.changed() => {
-                    self.check_for_opportunity().await?;
+            };
+
+            // the timeout notices a leg going quiet while the other is quiet too
+            let updated = tokio::select! {
+                _ = self.cex_rx.changed() => Some("cex"),
+                _ = self.dex_rx.changed() => Some("dex"),
+                _ = bridge_changed => Some("bridge"),
+                _ = sleep(self.feed_stale) => None,
+            };
+            if let Some(feed) = updated {
+                self.delivered(feed, self.clock.now());
+            }
+            let sizer = self.sizer();
+            for opportunity in self.check_for_opportunity().await? {
+                if opportunity.profit.net > 0.0 {
+                    info!(
+                        "💡 {} {:?} net ${:.4} [{}]",
+                        self.venue, opportunity.direction, opportunity.profit.net, opportunity.id
+                    );
+                }
+                let (cex_price, dex_price) = self.last_prices.as_ref().map_or((0.0, 0.0), |p| p.legs(&opportunity.direction));
+                let size = self.config.exec_base_size;
+                let mut details = OpportunityDetails {
+                    pair: DEFAULT_PAIR.to_string(),
+                    size,
+                    expected_net: expected_net(&opportunity.profit, size),
+                    cex_price,
+                    dex_price,
+                };
+                // sized as the scanner sizes its best, when it beats its
+                // costs before gas
+                if let Some(sizer) = &sizer
+                    && opportunity.profit.net + opportunity.profit.gas_cost > 0.0
+                {
+                    let peg = self.last_prices.as_ref().and_then(|p| p.peg);
+                    match sizer.size(&opportunity.direction, &opportunity.profit, peg).await {
+                        Ok(Some(sized)) => {
+                            details = OpportunityDetails {
+                                size: sized.size,
+                                expected_net: sized.net,
+                                cex_price: sized.cex_price,
+                                dex_price: sized.dex_price,
+                                ..details
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("sizing {} {:?} failed: {:#}", self.venue, opportunity.direction, e),
+                    }
                 }
+                self.notifier.opportunity(&self.venue, &opportunity, details);
             }
         }
     }
 
-    pub async fn check_for_opportunity(&mut self) -> Result<()> {
-        let cex_data = self.cex_rx.borrow().clone();
-        let dex_data = self.dex_rx.borrow().clone();
-
-        let (cex_price, dex_price) = match (cex_data.as_ref(), dex_data.as_ref()) {
-            (Some(cex), Some(dex)) => (cex, dex),
-            _ => return Ok(()),
+    /// Runs the strategy on the latest prices. Returns nothing while either
+    /// feed is empty, degraded or trading is paused.
+    pub async fn check_for_opportunity(&mut self) -> Result<Vec<Opportunity>> {
+        let Some(prices) = self.tick_prices() else {
+            return Ok(Vec::new());
         };
 
-        let gas_price_wei = self.provider.get_gas_price().await?;
+        let mut tick = self.tracer.span("tick");
+        tick.set("venue", self.venue.as_str());
+        tick.set("strategy", self.strategy.name());
 
-        // if dex_price.bid > cex_price.ask {
-            self.calculate_arbitrage(
-                cex_price.ask,
-                dex_price.bid,
-                ArbDirection::BuyCex,
-                gas_price_wei,
-            );
-        // }
-        // if cex_price.bid > dex_price.ask {
-            self.calculate_arbitrage(
-                dex_price.ask,
-                cex_price.bid,
-                ArbDirection::BuyDex,
-                gas_price_wei,
-            );
-        // }
+        let gas_span = tick.child("gas_price");
+        let gas_price_wei = self.provider.get_gas_price().await?;
+        gas_span.end();
 
-        Ok(())
+        Ok(self.evaluate(&prices, gas_price_wei, &tick))
     }
 
-    fn calculate_arbitrage(
-        &self,
-        buy_price: f64,
-        sell_price: f64,
-        direction: ArbDirection,
-        gas_price_wei: u128,
-    ) {
-        let gross_profit = sell_price - buy_price;
+    /// The prices to evaluate this tick, `None` while either feed is empty,
+    /// too thin for the size it is priced at, degraded or trading is paused.
+    pub fn tick_prices(&mut self) -> Option<TickPrices> {
+        // updates `run` did not wait for itself
+        let now = self.clock.now();
+        let changed = |rx: Option<&watch::Receiver<Option<PriceData>>>| rx.is_some_and(|rx| rx.has_changed().unwrap_or(false));
+        let updated: Vec<_> = [
+            ("cex", Some(&self.cex_rx)),
+            ("dex", Some(&self.dex_rx)),
+            ("bridge", self.bridge_rx.as_ref()),
+            ("peg", self.peg_rx.as_ref()),
+        ]
+        .into_iter()
+        .filter(|(_, rx)| changed(*rx))
+        .map(|(feed, _)| feed)
+        .collect();
+        for feed in updated {
+            self.delivered(feed, now);
+        }
+        if self.warming_up(now) {
+            return None;
+        }
+
+        let cex_data = self.cex_rx.borrow_and_update().clone();
+        let dex_data = match &self.bridge_rx {
+            Some(bridge_rx) => match (self.dex_rx.borrow_and_update().as_ref(), bridge_rx.borrow().as_ref()) {
+                (Some(dex), Some(bridge)) => Some(implied_price(dex, bridge)),
+                _ => None,
+            },
+            None => self.dex_rx.borrow_and_update().clone(),
+        };
+
+        let (Some(cex), Some(dex)) = (cex_data, dex_data) else {
+            return None;
+        };
+        // the book can't fill the trade size, so neither leg is priced right
+        if cex.thin || dex.thin {
+            return None;
+        }
+
+        if self.check_feeds(&cex, &dex, now) {
+            return None;
+        }
 
-        // Calculate gas cost in HYPE tokens
-        let gas_cost_wei = gas_price_wei * self.config.dex_gas_used as u128;
-        let gas_cost_hype = gas_cost_wei as f64 / 1e18;
+        self.strategy.on_price_update(&cex, &dex);
+        self.check_spread(&cex, &dex);
+        if self.risk.is_paused() {
+            return None;
+        }
 
-        let hype_price = match direction {
-            ArbDirection::BuyCex => sell_price,
-            ArbDirection::BuyDex => buy_price,
+        // without a peg there is no telling what a quote-token profit is worth
+        let peg = match &self.peg_rx {
+            Some(peg_rx) => Some(quote_peg(peg_rx.borrow().as_ref()?)),
+            None => None,
         };
-        let gas_cost_usd = gas_cost_hype * hype_price;
 
-        let cex_price = match direction {
-            ArbDirection::BuyCex => buy_price,
-            ArbDirection::BuyDex => sell_price,
+        let age_weight = self.config.quote_age_half_life_ms.map(|half_life_ms| {
+            let (cex_age_ms, dex_age_ms) = self.quote_ages(&cex, &dex, now);
+            age_weight(cex_age_ms, dex_age_ms, half_life_ms)
+        });
+
+        let prices = TickPrices { cex, dex, peg, age_weight };
+        self.last_prices = Some(prices.clone());
+        Some(prices)
+    }
+
+    /// Runs the strategy on `prices` at the given gas price, traced as a
+    /// child of `tick`, and records the outcome with `risk.audit`. Every
+    /// candidate gets a fresh id.
+    pub fn evaluate(&mut self, prices: &TickPrices, gas_price_wei: u128, tick: &Span) -> Vec<Opportunity> {
+        let mut evaluate_span = tick.child("evaluate");
+        evaluate_span.set("venue", self.venue.as_str());
+        let found = price_candidates(self.strategy.as_mut(), &self.borrow, prices, gas_price_wei);
+        let opportunities: Vec<_> = found.iter().map(|(d, p)| Opportunity::new(d.clone(), *p)).collect();
+        let ids: Vec<_> = opportunities.iter().map(|o| o.id).collect();
+        evaluate_span.set("candidates", found.len());
+        evaluate_span.set("profitable", found.iter().filter(|(_, p)| p.net > 0.0).count());
+        evaluate_span.set("opportunity_ids", ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(","));
+        evaluate_span.end();
+
+        let inputs = ArbInputs::new(self.strategy.name(), prices, gas_price_wei);
+        self.risk.audit.record(arb_decision(&self.venue, &inputs, &found).with_opportunities(ids));
+        opportunities
+    }
+
+    pub fn strategy_name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    pub fn degraded(&self) -> Option<&Degraded> {
+        self.degraded.as_ref()
+    }
+
+    /// The feeds the engine still waits on before it evaluates, `None`
+    /// once warm.
+    pub fn warmup(&self) -> Option<&Warmup> {
+        self.warmup.as_ref()
+    }
+
+    fn require(&mut self, feed: &'static str) {
+        if let Some(warmup) = &mut self.warmup {
+            warmup.require(feed);
+        }
+    }
+
+    // `feed` published a new price
+    fn delivered(&mut self, feed: &str, now: Instant) {
+        let rx = match feed {
+            "cex" => {
+                self.cex_updated_at = Some(now);
+                Some(&self.cex_rx)
+            }
+            "dex" => {
+                self.dex_updated_at = Some(now);
+                Some(&self.dex_rx)
+            }
+            "bridge" => self.bridge_rx.as_ref(),
+            _ => self.peg_rx.as_ref(),
         };
-        let cex_fee_usd = (self.config.cex_fee_bps as f64 / 10000.0) * cex_price;
-        let net_profit = gross_profit - cex_fee_usd - gas_cost_usd;
+        if let (Some(warmup), Some(rx)) = (&mut self.warmup, rx) {
+            warmup.delivered(feed, rx.borrow().as_ref());
+        }
+    }
+
+    // how long ago each leg was quoted: by the exchange's timestamp of a
+    // CEX price, since its block was first seen for a DEX one, and since
+    // arrival for a feed stamping neither
+    fn quote_ages(&mut self, cex: &PriceData, dex: &PriceData, now: Instant) -> (u64, u64) {
+        let cex_age_ms = self.quote_age(cex.source, self.cex_updated_at, now);
+        let dex_age_ms = self.quote_age(dex.source, self.dex_updated_at, now);
+        (cex_age_ms, dex_age_ms)
+    }
+
+    fn quote_age(&mut self, source: Option<QuoteSource>, updated_at: Option<Instant>, now: Instant) -> u64 {
+        let since = |at: Option<Instant>| at.map_or(0, |at| now.duration_since(at).as_millis() as u64);
+        match source {
+            Some(QuoteSource::Exchange(ts_ms)) => self.clock.now_ms().saturating_sub(ts_ms),
+            Some(QuoteSource::Block(number)) => {
+                if self.dex_block.is_none_or(|(seen, _)| seen != number) {
+                    self.dex_block = Some((number, now));
+                }
+                since(self.dex_block.map(|(_, at)| at))
+            }
+            None => since(updated_at),
+        }
+    }
 
-        if net_profit <= 0.0 {
+    // true while a feed has yet to deliver, logged once per feed_stale_secs
+    fn warming_up(&mut self, now: Instant) -> bool {
+        let Some(warmup) = &mut self.warmup else {
+            return false;
+        };
+        if warmup.is_warm() {
+            info!("🔥 {} feeds warm after {:.0?}, evaluating", self.venue, now.duration_since(warmup.since));
+            self.warmup = None;
+            return false;
+        }
+        if warmup.logged_at.is_none_or(|at| now.duration_since(at) >= self.feed_stale) {
+            warmup.logged_at = Some(now);
             info!(
-                "🔴 NO ARB: buy ${:.4}, sell ${:.4}, net ${:.4}, cex fee: ${:.4}, gas: ${:.4}",
-                buy_price, sell_price, net_profit, cex_fee_usd, gas_cost_usd
+                "⏳ {} warming up for {:.0?}, waiting on {} feed",
+                self.venue,
+                now.duration_since(warmup.since),
+                warmup.waiting.join(", ")
             );
+        }
+        true
+    }
+
+    // a leg without an update for `feed_stale_secs` is frozen, not flat:
+    // evaluating against it would trade on a price that may be long gone
+    fn check_feeds(&mut self, cex_price: &PriceData, dex_price: &PriceData, now: Instant) -> bool {
+        let stale = |at: Option<Instant>| at.is_some_and(|at| now.duration_since(at) > self.feed_stale);
+        let leg = if stale(self.dex_updated_at) {
+            Some(Leg::Dex)
+        } else if stale(self.cex_updated_at) {
+            Some(Leg::Cex)
         } else {
-            info!(
-                "🟢 ARB: buy ${:.4}, sell ${:.4}, net ${:.4}, cex fee: ${:.4}, gas: ${:.4}",
-                buy_price, sell_price, net_profit, cex_fee_usd, gas_cost_usd
-            );
+            None
+        };
+
+        let Some(leg) = leg else {
+            if let Some(degraded) = self.degraded.take() {
+                info!(
+                    "✅ {} {:?} feed back after {:.0?}, resuming evaluation",
+                    self.venue,
+                    degraded.leg,
+                    now.duration_since(degraded.since)
+                );
+            }
+            return false;
+        };
+
+        let cex_mid = (cex_price.bid + cex_price.ask) / 2.0;
+        let dex_mid = (dex_price.bid + dex_price.ask) / 2.0;
+        let spread_bps = (dex_mid - cex_mid) / cex_mid * 10000.0;
+
+        match &mut self.degraded {
+            Some(degraded) if degraded.leg == leg => {
+                if now.duration_since(degraded.logged_at) >= self.feed_stale {
+                    degraded.logged_at = now;
+                    info!(
+                        "📉 {} degraded for {:.0?}: last known spread {:.1} bps (CEX mid ${:.4}, DEX mid ${:.4})",
+                        self.venue,
+                        now.duration_since(degraded.since),
+                        spread_bps,
+                        cex_mid,
+                        dex_mid
+                    );
+                }
+            }
+            _ => {
+                warn!(
+                    "⚠️ {} {:?} feed silent for over {:?}, monitoring only: last known spread {:.1} bps",
+                    self.venue, leg, self.feed_stale, spread_bps
+                );
+                self.degraded = Some(Degraded { leg, since: now, logged_at: now });
+            }
+        }
+        true
+    }
+
+    /// Whether a trade in `direction` closes a position, see
+    /// `Strategy::closes`.
+    pub fn closes(&self, direction: &ArbDirection) -> bool {
+        self.strategy.closes(direction)
+    }
+
+    /// Hands a settled trade back to the strategy.
+    pub fn on_fill(&mut self, trade: &TradeRecord) {
+        self.notifier.trade(&self.venue, trade);
+        self.strategy.on_fill(trade);
+    }
+
+    // a CEX/DEX gap wider than any real arb means one feed is broken
+    fn check_spread(&mut self, cex_price: &PriceData, dex_price: &PriceData) {
+        let cex_mid = (cex_price.bid + cex_price.ask) / 2.0;
+        let dex_mid = (dex_price.bid + dex_price.ask) / 2.0;
+        let spread_bps = (dex_mid - cex_mid).abs() / cex_mid * 10000.0;
+
+        let out_of_bounds = spread_bps > self.config.max_spread_bps as f64;
+        if out_of_bounds == self.spread_out_of_bounds {
+            return;
         }
+        self.spread_out_of_bounds = out_of_bounds;
+
+        let venue = self.venue.clone();
+        if out_of_bounds {
+            self.risk.emit(RiskEvent::SpreadOutOfBounds { venue, spread_bps });
+        } else {
+            self.risk.emit(RiskEvent::SpreadNormal { venue });
+        }
+    }
+}
+
+/// Price of base in the bridge's quote currency, through base/mid from
+/// `leg` and mid/quote from `bridge`: selling base hits both bids, buying
+/// it lifts both asks.
+pub fn implied_price(leg: &PriceData, bridge: &PriceData) -> PriceData {
+    PriceData {
+        bid: leg.bid * bridge.bid,
+        ask: leg.ask * bridge.ask,
+        source: leg.source,
+        thin: leg.thin || bridge.thin,
+    }
+}
+
+/// How much of an edge between legs quoted `cex_age_ms` and `dex_age_ms`
+/// ago to believe: halved for every `half_life_ms` one is older than the
+/// other, as the older leg may have moved since. Legs quoted together
+/// keep all of it however old both are; `feed_stale_secs` cuts those off.
+pub fn age_weight(cex_age_ms: u64, dex_age_ms: u64, half_life_ms: u64) -> f64 {
+    0.5f64.powf(cex_age_ms.abs_diff(dex_age_ms) as f64 / half_life_ms.max(1) as f64)
+}
+
+/// USD value of one quote token from a feed of the reference asset priced
+/// in the quote token, e.g. bybit USDCUSDT for USDT against USDC.
+pub fn quote_peg(peg: &PriceData) -> f64 {
+    2.0 / (peg.bid + peg.ask)
+}
+
+/// Candidates of `strategy` on `prices` with the margin interest of
+/// `borrow`, in USD when `prices` carry a peg. The whole of an engine
+/// evaluation, so a replay of the audit log runs the same code.
+pub fn price_candidates(
+    strategy: &mut dyn Strategy,
+    borrow: &BorrowCost,
+    prices: &TickPrices,
+    gas_price_wei: u128,
+) -> Vec<(ArbDirection, ArbProfit)> {
+    let mut found = strategy.evaluate(&prices.cex, &prices.dex, gas_price_wei);
+    if !borrow.is_zero() {
+        for (direction, profit) in found.iter_mut() {
+            let cex_price = match direction {
+                ArbDirection::BuyCex => prices.cex.ask,
+                ArbDirection::BuyDex => prices.cex.bid,
+            };
+            *profit = profit.with_borrow_cost(borrow.cost(direction, 1.0, cex_price));
+        }
+    }
+
+    if let Some(weight) = prices.age_weight {
+        for (_, profit) in found.iter_mut() {
+            *profit = profit.discounted(weight);
+        }
+    }
+
+    match prices.peg {
+        Some(peg) => found.into_iter().map(|(d, profit)| (d, profit.denominated(peg))).collect(),
+        None => found,
+    }
+}
+
+/// Audit `source` of the engine for `venue`.
+pub fn engine_source(venue: &str) -> String {
+    format!("engine:{}", venue)
+}
+
+/// What an engine evaluation depends on besides the code and config, as
+/// recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArbInputs {
+    pub strategy: String,
+    pub cex_bid: f64,
+    pub cex_ask: f64,
+    pub dex_bid: f64,
+    pub dex_ask: f64,
+    pub peg: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_weight: Option<f64>,
+    pub gas_price_wei: u128,
+}
+
+impl ArbInputs {
+    pub fn new(strategy: &str, prices: &TickPrices, gas_price_wei: u128) -> Self {
+        Self {
+            strategy: strategy.to_string(),
+            cex_bid: prices.cex.bid,
+            cex_ask: prices.cex.ask,
+            dex_bid: prices.dex.bid,
+            dex_ask: prices.dex.ask,
+            peg: prices.peg,
+            age_weight: prices.age_weight,
+            gas_price_wei,
+        }
+    }
+
+    pub fn prices(&self) -> TickPrices {
+        TickPrices {
+            cex: PriceData::new(self.cex_bid, self.cex_ask),
+            dex: PriceData::new(self.dex_bid, self.dex_ask),
+            peg: self.peg,
+            age_weight: self.age_weight,
+        }
+    }
+}
+
+/// The audit record of an evaluation: taken when a candidate nets a
+/// profit, with every candidate as outputs.
+pub fn arb_decision(venue: &str, inputs: &ArbInputs, found: &[(ArbDirection, ArbProfit)]) -> Decision {
+    let source = engine_source(venue);
+    let inputs_json = serde_json::to_value(inputs).unwrap_or_default();
+    let outputs = json!(found.iter().map(|(direction, profit)| json!({ "direction": direction, "profit": profit })).collect::<Vec<_>>());
+
+    let best = found.iter().map(|(_, p)| p.net).fold(f64::NEG_INFINITY, f64::max);
+    let decision = if best > 0.0 {
+        Decision::taken(&source, "arb", inputs_json)
+    } else if found.is_empty() {
+        Decision::skipped(&source, "arb", "no candidates", inputs_json)
+    } else {
+        Decision::skipped(&source, "arb", format!("best net {:.6} is not a profit", best), inputs_json)
+    };
+    decision.with_outputs(outputs)
+}
+
+/// Profit of buying one unit of base at `buy_price` and selling it at
+/// `sell_price`, in quote currency.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ArbProfit {
+    pub gross: f64,
+    pub cex_fee: f64,
+    pub gas_cost: f64,
+    // margin interest of the CEX leg, see `borrow::BorrowCost`
+    pub borrow_cost: f64,
+    pub net: f64,
+}
+
+impl ArbProfit {
+    /// The same profit counted in another currency, `rate` of it per quote
+    /// token.
+    pub fn denominated(&self, rate: f64) -> Self {
+        Self {
+            gross: self.gross * rate,
+            cex_fee: self.cex_fee * rate,
+            gas_cost: self.gas_cost * rate,
+            borrow_cost: self.borrow_cost * rate,
+            net: self.net * rate,
+        }
+    }
+
+    /// The same profit with only `weight` of a positive gross edge kept,
+    /// see `age_weight`.
+    pub fn discounted(&self, weight: f64) -> Self {
+        let cut = self.gross.max(0.0) * (1.0 - weight);
+        Self { gross: self.gross - cut, net: self.net - cut, ..*self }
+    }
+
+    /// The same profit with `borrow_cost` of margin interest on top.
+    pub fn with_borrow_cost(&self, borrow_cost: f64) -> Self {
+        Self { borrow_cost: self.borrow_cost + borrow_cost, net: self.net - borrow_cost, ..*self }
+    }
+}
+
+/// `arb_profit_in` for a pair whose base is the gas token.
+pub fn arb_profit(
+    cex_fee_bps: u32,
+    dex_gas_used: u64,
+    buy_price: f64,
+    sell_price: f64,
+    direction: &ArbDirection,
+    gas_price_wei: u128,
+) -> ArbProfit {
+    arb_profit_in(GasToken::Base, cex_fee_bps, dex_gas_used, buy_price, sell_price, direction, gas_price_wei)
+}
+
+pub fn arb_profit_in(
+    gas_token: GasToken,
+    cex_fee_bps: u32,
+    dex_gas_used: u64,
+    buy_price: f64,
+    sell_price: f64,
+    direction: &ArbDirection,
+    gas_price_wei: u128,
+) -> ArbProfit {
+    let gross = sell_price - buy_price;
+
+    // Calculate gas cost in HYPE tokens
+    let gas_cost_wei = gas_price_wei * dex_gas_used as u128;
+    let gas_cost_hype = gas_cost_wei as f64 / 1e18;
+
+    // quote per gas token, the DEX price of base when base is the gas token
+    let hype_price = match (gas_token, direction) {
+        (GasToken::Quote, _) => 1.0,
+        (GasToken::Base, ArbDirection::BuyCex) => sell_price,
+        (GasToken::Base, ArbDirection::BuyDex) => buy_price,
+    };
+    let gas_cost = gas_cost_hype * hype_price;
+
+    let cex_price = match direction {
+        ArbDirection::BuyCex => buy_price,
+        ArbDirection::BuyDex => sell_price,
+    };
+    let cex_fee = (cex_fee_bps as f64 / 10000.0) * cex_price;
+
+    ArbProfit {
+        gross,
+        cex_fee,
+        gas_cost,
+        borrow_cost: 0.0,
+        net: gross - cex_fee - gas_cost,
     }
 }
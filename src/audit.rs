@@ -0,0 +1,243 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::notify::{QUEUE_CAPACITY, offer};
+use crate::opportunity::OpportunityId;
+use crate::settings::Settings;
+
+/// `prev` of the first entry of a log.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One execution decision: what was considered, on which inputs (prices,
+/// gas, fees, thresholds) and whether it was acted on, with the reason
+/// when it was not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    // component that decided, e.g. `market_maker`
+    pub source: String,
+    // e.g. `hedge` or `quote`
+    pub action: String,
+    pub taken: bool,
+    pub reason: Option<String>,
+    pub inputs: Value,
+    // what was computed from the inputs, for `replay` to compare against
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub outputs: Value,
+    // ids of the opportunities found, left out of what `replay` compares
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opportunities: Vec<OpportunityId>,
+}
+
+impl Decision {
+    pub fn taken(source: &str, action: &str, inputs: Value) -> Self {
+        Self {
+            source: source.to_string(),
+            action: action.to_string(),
+            taken: true,
+            reason: None,
+            inputs,
+            outputs: Value::Null,
+            opportunities: Vec::new(),
+        }
+    }
+
+    pub fn skipped(source: &str, action: &str, reason: impl ToString, inputs: Value) -> Self {
+        Self { reason: Some(reason.to_string()), taken: false, ..Self::taken(source, action, inputs) }
+    }
+
+    pub fn with_outputs(mut self, outputs: Value) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    pub fn with_opportunities(mut self, ids: Vec<OpportunityId>) -> Self {
+        self.opportunities = ids;
+        self
+    }
+}
+
+/// A line of the audit log. `hash` is the SHA-256 of `prev` followed by the
+/// JSON of the entry without `hash`, so altering, dropping or reordering
+/// any line breaks the chain from there on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub ts_ms: u64,
+    pub prev: String,
+    pub decision: Decision,
+    pub hash: String,
+}
+
+#[derive(Serialize)]
+struct Unhashed<'a> {
+    seq: u64,
+    ts_ms: u64,
+    prev: &'a str,
+    decision: &'a Decision,
+}
+
+impl AuditEntry {
+    fn new(seq: u64, ts_ms: u64, prev: String, decision: Decision) -> Result<Self> {
+        let hash = entry_hash(seq, ts_ms, &prev, &decision)?;
+        Ok(Self { seq, ts_ms, prev, decision, hash })
+    }
+}
+
+fn entry_hash(seq: u64, ts_ms: u64, prev: &str, decision: &Decision) -> Result<String> {
+    let body = serde_json::to_vec(&Unhashed { seq, ts_ms, prev, decision })?;
+    let mut hasher = Sha256::new();
+    hasher.update(prev.as_bytes());
+    hasher.update(&body);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Append-only, hash-chained file of decisions, continuing the chain of
+/// whatever the file already holds.
+pub struct AuditLog {
+    path: PathBuf,
+    file: File,
+    next_seq: u64,
+    prev: String,
+}
+
+impl AuditLog {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+
+        let (next_seq, prev) = match last_entry(&path)? {
+            Some(last) => (last.seq + 1, last.hash),
+            None => (0, GENESIS.to_string()),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self { path, file, next_seq, prev })
+    }
+
+    pub fn append(&mut self, decision: Decision) -> Result<AuditEntry> {
+        let entry = AuditEntry::new(self.next_seq, now_ms(), self.prev.clone(), decision)?;
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .and_then(|_| self.file.flush())
+            .with_context(|| format!("failed to append to {}", self.path.display()))?;
+
+        self.next_seq += 1;
+        self.prev = entry.hash.clone();
+        Ok(entry)
+    }
+}
+
+fn last_entry(path: &Path) -> Result<Option<AuditEntry>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let reader = BufReader::new(File::open(path).with_context(|| format!("failed to open {}", path.display()))?);
+    let mut last = None;
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    match last {
+        Some(line) => Ok(Some(serde_json::from_str(&line).with_context(|| format!("bad last line in {}", path.display()))?)),
+        None => Ok(None),
+    }
+}
+
+/// Checks the chain of the log at `path` end to end, returning the number
+/// of entries and the hash of the last one.
+pub fn verify(path: impl AsRef<Path>) -> Result<(u64, String)> {
+    let entries = entries(path)?;
+    let last = entries.last().map_or_else(|| GENESIS.to_string(), |e| e.hash.clone());
+    Ok((entries.len() as u64, last))
+}
+
+/// The entries of the log at `path` in order, failing unless the chain is
+/// intact.
+pub fn entries(path: impl AsRef<Path>) -> Result<Vec<AuditEntry>> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path).with_context(|| format!("failed to open {}", path.display()))?);
+
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line).with_context(|| format!("line {} is not an entry", i + 1))?;
+        let prev = entries.last().map_or(GENESIS, |e| e.hash.as_str());
+        if entry.seq != entries.len() as u64 || entry.prev != prev {
+            bail!("line {}: chain broken, expected seq {} after {}", i + 1, entries.len(), prev);
+        }
+        if entry_hash(entry.seq, entry.ts_ms, &entry.prev, &entry.decision)? != entry.hash {
+            bail!("line {}: hash does not match its contents", i + 1);
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Hands decisions to the audit writer without waiting on the disk. Cheap
+/// to clone; an auditor without a writer drops everything.
+#[derive(Debug, Clone, Default)]
+pub struct Auditor {
+    tx: Option<mpsc::Sender<Decision>>,
+}
+
+impl Auditor {
+    /// Auditor for `audit_file` and the task writing it, `None` for the
+    /// task when no file is configured.
+    pub fn from_config(cfg: &Settings) -> Result<(Self, Option<AuditWriter>)> {
+        let Some(path) = &cfg.audit_file else {
+            return Ok((Self::default(), None));
+        };
+
+        let log = AuditLog::open(path)?;
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        Ok((Self { tx: Some(tx) }, Some(AuditWriter { log, rx })))
+    }
+
+    pub fn record(&self, decision: Decision) {
+        if let Some(tx) = &self.tx {
+            offer(tx, decision, "audit");
+        }
+    }
+}
+
+/// Appends decisions to the audit log in the order they were made.
+pub struct AuditWriter {
+    log: AuditLog,
+    rx: mpsc::Receiver<Decision>,
+}
+
+impl AuditWriter {
+    pub async fn run(mut self) {
+        info!("📜 auditing decisions to {}", self.log.path.display());
+        while let Some(decision) = self.rx.recv().await {
+            if let Err(e) = self.log.append(decision) {
+                warn!("audit append failed: {:#}", e);
+            }
+        }
+    }
+}
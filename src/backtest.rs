@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::{Context, Result, anyhow, bail};
+use futures_util::future::select_all;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::arbitrage::{ArbDirection, ArbEngine, ArbProfit, DEX_VENUE, PriceData, arb_profit_in};
+use crate::clock::{Clock, SimClock};
+use crate::execution::executor::expected_net;
+use crate::risk::{RiskHandle, TradingState, risk_channel};
+use crate::settings::Settings;
+use crate::summary::format_date;
+
+/// One top-of-book update as stored in backtest data, one JSON object per
+/// line. DEX ticks may carry the gas price of their block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    pub ts_ms: u64,
+    pub venue: String,
+    pub bid: f64,
+    pub ask: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_price_wei: Option<u128>,
+}
+
+/// Knobs of a backtest run besides the fee and gas settings of the config.
+#[derive(Debug, Clone)]
+pub struct BacktestParams {
+    // inclusive bounds, unix ms
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+    pub size: f64,
+    pub min_profit: f64,
+    // from taking an opportunity to both legs filling
+    pub latency_ms: u64,
+    // used until the data provides a gas price
+    pub gas_price_wei: u128,
+}
+
+impl BacktestParams {
+    fn in_range(&self, tick: &Tick) -> bool {
+        self.from_ms.is_none_or(|from| tick.ts_ms >= from) && self.to_ms.is_none_or(|to| tick.ts_ms <= to)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestReport {
+    pub ticks: usize,
+    pub first_ts_ms: Option<u64>,
+    pub last_ts_ms: Option<u64>,
+    pub opportunities: usize,
+    pub opportunities_by_route: BTreeMap<String, usize>,
+    pub gross_pnl: f64,
+    pub cex_fees: f64,
+    pub gas_costs: f64,
+    pub net_pnl: f64,
+    pub max_drawdown: f64,
+    // opportunities taken and filled, and the share of them netting a profit
+    pub trades: usize,
+    pub wins: usize,
+    pub hit_rate: f64,
+    pub parameters: BTreeMap<String, String>,
+}
+
+impl BacktestReport {
+    fn new(cfg: &Settings, params: &BacktestParams) -> Self {
+        Self {
+            ticks: 0,
+            first_ts_ms: None,
+            last_ts_ms: None,
+            opportunities: 0,
+            opportunities_by_route: BTreeMap::new(),
+            gross_pnl: 0.0,
+            cex_fees: 0.0,
+            gas_costs: 0.0,
+            net_pnl: 0.0,
+            max_drawdown: 0.0,
+            trades: 0,
+            wins: 0,
+            hit_rate: 0.0,
+            parameters: parameters(cfg, params),
+        }
+    }
+
+    fn count(&mut self, tick: &Tick) {
+        self.ticks += 1;
+        self.first_ts_ms.get_or_insert(tick.ts_ms);
+        self.last_ts_ms = Some(tick.ts_ms);
+    }
+
+    fn finish(mut self) -> Self {
+        self.hit_rate = if self.trades == 0 { 0.0 } else { self.wins as f64 / self.trades as f64 };
+        self
+    }
+}
+
+/// Ticks from a `.jsonl` file or every `.jsonl` file of a directory, in
+/// timestamp order.
+pub fn load_ticks(path: &Path) -> Result<Vec<Tick>> {
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        files.retain(|f| f.extension().is_some_and(|ext| ext == "jsonl"));
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut ticks = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?;
+        for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let tick = serde_json::from_str(line).with_context(|| format!("{}:{}", file.display(), i + 1))?;
+            ticks.push(tick);
+        }
+    }
+
+    // stable, so same-millisecond ticks keep their recorded order
+    ticks.sort_by_key(|t: &Tick| t.ts_ms);
+    Ok(ticks)
+}
+
+/// Appends ticks to one `ticks-YYYY-MM-DD.jsonl` file per UTC day in a
+/// directory, the layout `load_ticks` reads back.
+#[derive(Debug)]
+pub struct TickWriter {
+    dir: PathBuf,
+    // the day the open file is for
+    file: Option<(String, File)>,
+}
+
+impl TickWriter {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(Self { dir, file: None })
+    }
+
+    pub fn write(&mut self, tick: &Tick) -> Result<()> {
+        let day = format_date(tick.ts_ms);
+        let file = match &mut self.file {
+            Some((open_day, file)) if *open_day == day => file,
+            file => {
+                let path = self.dir.join(format!("ticks-{}.jsonl", day));
+                let opened = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("failed to open {}", path.display()))?;
+                &mut file.insert((day, opened)).1
+            }
+        };
+        writeln!(file, "{}", serde_json::to_string(tick)?)?;
+        Ok(())
+    }
+}
+
+/// Record mode: writes every update of `feeds` to `dir` as backtest ticks
+/// until they all closed, stamped with `clock`. Updates of the `hyperswap`
+/// feed carry the gas price read from `provider` as they come in.
+pub async fn run_tick_recorder(
+    dir: PathBuf,
+    mut feeds: Vec<(String, watch::Receiver<Option<PriceData>>)>,
+    provider: Arc<dyn Provider>,
+    clock: Arc<dyn Clock>,
+) {
+    let mut writer = match TickWriter::new(&dir) {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!("not recording ticks: {:#}", e);
+            return;
+        }
+    };
+    info!("📼 recording {} feeds to {}", feeds.len(), dir.display());
+
+    while !feeds.is_empty() {
+        let changed = feeds.iter_mut().map(|(_, rx)| Box::pin(rx.changed()));
+        let (result, i, _) = select_all(changed).await;
+        if result.is_err() {
+            feeds.remove(i);
+            continue;
+        }
+
+        let (venue, rx) = &mut feeds[i];
+        let Some(price) = rx.borrow_and_update().clone() else {
+            continue;
+        };
+        let gas_price_wei = match venue.as_str() {
+            DEX_VENUE => provider.get_gas_price().await.ok(),
+            _ => None,
+        };
+        let tick = Tick { ts_ms: clock.now_ms(), venue: venue.clone(), bid: price.bid, ask: price.ask, gas_price_wei };
+        if let Err(e) = writer.write(&tick) {
+            warn!("recording a {} tick failed: {:#}", tick.venue, e);
+        }
+    }
+}
+
+/// Replays `ticks` against the latest DEX price, taking each CEX/DEX
+/// opportunity once when it opens: the first tick its net profit at
+/// `size` reaches `min_profit`. Each one fills `latency_ms` later at the
+/// prices then in force, which is what the PnL and drawdown reflect. Ticks
+/// while the CEX/DEX spread exceeds `max_spread_bps` are skipped, as the
+/// live engine pauses on them.
+pub fn run_backtest(cfg: &Settings, params: &BacktestParams, ticks: &[Tick]) -> BacktestReport {
+    let mut book = Book::new(params);
+    let mut open: HashMap<String, bool> = HashMap::new();
+    let mut report = BacktestReport::new(cfg, params);
+
+    for tick in ticks.iter().filter(|t| params.in_range(t)) {
+        report.count(tick);
+        for venue in book.apply(cfg, params, tick, &mut report) {
+            let Some(spread_ok) = book.spread_ok(cfg, &venue) else {
+                continue;
+            };
+
+            for direction in [ArbDirection::BuyCex, ArbDirection::BuyDex] {
+                let route = format!("{}:{:?}", venue, direction);
+                let net = book.net(cfg, params, &venue, &direction).unwrap_or(0.0);
+
+                let profitable = spread_ok && net > 0.0 && net >= params.min_profit;
+                let was_open = open.insert(route.clone(), profitable).unwrap_or(false);
+                if !profitable || was_open {
+                    continue;
+                }
+
+                report.opportunities += 1;
+                *report.opportunities_by_route.entry(route).or_default() += 1;
+                book.take(params, tick, &venue, direction);
+            }
+        }
+    }
+
+    book.settle(cfg, params, &mut report);
+    report.finish()
+}
+
+/// Replays `ticks` through an `ArbEngine` per CEX venue, the same code path
+/// as live (warmup, stale feeds, the configured strategy), timed on a
+/// `SimClock` at the recorded timestamps. Every profitable evaluation
+/// netting `min_profit` at `size` counts as an opportunity; like the
+/// executor, one is taken at a time and fills `latency_ms` later at the
+/// prices then in force. With `speed` the replay is paced at that many
+/// times real time, otherwise it runs as fast as it can.
+pub async fn run_engine_backtest(cfg: &Settings, params: &BacktestParams, ticks: &[Tick], speed: Option<f64>) -> Result<BacktestReport> {
+    let ticks: Vec<&Tick> = ticks.iter().filter(|t| params.in_range(t)).collect();
+    let clock = Arc::new(SimClock::new(ticks.first().map_or(0, |t| t.ts_ms)));
+    // never asked, gas prices come from the data
+    let provider: Arc<dyn Provider> = Arc::new(ProviderBuilder::new().connect_http("http://127.0.0.1:1".parse()?));
+    let (events, _) = risk_channel();
+    let (_trading_tx, trading) = watch::channel(TradingState::default());
+    let risk = RiskHandle {
+        events,
+        trading,
+        exposure: Default::default(),
+        throttle: Default::default(),
+        audit: Default::default(),
+        markets: Default::default(),
+        switches: Default::default(),
+    };
+
+    let (dex_tx, dex_rx) = watch::channel(None);
+    let mut engines: BTreeMap<String, (watch::Sender<Option<PriceData>>, ArbEngine)> = BTreeMap::new();
+    let mut book = Book::new(params);
+    let mut report = BacktestReport::new(cfg, params);
+    let mut busy_until = 0;
+
+    for (i, tick) in ticks.iter().enumerate() {
+        if let (Some(speed), Some(prev)) = (speed, i.checked_sub(1).map(|i| ticks[i])) {
+            tokio::time::sleep(Duration::from_millis(tick.ts_ms - prev.ts_ms).div_f64(speed)).await;
+        }
+        clock.set(tick.ts_ms);
+        report.count(tick);
+
+        let venues = book.apply(cfg, params, tick, &mut report);
+        let price = Some(PriceData::new(tick.bid, tick.ask));
+        if tick.venue == DEX_VENUE {
+            dex_tx.send_replace(price);
+        } else {
+            let (cex_tx, _) = engines.entry(tick.venue.clone()).or_insert_with(|| {
+                let (cex_tx, cex_rx) = watch::channel(None);
+                let engine = ArbEngine::new(cfg.clone(), &tick.venue, cex_rx, dex_rx.clone(), provider.clone(), risk.clone())
+                    .with_clock(clock.clone());
+                (cex_tx, engine)
+            });
+            cex_tx.send_replace(price);
+        }
+
+        for venue in venues {
+            let Some((_, engine)) = engines.get_mut(&venue) else {
+                continue;
+            };
+            let Some(prices) = engine.tick_prices() else {
+                continue;
+            };
+            let span = engine.tracer.span("tick");
+            let spread_ok = book.spread_ok(cfg, &venue).unwrap_or(false);
+            for opportunity in engine.evaluate(&prices, book.gas_price_wei, &span) {
+                if opportunity.profit.net <= 0.0 || expected_net(&opportunity.profit, params.size) < params.min_profit {
+                    continue;
+                }
+                report.opportunities += 1;
+                *report.opportunities_by_route.entry(format!("{}:{:?}", venue, opportunity.direction)).or_default() += 1;
+                if spread_ok && tick.ts_ms >= busy_until {
+                    busy_until = tick.ts_ms + params.latency_ms;
+                    book.take(params, tick, &venue, opportunity.direction);
+                }
+            }
+            span.end();
+        }
+    }
+
+    book.settle(cfg, params, &mut report);
+    Ok(report.finish())
+}
+
+// an opportunity taken but not filled yet
+struct Pending {
+    fill_ts_ms: u64,
+    venue: String,
+    direction: ArbDirection,
+}
+
+// latest prices per venue and gas price, as of the replay position, and
+// the opportunities taken but not filled yet
+struct Book {
+    latest: HashMap<String, PriceData>,
+    gas_price_wei: u128,
+    pending: Vec<Pending>,
+    peak: f64,
+}
+
+impl Book {
+    fn new(params: &BacktestParams) -> Self {
+        Self { latest: HashMap::new(), gas_price_wei: params.gas_price_wei, pending: Vec::new(), peak: 0.0 }
+    }
+
+    // fills what is due before `tick`, then books it; returns the CEX
+    // venues it moves: a DEX tick every pair, a CEX tick only its own
+    fn apply(&mut self, cfg: &Settings, params: &BacktestParams, tick: &Tick, report: &mut BacktestReport) -> Vec<String> {
+        // fills due before this tick see the book as it was
+        let (due, pending) = std::mem::take(&mut self.pending).into_iter().partition(|p| p.fill_ts_ms < tick.ts_ms);
+        self.pending = pending;
+        for p in &due {
+            self.fill(cfg, params, p, report);
+        }
+
+        if let Some(gas) = tick.gas_price_wei {
+            self.gas_price_wei = gas;
+        }
+        self.latest.insert(tick.venue.clone(), PriceData::new(tick.bid, tick.ask));
+
+        if tick.venue == DEX_VENUE {
+            self.latest.keys().filter(|v| *v != DEX_VENUE).cloned().collect()
+        } else {
+            vec![tick.venue.clone()]
+        }
+    }
+
+    fn take(&mut self, params: &BacktestParams, tick: &Tick, venue: &str, direction: ArbDirection) {
+        self.pending.push(Pending { fill_ts_ms: tick.ts_ms + params.latency_ms, venue: venue.to_string(), direction });
+    }
+
+    // whatever is still in flight fills at the last prices
+    fn settle(&mut self, cfg: &Settings, params: &BacktestParams, report: &mut BacktestReport) {
+        for p in std::mem::take(&mut self.pending) {
+            self.fill(cfg, params, &p, report);
+        }
+    }
+
+    // `None` until both the CEX and the DEX have quoted
+    fn spread_ok(&self, cfg: &Settings, venue: &str) -> Option<bool> {
+        let (cex, dex) = (self.latest.get(venue)?, self.latest.get(DEX_VENUE)?);
+        let cex_mid = (cex.bid + cex.ask) / 2.0;
+        let dex_mid = (dex.bid + dex.ask) / 2.0;
+        let spread_bps = (dex_mid - cex_mid).abs() / cex_mid * 10000.0;
+
+        Some(spread_bps <= cfg.max_spread_bps as f64)
+    }
+
+    fn profit(&self, cfg: &Settings, venue: &str, direction: &ArbDirection) -> Option<ArbProfit> {
+        let (cex, dex) = (self.latest.get(venue)?, self.latest.get(DEX_VENUE)?);
+        let (buy, sell) = match direction {
+            ArbDirection::BuyCex => (cex.ask, dex.bid),
+            ArbDirection::BuyDex => (dex.ask, cex.bid),
+        };
+
+        Some(arb_profit_in(cfg.gas_token(), cfg.cex_fee_bps, cfg.dex_gas_used, buy, sell, direction, self.gas_price_wei))
+    }
+
+    // fees scale with size, gas is paid once per trade
+    fn net(&self, cfg: &Settings, params: &BacktestParams, venue: &str, direction: &ArbDirection) -> Option<f64> {
+        let profit = self.profit(cfg, venue, direction)?;
+        Some((profit.gross - profit.cex_fee) * params.size - profit.gas_cost)
+    }
+
+    fn fill(&mut self, cfg: &Settings, params: &BacktestParams, p: &Pending, report: &mut BacktestReport) {
+        let Some(profit) = self.profit(cfg, &p.venue, &p.direction) else {
+            return;
+        };
+
+        let net = (profit.gross - profit.cex_fee) * params.size - profit.gas_cost;
+        report.gross_pnl += profit.gross * params.size;
+        report.cex_fees += profit.cex_fee * params.size;
+        report.gas_costs += profit.gas_cost;
+        report.net_pnl += net;
+        report.trades += 1;
+        report.wins += (net > 0.0) as usize;
+
+        self.peak = self.peak.max(report.net_pnl);
+        report.max_drawdown = report.max_drawdown.max(self.peak - report.net_pnl);
+    }
+}
+
+fn parameters(cfg: &Settings, params: &BacktestParams) -> BTreeMap<String, String> {
+    let mut table = BTreeMap::new();
+    table.insert("cex_fee_bps".to_string(), cfg.cex_fee_bps.to_string());
+    table.insert("dex_gas_used".to_string(), cfg.dex_gas_used.to_string());
+    table.insert("max_spread_bps".to_string(), cfg.max_spread_bps.to_string());
+    table.insert("size".to_string(), params.size.to_string());
+    table.insert("min_profit".to_string(), params.min_profit.to_string());
+    table.insert("latency_ms".to_string(), params.latency_ms.to_string());
+    table.insert("default_gas_price_wei".to_string(), params.gas_price_wei.to_string());
+    if let Some(from) = params.from_ms {
+        table.insert("from_ms".to_string(), from.to_string());
+    }
+    if let Some(to) = params.to_ms {
+        table.insert("to_ms".to_string(), to.to_string());
+    }
+    table
+}
+
+/// Unix ms at 00:00 UTC of a `YYYY-MM-DD` date.
+pub fn parse_date_ms(date: &str) -> Result<u64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [y, m, d] = parts[..] else {
+        bail!("expected YYYY-MM-DD, got {}", date);
+    };
+    let (y, m, d): (i64, i64, i64) = (y.parse()?, m.parse()?, d.parse()?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        bail!("invalid date {}", date);
+    }
+
+    // days since 1970-01-01 in the proleptic gregorian calendar
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u64::try_from(days * 86_400_000).map_err(|_| anyhow!("{} is before 1970", date))
+}
@@ -0,0 +1,52 @@
+use crate::arbitrage::ArbDirection;
+use crate::settings::Settings;
+
+/// Cost of funding the CEX leg on margin. Buying base borrows the quote
+/// asset, selling base borrows base; either is charged at the hourly rate
+/// configured for that asset on the venue for `borrow_hold_secs`, the time
+/// until inventory rebalancing repays the loan. Legs without a rate are
+/// funded from inventory and cost nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BorrowCost {
+    base_hourly_bps: f64,
+    quote_hourly_bps: f64,
+    hold_hours: f64,
+}
+
+impl BorrowCost {
+    /// Rates for `venue`, e.g. `bybit` or `bybit:<pair>`, matched against
+    /// the ticker it trades: an asset prefixing it is the base, one
+    /// suffixing it the quote.
+    pub fn new(cfg: &Settings, venue: &str) -> Self {
+        let exchange = venue.split(':').next().unwrap_or(venue);
+        let ticker = match exchange {
+            "bybit" => cfg.bybit_ticker.as_str(),
+            "gateio" => cfg.gateio_ticker.as_str(),
+            _ => return Self::default(),
+        };
+
+        let mut cost = Self { hold_hours: cfg.borrow_hold_secs as f64 / 3600.0, ..Default::default() };
+        for rate in cfg.borrow_rates.iter().filter(|r| r.venue == exchange) {
+            if ticker.starts_with(&rate.asset) {
+                cost.base_hourly_bps = rate.hourly_bps;
+            } else if ticker.ends_with(&rate.asset) {
+                cost.quote_hourly_bps = rate.hourly_bps;
+            }
+        }
+        cost
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.hold_hours == 0.0 || (self.base_hourly_bps == 0.0 && self.quote_hourly_bps == 0.0)
+    }
+
+    /// Borrow cost in quote tokens of trading `base_amount` on the CEX at
+    /// `cex_price` in `direction`.
+    pub fn cost(&self, direction: &ArbDirection, base_amount: f64, cex_price: f64) -> f64 {
+        let hourly_bps = match direction {
+            ArbDirection::BuyCex => self.quote_hourly_bps,
+            ArbDirection::BuyDex => self.base_hourly_bps,
+        };
+        base_amount * cex_price * hourly_bps / 10000.0 * self.hold_hours
+    }
+}
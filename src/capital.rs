@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use log::info;
+use tokio::sync::watch;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::arbitrage::PriceData;
+use crate::exposure::Exposure;
+use crate::settings::Settings;
+
+/// Budget key of a strategy trading a pair, e.g. `cex_dex/default`.
+pub fn book(strategy: &str, pair: &str) -> String {
+    format!("{}/{}", strategy, pair)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Book {
+    pub budget: f64,
+    // notional of executions under way
+    pub in_use: f64,
+    pub realized_pnl: f64,
+}
+
+/// Splits `capital_total` (quote tokens) into budgets per strategy/pair
+/// book. Budgets start even and on each `rebalance` shift toward books
+/// with realized profit by `capital_perf_weight`, never below
+/// `capital_min_share` of an even split. An execution must `reserve` its
+/// notional first, which fails beyond the book's budget or the total still
+/// free, so concurrent executions never exceed the capital.
+#[derive(Debug, Clone)]
+pub struct CapitalAllocator {
+    total: f64,
+    perf_weight: f64,
+    min_share: f64,
+    // quote value of funds between venues, part of the total but not free
+    in_flight: f64,
+    books: BTreeMap<String, Book>,
+}
+
+impl CapitalAllocator {
+    pub fn new(total: f64, perf_weight: f64, min_share: f64) -> Self {
+        Self {
+            total,
+            perf_weight: perf_weight.clamp(0.0, 1.0),
+            min_share: min_share.max(0.0),
+            in_flight: 0.0,
+            books: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a book and re-splits the capital. Known books are kept.
+    pub fn register(&mut self, book: &str) {
+        if !self.books.contains_key(book) {
+            self.books.insert(book.to_string(), Book::default());
+            self.rebalance();
+        }
+    }
+
+    /// Drops a book once nothing of it is in use.
+    pub fn unregister(&mut self, book: &str) -> Result<()> {
+        match self.books.get(book) {
+            Some(b) if b.in_use > 0.0 => bail!("{} still has {:.2} in use", book, b.in_use),
+            Some(_) => {
+                self.books.remove(book);
+                self.rebalance();
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn book(&self, book: &str) -> Option<&Book> {
+        self.books.get(book)
+    }
+
+    pub fn books(&self) -> &BTreeMap<String, Book> {
+        &self.books
+    }
+
+    pub fn in_use(&self) -> f64 {
+        self.books.values().map(|b| b.in_use).sum()
+    }
+
+    pub fn in_flight(&self) -> f64 {
+        self.in_flight
+    }
+
+    /// Sets the quote value of transfers in flight, taken out of the
+    /// capital budgets are split from until they arrive.
+    pub fn set_in_flight(&mut self, value: f64) {
+        self.in_flight = value.clamp(0.0, self.total);
+    }
+
+    /// Sets `notional` of `book`'s budget aside for an execution.
+    pub fn reserve(&mut self, book: &str, notional: f64) -> Result<()> {
+        if !(notional.is_finite() && notional >= 0.0) {
+            bail!("invalid notional {}", notional);
+        }
+        let free_total = self.total - self.in_flight - self.in_use();
+        let b = self.books.get_mut(book).ok_or_else(|| anyhow!("no capital allocated to {}", book))?;
+        if b.in_use + notional > b.budget {
+            bail!("{} needs {:.2}, {:.2} of its {:.2} budget is free", book, notional, b.budget - b.in_use, b.budget);
+        }
+        if notional > free_total {
+            bail!("{} needs {:.2}, only {:.2} of all capital is free", book, notional, free_total);
+        }
+        b.in_use += notional;
+        Ok(())
+    }
+
+    /// Returns what an execution reserved once it settled.
+    pub fn release(&mut self, book: &str, notional: f64) {
+        if let Some(b) = self.books.get_mut(book) {
+            b.in_use = (b.in_use - notional).max(0.0);
+        }
+    }
+
+    pub fn record_pnl(&mut self, book: &str, pnl: f64) {
+        if let Some(b) = self.books.get_mut(book) {
+            b.realized_pnl += pnl;
+        }
+    }
+
+    /// Re-splits the capital not in flight by realized performance. Budgets
+    /// may drop below what is in use, the book then waits for releases.
+    pub fn rebalance(&mut self) {
+        let n = self.books.len() as f64;
+        if n == 0.0 {
+            return;
+        }
+
+        let profit: f64 = self.books.values().map(|b| b.realized_pnl.max(0.0)).sum();
+        let weights: Vec<f64> = self
+            .books
+            .values()
+            .map(|b| {
+                // share of the profit relative to an even share
+                let perf = if profit > 0.0 { n * b.realized_pnl.max(0.0) / profit } else { 1.0 };
+                ((1.0 - self.perf_weight) + self.perf_weight * perf).max(self.min_share)
+            })
+            .collect();
+
+        let sum: f64 = weights.iter().sum();
+        let tradable = self.total - self.in_flight;
+        for (b, weight) in self.books.values_mut().zip(weights) {
+            b.budget = tradable * weight / sum;
+        }
+    }
+}
+
+/// A `CapitalAllocator` shared by everything that executes.
+#[derive(Debug, Clone)]
+pub struct Capital(Arc<Mutex<CapitalAllocator>>);
+
+impl Capital {
+    pub fn new(allocator: CapitalAllocator) -> Self {
+        Self(Arc::new(Mutex::new(allocator)))
+    }
+
+    /// The allocator of `capital_total`, `None` when capital is not limited.
+    pub fn from_config(cfg: &Settings) -> Option<Self> {
+        let total = cfg.capital_total?;
+        Some(Self::new(CapitalAllocator::new(total, cfg.capital_perf_weight, cfg.capital_min_share)))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, CapitalAllocator> {
+        // the allocator stays consistent across a panicking holder
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn register(&self, book: &str) {
+        self.lock().register(book);
+    }
+
+    /// Reserves `notional` of `book`, released when the returned guard drops.
+    pub fn reserve(&self, book: &str, notional: f64) -> Result<Reservation> {
+        self.lock().reserve(book, notional)?;
+        Ok(Reservation { capital: self.clone(), book: book.to_string(), notional })
+    }
+
+    pub fn record_pnl(&self, book: &str, pnl: f64) {
+        self.lock().record_pnl(book, pnl);
+    }
+
+    /// Takes the transfers in flight in `exposure` out of the free capital,
+    /// base valued at `base_price`. Returns their quote value.
+    pub fn track_in_flight(&self, exposure: &Exposure, base_price: f64) -> f64 {
+        let value = exposure.balance("quote").in_flight + exposure.balance("base").in_flight * base_price;
+        self.lock().set_in_flight(value);
+        value
+    }
+}
+
+/// Capital set aside for one execution until dropped.
+#[derive(Debug)]
+pub struct Reservation {
+    capital: Capital,
+    book: String,
+    notional: f64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.capital.lock().release(&self.book, self.notional);
+    }
+}
+
+/// Rebalances `capital` every `every`, net of the transfers in flight in
+/// `exposure` valued at the `price` mid, and logs the new budgets.
+pub async fn run_capital_rebalancer(
+    capital: Capital,
+    exposure: Exposure,
+    price: watch::Receiver<Option<PriceData>>,
+    every: Duration,
+) {
+    let mut tick = interval(every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // the first tick completes right away, budgets start even
+    tick.tick().await;
+
+    loop {
+        tick.tick().await;
+        // without a price the last value stays
+        let mid = price.borrow().as_ref().filter(|p| p.bid > 0.0 && p.ask > 0.0).map(|p| (p.bid + p.ask) / 2.0);
+        if let Some(mid) = mid {
+            let in_flight = capital.track_in_flight(&exposure, mid);
+            if in_flight > 0.0 {
+                info!("🚚 ${:.2} in flight between venues", in_flight);
+            }
+        }
+
+        let mut allocator = capital.lock();
+        allocator.rebalance();
+        for (name, b) in allocator.books() {
+            info!("💼 {} budget ${:.2} (pnl ${:.4}, in use ${:.2})", name, b.budget, b.realized_pnl, b.in_use);
+        }
+    }
+}
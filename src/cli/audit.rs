@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::audit::verify;
+use crate::replay::replay_file;
+use crate::settings::Settings;
+
+#[derive(Args)]
+pub struct AuditArgs {
+    /// Audit log to check; defaults to `audit_file`
+    pub path: Option<String>,
+    /// Re-run recorded engine decisions through the current strategy and
+    /// config and list those that come out differently
+    #[arg(long)]
+    pub replay: bool,
+}
+
+/// Walks the hash chain of the audit log, failing at the first entry that
+/// was altered, dropped or reordered, then replays it with `--replay`.
+pub fn run_audit(cfg: &Settings, args: &AuditArgs) -> Result<()> {
+    let path = args
+        .path
+        .as_deref()
+        .or(cfg.audit_file.as_deref())
+        .context("no path given and audit_file is not configured")?;
+
+    if args.replay {
+        return run_replay(cfg, path);
+    }
+    let (entries, last) = verify(path)?;
+    println!("{}: {} entries, chain intact, last hash {}", path, entries, last);
+    Ok(())
+}
+
+fn run_replay(cfg: &Settings, path: &str) -> Result<()> {
+    let report = replay_file(cfg, path)?;
+    for diff in &report.diffs {
+        let outcome = |taken: bool, reason: &Option<String>| match (taken, reason) {
+            (true, _) => "taken".to_string(),
+            (false, Some(reason)) => format!("skipped ({})", reason),
+            (false, None) => "skipped".to_string(),
+        };
+        println!(
+            "#{} {}: {} -> {}, net {:+.6}",
+            diff.seq,
+            diff.recorded.source,
+            outcome(diff.recorded.taken, &diff.recorded.reason),
+            outcome(diff.replayed.taken, &diff.replayed.reason),
+            diff.net_change()
+        );
+    }
+    println!(
+        "{}: {} decisions replayed ({} not replayable), {} changed, {} flipped, net {:+.6}",
+        path,
+        report.replayed,
+        report.skipped,
+        report.diffs.len(),
+        report.flipped(),
+        report.net_change()
+    );
+    Ok(())
+}
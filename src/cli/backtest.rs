@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Args;
+
+use crate::backtest::{BacktestParams, load_ticks, parse_date_ms, run_backtest, run_engine_backtest};
+use crate::settings::Settings;
+
+#[derive(Debug, Args)]
+pub struct BacktestArgs {
+    /// Tick data, a `.jsonl` file or a directory of them
+    pub data: PathBuf,
+    /// First day to replay, YYYY-MM-DD (UTC)
+    #[arg(long)]
+    pub from: Option<String>,
+    /// Last day to replay, YYYY-MM-DD (UTC), inclusive
+    #[arg(long)]
+    pub to: Option<String>,
+    /// Trade size in base tokens
+    #[arg(long, default_value_t = 1.0)]
+    pub size: f64,
+    /// Minimum net profit per trade, in quote tokens
+    #[arg(long, default_value_t = 0.0)]
+    pub min_profit: f64,
+    /// Delay from taking an opportunity to both legs filling
+    #[arg(long, default_value_t = 250)]
+    pub latency_ms: u64,
+    /// Gas price until the data carries one, in gwei
+    #[arg(long, default_value_t = 1.0)]
+    pub gas_price_gwei: f64,
+    /// Replay through the live engine and strategy on a simulated clock,
+    /// taking one opportunity at a time as the executor does
+    #[arg(long)]
+    pub engine: bool,
+    /// With --engine, pace the replay at this many times real time
+    /// instead of as fast as possible
+    #[arg(long, requires = "engine")]
+    pub speed: Option<f64>,
+    /// Also write the report as JSON to this path, `-` for stdout
+    #[arg(long)]
+    pub json: Option<PathBuf>,
+}
+
+/// Replays recorded ticks through the opportunity math, or the engine with
+/// `--engine`, and prints a summary with the parameters it ran with.
+pub async fn run_backtest_command(cfg: &Settings, args: &BacktestArgs) -> Result<()> {
+    let params = BacktestParams {
+        from_ms: args.from.as_deref().map(parse_date_ms).transpose()?,
+        to_ms: args.to.as_deref().map(|d| parse_date_ms(d).map(|ms| ms + 86_400_000 - 1)).transpose()?,
+        size: args.size,
+        min_profit: args.min_profit,
+        latency_ms: args.latency_ms,
+        gas_price_wei: (args.gas_price_gwei * 1e9) as u128,
+    };
+    if let (Some(from), Some(to)) = (params.from_ms, params.to_ms)
+        && from > to
+    {
+        bail!("--from is after --to");
+    }
+
+    let ticks = load_ticks(&args.data)?;
+    if args.speed.is_some_and(|s| s.is_nan() || s <= 0.0) {
+        bail!("--speed must be positive");
+    }
+    let report = if args.engine {
+        run_engine_backtest(cfg, &params, &ticks, args.speed).await?
+    } else {
+        run_backtest(cfg, &params, &ticks)
+    };
+
+    println!("{:<24} {:>16}", "ticks", report.ticks);
+    println!("{:<24} {:>16}", "opportunities", report.opportunities);
+    for (route, count) in &report.opportunities_by_route {
+        println!("  {:<22} {:>16}", route, count);
+    }
+    println!("{:<24} {:>16.4}", "gross pnl", report.gross_pnl);
+    println!("{:<24} {:>16.4}", "cex fees", report.cex_fees);
+    println!("{:<24} {:>16.4}", "gas", report.gas_costs);
+    println!("{:<24} {:>16.4}", "net pnl", report.net_pnl);
+    println!("{:<24} {:>16.4}", "max drawdown", report.max_drawdown);
+    println!("{:<24} {:>16}", "trades", report.trades);
+    println!("{:<24} {:>15.1}%", "hit rate", report.hit_rate * 100.0);
+    println!();
+    println!("{:<24} {:>16}", "parameter", "value");
+    for (name, value) in &report.parameters {
+        println!("{:<24} {:>16}", name, value);
+    }
+
+    match args.json.as_deref() {
+        Some(path) if path.as_os_str() == "-" => println!("{}", serde_json::to_string_pretty(&report)?),
+        Some(path) => fs::write(path, serde_json::to_string_pretty(&report)?)?,
+        None => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use alloy::primitives::U256;
+use anyhow::Result;
+
+use crate::execution::hyperliquid::HyperliquidClient;
+use crate::helpers::provider::connect_provider;
+use crate::inventory::{core_inventory, evm_inventory};
+use crate::normalize::Normalizer;
+use crate::settings::Settings;
+
+/// Prints the bot's pair token balances and router allowances on HyperEVM
+/// and, when `hyperliquid_private_key` is set, its HyperCore spot balances.
+pub async fn run_balances(cfg: &Settings) -> Result<()> {
+    let provider = connect_provider(cfg.quote_rpc()).await?;
+    let evm = evm_inventory(cfg, &provider, cfg.self_addr).await?;
+
+    println!("account {}, base {}, quote {}", cfg.self_addr, cfg.weth_addr, cfg.usdt_addr);
+    println!();
+    println!("{:<10} {:<7} {:>18} {:>14} {:>18}", "venue", "asset", "balance", "in orders", "router allowance");
+
+    let norm = Normalizer::new(cfg);
+    let base = |amount: U256| norm.base(amount);
+    let quote = |amount: U256| norm.quote(amount);
+    let allowance = |amount: U256, to_tokens: &dyn Fn(U256) -> f64| {
+        if amount == U256::MAX {
+            "unlimited".to_string()
+        } else {
+            format!("{:.6}", to_tokens(amount))
+        }
+    };
+
+    println!("{:<10} {:<7} {:>18.6} {:>14} {:>18}", "hyperevm", "native", base(evm.native), "-", "-");
+    println!(
+        "{:<10} {:<7} {:>18.6} {:>14} {:>18}",
+        "hyperevm",
+        "base",
+        base(evm.base),
+        "-",
+        allowance(evm.base_allowance, &base)
+    );
+    println!(
+        "{:<10} {:<7} {:>18.6} {:>14} {:>18}",
+        "hyperevm",
+        "quote",
+        quote(evm.quote),
+        "-",
+        allowance(evm.quote_allowance, &quote)
+    );
+
+    if cfg.hyperliquid_private_key.is_none() {
+        println!("{:<10} hyperliquid_private_key not set", "hypercore");
+        return Ok(());
+    }
+
+    let core = async {
+        let client = HyperliquidClient::new(cfg)?;
+        core_inventory(cfg, &client).await
+    };
+    match core.await {
+        Ok(core) => {
+            println!("{:<10} {:<7} {:>18.6} {:>14.6} {:>18}", "hypercore", "base", core.base, core.base_hold, "-");
+            println!("{:<10} {:<7} {:>18.6} {:>14.6} {:>18}", "hypercore", "quote", core.quote, core.quote_hold, "-");
+        }
+        Err(e) => println!("{:<10} error: {:#}", "hypercore", e),
+    }
+
+    Ok(())
+}
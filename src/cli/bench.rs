@@ -0,0 +1,178 @@
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy::primitives::{Bytes, U256, utils::parse_ether};
+use anyhow::Result;
+use clap::{Args, Subcommand, ValueEnum};
+use revm::state::Bytecode;
+
+use crate::adapters::{bybit, gateio, hyperswap};
+use crate::arbitrage::PriceData;
+use crate::execution::split::PoolRoute;
+use crate::helpers::provider::connect_provider;
+use crate::helpers::revm::{BlockTracker, init_account_with_bytecode, init_cache_db, insert_mapping_storage_slot};
+use crate::settings::Settings;
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(subcommand)]
+    pub mode: BenchMode,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BenchMode {
+    /// Time DEX quotes over RPC, in REVM and with local V3 math
+    DexQuotes(DexQuotesArgs),
+    /// Measure CEX message parsing throughput
+    WsParse(WsParseArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DexQuotesArgs {
+    /// Timed calls per mode and volume, after the first
+    #[arg(long, default_value_t = 10)]
+    pub calls: usize,
+    /// Base amounts to quote, in whole tokens
+    #[arg(long, value_delimiter = ',', default_value = "1")]
+    pub volumes: Vec<String>,
+    #[arg(long, value_enum, default_value_t = QuoteMode::All)]
+    pub mode: QuoteMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum QuoteMode {
+    /// Plain `eth_call`s
+    Rpc,
+    /// REVM on state fetched from the RPC
+    Revm,
+    /// REVM with the pair tokens replaced by a generic ERC20
+    RevmMocked,
+    /// Local UniswapV3 math on the REVM-cached pool state
+    Math,
+    All,
+}
+
+#[derive(Debug, Args)]
+pub struct WsParseArgs {
+    /// Messages to parse per venue
+    #[arg(long, default_value_t = 1_000_000)]
+    pub messages: usize,
+}
+
+// a mix of book updates and the control messages every stream carries
+const BYBIT_MESSAGES: [&str; 3] = [
+    r#"{"topic":"orderbook.1.HYPEUSDT","type":"snapshot","ts":1752000000000,"data":{"s":"HYPEUSDT","b":[["41.512","120.5"]],"a":[["41.519","88.1"]],"u":123456,"seq":987654},"cts":1751999999990}"#,
+    r#"{"topic":"orderbook.1.HYPEUSDT","type":"delta","ts":1752000000100,"data":{"s":"HYPEUSDT","b":[["41.513","10.0"]],"a":[["41.520","12.0"]],"u":123457,"seq":987655},"cts":1752000000090}"#,
+    r#"{"success":true,"ret_msg":"","conn_id":"abc","op":"subscribe"}"#,
+];
+const GATEIO_MESSAGES: [&str; 3] = [
+    r#"{"time":1752000000,"time_ms":1752000000123,"channel":"spot.tickers","event":"update","result":{"currency_pair":"HYPE_USDT","last":"41.515","lowest_ask":"41.52","highest_bid":"41.51","change_percentage":"1.2","base_volume":"100000","quote_volume":"4150000","high_24h":"42","low_24h":"40"}}"#,
+    r#"{"time":1752000000,"channel":"spot.tickers","event":"subscribe","result":{"status":"success"}}"#,
+    r#"{"time":1752000000,"channel":"spot.pong","event":"","result":null}"#,
+];
+
+pub async fn run_bench(cfg: &Settings, args: &BenchArgs) -> Result<()> {
+    match &args.mode {
+        BenchMode::DexQuotes(args) => bench_dex_quotes(cfg, args).await,
+        BenchMode::WsParse(args) => {
+            bench_ws_parse(args);
+            Ok(())
+        }
+    }
+}
+
+/// Times quoting the primary pool in each mode at each volume. The first
+/// call of a mode is reported apart, as it pays for cold state fetches.
+async fn bench_dex_quotes(cfg: &Settings, args: &DexQuotesArgs) -> Result<()> {
+    let volumes = args.volumes.iter().map(|v| parse_ether(v)).collect::<Result<Vec<U256>, _>>()?;
+    let modes = match args.mode {
+        QuoteMode::All => vec![QuoteMode::Rpc, QuoteMode::Revm, QuoteMode::RevmMocked, QuoteMode::Math],
+        mode => vec![mode],
+    };
+
+    let provider = Arc::new(connect_provider(cfg.quote_rpc()).await?);
+    let route = PoolRoute::all(cfg).remove(0);
+
+    println!(
+        "{:<12} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "mode", "volume", "first", "avg", "min", "max"
+    );
+    for mode in modes {
+        let mut cache_db = init_cache_db(provider.clone());
+        let mut tracker = BlockTracker::new(vec![route.pool_addr]);
+
+        if mode == QuoteMode::RevmMocked {
+            let mocked_erc20 = include_str!("../bytecode/generic_erc20.hex").parse::<Bytes>()?;
+            init_account_with_bytecode(cfg.weth_addr, Bytecode::new_raw(mocked_erc20), &mut cache_db).await?;
+
+            let big = U256::MAX / U256::from(2);
+            insert_mapping_storage_slot(cfg.weth_addr, U256::ZERO, route.pool_addr, big, &mut cache_db).await?;
+            insert_mapping_storage_slot(cfg.usdt_addr, U256::ZERO, route.pool_addr, big, &mut cache_db).await?;
+        }
+
+        for volume in &volumes {
+            let mut samples = Vec::with_capacity(args.calls + 1);
+            for _ in 0..=args.calls {
+                let start = Instant::now();
+                match mode {
+                    QuoteMode::Rpc => hyperswap::quote_route(cfg, &*provider, &route, *volume).await?,
+                    QuoteMode::Math => {
+                        hyperswap::quote_route_math(cfg, &provider, &route, *volume, &mut cache_db, &mut tracker)
+                            .await?
+                    }
+                    _ => {
+                        hyperswap::quote_route_revm(cfg, &provider, &route, *volume, &mut cache_db, &mut tracker)
+                            .await?
+                    }
+                };
+                samples.push(start.elapsed());
+            }
+
+            let (first, rest) = samples.split_first().unwrap();
+            let rest = if rest.is_empty() { &samples[..] } else { rest };
+            let avg = rest.iter().sum::<Duration>() / rest.len() as u32;
+            println!(
+                "{:<12} {:>10} {:>12.2?} {:>12.2?} {:>12.2?} {:>12.2?}",
+                mode.to_possible_value().unwrap().get_name(),
+                f64::from(*volume) / 1e18,
+                first,
+                avg,
+                rest.iter().min().unwrap(),
+                rest.iter().max().unwrap(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a fixed mix of each venue's messages in a loop.
+fn bench_ws_parse(args: &WsParseArgs) {
+    type Parser = fn(&str) -> Option<PriceData>;
+    let venues: [(&str, &[&str], Parser); 2] = [
+        ("bybit", &BYBIT_MESSAGES, bybit::parse_message),
+        ("gateio", &GATEIO_MESSAGES, gateio::parse_message),
+    ];
+
+    println!("{:<8} {:>12} {:>12} {:>14} {:>10}", "venue", "messages", "prices", "msgs/sec", "ns/msg");
+    for (venue, messages, parse) in venues {
+        let start = Instant::now();
+        let mut prices = 0;
+        for i in 0..args.messages {
+            if black_box(parse(black_box(messages[i % messages.len()]))).is_some() {
+                prices += 1;
+            }
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+
+        println!(
+            "{:<8} {:>12} {:>12} {:>14.0} {:>10.0}",
+            venue,
+            args.messages,
+            prices,
+            args.messages as f64 / elapsed,
+            elapsed * 1e9 / args.messages as f64,
+        );
+    }
+}
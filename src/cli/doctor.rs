@@ -0,0 +1,337 @@
+use std::fmt::Display;
+
+use alloy::{
+    primitives::{Address, Bytes},
+    providers::Provider,
+    signers::local::PrivateKeySigner,
+};
+use anyhow::{Result, bail};
+
+use crate::adapters::{bybit, gateio, hyperswap};
+use crate::clock;
+use crate::dex::profile::{interfaces, verify_interface};
+use crate::dex::router::{quote_book, router_for};
+use crate::execution::hyperliquid::HyperliquidClient;
+use crate::execution::split::PoolRoute;
+use crate::helpers::abi::{
+    ONE_ETHER, call_tx, decode_address_response, decode_pool_fee_response, pool_fee_calldata,
+    pool_token0_calldata, pool_token1_calldata,
+};
+use crate::helpers::provider::connect_provider;
+use crate::normalize::Normalizer;
+use crate::settings::{DexKind, Settings};
+
+/// Checks the config end to end against the live endpoints: RPC reachable
+/// and on `chain_id`, contracts deployed with the expected interfaces,
+/// every pool on the configured pair and fee tier, quoter answering, CEX symbols trading, the clock in sync
+/// and keys valid.
+/// Prints one line per check with a hint for each failure and errors if
+/// any failed.
+pub async fn run_doctor(cfg: &Settings) -> Result<()> {
+    let mut report = Report::default();
+
+    let mut rpc_ok = true;
+    let mut endpoints: Vec<(&str, &str)> = Vec::new();
+    for (name, url) in [("quote", cfg.quote_rpc()), ("subscribe", cfg.subscribe_rpc()), ("send", cfg.send_rpc())] {
+        // the split endpoints default to rpc_url, check each url once
+        if !endpoints.iter().any(|(_, u)| *u == url) {
+            endpoints.push((name, url));
+        }
+    }
+    for (name, url) in endpoints {
+        rpc_ok &= check_rpc(&mut report, cfg, name, url).await;
+    }
+
+    // everything on chain goes through the quote endpoint
+    if rpc_ok {
+        let provider = connect_provider(cfg.quote_rpc()).await?;
+        check_contracts(&mut report, cfg, &provider).await;
+        check_interfaces(&mut report, cfg, &provider).await;
+        if cfg.dex_kind == DexKind::V3 {
+            for route in PoolRoute::all(cfg) {
+                check_pool(&mut report, cfg, &provider, &route).await;
+            }
+        } else {
+            check_router(&mut report, cfg, &provider).await;
+        }
+    } else {
+        report.skip("contracts, pools and quoter", "fix the rpc first");
+    }
+
+    check_cex(&mut report, cfg).await;
+    check_clock(&mut report, cfg).await;
+    check_keys(&mut report, cfg).await;
+
+    println!();
+    if report.failures > 0 {
+        bail!("{} check(s) failed", report.failures);
+    }
+    println!("all checks passed");
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn pass(&mut self, check: &str, detail: impl Display) {
+        println!("✅ {:<28} {}", check, detail);
+    }
+
+    fn fail(&mut self, check: &str, error: impl Display, hint: impl Display) {
+        self.failures += 1;
+        println!("🚨 {:<28} {}", check, error);
+        println!("   {:<28} hint: {}", "", hint);
+    }
+
+    fn skip(&mut self, check: &str, reason: &str) {
+        println!("⏭️  {:<28} skipped, {}", check, reason);
+    }
+}
+
+async fn check_rpc(report: &mut Report, cfg: &Settings, name: &str, url: &str) -> bool {
+    let check = format!("{} rpc", name);
+    let chain_id = match connect_provider(url).await {
+        Ok(provider) => provider.get_chain_id().await.map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+
+    match chain_id {
+        Ok(id) if id == cfg.chain_id => {
+            report.pass(&check, format!("{} on chain {}", url, id));
+            true
+        }
+        Ok(id) => {
+            report.fail(
+                &check,
+                format!("{} is on chain {}, expected {}", url, id, cfg.chain_id),
+                "point the url at a HyperEVM node, or set chain_id if targeting another network on purpose",
+            );
+            false
+        }
+        Err(e) => {
+            report.fail(
+                &check,
+                format!("{:#}", e),
+                format!("check that {} is reachable and speaks JSON-RPC (RPC_URL / {}_RPC_URL)", url, name.to_uppercase()),
+            );
+            false
+        }
+    }
+}
+
+async fn check_contracts(report: &mut Report, cfg: &Settings, provider: &impl Provider) {
+    let contracts = [
+        ("weth_addr", cfg.weth_addr),
+        ("usdt_addr", cfg.usdt_addr),
+        ("router_addr", cfg.router_addr),
+        ("multicall3_addr", cfg.multicall3_addr),
+    ];
+
+    for (key, address) in contracts {
+        match provider.get_code_at(address).await {
+            Ok(code) if !code.is_empty() => report.pass(key, address),
+            Ok(_) => report.fail(
+                key,
+                format!("no contract at {}", address),
+                format!("{} must be deployed on chain {}; check the address", key, cfg.chain_id),
+            ),
+            Err(e) => report.fail(key, e, "the rpc answered eth_chainId but not eth_getCode; try another node"),
+        }
+    }
+}
+
+async fn check_interfaces(report: &mut Report, cfg: &Settings, provider: &impl Provider) {
+    if !cfg.verify_dex_interfaces {
+        report.skip("dex interfaces", "verify_dex_interfaces is off");
+        return;
+    }
+    for interface in interfaces(cfg) {
+        let check = format!("{} interface", interface.key);
+        match verify_interface(provider, interface.address, &interface.functions).await {
+            Ok(()) => report.pass(&check, format!("{} functions at {}", interface.functions.len(), interface.address)),
+            Err(e) => report.fail(
+                &check,
+                format!("{:#}", e),
+                "check dex_profile, dex_kind and quoter_kind, or turn off verify_dex_interfaces for contracts behind proxies",
+            ),
+        }
+    }
+}
+
+async fn check_pool(report: &mut Report, cfg: &Settings, provider: &impl Provider, route: &PoolRoute) {
+    let check = format!("pool {}", route.pool_addr);
+    match pool_info(cfg, provider, route.pool_addr).await {
+        Ok((token0, token1, fee)) => {
+            let pair_ok = (token0 == cfg.weth_addr && token1 == cfg.usdt_addr)
+                || (token0 == cfg.usdt_addr && token1 == cfg.weth_addr);
+            if !pair_ok {
+                report.fail(
+                    &check,
+                    format!("pool trades {}/{}", token0, token1),
+                    "the pool must pair weth_addr with usdt_addr; check pool_addr / extra_pools",
+                );
+                return;
+            }
+            if fee != route.fee_tier {
+                report.fail(
+                    &check,
+                    format!("pool fee is {}, configured {}", fee, route.fee_tier),
+                    format!("set the fee tier of this pool to {}", fee),
+                );
+                return;
+            }
+            report.pass(&check, format!("fee tier {}", fee));
+        }
+        Err(e) => {
+            report.fail(
+                &check,
+                format!("{:#}", e),
+                "not a v3 pool; check pool_addr / extra_pools",
+            );
+            return;
+        }
+    }
+
+    let check = format!("quoter {}", route.quoter_addr);
+    match hyperswap::quote_route(cfg, provider, route, ONE_ETHER).await {
+        Ok(price) if price.bid > 0.0 && price.ask > 0.0 => {
+            report.pass(&check, format!("{:?}, bid {:.4} ask {:.4}", route.quoter_kind, price.bid, price.ask))
+        }
+        Ok(price) => report.fail(
+            &check,
+            format!("quoted bid {} ask {}", price.bid, price.ask),
+            "the pool has no liquidity around the current price",
+        ),
+        Err(e) => report.fail(
+            &check,
+            format!("{:#}", e),
+            "check quoter_v2_addr and quoter_kind (v1 and v2 quoters take different calldata)",
+        ),
+    }
+}
+
+async fn check_router(report: &mut Report, cfg: &Settings, provider: &dyn Provider) {
+    let check = format!("{:?} router", cfg.dex_kind);
+    let quoted = match router_for(cfg) {
+        Ok(router) => quote_book(cfg, &*router, provider, Normalizer::new(cfg).one_base()).await.map(|p| (p, router.fee_bps())),
+        Err(e) => Err(e),
+    };
+    match quoted {
+        Ok((price, fee_bps)) if price.bid > 0.0 && price.ask > 0.0 => {
+            report.pass(&check, format!("bid {:.4} ask {:.4}, fee {:.1} bps", price.bid, price.ask, fee_bps))
+        }
+        Ok((price, _)) => report.fail(
+            &check,
+            format!("quoted bid {} ask {}", price.bid, price.ask),
+            "the pair has no liquidity around the current price",
+        ),
+        Err(e) => report.fail(&check, format!("{:#}", e), "check router_addr, dex_kind and aggregator_url"),
+    }
+}
+
+async fn pool_info(cfg: &Settings, provider: &impl Provider, pool: Address) -> Result<(Address, Address, u32)> {
+    let call = |calldata: Bytes| provider.call(call_tx(pool, cfg.self_addr, calldata));
+
+    let (token0, token1, fee) = tokio::try_join!(
+        call(pool_token0_calldata()).into_future(),
+        call(pool_token1_calldata()).into_future(),
+        call(pool_fee_calldata()).into_future(),
+    )?;
+
+    Ok((decode_address_response(token0)?, decode_address_response(token1)?, decode_pool_fee_response(fee)?))
+}
+
+async fn check_cex(report: &mut Report, cfg: &Settings) {
+    let (bybit, gateio) = tokio::join!(bybit::fetch_symbol_status(cfg), gateio::fetch_pair_status(cfg));
+
+    let check = format!("bybit {}", cfg.bybit_ticker);
+    match bybit {
+        Ok(status) if status == "Trading" => report.pass(&check, status),
+        Ok(status) => report.fail(&check, format!("status {}", status), "the symbol is listed but not trading"),
+        Err(e) => report.fail(
+            &check,
+            format!("{:#}", e),
+            format!("check bybit_ticker (e.g. HYPEUSDT) and that {} is reachable", cfg.bybit_rest_endpoint),
+        ),
+    }
+
+    let check = format!("gateio {}", cfg.gateio_ticker);
+    match gateio {
+        Ok(status) if status == "tradable" => report.pass(&check, status),
+        Ok(status) => report.fail(&check, format!("trade status {}", status), "the pair is listed but not tradable"),
+        Err(e) => report.fail(
+            &check,
+            format!("{:#}", e),
+            format!("check gateio_ticker (e.g. HYPE_USDT) and that {} is reachable", cfg.gateio_rest_endpoint),
+        ),
+    }
+}
+
+async fn check_clock(report: &mut Report, cfg: &Settings) {
+    let clock = clock::measure(cfg).await;
+    for sample in &clock.samples {
+        report.pass(&format!("clock vs {}", sample.source), format!("{}ms off (rtt {}ms)", sample.offset_ms, sample.rtt_ms));
+    }
+    for (source, e) in &clock.failures {
+        report.skip(&format!("clock vs {}", source), &format!("not answering: {}", e));
+    }
+
+    let Some(skew) = clock.skew_ms() else {
+        return;
+    };
+    for (venue, max) in [
+        ("bybit", clock::BYBIT_MAX_SKEW_MS),
+        ("gateio", clock::GATEIO_MAX_SKEW_MS),
+        ("hyperliquid", clock::HYPERLIQUID_MAX_SKEW_MS),
+    ] {
+        let check = format!("clock for {} signing", venue);
+        if skew.abs() > max {
+            report.fail(&check, format!("{}ms off, {} accepts {}ms", skew, venue, max), "sync the host clock (chrony, ntpd)");
+        } else {
+            report.pass(&check, format!("{}ms off", skew));
+        }
+    }
+}
+
+async fn check_keys(report: &mut Report, cfg: &Settings) {
+    match &cfg.private_key {
+        Some(key) => match key.expose().parse::<PrivateKeySigner>() {
+            Ok(signer) if signer.address() == cfg.self_addr => report.pass("private_key", signer.address()),
+            Ok(signer) => report.fail(
+                "private_key",
+                format!("key is for {}, self_addr is {}", signer.address(), cfg.self_addr),
+                "set self_addr to the address of PRIVATE_KEY",
+            ),
+            Err(_) => report.fail("private_key", "not a valid private key", "PRIVATE_KEY must be 32 hex-encoded bytes"),
+        },
+        None => report.skip("private_key", "not set"),
+    }
+
+    if cfg.hyperliquid_private_key.is_none() {
+        report.skip("hyperliquid_private_key", "not set");
+        return;
+    }
+
+    let role = async {
+        let client = HyperliquidClient::new(cfg)?;
+        let role = client.user_role(client.address()).await?;
+        Ok::<_, anyhow::Error>((client.address(), role))
+    };
+    match role.await {
+        Ok((address, role)) if role != "missing" => report.pass("hyperliquid_private_key", format!("{} ({})", address, role)),
+        Ok((address, _)) => report.fail(
+            "hyperliquid_private_key",
+            format!("{} is unknown to hyperliquid", address),
+            "approve the key as an API wallet of the trading account, or fund the address",
+        ),
+        Err(e) => report.fail(
+            "hyperliquid_private_key",
+            format!("{:#}", e),
+            format!("check HYPERLIQUID_PRIVATE_KEY and that {} is reachable", cfg.hyperliquid_api_url),
+        ),
+    }
+}
@@ -0,0 +1,24 @@
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::control::send_command;
+use crate::settings::Settings;
+
+#[derive(Args)]
+pub struct LogArgs {
+    /// New filter in RUST_LOG syntax, e.g. "info,rust_arb_bot::adapters::hyperswap=warn";
+    /// prints the current one when omitted
+    pub filter: Option<String>,
+}
+
+/// Shows or changes the log filter of the bot running on `control_socket`.
+pub async fn run_log(cfg: &Settings, args: &LogArgs) -> Result<()> {
+    let path = cfg.control_socket.as_deref().context("control_socket is not configured")?;
+    let command = match &args.filter {
+        Some(filter) => format!("log {}", filter),
+        None => "log".to_string(),
+    };
+
+    println!("{}", send_command(path, &command).await?);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+
+use crate::control::send_command;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Switch {
+    On,
+    Off,
+}
+
+#[derive(Args)]
+pub struct MaintenanceArgs {
+    /// Start or end maintenance; prints the current phase when omitted
+    pub switch: Option<Switch>,
+}
+
+/// Starts or ends maintenance of the bot running on `control_socket`, or
+/// shows its phase.
+pub async fn run_maintenance(cfg: &Settings, args: &MaintenanceArgs) -> Result<()> {
+    let path = cfg.control_socket.as_deref().context("control_socket is not configured")?;
+    let command = match args.switch {
+        Some(Switch::On) => "maintenance on",
+        Some(Switch::Off) => "maintenance off",
+        None => "maintenance",
+    };
+
+    println!("{}", send_command(path, command).await?);
+    Ok(())
+}
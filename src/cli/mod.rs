@@ -0,0 +1,12 @@
+pub mod audit;
+pub mod backtest;
+pub mod balances;
+pub mod bench;
+pub mod doctor;
+pub mod log;
+pub mod maintenance;
+pub mod pair;
+pub mod quote;
+pub mod rehearse;
+pub mod spreads;
+pub mod whatif;
@@ -0,0 +1,80 @@
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+
+use crate::control::send_command;
+use crate::pairs::PairSpec;
+use crate::settings::Settings;
+
+#[derive(Args)]
+pub struct PairArgs {
+    #[command(subcommand)]
+    pub command: PairCommand,
+}
+
+#[derive(Subcommand)]
+pub enum PairCommand {
+    /// Start trading a pair: its CEX feeds, pool and engines
+    Add {
+        /// Name to refer to the pair by, e.g. PURR
+        name: String,
+        #[arg(long)]
+        base: Address,
+        #[arg(long)]
+        quote: Address,
+        #[arg(long)]
+        pool: Address,
+        #[arg(long, default_value_t = 3000)]
+        fee_tier: u32,
+        /// Bybit symbol, e.g. PURRUSDT
+        #[arg(long)]
+        bybit: Option<String>,
+        /// Gate.io currency pair, e.g. PURR_USDT
+        #[arg(long)]
+        gateio: Option<String>,
+        /// Decimals of the base token, if not those of the configured pair
+        #[arg(long)]
+        base_decimals: Option<u8>,
+        /// Decimals of the quote token, if not those of the configured pair
+        #[arg(long)]
+        quote_decimals: Option<u8>,
+        /// DEX profile whose quoter and router to use, if not those of the
+        /// configured pair
+        #[arg(long)]
+        dex_profile: Option<String>,
+    },
+    /// Stop trading a pair added at runtime
+    Remove { name: String },
+    /// List the pairs added at runtime
+    List,
+}
+
+/// Adds, removes or lists pairs of the bot running on `control_socket`.
+pub async fn run_pair(cfg: &Settings, args: &PairArgs) -> Result<()> {
+    let path = cfg.control_socket.as_deref().context("control_socket is not configured")?;
+    let command = match &args.command {
+        PairCommand::Add { name, base, quote, pool, fee_tier, bybit, gateio, base_decimals, quote_decimals, dex_profile } => {
+            let spec = PairSpec {
+                name: name.clone(),
+                base_addr: *base,
+                quote_addr: *quote,
+                pool_addr: *pool,
+                fee_tier: *fee_tier,
+                bybit_ticker: bybit.clone(),
+                gateio_ticker: gateio.clone(),
+                base_decimals: *base_decimals,
+                quote_decimals: *quote_decimals,
+                dex_profile: dex_profile.clone(),
+            };
+            format!("pair add {}", serde_json::to_string(&spec)?)
+        }
+        PairCommand::Remove { name } => format!("pair remove {}", name),
+        PairCommand::List => "pair list".to_string(),
+    };
+
+    let reply = send_command(path, &command).await?;
+    if !reply.is_empty() {
+        println!("{}", reply);
+    }
+    Ok(())
+}
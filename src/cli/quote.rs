@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, U256, utils::parse_units},
+    providers::Provider,
+};
+use anyhow::{Result, anyhow, bail};
+use clap::Args;
+
+use crate::adapters::{bybit, gateio};
+use crate::arbitrage::{ArbDirection, PriceData, arb_profit_in};
+use crate::dex::router::{DexRouter, UniswapV3, quote_book, router_for};
+use crate::execution::split::PoolRoute;
+use crate::normalize::Normalizer;
+use crate::helpers::provider::connect_provider;
+use crate::settings::{DexKind, Settings};
+
+#[derive(Debug, Args)]
+pub struct QuoteArgs {
+    /// Base amount to quote on the DEX, in whole tokens
+    #[arg(long, default_value = "1")]
+    pub size: String,
+    /// Pool to quote, one of `pool_addr` or `extra_pools` (default: `pool_addr`);
+    /// v3 only
+    #[arg(long)]
+    pub pool: Option<Address>,
+}
+
+/// Quotes the DEX once in both directions and takes one REST snapshot
+/// from each CEX, printing them side by side with the net result of each
+/// arbitrage direction at `size`.
+pub async fn run_quote(cfg: &Settings, args: &QuoteArgs) -> Result<()> {
+    let router: Arc<dyn DexRouter> = match cfg.dex_kind {
+        DexKind::V3 => {
+            let routes = PoolRoute::all(cfg);
+            let route = match args.pool {
+                Some(pool) => routes
+                    .into_iter()
+                    .find(|r| r.pool_addr == pool)
+                    .ok_or_else(|| anyhow!("pool {} is not configured (pool_addr or extra_pools)", pool))?,
+                None => routes.into_iter().next().unwrap(),
+            };
+            println!(
+                "pool {} (fee tier {}, {:?} quoter {})",
+                route.pool_addr, route.fee_tier, route.quoter_kind, route.quoter_addr
+            );
+            Arc::new(UniswapV3::new(cfg, route))
+        }
+        kind => {
+            if args.pool.is_some() {
+                bail!("--pool picks among v3 pools, dex_kind is {:?}", kind);
+            }
+            let router = router_for(cfg)?;
+            println!("{:?} router {} (fee {:.1} bps)", kind, router.router_addr(), router.fee_bps());
+            router
+        }
+    };
+
+    let norm = Normalizer::new(cfg);
+    let base_amount: U256 = parse_units(&args.size, norm.base_decimals)?.into();
+    let size = norm.base(base_amount);
+    if size == 0.0 {
+        return Err(anyhow!("size must be positive"));
+    }
+
+    let provider = connect_provider(cfg.quote_rpc()).await?;
+    let (dex, bybit, gateio, gas_price) = tokio::join!(
+        quote_book(cfg, &*router, &provider, base_amount),
+        bybit::fetch_snapshot(cfg),
+        gateio::fetch_snapshot(cfg),
+        provider.get_gas_price().into_future(),
+    );
+
+    println!("size {} base", size);
+    println!();
+    println!("{:<18} {:>12} {:>12} {:>10}", "venue", "bid", "ask", "width bps");
+    print_row("hyperswap", &dex);
+    print_row(&format!("bybit {}", cfg.bybit_ticker), &bybit);
+    print_row(&format!("gateio {}", cfg.gateio_ticker), &gateio);
+
+    let (Ok(dex), Ok(gas_price)) = (&dex, &gas_price) else {
+        if let Err(e) = &gas_price {
+            println!("\ngas price unavailable: {}", e);
+        }
+        return Ok(());
+    };
+
+    println!();
+    println!("gas price {:.3} gwei", *gas_price as f64 / 1e9);
+    println!("{:<18} {:>14} {:>14}", "vs", "buy cex net", "buy dex net");
+    for (venue, cex) in [("bybit", &bybit), ("gateio", &gateio)] {
+        let Ok(cex) = cex else {
+            continue;
+        };
+
+        // fees scale with size, gas is paid once per trade
+        let net = |buy: f64, sell: f64, direction: ArbDirection| {
+            let profit = arb_profit_in(cfg.gas_token(), cfg.cex_fee_bps, cfg.dex_gas_used, buy, sell, &direction, *gas_price);
+            (profit.gross - profit.cex_fee) * size - profit.gas_cost
+        };
+        let buy_cex = net(cex.ask, dex.bid, ArbDirection::BuyCex);
+        let buy_dex = net(dex.ask, cex.bid, ArbDirection::BuyDex);
+
+        println!("{:<18} {:>14.4} {:>14.4}", venue, buy_cex, buy_dex);
+    }
+
+    Ok(())
+}
+
+fn print_row(venue: &str, price: &Result<PriceData>) {
+    match price {
+        Ok(p) => {
+            let width_bps = (p.ask - p.bid) / ((p.ask + p.bid) / 2.0) * 10000.0;
+            println!("{:<18} {:>12.4} {:>12.4} {:>10.1}", venue, p.bid, p.ask, width_bps);
+        }
+        Err(e) => println!("{:<18} error: {}", venue, e),
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::{Address, TxHash, U256, utils::parse_ether};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionReceipt;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Args;
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+use crate::arbitrage::{ArbDirection, DEX_VENUE, PriceData, VenueId};
+use crate::dex::router::{quote_book, router_for};
+use crate::execution::bybit::BybitClient;
+use crate::execution::confirmations::ConfirmationTracker;
+use crate::execution::dex::DexSwap;
+use crate::execution::executor::{ArbExecutor, Execution};
+use crate::execution::sender::{TxOverrides, TxSender};
+use crate::execution::verify::verify_swap_fill;
+use crate::helpers::abi::{approve_calldata, depositCall};
+use crate::helpers::provider::connect_provider;
+use crate::inventory::evm_inventory;
+use crate::markets::{MarketMeta, Markets};
+use crate::normalize::Normalizer;
+use crate::pnl::{PnlLedger, Settlement};
+use crate::risk::{RiskHandle, TradingState, risk_channel};
+use crate::scanner::{MatrixScanner, best};
+use crate::settings::{ExecutionMode, Secret, Settings};
+
+// the first dev account anvil funds on every fork
+const ANVIL_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const RECEIPT_POLLS: u32 = 50;
+
+#[derive(Debug, Args)]
+pub struct RehearseArgs {
+    /// RPC of an anvil fork of the chain, e.g. `anvil --fork-url $RPC_URL`
+    #[arg(long, default_value = "http://127.0.0.1:8545")]
+    pub fork_url: String,
+    /// Key of the account trading on the fork (default: anvil's first dev
+    /// account); its balance is set by the rehearsal
+    #[arg(long)]
+    pub key: Option<String>,
+    /// Base amount traded, in whole tokens
+    #[arg(long, default_value_t = 1.0)]
+    pub size: f64,
+    /// How far below the DEX bid the seeded CEX ask sits, in bps
+    #[arg(long, default_value_t = 200)]
+    pub edge_bps: u32,
+}
+
+/// Runs one arbitrage end to end on an anvil fork: funds the test account,
+/// seeds a BuyCex opportunity with a CEX ask under the fork's DEX bid,
+/// detects it with the matrix scanner, executes it live (the swap on the
+/// fork, the market order on a local mock of the Bybit API) and checks the
+/// PnL ledger against the balance changes on chain.
+pub async fn run_rehearse(cfg: &Settings, args: &RehearseArgs) -> Result<()> {
+    // the base leg is funded by wrapping native tokens
+    if cfg.native_token != cfg.pair_base {
+        bail!("rehearse needs the pair base to be the native token, {} is not {}", cfg.pair_base, cfg.native_token);
+    }
+    let signer: PrivateKeySigner = args.key.as_deref().unwrap_or(ANVIL_KEY).parse().context("invalid --key")?;
+    let account = signer.address();
+    let provider: Arc<dyn Provider> = Arc::new(connect_provider(&args.fork_url).await?);
+    let norm = Normalizer::new(cfg);
+    let base_amount = norm.base_units(args.size);
+
+    let sender = Arc::new(TxSender::connect(&args.fork_url, signer, cfg.router_addr).await?);
+    fund(cfg, &*provider, &sender, base_amount).await?;
+    println!("1. funded {} with {} base, router approved", account, args.size);
+
+    let router = router_for(cfg)?;
+    let dex = quote_book(cfg, &*router, &*provider, base_amount).await?;
+    let ask = dex.bid * (1.0 - args.edge_bps as f64 / 10000.0);
+    let cex = PriceData::new(ask * 0.9995, ask);
+    let exchange = MockBybit::start(ask).await?;
+    let cfg = rehearsal_settings(cfg, args, account, &exchange.url);
+
+    let (risk, _trading) = rehearsal_risk();
+    let (cex_tx, cex_rx) = watch::channel(None);
+    let (dex_tx, dex_rx) = watch::channel(None);
+    cex_tx.send_replace(Some(cex.clone()));
+    dex_tx.send_replace(Some(dex.clone()));
+    let feeds = vec![(VenueId::Cex("bybit".to_string()), cex_rx), (VenueId::Dex(DEX_VENUE.to_string()), dex_rx)];
+    let mut scanner = MatrixScanner::new(&cfg, feeds, provider.clone(), risk.clone());
+    let found = scanner.scan().await?;
+    let candidate = best(&found, &ArbDirection::BuyCex)
+        .filter(|c| c.profit.net > 0.0)
+        .ok_or_else(|| anyhow!("seeded bybit ask {:.4} under dex bid {:.4} was not detected", cex.ask, dex.bid))?
+        .clone();
+    println!(
+        "2. detected {:?}: bybit ask {:.4}, dex bid {:.4}, net ${:.4} per base [{}]",
+        candidate.direction, cex.ask, dex.bid, candidate.profit.net, candidate.id
+    );
+
+    // quoted before the swap moves the pool, as the executor does
+    let quoted = router.quote(&*provider, &ArbDirection::BuyCex, base_amount).await?;
+    let before = evm_inventory(&cfg, &*provider, account).await?;
+    let (tracker, _) = ConfirmationTracker::new(&cfg, sender.clone());
+    let executor = ArbExecutor::new(&cfg, provider.clone(), risk.clone())?
+        .with_sender(sender, tracker.handle())
+        .with_venue(Arc::new(BybitClient::new(&cfg)?));
+    tokio::spawn(tracker.run(risk.events.subscribe()));
+    let (fill, tx, on) = match executor.execute(&candidate).await {
+        Execution::Sent { fill, tx, account: on, .. } => (fill, tx, on),
+        other => bail!("the opportunity was not executed: {:?}", other),
+    };
+    println!("3. bought {:.6} on the mock bybit at {:.4}, sold on the fork in {}", fill.base, fill.price, tx);
+
+    let receipt = wait_receipt(&*provider, tx).await?;
+    let swap = DexSwap::for_direction(&cfg, &ArbDirection::BuyCex, base_amount, U256::from(quoted));
+    let verified = verify_swap_fill(&receipt, &swap, account, U256::from(quoted))?;
+    let mut ledger = PnlLedger::default();
+    let settlement = Settlement::new("bybit", tx, fill.price).with_account(&on).with_wallet(account);
+    let record = ledger.record(&cfg, &settlement, &candidate.opportunity(), &verified).clone();
+    let after = evm_inventory(&cfg, &*provider, account).await?;
+
+    // the ledger reads the receipt, the balances are what the chain kept
+    let base_spent = before.base.saturating_sub(after.base);
+    let quote_received = after.quote.saturating_sub(before.quote);
+    if base_spent != verified.amount_in || quote_received != verified.amount_out {
+        bail!(
+            "ledger disagrees with balances: swap in {} out {}, balances moved {} and {}",
+            verified.amount_in, verified.amount_out, base_spent, quote_received
+        );
+    }
+    let orders = exchange.orders.lock().unwrap().len();
+    if orders != 1 || (fill.base - record.base_amount).abs() > 1e-9 {
+        bail!("the CEX leg does not match the DEX leg: {} orders, {} bought, {} sold", orders, fill.base, record.base_amount);
+    }
+    println!(
+        "4. pnl ${:.4} (estimated ${:.4}, cex fee ${:.4}), balances reconcile: -{:.6} base, +{:.6} quote",
+        record.pnl,
+        record.estimated_pnl,
+        record.cex_fee,
+        norm.base(base_spent),
+        norm.quote(quote_received)
+    );
+    println!("rehearsal passed");
+    Ok(())
+}
+
+// native tokens for gas and the swap, wrapped into the base token and
+// approved for the router
+async fn fund(cfg: &Settings, provider: &dyn Provider, sender: &TxSender, base_amount: U256) -> Result<()> {
+    let gas_money = parse_ether("10")?;
+    let _: Value = sender
+        .provider()
+        .raw_request("anvil_setBalance".into(), (sender.address(), base_amount * U256::from(2u64) + gas_money))
+        .await
+        .context("anvil_setBalance failed, is --fork-url an anvil fork?")?;
+
+    let deposit = TxOverrides { value: Some(base_amount), ..Default::default() };
+    let wrap = sender.send_call(cfg.weth_addr, depositCall {}.abi_encode().into(), deposit).await?;
+    wait_receipt(provider, wrap.hash).await?;
+    let approve = sender
+        .send_call(cfg.weth_addr, approve_calldata(cfg.router_addr, U256::MAX), TxOverrides::default())
+        .await?;
+    wait_receipt(provider, approve.hash).await?;
+    Ok(())
+}
+
+// live on the mock exchange, any profit counting
+fn rehearsal_settings(cfg: &Settings, args: &RehearseArgs, account: Address, exchange_url: &str) -> Settings {
+    let mut cfg = cfg.clone();
+    cfg.execution_mode = ExecutionMode::Live;
+    cfg.exec_base_size = args.size;
+    cfg.exec_min_profit = 0.0;
+    // anvil mines a block per transaction, none would follow the swap
+    cfg.confirmation_depth = 1;
+    cfg.self_addr = account;
+    cfg.cex_venues = vec!["bybit".to_string()];
+    cfg.bybit_rest_endpoint = exchange_url.to_string();
+    cfg.bybit_api_key = Some("rehearsal".to_string());
+    cfg.bybit_api_secret = Some(Secret::new("rehearsal"));
+    cfg
+}
+
+// no limits beyond a fine grid for the mock symbol
+fn rehearsal_risk() -> (RiskHandle, watch::Sender<TradingState>) {
+    let (events, _) = risk_channel();
+    let (trading_tx, trading) = watch::channel(TradingState::default());
+    let meta = MarketMeta {
+        symbol: "REHEARSAL".to_string(),
+        tick_size: 1e-8,
+        lot_size: 1e-8,
+        min_qty: 0.0,
+        min_notional: 0.0,
+        price_precision: 8,
+        qty_precision: 8,
+        sig_figs: None,
+    };
+    let risk = RiskHandle {
+        events,
+        trading,
+        exposure: Default::default(),
+        throttle: Default::default(),
+        audit: Default::default(),
+        markets: Markets::new(BTreeMap::from([("bybit".to_string(), meta)])),
+        switches: Default::default(),
+    };
+    (risk, trading_tx)
+}
+
+async fn wait_receipt(provider: &dyn Provider, hash: TxHash) -> Result<TransactionReceipt> {
+    for _ in 0..RECEIPT_POLLS {
+        if let Some(receipt) = provider.get_transaction_receipt(hash).await? {
+            if !receipt.status() {
+                bail!("tx {} reverted on the fork", hash);
+            }
+            return Ok(receipt);
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    bail!("tx {} not mined on the fork", hash)
+}
+
+/// Just enough of the Bybit v5 order API for a rehearsal: orders fill at
+/// once at `price` and show up in the realtime order query.
+pub struct MockBybit {
+    pub url: String,
+    pub orders: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockBybit {
+    pub async fn start(price: f64) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let orders = Arc::new(Mutex::new(Vec::new()));
+        let served = orders.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let orders = served.clone();
+                tokio::spawn(async move {
+                    let _ = serve(socket, &orders, price).await;
+                });
+            }
+        });
+        Ok(Self { url, orders })
+    }
+}
+
+async fn serve(mut socket: TcpStream, orders: &Mutex<Vec<Value>>, price: f64) -> Result<()> {
+    let (method, target, body) = read_request(&mut socket).await?;
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let result = match (method.as_str(), path) {
+        ("POST", "/v5/order/create") => {
+            let order: Value = serde_json::from_slice(&body)?;
+            let mut orders = orders.lock().unwrap();
+            orders.push(order.clone());
+            json!({ "orderId": orders.len().to_string(), "orderLinkId": order["orderLinkId"] })
+        }
+        ("GET", "/v5/order/realtime") => {
+            let link_id = query.split('&').find_map(|kv| kv.strip_prefix("orderLinkId=")).unwrap_or_default();
+            let list: Vec<_> = orders
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|o| o["orderLinkId"] == link_id)
+                .map(|o| {
+                    json!({
+                        "orderLinkId": o["orderLinkId"],
+                        "orderStatus": "Filled",
+                        "cumExecQty": o["qty"],
+                        "avgPrice": price.to_string(),
+                    })
+                })
+                .collect();
+            json!({ "list": list })
+        }
+        _ => json!(null),
+    };
+    let body = json!({ "retCode": if result.is_null() { 10001 } else { 0 }, "retMsg": "OK", "result": result }).to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+// method, target and body of one HTTP/1.1 request
+async fn read_request(socket: &mut TcpStream) -> Result<(String, String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            bail!("connection closed mid-request");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, target) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+    let length = head
+        .lines()
+        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>()))
+        .transpose()?
+        .unwrap_or(0);
+    while buf.len() < head_end + length {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok((method.to_string(), target.to_string(), buf[head_end..].to_vec()))
+}
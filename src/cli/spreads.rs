@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::control::send_command;
+use crate::settings::Settings;
+use crate::spreads::{PERCENTILES, SpreadSummary};
+
+#[derive(Args)]
+pub struct SpreadsArgs {
+    /// Only this pair, e.g. PURR; every pair scanned when omitted
+    pub pair: Option<String>,
+}
+
+/// Prints the net spread percentiles per pair and direction the bot running
+/// on `control_socket` has observed.
+pub async fn run_spreads(cfg: &Settings, args: &SpreadsArgs) -> Result<()> {
+    let path = cfg.control_socket.as_deref().context("control_socket is not configured")?;
+    let command = match &args.pair {
+        Some(pair) => format!("spreads {}", pair),
+        None => "spreads".to_string(),
+    };
+    let summaries: Vec<SpreadSummary> = serde_json::from_str(&send_command(path, &command).await?)?;
+
+    let header: String = PERCENTILES.iter().map(|p| format!("{:>10}", format!("p{}", p))).collect();
+    println!("{:<12} {:<8} {:>8} {:>10}{} {:>10}", "pair", "side", "count", "min", header, "max");
+    for s in &summaries {
+        let cell = |v: Option<f64>| v.map_or(format!("{:>10}", "-"), |v| format!("{:>10.4}", v));
+        let percentiles: String = PERCENTILES.iter().map(|p| cell(s.at(*p))).collect();
+        println!(
+            "{:<12} {:<8} {:>8} {}{} {}",
+            s.pair,
+            format!("{:?}", s.direction),
+            s.count,
+            cell(s.min),
+            percentiles,
+            cell(s.max)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::settings::Settings;
+use crate::whatif::{Outcome, WhatIf, what_if_file};
+
+#[derive(Args)]
+pub struct WhatIfArgs {
+    /// Audit log to re-price; defaults to `audit_file`
+    pub path: Option<String>,
+    /// CEX taker fee, in bps
+    #[arg(long)]
+    pub cex_fee_bps: Option<u32>,
+    /// Pool fee tier, in pips (500 = 0.05%)
+    #[arg(long)]
+    pub dex_fee_tier: Option<u32>,
+    /// Gas used by the DEX leg
+    #[arg(long)]
+    pub gas_used: Option<u64>,
+    /// Gas price, in gwei, instead of the recorded one
+    #[arg(long)]
+    pub gas_price_gwei: Option<f64>,
+    /// Base per trade instead of `exec_base_size`
+    #[arg(long)]
+    pub size: Option<f64>,
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Re-prices the engine evaluations recorded in the audit log with the
+/// given fee, gas and size parameters and compares them with the config.
+pub fn run_what_if(cfg: &Settings, args: &WhatIfArgs) -> Result<()> {
+    let path = args
+        .path
+        .as_deref()
+        .or(cfg.audit_file.as_deref())
+        .context("no path given and audit_file is not configured")?;
+    let what_if = WhatIf {
+        cex_fee_bps: args.cex_fee_bps,
+        dex_fee_tier: args.dex_fee_tier,
+        dex_gas_used: args.gas_used,
+        gas_price_gwei: args.gas_price_gwei,
+        size: args.size,
+    };
+    let report = what_if_file(cfg, path, &what_if)?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("{}: {} evaluations, trades netting at least {}", path, report.evaluations, cfg.exec_min_profit);
+    println!("{:<10} {:>10} {:>12} {:>14} {:>12}", "", "size", "trades", "net", "net/trade");
+    let row = |label: &str, o: &Outcome| {
+        let per_trade = if o.profitable > 0 { o.net / o.profitable as f64 } else { 0.0 };
+        println!("{:<10} {:>10.4} {:>12} {:>+14.6} {:>+12.6}", label, o.size, o.profitable, o.net, per_trade);
+    };
+    row("current", &report.current);
+    row("what-if", &report.what_if);
+    println!(
+        "{:<10} {:>10} {:>+12} {:>+14.6}",
+        "change",
+        "",
+        report.what_if.profitable as i64 - report.current.profitable as i64,
+        report.what_if.net - report.current.net
+    );
+    Ok(())
+}
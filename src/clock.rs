@@ -0,0 +1,280 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow, bail};
+use log::{error, info, warn};
+use serde_json::Value;
+use tokio::net::UdpSocket;
+use tokio::time::{Instant, MissedTickBehavior, interval, timeout};
+
+use crate::settings::Settings;
+
+// most the local clock may be off before signed requests to a venue are
+// refused, inside what the venue accepts to leave room for latency
+
+/// Bybit rejects timestamps outside its 5s receive window.
+pub const BYBIT_MAX_SKEW_MS: i64 = 4_000;
+/// Gate.io allows 60s.
+pub const GATEIO_MAX_SKEW_MS: i64 = 50_000;
+/// Hyperliquid nonces must be within a day of its clock.
+pub const HYPERLIQUID_MAX_SKEW_MS: i64 = 3_600_000;
+
+const NTP_EPOCH_OFFSET_SECS: f64 = 2_208_988_800.0;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+static SKEW_MS: AtomicI64 = AtomicI64::new(0);
+static MEASURED: AtomicBool = AtomicBool::new(false);
+
+/// Where the engines read the time from: the wall clock live, the
+/// recorded timestamps in a backtest.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn now_ms(&self) -> u64;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_ms(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+    }
+}
+
+/// A clock only moving when `set`, to replay recorded data at any speed.
+/// `now` counts from when it was created, at its `start_ms`.
+#[derive(Debug)]
+pub struct SimClock {
+    start: Instant,
+    start_ms: u64,
+    ms: AtomicU64,
+}
+
+impl SimClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self { start: Instant::now(), start_ms, ms: AtomicU64::new(start_ms) }
+    }
+
+    /// Moves the clock to `ms`, never backwards.
+    pub fn set(&self, ms: u64) {
+        self.ms.fetch_max(ms, Ordering::Relaxed);
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_millis(self.now_ms() - self.start_ms)
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.ms.load(Ordering::Relaxed)
+    }
+}
+
+/// How far the local clock is behind one time source, in ms; negative when
+/// it runs ahead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockSample {
+    pub source: String,
+    pub offset_ms: i64,
+    pub rtt_ms: u64,
+}
+
+/// Offset of a source that stamped `server_ms` while a request went out
+/// at `sent_ms` and came back at `received_ms`, taking the server to have
+/// answered halfway through.
+pub fn request_offset(sent_ms: u64, received_ms: u64, server_ms: u64) -> i64 {
+    server_ms as i64 - (sent_ms + received_ms) as i64 / 2
+}
+
+/// NTP offset from the client send (`t0`), server receive (`t1`), server
+/// transmit (`t2`) and client receive (`t3`) times, all in seconds.
+pub fn ntp_offset(t0: f64, t1: f64, t2: f64, t3: f64) -> f64 {
+    ((t1 - t0) + (t2 - t3)) / 2.0
+}
+
+/// Receive and transmit times of an NTP reply, in Unix seconds.
+pub fn parse_ntp_reply(reply: &[u8]) -> Result<(f64, f64)> {
+    if reply.len() < 48 {
+        bail!("ntp reply of {} bytes", reply.len());
+    }
+    let timestamp = |at: usize| {
+        let secs = u32::from_be_bytes(reply[at..at + 4].try_into().unwrap()) as f64;
+        let frac = u32::from_be_bytes(reply[at + 4..at + 8].try_into().unwrap()) as f64 / 4_294_967_296.0;
+        secs + frac - NTP_EPOCH_OFFSET_SECS
+    };
+    Ok((timestamp(32), timestamp(40)))
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or_default()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Asks the NTP server at `server` (`host:port`) for the time over SNTP.
+pub async fn query_ntp(server: &str) -> Result<ClockSample> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await.with_context(|| format!("failed to resolve {}", server))?;
+
+    // version 4, client mode
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    let t0 = now_secs();
+    socket.send(&request).await?;
+
+    let mut reply = [0u8; 48];
+    let n = timeout(QUERY_TIMEOUT, socket.recv(&mut reply)).await.map_err(|_| anyhow!("{} did not answer", server))??;
+    let t3 = now_secs();
+    let (t1, t2) = parse_ntp_reply(&reply[..n])?;
+
+    Ok(ClockSample {
+        source: format!("ntp {}", server),
+        offset_ms: (ntp_offset(t0, t1, t2, t3) * 1000.0).round() as i64,
+        rtt_ms: (((t3 - t0) - (t2 - t1)).max(0.0) * 1000.0) as u64,
+    })
+}
+
+// `source` server time from a GET of `url`, read by `server_ms`
+async fn query_http(source: &str, url: &str, server_ms: impl Fn(&Value) -> Option<u64>) -> Result<ClockSample> {
+    let sent = now_ms();
+    let body: Value = timeout(QUERY_TIMEOUT, async { reqwest::get(url).await?.error_for_status()?.json().await })
+        .await
+        .map_err(|_| anyhow!("{} did not answer", url))??;
+    let received = now_ms();
+    let server = server_ms(&body).ok_or_else(|| anyhow!("unexpected {} time response: {}", source, body))?;
+
+    Ok(ClockSample {
+        source: source.to_string(),
+        offset_ms: request_offset(sent, received, server),
+        rtt_ms: received.saturating_sub(sent),
+    })
+}
+
+pub async fn query_bybit(cfg: &Settings) -> Result<ClockSample> {
+    let url = format!("{}/v5/market/time", cfg.bybit_rest_endpoint.trim_end_matches('/'));
+    query_http("bybit", &url, |body| body["time"].as_u64()).await
+}
+
+pub async fn query_gateio(cfg: &Settings) -> Result<ClockSample> {
+    let url = format!("{}/api/v4/spot/time", cfg.gateio_rest_endpoint.trim_end_matches('/'));
+    query_http("gateio", &url, |body| body["server_time"].as_u64()).await
+}
+
+/// Offsets from `clock_ntp_server` and the exchange time endpoints, with
+/// the sources that could not be asked.
+#[derive(Debug, Clone, Default)]
+pub struct ClockReport {
+    pub samples: Vec<ClockSample>,
+    pub failures: Vec<(String, String)>,
+}
+
+impl ClockReport {
+    /// Median offset of the sources that answered, so one exchange with a
+    /// drifting clock of its own does not decide it.
+    pub fn skew_ms(&self) -> Option<i64> {
+        let mut offsets: Vec<i64> = self.samples.iter().map(|s| s.offset_ms).collect();
+        offsets.sort_unstable();
+        match offsets.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(offsets[n / 2]),
+            n => Some((offsets[n / 2 - 1] + offsets[n / 2]) / 2),
+        }
+    }
+}
+
+pub async fn measure(cfg: &Settings) -> ClockReport {
+    let ntp = async {
+        match &cfg.clock_ntp_server {
+            Some(server) => Some(query_ntp(server).await.map_err(|e| (format!("ntp {}", server), e))),
+            None => None,
+        }
+    };
+    let (ntp, bybit, gateio) = tokio::join!(ntp, query_bybit(cfg), query_gateio(cfg));
+
+    let mut report = ClockReport::default();
+    let results = [
+        ntp,
+        Some(bybit.map_err(|e| ("bybit".to_string(), e))),
+        Some(gateio.map_err(|e| ("gateio".to_string(), e))),
+    ];
+    for result in results.into_iter().flatten() {
+        match result {
+            Ok(sample) => report.samples.push(sample),
+            Err((source, e)) => report.failures.push((source, format!("{:#}", e))),
+        }
+    }
+    report
+}
+
+/// Takes `skew_ms` as the current skew that signed requests are checked
+/// against.
+pub fn set_skew(skew_ms: i64) {
+    SKEW_MS.store(skew_ms, Ordering::Relaxed);
+    MEASURED.store(true, Ordering::Relaxed);
+}
+
+/// The last measured skew, `None` before the first measurement.
+pub fn skew_ms() -> Option<i64> {
+    MEASURED.load(Ordering::Relaxed).then(|| SKEW_MS.load(Ordering::Relaxed))
+}
+
+/// Fails when the measured skew exceeds `max_skew_ms`, what `venue`
+/// tolerates on signed requests. Passes until a skew was measured.
+pub fn check_signing(venue: &str, max_skew_ms: i64) -> Result<()> {
+    match skew_ms() {
+        Some(skew) if skew.abs() > max_skew_ms => {
+            bail!("local clock is off by {}ms, beyond the {}ms {} accepts on signed requests", skew, max_skew_ms, venue)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Measures the clock, logs what came out and sets the skew signed
+/// requests are checked against. The previous skew stays when no source
+/// answered.
+pub async fn discipline(cfg: &Settings) -> ClockReport {
+    let report = measure(cfg).await;
+    for (source, e) in &report.failures {
+        warn!("clock check against {} failed: {}", source, e);
+    }
+    let Some(skew) = report.skew_ms() else {
+        warn!("🕰️ no time source answered, clock skew unknown");
+        return report;
+    };
+    set_skew(skew);
+
+    let refused: Vec<&str> = [("bybit", BYBIT_MAX_SKEW_MS), ("gateio", GATEIO_MAX_SKEW_MS), ("hyperliquid", HYPERLIQUID_MAX_SKEW_MS)]
+        .into_iter()
+        .filter(|(_, max)| skew.abs() > *max)
+        .map(|(venue, _)| venue)
+        .collect();
+    if !refused.is_empty() {
+        error!("🕰️ clock off by {}ms, refusing signed requests to {}; fix NTP on this host", skew, refused.join(", "));
+    } else if skew.abs() > cfg.clock_warn_skew_ms as i64 {
+        warn!("🕰️ clock off by {}ms (warning above {}ms)", skew, cfg.clock_warn_skew_ms);
+    } else {
+        info!("🕰️ clock off by {}ms across {} sources", skew, report.samples.len());
+    }
+    report
+}
+
+/// Re-measures the clock every `every`, since hosts drift and NTP daemons
+/// die.
+pub async fn run_clock_check(cfg: Settings, every: Duration) {
+    let mut tick = interval(every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // the first tick completes right away, startup measured already
+    tick.tick().await;
+
+    loop {
+        tick.tick().await;
+        discipline(&cfg).await;
+    }
+}
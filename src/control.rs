@@ -0,0 +1,157 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::logging;
+use crate::maintenance::{MaintenanceCommand, MaintenanceHandle};
+use crate::pairs::{PairManager, PairSpec};
+use crate::spreads::Spreads;
+
+/// What the control socket acts on; commands for parts left `None` are
+/// refused.
+#[derive(Clone, Default)]
+pub struct Control {
+    pub pairs: Option<Arc<PairManager>>,
+    pub maintenance: Option<MaintenanceHandle>,
+    pub spreads: Option<Spreads>,
+}
+
+/// Serves the control socket of a running bot: one command per line, one
+/// reply line each. Commands:
+///
+/// - `log` replies with the log filter in force
+/// - `log <filter>` replaces it, `RUST_LOG` syntax
+/// - `pair list` replies with the pairs added at runtime, as JSON
+/// - `pair add <json>` starts trading a `PairSpec`
+/// - `pair remove <name>` stops trading an added pair
+/// - `maintenance` replies with the maintenance phase
+/// - `maintenance on|off` starts or ends maintenance
+/// - `spreads [pair]` replies with net spread percentiles, as JSON
+///
+/// Replies start with `ok` or `error:`.
+pub async fn run_control_socket(path: String, control: Control) -> Result<()> {
+    // a socket left behind by a previous run blocks the bind
+    if Path::new(&path).exists() {
+        fs::remove_file(&path).with_context(|| format!("failed to remove stale {}", path))?;
+    }
+    let listener = UnixListener::bind(&path).with_context(|| format!("failed to bind {}", path))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    info!("control socket listening on {}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let control = control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve(stream, &control).await {
+                warn!("control connection error: {:#}", e);
+            }
+        });
+    }
+}
+
+async fn serve(stream: UnixStream, control: &Control) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let reply = execute(line.trim(), control).await;
+        write.write_all(format!("{}\n", reply).as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Runs one control command and returns its reply line.
+pub async fn execute(command: &str, control: &Control) -> String {
+    let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    match (name, arg.trim()) {
+        ("pair", arg) => match control.pairs.as_deref() {
+            Some(pairs) => match pair_command(pairs, arg).await {
+                Ok(reply) => format!("ok {}", reply).trim_end().to_string(),
+                Err(e) => format!("error: {:#}", e),
+            },
+            None => "error: pairs can not be changed in this process".to_string(),
+        },
+        ("maintenance", arg) => match &control.maintenance {
+            Some(maintenance) => match maintenance_command(maintenance, arg) {
+                Ok(reply) => format!("ok {}", reply),
+                Err(e) => format!("error: {:#}", e),
+            },
+            None => "error: maintenance is not available in this process".to_string(),
+        },
+        ("spreads", pair) => match &control.spreads {
+            Some(spreads) => {
+                let summaries: Vec<_> =
+                    spreads.summaries().into_iter().filter(|s| pair.is_empty() || s.pair == pair).collect();
+                match serde_json::to_string(&summaries) {
+                    Ok(json) => format!("ok {}", json),
+                    Err(e) => format!("error: {:#}", e),
+                }
+            }
+            None => "error: spreads are not tracked in this process".to_string(),
+        },
+        ("log", "") => match logging::current_filter() {
+            Some(filter) => format!("ok {}", filter),
+            None => "error: logger is not initialized".to_string(),
+        },
+        ("log", filter) => match logging::set_filter(filter) {
+            Ok(()) => {
+                info!("🔧 log filter set to {}", filter);
+                format!("ok {}", filter)
+            }
+            Err(e) => format!("error: {:#}", e),
+        },
+        _ => format!("error: unknown command {:?}", name),
+    }
+}
+
+async fn pair_command(pairs: &PairManager, arg: &str) -> Result<String> {
+    let (action, arg) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+    match action {
+        "list" => Ok(serde_json::to_string(&pairs.list())?),
+        "add" => {
+            let spec: PairSpec = serde_json::from_str(arg)?;
+            pairs.add(spec).await?;
+            Ok(String::new())
+        }
+        "remove" => {
+            pairs.remove(arg.trim())?;
+            Ok(String::new())
+        }
+        _ => bail!("unknown pair command {:?}", action),
+    }
+}
+
+fn maintenance_command(maintenance: &MaintenanceHandle, arg: &str) -> Result<&'static str> {
+    match arg {
+        "" => {}
+        "on" => maintenance.send(MaintenanceCommand::Enter)?,
+        "off" => maintenance.send(MaintenanceCommand::Exit)?,
+        _ => bail!("unknown maintenance command {:?}", arg),
+    }
+    // the phase as of now, an `on` takes a while to reach idle
+    Ok(maintenance.phase().as_str())
+}
+
+/// Sends `command` to the bot listening on `path` and returns the reply
+/// without its `ok` prefix.
+pub async fn send_command(path: &str, command: &str) -> Result<String> {
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("failed to connect to {}, is the bot running?", path))?;
+    let (read, mut write) = stream.into_split();
+    write.write_all(format!("{}\n", command).as_bytes()).await?;
+
+    let Some(reply) = BufReader::new(read).lines().next_line().await? else {
+        bail!("control socket closed without a reply");
+    };
+    match reply.strip_prefix("error: ") {
+        Some(e) => bail!("{}", e),
+        None => Ok(reply.strip_prefix("ok").unwrap_or(&reply).trim().to_string()),
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use futures_util::future::select_all;
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+use crate::arbitrage::PriceData;
+use crate::settings::Settings;
+
+struct CexFeed {
+    rx: watch::Receiver<Option<PriceData>>,
+    // mid at the last DEX refresh
+    anchor: Option<f64>,
+}
+
+/// When the DEX listeners quote next. While the DEX price keeps changing
+/// they quote every `dex_refresh_fast_ms`; each quote that finds it
+/// unchanged doubles the wait, up to `dex_refresh_slow_ms`. A CEX mid
+/// moving more than `dex_refresh_move_bps` since the last quote, or a pool
+/// event, cuts the wait short and goes back to the fast cadence.
+pub struct RefreshCadence {
+    fast: Duration,
+    slow: Duration,
+    move_bps: f64,
+    wait: Duration,
+    feeds: Vec<CexFeed>,
+}
+
+impl RefreshCadence {
+    pub fn new(fast: Duration, slow: Duration, move_bps: f64) -> Self {
+        Self { fast, slow: slow.max(fast), move_bps, wait: fast, feeds: Vec::new() }
+    }
+
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self::new(
+            Duration::from_millis(cfg.dex_refresh_fast_ms),
+            Duration::from_millis(cfg.dex_refresh_slow_ms),
+            cfg.dex_refresh_move_bps,
+        )
+    }
+
+    /// Wakes on moves of these CEX feeds.
+    pub fn with_cex_feeds(mut self, feeds: Vec<watch::Receiver<Option<PriceData>>>) -> Self {
+        self.feeds = feeds.into_iter().map(|rx| CexFeed { rx, anchor: None }).collect();
+        self
+    }
+
+    pub fn wait(&self) -> Duration {
+        self.wait
+    }
+
+    /// After a round of quotes: back to the fast cadence if they `changed`
+    /// a price, slower otherwise. CEX moves count from here on.
+    pub fn refreshed(&mut self, changed: bool) {
+        self.wait = if changed { self.fast } else { (self.wait * 2).min(self.slow) };
+        for feed in &mut self.feeds {
+            feed.anchor = mid(feed.rx.borrow_and_update().as_ref());
+        }
+    }
+
+    /// Back to the fast cadence, e.g. on a pool event.
+    pub fn wake(&mut self) {
+        self.wait = self.fast;
+    }
+
+    /// Waits until the next quote is due.
+    pub async fn tick(&mut self) {
+        tokio::select! {
+            _ = sleep(self.wait) => {}
+            _ = cex_moved(&mut self.feeds, self.move_bps) => self.wait = self.fast,
+        }
+    }
+}
+
+fn mid(price: Option<&PriceData>) -> Option<f64> {
+    price.filter(|p| p.bid > 0.0 && p.ask > 0.0).map(|p| (p.bid + p.ask) / 2.0)
+}
+
+// resolves once a feed's mid is more than `move_bps` off its anchor, never
+// while no feed is left
+async fn cex_moved(feeds: &mut Vec<CexFeed>, move_bps: f64) {
+    loop {
+        if feeds.is_empty() {
+            return std::future::pending().await;
+        }
+        let (changed, i, _) = select_all(feeds.iter_mut().map(|feed| Box::pin(feed.rx.changed()))).await;
+        if changed.is_err() {
+            // the feed is gone, it moves nothing anymore
+            feeds.remove(i);
+            continue;
+        }
+        let feed = &mut feeds[i];
+        let Some(now) = mid(feed.rx.borrow_and_update().as_ref()) else {
+            continue;
+        };
+        match feed.anchor {
+            Some(anchor) if ((now - anchor) / anchor).abs() * 10000.0 > move_bps => return,
+            Some(_) => {}
+            // the first price since the last quote is where moves count from
+            None => feed.anchor = Some(now),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod cadence;
+pub mod pool_events;
+pub mod profile;
+pub mod router;
+pub mod univ3_math;
@@ -0,0 +1,95 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::Log,
+};
+use revm::database::{AlloyDB, CacheDB, WrapDatabaseAsync};
+
+use crate::helpers::abi::{Burn, Mint, Swap};
+use crate::helpers::revm::{BlockTracker, POOL_LIQUIDITY, POOL_SLOT0, invalidate_storage};
+
+/// What the pool log watcher forwards.
+#[derive(Debug, Clone)]
+pub enum PoolLog {
+    /// The log stream started (`true`) or stopped: only while it runs can
+    /// pool state be kept current from events.
+    Following(bool),
+    Log(Box<Log>),
+}
+
+/// A pool state change read off one of its logs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolEvent {
+    /// A swap left the pool at this price, in-range liquidity and tick.
+    Swap {
+        pool: Address,
+        sqrt_price_x96: U256,
+        liquidity: u128,
+        tick: i32,
+    },
+    /// A Mint or Burn, which rewrites tick storage the log doesn't carry.
+    Liquidity { pool: Address },
+}
+
+impl PoolEvent {
+    pub fn decode(log: &Log) -> Option<Self> {
+        let pool = log.address();
+        if let Ok(swap) = log.log_decode::<Swap>() {
+            let Swap { sqrtPriceX96, liquidity, tick, .. } = swap.inner.data;
+            return Some(Self::Swap { pool, sqrt_price_x96: U256::from(sqrtPriceX96), liquidity, tick: tick.as_i32() });
+        }
+        if log.log_decode::<Mint>().is_ok() || log.log_decode::<Burn>().is_ok() {
+            return Some(Self::Liquidity { pool });
+        }
+        None
+    }
+
+    pub fn pool(&self) -> Address {
+        match self {
+            Self::Swap { pool, .. } | Self::Liquidity { pool } => *pool,
+        }
+    }
+}
+
+// slot0 packs sqrtPriceX96 (bits 0-159) and tick (160-183) below the
+// oracle indices, fee protocol and reentrancy lock
+const TICK_SHIFT: usize = 160;
+const PRICE_AND_TICK_BITS: usize = 184;
+
+/// Writes `event` into the cached pool state, sparing the storage reads a
+/// quote would otherwise make. Returns whether it could: a Swap needs the
+/// rest of slot0 cached, anything else drops the pool's storage and puts it
+/// on `tracker.recheck`, to be read afresh now and again at the next block.
+pub fn apply_pool_event<P: Provider + Clone>(
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    tracker: &mut BlockTracker,
+    event: &PoolEvent,
+) -> bool {
+    let pool = event.pool();
+    let cached_slot0 = cache_db
+        .cache
+        .accounts
+        .get(&pool)
+        .and_then(|account| account.storage.get(&U256::from(POOL_SLOT0)))
+        .copied();
+
+    match (event, cached_slot0) {
+        (PoolEvent::Swap { sqrt_price_x96, liquidity, tick, .. }, Some(slot0)) => {
+            let kept = slot0 >> PRICE_AND_TICK_BITS << PRICE_AND_TICK_BITS;
+            let tick = U256::from(*tick as u32 & 0xff_ffff) << TICK_SHIFT;
+            let slot0 = kept | tick | sqrt_price_x96;
+            // the account is cached, both inserts succeed
+            let _ = cache_db.insert_account_storage(pool, U256::from(POOL_SLOT0), slot0);
+            let _ = cache_db.insert_account_storage(pool, U256::from(POOL_LIQUIDITY), U256::from(*liquidity));
+            true
+        }
+        _ => {
+            invalidate_storage(cache_db, &[pool]);
+            if !tracker.recheck.contains(&pool) {
+                tracker.recheck.push(pool);
+            }
+            false
+        }
+    }
+}
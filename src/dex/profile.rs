@@ -0,0 +1,139 @@
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result, bail};
+
+use crate::helpers::abi::{
+    IQuoterV1, IUniswapV2Router, exactInputSingleCall, exactOutputSingleCall, quoteExactInputSingleCall,
+    quoteExactOutputSingleCall,
+};
+use crate::settings::{DexKind, QuoterKind, Settings};
+
+const PUSH1: u8 = 0x60;
+const PUSH3: u8 = 0x62;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+
+/// Functions the bot calls on a contract of `kind`, by name and selector.
+pub fn expected_selectors(kind: QuoterKind) -> Vec<(&'static str, [u8; 4])> {
+    match kind {
+        QuoterKind::V2 => vec![
+            ("quoteExactInputSingle", quoteExactInputSingleCall::SELECTOR),
+            ("quoteExactOutputSingle", quoteExactOutputSingleCall::SELECTOR),
+        ],
+        QuoterKind::V1 => vec![
+            ("quoteExactInputSingle", IQuoterV1::quoteExactInputSingleCall::SELECTOR),
+            ("quoteExactOutputSingle", IQuoterV1::quoteExactOutputSingleCall::SELECTOR),
+        ],
+        QuoterKind::Router => vec![
+            ("exactInputSingle", exactInputSingleCall::SELECTOR),
+            ("exactOutputSingle", exactOutputSingleCall::SELECTOR),
+        ],
+    }
+}
+
+/// Selectors pushed by `code`, which is how solidity dispatchers compare
+/// calldata against the functions of a contract. Push data is skipped, so
+/// constants that happen to contain a selector don't count.
+pub fn pushed_selectors(code: &[u8]) -> Vec<[u8; 4]> {
+    let mut selectors = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        if !(PUSH1..=PUSH32).contains(&op) {
+            pc += 1;
+            continue;
+        }
+        let len = (op - PUSH1 + 1) as usize;
+        let data = &code[(pc + 1).min(code.len())..(pc + 1 + len).min(code.len())];
+        // the optimizer drops the leading zero byte of a selector
+        match op {
+            PUSH4 if data.len() == 4 => selectors.push(data.try_into().unwrap()),
+            PUSH3 if data.len() == 3 => selectors.push([0, data[0], data[1], data[2]]),
+            _ => {}
+        }
+        pc += 1 + len;
+    }
+    selectors
+}
+
+/// Functions a UniswapV2-style router is quoted and swapped through.
+pub fn v2_router_selectors() -> Vec<(&'static str, [u8; 4])> {
+    vec![
+        ("getAmountsOut", IUniswapV2Router::getAmountsOutCall::SELECTOR),
+        ("getAmountsIn", IUniswapV2Router::getAmountsInCall::SELECTOR),
+        ("swapExactTokensForTokens", IUniswapV2Router::swapExactTokensForTokensCall::SELECTOR),
+        ("swapTokensForExactTokens", IUniswapV2Router::swapTokensForExactTokensCall::SELECTOR),
+    ]
+}
+
+/// Names of the `functions` that `code` does not dispatch.
+pub fn missing_functions(code: &[u8], functions: &[(&'static str, [u8; 4])]) -> Vec<&'static str> {
+    let pushed = pushed_selectors(code);
+    functions
+        .iter()
+        .filter(|(_, selector)| !pushed.contains(selector))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// A contract the bot calls into, under its config key, with the
+/// functions it calls on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub key: String,
+    pub address: Address,
+    pub functions: Vec<(&'static str, [u8; 4])>,
+}
+
+/// The contracts `dex_kind` calls into: quoter, router and the quoters of
+/// `extra_pools` for v3, the router for v2. Aggregator routers take
+/// whatever calldata the API builds, so there is nothing to compare.
+pub fn interfaces(cfg: &Settings) -> Vec<Interface> {
+    let interface = |key: String, address, functions| Interface { key, address, functions };
+    let mut contracts = match cfg.dex_kind {
+        DexKind::V3 => vec![
+            interface("quoter_v2_addr".to_string(), cfg.quoter_v2_addr, expected_selectors(cfg.quoter_kind)),
+            interface("router_addr".to_string(), cfg.router_addr, expected_selectors(QuoterKind::Router)),
+        ],
+        DexKind::V2 => vec![interface("router_addr".to_string(), cfg.router_addr, v2_router_selectors())],
+        DexKind::Aggregator => return Vec::new(),
+    };
+    // split routing only spans v3 pools
+    for (i, pool) in cfg.extra_pools.iter().enumerate().filter(|_| cfg.dex_kind == DexKind::V3) {
+        if let Some(quoter) = pool.quoter_addr {
+            contracts.push(interface(format!("extra_pools[{}] quoter", i), quoter, expected_selectors(pool.quoter_kind)));
+        }
+    }
+    contracts.dedup_by(|a, b| a.address == b.address && a.functions == b.functions);
+    contracts
+}
+
+/// Fails unless the bytecode at `address` dispatches every one of
+/// `functions`, e.g. a quoter v1 configured as v2 or a profile pointing at
+/// the wrong contract.
+pub async fn verify_interface(
+    provider: &impl Provider,
+    address: Address,
+    functions: &[(&'static str, [u8; 4])],
+) -> Result<()> {
+    let code = provider.get_code_at(address).await.with_context(|| format!("failed to read code at {}", address))?;
+    if code.is_empty() {
+        bail!("no contract at {}", address);
+    }
+    let missing = missing_functions(&code, functions);
+    if !missing.is_empty() {
+        bail!("{} does not dispatch {}", address, missing.join(", "));
+    }
+    Ok(())
+}
+
+/// Runs `verify_interface` over every contract of `interfaces`.
+pub async fn verify_interfaces(cfg: &Settings, provider: &impl Provider) -> Result<()> {
+    for interface in interfaces(cfg) {
+        verify_interface(provider, interface.address, &interface.functions)
+            .await
+            .with_context(|| format!("{} failed verification", interface.key))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,353 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use log::{error, info};
+use serde_json::Value;
+use tokio::sync::{mpsc, watch};
+
+use crate::adapters::hyperswap::PoolCommand;
+use crate::arbitrage::{ArbDirection, PriceData};
+use crate::dex::cadence::RefreshCadence;
+use crate::execution::dex::{DexSwap, SwapMode};
+use crate::execution::split::PoolRoute;
+use crate::helpers::abi::{
+    IUniswapV2Router, call_tx, decode_quote_output_response_for, decode_quote_response_for,
+    quote_calldata_for, quote_exact_output_calldata_for,
+};
+use crate::normalize::Normalizer;
+use crate::settings::{DexKind, Secret, Settings};
+
+/// One flavor of DEX: how to quote the pair on it, how to swap through it
+/// and what it charges. Quoting and execution go through this, so neither
+/// cares which flavor serves a pair.
+#[async_trait]
+pub trait DexRouter: Send + Sync {
+    fn kind(&self) -> DexKind;
+
+    /// Contract swaps are sent to, and input tokens approved for.
+    fn router_addr(&self) -> Address;
+
+    /// LP fee of a swap, in bps of its input.
+    fn fee_bps(&self) -> f64;
+
+    /// Quote-token amount for `base_amount` base: received when selling it
+    /// on the DEX (`BuyCex`), paid when buying it (`BuyDex`).
+    async fn quote(&self, provider: &dyn Provider, direction: &ArbDirection, base_amount: U256) -> Result<u128>;
+
+    /// Calldata making `swap` through `router_addr`, paying out to
+    /// `recipient`.
+    async fn swap_calldata(&self, swap: &DexSwap, recipient: Address, deadline: U256) -> Result<Bytes>;
+}
+
+/// The router of `dex_kind`, for the configured pair.
+pub fn router_for(cfg: &Settings) -> Result<Arc<dyn DexRouter>> {
+    Ok(match cfg.dex_kind {
+        DexKind::V3 => Arc::new(UniswapV3::new(cfg, PoolRoute::all(cfg).remove(0))),
+        DexKind::V2 => Arc::new(UniswapV2::new(cfg)),
+        DexKind::Aggregator => Arc::new(Aggregator::new(cfg)?),
+    })
+}
+
+/// Bid and ask per base unit for trading `base_amount` through `router`.
+pub async fn quote_book(
+    cfg: &Settings,
+    router: &dyn DexRouter,
+    provider: &dyn Provider,
+    base_amount: U256,
+) -> Result<PriceData> {
+    let (bid, ask) = tokio::try_join!(
+        router.quote(provider, &ArbDirection::BuyCex, base_amount),
+        router.quote(provider, &ArbDirection::BuyDex, base_amount),
+    )?;
+    Ok(Normalizer::new(cfg).dex_book(bid, ask, base_amount))
+}
+
+/// Quotes one base unit through `router` as often as `RefreshCadence`
+/// says, for DEX kinds the REVM pool listener can't simulate. Pairs added at runtime are refused,
+/// they need a v3 pool.
+pub async fn run_quote_listener(
+    cfg: Settings,
+    router: Arc<dyn DexRouter>,
+    provider: Arc<dyn Provider>,
+    tx: watch::Sender<Option<PriceData>>,
+    cex_feeds: Vec<watch::Receiver<Option<PriceData>>>,
+    mut pool_rx: mpsc::UnboundedReceiver<PoolCommand>,
+) -> Result<()> {
+    let volume = Normalizer::new(&cfg).one_base();
+    let mut idle = false;
+    let mut cadence = RefreshCadence::from_config(&cfg).with_cex_feeds(cex_feeds);
+
+    loop {
+        if !idle {
+            match quote_book(&cfg, &*router, &*provider, volume).await {
+                Ok(price) => {
+                    cadence.refreshed(tx.borrow().as_ref() != Some(&price));
+                    info!("⚠️ {:?} DEX {}: bid ${:.2} ask ${:.2}", router.kind(), cfg.denomination(), price.bid, price.ask);
+                    if let Err(e) = tx.send(Some(price)) {
+                        error!("failed to send DEX price update: {}", e);
+                    }
+                }
+                Err(e) => {
+                    cadence.refreshed(false);
+                    error!("DEX price fetch error: {:#}", e)
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = cadence.tick() => {}
+            Some(command) = pool_rx.recv() => match command {
+                // dropping the sender tells the engines the pair is gone
+                PoolCommand::Add { name, .. } => {
+                    error!("cannot quote {}: runtime pairs need dex_kind v3, not {:?}", name, router.kind())
+                }
+                PoolCommand::Remove { .. } => {}
+                PoolCommand::Idle(on) => {
+                    idle = on;
+                    info!("DEX quoting {}", if on { "idle" } else { "resumed" });
+                }
+            },
+        }
+    }
+}
+
+/// A UniswapV3-style pool, quoted by its quoter and swapped through the
+/// swap router.
+pub struct UniswapV3 {
+    route: PoolRoute,
+    router: Address,
+    base: Address,
+    quote: Address,
+    caller: Address,
+}
+
+impl UniswapV3 {
+    pub fn new(cfg: &Settings, route: PoolRoute) -> Self {
+        Self { router: route.router_addr, route, base: cfg.weth_addr, quote: cfg.usdt_addr, caller: cfg.self_addr }
+    }
+}
+
+#[async_trait]
+impl DexRouter for UniswapV3 {
+    fn kind(&self) -> DexKind {
+        DexKind::V3
+    }
+
+    fn router_addr(&self) -> Address {
+        self.router
+    }
+
+    fn fee_bps(&self) -> f64 {
+        // fee tiers are in hundredths of a bp
+        self.route.fee_tier as f64 / 100.0
+    }
+
+    async fn quote(&self, provider: &dyn Provider, direction: &ArbDirection, base_amount: U256) -> Result<u128> {
+        let (kind, fee) = (self.route.quoter_kind, self.route.fee_tier);
+        let calldata = match direction {
+            ArbDirection::BuyCex => quote_calldata_for(kind, self.base, self.quote, base_amount, fee, self.caller),
+            ArbDirection::BuyDex => quote_exact_output_calldata_for(kind, self.quote, self.base, base_amount, fee, self.caller),
+        };
+        let response = provider.call(call_tx(self.route.quoter_addr, self.caller, calldata)).await?;
+        match direction {
+            ArbDirection::BuyCex => decode_quote_response_for(kind, response),
+            ArbDirection::BuyDex => decode_quote_output_response_for(kind, response),
+        }
+    }
+
+    async fn swap_calldata(&self, swap: &DexSwap, recipient: Address, deadline: U256) -> Result<Bytes> {
+        Ok(swap.calldata(recipient, deadline))
+    }
+}
+
+/// A UniswapV2-style constant product pair, quoted and swapped through the
+/// router along the direct path.
+pub struct UniswapV2 {
+    router: Address,
+    base: Address,
+    quote: Address,
+    caller: Address,
+    fee_bps: f64,
+}
+
+impl UniswapV2 {
+    pub fn new(cfg: &Settings) -> Self {
+        Self {
+            router: cfg.router_addr,
+            base: cfg.weth_addr,
+            quote: cfg.usdt_addr,
+            caller: cfg.self_addr,
+            // 3000 for the usual 0.3%, forks charging 0.25% configure 2500
+            fee_bps: cfg.dex_fee_tier as f64 / 100.0,
+        }
+    }
+}
+
+#[async_trait]
+impl DexRouter for UniswapV2 {
+    fn kind(&self) -> DexKind {
+        DexKind::V2
+    }
+
+    fn router_addr(&self) -> Address {
+        self.router
+    }
+
+    fn fee_bps(&self) -> f64 {
+        self.fee_bps
+    }
+
+    async fn quote(&self, provider: &dyn Provider, direction: &ArbDirection, base_amount: U256) -> Result<u128> {
+        let amount = match direction {
+            ArbDirection::BuyCex => {
+                let call = IUniswapV2Router::getAmountsOutCall { amountIn: base_amount, path: vec![self.base, self.quote] };
+                let response = provider.call(call_tx(self.router, self.caller, call.abi_encode().into())).await?;
+                IUniswapV2Router::getAmountsOutCall::abi_decode_returns(&response)?.last().copied()
+            }
+            ArbDirection::BuyDex => {
+                let call = IUniswapV2Router::getAmountsInCall { amountOut: base_amount, path: vec![self.quote, self.base] };
+                let response = provider.call(call_tx(self.router, self.caller, call.abi_encode().into())).await?;
+                IUniswapV2Router::getAmountsInCall::abi_decode_returns(&response)?.first().copied()
+            }
+        };
+        Ok(amount.ok_or_else(|| anyhow!("router returned no amounts"))?.try_into()?)
+    }
+
+    async fn swap_calldata(&self, swap: &DexSwap, recipient: Address, deadline: U256) -> Result<Bytes> {
+        let path = vec![swap.token_in, swap.token_out];
+        let calldata = match swap.mode {
+            SwapMode::ExactInput => IUniswapV2Router::swapExactTokensForTokensCall {
+                amountIn: swap.amount,
+                amountOutMin: swap.limit,
+                path,
+                to: recipient,
+                deadline,
+            }
+            .abi_encode(),
+            SwapMode::ExactOutput => IUniswapV2Router::swapTokensForExactTokensCall {
+                amountOut: swap.amount,
+                amountInMax: swap.limit,
+                path,
+                to: recipient,
+                deadline,
+            }
+            .abi_encode(),
+        };
+        Ok(calldata.into())
+    }
+}
+
+/// A DEX aggregator speaking the 0x swap API: `/swap/v1/quote` answers
+/// with the amounts of the best route and the transaction taking it,
+/// which must go to `router_addr`, the contract the tokens are approved
+/// for.
+pub struct Aggregator {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<Secret>,
+    router: Address,
+    base: Address,
+    quote: Address,
+    taker: Address,
+    slippage_bps: u32,
+    fee_bps: f64,
+}
+
+impl Aggregator {
+    pub fn new(cfg: &Settings) -> Result<Self> {
+        let url = cfg.aggregator_url.clone().context("dex_kind aggregator needs aggregator_url")?;
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(Duration::from_secs(5)).build()?,
+            url: url.trim_end_matches('/').to_string(),
+            api_key: cfg.aggregator_api_key.clone(),
+            router: cfg.router_addr,
+            base: cfg.weth_addr,
+            quote: cfg.usdt_addr,
+            taker: cfg.self_addr,
+            slippage_bps: cfg.dex_slippage_bps,
+            // routes vary, the configured tier stands in for their fees
+            fee_bps: cfg.dex_fee_tier as f64 / 100.0,
+        })
+    }
+
+    // `side` is "sellAmount" or "buyAmount", whichever `amount` fixes
+    async fn fetch(&self, sell: Address, buy: Address, side: &str, amount: U256, taker: Address) -> Result<Value> {
+        let params = [
+            ("sellToken", sell.to_string()),
+            ("buyToken", buy.to_string()),
+            (side, amount.to_string()),
+            ("takerAddress", taker.to_string()),
+            ("slippagePercentage", (self.slippage_bps as f64 / 10_000.0).to_string()),
+        ];
+        let mut request = self.http.get(format!("{}/swap/v1/quote", self.url)).query(&params);
+        if let Some(key) = &self.api_key {
+            request = request.header("0x-api-key", key.expose());
+        }
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+}
+
+fn amount(body: &Value, key: &str) -> Result<U256> {
+    let raw = body[key].as_str().ok_or_else(|| anyhow!("aggregator quote without {}: {}", key, body))?;
+    raw.parse().with_context(|| format!("aggregator {} {:?} is not an amount", key, raw))
+}
+
+#[async_trait]
+impl DexRouter for Aggregator {
+    fn kind(&self) -> DexKind {
+        DexKind::Aggregator
+    }
+
+    fn router_addr(&self) -> Address {
+        self.router
+    }
+
+    fn fee_bps(&self) -> f64 {
+        self.fee_bps
+    }
+
+    async fn quote(&self, _: &dyn Provider, direction: &ArbDirection, base_amount: U256) -> Result<u128> {
+        let amount = match direction {
+            ArbDirection::BuyCex => {
+                amount(&self.fetch(self.base, self.quote, "sellAmount", base_amount, self.taker).await?, "buyAmount")?
+            }
+            ArbDirection::BuyDex => {
+                amount(&self.fetch(self.quote, self.base, "buyAmount", base_amount, self.taker).await?, "sellAmount")?
+            }
+        };
+        Ok(amount.try_into()?)
+    }
+
+    async fn swap_calldata(&self, swap: &DexSwap, recipient: Address, _deadline: U256) -> Result<Bytes> {
+        let body = match swap.mode {
+            SwapMode::ExactInput => {
+                let body = self.fetch(swap.token_in, swap.token_out, "sellAmount", swap.amount, recipient).await?;
+                let out = amount(&body, "buyAmount")?;
+                if out < swap.limit {
+                    bail!("aggregator offers {} out, below the limit of {}", out, swap.limit);
+                }
+                body
+            }
+            SwapMode::ExactOutput => {
+                let body = self.fetch(swap.token_in, swap.token_out, "buyAmount", swap.amount, recipient).await?;
+                let paid = amount(&body, "sellAmount")?;
+                if paid > swap.limit {
+                    bail!("aggregator asks {} in, above the limit of {}", paid, swap.limit);
+                }
+                body
+            }
+        };
+
+        let to: Address = body["to"].as_str().unwrap_or_default().parse().context("aggregator quote without a target")?;
+        if to != self.router {
+            // the approvals only cover router_addr
+            bail!("aggregator routes through {}, not router_addr {}", to, self.router);
+        }
+        body["data"].as_str().unwrap_or_default().parse().context("aggregator quote without calldata")
+    }
+}
@@ -0,0 +1,411 @@
+use alloy::primitives::{I256, U256, U512, uint};
+use anyhow::{Result, anyhow, bail};
+
+// sqrt prices are Q64.96 fixed point
+const Q96_SHIFT: usize = 96;
+const FEE_PIPS: u64 = 1_000_000;
+
+/// Price-relevant part of a UniswapV3 pool's state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolPrice {
+    pub sqrt_price_x96: U256,
+    pub liquidity: u128,
+}
+
+impl PoolPrice {
+    /// From the raw `slot0` and `liquidity` storage words.
+    pub fn from_storage(slot0: U256, liquidity: U256) -> Self {
+        // slot0 packs sqrtPriceX96 into its low 160 bits
+        let mask = (U256::from(1) << 160usize) - U256::from(1);
+        Self {
+            sqrt_price_x96: slot0 & mask,
+            liquidity: liquidity.saturating_to(),
+        }
+    }
+
+    /// Price of token0 in token1, in raw units.
+    pub fn price(&self) -> f64 {
+        let sqrt = f64::from(self.sqrt_price_x96) / 2f64.powi(96);
+        sqrt * sqrt
+    }
+
+    /// The pool after a swap of `amount` in the given direction, `amount`
+    /// being the input (before the fee) for an exact-input swap and the
+    /// output otherwise. Assumes the swap stays within the current tick
+    /// range, so larger swaps are approximated with the liquidity in range.
+    /// A swap the range cannot fill leaves the price at its bound.
+    pub fn after_swap(&self, zero_for_one: bool, exact_input: bool, amount: U256, fee_pips: u32) -> Self {
+        let sqrt_price_x96 = if exact_input {
+            let amount = amount * U256::from(FEE_PIPS - fee_pips as u64) / U256::from(FEE_PIPS);
+            self.sqrt_price_after_input(zero_for_one, amount)
+        } else {
+            self.sqrt_price_after_output(zero_for_one, amount)
+        };
+        Self { sqrt_price_x96, liquidity: self.liquidity }
+    }
+
+    // getNextSqrtPriceFromInput, without the final rounding direction
+    fn sqrt_price_after_input(&self, zero_for_one: bool, amount: U256) -> U256 {
+        if self.liquidity == 0 || amount.is_zero() {
+            return self.sqrt_price_x96;
+        }
+        let liquidity = U512::from(self.liquidity) << Q96_SHIFT;
+        let sqrt = U512::from(self.sqrt_price_x96);
+        let amount = U512::from(amount);
+
+        if zero_for_one {
+            // token0 in: L * sqrtP / (L + amount * sqrtP)
+            (liquidity * sqrt / (liquidity + amount * sqrt)).saturating_to()
+        } else {
+            // token1 in: sqrtP + amount / L
+            (sqrt + (amount << Q96_SHIFT) / U512::from(self.liquidity)).saturating_to()
+        }
+    }
+
+    // getNextSqrtPriceFromOutput
+    fn sqrt_price_after_output(&self, zero_for_one: bool, amount: U256) -> U256 {
+        if self.liquidity == 0 || amount.is_zero() {
+            return self.sqrt_price_x96;
+        }
+        let liquidity = U512::from(self.liquidity) << Q96_SHIFT;
+        let sqrt = U512::from(self.sqrt_price_x96);
+        let amount = U512::from(amount);
+
+        if zero_for_one {
+            // token1 out: sqrtP - amount / L
+            let delta = (amount << Q96_SHIFT) / U512::from(self.liquidity);
+            sqrt.saturating_sub(delta).saturating_to()
+        } else {
+            // token0 out: L * sqrtP / (L - amount * sqrtP)
+            let used = amount * sqrt;
+            if used >= liquidity {
+                return U256::MAX >> 96usize;
+            }
+            (liquidity * sqrt / (liquidity - used)).saturating_to()
+        }
+    }
+}
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+pub const MIN_SQRT_RATIO: U256 = uint!(4295128739_U256);
+pub const MAX_SQRT_RATIO: U256 = uint!(1461446703485210103287273052203988822378723970342_U256);
+
+// TickMath: sqrt(1.0001^(2^i)) as Q128.128, for each bit i of |tick|
+const TICK_RATIOS: [U256; 19] = [
+    uint!(0xfff97272373d413259a46990580e213a_U256),
+    uint!(0xfff2e50f5f656932ef12357cf3c7fdcc_U256),
+    uint!(0xffe5caca7e10e4e61c3624eaa0941cd0_U256),
+    uint!(0xffcb9843d60f6159c9db58835c926644_U256),
+    uint!(0xff973b41fa98c081472e6896dfb254c0_U256),
+    uint!(0xff2ea16466c96a3843ec78b326b52861_U256),
+    uint!(0xfe5dee046a99a2a811c461f1969c3053_U256),
+    uint!(0xfcbe86c7900a88aedcffc83b479aa3a4_U256),
+    uint!(0xf987a7253ac413176f2b074cf7815e54_U256),
+    uint!(0xf3392b0822b70005940c7a398e4b70f3_U256),
+    uint!(0xe7159475a2c29b7443b29c7fa6e889d9_U256),
+    uint!(0xd097f3bdfd2022b8845ad8f792aa5825_U256),
+    uint!(0xa9f746462d870fdf8a65dc1f90e061e5_U256),
+    uint!(0x70d869a156d2a1b890bb3df62baf32f7_U256),
+    uint!(0x31be135f97d08fd981231505542fcfa6_U256),
+    uint!(0x9aa508b5b7a84e1c677de54f3e99bc9_U256),
+    uint!(0x5d6af8dedb81196699c329225ee604_U256),
+    uint!(0x2216e584f5fa1ea926041bedfe98_U256),
+    uint!(0x48a170391f7dc42444e8fa2_U256),
+];
+
+/// TickMath.getSqrtRatioAtTick: sqrt(1.0001^tick) as Q64.96, rounded up.
+pub fn sqrt_ratio_at_tick(tick: i32) -> U256 {
+    let abs = tick.unsigned_abs().min(MAX_TICK as u32);
+    let mut ratio = if abs & 1 != 0 {
+        uint!(0xfffcb933bd6fad37aa2d162d1a594001_U256)
+    } else {
+        U256::from(1) << 128usize
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate() {
+        if abs & (2 << bit) != 0 {
+            ratio = (ratio * factor) >> 128usize;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    let rounded_up = !(ratio % (U256::from(1) << 32usize)).is_zero();
+    (ratio >> 32usize) + U256::from(rounded_up as u8)
+}
+
+fn mul_div(a: U256, b: U256, denominator: U256) -> U256 {
+    (U512::from(a) * U512::from(b) / U512::from(denominator)).saturating_to()
+}
+
+fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> U256 {
+    let product = U512::from(a) * U512::from(b);
+    let denominator = U512::from(denominator);
+    let rounded_up = !(product % denominator).is_zero();
+    (product / denominator + U512::from(rounded_up as u8)).saturating_to()
+}
+
+fn div_rounding_up(a: U256, b: U256) -> U256 {
+    a / b + U256::from(!(a % b).is_zero() as u8)
+}
+
+fn q96() -> U256 {
+    U256::from(1) << Q96_SHIFT
+}
+
+/// SqrtPriceMath.getAmount0Delta between two sqrt prices.
+pub fn amount0_delta(a: U256, b: U256, liquidity: u128, round_up: bool) -> U256 {
+    let (a, b) = if a > b { (b, a) } else { (a, b) };
+    let numerator1 = U256::from(liquidity) << Q96_SHIFT;
+    let numerator2 = b - a;
+    if round_up {
+        div_rounding_up(mul_div_rounding_up(numerator1, numerator2, b), a)
+    } else {
+        mul_div(numerator1, numerator2, b) / a
+    }
+}
+
+/// SqrtPriceMath.getAmount1Delta between two sqrt prices.
+pub fn amount1_delta(a: U256, b: U256, liquidity: u128, round_up: bool) -> U256 {
+    let (a, b) = if a > b { (b, a) } else { (a, b) };
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), b - a, q96())
+    } else {
+        mul_div(U256::from(liquidity), b - a, q96())
+    }
+}
+
+fn next_sqrt_price_from_amount0(sqrt_price: U256, liquidity: u128, amount: U256, add: bool) -> Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price);
+    }
+    let numerator1 = U256::from(liquidity) << Q96_SHIFT;
+    let product = amount.checked_mul(sqrt_price);
+    if add {
+        if let Some(denominator) = product.and_then(|p| numerator1.checked_add(p)) {
+            return Ok(mul_div_rounding_up(numerator1, sqrt_price, denominator));
+        }
+        Ok(div_rounding_up(numerator1, numerator1 / sqrt_price + amount))
+    } else {
+        match product {
+            Some(product) if numerator1 > product => Ok(mul_div_rounding_up(numerator1, sqrt_price, numerator1 - product)),
+            _ => bail!("output exceeds the token0 reserves in range"),
+        }
+    }
+}
+
+fn next_sqrt_price_from_amount1(sqrt_price: U256, liquidity: u128, amount: U256, add: bool) -> Result<U256> {
+    let liquidity = U256::from(liquidity);
+    let fits_160 = amount < U256::from(1) << 160usize;
+    if add {
+        let quotient = if fits_160 { (amount << Q96_SHIFT) / liquidity } else { mul_div(amount, q96(), liquidity) };
+        Ok(sqrt_price + quotient)
+    } else {
+        let quotient = if fits_160 {
+            div_rounding_up(amount << Q96_SHIFT, liquidity)
+        } else {
+            mul_div_rounding_up(amount, q96(), liquidity)
+        };
+        if sqrt_price <= quotient {
+            bail!("output exceeds the token1 reserves in range");
+        }
+        Ok(sqrt_price - quotient)
+    }
+}
+
+/// One SwapMath.computeSwapStep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapStep {
+    pub sqrt_price_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// SwapMath.computeSwapStep: swaps `remaining` (positive exact input,
+/// negative exact output) from `current` towards `target` within one
+/// range of constant `liquidity`.
+pub fn compute_swap_step(current: U256, target: U256, liquidity: u128, remaining: I256, fee_pips: u32) -> Result<SwapStep> {
+    let zero_for_one = current >= target;
+    let exact_input = !remaining.is_negative();
+    let remaining_abs = remaining.unsigned_abs();
+    let fee = U256::from(fee_pips);
+    let pips = U256::from(FEE_PIPS);
+
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+    let next = if exact_input {
+        let less_fee = mul_div(remaining_abs, pips - fee, pips);
+        amount_in = if zero_for_one {
+            amount0_delta(target, current, liquidity, true)
+        } else {
+            amount1_delta(current, target, liquidity, true)
+        };
+        if less_fee >= amount_in {
+            target
+        } else if zero_for_one {
+            next_sqrt_price_from_amount0(current, liquidity, less_fee, true)?
+        } else {
+            next_sqrt_price_from_amount1(current, liquidity, less_fee, true)?
+        }
+    } else {
+        amount_out = if zero_for_one {
+            amount1_delta(target, current, liquidity, false)
+        } else {
+            amount0_delta(current, target, liquidity, false)
+        };
+        if remaining_abs >= amount_out {
+            target
+        } else if zero_for_one {
+            next_sqrt_price_from_amount1(current, liquidity, remaining_abs, false)?
+        } else {
+            next_sqrt_price_from_amount0(current, liquidity, remaining_abs, false)?
+        }
+    };
+
+    let max = next == target;
+    if zero_for_one {
+        if !max || !exact_input {
+            amount_in = amount0_delta(next, current, liquidity, true);
+        }
+        if !max || exact_input {
+            amount_out = amount1_delta(next, current, liquidity, false);
+        }
+    } else {
+        if !max || !exact_input {
+            amount_in = amount1_delta(current, next, liquidity, true);
+        }
+        if !max || exact_input {
+            amount_out = amount0_delta(current, next, liquidity, false);
+        }
+    }
+    if !exact_input && amount_out > remaining_abs {
+        amount_out = remaining_abs;
+    }
+
+    let fee_amount = if exact_input && next != target {
+        remaining_abs - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, fee, pips - fee)
+    };
+
+    Ok(SwapStep { sqrt_price_x96: next, amount_in, amount_out, fee_amount })
+}
+
+/// Tick storage a swap walks through, e.g. a pool's as cached by REVM.
+pub trait TickData {
+    /// The `tickBitmap` word at `word`.
+    fn bitmap_word(&mut self, word: i16) -> Result<U256>;
+    /// `liquidityNet` of the initialized `tick`.
+    fn liquidity_net(&mut self, tick: i32) -> Result<i128>;
+}
+
+/// TickBitmap.nextInitializedTickWithinOneWord.
+pub fn next_initialized_tick(ticks: &mut impl TickData, tick: i32, spacing: i32, lte: bool) -> Result<(i32, bool)> {
+    let compressed = tick.div_euclid(spacing);
+    if lte {
+        let (word, bit) = ((compressed >> 8) as i16, (compressed & 0xff) as usize);
+        let mask = (U256::from(1) << bit) - U256::from(1) + (U256::from(1) << bit);
+        let masked = ticks.bitmap_word(word)? & mask;
+        let next = if masked.is_zero() {
+            compressed - bit as i32
+        } else {
+            compressed - (bit as i32 - (255 - masked.leading_zeros() as i32))
+        };
+        Ok((next * spacing, !masked.is_zero()))
+    } else {
+        let compressed = compressed + 1;
+        let (word, bit) = ((compressed >> 8) as i16, (compressed & 0xff) as usize);
+        let mask = !((U256::from(1) << bit) - U256::from(1));
+        let masked = ticks.bitmap_word(word)? & mask;
+        let next = if masked.is_zero() {
+            compressed + (255 - bit as i32)
+        } else {
+            compressed + (masked.trailing_zeros() as i32 - bit as i32)
+        };
+        Ok((next * spacing, !masked.is_zero()))
+    }
+}
+
+/// A pool's state as the swap loop reads it from slot0 and liquidity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolState {
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+impl PoolState {
+    pub fn from_storage(slot0: U256, liquidity: U256) -> Self {
+        let tick = ((slot0 >> 160usize).wrapping_to::<u32>() & 0xff_ffff) as i32;
+        Self {
+            sqrt_price_x96: PoolPrice::from_storage(slot0, liquidity).sqrt_price_x96,
+            // sign-extend int24
+            tick: (tick << 8) >> 8,
+            liquidity: liquidity.saturating_to(),
+        }
+    }
+}
+
+/// What a swap took in and paid out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapAmounts {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub sqrt_price_x96: U256,
+}
+
+/// UniswapV3Pool.swap without a price limit, as the quoter runs it: exact
+/// input when `amount` is positive, exact output when negative. Crosses
+/// initialized ticks as it goes, so the amounts match the quoter's to the
+/// wei. An exact output the pool can't fill fails, like QuoterV2.
+pub fn swap(
+    pool: PoolState,
+    ticks: &mut impl TickData,
+    spacing: i32,
+    fee_pips: u32,
+    zero_for_one: bool,
+    amount: I256,
+) -> Result<SwapAmounts> {
+    let exact_input = !amount.is_negative();
+    let limit = if zero_for_one { MIN_SQRT_RATIO + U256::from(1) } else { MAX_SQRT_RATIO - U256::from(1) };
+
+    let mut remaining = amount;
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+    let PoolState { mut sqrt_price_x96, mut tick, mut liquidity } = pool;
+
+    while !remaining.is_zero() && sqrt_price_x96 != limit {
+        let (next_tick, initialized) = next_initialized_tick(ticks, tick, spacing.max(1), zero_for_one)?;
+        let next_tick = next_tick.clamp(MIN_TICK, MAX_TICK);
+        let next_price = sqrt_ratio_at_tick(next_tick);
+        let target = if zero_for_one { next_price.max(limit) } else { next_price.min(limit) };
+
+        let step = compute_swap_step(sqrt_price_x96, target, liquidity, remaining, fee_pips)?;
+        sqrt_price_x96 = step.sqrt_price_x96;
+        let spent = step.amount_in + step.fee_amount;
+        amount_in += spent;
+        amount_out += step.amount_out;
+        remaining = if exact_input {
+            remaining - I256::from_raw(spent)
+        } else {
+            remaining + I256::from_raw(step.amount_out)
+        };
+
+        if sqrt_price_x96 == next_price {
+            if initialized {
+                let net = ticks.liquidity_net(next_tick)?;
+                let net = if zero_for_one { -net } else { net };
+                liquidity = liquidity.checked_add_signed(net).ok_or_else(|| anyhow!("liquidity underflow at tick {}", next_tick))?;
+            }
+            tick = if zero_for_one { next_tick - 1 } else { next_tick };
+        }
+        // stopping short of the next tick spent what was left, the tick
+        // isn't read again
+    }
+
+    if !exact_input && !remaining.is_zero() {
+        bail!("pool can't fill the exact output, {} short", remaining.unsigned_abs());
+    }
+    Ok(SwapAmounts { amount_in, amount_out, sqrt_price_x96 })
+}
@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::execution::bybit::BybitClient;
+use crate::execution::cex::{CexFill, CexOrders, OrderPolicy};
+use crate::execution::client_order::ClientOrderId;
+use crate::execution::gateio::GateioClient;
+use crate::settings::{CexAccount, Settings};
+use crate::state::StateStore;
+use crate::throttle::RateLimit;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Free base and quote on an account, in whole tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct AccountBalance {
+    pub base: f64,
+    pub quote: f64,
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    orders: VecDeque<Instant>,
+    // unknown until set, then moved by every fill
+    balance: Option<AccountBalance>,
+}
+
+/// One API-key account on a CEX and the pairs and books it trades.
+pub struct Account {
+    pub name: String,
+    pub orders: Arc<dyn CexOrders>,
+    pairs: Vec<String>,
+    books: Vec<String>,
+    limit: RateLimit,
+    usage: Mutex<Usage>,
+    // where the known balance is saved across restarts, see `with_state`
+    state: Option<StateStore>,
+}
+
+impl Account {
+    /// An account taking any pair and book without a rate limit, e.g. the
+    /// venue's own keys.
+    pub fn new(name: &str, orders: Arc<dyn CexOrders>) -> Self {
+        Self {
+            name: name.to_string(),
+            orders,
+            pairs: Vec::new(),
+            books: Vec::new(),
+            limit: RateLimit::default(),
+            usage: Mutex::default(),
+            state: None,
+        }
+    }
+
+    /// Keeps the account to `pairs`, any when empty.
+    pub fn with_pairs(mut self, pairs: Vec<String>) -> Self {
+        self.pairs = pairs;
+        self
+    }
+
+    /// Keeps the account to strategy `books`, any when empty.
+    pub fn with_books(mut self, books: Vec<String>) -> Self {
+        self.books = books;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: RateLimit) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Saves the known balance to `state` whenever it moves, starting from
+    /// the one saved there for this account.
+    pub fn with_state(mut self, state: StateStore) -> Self {
+        if let Some(balance) = state.snapshot().accounts.get(&self.name)
+            && let Ok(usage) = self.usage.get_mut()
+        {
+            usage.balance = Some(*balance);
+        }
+        self.state = Some(state);
+        self
+    }
+
+    pub fn venue(&self) -> &'static str {
+        self.orders.venue()
+    }
+
+    pub fn balance(&self) -> Option<AccountBalance> {
+        self.usage.lock().ok().and_then(|u| u.balance)
+    }
+
+    pub fn set_balance(&self, balance: AccountBalance) {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage.balance = Some(balance);
+        }
+        self.save(balance);
+    }
+
+    /// Places a market order on this account, see `CexOrders::market_order`,
+    /// and moves the known balance by its fill.
+    pub async fn market_order(&self, is_buy: bool, base: f64, price: f64, id: ClientOrderId, policy: &OrderPolicy) -> Result<CexFill> {
+        let fill = self.orders.market_order(is_buy, base, price, id, policy).await?;
+        self.record_fill(is_buy, &fill);
+        Ok(fill)
+    }
+
+    /// Moves the known balance by what a market order filled.
+    pub fn record_fill(&self, is_buy: bool, fill: &CexFill) {
+        let Ok(mut usage) = self.usage.lock() else {
+            return;
+        };
+        if let Some(balance) = &mut usage.balance {
+            let (base, quote) = if is_buy { (fill.base, -fill.base * fill.price) } else { (-fill.base, fill.base * fill.price) };
+            balance.base += base;
+            balance.quote += quote;
+            let balance = *balance;
+            drop(usage);
+            self.save(balance);
+        }
+    }
+
+    fn save(&self, balance: AccountBalance) {
+        if let Some(state) = &self.state {
+            state.set_account_balance(&self.name, balance);
+        }
+    }
+
+    fn takes(&self, pair: &str, book: &str) -> bool {
+        (self.pairs.is_empty() || self.pairs.iter().any(|p| p == pair))
+            && (self.books.is_empty() || self.books.iter().any(|b| b == book))
+    }
+
+    // counts an order of `qty` at `px` if the rate limit has room and the
+    // balance, when known, covers it
+    fn acquire(&self, is_buy: bool, px: f64, qty: f64, now: Instant) -> Result<()> {
+        let Ok(mut usage) = self.usage.lock() else {
+            bail!("account {} lock poisoned", self.name);
+        };
+        while usage.orders.front().is_some_and(|t| now.duration_since(*t) >= HOUR) {
+            usage.orders.pop_front();
+        }
+        self.limit.check(&usage.orders, now, &format!("account {}", self.name))?;
+        match usage.balance {
+            Some(b) if is_buy && b.quote < px * qty => {
+                bail!("account {} holds {:.2} quote, {:.2} needed", self.name, b.quote, px * qty)
+            }
+            Some(b) if !is_buy && b.base < qty => bail!("account {} holds {:.4} base, {:.4} needed", self.name, b.base, qty),
+            _ => {}
+        }
+        usage.orders.push_back(now);
+        Ok(())
+    }
+}
+
+/// The account of a `cex_accounts` entry, on its own keys.
+pub fn cex_account(cfg: &Settings, account: &CexAccount) -> Result<Account> {
+    let orders: Arc<dyn CexOrders> = match account.venue.as_str() {
+        "bybit" => Arc::new(BybitClient::with_keys(cfg, &account.api_key, &account.api_secret)),
+        "gateio" => Arc::new(GateioClient::with_keys(cfg, &account.api_key, &account.api_secret)),
+        venue => bail!("account {}: {} accounts are not supported", account.name, venue),
+    };
+    Ok(Account::new(&account.name, orders)
+        .with_pairs(account.pairs.clone())
+        .with_books(account.books.clone())
+        .with_limit(RateLimit { per_minute: account.max_orders_per_minute, per_hour: account.max_orders_per_hour }))
+}
+
+/// The accounts CEX orders go out on, picked per order in the order they
+/// were added. Clones share the accounts.
+#[derive(Clone, Default)]
+pub struct AccountRouter {
+    accounts: Vec<Arc<Account>>,
+}
+
+impl AccountRouter {
+    pub fn add(&mut self, account: Account) {
+        self.accounts.push(Arc::new(account));
+    }
+
+    pub fn accounts(&self) -> &[Arc<Account>] {
+        &self.accounts
+    }
+
+    pub fn has_venue(&self, venue: &str) -> bool {
+        self.accounts.iter().any(|a| a.venue() == venue)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Account>> {
+        self.accounts.iter().find(|a| a.name == name).cloned()
+    }
+
+    /// The first account on `venue` taking `pair` and `book` that is within
+    /// its rate limit and holds enough for the order, which is counted on it.
+    /// `order` is the price and quantity of the order.
+    pub fn route(&self, venue: &str, pair: &str, book: &str, is_buy: bool, order: (f64, f64)) -> Result<Arc<Account>> {
+        self.route_at(venue, pair, book, is_buy, order, Instant::now())
+    }
+
+    pub fn route_at(&self, venue: &str, pair: &str, book: &str, is_buy: bool, order: (f64, f64), now: Instant) -> Result<Arc<Account>> {
+        let (px, qty) = order;
+        let mut candidates = self.accounts.iter().filter(|a| a.venue() == venue).peekable();
+        if candidates.peek().is_none() {
+            bail!("no order client for {}", venue);
+        }
+
+        let mut refusals = Vec::new();
+        for account in candidates.filter(|a| a.takes(pair, book)) {
+            match account.acquire(is_buy, px, qty, now) {
+                Ok(()) => return Ok(account.clone()),
+                Err(e) => refusals.push(format!("{:#}", e)),
+            }
+        }
+        if refusals.is_empty() {
+            bail!("no {} account takes {} on {}", venue, pair, book);
+        }
+        bail!("no {} account free: {}", venue, refusals.join("; "))
+    }
+}
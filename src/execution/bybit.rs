@@ -0,0 +1,253 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use log::warn;
+use tokio::time::sleep;
+
+use crate::clock;
+use crate::execution::cex::{CexFill, CexHoldings, CexOrders, OrderPolicy};
+use crate::execution::client_order::{ClientOrderId, acked, is_ambiguous, submit};
+use crate::execution::margin::{MarginAccount, MarginLoan};
+use crate::settings::{Secret, Settings};
+
+const RECV_WINDOW: &str = "5000";
+const FILL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Signed client for the Bybit v5 API, for spot orders on `bybit_ticker`
+/// and borrowing and repaying on the spot cross-margin account.
+pub struct BybitClient {
+    http: reqwest::Client,
+    base_url: String,
+    symbol: String,
+    // coins of the pair, as the wallet lists them
+    coins: (String, String),
+    api_key: String,
+    api_secret: Secret,
+}
+
+impl BybitClient {
+    pub fn new(cfg: &Settings) -> Result<Self> {
+        let (Some(api_key), Some(api_secret)) = (&cfg.bybit_api_key, &cfg.bybit_api_secret) else {
+            bail!("bybit_api_key and bybit_api_secret are not set");
+        };
+        Ok(Self::with_keys(cfg, api_key, api_secret))
+    }
+
+    /// A client of another account, e.g. a sub-account in `cex_accounts`.
+    pub fn with_keys(cfg: &Settings, api_key: &str, api_secret: &Secret) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: cfg.bybit_rest_endpoint.trim_end_matches('/').to_string(),
+            symbol: cfg.bybit_ticker.clone(),
+            coins: (cfg.pair_base.clone(), cfg.pair_quote.clone()),
+            api_key: api_key.to_string(),
+            api_secret: api_secret.clone(),
+        }
+    }
+
+    async fn get(&self, path: &str, query: &str) -> Result<Value> {
+        let request = self.http.get(format!("{}{}?{}", self.base_url, path, query));
+        self.send(path, request, query).await
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<Value> {
+        let body = body.to_string();
+        let request = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .header("Content-Type", "application/json")
+            .body(body.clone());
+        self.send(path, request, &body).await
+    }
+
+    // signs the query string of a GET or the body of a POST, returning `result`
+    async fn send(&self, path: &str, request: reqwest::RequestBuilder, payload: &str) -> Result<Value> {
+        clock::check_signing("bybit", clock::BYBIT_MAX_SKEW_MS)?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis().to_string();
+        let signature = sign(self.api_secret.expose(), &format!("{}{}{}{}", timestamp, self.api_key, RECV_WINDOW, payload));
+
+        let body: Value = request
+            .header("X-BAPI-API-KEY", &self.api_key)
+            .header("X-BAPI-TIMESTAMP", timestamp)
+            .header("X-BAPI-RECV-WINDOW", RECV_WINDOW)
+            .header("X-BAPI-SIGN", signature)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if body["retCode"].as_i64() != Some(0) {
+            bail!("bybit {}: {} ({})", path, body["retMsg"], body["retCode"]);
+        }
+        body.get("result").cloned().ok_or_else(|| anyhow!("bybit {} response has no result", path))
+    }
+
+    async fn place_market(&self, is_buy: bool, base: f64, id: ClientOrderId) -> Result<()> {
+        let body = json!({
+            "category": "spot",
+            "symbol": self.symbol,
+            "side": if is_buy { "Buy" } else { "Sell" },
+            "orderType": "Market",
+            "qty": base.to_string(),
+            "marketUnit": "baseCoin",
+            "orderLinkId": id.to_string(),
+        });
+        self.post("/v5/order/create", body).await?;
+        Ok(())
+    }
+
+    async fn cancel(&self, id: ClientOrderId) -> Result<()> {
+        let body = json!({ "category": "spot", "symbol": self.symbol, "orderLinkId": id.to_string() });
+        self.post("/v5/order/cancel", body).await?;
+        Ok(())
+    }
+
+    // an order already done or never placed cannot be cancelled, and that
+    // is what the cancel is after
+    async fn cancel_quietly(&self, id: ClientOrderId) {
+        if let Err(e) = self.cancel(id).await {
+            warn!("bybit order {} not cancelled: {:#}", id, e);
+        }
+    }
+
+    // the order placed under `id`, open or recently closed
+    async fn find_order(&self, id: ClientOrderId) -> Result<Option<Value>> {
+        let result = self.get("/v5/order/realtime", &format!("category=spot&orderLinkId={}", id)).await?;
+        Ok(result["list"].as_array().and_then(|orders| orders.first()).cloned())
+    }
+}
+
+/// Pair balances in a Bybit unified wallet and what the open spot orders
+/// in `orders` would still trade. Coins never held are missing and read as 0.
+pub fn holdings(wallet: &Value, orders: &Value, base: &str, quote: &str) -> Result<CexHoldings> {
+    let coins = wallet["list"].as_array().and_then(|accounts| accounts.first()).and_then(|a| a["coin"].as_array());
+    let balance = |coin: &str| match coins.and_then(|coins| coins.iter().find(|c| c["coin"] == coin)) {
+        Some(c) => amount(&c["walletBalance"]),
+        None => Ok(0.0),
+    };
+    let mut holdings = CexHoldings { base: balance(base)?, quote: balance(quote)?, ..Default::default() };
+    for order in orders["list"].as_array().into_iter().flatten() {
+        let left = amount(&order["qty"])? - amount(&order["cumExecQty"])?;
+        let price = amount(&order["price"])?;
+        if order["side"] == "Buy" {
+            holdings.bids += left;
+            holdings.bids_quote += left * price;
+        } else {
+            holdings.asks += left;
+            holdings.asks_quote += left * price;
+        }
+    }
+    Ok(holdings)
+}
+
+/// What a Bybit spot order filled once it is done, `None` while it is
+/// still working. A done order without any fill is an error.
+pub fn order_fill(order: &Value) -> Result<Option<CexFill>> {
+    match order["orderStatus"].as_str() {
+        Some("Filled" | "PartiallyFilledCanceled" | "Cancelled" | "Rejected") => {}
+        _ => return Ok(None),
+    }
+    let base = amount(&order["cumExecQty"])?;
+    if base <= 0.0 {
+        bail!("bybit order {} {} without a fill", order["orderLinkId"], order["orderStatus"]);
+    }
+    Ok(Some(CexFill { base, price: amount(&order["avgPrice"])? }))
+}
+
+/// Hex HMAC-SHA256 of `payload` under `secret`, as Bybit signs requests.
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn amount(value: &Value) -> Result<f64> {
+    match value {
+        Value::String(s) => Ok(s.parse()?),
+        value => value.as_f64().ok_or_else(|| anyhow!("unexpected bybit amount {}", value)),
+    }
+}
+
+#[async_trait]
+impl MarginAccount for BybitClient {
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+
+    async fn borrow(&self, asset: &str, amount: f64) -> Result<()> {
+        let body = json!({ "coin": asset, "qty": amount.to_string() });
+        self.post("/v5/spot-cross-margin-trade/loan", body).await?;
+        Ok(())
+    }
+
+    async fn repay(&self, asset: &str, amount: f64) -> Result<()> {
+        let body = json!({ "coin": asset, "qty": amount.to_string() });
+        self.post("/v5/spot-cross-margin-trade/repay", body).await?;
+        Ok(())
+    }
+
+    async fn loan(&self, asset: &str) -> Result<MarginLoan> {
+        let account = self.get("/v5/spot-cross-margin-trade/account", "").await?;
+        let borrowed = match account["loanAccountList"]
+            .as_array()
+            .and_then(|loans| loans.iter().find(|l| l["tokenId"] == asset))
+        {
+            Some(loan) => amount(&loan["loan"])?,
+            None => 0.0,
+        };
+
+        // bybit quotes a daily rate
+        let info = self.get("/v5/spot-cross-margin-trade/loan-info", &format!("coin={}", asset)).await?;
+        Ok(MarginLoan { borrowed, hourly_rate: amount(&info["interestRate"])? / 24.0 })
+    }
+}
+
+#[async_trait]
+impl CexOrders for BybitClient {
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+
+    async fn market_order(&self, is_buy: bool, base: f64, _price: f64, id: ClientOrderId, policy: &OrderPolicy) -> Result<CexFill> {
+        let place = || acked(policy.ack_timeout, self.place_market(is_buy, base, id));
+        let lookup = || acked(policy.ack_timeout, self.find_order(id));
+        if let Err(e) = submit(id, policy.attempts, place, lookup).await {
+            // a try given up on may still reach the book late
+            if is_ambiguous(&e) {
+                self.cancel_quietly(id).await;
+            }
+            return Err(e);
+        }
+
+        // the create answer only acknowledges the order, the fill follows
+        let deadline = Instant::now() + policy.fill_timeout;
+        while Instant::now() < deadline {
+            if let Some(order) = self.find_order(id).await?
+                && let Some(fill) = order_fill(&order)?
+            {
+                return Ok(fill);
+            }
+            sleep(FILL_POLL_INTERVAL).await;
+        }
+
+        // what filled by now is the fill, the rest is cancelled
+        warn!("⏱️ bybit order {} not done within {:?}, cancelling", id, policy.fill_timeout);
+        self.cancel_quietly(id).await;
+        match self.find_order(id).await? {
+            Some(order) => order_fill(&order)?.ok_or_else(|| anyhow!("bybit order {} still {} after cancelling", id, order["orderStatus"])),
+            None => bail!("bybit order {} not found after cancelling", id),
+        }
+    }
+
+    async fn holdings(&self) -> Result<CexHoldings> {
+        let (base, quote) = &self.coins;
+        let wallet = self.get("/v5/account/wallet-balance", &format!("accountType=UNIFIED&coin={},{}", base, quote)).await?;
+        let orders = self.get("/v5/order/realtime", &format!("category=spot&symbol={}&openOnly=0", self.symbol)).await?;
+        holdings(&wallet, &orders, base, quote)
+    }
+}
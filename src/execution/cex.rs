@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::execution::client_order::ClientOrderId;
+use crate::settings::Settings;
+
+/// How much of a market order filled and at what average price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CexFill {
+    pub base: f64,
+    pub price: f64,
+}
+
+/// Base and quote on a CEX account, free and locked alike, and what its
+/// open orders would buy (`bids`) and sell (`asks`) of base if they filled.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CexHoldings {
+    pub base: f64,
+    pub quote: f64,
+    pub bids: f64,
+    pub asks: f64,
+    // quote the bids would spend and the asks take in
+    pub bids_quote: f64,
+    pub asks_quote: f64,
+}
+
+/// How long a market order may take. Each of up to `attempts` tries must
+/// be answered within `ack_timeout`, and what has not filled
+/// `fill_timeout` after the answer is cancelled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderPolicy {
+    pub attempts: u32,
+    pub ack_timeout: Duration,
+    pub fill_timeout: Duration,
+}
+
+impl OrderPolicy {
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self {
+            attempts: cfg.order_submit_attempts.max(1),
+            ack_timeout: Duration::from_millis(cfg.cex_ack_timeout_ms),
+            fill_timeout: Duration::from_millis(cfg.cex_fill_timeout_ms),
+        }
+    }
+}
+
+/// Spot order placement on a CEX, the leg the DEX swap is matched against.
+#[async_trait]
+pub trait CexOrders: Send + Sync {
+    fn venue(&self) -> &'static str;
+
+    /// Buys or sells `base` at market under `id`, at most once within
+    /// `policy`, and waits for the fill. `price` is the side of the book
+    /// the order takes, for venues that size market buys in quote.
+    async fn market_order(&self, is_buy: bool, base: f64, price: f64, id: ClientOrderId, policy: &OrderPolicy) -> Result<CexFill>;
+
+    /// Balances and open orders of the pair on the account.
+    async fn holdings(&self) -> Result<CexHoldings> {
+        bail!("{} holdings are not read", self.venue())
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use alloy::primitives::{B128, keccak256};
+use anyhow::{Context, Result};
+use log::warn;
+use tokio::time::error::Elapsed;
+use tokio::time::timeout;
+
+use crate::arbitrage::Leg;
+use crate::opportunity::OpportunityId;
+
+/// Client order id of one leg of an opportunity. It is derived from the
+/// two, so a retry of the leg goes out under the same id and the venue
+/// can be asked whether the first attempt got through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientOrderId([u8; 16]);
+
+impl ClientOrderId {
+    pub fn new(opportunity: OpportunityId, leg: Leg) -> Self {
+        let leg: &[u8] = match leg {
+            Leg::Cex => b"cex",
+            Leg::Dex => b"dex",
+        };
+        let hash = keccak256([opportunity.as_bytes().as_slice(), leg].concat());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        Self(bytes)
+    }
+
+    /// Id of the order reversing this one, when its leg has to be unwound.
+    pub fn unwind(&self) -> Self {
+        let hash = keccak256([self.0.as_slice(), b"unwind"].concat());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        Self(bytes)
+    }
+
+    /// As Hyperliquid takes it, `cloid` of an order.
+    pub fn cloid(&self) -> B128 {
+        B128::from(self.0)
+    }
+
+    /// As Gate.io takes it, `text` of an order: `t-` and the hex of the
+    /// first 14 bytes, as no more than 28 characters may follow the prefix.
+    pub fn gateio_text(&self) -> String {
+        format!("t-{}", hex::encode(&self.0[..14]))
+    }
+}
+
+/// 32 hex digits, fitting Bybit's `orderLinkId`.
+impl fmt::Display for ClientOrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// How a submission ended: the venue answered the order itself, or the
+/// answer was lost and a lookup by client order id found the order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Submitted<T, S> {
+    Placed(T),
+    Found(S),
+}
+
+/// True when `e` leaves open whether the venue got the request: it timed
+/// out, the connection dropped mid-way or a gateway gave up on it. Errors
+/// the venue answered with, such as a rejection, are definite.
+pub fn is_ambiguous(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| cause.is::<Elapsed>())
+        || e.chain().filter_map(|cause| cause.downcast_ref::<reqwest::Error>()).any(|e| {
+            e.is_timeout()
+                || e.is_request()
+                || e.is_body()
+                || e.is_decode()
+                || e.status().is_some_and(|s| s.is_server_error())
+        })
+}
+
+/// `request` as long as the venue answers within `limit`; past it the
+/// request is given up on, ambiguously.
+pub async fn acked<T>(limit: Duration, request: impl Future<Output = Result<T>>) -> Result<T> {
+    timeout(limit, request).await.with_context(|| format!("no answer within {:?}", limit))?
+}
+
+/// Submits an order at most once per client order id over up to
+/// `attempts` tries. A definite error ends it; after an ambiguous one
+/// `lookup` asks the venue for the order by its client order id, and only
+/// when the venue does not know it is the order sent again. A failed
+/// lookup ends it as well, sending again could double the order.
+pub async fn submit<T, S, P, PF, L, LF>(id: ClientOrderId, attempts: u32, mut place: P, mut lookup: L) -> Result<Submitted<T, S>>
+where
+    P: FnMut() -> PF,
+    PF: Future<Output = Result<T>>,
+    L: FnMut() -> LF,
+    LF: Future<Output = Result<Option<S>>>,
+{
+    let mut attempt = 1;
+    loop {
+        let e = match place().await {
+            Ok(placed) => return Ok(Submitted::Placed(placed)),
+            Err(e) if !is_ambiguous(&e) => return Err(e),
+            Err(e) => e,
+        };
+        warn!("order {} attempt {} ended ambiguously: {:#}", id, attempt, e);
+
+        if let Some(found) = lookup().await? {
+            return Ok(Submitted::Found(found));
+        }
+        if attempt >= attempts {
+            return Err(e.context(format!("order {} not placed after {} attempts", id, attempt)));
+        }
+        attempt += 1;
+    }
+}
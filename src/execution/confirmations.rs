@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy::{
+    primitives::{Address, B256, FixedBytes, TxHash},
+    providers::Provider,
+};
+use anyhow::{Result, anyhow};
+use log::{error, info, warn};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::sleep;
+
+use crate::execution::sender::{PendingTx, TxSender};
+use crate::risk::RiskEvent;
+use crate::settings::Settings;
+
+/// Where a submitted transaction stands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxState {
+    Pending,
+    Included {
+        block_number: u64,
+        block_hash: B256,
+        success: bool,
+        confirmations: u64,
+    },
+    Confirmed {
+        block_number: u64,
+        success: bool,
+        gas_used: u64,
+        effective_gas_price: u128,
+    },
+    /// Not included in time, re-sent under the same nonce as `by`. Still
+    /// followed, as either of the two may end up included.
+    Replaced { by: TxHash },
+    /// Will never be included.
+    Dropped { reason: DropReason },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DropReason {
+    /// Another of our transactions with the same nonce was included.
+    Superseded { by: TxHash },
+    /// The nonce was used by a transaction we didn't send from here.
+    NonceReused,
+    /// The node no longer knows the transaction.
+    Evicted,
+    /// Not included after `max_replacements` fee bumps, its nonce taken
+    /// by a cancellation.
+    GaveUp,
+}
+
+#[derive(Debug, Clone)]
+pub struct TxUpdate {
+    pub hash: TxHash,
+    pub nonce: u64,
+    pub to: Address,
+    pub selector: Option<FixedBytes<4>>,
+    pub state: TxState,
+}
+
+/// Confirmed transactions are followed this many blocks past their
+/// inclusion, so a reorg that undoes them is noticed. Deeper reorgs are not.
+pub const REORG_HORIZON_BLOCKS: u64 = 64;
+
+struct Tracked {
+    tx: PendingTx,
+    state: TxState,
+    replacements: u32,
+}
+
+/// Hands transactions to a running `ConfirmationTracker` and reads what it
+/// publishes about them.
+#[derive(Clone)]
+pub struct TrackerHandle {
+    submitted: mpsc::UnboundedSender<PendingTx>,
+    updates: broadcast::Sender<TxUpdate>,
+}
+
+impl TrackerHandle {
+    /// Subscribe before `track`ing, or the first updates may be missed.
+    pub fn subscribe(&self) -> broadcast::Receiver<TxUpdate> {
+        self.updates.subscribe()
+    }
+
+    pub fn track(&self, tx: PendingTx) -> Result<()> {
+        self.submitted.send(tx).map_err(|_| anyhow!("confirmation tracker stopped"))
+    }
+}
+
+/// Follows submitted transactions until `confirmation_depth` blocks deep,
+/// publishing every state change. Transactions not included within
+/// `inclusion_timeout_secs` are replaced with bumped fees, and ones that
+/// can no longer be included are reported as dropped instead of being
+/// waited on forever. Confirmed ones stay followed until
+/// `REORG_HORIZON_BLOCKS` deep, and are verified again after a reorg.
+pub struct ConfirmationTracker {
+    sender: Arc<TxSender>,
+    depth: u64,
+    inclusion_timeout: Duration,
+    fee_bump_pct: u32,
+    max_replacements: u32,
+    tracked: HashMap<TxHash, Tracked>,
+    updates: broadcast::Sender<TxUpdate>,
+    submitted: mpsc::UnboundedSender<PendingTx>,
+    submitted_rx: mpsc::UnboundedReceiver<PendingTx>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(cfg: &Settings, sender: Arc<TxSender>) -> (Self, broadcast::Receiver<TxUpdate>) {
+        let (updates, updates_rx) = broadcast::channel(256);
+        let (submitted, submitted_rx) = mpsc::unbounded_channel();
+
+        let tracker = Self {
+            sender,
+            depth: cfg.confirmation_depth.max(1),
+            inclusion_timeout: Duration::from_secs(cfg.inclusion_timeout_secs),
+            fee_bump_pct: cfg.replacement_fee_bump_pct,
+            max_replacements: cfg.max_replacements,
+            tracked: HashMap::new(),
+            updates,
+            submitted,
+            submitted_rx,
+        };
+
+        (tracker, updates_rx)
+    }
+
+    /// Publishes into `updates` instead of a channel of its own, so the
+    /// trackers of several wallets can be read as one.
+    pub fn with_updates(mut self, updates: broadcast::Sender<TxUpdate>) -> Self {
+        self.updates = updates;
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TxUpdate> {
+        self.updates.subscribe()
+    }
+
+    pub fn handle(&self) -> TrackerHandle {
+        TrackerHandle { submitted: self.submitted.clone(), updates: self.updates.clone() }
+    }
+
+    pub async fn run(mut self, mut risk_rx: broadcast::Receiver<RiskEvent>) {
+        loop {
+            tokio::select! {
+                Some(tx) = self.submitted_rx.recv() => self.track(tx),
+                Ok(RiskEvent::Reorg { fork_block, .. }) = risk_rx.recv() => self.on_reorg(fork_block),
+                _ = sleep(Duration::from_millis(500)) => {
+                    if let Err(e) = self.poll().await {
+                        error!("confirmation poll error: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn track(&mut self, tx: PendingTx) {
+        self.track_replacement(tx, 0);
+    }
+
+    fn track_replacement(&mut self, tx: PendingTx, replacements: u32) {
+        self.publish(&tx, TxState::Pending);
+        self.tracked.insert(tx.hash, Tracked { tx, state: TxState::Pending, replacements });
+    }
+
+    /// Inclusions above the fork may have been undone; send them back to
+    /// pending, with a fresh inclusion timeout, so they are looked up (and
+    /// confirmed) again.
+    pub fn on_reorg(&mut self, fork_block: u64) {
+        for tracked in self.tracked.values_mut() {
+            if let TxState::Included { block_number, .. } | TxState::Confirmed { block_number, .. } = tracked.state
+                && block_number > fork_block
+            {
+                warn!("tx {} was included above reorged block {}, re-verifying", tracked.tx.hash, fork_block);
+                tracked.state = TxState::Pending;
+                tracked.tx.sent_at = Instant::now();
+                publish(&self.updates, &tracked.tx, TxState::Pending);
+            }
+        }
+    }
+
+    /// Whether `hash` is still followed.
+    pub fn is_tracked(&self, hash: TxHash) -> bool {
+        self.tracked.contains_key(&hash)
+    }
+
+    pub async fn poll(&mut self) -> Result<()> {
+        if self.tracked.is_empty() {
+            return Ok(());
+        }
+
+        let sender = self.sender.clone();
+        let provider = sender.provider();
+
+        // read before the receipts: a nonce below this was used by a block
+        // whose receipts are already served
+        let account_nonce = provider.get_transaction_count(sender.address()).await?;
+        let head = provider.get_block_number().await?;
+
+        // confirmed ones are only looked up again after a reorg
+        self.tracked.retain(|_, tracked| match tracked.state {
+            TxState::Confirmed { block_number, .. } => head.saturating_sub(block_number) < REORG_HORIZON_BLOCKS,
+            _ => true,
+        });
+        let (confirmed, hashes): (Vec<TxHash>, Vec<TxHash>) =
+            self.tracked.keys().copied().partition(|hash| matches!(self.tracked[hash].state, TxState::Confirmed { .. }));
+
+        let mut receipts = HashMap::new();
+        for &hash in &hashes {
+            if let Some(receipt) = provider.get_transaction_receipt(hash).await? {
+                receipts.insert(hash, receipt);
+            }
+        }
+        let included: HashMap<u64, TxHash> = receipts
+            .keys()
+            .chain(&confirmed)
+            .map(|hash| (self.tracked[hash].tx.nonce, *hash))
+            .collect();
+
+        for hash in hashes {
+            let tracked = &self.tracked[&hash];
+            let nonce = tracked.tx.nonce;
+
+            let state = match receipts.get(&hash) {
+                Some(receipt) => {
+                    let block_number = receipt.block_number.unwrap_or(head);
+                    let confirmations = head.saturating_sub(block_number) + 1;
+                    let success = receipt.status();
+
+                    if confirmations >= self.depth {
+                        TxState::Confirmed {
+                            block_number,
+                            success,
+                            gas_used: receipt.gas_used,
+                            effective_gas_price: receipt.effective_gas_price,
+                        }
+                    } else {
+                        TxState::Included {
+                            block_number,
+                            block_hash: receipt.block_hash.unwrap_or_default(),
+                            success,
+                            confirmations,
+                        }
+                    }
+                }
+                None => match included.get(&nonce) {
+                    Some(&by) => TxState::Dropped { reason: DropReason::Superseded { by } },
+                    None if nonce < account_nonce => TxState::Dropped { reason: DropReason::NonceReused },
+                    None if matches!(tracked.state, TxState::Replaced { .. }) => continue,
+                    None if tracked.tx.sent_at.elapsed() > self.inclusion_timeout => {
+                        self.on_timeout(hash).await?
+                    }
+                    None => TxState::Pending,
+                },
+            };
+
+            self.set_state(hash, state);
+        }
+
+        Ok(())
+    }
+
+    async fn on_timeout(&mut self, hash: TxHash) -> Result<TxState> {
+        let sender = self.sender.clone();
+
+        if sender.provider().get_transaction_by_hash(hash).await?.is_none() {
+            // its nonce is free again, later sends must not skip it
+            warn!("tx {} vanished from the mempool", hash);
+            sender.reset_nonce().await;
+            return Ok(TxState::Dropped { reason: DropReason::Evicted });
+        }
+
+        let tracked = &self.tracked[&hash];
+        if tracked.replacements >= self.max_replacements {
+            warn!("tx {} not included after {} replacements, giving up", hash, tracked.replacements);
+            // an empty self-transfer takes the nonce, followed on its own
+            let tx = tracked.tx.clone();
+            match sender.cancel(&tx, self.fee_bump_pct).await {
+                Ok(cancel) => {
+                    info!("🚫 tx {} cancelled by {}", hash, cancel.hash);
+                    self.track(cancel);
+                }
+                Err(e) => warn!("failed to cancel tx {}: {}", hash, e),
+            }
+            return Ok(TxState::Dropped { reason: DropReason::GaveUp });
+        }
+
+        let (tx, replacements) = (tracked.tx.clone(), tracked.replacements + 1);
+        match sender.replace(&tx, self.fee_bump_pct).await {
+            Ok(replacement) => {
+                info!("⛽ tx {} not included in {:?}, replaced by {}", hash, self.inclusion_timeout, replacement.hash);
+                let by = replacement.hash;
+                self.track_replacement(replacement, replacements);
+                Ok(TxState::Replaced { by })
+            }
+            Err(e) => {
+                // most likely mined meanwhile, the next poll will tell
+                warn!("failed to replace tx {}: {}", hash, e);
+                Ok(TxState::Pending)
+            }
+        }
+    }
+
+    fn set_state(&mut self, hash: TxHash, state: TxState) {
+        let Some(tracked) = self.tracked.get_mut(&hash) else {
+            return;
+        };
+        if tracked.state == state {
+            return;
+        }
+
+        tracked.state = state.clone();
+        publish(&self.updates, &tracked.tx, state.clone());
+
+        if matches!(state, TxState::Dropped { .. }) {
+            self.tracked.remove(&hash);
+        }
+    }
+
+    fn publish(&self, tx: &PendingTx, state: TxState) {
+        publish(&self.updates, tx, state);
+    }
+}
+
+fn publish(updates: &broadcast::Sender<TxUpdate>, tx: &PendingTx, state: TxState) {
+    info!("tx {} (nonce {}): {:?}", tx.hash, tx.nonce, state);
+
+    let _ = updates.send(TxUpdate {
+        hash: tx.hash,
+        nonce: tx.nonce,
+        to: tx.to,
+        selector: tx.calldata.get(..4).map(FixedBytes::from_slice),
+        state,
+    });
+}
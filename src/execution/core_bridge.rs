@@ -0,0 +1,175 @@
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, U256, address},
+    rpc::types::TransactionRequest,
+    sol_types::SolValue,
+};
+use anyhow::{Result, anyhow, bail};
+
+use crate::helpers::abi::{core_action_calldata, deposit_calldata, transfer_calldata, withdraw_calldata};
+use crate::settings::Settings;
+
+/// Receives native HYPE on the EVM side and credits it on Core.
+pub const HYPE_SYSTEM_ADDRESS: Address = address!("0x2222222222222222222222222222222222222222");
+pub const CORE_WRITER_ADDRESS: Address = address!("0x3333333333333333333333333333333333333333");
+pub const HYPE_CORE_INDEX: u64 = 150;
+
+const SPOT_SEND_ACTION: u32 = 6;
+
+/// A HyperCore spot token linked to an EVM token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoreToken {
+    pub index: u64,
+    // EVM token, the wrapped one for HYPE
+    pub evm_addr: Address,
+    // EVM decimals minus Core wei decimals
+    pub evm_extra_wei_decimals: i32,
+}
+
+impl CoreToken {
+    pub fn base(cfg: &Settings) -> Self {
+        Self {
+            index: cfg.base_core_index,
+            evm_addr: cfg.weth_addr,
+            evm_extra_wei_decimals: cfg.base_core_extra_wei_decimals,
+        }
+    }
+
+    pub fn quote(cfg: &Settings) -> Self {
+        Self {
+            index: cfg.quote_core_index,
+            evm_addr: cfg.usdt_addr,
+            evm_extra_wei_decimals: cfg.quote_core_extra_wei_decimals,
+        }
+    }
+
+    pub fn is_hype(&self) -> bool {
+        self.index == HYPE_CORE_INDEX
+    }
+
+    /// Address that credits this token on Core when sent to on the EVM side,
+    /// and on the EVM side when spot-sent to on Core.
+    pub fn system_address(&self) -> Address {
+        if self.is_hype() {
+            return HYPE_SYSTEM_ADDRESS;
+        }
+
+        let mut bytes = [0u8; 20];
+        bytes[0] = 0x20;
+        bytes[12..].copy_from_slice(&self.index.to_be_bytes());
+        Address::from(bytes)
+    }
+
+    /// Core wei for an EVM amount. Fails if the amount has dust below Core
+    /// precision, which the bridge would silently burn.
+    pub fn to_core_wei(&self, evm_amount: U256) -> Result<u64> {
+        let scale = U256::from(10).pow(U256::from(self.evm_extra_wei_decimals.unsigned_abs()));
+
+        let core_wei = if self.evm_extra_wei_decimals >= 0 {
+            if evm_amount % scale != U256::ZERO {
+                bail!("{} has dust below core precision of token {}", evm_amount, self.index);
+            }
+            evm_amount / scale
+        } else {
+            evm_amount * scale
+        };
+
+        core_wei
+            .try_into()
+            .map_err(|_| anyhow!("{} overflows core wei of token {}", evm_amount, self.index))
+    }
+
+    pub fn to_evm_amount(&self, core_wei: u64) -> U256 {
+        let scale = U256::from(10).pow(U256::from(self.evm_extra_wei_decimals.unsigned_abs()));
+
+        if self.evm_extra_wei_decimals >= 0 {
+            U256::from(core_wei) * scale
+        } else {
+            U256::from(core_wei) / scale
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgeDirection {
+    EvmToCore,
+    CoreToEvm,
+}
+
+/// One EVM call of a transfer.
+#[derive(Debug, Clone)]
+pub struct BridgeCall {
+    pub to: Address,
+    pub calldata: Bytes,
+    pub value: U256,
+}
+
+impl BridgeCall {
+    pub fn tx(&self, from: Address) -> TransactionRequest {
+        TransactionRequest::default()
+            .from(from)
+            .to(self.to)
+            .with_input(self.calldata.clone())
+            .with_value(self.value)
+    }
+}
+
+/// Moves `amount` (in EVM units) of a token between the EVM wallet and its
+/// HyperCore spot balance. HYPE is unwrapped before and wrapped after the
+/// bridge so the EVM side always holds WHYPE.
+#[derive(Debug, Clone)]
+pub struct CoreTransfer {
+    pub token: CoreToken,
+    pub direction: BridgeDirection,
+    pub amount: U256,
+}
+
+impl CoreTransfer {
+    /// The calls to send in order, all from the wallet.
+    pub fn calls(&self) -> Result<Vec<BridgeCall>> {
+        let system = self.token.system_address();
+        // also rejects dust the bridge can't represent
+        let core_wei = self.token.to_core_wei(self.amount)?;
+
+        let calls = match (self.direction, self.token.is_hype()) {
+            (BridgeDirection::EvmToCore, true) => vec![
+                BridgeCall {
+                    to: self.token.evm_addr,
+                    calldata: withdraw_calldata(self.amount),
+                    value: U256::ZERO,
+                },
+                BridgeCall {
+                    to: system,
+                    calldata: Bytes::new(),
+                    value: self.amount,
+                },
+            ],
+            (BridgeDirection::EvmToCore, false) => vec![BridgeCall {
+                to: self.token.evm_addr,
+                calldata: transfer_calldata(system, self.amount),
+                value: U256::ZERO,
+            }],
+            (BridgeDirection::CoreToEvm, is_hype) => {
+                // spotSend(destination, token, wei) to the system address
+                let args = (system, self.token.index, core_wei).abi_encode();
+                let mut calls = vec![BridgeCall {
+                    to: CORE_WRITER_ADDRESS,
+                    calldata: core_action_calldata(SPOT_SEND_ACTION, args),
+                    value: U256::ZERO,
+                }];
+
+                // Core credits native HYPE, which only shows up a block later
+                if is_hype {
+                    calls.push(BridgeCall {
+                        to: self.token.evm_addr,
+                        calldata: deposit_calldata(),
+                        value: self.amount,
+                    });
+                }
+                calls
+            }
+        };
+
+        Ok(calls)
+    }
+}
@@ -0,0 +1,101 @@
+use alloy::primitives::{Address, Bytes, U256};
+
+use crate::arbitrage::ArbDirection;
+use crate::helpers::abi::{exact_input_single_calldata, exact_output_single_calldata};
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwapMode {
+    /// spend exactly `amount` of token_in, receive at least `limit` token_out
+    ExactInput,
+    /// receive exactly `amount` of token_out, spend at most `limit` token_in
+    ExactOutput,
+}
+
+/// A single-pool swap on the DEX leg of an arbitrage.
+#[derive(Debug, Clone)]
+pub struct DexSwap {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub fee: u32,
+    pub mode: SwapMode,
+    pub amount: U256,
+    pub limit: U256,
+}
+
+impl DexSwap {
+    /// Builds the DEX leg so that the base quantity always matches the CEX
+    /// fill: selling base is exact-input, buying base is exact-output.
+    ///
+    /// `quoted` is the quote-token amount from the quoter for `base_amount`
+    /// (amountOut when selling, amountIn when buying).
+    pub fn for_direction(
+        cfg: &Settings,
+        direction: &ArbDirection,
+        base_amount: U256,
+        quoted: U256,
+    ) -> Self {
+        match direction {
+            ArbDirection::BuyCex => Self::sell_base(cfg, base_amount, quoted),
+            ArbDirection::BuyDex => Self::buy_base(cfg, base_amount, quoted),
+        }
+    }
+
+    pub fn sell_base(cfg: &Settings, base_amount: U256, quoted_out: U256) -> Self {
+        Self {
+            token_in: cfg.weth_addr,
+            token_out: cfg.usdt_addr,
+            fee: cfg.dex_fee_tier,
+            mode: SwapMode::ExactInput,
+            amount: base_amount,
+            limit: apply_slippage(quoted_out, cfg.dex_slippage_bps, false),
+        }
+    }
+
+    pub fn buy_base(cfg: &Settings, base_amount: U256, quoted_in: U256) -> Self {
+        Self {
+            token_in: cfg.usdt_addr,
+            token_out: cfg.weth_addr,
+            fee: cfg.dex_fee_tier,
+            mode: SwapMode::ExactOutput,
+            amount: base_amount,
+            limit: apply_slippage(quoted_in, cfg.dex_slippage_bps, true),
+        }
+    }
+
+    pub fn calldata(&self, recipient: Address, deadline: U256) -> Bytes {
+        match self.mode {
+            SwapMode::ExactInput => exact_input_single_calldata(
+                self.token_in,
+                self.token_out,
+                self.fee,
+                recipient,
+                deadline,
+                self.amount,
+                self.limit,
+            ),
+            SwapMode::ExactOutput => exact_output_single_calldata(
+                self.token_in,
+                self.token_out,
+                self.fee,
+                recipient,
+                deadline,
+                self.amount,
+                self.limit,
+            ),
+        }
+    }
+}
+
+/// Widens a quoted amount into a swap limit: up for a max input, down for
+/// a min output.
+pub fn apply_slippage(amount: U256, slippage_bps: u32, round_up: bool) -> U256 {
+    let bps = U256::from(10_000);
+    let slippage = U256::from(slippage_bps);
+
+    if round_up {
+        amount * (bps + slippage) / bps
+    } else {
+        amount * (bps - slippage) / bps
+    }
+}
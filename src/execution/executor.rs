@@ -0,0 +1,731 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{Address, TxHash, U256};
+use alloy::providers::Provider;
+use anyhow::{Result, anyhow, bail};
+use log::{info, warn};
+use serde_json::{Value, json};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+
+use crate::arbitrage::{ArbDirection, ArbProfit, GasToken, Leg};
+use crate::audit::Decision;
+use crate::capital::{Capital, Reservation};
+use crate::dex::router::{DexRouter, UniswapV3, router_for};
+use crate::execution::accounts::{Account, AccountRouter};
+use crate::execution::bybit::BybitClient;
+use crate::execution::cex::{CexFill, CexOrders, OrderPolicy};
+use crate::execution::client_order::ClientOrderId;
+use crate::execution::confirmations::{TrackerHandle, TxState};
+use crate::execution::dex::DexSwap;
+use crate::execution::gateio::GateioClient;
+use crate::execution::margin::MarginAccount;
+use crate::execution::hyperliquid::HyperliquidSpot;
+use crate::execution::paper::{PaperFill, PaperLedger};
+use crate::execution::sender::{PendingTx, TxOverrides, TxSender};
+use crate::execution::short::{CexSell, ShortSeller};
+use crate::execution::split::plan_split;
+use crate::execution::verify::{VerifiedFill, verify_swap_fill};
+use crate::execution::wallets::WalletPool;
+use crate::helpers::revm::init_cache_db;
+use crate::inventory::{borrow_shortfall, repay_loan};
+use crate::normalize::Normalizer;
+use crate::pnl::{PnlLedger, Settlement, TradeRecord};
+use crate::risk::RiskHandle;
+use crate::scanner::{Candidate, DEFAULT_PAIR};
+use crate::settings::{DexKind, ExecutionMode, Settings, StrategyKind};
+use crate::state::StateStore;
+
+const AUDIT_SOURCE: &str = "executor";
+const SWAP_DEADLINE_SECS: u64 = 60;
+
+/// How an opportunity handed to the executor ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Execution {
+    /// nets less than `exec_min_profit` at its size, `exec_base_size`
+    /// unless sized
+    BelowThreshold,
+    /// another execution is under way
+    Busy,
+    /// refused by risk or capital, or a leg did not go out
+    Skipped(String),
+    /// logged and audited only, in log mode
+    DryRun,
+    /// filled against virtual balances, in paper mode
+    Paper(PaperFill),
+    /// `account` is the CEX account the order went out on, `wallet` the
+    /// one that sent the swap; `trade` is priced from the swap's receipts,
+    /// unless they could not be verified
+    Sent { fill: CexFill, tx: TxHash, account: String, wallet: Address, trade: Option<TradeRecord> },
+    /// one leg failed for good and the other was reversed
+    Unwound(String),
+}
+
+// the CEX order rounded onto the venue's grid, the account it goes out
+// on, the DEX leg's balance changes and the capital held while it is
+// under way
+struct Checked {
+    px: f64,
+    qty: f64,
+    account: Option<Arc<Account>>,
+    deltas: [(&'static str, f64); 2],
+    reservation: Option<Reservation>,
+}
+
+// what the CEX leg filled, and whether it sold short rather than from
+// inventory
+struct CexLeg {
+    fill: CexFill,
+    shorted: bool,
+}
+
+// a swap sent for part or all of a DEX leg, with its share of the leg and
+// the quote it was built from
+struct SentSwap {
+    pending: PendingTx,
+    share: f64,
+    swap: DexSwap,
+    quoted: U256,
+}
+
+// where a DEX leg settled and what it filled, read back from its receipts
+// unless they could not be verified; `unfilled` is the share of a split
+// leg whose swaps did not go out or did not land, and why
+struct SwapLeg {
+    tx: TxHash,
+    wallet: Address,
+    fill: Option<VerifiedFill>,
+    unfilled: Option<(f64, anyhow::Error)>,
+}
+
+/// Net profit of `size` base at the per-unit `profit`, gas paid once.
+pub fn expected_net(profit: &ArbProfit, size: f64) -> f64 {
+    (profit.net + profit.gas_cost) * size - profit.gas_cost
+}
+
+/// USD per gas token, from the price of the pair with its non-gas side
+/// taken as a dollar.
+pub fn gas_token_usd(cfg: &Settings, price: f64) -> f64 {
+    match cfg.gas_token() {
+        GasToken::Base => price,
+        GasToken::Quote => 1.0 / price,
+    }
+}
+
+/// Refuses a DEX leg at `gas_price_wei` above `max_gas_price_gwei`, or
+/// whose `dex_gas_used` gas costs more than `max_gas_cost_usd` with the gas
+/// token at `gas_token_usd`.
+pub fn check_gas_ceiling(cfg: &Settings, gas_price_wei: u128, gas_token_usd: f64) -> Result<()> {
+    let gwei = gas_price_wei as f64 / 1e9;
+    if let Some(max) = cfg.max_gas_price_gwei
+        && gwei > max
+    {
+        bail!("gas price {:.2} gwei is above max_gas_price_gwei {:.2}", gwei, max);
+    }
+    let cost = (gas_price_wei * cfg.dex_gas_used as u128) as f64 / 1e18 * gas_token_usd;
+    if let Some(max) = cfg.max_gas_cost_usd
+        && cost > max
+    {
+        bail!("gas would cost ${:.4}, above max_gas_cost_usd ${:.4}", cost, max);
+    }
+    Ok(())
+}
+
+/// The order client of CEX `venue`, on its API keys.
+pub fn cex_orders(cfg: &Settings, venue: &str) -> Result<Arc<dyn CexOrders>> {
+    Ok(match venue {
+        "bybit" => Arc::new(BybitClient::new(cfg)?),
+        "gateio" => Arc::new(GateioClient::new(cfg)?),
+        "hyperliquid" => Arc::new(HyperliquidSpot::new(cfg)?),
+        venue => bail!("no order client for {}", venue),
+    })
+}
+
+/// Sends both legs of the opportunities the scanner ranks best at once: a
+/// market order on the CEX and a swap through the DEX router. One
+/// execution runs at a time. Only in live `execution_mode` anything is
+/// signed; in log mode the trade is only logged and audited, in paper mode
+/// it is filled at the quoted prices against a `PaperLedger`.
+///
+/// No leg is waited on unbounded: CEX orders run within their
+/// `OrderPolicy` and are cancelled past it, and swaps are followed by their
+/// wallet's `ConfirmationTracker`, which replaces them with bumped fees
+/// when not included within `inclusion_timeout_secs` and gives up after
+/// `max_replacements`. A swap counts once `confirmation_depth` deep. When
+/// one leg fails for good the other is unwound.
+pub struct ArbExecutor {
+    cfg: Settings,
+    policy: OrderPolicy,
+    provider: Arc<dyn Provider>,
+    router: Arc<dyn DexRouter>,
+    wallets: WalletPool,
+    accounts: AccountRouter,
+    shorts: Option<ShortSeller>,
+    margin: Vec<Arc<dyn MarginAccount>>,
+    risk: RiskHandle,
+    capital: Option<Capital>,
+    state: Option<StateStore>,
+    paper: PaperLedger,
+    pnl: Mutex<PnlLedger>,
+    busy: AtomicBool,
+}
+
+impl ArbExecutor {
+    pub fn new(cfg: &Settings, provider: Arc<dyn Provider>, risk: RiskHandle) -> Result<Self> {
+        Ok(Self {
+            cfg: cfg.clone(),
+            policy: OrderPolicy::from_config(cfg),
+            provider,
+            router: router_for(cfg)?,
+            wallets: WalletPool::new(cfg.wallet_selection),
+            accounts: AccountRouter::default(),
+            shorts: None,
+            margin: Vec::new(),
+            risk,
+            capital: None,
+            state: None,
+            paper: PaperLedger::from_config(cfg),
+            pnl: Mutex::default(),
+            busy: AtomicBool::new(false),
+        })
+    }
+
+    /// Signs DEX legs from `sender`'s wallet, followed by `tracker`, the
+    /// running `ConfirmationTracker` of `sender`; at least one is needed
+    /// in live mode. With more, each swap goes out from one picked by
+    /// `wallet_selection`.
+    pub fn with_sender(mut self, sender: Arc<TxSender>, tracker: TrackerHandle) -> Self {
+        self.wallets.add(sender, tracker);
+        self
+    }
+
+    /// Keeps every swap hash, replacements included, in `state` until it
+    /// is confirmed or dropped, so a restart or drain knows what is in
+    /// flight.
+    pub fn with_state(mut self, state: StateStore) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Places the CEX leg on `venue`; opportunities on a venue without an
+    /// order client are skipped.
+    pub fn with_venue(mut self, venue: Arc<dyn CexOrders>) -> Self {
+        self.accounts.add(Account::new(venue.venue(), venue));
+        self
+    }
+
+    /// Places CEX legs on `account` where it takes them, ahead of accounts
+    /// added after it.
+    pub fn with_account(mut self, account: Account) -> Self {
+        self.accounts.add(account);
+        self
+    }
+
+    /// Sells the CEX leg of a BuyDex on HyperCore through `seller`, short
+    /// when the spot inventory does not cover it.
+    pub fn with_short_seller(mut self, seller: ShortSeller) -> Self {
+        self.shorts = Some(seller);
+        self
+    }
+
+    /// Borrows on `account` what a CEX leg on its venue's own keys lacks of
+    /// an asset listed in `borrow_rates`, and repays from free balance once
+    /// a later leg brings the asset back.
+    pub fn with_margin(mut self, account: Arc<dyn MarginAccount>) -> Self {
+        self.margin.push(account);
+        self
+    }
+
+    /// Reserves the notional of each execution in its book for as long as
+    /// it is under way.
+    pub fn with_capital(mut self, capital: Capital) -> Self {
+        self.capital = Some(capital);
+        self
+    }
+
+    /// The virtual balances and PnL of paper mode.
+    pub fn paper(&self) -> &PaperLedger {
+        &self.paper
+    }
+
+    /// Realized PnL of live trades, from their verified receipts.
+    pub fn pnl(&self) -> MutexGuard<'_, PnlLedger> {
+        self.pnl.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub async fn execute(&self, candidate: &Candidate) -> Execution {
+        let (size, net) = match &candidate.size {
+            Some(sized) => (sized.size, sized.net),
+            None => (self.cfg.exec_base_size, expected_net(&candidate.profit, self.cfg.exec_base_size)),
+        };
+        if net < self.cfg.exec_min_profit && !candidate.closing {
+            return Execution::BelowThreshold;
+        }
+        if self.busy.swap(true, Ordering::AcqRel) {
+            return Execution::Busy;
+        }
+        let execution = self.take(candidate, size, net).await;
+        self.busy.store(false, Ordering::Release);
+        execution
+    }
+
+    async fn take(&self, candidate: &Candidate, size: f64, net: f64) -> Execution {
+        let inputs = json!({
+            "pair": candidate.pair,
+            "venue": candidate.venue,
+            "dex_venue": candidate.dex_venue,
+            "direction": format!("{:?}", candidate.direction),
+            "size": size,
+            "cex_price": candidate.cex_price,
+            "expected_net": net,
+            "exec_min_profit": self.cfg.exec_min_profit,
+            "execution_mode": self.cfg.execution_mode,
+        });
+        let Checked { px, qty, account, deltas, reservation: _reservation } = match self.check(candidate, size) {
+            Ok(checked) => checked,
+            Err(e) => return self.skipped(candidate, inputs, e),
+        };
+        if let Err(e) = self.check_gas(px).await {
+            warn!("⛽ skipping {:?} [{}]: {:#}", candidate.direction, candidate.id, e);
+            return self.skipped(candidate, inputs, e);
+        }
+
+        match self.cfg.execution_mode {
+            ExecutionMode::Log => {
+                info!(
+                    "🧪 DRY RUN {:?} {:.4} on {}/{} @ ${:.4}, net ${:.4} [{}]",
+                    candidate.direction, qty, candidate.venue, candidate.dex_venue, px, net, candidate.id
+                );
+                let decision = Decision::skipped(AUDIT_SOURCE, "arb", "dry run", inputs);
+                self.risk.audit.record(decision.with_opportunities(vec![candidate.id]));
+                return Execution::DryRun;
+            }
+            ExecutionMode::Paper => return self.paper_fill(candidate, px, qty, inputs),
+            ExecutionMode::Live => {}
+        }
+        let Some(account) = account else {
+            return self.skipped(candidate, inputs, anyhow!("no order client for {}", candidate.venue));
+        };
+        let mut inputs = inputs;
+        inputs["account"] = json!(account.name);
+
+        let (failed, unwound) = match self.send(candidate, &account, px, qty, &deltas).await {
+            (Ok(CexLeg { fill, .. }), Ok(SwapLeg { tx, wallet, fill: dex_fill, unfilled: None })) => {
+                info!(
+                    "🟢 ARB {:?} {:.4} on {}/{}: cex {:.4} @ ${:.4}, dex {} [{}]",
+                    candidate.direction, qty, candidate.venue, candidate.dex_venue, fill.base, fill.price, tx, candidate.id
+                );
+                inputs["tx"] = json!(tx);
+                inputs["wallet"] = json!(wallet);
+                inputs["cex_fill"] = json!({ "base": fill.base, "price": fill.price });
+                let decision = Decision::taken(AUDIT_SOURCE, "arb", inputs);
+                self.risk.audit.record(decision.with_opportunities(vec![candidate.id]));
+                let trade = dex_fill.map(|dex_fill| {
+                    let settlement = Settlement::new(&candidate.venue, tx, fill.price).with_account(&account.name).with_wallet(wallet);
+                    self.pnl().record(&self.cfg, &settlement, &candidate.opportunity(), &dex_fill).clone()
+                });
+                return Execution::Sent { fill, tx, account: account.name.clone(), wallet, trade };
+            }
+            (Err(cex), Err(dex)) => {
+                let reason = anyhow!("cex leg: {:#}; dex leg: {:#}", cex, dex);
+                warn!("⚠️ ARB {:?} [{}] failed on both legs: {:#}", candidate.direction, candidate.id, reason);
+                return self.skipped(candidate, inputs, reason);
+            }
+            (Ok(leg), Err(dex)) => {
+                let base = leg.fill.base;
+                let unwound = self.unwind_cex(candidate, &account, leg, base).await;
+                (format!("dex leg: {:#}", dex), unwound.map(|f| format!("cex {:.4} @ ${:.4}", f.base, f.price)))
+            }
+            // only what the DEX leg left unfilled is traded back on the CEX
+            (Ok(leg), Ok(SwapLeg { tx, unfilled: Some((share, dex)), .. })) => {
+                let base = leg.fill.base * share;
+                let unwound = self.unwind_cex(candidate, &account, leg, base).await;
+                let failed = format!("dex leg {} filled {:.0}%: {:#}", tx, (1.0 - share) * 100.0, dex);
+                (failed, unwound.map(|f| format!("cex {:.4} @ ${:.4}", f.base, f.price)))
+            }
+            (Err(cex), Ok(SwapLeg { unfilled, .. })) => {
+                let filled = 1.0 - unfilled.map_or(0.0, |(share, _)| share);
+                let unwound = self.unwind_dex(candidate, qty * filled, &deltas, filled).await;
+                (format!("cex leg: {:#}", cex), unwound.map(|tx| format!("dex {}", tx)))
+            }
+        };
+
+        match unwound {
+            Ok(reversal) => {
+                let reason = format!("unwound after {}", failed);
+                warn!("↩️ UNWOUND {:?} [{}] with {} after {}", candidate.direction, candidate.id, reversal, failed);
+                inputs["unwind"] = json!(reversal);
+                let decision = Decision::skipped(AUDIT_SOURCE, "arb", &reason, inputs);
+                self.risk.audit.record(decision.with_opportunities(vec![candidate.id]));
+                Execution::Unwound(reason)
+            }
+            Err(e) => {
+                warn!("⚠️ ARB {:?} [{}] left one-sided after {}: unwind failed: {:#}", candidate.direction, candidate.id, failed, e);
+                self.skipped(candidate, inputs, anyhow!("{}; unwind: {:#}", failed, e))
+            }
+        }
+    }
+
+    // the order as it may go out, or why it may not
+    fn check(&self, candidate: &Candidate, size: f64) -> Result<Checked> {
+        // swaps are built on the tokens of the configured pair
+        if candidate.pair != DEFAULT_PAIR {
+            bail!("only the {} pair is executed", DEFAULT_PAIR);
+        }
+        if candidate.cross_cex {
+            bail!("{}/{} has no DEX leg to execute", candidate.venue, candidate.dex_venue);
+        }
+        let live = self.cfg.execution_mode == ExecutionMode::Live;
+        if live && !self.accounts.has_venue(&candidate.venue) {
+            bail!("no order client for {}", candidate.venue);
+        }
+        if !self.risk.can_execute() {
+            bail!("trading is paused or outside its window");
+        }
+        self.risk.check_venue(&candidate.venue, &candidate.direction)?;
+
+        let cex_buy = candidate.direction == ArbDirection::BuyCex;
+        // a sized candidate was priced through the book
+        let cex_price = candidate.size.map_or(candidate.cex_price, |sized| sized.cex_price);
+        let (px, qty) = self.risk.check_order(&candidate.venue, cex_buy, cex_price, size)?;
+        let base = if cex_buy { -qty } else { qty };
+        let deltas = [("base", base), ("quote", -base * px)];
+        self.risk.check_exposure(&deltas)?;
+        let reservation = match &self.capital {
+            Some(capital) => Some(capital.reserve(&candidate.book, px * qty)?),
+            None => None,
+        };
+        self.risk.throttle(&candidate.pair)?;
+        let account = match live {
+            false => None,
+            true => Some(self.accounts.route(&candidate.venue, &candidate.pair, &candidate.book, cex_buy, (px, qty))?),
+        };
+        Ok(Checked { px, qty, account, deltas, reservation })
+    }
+
+    // both legs at the prices they were quoted at, `px` the CEX one as
+    // rounded onto the venue's grid
+    fn paper_fill(&self, candidate: &Candidate, px: f64, qty: f64, mut inputs: Value) -> Execution {
+        let dex_price = candidate.size.map_or(candidate.dex_price, |sized| sized.dex_price);
+        let prices = (px, dex_price);
+        let fill =
+            match self.paper.fill(&candidate.venue, &candidate.dex_venue, &candidate.direction, qty, prices, candidate.profit.gas_cost) {
+                Ok(fill) => fill,
+                Err(e) => return self.skipped(candidate, inputs, e),
+            };
+        info!(
+            "📝 PAPER {:?} {:.4} on {}/{}: cex @ ${:.4}, dex @ ${:.4}, pnl ${:.4}, cumulative ${:.4} [{}]",
+            candidate.direction,
+            qty,
+            candidate.venue,
+            candidate.dex_venue,
+            fill.cex_price,
+            fill.dex_price,
+            fill.pnl,
+            fill.cumulative_pnl,
+            candidate.id
+        );
+        inputs["paper_fill"] = json!(fill);
+        let decision = Decision::skipped(AUDIT_SOURCE, "arb", "paper", inputs);
+        self.risk.audit.record(decision.with_opportunities(vec![candidate.id]));
+        Execution::Paper(fill)
+    }
+
+    // fees may have spiked since the opportunity was quoted
+    async fn check_gas(&self, px: f64) -> Result<()> {
+        if self.cfg.max_gas_price_gwei.is_none() && self.cfg.max_gas_cost_usd.is_none() {
+            return Ok(());
+        }
+        let gas_price_wei = self.provider.get_gas_price().await?;
+        check_gas_ceiling(&self.cfg, gas_price_wei, gas_token_usd(&self.cfg, px))
+    }
+
+    // both legs at once, neither waits for the other
+    async fn send(
+        &self,
+        candidate: &Candidate,
+        account: &Account,
+        px: f64,
+        qty: f64,
+        deltas: &[(&str, f64)],
+    ) -> (Result<CexLeg>, Result<SwapLeg>) {
+        let cex = self.cex_leg(candidate, account, px, qty);
+        let dex = self.swap(&candidate.direction, qty, deltas);
+        if self.cfg.strategy != StrategyKind::LatencyArb {
+            return tokio::join!(cex, dex);
+        }
+
+        // latency arb leads with the DEX leg and hedges on its fill, or once
+        // it stayed unconfirmed for as long as the exposure may last
+        tokio::pin!(dex);
+        let max_exposure = Duration::from_millis(self.cfg.latency_max_exposure_ms);
+        match timeout(max_exposure, &mut dex).await {
+            Ok(Ok(leg)) => (cex.await, Ok(leg)),
+            Ok(Err(e)) => (Err(anyhow!("lead leg failed, not hedged")), Err(e)),
+            Err(_) => {
+                warn!("⏱️ lead leg of [{}] unconfirmed after {:?}, hedging", candidate.id, max_exposure);
+                tokio::join!(cex, dex)
+            }
+        }
+    }
+
+    // a HyperCore sell goes through the short seller when there is one
+    async fn cex_leg(&self, candidate: &Candidate, account: &Account, px: f64, qty: f64) -> Result<CexLeg> {
+        let cex_buy = candidate.direction == ArbDirection::BuyCex;
+        if let Some(seller) = &self.shorts
+            && !cex_buy
+            && account.venue() == "hyperliquid"
+        {
+            let (sell, fill) = seller.sell_free(candidate.id, qty, px).await?;
+            if sell == CexSell::FromInventory {
+                account.record_fill(false, &fill);
+            }
+            return Ok(CexLeg { fill, shorted: sell == CexSell::Short });
+        }
+        let id = ClientOrderId::new(candidate.id, Leg::Cex);
+        let fill = self.market_order(account, cex_buy, qty, px, id).await?;
+        Ok(CexLeg { fill, shorted: false })
+    }
+
+    // a market order on `account`, borrowing first what it lacks and paying
+    // a loan of what the fill brought in back out of it
+    async fn market_order(&self, account: &Account, is_buy: bool, base: f64, px: f64, id: ClientOrderId) -> Result<CexFill> {
+        let margin = self.margin_for(account, is_buy);
+        if let Some((margin, spent, _)) = &margin {
+            let holdings = account.orders.holdings().await?;
+            let (needed, held) =
+                if is_buy { (base * px, holdings.quote - holdings.bids_quote) } else { (base, holdings.base - holdings.asks) };
+            borrow_shortfall(&**margin, spent, needed, held).await?;
+        }
+        let fill = account.market_order(is_buy, base, px, id, &self.policy).await?;
+        if let Some((margin, _, received)) = &margin
+            && let Err(e) = repay(&**margin, account, received, is_buy).await
+        {
+            warn!("🏦 repaying {} on {} failed: {:#}", received, account.name, e);
+        }
+        Ok(fill)
+    }
+
+    // the margin account of `account`'s venue with the asset an order
+    // spends and the one it receives, when the venue's own keys trade it
+    // and either asset can be borrowed there
+    fn margin_for(&self, account: &Account, cex_buy: bool) -> Option<(Arc<dyn MarginAccount>, String, String)> {
+        if account.name != account.venue() {
+            return None;
+        }
+        let margin = self.margin.iter().find(|m| m.venue() == account.venue())?;
+        let (base, quote) = (self.cfg.pair_base.clone(), self.cfg.pair_quote.clone());
+        let (spent, received) = if cex_buy { (quote, base) } else { (base, quote) };
+        let borrowable = |asset: &str| self.cfg.borrow_rates.iter().any(|r| r.venue == account.venue() && r.asset == asset);
+        (borrowable(&spent) || borrowable(&received)).then(|| (margin.clone(), spent, received))
+    }
+
+    // `base` of the CEX fill traded back at market, on the account it went
+    // out on; a short is bought back right away instead of at its hold time
+    async fn unwind_cex(&self, candidate: &Candidate, account: &Account, leg: CexLeg, base: f64) -> Result<CexFill> {
+        if leg.shorted
+            && let Some(seller) = &self.shorts
+        {
+            return seller.cover(base, leg.fill.price).await;
+        }
+        let id = ClientOrderId::new(candidate.id, Leg::Cex).unwind();
+        let cex_buy = candidate.direction == ArbDirection::BuyCex;
+        self.market_order(account, !cex_buy, base, leg.fill.price, id).await
+    }
+
+    // the included swap swapped back, `qty` of it when only the `filled`
+    // share of a split landed
+    async fn unwind_dex(&self, candidate: &Candidate, qty: f64, deltas: &[(&str, f64)], filled: f64) -> Result<TxHash> {
+        let reverse = match candidate.direction {
+            ArbDirection::BuyCex => ArbDirection::BuyDex,
+            ArbDirection::BuyDex => ArbDirection::BuyCex,
+        };
+        let deltas: Vec<_> = deltas.iter().map(|(asset, delta)| (*asset, -delta * filled)).collect();
+        let leg = self.swap(&reverse, qty, &deltas).await?;
+        if let Some((share, e)) = leg.unfilled {
+            bail!("only {:.0}% swapped back by {}: {:#}", (1.0 - share) * 100.0, leg.tx, e);
+        }
+        Ok(leg.tx)
+    }
+
+    // sends the swap from a wallet of the pool, each leg of a split counted
+    // as its share of the exposure from then on, and waits for every leg
+    // that went out to be confirmed; the wallet is held until then. It
+    // fails only when none of them landed, the hash is the first that did
+    async fn swap(&self, direction: &ArbDirection, size: f64, deltas: &[(&str, f64)]) -> Result<SwapLeg> {
+        let wallet = self.wallets.acquire(&self.cfg, &*self.provider, direction).await?;
+        let (sent, mut failed) = self.send_swap(wallet.sender(), direction, size).await?;
+        for SentSwap { pending, share, .. } in &sent {
+            let deltas: Vec<_> = deltas.iter().map(|(asset, delta)| (*asset, delta * share)).collect();
+            self.risk.exposure.begin_leg(pending.hash, &deltas);
+        }
+
+        let mode = sent.first().map(|s| s.swap.mode);
+        let (mut first, mut fills, mut unfilled) = (None, Vec::new(), 1.0);
+        for SentSwap { pending, share, swap, quoted } in sent {
+            match self.confirmed(wallet.tracker(), pending).await {
+                Ok(hash) => {
+                    first.get_or_insert(hash);
+                    unfilled -= share;
+                    fills.push(verified(wallet.sender(), hash, &swap, quoted).await);
+                }
+                Err(e) => {
+                    warn!("⚠️ split leg of {:.0}% failed: {:#}", share * 100.0, e);
+                    failed.get_or_insert(e);
+                }
+            }
+        }
+        let (Some(tx), Some(mode)) = (first, mode) else {
+            return Err(failed.unwrap_or_else(|| anyhow!("no swap was sent")));
+        };
+        // the swap is done either way, only its PnL goes unrecorded
+        let fill = match fills.into_iter().collect::<Result<Vec<_>>>() {
+            Ok(fills) => VerifiedFill::combine(mode, &fills),
+            Err(e) => {
+                warn!("⚠️ swap {} not verified, its PnL is not recorded: {:#}", tx, e);
+                None
+            }
+        };
+        let unfilled = failed.map(|e| (unfilled.max(0.0), e));
+        Ok(SwapLeg { tx, wallet: wallet.address(), fill, unfilled })
+    }
+
+    // every sent swap with its share of `size`, and why the rest did not go
+    // out; v3 swaps are split across `extra_pools` as `plan_split` says when
+    // there are any. An error means nothing went out
+    async fn send_swap(&self, sender: &TxSender, direction: &ArbDirection, size: f64) -> Result<(Vec<SentSwap>, Option<anyhow::Error>)> {
+        let base_amount = Normalizer::new(&self.cfg).base_units(size);
+        if self.cfg.dex_kind == DexKind::V3 && !self.cfg.extra_pools.is_empty() {
+            return self.send_split(sender, direction, base_amount).await;
+        }
+
+        let quoted = self.router.quote(&*self.provider, direction, base_amount).await?;
+        if quoted == 0 {
+            bail!("no DEX liquidity for {:.4}", size);
+        }
+
+        let deadline = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + SWAP_DEADLINE_SECS;
+        let quoted = U256::from(quoted);
+        let swap = DexSwap::for_direction(&self.cfg, direction, base_amount, quoted);
+        let calldata = self.router.swap_calldata(&swap, sender.address(), U256::from(deadline)).await?;
+        let pending = sender.send_swap(calldata, TxOverrides::default()).await?;
+        Ok((vec![SentSwap { pending, share: 1.0, swap, quoted }], None))
+    }
+
+    // one swap per leg of the split, each through its pool's router; a leg
+    // that cannot go out leaves those already sent to be followed
+    async fn send_split(
+        &self,
+        sender: &TxSender,
+        direction: &ArbDirection,
+        base_amount: U256,
+    ) -> Result<(Vec<SentSwap>, Option<anyhow::Error>)> {
+        let provider = Arc::new(sender.provider().clone());
+        let mut cache_db = init_cache_db(provider.clone());
+        let plan = plan_split(&self.cfg, &provider, direction, base_amount, &mut cache_db).await?;
+
+        let deadline = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + SWAP_DEADLINE_SECS;
+        let mut sent = Vec::new();
+        for (leg, swap) in plan.legs.iter().zip(plan.swaps(&self.cfg, direction)) {
+            let router = UniswapV3::new(&self.cfg, leg.route.clone());
+            let pending = async {
+                let calldata = router.swap_calldata(&swap, sender.address(), U256::from(deadline)).await?;
+                sender.send_call(leg.route.router_addr, calldata, TxOverrides::default()).await
+            };
+            let pending = match pending.await {
+                Ok(pending) => pending,
+                Err(e) if sent.is_empty() => return Err(e),
+                Err(e) => return Ok((sent, Some(e.context(format!("split leg through pool {}", leg.route.pool_addr))))),
+            };
+            info!("🔀 split leg {} of {} base through pool {}", pending.hash, leg.base_amount, leg.route.pool_addr);
+            let share = f64::from(leg.base_amount) / f64::from(base_amount);
+            sent.push(SentSwap { pending, share, swap, quoted: U256::from(leg.quoted) });
+        }
+        Ok((sent, None))
+    }
+
+    // the hash of whichever of `tx` and its replacements the tracker
+    // confirmed, or why none of them will be
+    async fn confirmed(&self, tracker: &TrackerHandle, tx: PendingTx) -> Result<TxHash> {
+        let mut updates = tracker.subscribe();
+        let mut live = HashSet::from([tx.hash]);
+        self.track_tx(tx.hash);
+        tracker.track(tx)?;
+
+        loop {
+            let update = match updates.recv().await {
+                Ok(update) if live.contains(&update.hash) => update,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(n)) => {
+                    warn!("swap confirmation lagged, {} tx updates missed", n);
+                    continue;
+                }
+                Err(RecvError::Closed) => bail!("confirmation tracker stopped"),
+            };
+            match update.state {
+                TxState::Confirmed { success, .. } => {
+                    self.untrack_tx(update.hash);
+                    if !success {
+                        bail!("swap {} reverted", update.hash);
+                    }
+                    return Ok(update.hash);
+                }
+                TxState::Replaced { by } => {
+                    // the refresh ends the leg once the replacement lands
+                    self.risk.exposure.rekey_leg(update.hash, by);
+                    self.track_tx(by);
+                    live.insert(by);
+                }
+                TxState::Dropped { reason } => {
+                    self.untrack_tx(update.hash);
+                    live.remove(&update.hash);
+                    let Some(other) = live.iter().next() else {
+                        self.risk.exposure.end_leg(update.hash);
+                        bail!("swap {} dropped: {:?}", update.hash, reason);
+                    };
+                    self.risk.exposure.rekey_leg(update.hash, *other);
+                }
+                TxState::Pending | TxState::Included { .. } => {}
+            }
+        }
+    }
+
+    fn track_tx(&self, hash: TxHash) {
+        if let Some(state) = &self.state {
+            state.track_tx(hash);
+        }
+    }
+
+    fn untrack_tx(&self, hash: TxHash) {
+        if let Some(state) = &self.state {
+            state.untrack_tx(hash);
+        }
+    }
+
+    fn skipped(&self, candidate: &Candidate, inputs: Value, reason: anyhow::Error) -> Execution {
+        let reason = format!("{:#}", reason);
+        let decision = Decision::skipped(AUDIT_SOURCE, "arb", &reason, inputs);
+        self.risk.audit.record(decision.with_opportunities(vec![candidate.id]));
+        Execution::Skipped(reason)
+    }
+}
+
+// what the swap `hash` filled, read back from its receipt
+async fn verified(sender: &TxSender, hash: TxHash, swap: &DexSwap, quoted: U256) -> Result<VerifiedFill> {
+    let receipt = sender
+        .provider()
+        .get_transaction_receipt(hash)
+        .await?
+        .ok_or_else(|| anyhow!("no receipt for swap {}", hash))?;
+    verify_swap_fill(&receipt, swap, sender.address(), quoted)
+}
+
+// repays the `asset` loan of `account`'s venue from what is free of it
+async fn repay(margin: &dyn MarginAccount, account: &Account, asset: &str, cex_buy: bool) -> Result<f64> {
+    let holdings = account.orders.holdings().await?;
+    let free = if cex_buy { holdings.base - holdings.asks } else { holdings.quote - holdings.bids_quote };
+    repay_loan(margin, asset, free).await
+}
@@ -0,0 +1,247 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use log::warn;
+use reqwest::Method;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha512};
+
+use crate::clock;
+use crate::execution::cex::{CexFill, CexHoldings, CexOrders, OrderPolicy};
+use crate::execution::client_order::{ClientOrderId, Submitted, acked, is_ambiguous, submit};
+use crate::execution::margin::{MarginAccount, MarginLoan};
+use crate::settings::{Secret, Settings};
+
+/// Signed client for the Gate.io v4 API, for spot orders on
+/// `gateio_ticker` and borrowing and repaying on its unified margin
+/// account.
+pub struct GateioClient {
+    http: reqwest::Client,
+    base_url: String,
+    currency_pair: String,
+    // currencies of the pair, as the spot accounts list them
+    currencies: (String, String),
+    api_key: String,
+    api_secret: Secret,
+}
+
+impl GateioClient {
+    pub fn new(cfg: &Settings) -> Result<Self> {
+        let (Some(api_key), Some(api_secret)) = (&cfg.gateio_api_key, &cfg.gateio_api_secret) else {
+            bail!("gateio_api_key and gateio_api_secret are not set");
+        };
+        Ok(Self::with_keys(cfg, api_key, api_secret))
+    }
+
+    /// A client of another account, e.g. a sub-account in `cex_accounts`.
+    pub fn with_keys(cfg: &Settings, api_key: &str, api_secret: &Secret) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: cfg.gateio_rest_endpoint.trim_end_matches('/').to_string(),
+            currency_pair: cfg.gateio_ticker.clone(),
+            currencies: (cfg.pair_base.clone(), cfg.pair_quote.clone()),
+            api_key: api_key.to_string(),
+            api_secret: api_secret.clone(),
+        }
+    }
+
+    // errors come back as `{label, message}` bodies
+    async fn request(&self, method: Method, path: &str, query: &str, body: Option<Value>) -> Result<Value> {
+        clock::check_signing("gateio", clock::GATEIO_MAX_SKEW_MS)?;
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs().to_string();
+        let signature = sign(self.api_secret.expose(), method.as_str(), path, query, &body, &timestamp);
+
+        let mut url = format!("{}{}", self.base_url, path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        let response = self
+            .http
+            .request(method, url)
+            .header("Content-Type", "application/json")
+            .header("KEY", &self.api_key)
+            .header("Timestamp", timestamp)
+            .header("SIGN", signature)
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        let body: Value = if text.is_empty() { Value::Null } else { serde_json::from_str(&text)? };
+        if !status.is_success() {
+            bail!("gateio {}: {} {} ({})", path, body["label"], body["message"], status);
+        }
+        Ok(body)
+    }
+
+    async fn loan_action(&self, kind: &str, asset: &str, amount: f64) -> Result<()> {
+        let body = json!({
+            "currency": asset,
+            "type": kind,
+            "amount": amount.to_string(),
+            "currency_pair": self.currency_pair,
+        });
+        self.request(Method::POST, "/api/v4/margin/uni/loans", "", Some(body)).await?;
+        Ok(())
+    }
+
+    async fn place_market(&self, is_buy: bool, base: f64, price: f64, id: ClientOrderId) -> Result<Value> {
+        // market buys are sized in quote
+        let amount = if is_buy { (base * price * 1e8).round() / 1e8 } else { base };
+        let body = json!({
+            "text": id.gateio_text(),
+            "currency_pair": self.currency_pair,
+            "type": "market",
+            "side": if is_buy { "buy" } else { "sell" },
+            "amount": amount.to_string(),
+            "time_in_force": "ioc",
+        });
+        self.request(Method::POST, "/api/v4/spot/orders", "", Some(body)).await
+    }
+
+    // an order given up on may still reach the book late; one already done
+    // or never placed cannot be cancelled, and that is what the cancel is after
+    async fn cancel_quietly(&self, id: ClientOrderId) {
+        let path = format!("/api/v4/spot/orders/{}", id.gateio_text());
+        let query = format!("currency_pair={}", self.currency_pair);
+        if let Err(e) = self.request(Method::DELETE, &path, &query, None).await {
+            warn!("gateio order {} not cancelled: {:#}", id, e);
+        }
+    }
+
+    // an IOC order is done by the time it could be looked up, and only its
+    // trades keep the text it was placed under
+    async fn find_fill(&self, id: ClientOrderId) -> Result<Option<CexFill>> {
+        let query = format!("currency_pair={}&limit=100", self.currency_pair);
+        let trades = self.request(Method::GET, "/api/v4/spot/my_trades", &query, None).await?;
+        trades_fill(&trades, id)
+    }
+}
+
+/// Pair balances in Gate.io spot `accounts` and what the open `orders`
+/// would still trade. Currencies never held are missing and read as 0.
+pub fn holdings(accounts: &Value, orders: &Value, base: &str, quote: &str) -> Result<CexHoldings> {
+    let balance = |currency: &str| -> Result<f64> {
+        match accounts.as_array().and_then(|a| a.iter().find(|c| c["currency"] == currency)) {
+            Some(c) => Ok(amount(&c["available"])? + amount(&c["locked"])?),
+            None => Ok(0.0),
+        }
+    };
+    let mut holdings = CexHoldings { base: balance(base)?, quote: balance(quote)?, ..Default::default() };
+    for order in orders.as_array().into_iter().flatten() {
+        let left = amount(&order["left"])?;
+        let price = amount(&order["price"])?;
+        if order["side"] == "buy" {
+            holdings.bids += left;
+            holdings.bids_quote += left * price;
+        } else {
+            holdings.asks += left;
+            holdings.asks_quote += left * price;
+        }
+    }
+    Ok(holdings)
+}
+
+/// What a done Gate.io spot order filled; one without any fill is an error.
+pub fn order_fill(order: &Value) -> Result<CexFill> {
+    let quote = amount(&order["filled_total"])?;
+    let price = amount(&order["avg_deal_price"])?;
+    if quote <= 0.0 || price <= 0.0 {
+        bail!("gateio order {} {} without a fill", order["text"], order["finish_as"]);
+    }
+    Ok(CexFill { base: quote / price, price })
+}
+
+/// The fill of the order placed under `id` among `trades`, `None` when
+/// none of them is its.
+pub fn trades_fill(trades: &Value, id: ClientOrderId) -> Result<Option<CexFill>> {
+    let text = id.gateio_text();
+    let (mut base, mut quote) = (0.0, 0.0);
+    for trade in trades.as_array().into_iter().flatten().filter(|t| t["text"] == text.as_str()) {
+        let size = amount(&trade["amount"])?;
+        base += size;
+        quote += size * amount(&trade["price"])?;
+    }
+    Ok((base > 0.0).then(|| CexFill { base, price: quote / base }))
+}
+
+/// Hex HMAC-SHA512 Gate.io signs requests with, over the method, path,
+/// query, hex SHA-512 of the body and timestamp.
+pub fn sign(secret: &str, method: &str, path: &str, query: &str, body: &str, timestamp: &str) -> String {
+    let payload = format!("{}\n{}\n{}\n{}\n{}", method, path, query, hex::encode(Sha512::digest(body)), timestamp);
+    let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn amount(value: &Value) -> Result<f64> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("unexpected gateio amount {}", value))?
+        .parse()
+        .map_err(Into::into)
+}
+
+#[async_trait]
+impl MarginAccount for GateioClient {
+    fn venue(&self) -> &'static str {
+        "gateio"
+    }
+
+    async fn borrow(&self, asset: &str, amount: f64) -> Result<()> {
+        self.loan_action("borrow", asset, amount).await
+    }
+
+    async fn repay(&self, asset: &str, amount: f64) -> Result<()> {
+        self.loan_action("repay", asset, amount).await
+    }
+
+    async fn loan(&self, asset: &str) -> Result<MarginLoan> {
+        let query = format!("currency_pair={}&currency={}", self.currency_pair, asset);
+        let loans = self.request(Method::GET, "/api/v4/margin/uni/loans", &query, None).await?;
+        let mut borrowed = 0.0;
+        for loan in loans.as_array().into_iter().flatten() {
+            borrowed += amount(&loan["amount"])?;
+        }
+
+        // an hourly estimate per currency
+        let rates = self.request(Method::GET, "/api/v4/margin/uni/estimate_rate", &format!("currencies={}", asset), None).await?;
+        Ok(MarginLoan { borrowed, hourly_rate: amount(&rates[asset])? })
+    }
+}
+
+#[async_trait]
+impl CexOrders for GateioClient {
+    fn venue(&self) -> &'static str {
+        "gateio"
+    }
+
+    // IOC orders are done by the time they are answered, so only the
+    // answer is bounded
+    async fn market_order(&self, is_buy: bool, base: f64, price: f64, id: ClientOrderId, policy: &OrderPolicy) -> Result<CexFill> {
+        let place = || acked(policy.ack_timeout, self.place_market(is_buy, base, price, id));
+        let lookup = || acked(policy.ack_timeout, self.find_fill(id));
+        match submit(id, policy.attempts, place, lookup).await {
+            Ok(Submitted::Placed(order)) => order_fill(&order),
+            Ok(Submitted::Found(fill)) => Ok(fill),
+            Err(e) => {
+                if is_ambiguous(&e) {
+                    self.cancel_quietly(id).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn holdings(&self) -> Result<CexHoldings> {
+        let (base, quote) = &self.currencies;
+        let accounts = self.request(Method::GET, "/api/v4/spot/accounts", "", None).await?;
+        let query = format!("currency_pair={}&status=open", self.currency_pair);
+        let orders = self.request(Method::GET, "/api/v4/spot/orders", &query, None).await?;
+        holdings(&accounts, &orders, base, quote)
+    }
+}
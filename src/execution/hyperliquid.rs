@@ -0,0 +1,502 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::{
+    primitives::{Address, B128, B256, keccak256},
+    signers::{SignerSync, local::PrivateKeySigner},
+    sol,
+    sol_types::{SolStruct, eip712_domain},
+};
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+
+use crate::adapters::hyperliquid::market_meta;
+use crate::clock;
+use crate::execution::cex::{CexFill, CexOrders, OrderPolicy};
+use crate::execution::client_order::{ClientOrderId, Submitted, acked, submit};
+use crate::settings::Settings;
+use crate::strategy::market_making::round_px;
+
+// spot assets are addressed as 10000 + spot index in order actions
+pub const SPOT_ASSET_OFFSET: u32 = 10000;
+
+sol! {
+    struct Agent {
+        string source;
+        bytes32 connectionId;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Tif {
+    Alo,
+    Ioc,
+    Gtc,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitOrderType {
+    pub tif: Tif,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderType {
+    pub limit: LimitOrderType,
+}
+
+// field names and order are part of the signed msgpack payload
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderWire {
+    #[serde(rename = "a")]
+    pub asset: u32,
+    #[serde(rename = "b")]
+    pub is_buy: bool,
+    #[serde(rename = "p")]
+    pub limit_px: String,
+    #[serde(rename = "s")]
+    pub sz: String,
+    #[serde(rename = "r")]
+    pub reduce_only: bool,
+    #[serde(rename = "t")]
+    pub order_type: OrderType,
+    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
+    pub cloid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelWire {
+    #[serde(rename = "a")]
+    pub asset: u32,
+    #[serde(rename = "o")]
+    pub oid: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    Order { orders: Vec<OrderWire>, grouping: String },
+    Cancel { cancels: Vec<CancelWire> },
+}
+
+/// Outcome of a single order within an order action.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderStatus {
+    Resting { oid: u64 },
+    Filled {
+        #[serde(rename = "totalSz")]
+        total_sz: String,
+        #[serde(rename = "avgPx")]
+        avg_px: String,
+        oid: u64,
+    },
+    Error(String),
+}
+
+/// One token of a `spotClearinghouseState` response; `hold` is the part
+/// of `total` locked in resting orders.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SpotBalance {
+    pub coin: String,
+    pub token: u64,
+    pub total: String,
+    pub hold: String,
+}
+
+/// Perp account of a user as `clearinghouseState` reports it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerpMargin {
+    pub account_value: f64,
+    // collateral not backing open positions
+    pub withdrawable: f64,
+}
+
+/// An order as `orderStatus` reports it. `status` is "open", "filled",
+/// "canceled" or one of the rejection reasons; `sz` is what remains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderState {
+    pub status: String,
+    pub limit_px: f64,
+    pub orig_sz: f64,
+    pub sz: f64,
+}
+
+impl OrderState {
+    pub fn filled_sz(&self) -> f64 {
+        self.orig_sz - self.sz
+    }
+}
+
+/// Signed client for the Hyperliquid `/exchange` API, for executing the
+/// CEX leg on HyperCore spot or perps.
+pub struct HyperliquidClient {
+    http: reqwest::Client,
+    base_url: String,
+    signer: PrivateKeySigner,
+    vault: Option<Address>,
+    is_mainnet: bool,
+    submit_attempts: u32,
+}
+
+impl HyperliquidClient {
+    pub fn new(cfg: &Settings) -> Result<Self> {
+        let key = cfg
+            .hyperliquid_private_key
+            .as_ref()
+            .map(|key| key.expose())
+            .ok_or_else(|| anyhow!("hyperliquid_private_key is not set"))?;
+        let signer: PrivateKeySigner = key.parse().context("invalid hyperliquid_private_key")?;
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: cfg.hyperliquid_api_url.trim_end_matches('/').to_string(),
+            signer,
+            vault: cfg.hyperliquid_vault_addr,
+            is_mainnet: !cfg.hyperliquid_api_url.contains("testnet"),
+            submit_attempts: cfg.order_submit_attempts.max(1),
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Account orders trade for: the vault if set, else the signer.
+    pub fn account(&self) -> Address {
+        self.vault.unwrap_or_else(|| self.signer.address())
+    }
+
+    /// Limit order on `asset`; an `Ioc` order behaves as a marketable
+    /// order capped at `limit_px`.
+    pub async fn place_order(
+        &self,
+        asset: u32,
+        is_buy: bool,
+        limit_px: f64,
+        sz: f64,
+        tif: Tif,
+        cloid: Option<B128>,
+    ) -> Result<OrderStatus> {
+        self.order(asset, is_buy, limit_px, sz, false, tif, cloid).await
+    }
+
+    /// `place_order` under `id` that is never placed twice: an attempt
+    /// whose answer got lost is looked up by `id` before it is retried, up
+    /// to `order_submit_attempts` in all.
+    pub async fn place_order_once(
+        &self,
+        asset: u32,
+        is_buy: bool,
+        limit_px: f64,
+        sz: f64,
+        tif: Tif,
+        id: ClientOrderId,
+    ) -> Result<Submitted<OrderStatus, OrderState>> {
+        submit(
+            id,
+            self.submit_attempts,
+            || self.place_order(asset, is_buy, limit_px, sz, tif, Some(id.cloid())),
+            || self.order_status_by_cloid(self.account(), id.cloid()),
+        )
+        .await
+    }
+
+    /// Immediate-or-cancel order on perp `asset` that can only shrink the
+    /// position, e.g. to buy back a short.
+    pub async fn reduce_position(&self, asset: u32, is_buy: bool, limit_px: f64, sz: f64) -> Result<OrderStatus> {
+        self.order(asset, is_buy, limit_px, sz, true, Tif::Ioc, None).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn order(
+        &self,
+        asset: u32,
+        is_buy: bool,
+        limit_px: f64,
+        sz: f64,
+        reduce_only: bool,
+        tif: Tif,
+        cloid: Option<B128>,
+    ) -> Result<OrderStatus> {
+        let order = OrderWire {
+            asset,
+            is_buy,
+            limit_px: float_to_wire(limit_px)?,
+            sz: float_to_wire(sz)?,
+            reduce_only,
+            order_type: OrderType { limit: LimitOrderType { tif } },
+            cloid: cloid.map(|c| c.to_string()),
+        };
+
+        let action = Action::Order {
+            orders: vec![order],
+            grouping: "na".to_string(),
+        };
+
+        let response = self.post_action(&action).await?;
+        let mut statuses: Vec<OrderStatus> =
+            serde_json::from_value(response["data"]["statuses"].clone()).context("unexpected order response")?;
+
+        statuses.pop().ok_or_else(|| anyhow!("order response without status"))
+    }
+
+    pub async fn cancel_order(&self, asset: u32, oid: u64) -> Result<()> {
+        let action = Action::Cancel {
+            cancels: vec![CancelWire { asset, oid }],
+        };
+
+        let response = self.post_action(&action).await?;
+        match response["data"]["statuses"].get(0) {
+            Some(Value::String(s)) if s == "success" => Ok(()),
+            Some(status) => bail!("cancel of {} rejected: {}", oid, status),
+            None => bail!("cancel response without status"),
+        }
+    }
+
+    /// Spot pairs and tokens of HyperCore, as the `spotMeta` info request
+    /// lists them.
+    pub async fn spot_meta(&self) -> Result<Value> {
+        self.info(json!({ "type": "spotMeta" })).await
+    }
+
+    /// What `user` is to Hyperliquid: "user", "agent" (an API wallet),
+    /// "vault", "subAccount" or "missing" when it never deposited.
+    pub async fn user_role(&self, user: Address) -> Result<String> {
+        let response = self.info(json!({ "type": "userRole", "user": user })).await?;
+
+        response["role"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("unexpected userRole response: {}", response))
+    }
+
+    /// Spot balances of `user` on HyperCore, one per token held.
+    pub async fn spot_balances(&self, user: Address) -> Result<Vec<SpotBalance>> {
+        let response = self.info(json!({ "type": "spotClearinghouseState", "user": user })).await?;
+
+        serde_json::from_value(response["balances"].clone()).context("unexpected spotClearinghouseState response")
+    }
+
+    /// Perp margin of `user`: account value and what is free for new
+    /// positions, both in USD.
+    pub async fn perp_margin(&self, user: Address) -> Result<PerpMargin> {
+        let response = self.info(json!({ "type": "clearinghouseState", "user": user })).await?;
+
+        let usd = |value: &Value| -> Result<f64> {
+            value
+                .as_str()
+                .ok_or_else(|| anyhow!("unexpected clearinghouseState response: {}", response))?
+                .parse()
+                .context("invalid amount in clearinghouseState response")
+        };
+        Ok(PerpMargin {
+            account_value: usd(&response["marginSummary"]["accountValue"])?,
+            withdrawable: usd(&response["withdrawable"])?,
+        })
+    }
+
+    /// State of order `oid` of `user`, `None` when Hyperliquid does not know it.
+    pub async fn order_status(&self, user: Address, oid: u64) -> Result<Option<OrderState>> {
+        self.query_order(user, json!(oid)).await
+    }
+
+    /// `order_status` of the order placed under client order id `cloid`.
+    pub async fn order_status_by_cloid(&self, user: Address, cloid: B128) -> Result<Option<OrderState>> {
+        self.query_order(user, json!(cloid.to_string())).await
+    }
+
+    async fn query_order(&self, user: Address, oid: Value) -> Result<Option<OrderState>> {
+        let response = self.info(json!({ "type": "orderStatus", "user": user, "oid": oid })).await?;
+        if response["status"] == "unknownOid" {
+            return Ok(None);
+        }
+
+        let order = &response["order"];
+        let size = |field: &str| -> Result<f64> {
+            order["order"][field]
+                .as_str()
+                .ok_or_else(|| anyhow!("unexpected orderStatus response: {}", response))?
+                .parse()
+                .with_context(|| format!("invalid {} in orderStatus response", field))
+        };
+
+        Ok(Some(OrderState {
+            status: order["status"]
+                .as_str()
+                .ok_or_else(|| anyhow!("unexpected orderStatus response: {}", response))?
+                .to_string(),
+            limit_px: size("limitPx")?,
+            orig_sz: size("origSz")?,
+            sz: size("sz")?,
+        }))
+    }
+
+    async fn info(&self, request: Value) -> Result<Value> {
+        Ok(self
+            .http
+            .post(format!("{}/info", self.base_url))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn post_action(&self, action: &Action) -> Result<Value> {
+        clock::check_signing("hyperliquid", clock::HYPERLIQUID_MAX_SKEW_MS)?;
+        let nonce = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let signature = self.sign_l1_action(action, nonce)?;
+
+        let body = json!({
+            "action": action,
+            "nonce": nonce,
+            "signature": {
+                "r": format!("{:#x}", signature.r()),
+                "s": format!("{:#x}", signature.s()),
+                "v": 27 + signature.v() as u8,
+            },
+            "vaultAddress": self.vault,
+        });
+
+        let response: Value = self
+            .http
+            .post(format!("{}/exchange", self.base_url))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if response["status"] != "ok" {
+            bail!("hyperliquid rejected action: {}", response["response"]);
+        }
+
+        Ok(response["response"].clone())
+    }
+
+    /// L1 actions are signed as an EIP-712 `Agent` whose connection id is
+    /// the hash of the msgpack-encoded action, nonce and vault.
+    fn sign_l1_action(&self, action: &Action, nonce: u64) -> Result<alloy::signers::Signature> {
+        let connection_id = action_hash(action, nonce, self.vault)?;
+
+        let agent = Agent {
+            source: if self.is_mainnet { "a" } else { "b" }.to_string(),
+            connectionId: connection_id,
+        };
+        let domain = eip712_domain! {
+            name: "Exchange",
+            version: "1",
+            chain_id: 1337,
+            verifying_contract: Address::ZERO,
+        };
+
+        Ok(self.signer.sign_hash_sync(&agent.eip712_signing_hash(&domain))?)
+    }
+}
+
+/// The `hyperliquid` CEX venue: market orders on HyperCore spot pair
+/// `mm_spot_pair_index`, sent as IOC limits `hyperliquid_ioc_slippage_bps`
+/// through the price they take. Sizes are rounded down to the base
+/// token's szDecimals, read from `spotMeta` with the first order.
+pub struct HyperliquidSpot {
+    client: HyperliquidClient,
+    pair_index: u32,
+    slippage_bps: u32,
+    sz_decimals: OnceCell<u32>,
+}
+
+impl HyperliquidSpot {
+    pub fn new(cfg: &Settings) -> Result<Self> {
+        Ok(Self {
+            client: HyperliquidClient::new(cfg)?,
+            pair_index: cfg.mm_spot_pair_index,
+            slippage_bps: cfg.hyperliquid_ioc_slippage_bps,
+            sz_decimals: OnceCell::new(),
+        })
+    }
+
+    async fn sz_decimals(&self) -> Result<u32> {
+        let load = || async { Ok::<_, anyhow::Error>(market_meta(&self.client.spot_meta().await?, self.pair_index)?.qty_precision) };
+        self.sz_decimals.get_or_try_init(load).await.copied()
+    }
+}
+
+#[async_trait]
+impl CexOrders for HyperliquidSpot {
+    fn venue(&self) -> &'static str {
+        "hyperliquid"
+    }
+
+    // IOC orders are done by the time they are answered, so only the
+    // answer is bounded
+    async fn market_order(&self, is_buy: bool, base: f64, price: f64, id: ClientOrderId, policy: &OrderPolicy) -> Result<CexFill> {
+        let slippage = self.slippage_bps as f64 / 10000.0;
+        let limit_px = if is_buy { round_px(price * (1.0 + slippage), true) } else { round_px(price * (1.0 - slippage), false) };
+        let sz = round_sz(base, self.sz_decimals().await?);
+        if sz <= 0.0 {
+            bail!("{} base rounds to nothing on hyperliquid", base);
+        }
+        let asset = SPOT_ASSET_OFFSET + self.pair_index;
+        let place = || acked(policy.ack_timeout, self.client.place_order(asset, is_buy, limit_px, sz, Tif::Ioc, Some(id.cloid())));
+        let lookup = || acked(policy.ack_timeout, self.client.order_status_by_cloid(self.client.account(), id.cloid()));
+        let (base, price) = submitted(submit(id, policy.attempts, place, lookup).await?)?;
+        Ok(CexFill { base, price })
+    }
+}
+
+/// `sz` rounded down to `sz_decimals`, what an order size may carry.
+pub fn round_sz(sz: f64, sz_decimals: u32) -> f64 {
+    let scale = 10f64.powi(sz_decimals as i32);
+    // snap float noise first so an exact size does not lose a step
+    (sz * scale + 1e-9).floor() / scale
+}
+
+/// Size and average price of an immediate-or-cancel fill.
+pub fn filled(status: OrderStatus) -> Result<(f64, f64)> {
+    match status {
+        OrderStatus::Filled { total_sz, avg_px, .. } => Ok((total_sz.parse()?, avg_px.parse()?)),
+        OrderStatus::Resting { oid } => Err(anyhow!("ioc order {} rested", oid)),
+        OrderStatus::Error(e) => Err(anyhow!("order rejected: {}", e)),
+    }
+}
+
+/// As `filled`, for an order that may only have been found by its client
+/// order id: `orderStatus` has no average price, the limit bounds it.
+pub fn submitted(submitted: Submitted<OrderStatus, OrderState>) -> Result<(f64, f64)> {
+    match submitted {
+        Submitted::Placed(status) => filled(status),
+        Submitted::Found(state) if state.filled_sz() > 0.0 => Ok((state.filled_sz(), state.limit_px)),
+        Submitted::Found(state) => Err(anyhow!("ioc order ended {} without a fill", state.status)),
+    }
+}
+
+pub fn action_hash(action: &Action, nonce: u64, vault: Option<Address>) -> Result<B256> {
+    let mut data = rmp_serde::to_vec_named(action)?;
+    data.extend_from_slice(&nonce.to_be_bytes());
+
+    match vault {
+        Some(vault) => {
+            data.push(1);
+            data.extend_from_slice(vault.as_slice());
+        }
+        None => data.push(0),
+    }
+
+    Ok(keccak256(data))
+}
+
+/// Prices and sizes go over the wire as decimal strings with at most 8
+/// decimals and no trailing zeros.
+pub fn float_to_wire(x: f64) -> Result<String> {
+    let rounded = format!("{:.8}", x);
+    if (rounded.parse::<f64>()? - x).abs() >= 1e-12 {
+        bail!("{} has more than 8 decimals", x);
+    }
+
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    Ok(if trimmed == "-0" { "0" } else { trimmed }.to_string())
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// What is borrowed of an asset on a margin account.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MarginLoan {
+    pub borrowed: f64,
+    // current interest per hour, as a fraction of the borrowed amount
+    pub hourly_rate: f64,
+}
+
+/// A CEX margin account the bot borrows on, so short-selling and
+/// rebalancing never wait on a manual loan. See `inventory::borrow_shortfall`
+/// and `inventory::repay_loan`.
+#[async_trait]
+pub trait MarginAccount: Send + Sync {
+    fn venue(&self) -> &'static str;
+
+    async fn borrow(&self, asset: &str, amount: f64) -> Result<()>;
+
+    async fn repay(&self, asset: &str, amount: f64) -> Result<()>;
+
+    async fn loan(&self, asset: &str) -> Result<MarginLoan>;
+}
@@ -0,0 +1,17 @@
+pub mod accounts;
+pub mod bybit;
+pub mod cex;
+pub mod client_order;
+pub mod confirmations;
+pub mod core_bridge;
+pub mod dex;
+pub mod executor;
+pub mod gateio;
+pub mod hyperliquid;
+pub mod margin;
+pub mod paper;
+pub mod sender;
+pub mod short;
+pub mod split;
+pub mod verify;
+pub mod wallets;
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::arbitrage::ArbDirection;
+use crate::settings::Settings;
+
+/// Virtual holdings of one venue, in tokens of the pair.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PaperBalance {
+    pub base: f64,
+    pub quote: f64,
+}
+
+/// A simulated execution: the CEX leg at `cex_price`, the DEX leg at
+/// `dex_price`, paying `cex_fee` and `gas_cost` (quote).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaperFill {
+    pub direction: ArbDirection,
+    pub base: f64,
+    pub cex_price: f64,
+    pub dex_price: f64,
+    pub cex_fee: f64,
+    pub gas_cost: f64,
+    pub pnl: f64,
+    // realized over every paper fill so far
+    pub cumulative_pnl: f64,
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    balances: BTreeMap<String, PaperBalance>,
+    fills: usize,
+    pnl: f64,
+}
+
+/// Books executions in `execution_mode = "paper"` against virtual
+/// balances instead of sending them. Each venue starts with
+/// `paper_base_balance` and `paper_quote_balance`, and a fill it cannot
+/// pay for is refused like a live order would be.
+#[derive(Debug)]
+pub struct PaperLedger {
+    start: PaperBalance,
+    cex_fee_bps: u32,
+    ledger: Mutex<Ledger>,
+}
+
+impl PaperLedger {
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self {
+            start: PaperBalance { base: cfg.paper_base_balance, quote: cfg.paper_quote_balance },
+            cex_fee_bps: cfg.cex_fee_bps,
+            ledger: Mutex::default(),
+        }
+    }
+
+    /// Fills `base` on `cex_venue` at `cex_price` and on `dex_venue` at
+    /// `dex_price`, the DEX fee already in its price, and moves both
+    /// venues' balances. Gas is paid in quote on the DEX venue.
+    pub fn fill(
+        &self,
+        cex_venue: &str,
+        dex_venue: &str,
+        direction: &ArbDirection,
+        base: f64,
+        (cex_price, dex_price): (f64, f64),
+        gas_cost: f64,
+    ) -> Result<PaperFill> {
+        let cex_fee = base * cex_price * self.cex_fee_bps as f64 / 10_000.0;
+        let (cex_base, dex_base) = match direction {
+            ArbDirection::BuyCex => (base, -base),
+            ArbDirection::BuyDex => (-base, base),
+        };
+        let cex_quote = -cex_base * cex_price - cex_fee;
+        let dex_quote = -dex_base * dex_price - gas_cost;
+
+        let mut ledger = self.ledger.lock().unwrap();
+        let cex = ledger.balances.get(cex_venue).copied().unwrap_or(self.start);
+        let dex = ledger.balances.get(dex_venue).copied().unwrap_or(self.start);
+        let cex = PaperBalance { base: cex.base + cex_base, quote: cex.quote + cex_quote };
+        let dex = PaperBalance { base: dex.base + dex_base, quote: dex.quote + dex_quote };
+        for (venue, balance) in [(cex_venue, cex), (dex_venue, dex)] {
+            if balance.base < 0.0 || balance.quote < 0.0 {
+                bail!("paper balance on {} would go negative", venue);
+            }
+        }
+
+        ledger.balances.insert(cex_venue.to_string(), cex);
+        ledger.balances.insert(dex_venue.to_string(), dex);
+        let pnl = cex_quote + dex_quote;
+        ledger.fills += 1;
+        ledger.pnl += pnl;
+        Ok(PaperFill {
+            direction: direction.clone(),
+            base,
+            cex_price,
+            dex_price,
+            cex_fee,
+            gas_cost,
+            pnl,
+            cumulative_pnl: ledger.pnl,
+        })
+    }
+
+    /// The virtual balance of `venue`, its starting one before any fill.
+    pub fn balance(&self, venue: &str) -> PaperBalance {
+        self.ledger.lock().unwrap().balances.get(venue).copied().unwrap_or(self.start)
+    }
+
+    /// Paper fills so far and the PnL they realized.
+    pub fn totals(&self) -> (usize, f64) {
+        let ledger = self.ledger.lock().unwrap();
+        (ledger.fills, ledger.pnl)
+    }
+}
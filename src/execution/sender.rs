@@ -0,0 +1,248 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use alloy::{
+    consensus::Transaction,
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, TxHash, U256},
+    providers::{
+        Identity, Provider, ProviderBuilder, RootProvider,
+        fillers::{ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, NonceManager, WalletFiller},
+    },
+    rpc::{
+        client::{ClientBuilder, RpcClient},
+        types::TransactionRequest,
+    },
+    signers::local::PrivateKeySigner,
+};
+use alloy::{network::Network, transports::TransportResult};
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use log::{info, warn};
+use tokio::sync::Mutex;
+
+use crate::settings::{Secret, Settings};
+
+pub type SendProvider = FillProvider<
+    JoinFill<
+        JoinFill<JoinFill<JoinFill<Identity, GasFiller>, NonceFiller<LocalNonceManager>>, ChainIdFiller>,
+        WalletFiller<EthereumWallet>,
+    >,
+    RootProvider,
+>;
+
+/// Hands out nonces from a local counter so back-to-back sends don't wait
+/// on the node. Unlike alloy's cached manager it can be reset, for when a
+/// sent transaction vanished and left a gap.
+#[derive(Debug, Clone, Default)]
+pub struct LocalNonceManager {
+    next: Arc<Mutex<Option<u64>>>,
+}
+
+impl LocalNonceManager {
+    /// Re-reads the nonce from the node on the next send.
+    pub async fn reset(&self) {
+        *self.next.lock().await = None;
+    }
+}
+
+#[async_trait]
+impl NonceManager for LocalNonceManager {
+    async fn get_next_nonce<P, N>(&self, provider: &P, address: Address) -> TransportResult<u64>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        let mut next = self.next.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => provider.get_transaction_count(address).await?,
+        };
+        *next = Some(nonce + 1);
+        Ok(nonce)
+    }
+}
+
+/// Per-transaction overrides of what the fillers would otherwise pick.
+#[derive(Debug, Clone, Default)]
+pub struct TxOverrides {
+    pub gas_limit: Option<u64>,
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+    // replacing a stuck transaction reuses its nonce
+    pub nonce: Option<u64>,
+    pub value: Option<U256>,
+}
+
+/// A broadcast transaction, not yet known to be included.
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub hash: TxHash,
+    pub nonce: u64,
+    pub to: Address,
+    pub calldata: Bytes,
+    pub value: U256,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub sent_at: Instant,
+}
+
+/// Signs and broadcasts transactions from the bot wallet. Nonce, gas and
+/// chain id are filled by the provider unless overridden.
+pub struct TxSender {
+    provider: SendProvider,
+    nonces: LocalNonceManager,
+    from: Address,
+    router: Address,
+}
+
+impl TxSender {
+    pub async fn new(cfg: &Settings) -> Result<Self> {
+        let key = match (&cfg.private_key, &cfg.private_key_file) {
+            (Some(key), _) => key.expose().to_string(),
+            (None, Some(path)) => {
+                std::fs::read_to_string(path).with_context(|| format!("failed to read private_key_file {}", path))?
+            }
+            (None, None) => bail!("private_key is not set"),
+        };
+        let signer: PrivateKeySigner = key.trim().parse().context("invalid private_key")?;
+
+        let from = signer.address();
+        if from != cfg.self_addr {
+            bail!("private_key is for {} but self_addr is {}", from, cfg.self_addr);
+        }
+
+        Self::connect(cfg.send_rpc(), signer, cfg.router_addr).await
+    }
+
+    /// Sender for another wallet than `self_addr`'s, e.g. one of
+    /// `executor_private_keys`.
+    pub async fn with_key(cfg: &Settings, key: &Secret) -> Result<Self> {
+        Self::connect(cfg.send_rpc(), signer(key)?, cfg.router_addr).await
+    }
+
+    /// Sender on any endpoint, e.g. a local anvil fork.
+    pub async fn connect(url: &str, signer: PrivateKeySigner, router: Address) -> Result<Self> {
+        let client = ClientBuilder::default().connect(url).await?;
+        Ok(Self::on_client(client, signer, router))
+    }
+
+    /// Sender over an already built client, e.g. one answered in-process.
+    pub fn on_client(client: RpcClient, signer: PrivateKeySigner, router: Address) -> Self {
+        let from = signer.address();
+        let nonces = LocalNonceManager::default();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .with_gas_estimation()
+            .with_nonce_management(nonces.clone())
+            .fetch_chain_id()
+            .wallet(signer)
+            .connect_client(client);
+
+        Self { provider, nonces, from, router }
+    }
+
+    pub fn address(&self) -> Address {
+        self.from
+    }
+
+    pub fn provider(&self) -> &SendProvider {
+        &self.provider
+    }
+
+    /// Forgets the local nonce after a transaction vanished, so its nonce
+    /// is handed out again instead of every later send queueing behind it.
+    pub async fn reset_nonce(&self) {
+        warn!("resetting local nonce of {}", self.from);
+        self.nonces.reset().await;
+    }
+
+    /// Sends swap calldata to the router.
+    pub async fn send_swap(&self, calldata: Bytes, overrides: TxOverrides) -> Result<PendingTx> {
+        self.send_call(self.router, calldata, overrides).await
+    }
+
+    pub async fn send_call(&self, to: Address, calldata: Bytes, overrides: TxOverrides) -> Result<PendingTx> {
+        let mut tx = TransactionRequest::default()
+            .from(self.from)
+            .to(to)
+            .with_input(calldata.clone());
+
+        if let Some(gas_limit) = overrides.gas_limit {
+            tx.set_gas_limit(gas_limit);
+        }
+        if let Some(max_fee) = overrides.max_fee_per_gas {
+            tx.set_max_fee_per_gas(max_fee);
+        }
+        if let Some(priority_fee) = overrides.max_priority_fee_per_gas {
+            tx.set_max_priority_fee_per_gas(priority_fee);
+        }
+        if let Some(nonce) = overrides.nonce {
+            tx.set_nonce(nonce);
+        }
+        if let Some(value) = overrides.value {
+            tx.set_value(value);
+        }
+
+        // fill and sign first, so the nonce and fees actually used are known
+        let envelope = self
+            .provider
+            .fill(tx)
+            .await?
+            .try_into_envelope()
+            .map_err(|e| anyhow!("transaction was not signed: {}", e))?;
+        let (nonce, gas_limit, value) = (envelope.nonce(), envelope.gas_limit(), envelope.value());
+        let max_fee_per_gas = envelope.max_fee_per_gas();
+        let max_priority_fee_per_gas = envelope.max_priority_fee_per_gas().unwrap_or(max_fee_per_gas);
+
+        let pending = self.provider.send_tx_envelope(envelope).await?;
+        let hash = *pending.tx_hash();
+
+        info!("📤 sent tx {} to {} (nonce {}, max fee {:.2} gwei)", hash, to, nonce, max_fee_per_gas as f64 / 1e9);
+
+        Ok(PendingTx {
+            hash,
+            nonce,
+            to,
+            calldata,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            sent_at: Instant::now(),
+        })
+    }
+
+    /// Re-sends `tx` under the same nonce with both fees raised by
+    /// `bump_pct`, so nodes accept it as a replacement.
+    pub async fn replace(&self, tx: &PendingTx, bump_pct: u32) -> Result<PendingTx> {
+        let bump = |fee: u128| fee + (fee * bump_pct as u128).div_ceil(100).max(1);
+
+        let overrides = TxOverrides {
+            gas_limit: Some(tx.gas_limit),
+            max_fee_per_gas: Some(bump(tx.max_fee_per_gas)),
+            max_priority_fee_per_gas: Some(bump(tx.max_priority_fee_per_gas)),
+            nonce: Some(tx.nonce),
+            value: Some(tx.value),
+        };
+
+        self.send_call(tx.to, tx.calldata.clone(), overrides).await
+    }
+
+    /// Takes the nonce of `tx` with an empty transfer to the wallet itself,
+    /// fees raised by `bump_pct`, so `tx` can no longer be included.
+    pub async fn cancel(&self, tx: &PendingTx, bump_pct: u32) -> Result<PendingTx> {
+        let cancel = PendingTx { to: self.from, calldata: Bytes::new(), value: U256::ZERO, gas_limit: 21_000, ..tx.clone() };
+        self.replace(&cancel, bump_pct).await
+    }
+}
+
+/// The wallet an `executor_private_keys` entry sends from.
+pub fn key_address(key: &Secret) -> Result<Address> {
+    Ok(signer(key)?.address())
+}
+
+fn signer(key: &Secret) -> Result<PrivateKeySigner> {
+    key.expose().trim().parse().context("invalid executor private key")
+}
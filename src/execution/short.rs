@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, bail};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::arbitrage::{Leg, PriceData};
+use crate::execution::cex::CexFill;
+use crate::execution::client_order::ClientOrderId;
+use crate::execution::hyperliquid::{HyperliquidClient, PerpMargin, SPOT_ASSET_OFFSET, Tif, filled, submitted};
+use crate::inventory::core_inventory;
+use crate::opportunity::OpportunityId;
+use crate::settings::Settings;
+use crate::strategy::market_making::round_px;
+
+/// Where the base sold on the CEX leg of a BuyDex comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CexSell {
+    // base already held on HyperCore spot
+    FromInventory,
+    // a perp short, bought back later
+    Short,
+}
+
+/// Sells from inventory when it covers `size`, else shorts the whole size
+/// so a leg is never split across both.
+pub fn plan_cex_sell(held_base: f64, size: f64) -> CexSell {
+    if held_base >= size { CexSell::FromInventory } else { CexSell::Short }
+}
+
+/// Fails unless `margin` can back a short of `notional` USD at up to
+/// `max_leverage` times the free collateral.
+pub fn check_margin(margin: &PerpMargin, notional: f64, max_leverage: f64) -> Result<()> {
+    let available = margin.withdrawable.max(0.0) * max_leverage;
+    if notional > available {
+        bail!("short of ${:.2} exceeds ${:.2} available at {}x", notional, available, max_leverage);
+    }
+    Ok(())
+}
+
+/// A short opened for the CEX leg, not bought back yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenShort {
+    pub size: f64,
+    pub entry_px: f64,
+    pub opened_ms: u64,
+}
+
+/// Open shorts shared by the seller and the cover task. Clones share it.
+#[derive(Debug, Clone, Default)]
+pub struct ShortBook {
+    shorts: Arc<Mutex<Vec<OpenShort>>>,
+}
+
+impl ShortBook {
+    pub fn open(&self, short: OpenShort) {
+        if let Ok(mut shorts) = self.shorts.lock() {
+            shorts.push(short);
+        }
+    }
+
+    pub fn all(&self) -> Vec<OpenShort> {
+        self.shorts.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Takes `size` out of the newest shorts, e.g. once bought back early.
+    pub fn close(&self, mut size: f64) {
+        let Ok(mut shorts) = self.shorts.lock() else {
+            return;
+        };
+        while size > 0.0
+            && let Some(newest) = shorts.last_mut()
+        {
+            if newest.size > size {
+                newest.size -= size;
+                break;
+            }
+            size -= newest.size;
+            shorts.pop();
+        }
+    }
+
+    /// Base currently sold short.
+    pub fn total_size(&self) -> f64 {
+        self.all().iter().map(|s| s.size).sum()
+    }
+
+    /// Removes and returns the shorts open for at least `max_hold`.
+    pub fn take_due(&self, now_ms: u64, max_hold: Duration) -> Vec<OpenShort> {
+        let Ok(mut shorts) = self.shorts.lock() else {
+            return Vec::new();
+        };
+        let max_hold_ms = max_hold.as_millis() as u64;
+        let (due, open) = shorts.drain(..).partition(|s| now_ms.saturating_sub(s.opened_ms) >= max_hold_ms);
+        *shorts = open;
+        due
+    }
+}
+
+/// Executes the CEX sell of a BuyDex on HyperCore, from spot inventory
+/// when there is enough and otherwise as a perp short on
+/// `oracle_perp_index` once the margin check passes, so both directions
+/// trade from a one-sided inventory. Shorts go into `book` for
+/// `run_short_cover` to buy back.
+pub struct ShortSeller {
+    cfg: Settings,
+    client: HyperliquidClient,
+    book: ShortBook,
+}
+
+impl ShortSeller {
+    pub fn new(cfg: Settings, client: HyperliquidClient, book: ShortBook) -> Self {
+        Self { cfg, client, book }
+    }
+
+    /// Sells `size` base into `bid` with up to `short_slippage_bps` of
+    /// slippage, `held_base` being the spot balance free to sell. The order
+    /// goes out under the client order id of `opportunity`'s CEX leg.
+    pub async fn sell(&self, opportunity: OpportunityId, size: f64, bid: f64, held_base: f64) -> Result<(CexSell, CexFill)> {
+        let id = ClientOrderId::new(opportunity, Leg::Cex);
+        let limit_px = round_px(bid * (1.0 - self.cfg.short_slippage_bps as f64 / 10000.0), false);
+        match plan_cex_sell(held_base, size) {
+            CexSell::FromInventory => {
+                let asset = SPOT_ASSET_OFFSET + self.cfg.mm_spot_pair_index;
+                let (base, price) = submitted(self.client.place_order_once(asset, false, limit_px, size, Tif::Ioc, id).await?)?;
+                Ok((CexSell::FromInventory, CexFill { base, price }))
+            }
+            CexSell::Short => {
+                if !self.cfg.short_selling {
+                    bail!("{:.4} base held, {:.4} needed and short selling is off", held_base, size);
+                }
+                let margin = self.client.perp_margin(self.client.account()).await?;
+                check_margin(&margin, size * bid, self.cfg.short_max_leverage)?;
+
+                let (size, entry_px) = submitted(
+                    self.client.place_order_once(self.cfg.oracle_perp_index, false, limit_px, size, Tif::Ioc, id).await?,
+                )?;
+                info!("📉 shorted {:.4} base at ${:.4} [{}]", size, entry_px, opportunity);
+                self.book.open(OpenShort { size, entry_px, opened_ms: now_ms() });
+                Ok((CexSell::Short, CexFill { base: size, price: entry_px }))
+            }
+        }
+    }
+
+    /// As `sell`, with the spot base not locked in orders read first.
+    pub async fn sell_free(&self, opportunity: OpportunityId, size: f64, bid: f64) -> Result<(CexSell, CexFill)> {
+        let core = core_inventory(&self.cfg, &self.client).await?;
+        self.sell(opportunity, size, bid, core.base - core.base_hold).await
+    }
+
+    /// Buys back `size` of a short right away at up to `short_slippage_bps`
+    /// over `ask`, e.g. when the DEX leg it hedged failed.
+    pub async fn cover(&self, size: f64, ask: f64) -> Result<CexFill> {
+        let limit_px = round_px(ask * (1.0 + self.cfg.short_slippage_bps as f64 / 10000.0), true);
+        let (base, price) = filled(self.client.reduce_position(self.cfg.oracle_perp_index, true, limit_px, size).await?)?;
+        self.book.close(base);
+        info!("📈 bought back {:.4} base at ${:.4}", base, price);
+        Ok(CexFill { base, price })
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Buys back shorts once they were open for `short_max_hold_secs`, with a
+/// reduce-only order at the perp price from `price_rx`. A short that fails
+/// to close stays in the book for the next round.
+pub async fn run_short_cover(
+    cfg: Settings,
+    client: HyperliquidClient,
+    book: ShortBook,
+    price_rx: watch::Receiver<Option<PriceData>>,
+) {
+    let mut tick = interval(Duration::from_secs(1));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let max_hold = Duration::from_secs(cfg.short_max_hold_secs);
+
+    loop {
+        tick.tick().await;
+        let Some(price) = price_rx.borrow().clone() else {
+            continue;
+        };
+
+        for short in book.take_due(now_ms(), max_hold) {
+            let limit_px = round_px(price.ask * (1.0 + cfg.short_slippage_bps as f64 / 10000.0), true);
+            match client.reduce_position(cfg.oracle_perp_index, true, limit_px, short.size).await.and_then(filled) {
+                Ok((size, px)) => {
+                    info!("📈 bought back {:.4} base at ${:.4}, pnl ${:.4}", size, px, (short.entry_px - px) * size);
+                    if size < short.size {
+                        book.open(OpenShort { size: short.size - size, ..short });
+                    }
+                }
+                Err(e) => {
+                    warn!("buying back {:.4} base failed: {:#}", short.size, e);
+                    book.open(short);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::Provider,
+};
+
+use revm::database::{AlloyDB, CacheDB, WrapDatabaseAsync};
+
+use anyhow::{Result, bail};
+
+use crate::arbitrage::ArbDirection;
+use crate::execution::dex::DexSwap;
+use crate::helpers::abi::{
+    decode_quote_output_response_for, decode_quote_response_for, quote_calldata_for,
+    quote_exact_output_calldata_for,
+};
+use crate::helpers::revm::{hydrate_pool_state, pin_cache_db_block, revm_call};
+use crate::settings::{QuoterKind, Settings};
+
+#[derive(Debug, Clone)]
+pub struct PoolRoute {
+    pub pool_addr: Address,
+    pub quoter_addr: Address,
+    pub quoter_kind: QuoterKind,
+    pub router_addr: Address,
+    pub fee_tier: u32,
+}
+
+impl PoolRoute {
+    /// The primary pool followed by every `extra_pools` entry.
+    pub fn all(cfg: &Settings) -> Vec<Self> {
+        let primary = Self {
+            pool_addr: cfg.pool_addr,
+            quoter_addr: cfg.quoter_v2_addr,
+            quoter_kind: cfg.quoter_kind,
+            router_addr: cfg.router_addr,
+            fee_tier: cfg.dex_fee_tier,
+        };
+
+        let extras = cfg.extra_pools.iter().map(|p| Self {
+            pool_addr: p.pool_addr,
+            quoter_addr: p.quoter_addr.unwrap_or(cfg.quoter_v2_addr),
+            quoter_kind: p.quoter_kind,
+            router_addr: p.router_addr.unwrap_or(cfg.router_addr),
+            fee_tier: p.fee_tier,
+        });
+
+        std::iter::once(primary).chain(extras).collect()
+    }
+}
+
+/// Cumulative quote-token amounts for `k * step` base, `k = 0..=steps`.
+/// Amounts received when selling base, amounts paid when buying it.
+/// The ladder is cut short at the first step the pool cannot fill.
+#[derive(Debug, Clone)]
+pub struct QuoteLadder {
+    pub route: PoolRoute,
+    pub step: U256,
+    pub amounts: Vec<u128>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitLeg {
+    pub route: PoolRoute,
+    pub base_amount: U256,
+    pub quoted: u128,
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitPlan {
+    pub legs: Vec<SplitLeg>,
+    pub total_quoted: u128,
+}
+
+impl SplitPlan {
+    pub fn swaps(&self, cfg: &Settings, direction: &ArbDirection) -> Vec<DexSwap> {
+        self.legs
+            .iter()
+            .map(|leg| {
+                let mut swap =
+                    DexSwap::for_direction(cfg, direction, leg.base_amount, U256::from(leg.quoted));
+                swap.fee = leg.route.fee_tier;
+                swap
+            })
+            .collect()
+    }
+}
+
+/// Splits `base_amount` across all configured pools so the total quote
+/// received (selling) is maximised or the total paid (buying) minimised.
+pub async fn plan_split<P: Provider + Clone>(
+    cfg: &Settings,
+    provider: &Arc<P>,
+    direction: &ArbDirection,
+    base_amount: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<SplitPlan> {
+    let steps = cfg.split_ladder_steps.max(1);
+    let step = base_amount / U256::from(steps);
+    if step.is_zero() {
+        bail!("trade size {base_amount} too small for {steps} ladder steps");
+    }
+
+    // every ladder is quoted against the same block
+    let block_number = provider.get_block_number().await?;
+    pin_cache_db_block(cache_db, provider, block_number);
+
+    let mut ladders = Vec::new();
+    for route in PoolRoute::all(cfg) {
+        hydrate_pool_state(cache_db, provider, route.pool_addr, block_number).await?;
+        ladders.push(build_ladder(cfg, route, direction, step, steps, cache_db));
+    }
+
+    let filled = allocate(&ladders, steps, direction)?;
+
+    // rounding dust from `base_amount / steps` goes to the largest leg,
+    // which is quoted again at its full size
+    let dust = base_amount - step * U256::from(steps);
+    let largest = (0..filled.len()).max_by_key(|&i| filled[i]).unwrap_or(0);
+
+    let mut legs = Vec::new();
+    for (i, (ladder, slices)) in ladders.into_iter().zip(filled).enumerate() {
+        if slices == 0 {
+            continue;
+        }
+
+        let mut leg = SplitLeg {
+            quoted: ladder.amounts[slices],
+            route: ladder.route,
+            base_amount: step * U256::from(slices),
+        };
+        if i == largest && !dust.is_zero() {
+            leg.base_amount += dust;
+            leg.quoted = quote_route(cfg, &leg.route, direction, leg.base_amount, cache_db)?;
+        }
+        legs.push(leg);
+    }
+
+    let total_quoted = legs.iter().map(|l| l.quoted).sum();
+    Ok(SplitPlan { legs, total_quoted })
+}
+
+fn build_ladder<P: Provider + Clone>(
+    cfg: &Settings,
+    route: PoolRoute,
+    direction: &ArbDirection,
+    step: U256,
+    steps: usize,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> QuoteLadder {
+    let mut amounts = vec![0];
+
+    for k in 1..=steps {
+        match quote_route(cfg, &route, direction, step * U256::from(k), cache_db) {
+            Ok(amount) => amounts.push(amount),
+            Err(_) => break,
+        }
+    }
+
+    QuoteLadder { route, step, amounts }
+}
+
+// quote-token amount for trading `volume` base through `route`
+fn quote_route<P: Provider + Clone>(
+    cfg: &Settings,
+    route: &PoolRoute,
+    direction: &ArbDirection,
+    volume: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<u128> {
+    match direction {
+        // selling base on the DEX
+        ArbDirection::BuyCex => {
+            let calldata = quote_calldata_for(
+                route.quoter_kind,
+                cfg.weth_addr,
+                cfg.usdt_addr,
+                volume,
+                route.fee_tier,
+                cfg.self_addr,
+            );
+            revm_call(cfg.self_addr, route.quoter_addr, calldata, cache_db)
+                .and_then(|r| decode_quote_response_for(route.quoter_kind, r))
+        }
+        // buying base on the DEX
+        ArbDirection::BuyDex => {
+            let calldata = quote_exact_output_calldata_for(
+                route.quoter_kind,
+                cfg.usdt_addr,
+                cfg.weth_addr,
+                volume,
+                route.fee_tier,
+                cfg.self_addr,
+            );
+            revm_call(cfg.self_addr, route.quoter_addr, calldata, cache_db)
+                .and_then(|r| decode_quote_output_response_for(route.quoter_kind, r))
+        }
+    }
+}
+
+/// Greedily hands out ladder slices to the pool with the best marginal
+/// price. Quotes are concave in size, so this yields the optimal split at
+/// ladder resolution. Returns the number of slices per ladder.
+pub fn allocate(ladders: &[QuoteLadder], steps: usize, direction: &ArbDirection) -> Result<Vec<usize>> {
+    let mut filled = vec![0; ladders.len()];
+
+    for _ in 0..steps {
+        let candidates = ladders
+            .iter()
+            .enumerate()
+            .filter(|(i, l)| filled[*i] + 1 < l.amounts.len())
+            .map(|(i, l)| (i, l.amounts[filled[i] + 1].saturating_sub(l.amounts[filled[i]])));
+
+        let best = match direction {
+            ArbDirection::BuyCex => candidates.max_by_key(|(_, marginal)| *marginal),
+            ArbDirection::BuyDex => candidates.min_by_key(|(_, marginal)| *marginal),
+        };
+
+        let Some((i, _)) = best else {
+            bail!("insufficient liquidity across {} pools", ladders.len());
+        };
+        filled[i] += 1;
+    }
+
+    Ok(filled)
+}
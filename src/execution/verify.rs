@@ -0,0 +1,140 @@
+use alloy::{
+    primitives::{Address, I256, U256},
+    rpc::types::TransactionReceipt,
+};
+use anyhow::{Result, bail};
+use log::{info, warn};
+
+use crate::execution::dex::{DexSwap, SwapMode};
+use crate::helpers::abi::{Swap, Transfer};
+
+/// What a DEX swap actually did, read back from its receipt.
+#[derive(Debug, Clone)]
+pub struct VerifiedFill {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    // from the quoter, before slippage
+    pub quoted_in: U256,
+    pub quoted_out: U256,
+    // positive when the fill was worse than quoted
+    pub shortfall_bps: f64,
+    pub gas_used: u64,
+    pub effective_gas_price: u128,
+}
+
+impl VerifiedFill {
+    /// The legs of one split swap as a single fill, all traded in `mode`.
+    pub fn combine(mode: SwapMode, fills: &[VerifiedFill]) -> Option<Self> {
+        let first = fills.first()?;
+        let sum = |amount: fn(&VerifiedFill) -> U256| fills.iter().map(amount).fold(U256::ZERO, |a, b| a + b);
+        let (amount_in, amount_out) = (sum(|f| f.amount_in), sum(|f| f.amount_out));
+        let (quoted_in, quoted_out) = (sum(|f| f.quoted_in), sum(|f| f.quoted_out));
+        let gas_used = fills.iter().map(|f| f.gas_used).sum::<u64>();
+        let gas_paid = fills.iter().map(|f| f.gas_used as u128 * f.effective_gas_price).sum::<u128>();
+
+        Some(Self {
+            amount_in,
+            amount_out,
+            quoted_in,
+            quoted_out,
+            shortfall_bps: match mode {
+                SwapMode::ExactInput => shortfall_bps(quoted_out, amount_out),
+                SwapMode::ExactOutput => -shortfall_bps(quoted_in, amount_in),
+            },
+            gas_used,
+            effective_gas_price: gas_paid.checked_div(gas_used as u128).unwrap_or(first.effective_gas_price),
+        })
+    }
+}
+
+/// Nets the token transfers of `recipient` in the receipt and cross-checks
+/// them against the pool's Swap events. `quoted` is the quoter amount the
+/// swap was built from (amountOut for exact input, amountIn for exact
+/// output).
+pub fn verify_swap_fill(
+    receipt: &TransactionReceipt,
+    swap: &DexSwap,
+    recipient: Address,
+    quoted: U256,
+) -> Result<VerifiedFill> {
+    if !receipt.status() {
+        bail!("swap tx {} reverted", receipt.transaction_hash);
+    }
+
+    let mut spent = I256::ZERO;
+    let mut received = I256::ZERO;
+    let mut pool_in = U256::ZERO;
+    let mut pool_out = U256::ZERO;
+    let zero_for_one = swap.token_in < swap.token_out;
+
+    for log in receipt.inner.logs() {
+        if let Ok(transfer) = log.log_decode::<Transfer>() {
+            let Transfer { from, to, value } = transfer.inner.data;
+            let value = I256::from_raw(value);
+
+            if log.address() == swap.token_in {
+                if from == recipient {
+                    spent += value;
+                }
+                if to == recipient {
+                    spent -= value;
+                }
+            } else if log.address() == swap.token_out {
+                if to == recipient {
+                    received += value;
+                }
+                if from == recipient {
+                    received -= value;
+                }
+            }
+        } else if let Ok(event) = log.log_decode::<Swap>() {
+            let (amount0, amount1) = (event.inner.data.amount0, event.inner.data.amount1);
+            let (amount_in, amount_out) = if zero_for_one { (amount0, amount1) } else { (amount1, amount0) };
+
+            pool_in += amount_in.unsigned_abs();
+            pool_out += amount_out.unsigned_abs();
+        }
+    }
+
+    if spent <= I256::ZERO || received <= I256::ZERO {
+        bail!("no net {} -> {} transfers of {} in tx {}", swap.token_in, swap.token_out, recipient, receipt.transaction_hash);
+    }
+    let (amount_in, amount_out) = (spent.unsigned_abs(), received.unsigned_abs());
+
+    // fee-on-transfer or rebasing tokens make these differ
+    if amount_in != pool_in || amount_out != pool_out {
+        warn!("transfers ({} in, {} out) disagree with pool swaps ({} in, {} out)", amount_in, amount_out, pool_in, pool_out);
+    }
+
+    let (quoted_in, quoted_out, shortfall_bps) = match swap.mode {
+        SwapMode::ExactInput => (swap.amount, quoted, shortfall_bps(quoted, amount_out)),
+        // paying more than quoted is the shortfall here
+        SwapMode::ExactOutput => (quoted, swap.amount, -shortfall_bps(quoted, amount_in)),
+    };
+
+    let fill = VerifiedFill {
+        amount_in,
+        amount_out,
+        quoted_in,
+        quoted_out,
+        shortfall_bps,
+        gas_used: receipt.gas_used,
+        effective_gas_price: receipt.effective_gas_price,
+    };
+
+    info!(
+        "✅ verified fill {}: in {} (quoted {}), out {} (quoted {}), shortfall {:.2} bps",
+        receipt.transaction_hash, fill.amount_in, fill.quoted_in, fill.amount_out, fill.quoted_out, fill.shortfall_bps
+    );
+
+    Ok(fill)
+}
+
+// how much `actual` fell short of `expected`, in bps of `expected`
+fn shortfall_bps(expected: U256, actual: U256) -> f64 {
+    let expected = f64::from(expected);
+    if expected == 0.0 {
+        return 0.0;
+    }
+    (expected - f64::from(actual)) / expected * 10000.0
+}
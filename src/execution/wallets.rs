@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use anyhow::{Result, bail};
+use log::warn;
+
+use crate::arbitrage::ArbDirection;
+use crate::execution::confirmations::TrackerHandle;
+use crate::execution::sender::TxSender;
+use crate::inventory::evm_inventory;
+use crate::settings::{Settings, WalletSelection};
+
+struct Wallet {
+    sender: Arc<TxSender>,
+    tracker: TrackerHandle,
+    busy: Arc<AtomicBool>,
+}
+
+/// A wallet taken for one swap, given back when dropped.
+pub struct WalletLease {
+    sender: Arc<TxSender>,
+    tracker: TrackerHandle,
+    busy: Arc<AtomicBool>,
+}
+
+impl WalletLease {
+    pub fn sender(&self) -> &TxSender {
+        &self.sender
+    }
+
+    /// Follows what the wallet sends until it is confirmed or dropped.
+    pub fn tracker(&self) -> &TrackerHandle {
+        &self.tracker
+    }
+
+    pub fn address(&self) -> Address {
+        self.sender.address()
+    }
+}
+
+impl Drop for WalletLease {
+    fn drop(&mut self) {
+        self.busy.store(false, Ordering::Release);
+    }
+}
+
+/// The wallets DEX legs go out from, each signing on its own nonces. A
+/// wallet carries one swap at a time, so swaps in flight at once never
+/// queue behind each other's nonces.
+pub struct WalletPool {
+    wallets: Vec<Wallet>,
+    selection: WalletSelection,
+    // where round-robin looks first
+    next: AtomicUsize,
+}
+
+impl WalletPool {
+    pub fn new(selection: WalletSelection) -> Self {
+        Self { wallets: Vec::new(), selection, next: AtomicUsize::new(0) }
+    }
+
+    /// `tracker` is the running `ConfirmationTracker` of `sender`.
+    pub fn add(&mut self, sender: Arc<TxSender>, tracker: TrackerHandle) {
+        self.wallets.push(Wallet { sender, tracker, busy: Arc::default() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wallets.is_empty()
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.wallets.iter().map(|w| w.sender.address()).collect()
+    }
+
+    /// A wallet without a swap in flight for a swap in `direction`, see
+    /// `WalletSelection`. Balances are only read for `Balance`.
+    pub async fn acquire(&self, cfg: &Settings, provider: &dyn Provider, direction: &ArbDirection) -> Result<WalletLease> {
+        if self.selection == WalletSelection::RoundRobin {
+            return self.lease(None);
+        }
+
+        let mut balances = Vec::with_capacity(self.wallets.len());
+        for wallet in &self.wallets {
+            let balance = match evm_inventory(cfg, provider, wallet.sender.address()).await {
+                // selling base into the pool for BuyCex, paying quote for BuyDex
+                Ok(inventory) if *direction == ArbDirection::BuyCex => inventory.base,
+                Ok(inventory) => inventory.quote,
+                Err(e) => {
+                    warn!("balance of wallet {} unknown: {:#}", wallet.sender.address(), e);
+                    U256::ZERO
+                }
+            };
+            balances.push(balance);
+        }
+        self.lease(Some(&balances))
+    }
+
+    /// Takes the first idle wallet in round-robin order, or with `balances`
+    /// (per wallet, in the order added) the idle one holding the most.
+    pub fn lease(&self, balances: Option<&[U256]>) -> Result<WalletLease> {
+        if self.wallets.is_empty() {
+            bail!("no wallet to sign the swap");
+        }
+
+        let start = self.next.load(Ordering::Relaxed);
+        let mut order: Vec<usize> = (0..self.wallets.len()).map(|i| (start + i) % self.wallets.len()).collect();
+        if let Some(balances) = balances {
+            // stable, so equal balances keep round-robin order
+            order.sort_by_key(|i| std::cmp::Reverse(balances.get(*i).copied().unwrap_or_default()));
+        }
+
+        for i in order {
+            let wallet = &self.wallets[i];
+            if wallet.busy.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+                self.next.store(i + 1, Ordering::Relaxed);
+                return Ok(WalletLease {
+                    sender: wallet.sender.clone(),
+                    tracker: wallet.tracker.clone(),
+                    busy: wallet.busy.clone(),
+                });
+            }
+        }
+        bail!("all {} wallets have a swap in flight", self.wallets.len())
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{Address, TxHash, U256};
+use alloy::providers::Provider;
+use anyhow::{Result, bail};
+use log::warn;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::execution::cex::{CexHoldings, CexOrders};
+use crate::execution::hyperliquid::HyperliquidClient;
+use crate::inventory::{AssetBalance, InFlightTransfer, core_inventory, evm_inventory};
+use crate::normalize::Normalizer;
+use crate::settings::{ExposureLimit, Settings};
+
+/// Where exposure to an asset is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExposureSource {
+    OnChain,
+    Cex,
+    // what resting orders would add if they filled
+    OpenOrders,
+}
+
+/// Exposure to one asset in its own units. `net` nets longs against
+/// shorts, `gross` adds them up.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AssetExposure {
+    pub net: f64,
+    pub gross: f64,
+}
+
+impl AssetExposure {
+    fn add(&mut self, long: f64, short: f64) {
+        self.net += long - short;
+        self.gross += long + short;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Book {
+    limits: Vec<ExposureLimit>,
+    // long and short amount per asset, source and CEX account, empty for
+    // sources not set per account
+    holdings: BTreeMap<(String, ExposureSource, String), (f64, f64)>,
+    // balance changes of legs sent but not settled
+    in_flight: BTreeMap<TxHash, Vec<(String, f64)>>,
+    // funds between venues by transfer id
+    transfers: BTreeMap<String, InFlightTransfer>,
+}
+
+/// Exposure per asset across on-chain and CEX balances, open orders,
+/// in-flight legs and transfers between venues, checked against `exposure_limits` before every
+/// execution. Assets are `base` and `quote` of the configured pair. Clones
+/// share it; without limits everything passes.
+#[derive(Debug, Clone, Default)]
+pub struct Exposure {
+    book: Arc<Mutex<Book>>,
+}
+
+impl Exposure {
+    pub fn new(limits: Vec<ExposureLimit>) -> Self {
+        Self { book: Arc::new(Mutex::new(Book { limits, ..Default::default() })) }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Book)) {
+        if let Ok(mut book) = self.book.lock() {
+            f(&mut book);
+        }
+    }
+
+    /// Sets a balance, negative for a short or a loan.
+    pub fn set_balance(&self, source: ExposureSource, asset: &str, amount: f64) {
+        self.set(source, asset, amount.max(0.0), (-amount).max(0.0));
+    }
+
+    /// Sets what resting orders would buy (`long`) and sell (`short`).
+    pub fn set_orders(&self, asset: &str, long: f64, short: f64) {
+        self.set(ExposureSource::OpenOrders, asset, long, short);
+    }
+
+    /// Sets the balances and open orders of a CEX account, apart from those
+    /// of other accounts.
+    pub fn set_cex(&self, account: &str, holdings: &CexHoldings) {
+        let entries = [
+            (ExposureSource::Cex, "base", holdings.base.max(0.0), (-holdings.base).max(0.0)),
+            (ExposureSource::Cex, "quote", holdings.quote.max(0.0), (-holdings.quote).max(0.0)),
+            (ExposureSource::OpenOrders, "base", holdings.bids, holdings.asks),
+            (ExposureSource::OpenOrders, "quote", holdings.asks_quote, holdings.bids_quote),
+        ];
+        self.update(|b| {
+            for (source, asset, long, short) in entries {
+                b.holdings.insert((asset.to_string(), source, account.to_string()), (long, short));
+            }
+        });
+    }
+
+    fn set(&self, source: ExposureSource, asset: &str, long: f64, short: f64) {
+        self.update(|b| {
+            b.holdings.insert((asset.to_string(), source, String::new()), (long, short));
+        });
+    }
+
+    /// Counts a sent leg's balance changes until `end_leg`.
+    pub fn begin_leg(&self, hash: TxHash, deltas: &[(&str, f64)]) {
+        let deltas = deltas.iter().map(|(asset, delta)| (asset.to_string(), *delta)).collect();
+        self.update(|b| {
+            b.in_flight.insert(hash, deltas);
+        });
+    }
+
+    /// Moves a leg's balance changes to the transaction that replaced it.
+    pub fn rekey_leg(&self, from: TxHash, to: TxHash) {
+        self.update(|b| {
+            if let Some(deltas) = b.in_flight.remove(&from) {
+                b.in_flight.insert(to, deltas);
+            }
+        });
+    }
+
+    pub fn end_leg(&self, hash: TxHash) {
+        self.update(|b| {
+            b.in_flight.remove(&hash);
+        });
+    }
+
+    pub fn in_flight(&self) -> Vec<TxHash> {
+        self.book.lock().map(|b| b.in_flight.keys().copied().collect()).unwrap_or_default()
+    }
+
+    /// Counts funds leaving a venue as in flight until `end_transfer`, so
+    /// they stay in equity and exposure while no balance shows them.
+    pub fn begin_transfer(&self, transfer: InFlightTransfer) {
+        self.update(|b| {
+            b.transfers.insert(transfer.id.clone(), transfer);
+        });
+    }
+
+    /// Forgets a transfer once the receiving venue credited it.
+    pub fn end_transfer(&self, id: &str) -> Option<InFlightTransfer> {
+        self.book.lock().ok().and_then(|mut b| b.transfers.remove(id))
+    }
+
+    pub fn transfers(&self) -> Vec<InFlightTransfer> {
+        self.book.lock().map(|b| b.transfers.values().cloned().collect()).unwrap_or_default()
+    }
+
+    /// On-chain and CEX balances of `asset` apart from transfers in flight.
+    pub fn balance(&self, asset: &str) -> AssetBalance {
+        self.book.lock().map(|b| b.balance(asset)).unwrap_or_default()
+    }
+
+    pub fn exposure(&self, asset: &str) -> AssetExposure {
+        self.book.lock().map(|b| b.exposure(asset)).unwrap_or_default()
+    }
+
+    /// Fails if executing `deltas` would take an asset beyond its limits.
+    /// Trades that shrink an exposure already over its limit pass, so the
+    /// bot can always work its way back.
+    pub fn check(&self, deltas: &[(&str, f64)]) -> Result<()> {
+        let Ok(book) = self.book.lock() else {
+            bail!("exposure book lock poisoned");
+        };
+        for limit in &book.limits {
+            let now = book.exposure(&limit.asset);
+            let mut after = now;
+            for (_, delta) in deltas.iter().filter(|(asset, _)| *asset == limit.asset) {
+                after.add(delta.max(0.0), (-delta).max(0.0));
+            }
+
+            if let Some(max_net) = limit.max_net
+                && after.net.abs() > max_net
+                && after.net.abs() > now.net.abs()
+            {
+                bail!("{} net exposure would reach {:.4}, limit {:.4}", limit.asset, after.net, max_net);
+            }
+            if let Some(max_gross) = limit.max_gross
+                && after.gross > max_gross
+                && after.gross > now.gross
+            {
+                bail!("{} gross exposure would reach {:.4}, limit {:.4}", limit.asset, after.gross, max_gross);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Book {
+    fn exposure(&self, asset: &str) -> AssetExposure {
+        let mut exposure = AssetExposure::default();
+        for (_, (long, short)) in self.holdings.iter().filter(|((a, _, _), _)| a == asset) {
+            exposure.add(*long, *short);
+        }
+        for (_, delta) in self.in_flight.values().flatten().filter(|(a, _)| a == asset) {
+            exposure.add(delta.max(0.0), (-delta).max(0.0));
+        }
+        exposure.add(self.in_transfer(asset), 0.0);
+        exposure
+    }
+
+    fn balance(&self, asset: &str) -> AssetBalance {
+        let tradable = self
+            .holdings
+            .iter()
+            .filter(|((a, source, _), _)| a == asset && *source != ExposureSource::OpenOrders)
+            .map(|(_, (long, short))| long - short)
+            .sum();
+        AssetBalance { tradable, in_flight: self.in_transfer(asset) }
+    }
+
+    fn in_transfer(&self, asset: &str) -> f64 {
+        self.transfers.values().filter(|t| t.asset == asset).map(|t| t.amount).sum()
+    }
+}
+
+/// Refreshes the on-chain balances of all `wallets`, (with `client`)
+/// HyperCore balances and those and the open orders of the CEX `accounts`,
+/// named by venue or account, every
+/// `every`, first dropping in-flight legs that landed so they are not
+/// counted twice. Transfers in flight beyond `transfer_stale_secs` are
+/// warned about, they are only dropped by whoever started them.
+pub async fn run_exposure_refresh(
+    cfg: Settings,
+    exposure: Exposure,
+    provider: Arc<dyn Provider>,
+    client: Option<HyperliquidClient>,
+    wallets: Vec<Address>,
+    accounts: Vec<(String, Arc<dyn CexOrders>)>,
+    every: Duration,
+) {
+    let mut tick = interval(every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let norm = Normalizer::new(&cfg);
+
+    loop {
+        tick.tick().await;
+
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+        for t in exposure.transfers() {
+            let secs = now_ms.saturating_sub(t.started_ms) / 1000;
+            if secs > cfg.transfer_stale_secs {
+                warn!("🚚 {:?} {} of {:.4} {} from {} to {} in flight for {}s", t.kind, t.id, t.amount, t.asset, t.from, t.to, secs);
+            }
+        }
+
+        for hash in exposure.in_flight() {
+            if let Ok(Some(_)) = provider.get_transaction_receipt(hash).await {
+                exposure.end_leg(hash);
+            }
+        }
+
+        // a wallet that failed to read would understate the total
+        match wallets_inventory(&cfg, &*provider, &wallets).await {
+            Ok((base, quote)) => {
+                exposure.set_balance(ExposureSource::OnChain, "base", norm.base(base));
+                exposure.set_balance(ExposureSource::OnChain, "quote", norm.quote(quote));
+            }
+            Err(e) => warn!("exposure refresh of HyperEVM balances failed: {:#}", e),
+        }
+        if let Some(client) = &client {
+            match core_inventory(&cfg, client).await {
+                Ok(core) => {
+                    exposure.set_balance(ExposureSource::Cex, "base", core.base);
+                    exposure.set_balance(ExposureSource::Cex, "quote", core.quote);
+                }
+                Err(e) => warn!("exposure refresh of HyperCore balances failed: {:#}", e),
+            }
+        }
+        for (name, orders) in &accounts {
+            match orders.holdings().await {
+                Ok(holdings) => exposure.set_cex(name, &holdings),
+                Err(e) => warn!("exposure refresh of {} balances failed: {:#}", name, e),
+            }
+        }
+    }
+}
+
+// raw base and quote held by all wallets together
+async fn wallets_inventory(cfg: &Settings, provider: &(impl Provider + ?Sized), wallets: &[Address]) -> Result<(U256, U256)> {
+    let (mut base, mut quote) = (U256::ZERO, U256::ZERO);
+    for wallet in wallets {
+        let evm = evm_inventory(cfg, provider, *wallet).await?;
+        base += evm.base;
+        quote += evm.quote;
+    }
+    Ok((base, quote))
+}
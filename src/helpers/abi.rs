@@ -9,6 +9,8 @@ use alloy::{
 
 use anyhow::Result;
 
+use crate::settings::QuoterKind;
+
 pub static ONE_ETHER: U256 = uint!(1_000_000_000_000_000_000_U256);
 
 sol! {
@@ -51,26 +53,328 @@ sol! {
     );
 }
 
+// QuoterV1 takes flat arguments and returns only the amount
+sol! {
+    interface IQuoterV1 {
+        function quoteExactInputSingle(
+            address tokenIn,
+            address tokenOut,
+            uint24 fee,
+            uint256 amountIn,
+            uint160 sqrtPriceLimitX96
+        ) external returns (uint256 amountOut);
+
+        function quoteExactOutputSingle(
+            address tokenIn,
+            address tokenOut,
+            uint24 fee,
+            uint256 amountOut,
+            uint160 sqrtPriceLimitX96
+        ) external returns (uint256 amountIn);
+    }
+}
+
+sol! {
+    struct ExactInputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint24 fee;
+        address recipient;
+        uint256 deadline;
+        uint256 amountIn;
+        uint256 amountOutMinimum;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    function exactInputSingle(ExactInputSingleParams calldata params)
+    external
+    payable
+    returns (uint256 amountOut);
+
+    struct ExactOutputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint24 fee;
+        address recipient;
+        uint256 deadline;
+        uint256 amountOut;
+        uint256 amountInMaximum;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    function exactOutputSingle(ExactOutputSingleParams calldata params)
+    external
+    payable
+    returns (uint256 amountIn);
+}
+
+// UniswapV2-style routers quote and swap along a token path
+sol! {
+    interface IUniswapV2Router {
+        function getAmountsOut(uint256 amountIn, address[] calldata path)
+            external view returns (uint256[] memory amounts);
+        function getAmountsIn(uint256 amountOut, address[] calldata path)
+            external view returns (uint256[] memory amounts);
+        function swapExactTokensForTokens(
+            uint256 amountIn,
+            uint256 amountOutMin,
+            address[] calldata path,
+            address to,
+            uint256 deadline
+        ) external returns (uint256[] memory amounts);
+        function swapTokensForExactTokens(
+            uint256 amountOut,
+            uint256 amountInMax,
+            address[] calldata path,
+            address to,
+            uint256 deadline
+        ) external returns (uint256[] memory amounts);
+    }
+}
+
+sol! {
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+
+        function getBasefee() external view returns (uint256 basefee);
+    }
+}
+
+sol! {
+    function balanceOf(address account) external view returns (uint256);
+    function allowance(address owner, address spender) external view returns (uint256);
+    function approve(address spender, uint256 amount) external returns (bool);
+    function transfer(address to, uint256 amount) external returns (bool);
+    function transferFrom(address from, address to, uint256 amount) external returns (bool);
+}
+
+sol! {
+    interface IUniswapV3Pool {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function fee() external view returns (uint24);
+    }
+}
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    // UniswapV3Pool; amounts are from the pool's side, positive when it received
+    event Swap(
+        address indexed sender,
+        address indexed recipient,
+        int256 amount0,
+        int256 amount1,
+        uint160 sqrtPriceX96,
+        uint128 liquidity,
+        int24 tick
+    );
+
+    // liquidity added to or removed from a position
+    event Mint(
+        address sender,
+        address indexed owner,
+        int24 indexed tickLower,
+        int24 indexed tickUpper,
+        uint128 amount,
+        uint256 amount0,
+        uint256 amount1
+    );
+    event Burn(
+        address indexed owner,
+        int24 indexed tickLower,
+        int24 indexed tickUpper,
+        uint128 amount,
+        uint256 amount0,
+        uint256 amount1
+    );
+}
+
+// wrapped native token (WHYPE)
+sol! {
+    function deposit() external payable;
+    function withdraw(uint256 wad) external;
+}
+
+// HyperCore system contract turning EVM calls into Core actions
+sol! {
+    interface ICoreWriter {
+        function sendRawAction(bytes calldata data) external;
+    }
+}
+
+// decoded with the declared return types: a u128 tuple would silently
+// truncate wider words instead of failing
 pub fn decode_quote_response(response: Bytes) -> Result<u128> {
-    let (amount_out, _, _, _) = <(u128, u128, u32, u128)>::abi_decode(&response)?;
-    Ok(amount_out)
+    let returns = quoteExactInputSingleCall::abi_decode_returns(&response)?;
+    Ok(returns.amountOut.try_into()?)
 }
 
 pub fn decode_quote_output_response(response: Bytes) -> Result<u128> {
-    let (amount_in, _, _, _) = <(u128, u128, u32, u128)>::abi_decode(&response)?;
-    Ok(amount_in)
+    let returns = quoteExactOutputSingleCall::abi_decode_returns(&response)?;
+    Ok(returns.amountIn.try_into()?)
 }
 
-pub fn quote_calldata(token_in: Address, token_out: Address, amount_in: U256, fee: u32) -> Bytes {
+pub fn decode_amount_response(response: Bytes) -> Result<u128> {
+    Ok(U256::abi_decode(&response)?.try_into()?)
+}
+
+pub fn decode_balance_response(response: Bytes) -> Result<U256> {
+    Ok(U256::abi_decode(&response)?)
+}
+
+// non-standard tokens (e.g. USDT) return nothing instead of `true`
+pub fn decode_bool_response(response: Bytes) -> Result<bool> {
+    if response.is_empty() {
+        return Ok(true);
+    }
+    Ok(bool::abi_decode(&response)?)
+}
+
+pub fn balance_of_calldata(account: Address) -> Bytes {
+    Bytes::from(balanceOfCall { account }.abi_encode())
+}
+
+pub fn allowance_calldata(owner: Address, spender: Address) -> Bytes {
+    Bytes::from(allowanceCall { owner, spender }.abi_encode())
+}
+
+pub fn approve_calldata(spender: Address, amount: U256) -> Bytes {
+    Bytes::from(approveCall { spender, amount }.abi_encode())
+}
+
+pub fn transfer_calldata(to: Address, amount: U256) -> Bytes {
+    Bytes::from(transferCall { to, amount }.abi_encode())
+}
+
+pub fn transfer_from_calldata(from: Address, to: Address, amount: U256) -> Bytes {
+    Bytes::from(transferFromCall { from, to, amount }.abi_encode())
+}
+
+pub fn deposit_calldata() -> Bytes {
+    Bytes::from(depositCall {}.abi_encode())
+}
+
+pub fn withdraw_calldata(wad: U256) -> Bytes {
+    Bytes::from(withdrawCall { wad }.abi_encode())
+}
+
+/// `CoreWriter.sendRawAction` calldata: a version byte, the big-endian
+/// 3-byte action id, then the abi-encoded action arguments.
+pub fn core_action_calldata(action_id: u32, args: Vec<u8>) -> Bytes {
+    let mut data = vec![1u8];
+    data.extend_from_slice(&action_id.to_be_bytes()[1..]);
+    data.extend(args);
+
+    Bytes::from(ICoreWriter::sendRawActionCall { data: data.into() }.abi_encode())
+}
+
+fn sqrt_price_limit_x96(token_in: Address, token_out: Address) -> U160 {
     let zero_for_one = token_in < token_out;
 
-    let sqrt_price_limit_x96: U160 = if zero_for_one {
+    if zero_for_one {
         "4295128749".parse().unwrap()
     } else {
         "1461446703485210103287273052203988822378723970341"
             .parse()
             .unwrap()
-    };
+    }
+}
+
+/// Exact-input quote calldata for any quoter kind. `caller` is only used by
+/// the router kind, as swap recipient.
+pub fn quote_calldata_for(
+    kind: QuoterKind,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    fee: u32,
+    caller: Address,
+) -> Bytes {
+    match kind {
+        QuoterKind::V2 => quote_calldata(token_in, token_out, amount_in, fee),
+        QuoterKind::V1 => Bytes::from(
+            IQuoterV1::quoteExactInputSingleCall {
+                tokenIn: token_in,
+                tokenOut: token_out,
+                fee: U24::from(fee),
+                amountIn: amount_in,
+                sqrtPriceLimitX96: sqrt_price_limit_x96(token_in, token_out),
+            }
+            .abi_encode(),
+        ),
+        QuoterKind::Router => exact_input_single_calldata(
+            token_in,
+            token_out,
+            fee,
+            caller,
+            U256::MAX,
+            amount_in,
+            U256::ZERO,
+        ),
+    }
+}
+
+/// Exact-output counterpart of `quote_calldata_for`.
+pub fn quote_exact_output_calldata_for(
+    kind: QuoterKind,
+    token_in: Address,
+    token_out: Address,
+    amount_out: U256,
+    fee: u32,
+    caller: Address,
+) -> Bytes {
+    match kind {
+        QuoterKind::V2 => quote_exact_output_calldata(token_in, token_out, amount_out, fee),
+        QuoterKind::V1 => Bytes::from(
+            IQuoterV1::quoteExactOutputSingleCall {
+                tokenIn: token_in,
+                tokenOut: token_out,
+                fee: U24::from(fee),
+                amountOut: amount_out,
+                sqrtPriceLimitX96: sqrt_price_limit_x96(token_in, token_out),
+            }
+            .abi_encode(),
+        ),
+        QuoterKind::Router => exact_output_single_calldata(
+            token_in,
+            token_out,
+            fee,
+            caller,
+            U256::MAX,
+            amount_out,
+            U256::MAX,
+        ),
+    }
+}
+
+pub fn decode_quote_response_for(kind: QuoterKind, response: Bytes) -> Result<u128> {
+    match kind {
+        QuoterKind::V2 => decode_quote_response(response),
+        QuoterKind::V1 | QuoterKind::Router => decode_amount_response(response),
+    }
+}
+
+pub fn decode_quote_output_response_for(kind: QuoterKind, response: Bytes) -> Result<u128> {
+    match kind {
+        QuoterKind::V2 => decode_quote_output_response(response),
+        QuoterKind::V1 | QuoterKind::Router => decode_amount_response(response),
+    }
+}
+
+pub fn quote_calldata(token_in: Address, token_out: Address, amount_in: U256, fee: u32) -> Bytes {
+    let sqrt_price_limit_x96 = sqrt_price_limit_x96(token_in, token_out);
 
     let params = QuoteExactInputSingleParams {
         tokenIn: token_in,
@@ -89,15 +393,7 @@ pub fn quote_exact_output_calldata(
     amount_out: U256,
     fee: u32,
 ) -> Bytes {
-    let zero_for_one = token_in < token_out;
-
-    let sqrt_price_limit_x96: U160 = if zero_for_one {
-        "4295128749".parse().unwrap()
-    } else {
-        "1461446703485210103287273052203988822378723970341"
-            .parse()
-            .unwrap()
-    };
+    let sqrt_price_limit_x96 = sqrt_price_limit_x96(token_in, token_out);
 
     let params = QuoteExactOutputSingleParams {
         tokenIn: token_in,
@@ -110,17 +406,226 @@ pub fn quote_exact_output_calldata(
     Bytes::from(quoteExactOutputSingleCall { params }.abi_encode())
 }
 
-pub fn build_tx(to: Address, from: Address, calldata: Bytes, base_fee: u128) -> TransactionRequest {
+pub fn exact_input_single_calldata(
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    recipient: Address,
+    deadline: U256,
+    amount_in: U256,
+    amount_out_minimum: U256,
+) -> Bytes {
+    let params = ExactInputSingleParams {
+        tokenIn: token_in,
+        tokenOut: token_out,
+        fee: U24::from(fee),
+        recipient,
+        deadline,
+        amountIn: amount_in,
+        amountOutMinimum: amount_out_minimum,
+        sqrtPriceLimitX96: U160::ZERO,
+    };
+
+    Bytes::from(exactInputSingleCall { params }.abi_encode())
+}
+
+pub fn exact_output_single_calldata(
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    recipient: Address,
+    deadline: U256,
+    amount_out: U256,
+    amount_in_maximum: U256,
+) -> Bytes {
+    let params = ExactOutputSingleParams {
+        tokenIn: token_in,
+        tokenOut: token_out,
+        fee: U24::from(fee),
+        recipient,
+        deadline,
+        amountOut: amount_out,
+        amountInMaximum: amount_in_maximum,
+        sqrtPriceLimitX96: U160::ZERO,
+    };
+
+    Bytes::from(exactOutputSingleCall { params }.abi_encode())
+}
+
+pub fn multicall3_calldata(calls: Vec<(Address, Bytes)>) -> Bytes {
+    let calls = calls
+        .into_iter()
+        .map(|(target, call_data)| IMulticall3::Call3 {
+            target,
+            allowFailure: false,
+            callData: call_data,
+        })
+        .collect();
+
+    Bytes::from(IMulticall3::aggregate3Call { calls }.abi_encode())
+}
+
+pub fn decode_multicall3_response(response: Bytes) -> Result<Vec<Bytes>> {
+    let results = IMulticall3::aggregate3Call::abi_decode_returns(&response)?;
+    Ok(results.into_iter().map(|r| r.returnData).collect())
+}
+
+pub fn get_basefee_calldata() -> Bytes {
+    Bytes::from(IMulticall3::getBasefeeCall {}.abi_encode())
+}
+
+pub fn pool_token0_calldata() -> Bytes {
+    Bytes::from(IUniswapV3Pool::token0Call {}.abi_encode())
+}
+
+pub fn pool_token1_calldata() -> Bytes {
+    Bytes::from(IUniswapV3Pool::token1Call {}.abi_encode())
+}
+
+pub fn pool_fee_calldata() -> Bytes {
+    Bytes::from(IUniswapV3Pool::feeCall {}.abi_encode())
+}
+
+pub fn decode_address_response(response: Bytes) -> Result<Address> {
+    Ok(Address::abi_decode(&response)?)
+}
+
+pub fn decode_pool_fee_response(response: Bytes) -> Result<u32> {
+    Ok(IUniswapV3Pool::feeCall::abi_decode_returns(&response)?.to())
+}
+
+/// Request for `eth_call`; sent transactions go through `TxSender`.
+pub fn call_tx(to: Address, from: Address, calldata: Bytes) -> TransactionRequest {
     TransactionRequest::default()
         .to(to)
         .from(from)
         .with_input(calldata)
-        .nonce(0)
-        .gas_limit(1000000)
-        .max_fee_per_gas(base_fee)
-        .max_priority_fee_per_gas(0)
-        .with_chain_id(999) // Hyperliquid mainnet chain ID
-        .build_unsigned()
-        .unwrap()
-        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, bytes};
+
+    use super::*;
+
+    // WHYPE/USDT0, 1 WHYPE through the 0.3% pool. Vectors are written out
+    // word by word rather than produced by the encoder under test.
+    const WHYPE: Address = address!("0x5555555555555555555555555555555555555555");
+    const USDT: Address = address!("0xb8ce59fc3717ada4c02eadf9682a9e934f625ebb");
+    const CALLER: Address = address!("0x1234567890123456789012345678901234567890");
+
+    const V2_EXACT_INPUT_CALLDATA: Bytes = bytes!(
+        "c6a5026a"
+        "0000000000000000000000005555555555555555555555555555555555555555"
+        "000000000000000000000000b8ce59fc3717ada4c02eadf9682a9e934f625ebb"
+        "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        "0000000000000000000000000000000000000000000000000000000000000bb8"
+        "00000000000000000000000000000000000000000000000000000001000276ad"
+    );
+
+    const V2_EXACT_OUTPUT_CALLDATA: Bytes = bytes!(
+        "bd21704a"
+        "000000000000000000000000b8ce59fc3717ada4c02eadf9682a9e934f625ebb"
+        "0000000000000000000000005555555555555555555555555555555555555555"
+        "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        "0000000000000000000000000000000000000000000000000000000000000bb8"
+        "000000000000000000000000fffd8963efd1fc6a506488495d951d5263988d25"
+    );
+
+    const V1_EXACT_INPUT_CALLDATA: Bytes = bytes!(
+        "f7729d43"
+        "0000000000000000000000005555555555555555555555555555555555555555"
+        "000000000000000000000000b8ce59fc3717ada4c02eadf9682a9e934f625ebb"
+        "0000000000000000000000000000000000000000000000000000000000000bb8"
+        "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        "00000000000000000000000000000000000000000000000000000001000276ad"
+    );
+
+    const V1_EXACT_OUTPUT_CALLDATA: Bytes = bytes!(
+        "30d07f21"
+        "000000000000000000000000b8ce59fc3717ada4c02eadf9682a9e934f625ebb"
+        "0000000000000000000000005555555555555555555555555555555555555555"
+        "0000000000000000000000000000000000000000000000000000000000000bb8"
+        "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+        "000000000000000000000000fffd8963efd1fc6a506488495d951d5263988d25"
+    );
+
+    // amountOut 41.512345 USDT, sqrtPriceX96After, 1 tick crossed, gas
+    const V2_EXACT_INPUT_RETURN: Bytes = bytes!(
+        "0000000000000000000000000000000000000000000000000000000002796d99"
+        "000000000000000000000000000000000000000000006c1889889a795eba4000"
+        "0000000000000000000000000000000000000000000000000000000000000001"
+        "00000000000000000000000000000000000000000000000000000000000148f5"
+    );
+
+    // amountIn 41.698702 USDT, 2 ticks crossed
+    const V2_EXACT_OUTPUT_RETURN: Bytes = bytes!(
+        "00000000000000000000000000000000000000000000000000000000027c458e"
+        "000000000000000000000000000000000000000000006c3460385ab4f2c06000"
+        "0000000000000000000000000000000000000000000000000000000000000002"
+        "0000000000000000000000000000000000000000000000000000000000018c01"
+    );
+
+    // a uint160 sqrtPriceX96After above u128::MAX, as pools pairing tokens of
+    // very different decimals report
+    const V2_WIDE_SQRT_PRICE_RETURN: Bytes = bytes!(
+        "0000000000000000000000000000000000000000000000000000000002796d99"
+        "0000000000000000000000000040000000000000000000000000000000003039"
+        "0000000000000000000000000000000000000000000000000000000000000000"
+        "00000000000000000000000000000000000000000000000000000000000148f5"
+    );
+
+    // amountOut of 2^128, beyond what the u128 amounts can hold
+    const V2_OVERFLOWING_AMOUNT_RETURN: Bytes = bytes!(
+        "0000000000000000000000000000000100000000000000000000000000000000"
+        "000000000000000000000000000000000000000000006c1889889a795eba4000"
+        "0000000000000000000000000000000000000000000000000000000000000001"
+        "00000000000000000000000000000000000000000000000000000000000148f5"
+    );
+
+    const V1_RETURN: Bytes = bytes!("0000000000000000000000000000000000000000000000000000000002796d99");
+
+    #[test]
+    fn quoter_v2_calldata_matches_vectors() {
+        assert_eq!(quote_calldata_for(QuoterKind::V2, WHYPE, USDT, ONE_ETHER, 3000, CALLER), V2_EXACT_INPUT_CALLDATA);
+        assert_eq!(
+            quote_exact_output_calldata_for(QuoterKind::V2, USDT, WHYPE, ONE_ETHER, 3000, CALLER),
+            V2_EXACT_OUTPUT_CALLDATA
+        );
+    }
+
+    #[test]
+    fn quoter_v1_calldata_matches_vectors() {
+        assert_eq!(quote_calldata_for(QuoterKind::V1, WHYPE, USDT, ONE_ETHER, 3000, CALLER), V1_EXACT_INPUT_CALLDATA);
+        assert_eq!(
+            quote_exact_output_calldata_for(QuoterKind::V1, USDT, WHYPE, ONE_ETHER, 3000, CALLER),
+            V1_EXACT_OUTPUT_CALLDATA
+        );
+    }
+
+    #[test]
+    fn quoter_v2_returns_decode_to_the_amount() {
+        assert_eq!(decode_quote_response_for(QuoterKind::V2, V2_EXACT_INPUT_RETURN).unwrap(), 41_512_345);
+        assert_eq!(decode_quote_output_response_for(QuoterKind::V2, V2_EXACT_OUTPUT_RETURN).unwrap(), 41_698_702);
+        assert_eq!(decode_quote_response_for(QuoterKind::V2, V2_WIDE_SQRT_PRICE_RETURN).unwrap(), 41_512_345);
+    }
+
+    #[test]
+    fn quoter_v1_returns_decode_to_the_amount() {
+        assert_eq!(decode_quote_response_for(QuoterKind::V1, V1_RETURN).unwrap(), 41_512_345);
+        assert_eq!(decode_quote_output_response_for(QuoterKind::V1, V1_RETURN).unwrap(), 41_512_345);
+    }
+
+    #[test]
+    fn truncated_returns_are_rejected() {
+        let truncated = V2_EXACT_INPUT_RETURN.slice(..96);
+        assert!(decode_quote_response(truncated).is_err());
+        assert!(decode_amount_response(Bytes::new()).is_err());
+    }
+
+    #[test]
+    fn amounts_beyond_u128_are_rejected_not_truncated() {
+        assert!(decode_quote_response(V2_OVERFLOWING_AMOUNT_RETURN).is_err());
+        assert!(decode_quote_output_response(V2_OVERFLOWING_AMOUNT_RETURN).is_err());
+    }
 }
@@ -1,2 +1,5 @@
 pub mod abi;
+pub mod profiler;
+pub mod provider;
+pub mod revert;
 pub mod revm;
@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, Bytes, FixedBytes},
+    providers::Provider,
+};
+
+use revm::{
+    Context, InspectEvm, Inspector, MainBuilder, MainContext,
+    context::{ContextTr, TxEnv},
+    context_interface::LocalContextTr,
+    database::{AlloyDB, CacheDB, WrapDatabaseAsync},
+    interpreter::{CallInput, CallInputs, CallOutcome},
+    primitives::TxKind,
+};
+
+use anyhow::Result;
+
+use crate::helpers::revert::call_output;
+
+/// Gas accounting for a single call frame.
+#[derive(Debug, Clone)]
+pub struct FrameGas {
+    pub depth: usize,
+    pub target: Address,
+    pub selector: Option<FixedBytes<4>>,
+    pub gas_limit: u64,
+    // includes gas spent by child frames
+    pub gas_used: u64,
+    pub success: bool,
+}
+
+/// Inspector recording gas per call frame, in call order.
+#[derive(Debug, Default)]
+pub struct GasProfiler {
+    pub frames: Vec<FrameGas>,
+    open: Vec<usize>,
+}
+
+impl<CTX: ContextTr> Inspector<CTX> for GasProfiler {
+    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        let selector = match &inputs.input {
+            CallInput::Bytes(bytes) => bytes.get(..4).map(FixedBytes::from_slice),
+            CallInput::SharedBuffer(range) => context
+                .local()
+                .shared_memory_buffer_slice(range.clone())
+                .and_then(|buf| buf.get(..4).map(FixedBytes::from_slice)),
+        };
+
+        self.open.push(self.frames.len());
+        self.frames.push(FrameGas {
+            depth: self.open.len() - 1,
+            target: inputs.target_address,
+            selector,
+            gas_limit: inputs.gas_limit,
+            gas_used: 0,
+            success: false,
+        });
+
+        None
+    }
+
+    fn call_end(&mut self, _context: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+        if let Some(idx) = self.open.pop() {
+            let frame = &mut self.frames[idx];
+            frame.gas_used = outcome.gas().spent();
+            frame.success = outcome.result.is_ok();
+        }
+    }
+}
+
+impl GasProfiler {
+    /// Gas spent in the frame itself, excluding its direct children.
+    pub fn self_gas(&self, idx: usize) -> u64 {
+        let depth = self.frames[idx].depth;
+        let children: u64 = self.frames[idx + 1..]
+            .iter()
+            .take_while(|f| f.depth > depth)
+            .filter(|f| f.depth == depth + 1)
+            .map(|f| f.gas_used)
+            .sum();
+
+        self.frames[idx].gas_used.saturating_sub(children)
+    }
+
+    /// Indented call tree with total and self gas per frame.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let selector = frame
+                .selector
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "-".to_string());
+
+            let _ = writeln!(
+                out,
+                "{:indent$}{} {} total {} self {} (limit {}){}",
+                "",
+                frame.target,
+                selector,
+                frame.gas_used,
+                self.self_gas(idx),
+                frame.gas_limit,
+                if frame.success { "" } else { " REVERTED" },
+                indent = frame.depth * 2,
+            );
+        }
+
+        out
+    }
+}
+
+/// `revm_call` with a `GasProfiler` attached.
+pub fn revm_call_profiled<P: Provider + Clone>(
+    from: Address,
+    to: Address,
+    calldata: Bytes,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<(Bytes, GasProfiler)> {
+    let mut evm = Context::mainnet()
+        .with_db(cache_db)
+        .modify_cfg_chained(|cfg| {
+            cfg.disable_nonce_check = true;
+            cfg.disable_eip3607 = true;
+        })
+        .build_mainnet_with_inspector(GasProfiler::default());
+
+    let tx = TxEnv {
+        caller: from,
+        kind: TxKind::Call(to),
+        data: calldata,
+        ..Default::default()
+    };
+
+    let result = evm.inspect_one_tx(tx)?;
+    let profiler = std::mem::take(&mut evm.inspector);
+
+    Ok((call_output(result)?, profiler))
+}
@@ -0,0 +1,13 @@
+use alloy::providers::{Provider, ProviderBuilder};
+
+use anyhow::{Context, Result};
+
+/// Connects to any supported endpoint: `http(s)://`, `ws(s)://`, or an IPC
+/// socket given as `ipc://<path>` or a bare filesystem path. IPC is the
+/// lowest-latency option when the bot runs next to its HyperEVM node.
+pub async fn connect_provider(url: &str) -> Result<impl Provider + Clone + 'static> {
+    ProviderBuilder::new()
+        .connect(url)
+        .await
+        .with_context(|| format!("failed to connect to {url}"))
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+
+use alloy::{
+    primitives::{Bytes, I256},
+    sol_types::{Panic, Revert, SolError, SolValue},
+};
+
+use revm::context::result::{ExecutionResult, HaltReason, Output};
+
+/// Why a simulated call failed, coarse enough for callers to react on
+/// (skip the size, fix the config, raise the gas limit).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RevertKind {
+    NoLiquidity,
+    BadCalldata,
+    OutOfGas,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct RevmCallError {
+    pub kind: RevertKind,
+    pub reason: String,
+}
+
+impl fmt::Display for RevmCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "execution failed ({:?}): {}", self.kind, self.reason)
+    }
+}
+
+impl std::error::Error for RevmCallError {}
+
+impl RevmCallError {
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        match result {
+            ExecutionResult::Revert { output, .. } => decode_revert(output),
+            ExecutionResult::Halt { reason: HaltReason::OutOfGas(e), .. } => Self {
+                kind: RevertKind::OutOfGas,
+                reason: format!("out of gas: {e:?}"),
+            },
+            ExecutionResult::Halt { reason, .. } => Self {
+                kind: RevertKind::Other,
+                reason: format!("halted: {reason:?}"),
+            },
+            ExecutionResult::Success { output, .. } => Self {
+                kind: RevertKind::BadCalldata,
+                reason: format!("unexpected success output: {output:?}"),
+            },
+        }
+    }
+}
+
+/// What a finished call returned. The `CustomQuoter` answers by reverting
+/// with its deltas, so that revert is returned as if the deltas were the
+/// return data; any other failure is a `RevmCallError`.
+pub fn call_output(result: ExecutionResult) -> Result<Bytes, RevmCallError> {
+    if let ExecutionResult::Success { output: Output::Call(value), .. } = result {
+        return Ok(value);
+    }
+    if let ExecutionResult::Revert { output, .. } = &result
+        && let Some(deltas) = quoter_payload(output)
+    {
+        return Ok(deltas);
+    }
+    Err(RevmCallError::from_result(&result))
+}
+
+/// `(amount0Delta, amount1Delta)` of a `CustomQuoter` revert, `None` for
+/// any other revert data.
+pub fn decode_quoter_deltas(output: &Bytes) -> Option<(I256, I256)> {
+    <(I256, I256)>::abi_decode_params(&quoter_payload(output)?).ok()
+}
+
+// the quoter reverts with `string(abi.encode(amount0Delta, amount1Delta))`:
+// an `Error(string)` of 64 bytes that are not text, as one delta is
+// negative and starts with 0xff
+fn quoter_payload(output: &Bytes) -> Option<Bytes> {
+    let data = output.strip_prefix(Revert::SELECTOR.as_slice())?;
+    let (payload,) = <(Bytes,)>::abi_decode_params(data).ok()?;
+    (payload.len() == 64 && std::str::from_utf8(&payload).is_err()).then_some(payload)
+}
+
+/// Decodes `Error(string)` and `Panic(uint256)` into a readable reason and
+/// classifies it.
+pub fn decode_revert(output: &Bytes) -> RevmCallError {
+    if output.is_empty() {
+        // typically a missing function selector or a call into an EOA
+        return RevmCallError {
+            kind: RevertKind::BadCalldata,
+            reason: "empty revert data".to_string(),
+        };
+    }
+
+    if let Ok(revert) = Revert::abi_decode(output) {
+        let reason = revert.reason().to_string();
+        return RevmCallError { kind: classify_reason(&reason), reason };
+    }
+
+    if let Ok(panic) = Panic::abi_decode(output) {
+        let reason = match panic.kind() {
+            Some(kind) => format!("panic: {kind}"),
+            None => format!("panic: code {}", panic.code),
+        };
+        return RevmCallError { kind: RevertKind::Other, reason };
+    }
+
+    RevmCallError {
+        kind: RevertKind::Other,
+        reason: format!("unknown revert data {output}"),
+    }
+}
+
+// UniswapV3 pools revert with short codes rather than sentences
+fn classify_reason(reason: &str) -> RevertKind {
+    match reason {
+        "SPL" => RevertKind::NoLiquidity,
+        "AS" | "Transaction too old" => RevertKind::BadCalldata,
+        r if r.to_lowercase().contains("liquidity") => RevertKind::NoLiquidity,
+        _ => RevertKind::Other,
+    }
+}
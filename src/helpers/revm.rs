@@ -1,15 +1,21 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use alloy::{
-    network::Ethereum,
-    primitives::{Address, Bytes, U256},
+    eips::{BlockId, BlockNumberOrTag},
+    network::{Ethereum, TransactionBuilder},
+    primitives::{Address, B256, Bytes, I256, U256},
     providers::Provider,
+    rpc::types::{Header, TransactionRequest},
     sol_types::SolValue,
 };
+use futures_util::future::try_join_all;
+use log::warn;
+use tokio::sync::broadcast;
 
 use revm::{
-    Context, ExecuteEvm, MainBuilder, MainContext,
-    context::result::{ExecutionResult, Output},
+    Context, Database, ExecuteCommitEvm, ExecuteEvm, MainBuilder, MainContext,
     database::{AlloyDB, CacheDB, WrapDatabaseAsync},
     primitives::{TxKind, keccak256},
     state::{AccountInfo, Bytecode},
@@ -17,6 +23,14 @@ use revm::{
 
 use anyhow::{Result, anyhow};
 
+use crate::dex::univ3_math::{PoolState, TickData};
+use crate::helpers::revert::call_output;
+use crate::risk::RiskEvent;
+
+/// Read-only call against the cache. Failures are returned as a
+/// `RevmCallError` (downcast it to inspect the revert kind), or as the
+/// `EVMError` when state could not be fetched. A `CustomQuoter` revert is
+/// the quote, returned as its encoded deltas.
 pub fn revm_call<P: Provider + Clone>(
     from: Address,
     to: Address,
@@ -33,20 +47,37 @@ pub fn revm_call<P: Provider + Clone>(
         })
         .build_mainnet();
 
-    let ref_tx = evm.replay().unwrap();
+    let ref_tx = evm.replay()?;
     let result = ref_tx.result;
 
-    let value = match result {
-        ExecutionResult::Success {
-            output: Output::Call(value),
-            ..
-        } => value,
-        result => {
-            return Err(anyhow!("execution failed: {result:?}"));
-        }
-    };
+    Ok(call_output(result)?)
+}
+
+// like `revm_call`, but commits state changes to the cache and allows any
+// caller (contracts included) so token holders can be impersonated
+pub fn revm_call_commit<P: Provider + Clone>(
+    from: Address,
+    to: Address,
+    calldata: Bytes,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<Bytes> {
+    let mut evm = Context::mainnet()
+        .with_db(cache_db)
+        .modify_cfg_chained(|cfg| {
+            cfg.disable_nonce_check = true;
+            cfg.disable_eip3607 = true;
+        })
+        .modify_tx_chained(|tx| {
+            tx.caller = from;
+            tx.kind = TxKind::Call(to);
+            tx.data = calldata;
+            tx.value = U256::ZERO;
+        })
+        .build_mainnet();
+
+    let result = evm.replay_commit()?;
 
-    Ok(value)
+    Ok(call_output(result)?)
 }
 
 pub fn init_cache_db<P: Provider + Clone>(
@@ -87,13 +118,261 @@ pub async fn insert_mapping_storage_slot<P: Provider + Clone>(
     Ok(())
 }
 
+/// Points every subsequent cold lookup of the cache at `block_number`, so a
+/// refresh can't mix state from both sides of a block boundary.
+pub fn pin_cache_db_block<P: Provider + Clone>(
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    provider: &Arc<P>,
+    block_number: u64,
+) {
+    cache_db.db = WrapDatabaseAsync::new(AlloyDB::new(
+        (**provider).clone(),
+        BlockId::number(block_number),
+    ))
+    .unwrap();
+}
+
+/// Drops every cached storage slot of `accounts`; the next access refetches
+/// from the pinned block. Code and account info stay cached.
+pub fn invalidate_storage<P: Provider + Clone>(
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    accounts: &[Address],
+) {
+    for address in accounts {
+        if let Some(account) = cache_db.cache.accounts.get_mut(address) {
+            account.storage.clear();
+        }
+    }
+}
+
+/// Drops cached storage of every account but `keep`, for when we can't tell
+/// which slots were derived from blocks that no longer exist.
+pub fn invalidate_all_storage<P: Provider + Clone>(
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    keep: &[Address],
+) {
+    for (address, account) in cache_db.cache.accounts.iter_mut() {
+        if !keep.contains(address) {
+            account.storage.clear();
+        }
+    }
+}
+
+// how many recent head hashes are kept for reorg detection
+const RECENT_HEADS: usize = 64;
+
+/// Follows the chain head: on every new block the cache is re-pinned and
+/// the storage of `volatile` accounts (pools) is invalidated, everything
+/// else is assumed to stay put between blocks.
+///
+/// Recent head hashes are kept to detect reorgs; on one, the whole storage
+/// cache is dropped and a `RiskEvent::Reorg` is emitted. With a lag limit
+/// set, a head older than the limit flags the tracker as lagging.
+///
+/// A head announced by a newHeads subscription (`announce`) is used as is,
+/// otherwise every `advance` asks the node for the latest block.
+///
+/// `followed` pools are kept current from their Swap events instead (see
+/// `dex::pool_events`): a new block leaves them be, unless an event that
+/// can't be applied put them on `recheck`.
+#[derive(Debug, Default)]
+pub struct BlockTracker {
+    pub block_number: Option<u64>,
+    pub volatile: Vec<Address>,
+    // accounts with synthetic (mocked) storage that must survive a reorg
+    pub keep: Vec<Address>,
+    pub recent: VecDeque<(u64, B256)>,
+    pub max_lag_secs: Option<u64>,
+    pub lagging: bool,
+    pub risk_tx: Option<broadcast::Sender<RiskEvent>>,
+    pub announced: Option<Header>,
+    pub followed: Vec<Address>,
+    // followed pools re-read at the next block
+    pub recheck: Vec<Address>,
+}
+
+impl BlockTracker {
+    pub fn new(volatile: Vec<Address>) -> Self {
+        Self {
+            volatile,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_keep(mut self, keep: Vec<Address>) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    pub fn with_max_lag(mut self, max_lag_secs: u64) -> Self {
+        self.max_lag_secs = Some(max_lag_secs);
+        self
+    }
+
+    /// Whether the last seen head was older than the lag limit. Quotes
+    /// taken meanwhile describe a stale chain and must not be traded on.
+    pub fn is_lagging(&self) -> bool {
+        self.lagging
+    }
+
+    pub fn with_risk_events(mut self, risk_tx: broadcast::Sender<RiskEvent>) -> Self {
+        self.risk_tx = Some(risk_tx);
+        self
+    }
+
+    /// Takes `head` as the chain head from here on, until `unannounce`.
+    pub fn announce(&mut self, head: Header) {
+        self.announced = Some(head);
+    }
+
+    /// Back to asking the node for the head, e.g. when the subscription
+    /// went quiet.
+    pub fn unannounce(&mut self) {
+        self.announced = None;
+    }
+
+    /// Whether `pool` is kept current from its events rather than re-read
+    /// every block.
+    pub fn follows(&self, pool: Address) -> bool {
+        self.followed.contains(&pool)
+    }
+
+    /// The number of the announced head, if any.
+    pub fn announced_number(&self) -> Option<u64> {
+        self.announced.as_ref().map(|head| head.number)
+    }
+
+    /// Returns the current head, re-pinning the cache if it moved.
+    pub async fn advance<P: Provider + Clone>(
+        &mut self,
+        cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+        provider: &Arc<P>,
+    ) -> Result<u64> {
+        let head = match &self.announced {
+            Some(head) => head.clone(),
+            None => provider
+                .get_block_by_number(BlockNumberOrTag::Latest)
+                .await?
+                .ok_or_else(|| anyhow!("node returned no latest block"))?
+                .header,
+        };
+        let (number, hash, parent_hash) = (head.number, head.hash, head.parent_hash);
+
+        // a halted chain keeps returning the same head, check before bailing out
+        self.check_lag(number, head.timestamp);
+
+        if self.recent.back() == Some(&(number, hash)) {
+            return Ok(number);
+        }
+
+        if let Some(fork_block) = self.find_fork(provider, number, hash, parent_hash).await? {
+            let old_head = self.block_number.unwrap_or(fork_block);
+            self.recent.retain(|(n, _)| *n <= fork_block);
+            invalidate_all_storage(cache_db, &self.keep);
+
+            let event = RiskEvent::Reorg {
+                fork_block,
+                depth: old_head.saturating_sub(fork_block),
+                new_head: number,
+            };
+            warn!("chain reorg detected: {:?}", event);
+            self.emit(event);
+        } else if self.block_number.is_some() {
+            let stale: Vec<Address> = self.volatile.iter().filter(|a| !self.follows(**a)).copied().collect();
+            invalidate_storage(cache_db, &stale);
+        }
+        // followed pools an event couldn't be applied to are re-read at the new head
+        invalidate_storage(cache_db, &std::mem::take(&mut self.recheck));
+
+        pin_cache_db_block(cache_db, provider, number);
+        self.block_number = Some(number);
+
+        self.recent.retain(|(n, _)| *n < number);
+        self.recent.push_back((number, hash));
+        while self.recent.len() > RECENT_HEADS {
+            self.recent.pop_front();
+        }
+
+        Ok(number)
+    }
+
+    fn check_lag(&mut self, head: u64, timestamp: u64) {
+        let Some(max_lag_secs) = self.max_lag_secs else {
+            return;
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let lag_secs = now.saturating_sub(timestamp);
+
+        if lag_secs > max_lag_secs && !self.lagging {
+            self.lagging = true;
+            warn!("chain head {} is {}s behind wall clock (limit {}s)", head, lag_secs, max_lag_secs);
+            self.emit(RiskEvent::ChainLag { head, lag_secs });
+        } else if lag_secs <= max_lag_secs && self.lagging {
+            self.lagging = false;
+            warn!("chain head {} caught up ({}s behind)", head, lag_secs);
+            self.emit(RiskEvent::ChainCaughtUp { head });
+        }
+    }
+
+    fn emit(&self, event: RiskEvent) {
+        if let Some(risk_tx) = &self.risk_tx {
+            let _ = risk_tx.send(event);
+        }
+    }
+
+    /// Returns the last common block if the new head doesn't extend the
+    /// chain we've seen.
+    async fn find_fork<P: Provider + Clone>(
+        &self,
+        provider: &Arc<P>,
+        number: u64,
+        hash: B256,
+        parent_hash: B256,
+    ) -> Result<Option<u64>> {
+        let known = |n: u64| self.recent.iter().find(|(k, _)| *k == n).map(|(_, h)| *h);
+
+        let extends = match (known(number), known(number.saturating_sub(1))) {
+            (Some(seen), _) => seen == hash,
+            (None, Some(parent)) => parent == parent_hash,
+            // gap larger than one block, nothing to compare against
+            (None, None) => true,
+        };
+        if extends {
+            return Ok(None);
+        }
+
+        // walk back until our recorded hash matches the canonical one
+        for &(n, seen) in self.recent.iter().rev().filter(|(n, _)| *n < number) {
+            let canonical = provider
+                .get_block_by_number(BlockNumberOrTag::Number(n))
+                .await?
+                .map(|b| b.header.hash);
+            if canonical == Some(seen) {
+                return Ok(Some(n));
+            }
+        }
+
+        // deeper than our history, assume everything we know is gone
+        let oldest = self.recent.front().map(|(n, _)| *n).unwrap_or(number);
+        Ok(Some(oldest.saturating_sub(1)))
+    }
+}
+
 pub async fn hydrate_pool_state<P: Provider + Clone>(
     cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
     provider: &Arc<P>,
     pool: Address,
+    block_number: u64,
 ) -> Result<()> {
     // slot0 (position 0)
-    let slot0 = provider.get_storage_at(pool, U256::ZERO).await?;
+    let slot0 = provider
+        .get_storage_at(pool, U256::ZERO)
+        .block_id(BlockId::number(block_number))
+        .await?;
     cache_db.insert_account_storage(pool, U256::from(0), slot0)?;
 
     // liquidity (slot 2)
@@ -102,3 +381,160 @@ pub async fn hydrate_pool_state<P: Provider + Clone>(
 
     Ok(())
 }
+
+// UniswapV3Pool storage layout
+pub const POOL_SLOT0: u64 = 0;
+pub const POOL_LIQUIDITY: u64 = 4;
+const POOL_TICKS: u64 = 5;
+const POOL_TICK_BITMAP: u64 = 6;
+const POOL_OBSERVATIONS: u64 = 8;
+
+// slot of `mapping(key => ...)` at `position`, for signed keys
+fn signed_mapping_slot(key: i64, position: u64) -> U256 {
+    let key = I256::try_from(key).unwrap_or_default();
+    keccak256((key, U256::from(position)).abi_encode()).into()
+}
+
+/// A pool's tick storage read through the REVM cache, for quoting with
+/// `univ3_math::swap` instead of the quoter. Missing slots are fetched at
+/// the pinned block like any other cache miss.
+pub struct CachedTicks<'a, P: Provider + Clone> {
+    pub cache_db: &'a mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    pub pool: Address,
+}
+
+impl<P: Provider + Clone> CachedTicks<'_, P> {
+    fn read(&mut self, slot: U256) -> Result<U256> {
+        self.cache_db.storage(self.pool, slot).map_err(|e| anyhow!("pool {} storage: {}", self.pool, e))
+    }
+
+    /// slot0 and liquidity of the pool.
+    pub fn state(&mut self) -> Result<PoolState> {
+        let slot0 = self.read(U256::from(POOL_SLOT0))?;
+        let liquidity = self.read(U256::from(POOL_LIQUIDITY))?;
+        Ok(PoolState::from_storage(slot0, liquidity))
+    }
+}
+
+impl<P: Provider + Clone> TickData for CachedTicks<'_, P> {
+    fn bitmap_word(&mut self, word: i16) -> Result<U256> {
+        self.read(signed_mapping_slot(word as i64, POOL_TICK_BITMAP))
+    }
+
+    fn liquidity_net(&mut self, tick: i32) -> Result<i128> {
+        // Tick.Info packs liquidityGross (low 128 bits) and liquidityNet
+        let info = self.read(signed_mapping_slot(tick as i64, POOL_TICKS))?;
+        Ok((info >> 128usize).wrapping_to::<u128>() as i128)
+    }
+}
+
+/// Prefetches code, balance and nonce of every account plus the listed
+/// storage slots concurrently, so the first simulations don't stall on a
+/// chain of cold AlloyDB lookups. Must run before any mocking, as it
+/// overwrites account info.
+pub async fn warm_cache_db<P: Provider + Clone>(
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+    provider: &Arc<P>,
+    accounts: &[Address],
+    storage: &[(Address, U256)],
+) -> Result<()> {
+    let infos = try_join_all(accounts.iter().map(|&address| async move {
+        let (balance, nonce, code) = tokio::try_join!(
+            provider.get_balance(address).into_future(),
+            provider.get_transaction_count(address).into_future(),
+            provider.get_code_at(address).into_future(),
+        )?;
+        anyhow::Ok((address, balance, nonce, code))
+    }))
+    .await?;
+
+    for (address, balance, nonce, code) in infos {
+        let bytecode = Bytecode::new_raw(code);
+        let acc_info = AccountInfo {
+            balance,
+            nonce,
+            code_hash: bytecode.hash_slow(),
+            code: Some(bytecode),
+        };
+        cache_db.insert_account_info(address, acc_info);
+    }
+
+    let values = try_join_all(storage.iter().map(|&(address, slot)| async move {
+        let value = provider.get_storage_at(address, slot).await?;
+        anyhow::Ok((address, slot, value))
+    }))
+    .await?;
+
+    for (address, slot, value) in values {
+        cache_db.insert_account_storage(address, slot, value)?;
+    }
+
+    Ok(())
+}
+
+/// Every account and storage slot a call touches, as reported by the node's
+/// `eth_createAccessList`. Lets the cache be warmed with the real slots of
+/// tokens whose storage layout we don't know.
+pub async fn access_list_slots<P: Provider + Clone>(
+    provider: &Arc<P>,
+    from: Address,
+    to: Address,
+    calldata: Bytes,
+) -> Result<(Vec<Address>, Vec<(Address, U256)>)> {
+    let tx = TransactionRequest::default()
+        .from(from)
+        .to(to)
+        .with_input(calldata);
+    let result = provider.create_access_list(&tx).await?;
+
+    let mut accounts = Vec::new();
+    let mut storage = Vec::new();
+    for item in result.access_list.0 {
+        accounts.push(item.address);
+        storage.extend(item.storage_keys.into_iter().map(|k| (item.address, k.into())));
+    }
+
+    Ok((accounts, storage))
+}
+
+/// Storage a quote always touches: slot0, liquidity, the current oracle
+/// observation and the tick bitmap words around the current tick.
+pub async fn pool_hot_slots<P: Provider + Clone>(
+    provider: &Arc<P>,
+    pool: Address,
+    tick_spacing: i32,
+) -> Result<Vec<U256>> {
+    let slot0 = provider.get_storage_at(pool, U256::from(POOL_SLOT0)).await?;
+
+    // slot0 packs sqrtPriceX96 (160 bits) | tick (int24) | observationIndex (uint16) | ...
+    let tick = ((slot0 >> 160usize).wrapping_to::<u32>() & 0xff_ffff) as i32;
+    let tick = (tick << 8) >> 8; // sign-extend int24
+    let observation_index = (slot0 >> 184usize).wrapping_to::<u64>() & 0xffff;
+
+    let compressed = tick.div_euclid(tick_spacing.max(1));
+    let word = compressed >> 8;
+
+    let mut slots = vec![
+        U256::from(POOL_SLOT0),
+        U256::from(POOL_LIQUIDITY),
+        U256::from(POOL_OBSERVATIONS + observation_index),
+    ];
+
+    for w in word - 1..=word + 1 {
+        // mapping(int16 => uint256): key is abi-encoded as a sign-extended word
+        slots.push(signed_mapping_slot(w as i64, POOL_TICK_BITMAP));
+    }
+
+    Ok(slots)
+}
+
+/// Tick spacing of the canonical UniswapV3 fee tiers.
+pub fn tick_spacing_for_fee(fee: u32) -> i32 {
+    match fee {
+        100 => 1,
+        500 => 10,
+        3000 => 60,
+        10000 => 200,
+        _ => 60,
+    }
+}
@@ -0,0 +1,153 @@
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+};
+use anyhow::{Result, anyhow};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::execution::hyperliquid::HyperliquidClient;
+use crate::execution::margin::MarginAccount;
+use crate::helpers::abi::{
+    allowance_calldata, balance_of_calldata, call_tx, decode_balance_response, decode_multicall3_response,
+    multicall3_calldata,
+};
+use crate::settings::Settings;
+
+/// Holdings of one account on HyperEVM, in raw token units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvmInventory {
+    pub native: U256,
+    pub base: U256,
+    pub quote: U256,
+    // what the router may pull from the account for swaps
+    pub base_allowance: U256,
+    pub quote_allowance: U256,
+}
+
+/// Spot holdings of the trading account on HyperCore, in whole tokens.
+/// `*_hold` is the part locked in resting orders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreInventory {
+    pub base: f64,
+    pub base_hold: f64,
+    pub quote: f64,
+    pub quote_hold: f64,
+}
+
+/// Why funds are between venues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferKind {
+    // requested from a CEX, not received yet
+    Withdrawal,
+    // sent to a CEX, not credited yet
+    Deposit,
+    // between HyperEVM and HyperCore
+    Bridge,
+}
+
+/// Funds that left one venue and were not credited at another yet, in
+/// whole tokens of `asset` (`base` or `quote`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InFlightTransfer {
+    pub id: String,
+    pub kind: TransferKind,
+    pub asset: String,
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+    pub started_ms: u64,
+}
+
+/// An asset split into what can be traded right away and what is in
+/// flight. Both count toward equity.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AssetBalance {
+    pub tradable: f64,
+    pub in_flight: f64,
+}
+
+impl AssetBalance {
+    pub fn equity(&self) -> f64 {
+        self.tradable + self.in_flight
+    }
+}
+
+/// Pair token balances and router allowances of `owner` in one multicall,
+/// alongside its native balance.
+pub async fn evm_inventory(cfg: &Settings, provider: &(impl Provider + ?Sized), owner: Address) -> Result<EvmInventory> {
+    let batch_calldata = multicall3_calldata(vec![
+        (cfg.weth_addr, balance_of_calldata(owner)),
+        (cfg.usdt_addr, balance_of_calldata(owner)),
+        (cfg.weth_addr, allowance_calldata(owner, cfg.router_addr)),
+        (cfg.usdt_addr, allowance_calldata(owner, cfg.router_addr)),
+    ]);
+
+    let (batch_response, native) = tokio::try_join!(
+        provider.call(call_tx(cfg.multicall3_addr, owner, batch_calldata)).into_future(),
+        provider.get_balance(owner).into_future(),
+    )?;
+
+    let [base, quote, base_allowance, quote_allowance]: [Bytes; 4] = decode_multicall3_response(batch_response)?
+        .try_into()
+        .map_err(|r: Vec<Bytes>| anyhow!("multicall returned {} results, expected 4", r.len()))?;
+
+    Ok(EvmInventory {
+        native,
+        base: decode_balance_response(base)?,
+        quote: decode_balance_response(quote)?,
+        base_allowance: decode_balance_response(base_allowance)?,
+        quote_allowance: decode_balance_response(quote_allowance)?,
+    })
+}
+
+/// Base and quote spot balances of the account `client` trades for. Tokens
+/// never held are missing from the response and read as 0.
+pub async fn core_inventory(cfg: &Settings, client: &HyperliquidClient) -> Result<CoreInventory> {
+    let balances = client.spot_balances(client.account()).await?;
+
+    let amounts = |index: u64| -> Result<(f64, f64)> {
+        match balances.iter().find(|b| b.token == index) {
+            Some(b) => Ok((b.total.parse()?, b.hold.parse()?)),
+            None => Ok((0.0, 0.0)),
+        }
+    };
+    let (base, base_hold) = amounts(cfg.base_core_index)?;
+    let (quote, quote_hold) = amounts(cfg.quote_core_index)?;
+
+    Ok(CoreInventory { base, base_hold, quote, quote_hold })
+}
+
+/// Borrows what `held` lacks of `needed` of `asset`, e.g. the base a short
+/// sells or the quote a rebalance spends. Returns the amount borrowed.
+pub async fn borrow_shortfall(account: &(impl MarginAccount + ?Sized), asset: &str, needed: f64, held: f64) -> Result<f64> {
+    let shortfall = needed - held;
+    if shortfall <= 0.0 {
+        return Ok(0.0);
+    }
+
+    account.borrow(asset, shortfall).await?;
+    info!("🏦 borrowed {:.6} {} on {}", shortfall, asset, account.venue());
+    Ok(shortfall)
+}
+
+/// Repays as much of the `asset` loan as `free` covers, e.g. once a short
+/// was bought back. Returns the amount repaid.
+pub async fn repay_loan(account: &(impl MarginAccount + ?Sized), asset: &str, free: f64) -> Result<f64> {
+    let loan = account.loan(asset).await?;
+    let repay = loan.borrowed.min(free);
+    if repay <= 0.0 {
+        return Ok(0.0);
+    }
+
+    account.repay(asset, repay).await?;
+    info!(
+        "🏦 repaid {:.6} {} on {}, {:.6} still borrowed at {:.4}%/h",
+        repay,
+        asset,
+        account.venue(),
+        loan.borrowed - repay,
+        loan.hourly_rate * 100.0
+    );
+    Ok(repay)
+}
@@ -1,4 +1,41 @@
 pub mod adapters;
+pub mod alerts;
 pub mod arbitrage;
+pub mod audit;
+pub mod backtest;
+pub mod borrow;
+pub mod capital;
+pub mod cli;
+pub mod clock;
+pub mod control;
+pub mod dex;
+pub mod execution;
+pub mod exposure;
 pub mod helpers;
+pub mod inventory;
+pub mod lifecycle;
+pub mod logging;
+pub mod maintenance;
+pub mod markets;
+pub mod normalize;
+pub mod notify;
+pub mod opportunity;
+pub mod pairs;
+pub mod pnl;
+pub mod replay;
+pub mod risk;
+pub mod scanner;
+pub mod schedule;
+pub mod screening;
 pub mod settings;
+pub mod sizing;
+pub mod spreads;
+pub mod state;
+pub mod storage;
+pub mod strategy;
+pub mod summary;
+pub mod switches;
+pub mod telemetry;
+pub mod throttle;
+pub mod venue_status;
+pub mod whatif;
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use log::info;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::time::timeout;
+
+use crate::maintenance::{MaintenanceCommand, MaintenanceHandle, MaintenancePhase};
+
+/// Why the process exits, told apart by exit code so a supervisor can
+/// decide whether restarting helps. Codes follow sysexits.h; anything
+/// unclassified exits with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    // bad or missing settings, restarting will not help
+    Config,
+    // RPC or another dependency unreachable at startup
+    Unavailable,
+    // a long-running task stopped
+    Crashed,
+    // the `--oneshot` self-test failed
+    Unhealthy,
+    // SIGTERM drain did not finish within `shutdown_drain_secs`
+    DrainTimeout,
+}
+
+impl Failure {
+    pub fn code(self) -> u8 {
+        match self {
+            Failure::Config => 78,
+            Failure::Unavailable => 69,
+            Failure::Crashed => 70,
+            // what docker expects of a failing HEALTHCHECK
+            Failure::Unhealthy => 1,
+            Failure::DrainTimeout => 75,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Failure::Config => "invalid configuration",
+            Failure::Unavailable => "dependency unavailable",
+            Failure::Crashed => "task stopped",
+            Failure::Unhealthy => "self-test failed",
+            Failure::DrainTimeout => "shutdown drain timed out",
+        })
+    }
+}
+
+/// Exit code of an error, by the `Failure` it was classified with as
+/// context.
+pub fn exit_code(err: &anyhow::Error) -> ExitCode {
+    ExitCode::from(err.downcast_ref::<Failure>().map_or(1, |f| f.code()))
+}
+
+/// Resolves on SIGTERM or Ctrl-C.
+pub async fn shutdown_signal() -> Result<()> {
+    let mut term = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = term.recv() => info!("🛑 SIGTERM received"),
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            info!("🛑 interrupt received");
+        }
+    }
+    Ok(())
+}
+
+/// Winds the bot down through maintenance (pause, let in-flight
+/// transactions land, save state), failing with `DrainTimeout` past
+/// `deadline`.
+pub async fn drain(mut maintenance: MaintenanceHandle, deadline: Duration) -> Result<()> {
+    info!("🛑 draining for up to {:?} before exiting", deadline);
+    maintenance.send(MaintenanceCommand::Enter)?;
+    timeout(deadline, maintenance.wait_for(MaintenancePhase::Idle))
+        .await
+        .map_err(|_| anyhow!("still {} after {:?}", maintenance.phase().as_str(), deadline))
+        .context(Failure::DrainTimeout)??;
+    info!("🛑 drained, exiting");
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use env_filter::Filter;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+use crate::settings::{LogRotation, Settings};
+
+// the installed logger, for changing its filter at runtime
+static LOGGER: OnceLock<&'static TeeLogger> = OnceLock::new();
+
+/// Installs the process logger: env_logger on stderr as before, filtered by
+/// `RUST_LOG`, plus JSON lines in `log_file` when set. The filter applies
+/// to both and can be swapped at runtime with `set_filter`.
+pub fn init(cfg: &Settings) -> Result<()> {
+    let spec = std::env::var("RUST_LOG").unwrap_or_else(|_| "error".to_string());
+    // lenient like env_logger: bad directives are reported and skipped
+    let filter = env_filter::Builder::new().parse(&spec).build();
+
+    // everything passes the console logger, `filter` decides
+    let console = env_logger::Builder::from_default_env().filter_level(LevelFilter::Trace).build();
+    let file = match &cfg.log_file {
+        Some(path) => Some(Mutex::new(RotatingFile::open(
+            path,
+            cfg.log_rotation,
+            cfg.log_max_bytes,
+            cfg.log_keep_files,
+        )?)),
+        None => None,
+    };
+
+    log::set_max_level(filter.filter());
+    let logger: &'static TeeLogger = Box::leak(Box::new(TeeLogger {
+        console,
+        file,
+        filter: RwLock::new((spec, filter)),
+    }));
+    log::set_logger(logger)?;
+    let _ = LOGGER.set(logger);
+    Ok(())
+}
+
+/// Parses `RUST_LOG` syntax, e.g. `info,rust_arb_bot::adapters::hyperswap=warn`.
+pub fn parse_filter(spec: &str) -> Result<Filter> {
+    Ok(env_filter::Builder::new().try_parse(spec).map_err(|e| anyhow!("invalid log filter {:?}: {}", spec, e))?.build())
+}
+
+/// Replaces the filter of the running logger, as if restarted with
+/// `RUST_LOG=<spec>`.
+pub fn set_filter(spec: &str) -> Result<()> {
+    let logger = LOGGER.get().ok_or_else(|| anyhow!("logger is not initialized"))?;
+    let filter = parse_filter(spec)?;
+
+    log::set_max_level(filter.filter());
+    *logger.filter.write().map_err(|_| anyhow!("log filter lock poisoned"))? = (spec.to_string(), filter);
+    Ok(())
+}
+
+/// The filter in force, in `RUST_LOG` syntax.
+pub fn current_filter() -> Option<String> {
+    let logger = LOGGER.get()?;
+    logger.filter.read().ok().map(|f| f.0.clone())
+}
+
+struct TeeLogger {
+    console: env_logger::Logger,
+    file: Option<Mutex<RotatingFile>>,
+    // the spec it was parsed from alongside
+    filter: RwLock<(String, Filter)>,
+}
+
+impl TeeLogger {
+    fn matches(&self, record: &Record) -> bool {
+        self.filter.read().is_ok_and(|f| f.1.matches(record))
+    }
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().is_ok_and(|f| f.1.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.matches(record) {
+            return;
+        }
+        self.console.log(record);
+
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.lock()
+        {
+            // a full disk must not take the bot down with it
+            let _ = file.write_line(&json_line(record, now_ms()), now_ms());
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// One log record as a JSON object, without the trailing newline.
+pub fn json_line(record: &Record, ts_ms: u64) -> String {
+    json!({
+        "ts_ms": ts_ms,
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "module": record.module_path(),
+        "line": record.line(),
+        "msg": record.args().to_string(),
+    })
+    .to_string()
+}
+
+/// Append-only log file rotated once it would exceed `max_bytes` or a new
+/// hour or day (UTC) starts. Rotated files are `<path>.1` (newest) up to
+/// `<path>.<keep>`, older ones are deleted.
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    max_bytes: u64,
+    keep: usize,
+    writer: BufWriter<File>,
+    size: u64,
+    // rotation period the open file belongs to
+    period: Option<u64>,
+}
+
+impl RotatingFile {
+    pub fn open(path: impl AsRef<Path>, rotation: LogRotation, max_bytes: u64, keep: usize) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let (writer, size) = open_append(&path)?;
+
+        // a file left by a previous run counts toward the period it was last written in
+        let modified_ms = fs::metadata(&path)?
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64);
+        let period = match size {
+            0 => None,
+            _ => modified_ms.and_then(|ms| period_of(rotation, ms)),
+        };
+
+        Ok(Self { path, rotation, max_bytes, keep, writer, size, period })
+    }
+
+    /// Appends `line` as written at `now_ms`, rotating first when due.
+    pub fn write_line(&mut self, line: &str, now_ms: u64) -> Result<()> {
+        let len = line.len() as u64 + 1;
+        let period = period_of(self.rotation, now_ms);
+
+        let period_over = self.period.is_some() && period != self.period;
+        let too_big = self.max_bytes > 0 && self.size > 0 && self.size + len > self.max_bytes;
+        if period_over || too_big {
+            self.rotate()?;
+        }
+
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        self.size += len;
+        self.period = period;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(numbered(self.keep));
+            for n in (1..self.keep).rev() {
+                if numbered(n).exists() {
+                    fs::rename(numbered(n), numbered(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, numbered(1))?;
+        }
+
+        let (writer, size) = open_append(&self.path)?;
+        self.writer = writer;
+        self.size = size;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let size = file.metadata()?.len();
+    Ok((BufWriter::new(file), size))
+}
+
+fn period_of(rotation: LogRotation, ms: u64) -> Option<u64> {
+    match rotation {
+        LogRotation::Never => None,
+        LogRotation::Hourly => Some(ms / 3_600_000),
+        LogRotation::Daily => Some(ms / 86_400_000),
+    }
+}
@@ -1,89 +1,591 @@
-mod adapters;
-mod arbitrage;
-mod helpers;
-mod settings;
-
-use alloy::providers::ProviderBuilder;
-use anyhow::Result;
-use log::{error, info};
+use anyhow::{Context, Result, anyhow};
+use clap::{Parser, Subcommand};
+use futures_util::future::select_all;
+use log::{error, info, warn};
+use std::collections::BTreeSet;
+use std::process::ExitCode;
 use std::sync::Arc;
-use tokio::sync::watch;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch};
 
-use crate::adapters::bybit::run_bybit_listener;
-use crate::adapters::gateio::run_gateio_listener;
-use crate::adapters::hyperswap::run_hyperswap_listener;
-use crate::arbitrage::{ArbEngine, PriceData};
+use rust_arb_bot::adapters::{AdapterRegistry, run_labeled_listener, run_listener};
+use rust_arb_bot::adapters::hyperliquid_oracle::run_oracle_listener;
+use rust_arb_bot::adapters::hyperswap::run_hyperswap_listener;
+use rust_arb_bot::adapters::mempool::run_mempool_monitor;
+use rust_arb_bot::alerts::ChatAlerts;
+use rust_arb_bot::arbitrage::{ArbEngine, DEX_VENUE, GasToken, PriceData, VenueId};
+use rust_arb_bot::audit::Auditor;
+use rust_arb_bot::backtest::run_tick_recorder;
+use rust_arb_bot::capital::{Capital, run_capital_rebalancer};
+use rust_arb_bot::cli::audit::{AuditArgs, run_audit};
+use rust_arb_bot::cli::backtest::{BacktestArgs, run_backtest_command};
+use rust_arb_bot::cli::balances::run_balances;
+use rust_arb_bot::cli::bench::{BenchArgs, run_bench};
+use rust_arb_bot::cli::doctor::run_doctor;
+use rust_arb_bot::cli::log::{LogArgs, run_log};
+use rust_arb_bot::cli::maintenance::{MaintenanceArgs, run_maintenance};
+use rust_arb_bot::cli::pair::{PairArgs, run_pair};
+use rust_arb_bot::cli::quote::{QuoteArgs, run_quote};
+use rust_arb_bot::cli::rehearse::{RehearseArgs, run_rehearse};
+use rust_arb_bot::cli::spreads::{SpreadsArgs, run_spreads};
+use rust_arb_bot::cli::whatif::{WhatIfArgs, run_what_if};
+use rust_arb_bot::clock::{self, SystemClock};
+use rust_arb_bot::control::{Control, run_control_socket};
+use rust_arb_bot::dex::profile::verify_interfaces;
+use rust_arb_bot::dex::router::{router_for, run_quote_listener};
+use rust_arb_bot::execution::accounts::cex_account;
+use rust_arb_bot::execution::bybit::BybitClient;
+use rust_arb_bot::execution::confirmations::ConfirmationTracker;
+use rust_arb_bot::execution::executor::{ArbExecutor, cex_orders};
+use rust_arb_bot::execution::gateio::GateioClient;
+use rust_arb_bot::execution::hyperliquid::HyperliquidClient;
+use rust_arb_bot::execution::margin::MarginAccount;
+use rust_arb_bot::execution::sender::{TxSender, key_address};
+use rust_arb_bot::exposure::{Exposure, run_exposure_refresh};
+use rust_arb_bot::execution::short::{ShortBook, ShortSeller, run_short_cover};
+use rust_arb_bot::helpers::provider::connect_provider;
+use rust_arb_bot::lifecycle::{Failure, drain, exit_code, shutdown_signal};
+use rust_arb_bot::maintenance::Maintenance;
+use rust_arb_bot::markets::Markets;
+use rust_arb_bot::notify::{Notifier, QUEUE_CAPACITY};
+use rust_arb_bot::pairs::PairManager;
+use rust_arb_bot::pnl::{GasTotals, run_gas_ledger};
+use rust_arb_bot::spreads::Spreads;
+use rust_arb_bot::telemetry::Tracer;
+use rust_arb_bot::throttle::TradeThrottle;
+use rust_arb_bot::risk::{RiskHandle, RiskManager, risk_channel, run_oracle_monitor, run_peg_monitor};
+use rust_arb_bot::scanner::MatrixScanner;
+use rust_arb_bot::schedule::{TradingSchedule, run_trading_schedule};
+use rust_arb_bot::settings::{DepegAction, DexKind, ExecutionMode, StrategyKind};
+use rust_arb_bot::sizing::Sizer;
+use rust_arb_bot::state::{StateStore, run_state_saver, warm_start};
+use rust_arb_bot::storage::{Storage, run_price_recorder};
+use rust_arb_bot::strategy::market_making::run_market_maker;
+use rust_arb_bot::summary::run_daily_summary;
+use rust_arb_bot::switches::TradingSwitches;
+use rust_arb_bot::venue_status::run_venue_status;
+use rust_arb_bot::{logging, screening, settings};
+
+/// CEX/HyperSwap arbitrage bot. Runs the bot unless a command is given.
+#[derive(Parser)]
+struct Cli {
+    /// Run the health self-test and exit, 0 when healthy
+    #[arg(long)]
+    oneshot: bool,
+    /// Log the trades the bot would make without sending them, whatever
+    /// `execution_mode` says
+    #[arg(long)]
+    dry_run: bool,
+    /// Record every CEX tick and DEX quote to this directory as backtest
+    /// data, whatever `record_dir` says
+    #[arg(long)]
+    record: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Quote the DEX and each CEX once and compare them side by side
+    Quote(QuoteArgs),
+    /// Check connectivity, contracts, CEX symbols and keys against the config
+    Doctor,
+    /// Print token balances, router allowances and HyperCore balances
+    Balances,
+    /// Replay recorded ticks and report opportunities and PnL
+    Backtest(BacktestArgs),
+    /// Benchmark DEX quoting or CEX message parsing
+    Bench(BenchArgs),
+    /// Show or change the log filter of the running bot
+    Log(LogArgs),
+    /// Add, remove or list pairs of the running bot
+    Pair(PairArgs),
+    /// Wind the running bot down for maintenance, or bring it back
+    Maintenance(MaintenanceArgs),
+    /// Verify the hash chain of the decision audit log, or replay it
+    /// through the current code
+    Audit(AuditArgs),
+    /// Trade one seeded opportunity end to end on an anvil fork against a
+    /// mock CEX and check the PnL accounting
+    Rehearse(RehearseArgs),
+    /// Show the net spread percentiles the running bot has observed per
+    /// pair and direction
+    Spreads(SpreadsArgs),
+    /// Re-price the opportunities in the audit log with hypothetical fees,
+    /// gas and size and compare them with the config
+    WhatIf(WhatIfArgs),
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    env_logger::init();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit_code(&e)
+        }
+    }
+}
 
-    let cfg = settings::Settings::load()?;
+async fn run(cli: Cli) -> Result<()> {
+    let mut cfg = settings::Settings::load().context(Failure::Config)?;
+    if cli.dry_run {
+        cfg.execution_mode = ExecutionMode::Log;
+    }
+    if let Some(dir) = cli.record {
+        cfg.record_dir = Some(dir);
+    }
+    logging::init(&cfg).context(Failure::Config)?;
 
+    if cli.oneshot {
+        return run_doctor(&cfg).await.context(Failure::Unhealthy);
+    }
+    match cli.command {
+        Some(Command::Quote(args)) => run_quote(&cfg, &args).await,
+        Some(Command::Doctor) => run_doctor(&cfg).await,
+        Some(Command::Balances) => run_balances(&cfg).await,
+        Some(Command::Backtest(args)) => run_backtest_command(&cfg, &args).await,
+        Some(Command::Bench(args)) => run_bench(&cfg, &args).await,
+        Some(Command::Log(args)) => run_log(&cfg, &args).await,
+        Some(Command::Pair(args)) => run_pair(&cfg, &args).await,
+        Some(Command::Maintenance(args)) => run_maintenance(&cfg, &args).await,
+        Some(Command::Audit(args)) => run_audit(&cfg, &args),
+        Some(Command::Rehearse(args)) => run_rehearse(&cfg, &args).await,
+        Some(Command::Spreads(args)) => run_spreads(&cfg, &args).await,
+        Some(Command::WhatIf(args)) => run_what_if(&cfg, &args),
+        None => run_bot(cfg).await,
+    }
+}
+
+async fn run_bot(cfg: settings::Settings) -> Result<()> {
     println!("{:#?}", cfg);
 
     // Create provider for real-time gas price fetching
-    let provider = ProviderBuilder::new().connect_http(cfg.rpc_url.parse()?);
+    let provider = connect_provider(cfg.quote_rpc()).await.context(Failure::Unavailable)?;
     let provider = Arc::new(provider);
 
-    let (bybit_tx, bybit_rx) = watch::channel::<Option<PriceData>>(None);
-    let (gateio_tx, gateio_rx) = watch::channel::<Option<PriceData>>(None);
-    let (hyperswap_tx, hyperswap_rx) = watch::channel::<Option<PriceData>>(None);
+    // a wrong profile or quoter kind otherwise only shows as reverted quotes
+    if cfg.verify_dex_interfaces {
+        verify_interfaces(&cfg, &*provider).await.context(Failure::Config)?;
+        info!("dex quoter and router interfaces verified");
+    }
 
-    info!("initializing bybit rpc ws connection...");
-    let bybit_task = tokio::spawn(run_bybit_listener(bybit_tx));
+    // screening and pending swap prediction simulate a v3 pool
+    let v3 = cfg.dex_kind == DexKind::V3;
+    if cfg.screen_tokens && !v3 {
+        warn!("token screening needs dex_kind v3, skipped for {:?}", cfg.dex_kind);
+    } else if cfg.screen_tokens {
+        info!("screening pair tokens before enabling trading...");
+        screening::screen_pair(&cfg, provider.clone()).await?;
+    }
 
-    info!("initializing gateio rpc ws connection...");
-    let gateio_task = tokio::spawn(run_gateio_listener(gateio_tx));
+    // signed CEX requests are refused while the clock is off beyond what
+    // the venue accepts
+    clock::discipline(&cfg).await;
+    tokio::spawn(clock::run_clock_check(cfg.clone(), Duration::from_secs(cfg.clock_check_secs)));
 
-    info!("initializing hyperswap price fetcher...");
-    let dex_task = tokio::spawn(run_hyperswap_listener(hyperswap_tx));
+    // nothing trades before the saved state is reconciled
+    let hyperliquid = || cfg.hyperliquid_private_key.as_ref().and_then(|_| HyperliquidClient::new(&cfg).ok());
+    let restored = match &cfg.state_file {
+        Some(path) => warm_start(&cfg, path, &*provider, hyperliquid().as_ref()).await?,
+        None => Default::default(),
+    };
+    let fresh_price = |feed: &str| {
+        restored.fresh_prices.iter().find(|(name, _)| name == feed).map(|(_, price)| price.clone())
+    };
+    let state = StateStore::new(restored.state.clone());
 
-    info!("initializing bybit-hyperswap arbitrage detection engine...");
-    let mut bybit_arbitrage_engine = ArbEngine::new(cfg.clone(), bybit_rx, hyperswap_rx.clone(), provider.clone());
+    let registry = AdapterRegistry::configured(&cfg).context(Failure::Config)?;
+    let adapters = registry.from_config(&cfg).context(Failure::Config)?;
+    let cex_channels: Vec<_> = adapters
+        .iter()
+        .map(|adapter| watch::channel::<Option<PriceData>>(fresh_price(adapter.venue())))
+        .collect();
+    let cex_feeds: Vec<_> =
+        adapters.iter().zip(&cex_channels).map(|(adapter, (_, rx))| (adapter.venue().to_string(), rx.clone())).collect();
+    let (hyperswap_tx, hyperswap_rx) = watch::channel::<Option<PriceData>>(fresh_price("hyperswap"));
+    let (oracle_tx, oracle_rx) = watch::channel::<Option<PriceData>>(fresh_price("oracle"));
+    let (risk_tx, risk_rx) = risk_channel();
 
-    info!("initializing gateio-hyperswap arbitrage detection engine...");
-    let mut gateio_arbitrage_engine = ArbEngine::new(cfg.clone(), gateio_rx, hyperswap_rx, provider);
+    let (risk_manager, trading_rx) = RiskManager::new(&cfg);
+    let (audit, audit_writer) = Auditor::from_config(&cfg)?;
+    if let Some(writer) = audit_writer {
+        tokio::spawn(writer.run());
+    }
+    let exposure = Exposure::new(cfg.exposure_limits.clone());
+    let risk = RiskHandle {
+        events: risk_tx.clone(),
+        trading: trading_rx,
+        exposure: exposure.clone(),
+        throttle: TradeThrottle::from_config(&cfg),
+        audit,
+        markets: Markets::fetch(&cfg).await,
+        switches: TradingSwitches::from_config(&cfg),
+    };
+    let detection_only = risk.switches.detection_only(&cfg.cex_venues);
+    if !detection_only.is_empty() {
+        info!("🔭 detection only on {}", detection_only.join(", "));
+    }
+    tokio::spawn(run_venue_status(cfg.clone(), risk.switches.clone()));
+    let risk_task = tokio::spawn(risk_manager.run(risk_rx));
 
-    let bybit_arbitrage_task = tokio::spawn(async move {
-        if let Err(e) = bybit_arbitrage_engine.run().await {
-            error!("bybit arbitrage engine error: {}", e);
+    if !cfg.exposure_limits.is_empty() {
+        // every wallet the executor sends from and every CEX account it trades
+        let mut wallets = vec![cfg.self_addr];
+        for key in &cfg.executor_private_keys {
+            wallets.push(key_address(key).context(Failure::Config)?);
         }
-    });
+        let mut accounts: Vec<_> = cfg
+            .cex_venues
+            .iter()
+            .filter(|venue| *venue != "hyperliquid")
+            .filter_map(|venue| cex_orders(&cfg, venue).ok().map(|orders| (venue.clone(), orders)))
+            .collect();
+        for account in &cfg.cex_accounts {
+            accounts.push((account.name.clone(), cex_account(&cfg, account).context(Failure::Config)?.orders));
+        }
+        tokio::spawn(run_exposure_refresh(
+            cfg.clone(),
+            exposure,
+            provider.clone(),
+            hyperliquid(),
+            wallets,
+            accounts,
+            Duration::from_secs(cfg.exposure_refresh_secs),
+        ));
+    }
+
+    let schedule = TradingSchedule::from_config(&cfg).context(Failure::Config)?;
+    if !schedule.is_empty() {
+        tokio::spawn(run_trading_schedule(schedule, risk_tx.clone()));
+    }
+
+    let mut oracle_feeds: Vec<_> = cex_feeds.iter().map(|(_, rx)| rx.clone()).collect();
+    oracle_feeds.push(oracle_rx.clone());
+    tokio::spawn(run_oracle_monitor(
+        oracle_feeds,
+        risk_tx.clone(),
+        cfg.max_oracle_divergence_bps as f64,
+    ));
+
+    let mut feed_tasks = Vec::new();
+    for (adapter, (tx, _)) in adapters.into_iter().zip(cex_channels) {
+        info!("initializing {} ws connection...", adapter.venue());
+        let ticker = adapter.ticker(&cfg);
+        feed_tasks.push(tokio::spawn(run_listener(adapter, cfg.clone(), ticker, tx, risk_tx.clone())));
+    }
+
+    // cross-pair: DEX prices are converted through this feed before the engines see them
+    let (bridge_tx, bridge_rx) = watch::channel::<Option<PriceData>>(None);
+    if let Some(ticker) = cfg.dex_bridge_ticker.clone() {
+        info!("initializing bybit {} bridge feed...", ticker);
+        let bybit = registry.get("bybit").context(Failure::Config)?;
+        let label = "bybit:bridge".to_string();
+        tokio::spawn(run_labeled_listener(bybit, label, cfg.clone(), ticker, bridge_tx, risk_tx.clone()));
+    }
 
-    let gateio_arbitrage_task = tokio::spawn(async move {
-        if let Err(e) = gateio_arbitrage_engine.run().await {
-            error!("gateio arbitrage engine error: {}", e);
+    let (peg_tx, peg_rx) = watch::channel::<Option<PriceData>>(None);
+    if let Some(ticker) = cfg.peg_ticker.clone() {
+        info!("initializing bybit {} peg feed...", ticker);
+        let bybit = registry.get("bybit").context(Failure::Config)?;
+        let label = format!("bybit:{}", ticker);
+        tokio::spawn(run_labeled_listener(bybit, label, cfg.clone(), ticker, peg_tx, risk_tx.clone()));
+        if cfg.depeg_action == DepegAction::Halt {
+            tokio::spawn(run_peg_monitor(peg_rx.clone(), risk_tx.clone(), cfg.max_depeg_bps as f64));
         }
-    });
+    }
 
-    tokio::select! {
-        result = bybit_task => {
-            if let Err(e) = result {
-                error!("bybit listener task failed: {}", e);
-            }
+    let (tracer, exporter) = Tracer::from_config(&cfg);
+    if let Some(exporter) = exporter {
+        tokio::spawn(exporter.run());
+    }
+
+    let (mut notifier, webhook) = Notifier::from_config(&cfg);
+    if let Some(webhook) = webhook {
+        tokio::spawn(webhook.run());
+    }
+    if let Some((chat_tx, chat)) = ChatAlerts::from_config(&cfg) {
+        tokio::spawn(chat.run());
+        notifier = notifier.with_chat(chat_tx, cfg.chat_min_profit);
+    }
+
+    let mut all_feeds = cex_feeds.clone();
+    all_feeds.push(("hyperswap".to_string(), hyperswap_rx.clone()));
+    all_feeds.push(("oracle".to_string(), oracle_rx.clone()));
+
+    if let Some(dir) = cfg.record_dir.clone() {
+        let mut feeds = cex_feeds.clone();
+        feeds.push((DEX_VENUE.to_string(), hyperswap_rx.clone()));
+        tokio::spawn(run_tick_recorder(dir.into(), feeds, provider.clone(), Arc::new(SystemClock)));
+    }
+
+    let (storage, storage_writer) = Storage::from_config(&cfg).await?;
+    if let Some(writer) = storage_writer {
+        tokio::spawn(writer.run());
+        tokio::spawn(run_price_recorder(storage.clone(), all_feeds.clone()));
+        notifier = notifier.with_storage(storage);
+    }
+
+    if let Some(path) = cfg.state_file.clone() {
+        let feeds = all_feeds.clone();
+        tokio::spawn(run_state_saver(
+            cfg.clone(),
+            state.clone(),
+            path,
+            feeds,
+            provider.clone(),
+            hyperliquid(),
+            Duration::from_secs(cfg.state_save_secs),
+        ));
+    }
+
+    // what every wallet's tracker publishes, booked by the gas ledger at
+    // the price of the native token
+    let (tx_updates, _) = broadcast::channel(256);
+    let native_price_rx = match cfg.gas_token() {
+        GasToken::Base => hyperswap_rx.clone(),
+        GasToken::Quote => watch::channel(Some(PriceData::new(1.0, 1.0))).1,
+    };
+    let (gas_tx, gas_rx) = watch::channel(GasTotals::default());
+    tokio::spawn(run_gas_ledger(tx_updates.subscribe(), native_price_rx, gas_tx));
+
+    if cfg.daily_summary {
+        let (summary_tx, summary_rx) = mpsc::channel(QUEUE_CAPACITY);
+        let feeds = all_feeds.clone();
+        tokio::spawn(run_daily_summary(
+            summary_rx,
+            feeds,
+            Some(gas_rx),
+            Duration::from_secs(cfg.feed_stale_secs),
+            notifier.clone(),
+        ));
+        notifier = notifier.with_summary(summary_tx);
+    }
+
+    let wire_engine = |mut engine: ArbEngine| {
+        engine = engine.with_notifier(notifier.clone()).with_tracer(tracer.clone());
+        // both only concern DEX quotes
+        if cfg.dex_bridge_ticker.is_some() && engine.counter.is_dex() {
+            engine = engine.with_dex_bridge(bridge_rx.clone());
         }
-        result = gateio_task => {
-            if let Err(e) = result {
-                error!("gateio listener task failed: {}", e);
-            }
+        if cfg.peg_ticker.is_some() && cfg.depeg_action == DepegAction::Redenominate && engine.counter.is_dex() {
+            engine = engine.with_quote_peg(peg_rx.clone());
         }
-        result = dex_task => {
-            if let Err(e) = result {
-                error!("dex price fetcher task failed: {}", e);
+        if cfg.sizing {
+            match Sizer::new(&cfg, &engine.venue, provider.clone()) {
+                Ok(sizer) => engine = engine.with_sizer(Arc::new(sizer)),
+                Err(e) => warn!("{} opportunities will not be sized: {:#}", engine.venue, e),
             }
         }
-        result = bybit_arbitrage_task => {
-            if let Err(e) = result {
-                error!("bybit arbitrage engine task failed: {}", e);
-            }
+        engine
+    };
+
+    info!("initializing hypercore oracle reader...");
+    tokio::spawn(async move {
+        if let Err(e) = run_oracle_listener(oracle_tx).await {
+            error!("oracle reader error: {}", e);
+        }
+    });
+
+    // shorts the executor's `ShortSeller` opens in this book are bought
+    // back against the perp price
+    let shorts = ShortBook::default();
+    if cfg.short_selling
+        && let Some(client) = hyperliquid()
+    {
+        tokio::spawn(run_short_cover(cfg.clone(), client, shorts.clone(), oracle_rx.clone()));
+    }
+
+    let (pool_tx, pool_rx) = mpsc::unbounded_channel();
+    // CEX moves wake the DEX quoting up early
+    let cex_rxs: Vec<_> = cex_feeds.iter().map(|(_, rx)| rx.clone()).collect();
+    let dex_task = if v3 {
+        info!("initializing hyperswap price fetcher...");
+        tokio::spawn(run_hyperswap_listener(hyperswap_tx, risk_tx.clone(), oracle_rx, cex_rxs, pool_rx))
+    } else {
+        info!("initializing {:?} DEX quoter...", cfg.dex_kind);
+        let router = router_for(&cfg).context(Failure::Config)?;
+        tokio::spawn(run_quote_listener(cfg.clone(), router, provider.clone(), hyperswap_tx, cex_rxs, pool_rx))
+    };
+
+    // the engines see the pool as it will be after pending swaps land
+    let engine_dex_rx = if cfg.predict_pending_swaps && v3 {
+        info!("initializing mempool monitor...");
+        let (predicted_tx, predicted_rx) = watch::channel::<Option<PriceData>>(None);
+        tokio::spawn(run_mempool_monitor(cfg.clone(), hyperswap_rx.clone(), predicted_tx));
+        predicted_rx
+    } else {
+        hyperswap_rx.clone()
+    };
+
+    let capital = Capital::from_config(&cfg);
+    if let Some(capital) = capital.clone() {
+        tokio::spawn(run_capital_rebalancer(
+            capital,
+            risk.exposure.clone(),
+            hyperswap_rx.clone(),
+            Duration::from_secs(cfg.capital_rebalance_secs),
+        ));
+    }
+
+    let market_maker_task = if cfg.strategy == StrategyKind::MarketMaking {
+        info!("initializing hypercore market maker...");
+        Some(tokio::spawn(run_market_maker(
+            cfg.clone(),
+            hyperswap_rx.clone(),
+            risk.clone(),
+            provider.clone(),
+            tracer.clone(),
+            state.clone(),
+            capital.clone(),
+        )))
+    } else {
+        None
+    };
+    let market_maker_task = async {
+        match market_maker_task {
+            Some(task) => task.await,
+            None => std::future::pending().await,
         }
-        result = gateio_arbitrage_task => {
-            if let Err(e) = result {
-                error!("gateio arbitrage engine task failed: {}", e);
+    };
+
+    info!("initializing arbitrage matrix scanner...");
+    let spreads = Spreads::from_config(&cfg);
+    let (scanner_tx, scanner_rx) = mpsc::unbounded_channel();
+    let mut feeds: Vec<_> = cex_feeds.iter().map(|(venue, rx)| (VenueId::Cex(venue.clone()), rx.clone())).collect();
+    feeds.push((VenueId::Dex(DEX_VENUE.to_string()), engine_dex_rx));
+    let mut scanner = MatrixScanner::new(&cfg, feeds, provider.clone(), risk.clone())
+    .map_engines(wire_engine)
+    .with_tracer(tracer.clone())
+    .with_commands(scanner_rx)
+    .with_spreads(spreads.clone());
+    // the market maker sends from the same wallet, two nonce sequences on
+    // it would collide
+    let mut exec_cfg = cfg.clone();
+    if cfg.strategy == StrategyKind::MarketMaking && cfg.execution_mode == ExecutionMode::Live {
+        warn!("the market maker trades the wallet, arbitrage opportunities are only logged");
+        exec_cfg.execution_mode = ExecutionMode::Log;
+    }
+    let mut executor = ArbExecutor::new(&exec_cfg, provider.clone(), risk.clone()).context(Failure::Config)?;
+    if exec_cfg.execution_mode == ExecutionMode::Log {
+        info!("🧪 dry run: opportunities are logged, not traded");
+    } else if exec_cfg.execution_mode == ExecutionMode::Paper {
+        info!("📝 paper trading: opportunities are filled against virtual balances");
+    } else {
+        // every wallet's swaps are followed by a tracker of its own
+        let mut senders = vec![TxSender::new(&cfg).await.context(Failure::Config)?];
+        for key in &cfg.executor_private_keys {
+            senders.push(TxSender::with_key(&cfg, key).await.context(Failure::Config)?);
+        }
+        for sender in senders {
+            let sender = Arc::new(sender);
+            let (tracker, _) = ConfirmationTracker::new(&cfg, sender.clone());
+            let tracker = tracker.with_updates(tx_updates.clone());
+            executor = executor.with_sender(sender, tracker.handle());
+            tokio::spawn(tracker.run(risk_tx.subscribe()));
+        }
+        executor = executor.with_state(state.clone());
+        if cfg.short_selling
+            && let Some(client) = hyperliquid()
+        {
+            executor = executor.with_short_seller(ShortSeller::new(cfg.clone(), client, shorts.clone()));
+        }
+        // extra accounts are tried before the venue's own keys
+        for account in &cfg.cex_accounts {
+            executor = executor.with_account(cex_account(&cfg, account).context(Failure::Config)?.with_state(state.clone()));
+        }
+        // venues with borrow rates fund legs on margin
+        for venue in cfg.borrow_rates.iter().map(|r| r.venue.as_str()).collect::<BTreeSet<_>>() {
+            let margin: Arc<dyn MarginAccount> = match venue {
+                "bybit" => Arc::new(BybitClient::new(&cfg).context(Failure::Config)?),
+                "gateio" => Arc::new(GateioClient::new(&cfg).context(Failure::Config)?),
+                venue => {
+                    warn!("{} legs are not borrowed for, no margin account", venue);
+                    continue;
+                }
+            };
+            executor = executor.with_margin(margin);
+        }
+        for venue in &cfg.cex_venues {
+            match cex_orders(&cfg, venue) {
+                Ok(orders) => executor = executor.with_venue(orders),
+                Err(_) if cfg.cex_accounts.iter().any(|a| &a.venue == venue) => {}
+                Err(e) => warn!("{} opportunities will not be traded: {:#}", venue, e),
             }
         }
     }
+    if let Some(capital) = capital {
+        executor = executor.with_capital(capital.clone());
+        scanner = scanner.with_capital(capital);
+    }
+    scanner = scanner.with_executor(Arc::new(executor));
+
+    let (maintenance, maintenance_handle) =
+        Maintenance::new(&cfg, risk_tx.clone(), provider.clone(), state.clone()).context(Failure::Config)?;
+    let drain_handle = maintenance_handle.clone();
+    tokio::spawn(maintenance.with_pool_commands(pool_tx.clone()).run());
+
+    // pairs can be added and removed and maintenance started through the control socket
+    if let Some(path) = cfg.control_socket.clone() {
+        let pairs = PairManager::new(cfg.clone(), provider, risk, pool_tx, scanner_tx)
+            .with_notifier(notifier.clone())
+            .with_tracer(tracer.clone());
+        let control = Control {
+            pairs: Some(Arc::new(pairs)),
+            maintenance: Some(maintenance_handle),
+            spreads: Some(spreads),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = run_control_socket(path, control).await {
+                error!("control socket error: {:#}", e);
+            }
+        });
+    }
 
-    Ok(())
+    let scanner_task = tokio::spawn(async move {
+        if let Err(e) = scanner.run().await {
+            error!("arbitrage scanner error: {}", e);
+        }
+    });
+
+    let venues: Vec<String> = cex_feeds.iter().map(|(venue, _)| venue.clone()).collect();
+    let feed_task = async {
+        if feed_tasks.is_empty() {
+            return std::future::pending().await;
+        }
+        let (result, i, _) = select_all(feed_tasks).await;
+        (venues[i].clone(), result)
+    };
+
+    // every task runs for the life of the bot, one that stops is a crash
+    let stopped = tokio::select! {
+        _ = shutdown_signal() => {
+            return drain(drain_handle, Duration::from_secs(cfg.shutdown_drain_secs)).await;
+        }
+        result = risk_task => match result {
+            Ok(Err(e)) => format!("risk manager error: {}", e),
+            Err(e) => format!("risk manager task failed: {}", e),
+            Ok(Ok(())) => "risk manager stopped".to_string(),
+        },
+        (venue, result) = feed_task => match result {
+            Err(e) => format!("{} listener task failed: {}", venue, e),
+            Ok(()) => format!("{} listener stopped", venue),
+        },
+        result = dex_task => match result {
+            Ok(Err(e)) => format!("dex price fetcher error: {:#}", e),
+            Err(e) => format!("dex price fetcher task failed: {}", e),
+            Ok(Ok(())) => "dex price fetcher stopped".to_string(),
+        },
+        result = scanner_task => match result {
+            Err(e) => format!("arbitrage scanner task failed: {}", e),
+            Ok(()) => "arbitrage scanner stopped".to_string(),
+        },
+        result = market_maker_task => match result {
+            Ok(Err(e)) => format!("market maker error: {:#}", e),
+            Err(e) => format!("market maker task failed: {}", e),
+            Ok(Ok(())) => "market maker stopped".to_string(),
+        },
+    };
+    error!("{}", stopped);
+    Err(anyhow!(stopped)).context(Failure::Crashed)
 }
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::providers::Provider;
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::{Instant, MissedTickBehavior, interval, sleep};
+
+use crate::adapters::hyperswap::PoolCommand;
+use crate::risk::RiskEvent;
+use crate::schedule::CronWindow;
+use crate::settings::Settings;
+use crate::state::StateStore;
+
+/// Where a maintenance wind-down stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaintenancePhase {
+    #[default]
+    Off,
+    /// No new executions, waiting for in-flight transactions to land.
+    Draining,
+    /// Settled and saved, DEX quoting stopped until maintenance ends.
+    Idle,
+}
+
+impl MaintenancePhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaintenancePhase::Off => "off",
+            MaintenancePhase::Draining => "draining",
+            MaintenancePhase::Idle => "idle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceCommand {
+    Enter,
+    Exit,
+}
+
+/// Starts and ends maintenance of a running `Maintenance` task.
+#[derive(Debug, Clone)]
+pub struct MaintenanceHandle {
+    commands: mpsc::UnboundedSender<MaintenanceCommand>,
+    phase: watch::Receiver<MaintenancePhase>,
+}
+
+impl MaintenanceHandle {
+    pub fn send(&self, command: MaintenanceCommand) -> Result<()> {
+        self.commands.send(command).map_err(|_| anyhow!("maintenance task is not running"))
+    }
+
+    pub fn phase(&self) -> MaintenancePhase {
+        *self.phase.borrow()
+    }
+
+    /// Waits until the phase is `phase`.
+    pub async fn wait_for(&mut self, phase: MaintenancePhase) -> Result<()> {
+        self.phase.wait_for(|p| *p == phase).await?;
+        Ok(())
+    }
+}
+
+/// Maintenance mode, entered on command or inside `maintenance_windows`,
+/// winds the bot down gracefully rather than killing it: trading pauses
+/// (so no new executions and market-maker quotes are pulled), in-flight
+/// transactions get up to `maintenance_settle_secs` to land, the state is
+/// saved and DEX quoting stops. CEX feeds stay connected so the bot picks
+/// up right away once maintenance ends.
+pub struct Maintenance {
+    risk_tx: broadcast::Sender<RiskEvent>,
+    provider: Arc<dyn Provider>,
+    store: StateStore,
+    state_file: Option<String>,
+    pool_tx: Option<mpsc::UnboundedSender<PoolCommand>>,
+    windows: Vec<CronWindow>,
+    settle_timeout: Duration,
+    phase_tx: watch::Sender<MaintenancePhase>,
+    commands: mpsc::UnboundedReceiver<MaintenanceCommand>,
+}
+
+impl Maintenance {
+    pub fn new(
+        cfg: &Settings,
+        risk_tx: broadcast::Sender<RiskEvent>,
+        provider: Arc<dyn Provider>,
+        store: StateStore,
+    ) -> Result<(Self, MaintenanceHandle)> {
+        let windows = cfg.maintenance_windows.iter().map(|w| CronWindow::parse(w)).collect::<Result<_>>()?;
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let (phase_tx, phase) = watch::channel(MaintenancePhase::Off);
+
+        let maintenance = Self {
+            risk_tx,
+            provider,
+            store,
+            state_file: cfg.state_file.clone(),
+            pool_tx: None,
+            windows,
+            settle_timeout: Duration::from_secs(cfg.maintenance_settle_secs),
+            phase_tx,
+            commands,
+        };
+        Ok((maintenance, MaintenanceHandle { commands: commands_tx, phase }))
+    }
+
+    /// Idles the DEX listener taking `pool_tx` commands while in maintenance.
+    pub fn with_pool_commands(mut self, pool_tx: mpsc::UnboundedSender<PoolCommand>) -> Self {
+        self.pool_tx = Some(pool_tx);
+        self
+    }
+
+    pub async fn run(mut self) {
+        let mut in_window = false;
+        let mut tick = interval(Duration::from_secs(1));
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(MaintenanceCommand::Enter) => self.enter().await,
+                    Some(MaintenanceCommand::Exit) => self.exit(),
+                    None => return,
+                },
+                _ = tick.tick(), if !self.windows.is_empty() => {
+                    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+                    let scheduled = self.windows.iter().any(|w| w.matches(now_ms));
+                    // only edges, so an operator can end a scheduled window early
+                    if scheduled != in_window {
+                        in_window = scheduled;
+                        if scheduled { self.enter().await } else { self.exit() }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn enter(&mut self) {
+        if *self.phase_tx.borrow() != MaintenancePhase::Off {
+            return;
+        }
+        info!("🛠️ entering maintenance, draining...");
+        self.phase_tx.send_replace(MaintenancePhase::Draining);
+        let _ = self.risk_tx.send(RiskEvent::Maintenance { active: true });
+
+        let deadline = Instant::now() + self.settle_timeout;
+        loop {
+            let pending = self.store.prune_landed(&*self.provider).await;
+            if pending == 0 {
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!("{} txs still in flight after {:?}, idling anyway", pending, self.settle_timeout);
+                break;
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        if let Some(path) = &self.state_file
+            && let Err(e) = self.store.save(path)
+        {
+            warn!("failed to save state to {}: {:#}", path, e);
+        }
+        if let Some(pool_tx) = &self.pool_tx {
+            let _ = pool_tx.send(PoolCommand::Idle(true));
+        }
+        self.phase_tx.send_replace(MaintenancePhase::Idle);
+        info!("🛠️ in maintenance, idle");
+    }
+
+    fn exit(&mut self) {
+        if *self.phase_tx.borrow() == MaintenancePhase::Off {
+            return;
+        }
+        if let Some(pool_tx) = &self.pool_tx {
+            let _ = pool_tx.send(PoolCommand::Idle(false));
+        }
+        let _ = self.risk_tx.send(RiskEvent::Maintenance { active: false });
+        self.phase_tx.send_replace(MaintenancePhase::Off);
+        info!("🛠️ maintenance over");
+    }
+}
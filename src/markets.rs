@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow, bail};
+use log::{info, warn};
+
+use crate::adapters::{bybit, gateio, hyperliquid};
+use crate::settings::Settings;
+
+/// Trading rules of a CEX symbol, as the exchange enforces them on orders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketMeta {
+    pub symbol: String,
+    pub tick_size: f64,
+    pub lot_size: f64,
+    pub min_qty: f64,
+    // quote-token value an order must reach
+    pub min_notional: f64,
+    pub price_precision: u32,
+    pub qty_precision: u32,
+    // significant figures a price may carry, where the tick grows with the
+    // price rather than being `tick_size` throughout
+    pub sig_figs: Option<u32>,
+}
+
+impl MarketMeta {
+    /// Rounds a limit order onto the tick and lot grid and checks it against
+    /// the minimums. Prices round away from the book (buys down, sells up)
+    /// so rounding never pays more than asked; quantities round down.
+    pub fn round_order(&self, is_buy: bool, px: f64, qty: f64) -> Result<(f64, f64)> {
+        if !(px.is_finite() && px > 0.0 && qty.is_finite() && qty > 0.0) {
+            bail!("{}: invalid order {} @ {}", self.symbol, qty, px);
+        }
+
+        let tick_size = self.tick_at(px);
+        let ticks = px / tick_size;
+        let ticks = if is_buy { (ticks + 1e-9).floor() } else { (ticks - 1e-9).ceil() };
+        let px = to_precision(ticks * tick_size, self.price_precision);
+        let qty = to_precision((qty / self.lot_size + 1e-9).floor() * self.lot_size, self.qty_precision);
+
+        if px <= 0.0 {
+            bail!("{}: price rounds to zero on a {} tick", self.symbol, tick_size);
+        }
+        if qty < self.min_qty || qty <= 0.0 {
+            bail!("{}: quantity {} is below the minimum of {}", self.symbol, qty, self.min_qty);
+        }
+        if px * qty < self.min_notional {
+            bail!("{}: notional {:.4} is below the minimum of {}", self.symbol, px * qty, self.min_notional);
+        }
+        Ok((px, qty))
+    }
+
+    /// Tick of a price around `px`: `tick_size`, or coarser where
+    /// `sig_figs` leaves fewer decimals.
+    pub fn tick_at(&self, px: f64) -> f64 {
+        match self.sig_figs {
+            Some(sig_figs) => self.tick_size.max(10f64.powi(px.log10().floor() as i32 + 1 - sig_figs as i32)),
+            None => self.tick_size,
+        }
+    }
+}
+
+fn to_precision(value: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (value * scale).round() / scale
+}
+
+/// Decimal places of a step such as `"0.001"`.
+pub fn step_precision(step: &str) -> u32 {
+    match step.trim_end_matches('0').split_once('.') {
+        Some((_, decimals)) => decimals.len() as u32,
+        None => 0,
+    }
+}
+
+/// Market metadata per venue, fetched once at startup. Outgoing orders go
+/// through `round_order`, and a venue whose metadata could not be loaded
+/// takes none rather than risking a rejection mid-trade.
+#[derive(Debug, Clone, Default)]
+pub struct Markets {
+    by_venue: BTreeMap<String, MarketMeta>,
+}
+
+impl Markets {
+    pub fn new(by_venue: BTreeMap<String, MarketMeta>) -> Self {
+        Self { by_venue }
+    }
+
+    /// Metadata of `bybit_ticker`, `gateio_ticker` and HyperCore spot pair
+    /// `mm_spot_pair_index`. A venue that fails is left out with a warning.
+    pub async fn fetch(cfg: &Settings) -> Self {
+        let (bybit, gateio, hyperliquid) =
+            tokio::join!(bybit::fetch_market(cfg), gateio::fetch_market(cfg), hyperliquid::fetch_market(cfg));
+
+        let mut by_venue = BTreeMap::new();
+        for (venue, meta) in [("bybit", bybit), ("gateio", gateio), ("hyperliquid", hyperliquid)] {
+            match meta {
+                Ok(meta) => {
+                    info!(
+                        "📏 {} {}: tick {}, lot {}, min qty {}, min notional {}",
+                        venue, meta.symbol, meta.tick_size, meta.lot_size, meta.min_qty, meta.min_notional
+                    );
+                    by_venue.insert(venue.to_string(), meta);
+                }
+                Err(e) => warn!("{} market metadata unavailable, its orders will be refused: {:#}", venue, e),
+            }
+        }
+        Self { by_venue }
+    }
+
+    pub fn get(&self, venue: &str) -> Option<&MarketMeta> {
+        self.by_venue.get(venue)
+    }
+
+    /// `MarketMeta::round_order` for `venue`.
+    pub fn round_order(&self, venue: &str, is_buy: bool, px: f64, qty: f64) -> Result<(f64, f64)> {
+        self.get(venue)
+            .ok_or_else(|| anyhow!("no market metadata for {}", venue))?
+            .round_order(is_buy, px, qty)
+    }
+}
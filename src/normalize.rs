@@ -0,0 +1,65 @@
+use alloy::primitives::U256;
+
+use crate::arbitrage::{Denomination, PriceData};
+use crate::settings::Settings;
+
+/// Converts between raw on-chain token amounts and the canonical prices
+/// and amounts the engine works with: `quote` per `base` in whole tokens,
+/// whatever the decimals of either token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalizer {
+    pub denomination: Denomination,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+}
+
+impl Normalizer {
+    pub fn new(cfg: &Settings) -> Self {
+        Self { denomination: cfg.denomination(), base_decimals: cfg.base_decimals, quote_decimals: cfg.quote_decimals }
+    }
+
+    /// Whole base tokens in `raw` units.
+    pub fn base(&self, raw: U256) -> f64 {
+        scale_down(raw, self.base_decimals)
+    }
+
+    /// Whole quote tokens in `raw` units.
+    pub fn quote(&self, raw: U256) -> f64 {
+        scale_down(raw, self.quote_decimals)
+    }
+
+    /// Raw units of `amount` base tokens, rounded down.
+    pub fn base_units(&self, amount: f64) -> U256 {
+        scale_up(amount, self.base_decimals)
+    }
+
+    /// Raw units of `amount` quote tokens, rounded down.
+    pub fn quote_units(&self, amount: f64) -> U256 {
+        scale_up(amount, self.quote_decimals)
+    }
+
+    /// Raw units of one base token, the volume of unit-price quotes.
+    pub fn one_base(&self) -> U256 {
+        U256::from(10u8).pow(U256::from(self.base_decimals))
+    }
+
+    /// Price per base token of a DEX quote: `quote_raw` received for (bid)
+    /// or paid for (ask) `base_raw`.
+    pub fn dex_price(&self, quote_raw: u128, base_raw: U256) -> f64 {
+        let base = self.base(base_raw);
+        if base > 0.0 { self.quote(U256::from(quote_raw)) / base } else { 0.0 }
+    }
+
+    /// The book of a DEX quote of `base_raw` in both directions.
+    pub fn dex_book(&self, bid_raw: u128, ask_raw: u128, base_raw: U256) -> PriceData {
+        PriceData::new(self.dex_price(bid_raw, base_raw), self.dex_price(ask_raw, base_raw))
+    }
+}
+
+fn scale_down(raw: U256, decimals: u8) -> f64 {
+    f64::from(raw) / 10f64.powi(decimals as i32)
+}
+
+fn scale_up(amount: f64, decimals: u8) -> U256 {
+    U256::from((amount.max(0.0) * 10f64.powi(decimals as i32)) as u128)
+}
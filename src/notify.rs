@@ -0,0 +1,283 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{Address, TxHash};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::alerts::{AlertLimiter, alert_key};
+use crate::arbitrage::ArbDirection;
+use crate::opportunity::{Opportunity, OpportunityId};
+use crate::pnl::TradeRecord;
+use crate::settings::{Secret, Settings};
+use crate::storage::Storage;
+
+/// Header carrying the hex HMAC-SHA256 of the body under `webhook_secret`,
+/// prefixed with `sha256=`.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// What a sink queues before dropping further items.
+pub const QUEUE_CAPACITY: usize = 1024;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client for the outbound sinks, giving up on a request after
+/// `HTTP_TIMEOUT` so a hung endpoint can't stall its queue.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder().timeout(HTTP_TIMEOUT).build().expect("tls backend available")
+}
+
+/// Queues `item` for `sink` without waiting, dropping it when the sink has
+/// fallen `QUEUE_CAPACITY` behind.
+pub fn offer<T>(tx: &mpsc::Sender<T>, item: T, sink: &str) {
+    if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(item) {
+        warn!("⚠️ {} queue full, dropping", sink);
+    }
+}
+
+/// An opportunity's pair, the base size it is priced at, what it nets
+/// there with gas paid once, and the CEX and DEX prices its legs take.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct OpportunityDetails {
+    pub pair: String,
+    pub size: f64,
+    pub expected_net: f64,
+    pub cex_price: f64,
+    pub dex_price: f64,
+}
+
+/// What the webhook is told about, one JSON object per request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    Opportunity {
+        ts_ms: u64,
+        id: OpportunityId,
+        venue: String,
+        direction: ArbDirection,
+        gross: f64,
+        cex_fee: f64,
+        gas_cost: f64,
+        borrow_cost: f64,
+        net: f64,
+        #[serde(flatten)]
+        details: OpportunityDetails,
+    },
+    Trade {
+        ts_ms: u64,
+        // the opportunity the trade executed, when it came from one
+        opportunity_id: Option<OpportunityId>,
+        venue: String,
+        // CEX sub-account and wallet the legs went out on, when known
+        #[serde(skip_serializing_if = "Option::is_none")]
+        account: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        wallet: Option<Address>,
+        dex_tx: TxHash,
+        direction: ArbDirection,
+        base_amount: f64,
+        cex_price: f64,
+        cex_fee: f64,
+        borrow_cost: f64,
+        pnl: f64,
+        estimated_pnl: f64,
+    },
+    /// The daily summary, see `summary::run_daily_summary`.
+    Summary {
+        ts_ms: u64,
+        date: String,
+        text: String,
+        html: String,
+    },
+}
+
+impl Notification {
+    pub fn opportunity(venue: &str, opportunity: &Opportunity, details: OpportunityDetails) -> Self {
+        let profit = &opportunity.profit;
+        Self::Opportunity {
+            ts_ms: now_ms(),
+            id: opportunity.id,
+            venue: venue.to_string(),
+            direction: opportunity.direction.clone(),
+            gross: profit.gross,
+            cex_fee: profit.cex_fee,
+            gas_cost: profit.gas_cost,
+            borrow_cost: profit.borrow_cost,
+            net: profit.net,
+            details,
+        }
+    }
+
+    pub fn trade(venue: &str, trade: &TradeRecord) -> Self {
+        Self::Trade {
+            ts_ms: now_ms(),
+            opportunity_id: trade.opportunity_id,
+            venue: venue.to_string(),
+            account: trade.account.clone(),
+            wallet: trade.wallet,
+            dex_tx: trade.dex_tx,
+            direction: trade.direction.clone(),
+            base_amount: trade.base_amount,
+            cex_price: trade.cex_price,
+            cex_fee: trade.cex_fee,
+            borrow_cost: trade.borrow_cost,
+            pnl: trade.pnl,
+            estimated_pnl: trade.estimated_pnl,
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Hands notifications to the webhook sink without waiting on it. Cheap to
+/// clone; a notifier without a sink drops everything.
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    tx: Option<mpsc::Sender<Notification>>,
+    min_profit: f64,
+    // lets a persistent opportunity out once per webhook_cooldown_secs
+    limiter: Option<Arc<Mutex<AlertLimiter>>>,
+    // sees every opportunity and trade, see `with_summary`
+    summary_tx: Option<mpsc::Sender<Notification>>,
+    // opportunities for Telegram/Discord, see `with_chat`
+    chat_tx: Option<mpsc::Sender<Notification>>,
+    chat_min_profit: f64,
+    // every opportunity, see `with_storage`
+    storage: Storage,
+}
+
+impl Notifier {
+    /// Notifier for `webhook_url` and the task delivering to it, `None`
+    /// for the task when no webhook is configured.
+    pub fn from_config(cfg: &Settings) -> (Self, Option<WebhookSink>) {
+        let Some(url) = cfg.webhook_url.clone() else {
+            return (Self::default(), None);
+        };
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let limiter = AlertLimiter::new(Duration::from_secs(cfg.webhook_cooldown_secs), cfg.webhook_max_per_hour);
+        let notifier = Self {
+            tx: Some(tx),
+            min_profit: cfg.webhook_min_profit,
+            limiter: Some(Arc::new(Mutex::new(limiter))),
+            ..Default::default()
+        };
+        (notifier, Some(WebhookSink::new(url, cfg.webhook_secret.clone(), rx)))
+    }
+
+    /// Also feeds every opportunity and trade to the daily summary.
+    pub fn with_summary(mut self, summary_tx: mpsc::Sender<Notification>) -> Self {
+        self.summary_tx = Some(summary_tx);
+        self
+    }
+
+    /// Also pushes opportunities netting at least `min_profit` to the chat
+    /// alerts, see `alerts::ChatAlerts`.
+    pub fn with_chat(mut self, chat_tx: mpsc::Sender<Notification>, min_profit: f64) -> Self {
+        self.chat_tx = Some(chat_tx);
+        self.chat_min_profit = min_profit;
+        self
+    }
+
+    /// Also stores every opportunity, see `storage::Storage`.
+    pub fn with_storage(mut self, storage: Storage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Only opportunities netting at least `webhook_min_profit` go out, the
+    /// same venue, pair and direction at most once per
+    /// `webhook_cooldown_secs`.
+    pub fn opportunity(&self, venue: &str, opportunity: &Opportunity, details: OpportunityDetails) {
+        let notification = Notification::opportunity(venue, opportunity, details);
+        self.to_summary(&notification);
+        self.storage.record(&notification);
+        if let Some(tx) = &self.chat_tx
+            && opportunity.profit.net >= self.chat_min_profit
+        {
+            offer(tx, notification.clone(), "chat");
+        }
+        if opportunity.profit.net >= self.min_profit && self.admit(&notification) {
+            self.send(notification);
+        }
+    }
+
+    pub fn trade(&self, venue: &str, trade: &TradeRecord) {
+        let notification = Notification::trade(venue, trade);
+        self.to_summary(&notification);
+        self.send(notification);
+    }
+
+    pub fn summary(&self, date: &str, text: String, html: String) {
+        self.send(Notification::Summary { ts_ms: now_ms(), date: date.to_string(), text, html });
+    }
+
+    fn admit(&self, notification: &Notification) -> bool {
+        let (Some(limiter), Some(key)) = (&self.limiter, alert_key(notification)) else {
+            return true;
+        };
+        limiter.lock().unwrap().admit(&key, Instant::now()).is_some()
+    }
+
+    fn to_summary(&self, notification: &Notification) {
+        if let Some(tx) = &self.summary_tx {
+            offer(tx, notification.clone(), "summary");
+        }
+    }
+
+    fn send(&self, notification: Notification) {
+        if let Some(tx) = &self.tx {
+            offer(tx, notification, "webhook");
+        }
+    }
+}
+
+/// POSTs notifications to the webhook one at a time, signing each body when
+/// a secret is set. Failed deliveries are logged and dropped.
+pub struct WebhookSink {
+    http: reqwest::Client,
+    url: String,
+    secret: Option<Secret>,
+    rx: mpsc::Receiver<Notification>,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, secret: Option<Secret>, rx: mpsc::Receiver<Notification>) -> Self {
+        Self { http: http_client(), url, secret, rx }
+    }
+
+    pub async fn run(mut self) {
+        info!("delivering notifications to {}", self.url);
+        while let Some(notification) = self.rx.recv().await {
+            if let Err(e) = self.deliver(&notification).await {
+                warn!("webhook delivery failed: {:#}", e);
+            }
+        }
+    }
+
+    async fn deliver(&self, notification: &Notification) -> Result<()> {
+        let body = serde_json::to_vec(notification)?;
+
+        let mut request = self.http.post(&self.url).header("Content-Type", "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret.expose().as_bytes(), &body)));
+        }
+
+        request.body(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Hex HMAC-SHA256 of `body` under `key`, as receivers should recompute it.
+pub fn sign(key: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::keccak256;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::arbitrage::{ArbDirection, ArbProfit};
+
+// like span ids: a per-process seed hashed with a counter, random enough
+// that ids of separate runs never collide
+static ID_SEED: LazyLock<(u128, u32)> = LazyLock::new(|| {
+    let ns = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    (ns, std::process::id())
+});
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// UUID (version 4 layout) of one detected opportunity, carried by its
+/// logs, spans, alerts, audit entries, client order ids and trade record
+/// so a single arb can be followed through all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpportunityId([u8; 16]);
+
+impl OpportunityId {
+    pub fn new() -> Self {
+        let (seed, pid) = *ID_SEED;
+        let n = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let hash = keccak256([seed.to_be_bytes().as_slice(), &pid.to_be_bytes(), &n.to_be_bytes()].concat());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Self(bytes)
+    }
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl Default for OpportunityId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for OpportunityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = hex::encode(self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+    }
+}
+
+impl FromStr for OpportunityId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex: String = s.chars().filter(|c| *c != '-').collect();
+        let mut bytes = [0u8; 16];
+        if s.len() != 36 || hex::decode_to_slice(&hex, &mut bytes).is_err() {
+            bail!("{:?} is not a uuid", s);
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for OpportunityId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for OpportunityId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A candidate trade an engine found, under the id it is known by from
+/// then on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opportunity {
+    pub id: OpportunityId,
+    pub direction: ArbDirection,
+    pub profit: ArbProfit,
+}
+
+impl Opportunity {
+    pub fn new(direction: ArbDirection, profit: ArbProfit) -> Self {
+        Self { id: OpportunityId::new(), direction, profit }
+    }
+}
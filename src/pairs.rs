@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use anyhow::{Context, Result, anyhow, bail};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use crate::adapters::hyperswap::PoolCommand;
+use crate::adapters::{AdapterRegistry, run_listener};
+use crate::arbitrage::{ArbEngine, DEX_VENUE, PriceData, VenueId};
+use crate::notify::Notifier;
+use crate::risk::RiskHandle;
+use crate::scanner::{DEFAULT_PAIR, ScannerCommand};
+use crate::screening;
+use crate::settings::{DexKind, Settings};
+use crate::telemetry::Tracer;
+
+/// A pair to trade besides the configured one. Token decimals default to
+/// those of the configured pair and the pool is quoted and routed through
+/// the contracts of `dex_profile`, else those of the configured pair.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairSpec {
+    pub name: String,
+    pub base_addr: Address,
+    pub quote_addr: Address,
+    pub pool_addr: Address,
+    pub fee_tier: u32,
+    #[serde(default)]
+    pub bybit_ticker: Option<String>,
+    #[serde(default)]
+    pub gateio_ticker: Option<String>,
+    #[serde(default)]
+    pub base_decimals: Option<u8>,
+    #[serde(default)]
+    pub quote_decimals: Option<u8>,
+    #[serde(default)]
+    pub dex_profile: Option<String>,
+}
+
+impl PairSpec {
+    /// The bot config with this pair in place of the configured one.
+    pub fn settings(&self, cfg: &Settings) -> Result<Settings> {
+        let mut cfg = cfg.clone();
+        if let Some(name) = &self.dex_profile {
+            let profile = cfg.find_dex_profile(name)?.clone();
+            cfg.quoter_v2_addr = profile.quoter_addr;
+            cfg.router_addr = profile.router_addr;
+            cfg.quoter_kind = profile.quoter_kind;
+        }
+        cfg.weth_addr = self.base_addr;
+        cfg.usdt_addr = self.quote_addr;
+        cfg.pool_addr = self.pool_addr;
+        cfg.dex_fee_tier = self.fee_tier;
+        cfg.extra_pools.clear();
+        cfg.dex_bridge_ticker = None;
+        if let Some(ticker) = &self.bybit_ticker {
+            cfg.bybit_ticker = ticker.clone();
+        }
+        if let Some(ticker) = &self.gateio_ticker {
+            cfg.gateio_ticker = ticker.clone();
+        }
+        cfg.base_decimals = self.base_decimals.unwrap_or(cfg.base_decimals);
+        cfg.quote_decimals = self.quote_decimals.unwrap_or(cfg.quote_decimals);
+        Ok(cfg)
+    }
+}
+
+/// Adds and removes pairs while the bot runs: screens the pair's tokens
+/// when `screen_tokens` is set, spawns its CEX feeds, has the DEX listener
+/// hydrate and quote its pool, and registers its engines with the scanner.
+/// Removing tears all of that down again.
+pub struct PairManager {
+    cfg: Settings,
+    provider: Arc<dyn Provider>,
+    risk: RiskHandle,
+    notifier: Notifier,
+    tracer: Tracer,
+    pool_tx: mpsc::UnboundedSender<PoolCommand>,
+    scanner_tx: mpsc::UnboundedSender<ScannerCommand>,
+    pairs: Mutex<BTreeMap<String, AddedPair>>,
+}
+
+struct AddedPair {
+    spec: PairSpec,
+    feed_tasks: Vec<JoinHandle<()>>,
+}
+
+impl PairManager {
+    pub fn new(
+        cfg: Settings,
+        provider: Arc<dyn Provider>,
+        risk: RiskHandle,
+        pool_tx: mpsc::UnboundedSender<PoolCommand>,
+        scanner_tx: mpsc::UnboundedSender<ScannerCommand>,
+    ) -> Self {
+        Self {
+            cfg,
+            provider,
+            risk,
+            notifier: Notifier::default(),
+            tracer: Tracer::default(),
+            pool_tx,
+            scanner_tx,
+            pairs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Starts trading `spec`, refused when its tokens fail screening. Needs
+    /// a multi-threaded tokio runtime to screen on and spawn its feeds on.
+    pub async fn add(&self, spec: PairSpec) -> Result<()> {
+        if spec.name == DEFAULT_PAIR || spec.name.is_empty() || spec.name.contains(char::is_whitespace) {
+            bail!("invalid pair name {:?}", spec.name);
+        }
+        if spec.bybit_ticker.is_none() && spec.gateio_ticker.is_none() {
+            bail!("pair {} needs a bybit or gateio ticker", spec.name);
+        }
+        self.check_new(&spec.name)?;
+
+        let cfg = spec.settings(&self.cfg)?;
+        // as the configured pair is at startup
+        if cfg.screen_tokens && cfg.dex_kind != DexKind::V3 {
+            warn!("token screening needs dex_kind v3, skipped for pair {}", spec.name);
+        } else if cfg.screen_tokens {
+            screening::screen_pair(&cfg, Arc::new(self.provider.root().clone()))
+                .await
+                .with_context(|| format!("pair {} refused", spec.name))?;
+        }
+
+        // another add of the same name may have gone through while screening
+        let mut pairs = self.pairs.lock().map_err(|_| anyhow!("pair registry lock poisoned"))?;
+        if pairs.contains_key(&spec.name) {
+            bail!("pair {} is already trading", spec.name);
+        }
+        let (dex_tx, dex_rx) = watch::channel::<Option<PriceData>>(None);
+        self.pool_tx
+            .send(PoolCommand::Add { name: spec.name.clone(), cfg: Box::new(cfg.clone()), tx: dex_tx })
+            .map_err(|_| anyhow!("DEX listener is not running"))?;
+
+        let mut tasks = Vec::new();
+        let mut feeds = vec![(VenueId::Dex(DEX_VENUE.to_string()), dex_rx)];
+        let cex_tickers = [("bybit", spec.bybit_ticker.clone()), ("gateio", spec.gateio_ticker.clone())];
+        for (venue, ticker) in cex_tickers {
+            let Some(ticker) = ticker else {
+                continue;
+            };
+            let (cex_tx, cex_rx) = watch::channel::<Option<PriceData>>(None);
+            let adapter = AdapterRegistry::builtin().get(venue)?;
+            tasks.push(tokio::spawn(run_listener(adapter, cfg.clone(), ticker, cex_tx, self.risk.events.clone())));
+            feeds.push((VenueId::Cex(format!("{}:{}", venue, spec.name)), cex_rx));
+        }
+
+        let engines = ArbEngine::across(&cfg, &feeds, self.provider.clone(), self.risk.clone())
+            .into_iter()
+            .map(|engine| engine.with_notifier(self.notifier.clone()).with_tracer(self.tracer.clone()))
+            .collect();
+        let feeds = feeds.into_iter().map(|(_, rx)| rx).collect();
+        let _ = self.scanner_tx.send(ScannerCommand::AddPair { pair: spec.name.clone(), engines, feeds });
+        info!("➕ added pair {} (pool {})", spec.name, spec.pool_addr);
+        pairs.insert(spec.name.clone(), AddedPair { spec, feed_tasks: tasks });
+        Ok(())
+    }
+
+    fn check_new(&self, name: &str) -> Result<()> {
+        let pairs = self.pairs.lock().map_err(|_| anyhow!("pair registry lock poisoned"))?;
+        if pairs.contains_key(name) {
+            bail!("pair {} is already trading", name);
+        }
+        Ok(())
+    }
+
+    /// Stops trading a pair added with `add`.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let mut pairs = self.pairs.lock().map_err(|_| anyhow!("pair registry lock poisoned"))?;
+        let Some(added) = pairs.remove(name) else {
+            bail!("no pair {} was added", name);
+        };
+
+        // engines go first, so none sees its feeds close
+        let _ = self.scanner_tx.send(ScannerCommand::RemovePair { pair: name.to_string() });
+        let _ = self.pool_tx.send(PoolCommand::Remove { name: name.to_string() });
+        for task in added.feed_tasks {
+            task.abort();
+        }
+        info!("➖ removed pair {}", name);
+        Ok(())
+    }
+
+    /// Pairs added at runtime.
+    pub fn list(&self) -> Vec<PairSpec> {
+        self.pairs
+            .lock()
+            .map(|pairs| pairs.values().map(|added| added.spec.clone()).collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use alloy::{
+    primitives::{Address, FixedBytes, TxHash, U256},
+    sol_types::SolCall,
+};
+use log::{info, warn};
+use tokio::sync::{broadcast, watch};
+
+use crate::arbitrage::{ArbDirection, PriceData};
+use crate::borrow::BorrowCost;
+use crate::execution::confirmations::{TxState, TxUpdate};
+use crate::execution::core_bridge::{CORE_WRITER_ADDRESS, HYPE_SYSTEM_ADDRESS};
+use crate::execution::verify::VerifiedFill;
+use crate::normalize::Normalizer;
+use crate::opportunity::{Opportunity, OpportunityId};
+use crate::helpers::abi::{ICoreWriter, approveCall, depositCall, exactInputSingleCall, exactOutputSingleCall, withdrawCall};
+use crate::settings::Settings;
+
+/// Where the legs of a trade settled: the CEX venue and account its order
+/// went out on, the wallet that sent the DEX swap and its price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub venue: String,
+    pub account: Option<String>,
+    pub wallet: Option<Address>,
+    pub dex_tx: TxHash,
+    pub cex_price: f64,
+}
+
+impl Settlement {
+    /// Settled on the venue's own keys and an unnamed wallet.
+    pub fn new(venue: &str, dex_tx: TxHash, cex_price: f64) -> Self {
+        Self { venue: venue.to_string(), account: None, wallet: None, dex_tx, cex_price }
+    }
+
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.account = Some(account.to_string());
+        self
+    }
+
+    pub fn with_wallet(mut self, wallet: Address) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+}
+
+/// One completed arbitrage, priced from what actually settled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeRecord {
+    pub opportunity_id: Option<OpportunityId>,
+    pub dex_tx: TxHash,
+    // CEX sub-account and on-chain wallet the legs went out on, when known
+    pub account: Option<String>,
+    pub wallet: Option<Address>,
+    pub direction: ArbDirection,
+    pub base_amount: f64,
+    // quote token paid (BuyDex) or received (BuyCex) on the DEX
+    pub dex_quote_amount: f64,
+    // what the pre-trade quote promised for the same leg
+    pub estimated_dex_quote_amount: f64,
+    pub cex_price: f64,
+    pub cex_fee: f64,
+    pub borrow_cost: f64,
+    pub pnl: f64,
+    pub estimated_pnl: f64,
+}
+
+/// Trades, volume and realized PnL of one account or wallet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountPnl {
+    pub trades: u64,
+    pub volume_base: f64,
+    pub pnl: f64,
+}
+
+impl AccountPnl {
+    pub fn add(&mut self, base_amount: f64, pnl: f64) {
+        self.trades += 1;
+        self.volume_base += base_amount;
+        self.pnl += pnl;
+    }
+}
+
+/// Realized PnL in quote-token terms. Trades are recorded from verified
+/// receipts, never from the quote they were opened on.
+#[derive(Debug, Default)]
+pub struct PnlLedger {
+    pub trades: Vec<TradeRecord>,
+    pub realized: f64,
+    // realized minus what the quotes promised, i.e. slippage cost
+    pub estimate_drift: f64,
+    // broken down by CEX sub-account (the venue when on its own keys) and
+    // by the wallet sending the swaps
+    pub by_account: BTreeMap<String, AccountPnl>,
+    pub by_wallet: BTreeMap<Address, AccountPnl>,
+}
+
+impl PnlLedger {
+    pub fn record(&mut self, cfg: &Settings, settlement: &Settlement, opportunity: &Opportunity, fill: &VerifiedFill) -> &TradeRecord {
+        let Settlement { venue, account, wallet, dex_tx, cex_price } = settlement.clone();
+        let norm = Normalizer::new(cfg);
+        let direction = opportunity.direction.clone();
+        let to_base = |amount: U256| norm.base(amount);
+        let to_quote = |amount: U256| norm.quote(amount);
+
+        let (base_amount, dex_quote_amount, estimated_dex_quote_amount) = match direction {
+            // base bought on the CEX is sold into the pool
+            ArbDirection::BuyCex => (to_base(fill.amount_in), to_quote(fill.amount_out), to_quote(fill.quoted_out)),
+            ArbDirection::BuyDex => (to_base(fill.amount_out), to_quote(fill.amount_in), to_quote(fill.quoted_in)),
+        };
+
+        let cex_notional = base_amount * cex_price;
+        let cex_fee = cex_notional * cfg.cex_fee_bps as f64 / 10000.0;
+        let gross = |dex_quote: f64| match direction {
+            ArbDirection::BuyCex => dex_quote - cex_notional,
+            ArbDirection::BuyDex => cex_notional - dex_quote,
+        };
+
+        let borrow_cost = BorrowCost::new(cfg, &venue).cost(&direction, base_amount, cex_price);
+        let costs = cex_fee + borrow_cost;
+        let (pnl, estimated_pnl) = (gross(dex_quote_amount) - costs, gross(estimated_dex_quote_amount) - costs);
+
+        let record = TradeRecord {
+            opportunity_id: Some(opportunity.id),
+            dex_tx,
+            account,
+            wallet,
+            direction,
+            base_amount,
+            dex_quote_amount,
+            estimated_dex_quote_amount,
+            cex_price,
+            cex_fee,
+            borrow_cost,
+            pnl,
+            estimated_pnl,
+        };
+
+        self.realized += record.pnl;
+        self.estimate_drift += record.pnl - record.estimated_pnl;
+        let account = record.account.clone().unwrap_or(venue);
+        self.by_account.entry(account).or_default().add(record.base_amount, record.pnl);
+        if let Some(wallet) = record.wallet {
+            self.by_wallet.entry(wallet).or_default().add(record.base_amount, record.pnl);
+        }
+
+        info!(
+            "💰 {:?} {:.4} base: pnl ${:.4} (estimated ${:.4}), realized ${:.4} [{}]",
+            record.direction, record.base_amount, record.pnl, record.estimated_pnl, self.realized, opportunity.id
+        );
+
+        self.trades.push(record);
+        self.trades.last().unwrap()
+    }
+}
+
+/// What a transaction was for, as far as gas accounting cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GasKind {
+    Swap,
+    Approval,
+    Bridge,
+    Other,
+}
+
+impl GasKind {
+    pub fn classify(to: Address, selector: Option<FixedBytes<4>>) -> Self {
+        let Some(selector) = selector else {
+            return if to == HYPE_SYSTEM_ADDRESS { Self::Bridge } else { Self::Other };
+        };
+
+        match selector.0 {
+            exactInputSingleCall::SELECTOR | exactOutputSingleCall::SELECTOR => Self::Swap,
+            approveCall::SELECTOR => Self::Approval,
+            ICoreWriter::sendRawActionCall::SELECTOR | withdrawCall::SELECTOR | depositCall::SELECTOR => {
+                Self::Bridge
+            }
+            _ if to == CORE_WRITER_ADDRESS => Self::Bridge,
+            _ => Self::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GasTotals {
+    pub txs: u64,
+    pub failed_txs: u64,
+    pub native: f64,
+    // valued at the native price when each tx confirmed
+    pub usd: f64,
+    pub failed_native: f64,
+    pub failed_usd: f64,
+    pub by_kind: BTreeMap<GasKind, f64>,
+}
+
+// confirmations remembered so one confirmed again after a reorg is not
+// booked twice
+const BOOKED_TXS: usize = 1024;
+
+/// Cumulative gas spent by every transaction the bot sent, reverted ones
+/// included, since those cost gas too.
+#[derive(Debug, Default)]
+pub struct GasLedger {
+    pub totals: GasTotals,
+    booked: VecDeque<TxHash>,
+}
+
+impl GasLedger {
+    /// Whether `hash` is booked for the first time, remembering it.
+    pub fn book(&mut self, hash: TxHash) -> bool {
+        if self.booked.contains(&hash) {
+            return false;
+        }
+        if self.booked.len() == BOOKED_TXS {
+            self.booked.pop_front();
+        }
+        self.booked.push_back(hash);
+        true
+    }
+
+    pub fn record(&mut self, kind: GasKind, success: bool, gas_used: u64, gas_price: u128, native_price: f64) {
+        let native = (gas_used as u128 * gas_price) as f64 / 1e18;
+        let usd = native * native_price;
+
+        let totals = &mut self.totals;
+        totals.txs += 1;
+        totals.native += native;
+        totals.usd += usd;
+        *totals.by_kind.entry(kind).or_default() += native;
+
+        if !success {
+            totals.failed_txs += 1;
+            totals.failed_native += native;
+            totals.failed_usd += usd;
+        }
+
+        info!(
+            "⛽ {:?} tx{} used {} gas ({:.6} native, ${:.4}), total {:.6} native ${:.4}",
+            kind, if success { "" } else { " (reverted)" }, gas_used, native, usd, totals.native, totals.usd
+        );
+    }
+}
+
+/// Books the gas of every confirmed transaction, valuing it at the latest
+/// `native_price_rx` mid, and publishes running totals.
+pub async fn run_gas_ledger(
+    mut updates_rx: broadcast::Receiver<TxUpdate>,
+    native_price_rx: watch::Receiver<Option<PriceData>>,
+    totals_tx: watch::Sender<GasTotals>,
+) {
+    let mut ledger = GasLedger::default();
+
+    loop {
+        let update = match updates_rx.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("gas ledger lagged, {} tx updates missed", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let TxState::Confirmed { success, gas_used, effective_gas_price, .. } = update.state else {
+            continue;
+        };
+        if !ledger.book(update.hash) {
+            continue;
+        }
+
+        let native_price = native_price_rx
+            .borrow()
+            .as_ref()
+            .map(|p| (p.bid + p.ask) / 2.0)
+            .unwrap_or_default();
+        if native_price == 0.0 {
+            warn!("no native price for tx {}, booking its gas at $0", update.hash);
+        }
+
+        let kind = GasKind::classify(update.to, update.selector);
+        ledger.record(kind, success, gas_used, effective_gas_price, native_price);
+        totals_tx.send_replace(ledger.totals.clone());
+    }
+}
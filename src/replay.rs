@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::arbitrage::{ArbInputs, arb_decision, price_candidates};
+use crate::audit::{AuditEntry, Decision, entries};
+use crate::borrow::BorrowCost;
+use crate::settings::Settings;
+use crate::strategy::{self, Strategy};
+
+/// A past decision the current code makes differently.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayDiff {
+    pub seq: u64,
+    pub recorded: Decision,
+    pub replayed: Decision,
+}
+
+impl ReplayDiff {
+    /// Whether the decision flipped between taken and skipped rather than
+    /// only its numbers moving.
+    pub fn flipped(&self) -> bool {
+        self.recorded.taken != self.replayed.taken
+    }
+
+    /// Change of the best candidate's net profit.
+    pub fn net_change(&self) -> f64 {
+        match (best_net(&self.recorded), best_net(&self.replayed)) {
+            (Some(recorded), Some(replayed)) => replayed - recorded,
+            _ => 0.0,
+        }
+    }
+}
+
+fn best_net(decision: &Decision) -> Option<f64> {
+    decision
+        .outputs
+        .as_array()?
+        .iter()
+        .filter_map(|c| c["profit"]["net"].as_f64())
+        .reduce(f64::max)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+    // decisions run again
+    pub replayed: u64,
+    // decisions that depend on live state and cannot be run again
+    pub skipped: u64,
+    pub diffs: Vec<ReplayDiff>,
+}
+
+impl ReplayReport {
+    pub fn flipped(&self) -> usize {
+        self.diffs.iter().filter(|d| d.flipped()).count()
+    }
+
+    pub fn net_change(&self) -> f64 {
+        self.diffs.iter().map(ReplayDiff::net_change).sum()
+    }
+}
+
+/// Feeds the inputs of recorded engine evaluations through the strategy of
+/// `cfg` in their original order, per venue, and collects every decision
+/// that comes out differently. Market maker decisions hang on live order
+/// and balance state and are skipped.
+pub fn replay(cfg: &Settings, entries: &[AuditEntry]) -> Result<ReplayReport> {
+    let mut engines: BTreeMap<String, (Box<dyn Strategy>, BorrowCost)> = BTreeMap::new();
+    let mut report = ReplayReport::default();
+
+    for entry in entries {
+        let recorded = &entry.decision;
+        let Some(venue) = recorded.source.strip_prefix("engine:").filter(|_| recorded.action == "arb") else {
+            report.skipped += 1;
+            continue;
+        };
+
+        let inputs: ArbInputs = serde_json::from_value(recorded.inputs.clone())?;
+        let (strategy, borrow) = engines
+            .entry(venue.to_string())
+            .or_insert_with(|| (strategy::from_config(cfg), BorrowCost::new(cfg, venue)));
+
+        let prices = inputs.prices();
+        strategy.on_price_update(&prices.cex, &prices.dex);
+        let found = price_candidates(strategy.as_mut(), borrow, &prices, inputs.gas_price_wei);
+        let replayed = arb_decision(venue, &ArbInputs { strategy: strategy.name().to_string(), ..inputs }, &found);
+
+        report.replayed += 1;
+        if replayed.taken != recorded.taken || replayed.reason != recorded.reason || replayed.outputs != recorded.outputs {
+            report.diffs.push(ReplayDiff { seq: entry.seq, recorded: recorded.clone(), replayed });
+        }
+    }
+    Ok(report)
+}
+
+/// `replay` of the log at `path` once its chain checks out.
+pub fn replay_file(cfg: &Settings, path: impl AsRef<Path>) -> Result<ReplayReport> {
+    replay(cfg, &entries(path)?)
+}
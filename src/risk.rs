@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::future::select_all;
+use log::{info, warn};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::{broadcast, watch};
+use tokio::time::{Instant, sleep_until};
+
+use crate::arbitrage::{ArbDirection, PriceData, quote_peg};
+use crate::audit::Auditor;
+use crate::exposure::Exposure;
+use crate::markets::Markets;
+use crate::throttle::TradeThrottle;
+use crate::settings::Settings;
+use crate::switches::TradingSwitches;
+
+/// Conditions that make current prices or past executions untrustworthy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskEvent {
+    /// The chain replaced blocks after `fork_block`; anything observed or
+    /// confirmed above it must be re-verified.
+    Reorg {
+        fork_block: u64,
+        depth: u64,
+        new_head: u64,
+    },
+    /// The newest block we can see is older than the configured threshold,
+    /// either the chain halted or the RPC stopped following it.
+    ChainLag { head: u64, lag_secs: u64 },
+    /// Blocks are fresh again after a `ChainLag`.
+    ChainCaughtUp { head: u64 },
+    /// A CEX stopped (or resumed) trading the pair.
+    CexHalt { venue: String, halted: bool },
+    /// CEX and DEX prices are further apart than any real arb could be,
+    /// usually one side is broken rather than mispriced.
+    SpreadOutOfBounds { venue: String, spread_bps: f64 },
+    SpreadNormal { venue: String },
+    /// Reference price feeds disagree beyond the configured bound.
+    OracleDivergence { divergence_bps: f64 },
+    OracleAligned,
+    /// The quote stablecoin trades `depeg_bps` off its USD reference.
+    QuoteDepeg { peg: f64, depeg_bps: f64 },
+    QuotePegRestored,
+    /// Execution entered (`open`) or left the configured trading windows.
+    /// Detection goes on either way.
+    TradingWindow { open: bool },
+    /// Maintenance mode started winding down (`active`) or ended.
+    Maintenance { active: bool },
+}
+
+/// A condition currently holding trading paused.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseReason {
+    ChainHalt,
+    CexHalt(String),
+    SpreadOutOfBounds(String),
+    OracleDivergence,
+    QuoteDepeg,
+    Maintenance,
+}
+
+/// Manual control over the automatic pause logic.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OperatorOverride {
+    #[default]
+    Auto,
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradingState {
+    pub paused: bool,
+    pub reasons: Vec<PauseReason>,
+    pub operator: OperatorOverride,
+    // outside `trading_windows`: opportunities are still found, not executed
+    pub outside_window: bool,
+}
+
+/// What a component needs to take part in risk management: a way to raise
+/// events and a view of whether trading is allowed.
+#[derive(Debug, Clone)]
+pub struct RiskHandle {
+    pub events: broadcast::Sender<RiskEvent>,
+    pub trading: watch::Receiver<TradingState>,
+    pub exposure: Exposure,
+    pub throttle: TradeThrottle,
+    // every execution decision and its inputs, see `audit`
+    pub audit: Auditor,
+    // tick, lot and minimum rules of the CEX symbols, see `markets`
+    pub markets: Markets,
+    // venues and directions allowed to execute, see `switches`
+    pub switches: TradingSwitches,
+}
+
+impl RiskHandle {
+    pub fn emit(&self, event: RiskEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.trading.borrow().paused
+    }
+
+    /// Whether trades may be sent: not paused and inside the trading windows.
+    pub fn can_execute(&self) -> bool {
+        let state = self.trading.borrow();
+        !state.paused && !state.outside_window
+    }
+
+    /// Whether a trade changing balances by `deltas` (per asset) keeps
+    /// exposure within `exposure_limits`; checked before every execution.
+    pub fn check_exposure(&self, deltas: &[(&str, f64)]) -> Result<()> {
+        self.exposure.check(deltas)
+    }
+
+    /// Counts an execution on `pair` against the `max_*trades_per_*`
+    /// limits, failing once they are used up.
+    pub fn throttle(&self, pair: &str) -> Result<()> {
+        self.throttle.acquire(pair)
+    }
+
+    /// Whether `venue` may execute `direction` under `venue_trading`.
+    pub fn check_venue(&self, venue: &str, direction: &ArbDirection) -> Result<()> {
+        self.switches.check(venue, direction)
+    }
+
+    /// An order for `venue` rounded onto its tick and lot grid, failing
+    /// when it would be rejected for precision or minimums.
+    pub fn check_order(&self, venue: &str, is_buy: bool, px: f64, qty: f64) -> Result<(f64, f64)> {
+        self.markets.round_order(venue, is_buy, px, qty)
+    }
+}
+
+pub fn risk_channel() -> (broadcast::Sender<RiskEvent>, broadcast::Receiver<RiskEvent>) {
+    broadcast::channel(64)
+}
+
+/// Turns risk events into a trading pause. Trading stops as soon as any
+/// trigger fires and resumes `auto_resume_secs` after the last one cleared.
+/// The operator can force either state with signals: SIGUSR1 pauses,
+/// SIGUSR2 resumes, SIGHUP hands control back to the triggers.
+pub struct RiskManager {
+    triggers: BTreeSet<PauseReason>,
+    operator: OperatorOverride,
+    auto_resume: Duration,
+    resume_at: Option<Instant>,
+    outside_window: bool,
+    state_tx: watch::Sender<TradingState>,
+}
+
+impl RiskManager {
+    pub fn new(cfg: &Settings) -> (Self, watch::Receiver<TradingState>) {
+        let (state_tx, state_rx) = watch::channel(TradingState::default());
+
+        let manager = Self {
+            triggers: BTreeSet::new(),
+            operator: OperatorOverride::Auto,
+            auto_resume: Duration::from_secs(cfg.auto_resume_secs),
+            resume_at: None,
+            outside_window: false,
+            state_tx,
+        };
+
+        (manager, state_rx)
+    }
+
+    pub async fn run(mut self, mut rx: broadcast::Receiver<RiskEvent>) -> Result<()> {
+        let mut pause_signal = signal(SignalKind::user_defined1())?;
+        let mut resume_signal = signal(SignalKind::user_defined2())?;
+        let mut auto_signal = signal(SignalKind::hangup())?;
+
+        loop {
+            let resume_at = self.resume_at;
+
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Ok(event) => self.on_event(event),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("risk manager lagged, {} events dropped", n)
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+                _ = pause_signal.recv() => self.set_operator(OperatorOverride::Pause),
+                _ = resume_signal.recv() => self.set_operator(OperatorOverride::Resume),
+                _ = auto_signal.recv() => self.set_operator(OperatorOverride::Auto),
+                _ = sleep_until(resume_at.unwrap_or_else(Instant::now)), if resume_at.is_some() => {
+                    self.resume_at = None;
+                }
+            }
+
+            self.publish();
+        }
+    }
+
+    fn on_event(&mut self, event: RiskEvent) {
+        if let RiskEvent::TradingWindow { open } = event {
+            info!("🕐 trading window {}", if open { "opened" } else { "closed" });
+            self.outside_window = !open;
+            return;
+        }
+        warn!("🚨 risk event: {:?}", event);
+
+        match event {
+            // reorged state is dropped and re-read, nothing to hold off on
+            RiskEvent::Reorg { .. } => {}
+            RiskEvent::ChainLag { .. } => self.raise(PauseReason::ChainHalt),
+            RiskEvent::ChainCaughtUp { .. } => self.clear(PauseReason::ChainHalt),
+            RiskEvent::CexHalt { venue, halted: true } => self.raise(PauseReason::CexHalt(venue)),
+            RiskEvent::CexHalt { venue, halted: false } => self.clear(PauseReason::CexHalt(venue)),
+            RiskEvent::SpreadOutOfBounds { venue, .. } => self.raise(PauseReason::SpreadOutOfBounds(venue)),
+            RiskEvent::SpreadNormal { venue } => self.clear(PauseReason::SpreadOutOfBounds(venue)),
+            RiskEvent::OracleDivergence { .. } => self.raise(PauseReason::OracleDivergence),
+            RiskEvent::OracleAligned => self.clear(PauseReason::OracleDivergence),
+            RiskEvent::QuoteDepeg { .. } => self.raise(PauseReason::QuoteDepeg),
+            RiskEvent::QuotePegRestored => self.clear(PauseReason::QuoteDepeg),
+            RiskEvent::Maintenance { active: true } => self.raise(PauseReason::Maintenance),
+            RiskEvent::Maintenance { active: false } => self.clear(PauseReason::Maintenance),
+            RiskEvent::TradingWindow { .. } => {}
+        }
+    }
+
+    fn raise(&mut self, reason: PauseReason) {
+        self.triggers.insert(reason);
+        self.resume_at = None;
+    }
+
+    fn clear(&mut self, reason: PauseReason) {
+        if self.triggers.remove(&reason) && self.triggers.is_empty() {
+            self.resume_at = Some(Instant::now() + self.auto_resume);
+        }
+    }
+
+    fn set_operator(&mut self, operator: OperatorOverride) {
+        info!("operator override: {:?}", operator);
+        self.operator = operator;
+    }
+
+    fn publish(&self) {
+        let paused = match self.operator {
+            OperatorOverride::Pause => true,
+            OperatorOverride::Resume => false,
+            OperatorOverride::Auto => !self.triggers.is_empty() || self.resume_at.is_some(),
+        };
+
+        let state = TradingState {
+            paused,
+            reasons: self.triggers.iter().cloned().collect(),
+            operator: self.operator,
+            outside_window: self.outside_window,
+        };
+
+        self.state_tx.send_if_modified(|current| {
+            if *current == state {
+                return false;
+            }
+            if current.paused != state.paused {
+                if state.paused {
+                    warn!("⛔ trading paused: {:?} ({:?})", state.reasons, state.operator);
+                } else {
+                    info!("✅ trading resumed ({:?})", state.operator);
+                }
+            }
+            *current = state;
+            true
+        });
+    }
+}
+
+/// Watches reference price feeds and raises `OracleDivergence` while their
+/// mids spread wider than `max_bps` of the lowest one.
+pub async fn run_oracle_monitor(
+    mut feeds: Vec<watch::Receiver<Option<PriceData>>>,
+    risk_tx: broadcast::Sender<RiskEvent>,
+    max_bps: f64,
+) {
+    let mut diverged = false;
+
+    loop {
+        let (changed, _, _) = select_all(feeds.iter_mut().map(|feed| Box::pin(feed.changed()))).await;
+        if changed.is_err() {
+            return;
+        }
+
+        let mids: Vec<f64> = feeds
+            .iter()
+            .filter_map(|feed| feed.borrow().as_ref().map(|p| (p.bid + p.ask) / 2.0))
+            .filter(|mid| *mid > 0.0)
+            .collect();
+        if mids.len() < 2 {
+            continue;
+        }
+
+        let lo = mids.iter().cloned().fold(f64::MAX, f64::min);
+        let hi = mids.iter().cloned().fold(f64::MIN, f64::max);
+        let divergence_bps = (hi - lo) / lo * 10000.0;
+
+        if divergence_bps > max_bps && !diverged {
+            diverged = true;
+            let _ = risk_tx.send(RiskEvent::OracleDivergence { divergence_bps });
+        } else if divergence_bps <= max_bps && diverged {
+            diverged = false;
+            let _ = risk_tx.send(RiskEvent::OracleAligned);
+        }
+    }
+}
+
+/// Watches the quote stablecoin against its USD reference (see `quote_peg`)
+/// and raises `QuoteDepeg` while it trades more than `max_bps` off par:
+/// profits counted in a depegging asset are illusory.
+pub async fn run_peg_monitor(
+    mut peg_rx: watch::Receiver<Option<PriceData>>,
+    risk_tx: broadcast::Sender<RiskEvent>,
+    max_bps: f64,
+) {
+    let mut depegged = false;
+
+    while peg_rx.changed().await.is_ok() {
+        let Some(peg) = peg_rx.borrow().as_ref().map(quote_peg).filter(|p| p.is_finite() && *p > 0.0) else {
+            continue;
+        };
+        let depeg_bps = (1.0 - peg).abs() * 10000.0;
+
+        if depeg_bps > max_bps && !depegged {
+            depegged = true;
+            let _ = risk_tx.send(RiskEvent::QuoteDepeg { peg, depeg_bps });
+        } else if depeg_bps <= max_bps && depegged {
+            depegged = false;
+            let _ = risk_tx.send(RiskEvent::QuotePegRestored);
+        }
+    }
+}
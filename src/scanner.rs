@@ -0,0 +1,412 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::providers::Provider;
+use anyhow::Result;
+use futures_util::future::select_all;
+use log::{info, warn};
+use tokio::sync::{mpsc, watch};
+use tokio::time::sleep;
+
+use crate::arbitrage::{ArbDirection, ArbEngine, ArbProfit, PriceData, VenueId};
+use crate::capital::{self, Capital};
+use crate::execution::executor::{ArbExecutor, Execution, expected_net};
+use crate::notify::OpportunityDetails;
+use crate::opportunity::{Opportunity, OpportunityId};
+use crate::pnl::TradeRecord;
+use crate::risk::RiskHandle;
+use crate::settings::Settings;
+use crate::sizing::{OptimalSize, Sizer};
+use crate::spreads::Spreads;
+use crate::telemetry::Tracer;
+
+/// An opportunity found in one cell of the matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub pair: String,
+    pub venue: String,
+    pub dex_venue: String,
+    pub id: OpportunityId,
+    pub direction: ArbDirection,
+    pub profit: ArbProfit,
+    // the CEX side the leg takes, ask when buying there
+    pub cex_price: f64,
+    // the DEX side the other leg takes, bid when buying on the CEX
+    pub dex_price: f64,
+    // capital book of the strategy and pair, see `capital::book`
+    pub book: String,
+    // the size netting the most, for the best candidates of sizing engines
+    pub size: Option<OptimalSize>,
+    // both legs on CEXes, `dex_venue` naming the second: reported, never
+    // executed
+    pub cross_cex: bool,
+    // closes a position of the strategy, taken even at a loss
+    pub closing: bool,
+}
+
+impl Candidate {
+    pub fn opportunity(&self) -> Opportunity {
+        Opportunity { id: self.id, direction: self.direction.clone(), profit: self.profit }
+    }
+
+    /// Pair, size, net and leg prices, at `base_size` unless sized.
+    pub fn details(&self, base_size: f64) -> OpportunityDetails {
+        let (size, expected_net, cex_price, dex_price) = match &self.size {
+            Some(sized) => (sized.size, sized.net, sized.cex_price, sized.dex_price),
+            None => (base_size, expected_net(&self.profit, base_size), self.cex_price, self.dex_price),
+        };
+        OpportunityDetails { pair: self.pair.clone(), size, expected_net, cex_price, dex_price }
+    }
+}
+
+/// Name of the pair the config describes, as opposed to pairs added at
+/// runtime.
+pub const DEFAULT_PAIR: &str = "default";
+
+/// Changes to the matrix while it runs.
+pub enum ScannerCommand {
+    /// Engines of a new pair, each trading against its `counter`, and the
+    /// pair's feeds to wake up on.
+    AddPair {
+        pair: String,
+        engines: Vec<ArbEngine>,
+        feeds: Vec<watch::Receiver<Option<PriceData>>>,
+    },
+    RemovePair {
+        pair: String,
+    },
+}
+
+struct Cell {
+    pair: String,
+    dex_venue: String,
+    engine: ArbEngine,
+}
+
+impl Cell {
+    fn new(pair: &str, engine: ArbEngine) -> Self {
+        Self { pair: pair.to_string(), dex_venue: engine.counter.name().to_string(), engine }
+    }
+
+    fn book(&self) -> String {
+        capital::book(self.engine.strategy_name(), &self.pair)
+    }
+}
+
+/// Evaluates every venue pair in one pass per tick: the gas price is read
+/// once for all of them and the results are ranked together, so the caller
+/// can pick the best venue per direction. Each pair keeps its own
+/// `ArbEngine` for strategy state, feed health and spread checks, see
+/// `ArbEngine::across` for which pairs there are.
+pub struct MatrixScanner {
+    cells: Vec<Cell>,
+    // one receiver per distinct feed and the pair it belongs to, only to
+    // wake up on
+    feeds: Vec<(String, watch::Receiver<Option<PriceData>>)>,
+    provider: Arc<dyn Provider>,
+    tracer: Tracer,
+    feed_stale: Duration,
+    commands: Option<mpsc::UnboundedReceiver<ScannerCommand>>,
+    capital: Option<Capital>,
+    executor: Option<Arc<ArbExecutor>>,
+    spreads: Option<Spreads>,
+    // trades the executor settled, handed back to their pair by `on_fill`
+    fills_tx: mpsc::UnboundedSender<Fill>,
+    fills_rx: mpsc::UnboundedReceiver<Fill>,
+}
+
+// a settled trade with the CEX and DEX venue it was taken on
+type Fill = (String, String, TradeRecord);
+
+impl MatrixScanner {
+    pub fn new(
+        cfg: &Settings,
+        feeds: Vec<(VenueId, watch::Receiver<Option<PriceData>>)>,
+        provider: Arc<dyn Provider>,
+        risk: RiskHandle,
+    ) -> Self {
+        let cells = ArbEngine::across(cfg, &feeds, provider.clone(), risk)
+            .into_iter()
+            .map(|engine| Cell::new(DEFAULT_PAIR, engine))
+            .collect();
+        let feeds = feeds.into_iter().map(|(_, rx)| (DEFAULT_PAIR.to_string(), rx)).collect();
+        let (fills_tx, fills_rx) = mpsc::unbounded_channel();
+        Self {
+            cells,
+            feeds,
+            provider,
+            tracer: Tracer::default(),
+            feed_stale: Duration::from_secs(cfg.feed_stale_secs),
+            commands: None,
+            capital: None,
+            executor: None,
+            spreads: None,
+            fills_tx,
+            fills_rx,
+        }
+    }
+
+    /// Applies `wire` to every cell's engine, e.g. to add a bridge or peg.
+    pub fn map_engines(mut self, mut wire: impl FnMut(ArbEngine) -> ArbEngine) -> Self {
+        self.cells = self
+            .cells
+            .into_iter()
+            .map(|cell| Cell { engine: wire(cell.engine), ..cell })
+            .collect();
+        self
+    }
+
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Gives every strategy/pair scanned a capital budget and credits it
+    /// with the PnL of its fills.
+    pub fn with_capital(mut self, capital: Capital) -> Self {
+        for cell in &self.cells {
+            capital.register(&cell.book());
+        }
+        self.capital = Some(capital);
+        self
+    }
+
+    /// Takes pairs to add or remove from `commands`, see `pairs::PairManager`.
+    pub fn with_commands(mut self, commands: mpsc::UnboundedReceiver<ScannerCommand>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    /// Hands the best opportunity per direction to `executor`, without
+    /// waiting for it.
+    pub fn with_executor(mut self, executor: Arc<ArbExecutor>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Records the net spread of every candidate priced in `spreads`.
+    pub fn with_spreads(mut self, spreads: Spreads) -> Self {
+        self.spreads = Some(spreads);
+        self
+    }
+
+    pub fn apply(&mut self, command: ScannerCommand) {
+        match command {
+            ScannerCommand::AddPair { pair, engines, feeds } => {
+                self.remove_pair(&pair);
+                info!("scanning pair {} on {} venue pairs", pair, engines.len());
+                for engine in engines {
+                    let cell = Cell::new(&pair, engine);
+                    if let Some(capital) = &self.capital {
+                        capital.register(&cell.book());
+                    }
+                    self.cells.push(cell);
+                }
+                self.feeds.extend(feeds.into_iter().map(|rx| (pair.clone(), rx)));
+            }
+            ScannerCommand::RemovePair { pair } => {
+                if self.remove_pair(&pair) {
+                    info!("stopped scanning pair {}", pair);
+                }
+                if let Some(spreads) = &self.spreads {
+                    spreads.forget(&pair);
+                }
+            }
+        }
+    }
+
+    fn remove_pair(&mut self, pair: &str) -> bool {
+        let before = self.cells.len();
+        if let Some(capital) = &self.capital {
+            for cell in self.cells.iter().filter(|c| c.pair == pair) {
+                // a book with executions under way keeps its budget
+                let _ = capital.lock().unregister(&cell.book());
+            }
+        }
+        self.cells.retain(|c| c.pair != pair);
+        self.feeds.retain(|(p, _)| p != pair);
+        self.cells.len() != before
+    }
+
+    /// Pairs being scanned, each once.
+    pub fn pairs(&self) -> Vec<&str> {
+        let mut pairs: Vec<&str> = self.cells.iter().map(|c| c.pair.as_str()).collect();
+        pairs.dedup();
+        pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        info!("starting matrix scanner over {} venue pairs...", self.cells.len());
+
+        loop {
+            let (command, closed, fill) = {
+                let feeds = &mut self.feeds;
+                let changed = async {
+                    // select_all wants at least one future
+                    if feeds.is_empty() {
+                        return std::future::pending().await;
+                    }
+                    select_all(feeds.iter_mut().map(|(_, rx)| Box::pin(rx.changed()))).await
+                };
+                let command = async {
+                    match self.commands.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                };
+                // the timeout notices feeds going quiet
+                tokio::select! {
+                    (result, i, _) = changed => (None, result.is_err().then_some(i), None),
+                    Some(command) = command => (Some(command), None, None),
+                    Some(fill) = self.fills_rx.recv() => (None, None, Some(fill)),
+                    _ = sleep(self.feed_stale) => (None, None, None),
+                }
+            };
+            // a feed whose task ended would wake us in a loop
+            if let Some(i) = closed {
+                let (pair, _) = self.feeds.remove(i);
+                warn!("a feed of pair {} closed", pair);
+            }
+            if let Some(command) = command {
+                self.apply(command);
+                continue;
+            }
+            if let Some((venue, dex_venue, trade)) = fill {
+                self.on_fill(&venue, &dex_venue, &trade);
+                continue;
+            }
+
+            let found = self.scan().await?;
+            for candidate in &found {
+                if let Some(cell) = self.cells.iter().find(|c| c.engine.venue == candidate.venue) {
+                    let details = candidate.details(cell.engine.config.exec_base_size);
+                    cell.engine.notifier.opportunity(&candidate.venue, &candidate.opportunity(), details);
+                }
+            }
+            for direction in [ArbDirection::BuyCex, ArbDirection::BuyDex] {
+                if let Some(best) = best(&found, &direction).filter(|c| c.profit.net > 0.0 || c.closing) {
+                    info!(
+                        "🏆 best {:?}: {}/{} net ${:.4} [{}]",
+                        direction, best.venue, best.dex_venue, best.profit.net, best.id
+                    );
+                    if let Some(size) = &best.size {
+                        info!(
+                            "📐 {:.4} nets ${:.4} (cex ${:.4}, dex ${:.4}) [{}]",
+                            size.size, size.net, size.cex_price, size.dex_price, best.id
+                        );
+                    }
+                    if let Some(executor) = self.executor.clone() {
+                        let (best, fills) = (best.clone(), self.fills_tx.clone());
+                        tokio::spawn(async move {
+                            if let Execution::Sent { trade: Some(trade), .. } = executor.execute(&best).await {
+                                let _ = fills.send((best.venue, best.dex_venue, trade));
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaluates every ready pair at one gas price read, best first. Reads
+    /// no gas price while no pair is ready.
+    pub async fn scan(&mut self) -> Result<Vec<Candidate>> {
+        let ready: Vec<_> = self
+            .cells
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, cell)| Some((i, cell.engine.tick_prices()?)))
+            .collect();
+        if ready.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut tick = self.tracer.span("scan");
+        tick.set("pairs", ready.len());
+
+        let gas_span = tick.child("gas_price");
+        let gas_price_wei = self.provider.get_gas_price().await?;
+        gas_span.end();
+
+        let mut found = Vec::new();
+        let mut pegs = Vec::new();
+        for (i, prices) in ready {
+            let cell = &mut self.cells[i];
+            for Opportunity { id, direction, profit } in cell.engine.evaluate(&prices, gas_price_wei, &tick) {
+                let (cex_price, dex_price) = prices.legs(&direction);
+                if let Some(spreads) = &self.spreads {
+                    spreads.record(&cell.pair, &direction, profit.net);
+                }
+                let closing = cell.engine.closes(&direction);
+                found.push(Candidate {
+                    pair: cell.pair.clone(),
+                    venue: cell.engine.venue.clone(),
+                    dex_venue: cell.dex_venue.clone(),
+                    id,
+                    direction,
+                    profit,
+                    cex_price,
+                    dex_price,
+                    book: cell.book(),
+                    size: None,
+                    cross_cex: !cell.engine.counter.is_dex(),
+                    closing,
+                });
+                pegs.push((i, prices.peg));
+            }
+        }
+
+        let mut found: Vec<_> = found.into_iter().zip(pegs).collect();
+        found.sort_by(|(a, _), (b, _)| b.profit.net.total_cmp(&a.profit.net));
+        let sizers: Vec<_> = self.cells.iter().map(|cell| cell.engine.sizer()).collect();
+        size_best(&sizers, &mut found).await;
+        Ok(found.into_iter().map(|(candidate, _)| candidate).collect())
+    }
+
+    /// Hands a settled trade back to the pair it was taken on.
+    pub fn on_fill(&mut self, venue: &str, dex_venue: &str, trade: &TradeRecord) {
+        if let Some(cell) = self.cells.iter_mut().find(|c| c.engine.venue == venue && c.dex_venue == dex_venue) {
+            cell.engine.on_fill(trade);
+            if let Some(capital) = &self.capital {
+                capital.record_pnl(&cell.book(), trade.pnl);
+            }
+        }
+    }
+}
+
+/// The most profitable candidate trading `direction` that can be executed,
+/// from a ranked scan.
+pub fn best<'a>(found: &'a [Candidate], direction: &ArbDirection) -> Option<&'a Candidate> {
+    found
+        .iter()
+        .filter(|c| c.direction == *direction && !c.cross_cex)
+        .max_by(|a, b| a.profit.net.total_cmp(&b.profit.net))
+}
+
+// sizes the best candidate per direction that beats its costs before
+// gas, which a larger size spreads thinner
+async fn size_best(sizers: &[Option<Arc<Sizer>>], found: &mut [(Candidate, (usize, Option<f64>))]) {
+    for direction in [ArbDirection::BuyCex, ArbDirection::BuyDex] {
+        let Some((candidate, (cell, peg))) = found.iter_mut().find(|(c, _)| c.direction == direction && !c.cross_cex) else {
+            continue;
+        };
+        if candidate.profit.net + candidate.profit.gas_cost <= 0.0 {
+            continue;
+        }
+        let Some(sizer) = &sizers[*cell] else {
+            continue;
+        };
+        match sizer.size(&direction, &candidate.profit, *peg).await {
+            Ok(size) => candidate.size = size,
+            Err(e) => warn!("sizing {} {:?} failed: {:#}", candidate.venue, direction, e),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow, bail};
+use tokio::sync::broadcast;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::risk::RiskEvent;
+use crate::settings::Settings;
+
+/// A UTC time window in cron syntax: `minute hour day-of-month month
+/// day-of-week`, each field `*`, a value, a range `a-b`, a step `*/n` or
+/// `a-b/n`, or a comma-separated list of those. Days of the week run from 0
+/// (Sunday) to 6. Unlike cron, a minute matches only when every field
+/// matches, day of month and day of week included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronWindow {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+}
+
+impl CronWindow {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!("trading window {:?} needs 5 fields, has {}", expr, fields.len());
+        };
+
+        let parse = |field: &str, min: u32, max: u32| {
+            parse_field(field, min, max).map_err(|e| anyhow!("trading window {:?}: {}", expr, e))
+        };
+        Ok(Self {
+            minutes: parse(minutes, 0, 59)?,
+            hours: parse(hours, 0, 23)?,
+            days: parse(days, 1, 31)?,
+            months: parse(months, 1, 12)?,
+            weekdays: parse(weekdays, 0, 6)?,
+        })
+    }
+
+    /// Whether the minute of unix ms timestamp `ms` falls in the window.
+    pub fn matches(&self, ms: u64) -> bool {
+        let secs = ms / 1000;
+        let days_since_epoch = secs / 86_400;
+        let (_, month, day) = civil_date(ms);
+        // 1970-01-01 was a Thursday
+        let weekday = (days_since_epoch + 4) % 7;
+
+        let has = |set: u64, value: u64| set & (1 << value) != 0;
+        has(self.minutes, secs / 60 % 60)
+            && has(self.hours, secs / 3600 % 24)
+            && has(self.days, day as u64)
+            && has(self.months, month as u64)
+            && has(self.weekdays, weekday)
+    }
+}
+
+// bit n set for every value n the field matches
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| anyhow!("bad step in {:?}", part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("zero step in {:?}", part);
+        }
+
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((lo, hi)) => (parse_value(lo, part)?, parse_value(hi, part)?),
+                None => {
+                    let value = parse_value(range, part)?;
+                    // `n/s` runs from n to the end, as in cron
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if lo < min || hi > max || lo > hi {
+            bail!("{:?} is outside {}-{}", part, min, max);
+        }
+
+        for value in (lo..=hi).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32> {
+    value.parse().map_err(|_| anyhow!("bad value in {:?}", part))
+}
+
+/// Year, month and day (UTC) of a unix ms timestamp.
+pub fn civil_date(ms: u64) -> (i64, u32, u32) {
+    // inverse of `backtest::parse_date_ms`
+    let days = (ms / 86_400_000) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + (m <= 2) as i64;
+    (y, m as u32, d as u32)
+}
+
+/// When execution is enabled: inside any of `trading_windows`, or always
+/// when none are configured. Detection runs regardless.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradingSchedule {
+    windows: Vec<CronWindow>,
+}
+
+impl TradingSchedule {
+    pub fn from_config(cfg: &Settings) -> Result<Self> {
+        let windows = cfg.trading_windows.iter().map(|w| CronWindow::parse(w)).collect::<Result<_>>()?;
+        Ok(Self { windows })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn is_open(&self, ms: u64) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|w| w.matches(ms))
+    }
+}
+
+/// Raises `TradingWindow` whenever the schedule opens or closes, checked
+/// every second. The first check always reports.
+pub async fn run_trading_schedule(schedule: TradingSchedule, risk_tx: broadcast::Sender<RiskEvent>) {
+    let mut open = None;
+    let mut tick = interval(Duration::from_secs(1));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tick.tick().await;
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+        let is_open = schedule.is_open(now_ms);
+        if open != Some(is_open) {
+            open = Some(is_open);
+            let _ = risk_tx.send(RiskEvent::TradingWindow { open: is_open });
+        }
+    }
+}
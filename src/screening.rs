@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::Provider,
+};
+
+use revm::database::{AlloyDB, CacheDB, WrapDatabaseAsync};
+
+use anyhow::{Context, Result, bail};
+use log::info;
+
+use crate::helpers::abi::{
+    approve_calldata, balance_of_calldata, decode_balance_response, decode_bool_response,
+    decode_quote_response_for, quote_calldata_for, transfer_calldata, transfer_from_calldata,
+};
+use crate::helpers::revm::{init_cache_db, revm_call, revm_call_commit};
+use crate::normalize::Normalizer;
+use crate::settings::Settings;
+
+/// Simulation-based safety gate run before a pair is enabled for trading.
+///
+/// Uses a scratch cache with the tokens' real bytecode (no mocks) and
+/// impersonates the pool as a token holder, so honeypots, fee-on-transfer
+/// tokens and broken `transferFrom` implementations are rejected.
+pub async fn screen_pair<P: Provider + Clone>(cfg: &Settings, provider: Arc<P>) -> Result<()> {
+    let mut cache_db = init_cache_db(provider);
+
+    for token in [cfg.weth_addr, cfg.usdt_addr] {
+        screen_transfers(cfg, token, &mut cache_db)
+            .with_context(|| format!("token {token} failed transfer screening"))?;
+    }
+
+    screen_round_trip(cfg, &mut cache_db).context("pair failed round-trip screening")?;
+
+    info!("✅ token screening passed for {} / {}", cfg.weth_addr, cfg.usdt_addr);
+    Ok(())
+}
+
+/// Moves a small slice of the pool's balance to `self_addr` and then back
+/// into the pool, each way with both `transfer` and `approve` +
+/// `transferFrom`, checking that exactly the requested amount leaves the
+/// sender and arrives at the recipient. The way back is the one a sell
+/// takes, which a sell-side honeypot blocks or taxes.
+fn screen_transfers<P: Provider + Clone>(
+    cfg: &Settings,
+    token: Address,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<()> {
+    let pool = cfg.pool_addr;
+
+    let amount = balance_of(token, pool, cache_db)? / U256::from(1000);
+    if amount.is_zero() {
+        bail!("pool holds no balance to simulate transfers with");
+    }
+
+    screen_moves(token, pool, cfg.self_addr, amount, cache_db).context("from the pool")?;
+    screen_moves(token, cfg.self_addr, pool, amount, cache_db).context("into the pool")?;
+
+    Ok(())
+}
+
+/// `amount` from `holder` to `recipient` with `transfer`, then once more
+/// with `approve` + `transferFrom`, the recipient acting as spender.
+fn screen_moves<P: Provider + Clone>(
+    token: Address,
+    holder: Address,
+    recipient: Address,
+    amount: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<()> {
+    // plain transfer
+    let (holder_before, recipient_before) =
+        (balance_of(token, holder, cache_db)?, balance_of(token, recipient, cache_db)?);
+    let ok = revm_call_commit(holder, token, transfer_calldata(recipient, amount), cache_db)
+        .context("transfer reverted")?;
+    if !decode_bool_response(ok)? {
+        bail!("transfer returned false");
+    }
+    check_deltas(token, holder, recipient, amount, holder_before, recipient_before, cache_db)
+        .context("transfer")?;
+
+    // approve + transferFrom
+    let ok = revm_call_commit(holder, token, approve_calldata(recipient, amount), cache_db)
+        .context("approve reverted")?;
+    if !decode_bool_response(ok)? {
+        bail!("approve returned false");
+    }
+
+    let (holder_before, recipient_before) =
+        (balance_of(token, holder, cache_db)?, balance_of(token, recipient, cache_db)?);
+    let ok = revm_call_commit(
+        recipient,
+        token,
+        transfer_from_calldata(holder, recipient, amount),
+        cache_db,
+    )
+    .context("transferFrom reverted")?;
+    if !decode_bool_response(ok)? {
+        bail!("transferFrom returned false");
+    }
+    check_deltas(token, holder, recipient, amount, holder_before, recipient_before, cache_db)
+        .context("transferFrom")?;
+
+    Ok(())
+}
+
+/// Sells one unit of base through the quoter and buys it back with the
+/// proceeds; anything beyond the two pool fees plus the configured tolerance
+/// points to hidden transfer taxes or a one-way pool.
+fn screen_round_trip<P: Provider + Clone>(
+    cfg: &Settings,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<()> {
+    let volume = Normalizer::new(cfg).base_units(1.0);
+
+    let sell_calldata = quote_calldata_for(
+        cfg.quoter_kind,
+        cfg.weth_addr,
+        cfg.usdt_addr,
+        volume,
+        cfg.dex_fee_tier,
+        cfg.self_addr,
+    );
+    let sell_response = revm_call(cfg.self_addr, cfg.quoter_v2_addr, sell_calldata, cache_db)
+        .context("sell quote reverted")?;
+    let quote_out = decode_quote_response_for(cfg.quoter_kind, sell_response)?;
+    if quote_out == 0 {
+        bail!("selling base returns nothing");
+    }
+
+    let buy_calldata = quote_calldata_for(
+        cfg.quoter_kind,
+        cfg.usdt_addr,
+        cfg.weth_addr,
+        U256::from(quote_out),
+        cfg.dex_fee_tier,
+        cfg.self_addr,
+    );
+    let buy_response = revm_call(cfg.self_addr, cfg.quoter_v2_addr, buy_calldata, cache_db)
+        .context("buy-back quote reverted")?;
+    let base_back = decode_quote_response_for(cfg.quoter_kind, buy_response)?;
+
+    let volume = volume.to::<u128>();
+    let loss_bps = volume.saturating_sub(base_back) * 10_000 / volume;
+    // fee tiers are in hundredths of a bip
+    let max_loss_bps = 2 * cfg.dex_fee_tier as u128 / 100 + cfg.screen_max_round_trip_loss_bps as u128;
+
+    if loss_bps > max_loss_bps {
+        bail!("round trip lost {loss_bps} bps (max {max_loss_bps} bps)");
+    }
+
+    Ok(())
+}
+
+fn balance_of<P: Provider + Clone>(
+    token: Address,
+    account: Address,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<U256> {
+    // view call from a plain EOA, the account itself may be a contract
+    let response = revm_call(Address::ZERO, token, balance_of_calldata(account), cache_db)?;
+    decode_balance_response(response)
+}
+
+fn check_deltas<P: Provider + Clone>(
+    token: Address,
+    from: Address,
+    to: Address,
+    amount: U256,
+    from_before: U256,
+    to_before: U256,
+    cache_db: &mut CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, P>>>,
+) -> Result<()> {
+    let sent = from_before.saturating_sub(balance_of(token, from, cache_db)?);
+    let received = balance_of(token, to, cache_db)?.saturating_sub(to_before);
+
+    if sent != amount {
+        bail!("holder debited {sent} instead of {amount}");
+    }
+    if received != amount {
+        bail!("recipient credited {received} instead of {amount} (fee-on-transfer?)");
+    }
+
+    Ok(())
+}
@@ -1,27 +1,557 @@
+use std::collections::{BTreeMap, HashMap};
+
 use alloy::primitives::Address;
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use config;
 use dotenvy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::arbitrage::{ArbDirection, Denomination, GasToken};
+
+/// Quoting interface exposed by a DEX deployment. All kinds decode to the
+/// same amount so callers don't care which one a pool uses.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoterKind {
+    #[default]
+    V2,
+    V1,
+    // static-call the swap router itself; the caller must hold and have
+    // approved the input token for the call not to revert
+    Router,
+}
+
+/// Flavor of DEX the pair trades on, see `dex::router::DexRouter`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DexKind {
+    // concentrated liquidity pool, quoted by `quoter_v2_addr`
+    #[default]
+    V3,
+    // constant product pair behind a UniswapV2-style `router_addr`
+    V2,
+    // 0x-compatible swap API at `aggregator_url`, settling through
+    // `router_addr`
+    Aggregator,
+}
+
+/// Decision logic the arbitrage engines run, see `strategy`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    // cross the CEX and DEX books directly
+    #[default]
+    CexDex,
+    // trade deviations of the CEX–DEX spread from its rolling mean
+    MeanReversion,
+    // quote both sides on HyperCore around the DEX price, hedging fills on
+    // the DEX
+    MarketMaking,
+    // take the stale DEX price after a sharp CEX move, hedge on the CEX later
+    LatencyArb,
+}
+
+/// What to do while the quote stablecoin is off its peg.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DepegAction {
+    // pause trading beyond `max_depeg_bps`
+    #[default]
+    Halt,
+    // keep trading, counting profits in USD through the peg feed
+    Redenominate,
+}
+
+/// Which wallet of the pool a DEX leg goes out from, among those without a
+/// swap in flight.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletSelection {
+    // each in turn
+    #[default]
+    RoundRobin,
+    // the one holding the most of the token the swap spends
+    Balance,
+}
+
+/// What the executor does with an opportunity it would take.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    // logged and audited only
+    #[default]
+    Log,
+    // filled against virtual balances, see `execution::paper`
+    Paper,
+    // both legs sent
+    Live,
+}
+
+/// When the JSON log file starts over, besides on reaching `log_max_bytes`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+}
+
+/// An additional pool for the same pair, e.g. another fee tier or a fork.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PoolConfig {
+    pub pool_addr: Address,
+    pub fee_tier: u32,
+    // defaults to the quoter of `dex_profile`, else `quoter_v2_addr`
+    pub quoter_addr: Option<Address>,
+    #[serde(default)]
+    pub quoter_kind: QuoterKind,
+    // defaults to the router of `dex_profile`, else `router_addr`
+    pub router_addr: Option<Address>,
+    pub dex_profile: Option<String>,
+}
+
+/// Quoter and router of a DEX deployment, so pairs on it only name the
+/// profile instead of entering the addresses.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct DexProfile {
+    pub quoter_addr: Address,
+    pub router_addr: Address,
+    #[serde(default)]
+    pub quoter_kind: QuoterKind,
+}
+
+/// Hourly rate of borrowing `asset` (e.g. `USDT`) on margin at `venue`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct BorrowRate {
+    pub venue: String,
+    pub asset: String,
+    pub hourly_bps: f64,
+}
+
+/// Directions `venue` may execute, `*` for every venue. An empty list
+/// keeps the venue detection-only.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct VenueTrading {
+    pub venue: String,
+    pub directions: Vec<ArbDirection>,
+}
+
+/// A CEX venue streamed without code of its own, see
+/// `adapters::declarative`. Paths read like `data[0].bids[0][0]` and
+/// conditions like `event=update`; `{ticker}` in conditions, and `{ticker}`
+/// or `{time}` (unix seconds) in `subscribe`, are filled in.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct WsVenue {
+    pub name: String,
+    pub endpoint: String,
+    pub ticker: String,
+    // json messages sent once connected
+    pub subscribe: Vec<String>,
+    // conditions all met by messages carrying a price
+    #[serde(default)]
+    pub only_when: Vec<String>,
+    // conditions all met by error messages, which end the connection
+    #[serde(default)]
+    pub error_when: Vec<String>,
+    pub bid: String,
+    pub ask: String,
+    // unix ms of the quote, a number or a decimal string
+    pub timestamp: Option<String>,
+    // text message, "ping" unless set, sent after this long without one
+    // from the venue
+    pub keepalive_secs: Option<u64>,
+    pub keepalive_message: Option<String>,
+}
+
+/// An extra API-key account on a CEX, e.g. a sub-account kept for some
+/// pairs or strategy books. Empty `pairs` or `books` take any.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CexAccount {
+    pub name: String,
+    pub venue: String,
+    pub api_key: String,
+    pub api_secret: Secret,
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    #[serde(default)]
+    pub books: Vec<String>,
+    pub max_orders_per_minute: Option<u32>,
+    pub max_orders_per_hour: Option<u32>,
+}
+
+/// Bybit order book levels streamed for `symbol`, overriding
+/// `bybit_orderbook_depth`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct BookDepth {
+    pub symbol: String,
+    pub depth: u32,
+}
+
+/// Depths Bybit offers for spot order book streams.
+pub const BYBIT_DEPTHS: [u32; 3] = [1, 50, 200];
+
+/// Most the bot may hold of `asset` (`base` or `quote`), netting longs
+/// against shorts or adding them up.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ExposureLimit {
+    pub asset: String,
+    pub max_net: Option<f64>,
+    pub max_gross: Option<f64>,
+}
+
+/// A credential that must not show up in logs, e.g. when printing settings.
+#[derive(Deserialize, Clone)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
     pub self_addr: Address,
     pub weth_addr: Address,
     pub usdt_addr: Address,
+    // unset ones come from `dex_profile`
+    #[serde(default)]
     pub quoter_v2_addr: Address,
+    #[serde(default)]
+    pub router_addr: Address,
+    pub multicall3_addr: Address,
+    // the kind of `quoter_v2_addr`: `quoter_kind` if set, else that of the
+    // profile the quoter came from
+    #[serde(skip)]
+    pub quoter_kind: QuoterKind,
+    #[serde(default, rename = "quoter_kind")]
+    pub configured_quoter_kind: Option<QuoterKind>,
+    #[serde(default)]
+    pub dex_kind: DexKind,
+    pub aggregator_url: Option<String>,
+    pub aggregator_api_key: Option<Secret>,
+    pub dex_profile: Option<String>,
+    #[serde(default)]
+    pub dex_profiles: BTreeMap<String, DexProfile>,
+    // check at startup that the quoter and router bytecode has the
+    // functions of their kind; off for deployments behind proxies
+    pub verify_dex_interfaces: bool,
     pub pool_addr: Address,
 
+    // the traded pair: prices everywhere are `pair_quote` per `pair_base`,
+    // and gas is paid in `native_token`, which must be one of the two
+    pub pair_base: String,
+    pub pair_quote: String,
+    pub native_token: String,
+    // on-chain decimals of the base and quote tokens
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
     pub bybit_ticker: String,
     pub gateio_ticker: String,
+    pub okx_ticker: String,
+    // CEX venues streamed and traded against, see `adapters::AdapterRegistry`
+    pub cex_venues: Vec<String>,
+    // venues described by config rather than code, streamed when listed in
+    // cex_venues
+    #[serde(default)]
+    pub ws_venues: Vec<WsVenue>,
+    // every two of them evaluated against each other too, reported only
+    pub scan_cross_cex: bool,
+    // the hyperliquid venue is HyperCore spot pair mm_spot_pair_index, its
+    // market orders are IOC limits this far through the side they take
+    pub hyperliquid_ioc_slippage_bps: u32,
+    // per venue (or `*`) the directions executed, see `switches`; venues
+    // without an entry trade both
+    #[serde(default)]
+    pub venue_trading: Vec<VenueTrading>,
+    // CEX symbols quoted base per quote, flipped on the way in
+    #[serde(default)]
+    pub inverse_tickers: Vec<String>,
+    // levels of the bybit order book streams (1, 50 or 200): top of book is
+    // fastest, deeper books serve sizing and VWAP
+    pub bybit_orderbook_depth: u32,
+    #[serde(default)]
+    pub bybit_depths: Vec<BookDepth>,
+    // bybit symbol pricing the DEX quote token in the CEX quote token, when
+    // the pool trades against a different asset than the CEX pair
+    pub dex_bridge_ticker: Option<String>,
     pub dex_fee_tier: u32,
     pub cex_fee_bps: u32,
+    // margin funding of the CEX leg, charged for borrow_hold_secs per trade
+    #[serde(default)]
+    pub borrow_rates: Vec<BorrowRate>,
+    pub borrow_hold_secs: u64,
     pub dex_gas_used: u64,
+    // the DEX is quoted every dex_refresh_fast_ms while its price moves,
+    // backing off to dex_refresh_slow_ms while it doesn't; a CEX mid moving
+    // dex_refresh_move_bps or a pool event quotes it right away
+    pub dex_refresh_fast_ms: u64,
+    pub dex_refresh_slow_ms: u64,
+    pub dex_refresh_move_bps: f64,
+    // quote the DEX once per block from a newHeads subscription, the
+    // cadence above only covers for it while no head arrives
+    pub dex_new_heads: bool,
+    pub dex_slippage_bps: u32,
+    // execution of the best opportunity per direction: both legs of
+    // exec_base_size base go out once they net exec_min_profit (quote) in
+    // live mode; paper mode fills them against virtual balances starting
+    // at paper_*_balance per venue, log mode only logs them
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
+    pub paper_base_balance: f64,
+    pub paper_quote_balance: f64,
+    pub exec_min_profit: f64,
+    pub exec_base_size: f64,
+    // DEX legs are not sent while the gas price is above max_gas_price_gwei
+    // or dex_gas_used gas would cost more than max_gas_cost_usd, however
+    // profitable the quote looked
+    pub max_gas_price_gwei: Option<f64>,
+    pub max_gas_cost_usd: Option<f64>,
+    // DEX legs also go out from these wallets besides private_key's, each on
+    // its own nonces, so swaps need not queue behind one nonce sequence
+    #[serde(default)]
+    pub executor_private_keys: Vec<Secret>,
+    #[serde(default)]
+    pub wallet_selection: WalletSelection,
+    // with sizing, the best opportunities trade the size netting the most
+    // instead: DEX quotes at sizing_steps sizes from sizing_min_size to
+    // sizing_max_size base against sizing_book_depth levels of the CEX
+    // book, refined by golden-section search to sizing_tolerance base
+    pub sizing: bool,
+    pub sizing_min_size: f64,
+    pub sizing_max_size: f64,
+    pub sizing_steps: usize,
+    pub sizing_tolerance: f64,
+    pub sizing_book_depth: u32,
+
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    // mean_reversion: samples in the rolling window and entry/exit z-scores
+    pub zscore_window: usize,
+    pub zscore_entry: f64,
+    pub zscore_exit: f64,
+    // market_making: HyperCore spot pair quoted (see spotMeta), quote size
+    // in base tokens, distance from the DEX price, drift that triggers a
+    // re-quote, minimum time between changes of a side and fill polling
+    pub mm_spot_pair_index: u32,
+    pub mm_quote_size: f64,
+    pub mm_edge_bps: u32,
+    pub mm_requote_bps: u32,
+    pub mm_min_requote_ms: u64,
+    pub mm_fill_poll_ms: u64,
+    // unhedged base size that triggers a DEX hedge
+    pub mm_hedge_min_size: f64,
+    // latency_arb: CEX mid move within the lookback that counts as a lead,
+    // and how long the DEX leg may stay unhedged
+    pub latency_lead_bps: u32,
+    pub latency_lookback_ms: u64,
+    pub latency_max_exposure_ms: u64,
+    // sell the CEX leg of BuyDex short on the oracle_perp_index perp when
+    // HyperCore spot holds too little base, see `execution::short`
+    pub short_selling: bool,
+    pub short_max_leverage: f64,
+    pub short_max_hold_secs: u64,
+    pub short_slippage_bps: u32,
+    // tries of a CEX order whose answer got lost, each after looking the
+    // previous one up by its client order id
+    pub order_submit_attempts: u32,
+    // a CEX order try not answered within this counts as lost; what has not
+    // filled this long after the answer is cancelled
+    pub cex_ack_timeout_ms: u64,
+    pub cex_fill_timeout_ms: u64,
+    // evaluate against the pool price after the swaps pending in the
+    // mempool land; needs a subscribe endpoint with pending transactions
+    pub predict_pending_swaps: bool,
+    // a pending swap not mined within this many blocks stops counting
+    pub pending_swap_max_blocks: u64,
+
+    pub base_core_index: u64,
+    pub base_core_extra_wei_decimals: i32,
+    pub quote_core_index: u64,
+    pub quote_core_extra_wei_decimals: i32,
+
+    pub confirmation_depth: u64,
+    pub inclusion_timeout_secs: u64,
+    pub replacement_fee_bump_pct: u32,
+    pub max_replacements: u32,
+
+    #[serde(default)]
+    pub extra_pools: Vec<PoolConfig>,
+    pub split_ladder_steps: usize,
+
+    pub revm_profile: bool,
+    pub mock_erc20: bool,
+    // quote with local UniswapV3 math on the cached pool state rather than
+    // by simulating the quoter, see `dex::univ3_math`
+    pub dex_local_math: bool,
+
+    pub screen_tokens: bool,
+    pub screen_max_round_trip_loss_bps: u32,
+
+    pub max_chain_lag_secs: u64,
+    pub max_spread_bps: u32,
+    pub max_oracle_divergence_bps: u32,
+    pub auto_resume_secs: u64,
+    // per-asset limits checked before every execution, counting balances,
+    // open orders and in-flight legs refreshed every exposure_refresh_secs
+    #[serde(default)]
+    pub exposure_limits: Vec<ExposureLimit>,
+    pub exposure_refresh_secs: u64,
+    // transfers between venues in flight longer than this are warned about
+    pub transfer_stale_secs: u64,
+    // executions per rolling minute/hour across all pairs and per pair,
+    // unlimited when unset
+    pub max_trades_per_minute: Option<u32>,
+    pub max_trades_per_hour: Option<u32>,
+    pub max_pair_trades_per_minute: Option<u32>,
+    pub max_pair_trades_per_hour: Option<u32>,
+    // UTC cron expressions of when execution is enabled, always when empty
+    #[serde(default)]
+    pub trading_windows: Vec<String>,
+    pub feed_stale_secs: u64,
+    // short of that, an edge loses half of itself per this many ms one leg
+    // was quoted before the other, see `arbitrage::age_weight`
+    pub quote_age_half_life_ms: Option<u64>,
+
+    pub oracle_perp_index: u32,
+    pub oracle_sz_decimals: u32,
+    pub oracle_band_bps: u32,
+
+    // bybit symbol of the USD reference priced in the quote token
+    pub peg_ticker: Option<String>,
+    pub max_depeg_bps: u32,
+    #[serde(default)]
+    pub depeg_action: DepegAction,
+
+    // POSTed JSON on opportunities netting at least webhook_min_profit and
+    // on trades, HMAC-signed when a secret is set; an opportunity once per
+    // webhook_cooldown_secs per venue, pair and direction and at most
+    // webhook_max_per_hour
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<Secret>,
+    pub webhook_min_profit: f64,
+    pub webhook_cooldown_secs: u64,
+    pub webhook_max_per_hour: Option<u32>,
+    // opportunities netting at least chat_min_profit pushed to a Telegram
+    // chat and/or a Discord webhook, once per chat_alert_cooldown_secs per
+    // venue, pair and direction and at most chat_max_alerts_per_hour
+    pub telegram_bot_token: Option<Secret>,
+    pub telegram_chat_id: Option<String>,
+    pub telegram_api_url: String,
+    pub discord_webhook_url: Option<Secret>,
+    pub chat_min_profit: f64,
+    pub chat_alert_cooldown_secs: u64,
+    pub chat_max_alerts_per_hour: Option<u32>,
+    // summary of each UTC day sent through the webhook after midnight
+    pub daily_summary: bool,
+
+    // JSON lines written alongside the console log, rotated by size and
+    // period, keeping log_keep_files old files
+    pub log_file: Option<String>,
+    #[serde(default)]
+    pub log_rotation: LogRotation,
+    pub log_max_bytes: u64,
+    pub log_keep_files: usize,
+
+    // OTLP/HTTP collector base url, e.g. http://localhost:4318
+    pub otlp_endpoint: Option<String>,
+    pub otlp_export_interval_ms: u64,
+    pub otel_service_name: String,
+
+    // quote-token notional shared by all strategy/pair books, unlimited
+    // when unset; budgets shift toward profitable books by
+    // capital_perf_weight, never below capital_min_share of an even split
+    pub capital_total: Option<f64>,
+    pub capital_perf_weight: f64,
+    pub capital_min_share: f64,
+    pub capital_rebalance_secs: u64,
+
+    // UTC cron expressions of scheduled maintenance, and how long it waits
+    // for in-flight transactions before idling
+    #[serde(default)]
+    pub maintenance_windows: Vec<String>,
+    pub maintenance_settle_secs: u64,
+    // on SIGTERM the bot winds down like maintenance, exiting non-zero if
+    // that takes longer than this
+    pub shutdown_drain_secs: u64,
+    // bybit system status and gate.io pair status polled every
+    // venue_status_poll_secs; execution on a venue halts this long before
+    // its announced maintenance until this long after
+    pub venue_status_poll_secs: u64,
+    pub venue_maintenance_margin_secs: u64,
+
+    // local clock checked against this NTP server and the exchange time
+    // endpoints at startup and every `clock_check_secs`
+    pub clock_ntp_server: Option<String>,
+    pub clock_warn_skew_ms: u64,
+    pub clock_check_secs: u64,
+
+    // append-only, hash-chained log of every execution decision
+    pub audit_file: Option<String>,
+
+    // every price update and evaluated opportunity, written to this SQLite
+    // or Postgres database every storage_flush_ms
+    pub storage_url: Option<Secret>,
+    pub storage_flush_ms: u64,
+
+    // record mode: every CEX tick and DEX quote written here as backtest
+    // data, see `backtest::run_tick_recorder`
+    pub record_dir: Option<String>,
+
+    // unix socket a running bot takes commands on, e.g. `rust-arb-bot log`
+    pub control_socket: Option<String>,
+    // net spreads of every candidate are counted in buckets this wide
+    // (quote), `rust-arb-bot spreads` shows their percentiles
+    pub spread_bucket: f64,
+
+    // prices, inventory, resting orders and pending txs saved every
+    // state_save_secs and reconciled on startup
+    pub state_file: Option<String>,
+    pub state_save_secs: u64,
 
     // from env
     pub rpc_url: String,
+    pub chain_id: u64,
+    // optional per-role endpoints, falling back to `rpc_url`
+    pub quote_rpc_url: Option<String>,
+    pub subscribe_rpc_url: Option<String>,
+    pub send_rpc_url: Option<String>,
+    // signs for `self_addr`; only needed to send transactions
+    pub private_key: Option<Secret>,
+    // file holding the key instead, read when private_key is not set
+    pub private_key_file: Option<String>,
+    pub hyperliquid_api_url: String,
+    pub hyperliquid_private_key: Option<Secret>,
+    // trade on behalf of a vault or sub-account
+    pub hyperliquid_vault_addr: Option<Address>,
     pub bybit_ws_endpoint: String,
     pub gateio_ws_endpoint: String,
+    pub okx_ws_endpoint: String,
+    pub hyperliquid_ws_endpoint: String,
+    // for one-shot snapshots, the bot itself streams
+    pub bybit_rest_endpoint: String,
+    pub gateio_rest_endpoint: String,
+    // margin borrowing and repaying, see `execution::margin`
+    pub bybit_api_key: Option<String>,
+    pub bybit_api_secret: Option<Secret>,
+    pub gateio_api_key: Option<String>,
+    pub gateio_api_secret: Option<Secret>,
+    // more accounts per venue, routed to by pair, book, rate limit and
+    // balance ahead of the venue's own keys above
+    #[serde(default)]
+    pub cex_accounts: Vec<CexAccount>,
 }
 
 impl Settings {
@@ -29,11 +559,115 @@ impl Settings {
     pub fn load() -> Result<Self> {
         dotenvy::dotenv().ok();
 
-        let cfg = config::Config::builder()
-            .add_source(config::File::with_name("config/default.toml"))
-            .add_source(config::Environment::default())
+        Self::load_from(&["config/default.toml"], None)
+    }
+
+    /// Layers `files` in order, then the environment on top. A given `env`
+    /// stands in for the process environment, e.g. for fixtures.
+    pub fn load_from(files: &[&str], env: Option<HashMap<String, String>>) -> Result<Self> {
+        let mut builder = config::Config::builder();
+        for file in files {
+            builder = builder.add_source(config::File::with_name(file));
+        }
+
+        let cfg = builder
+            .add_source(config::Environment::default().source(env))
             .build()?;
 
-        Ok(cfg.try_deserialize()?)
+        let mut cfg: Self = cfg.try_deserialize()?;
+        cfg.resolve_dex_profiles()?;
+        if cfg.denomination().gas_token(&cfg.native_token).is_none() {
+            bail!("native_token {} is neither side of {}", cfg.native_token, cfg.denomination());
+        }
+        let depths = cfg.bybit_depths.iter().map(|d| d.depth).chain([cfg.bybit_orderbook_depth]);
+        if let Some(depth) = depths.into_iter().find(|d| !BYBIT_DEPTHS.contains(d)) {
+            bail!("bybit order book depth {} is not one of {:?}", depth, BYBIT_DEPTHS);
+        }
+        // a min output limit can't go below nothing
+        if cfg.dex_slippage_bps >= 10_000 {
+            bail!("dex_slippage_bps {} is not below 10000", cfg.dex_slippage_bps);
+        }
+        Ok(cfg)
+    }
+
+    /// The profile in `dex_profiles` called `name`.
+    pub fn find_dex_profile(&self, name: &str) -> Result<&DexProfile> {
+        self.dex_profiles.get(name).ok_or_else(|| {
+            let known: Vec<_> = self.dex_profiles.keys().map(String::as_str).collect();
+            anyhow!("unknown dex_profile {:?}, known: {}", name, known.join(", "))
+        })
+    }
+
+    // fills the addresses left unset from the named profiles, explicit
+    // ones win
+    fn resolve_dex_profiles(&mut self) -> Result<()> {
+        if let Some(name) = &self.dex_profile {
+            let profile = self.find_dex_profile(name)?.clone();
+            if self.quoter_v2_addr.is_zero() {
+                self.quoter_v2_addr = profile.quoter_addr;
+                self.quoter_kind = profile.quoter_kind;
+            }
+            if self.router_addr.is_zero() {
+                self.router_addr = profile.router_addr;
+            }
+        }
+        if let Some(kind) = self.configured_quoter_kind {
+            self.quoter_kind = kind;
+        }
+        if self.quoter_v2_addr.is_zero() || self.router_addr.is_zero() {
+            bail!("set dex_profile, or quoter_v2_addr and router_addr");
+        }
+
+        let mut pools = std::mem::take(&mut self.extra_pools);
+        for pool in pools.iter_mut() {
+            if let Some(name) = &pool.dex_profile {
+                let profile = self.find_dex_profile(name)?;
+                if pool.quoter_addr.is_none() {
+                    pool.quoter_addr = Some(profile.quoter_addr);
+                    pool.quoter_kind = profile.quoter_kind;
+                }
+                pool.router_addr.get_or_insert(profile.router_addr);
+            }
+        }
+        self.extra_pools = pools;
+        Ok(())
+    }
+
+    /// What every price of the pair is quoted in.
+    pub fn denomination(&self) -> Denomination {
+        Denomination { base: self.pair_base.clone(), quote: self.pair_quote.clone() }
+    }
+
+    /// The side of the pair gas is paid in.
+    pub fn gas_token(&self) -> GasToken {
+        self.denomination().gas_token(&self.native_token).unwrap_or_default()
+    }
+
+    /// Whether CEX `symbol` is quoted the other way round than the pair.
+    pub fn is_inverse(&self, symbol: &str) -> bool {
+        self.inverse_tickers.iter().any(|t| t == symbol)
+    }
+
+    /// Order book depth streamed for bybit `symbol`.
+    pub fn bybit_depth(&self, symbol: &str) -> u32 {
+        self.bybit_depths
+            .iter()
+            .find(|d| d.symbol == symbol)
+            .map_or(self.bybit_orderbook_depth, |d| d.depth)
+    }
+
+    /// Endpoint for read-heavy traffic: quotes, storage and gas price reads.
+    pub fn quote_rpc(&self) -> &str {
+        self.quote_rpc_url.as_deref().unwrap_or(&self.rpc_url)
+    }
+
+    /// Endpoint for head and log subscriptions.
+    pub fn subscribe_rpc(&self) -> &str {
+        self.subscribe_rpc_url.as_deref().unwrap_or(&self.rpc_url)
+    }
+
+    /// Endpoint transactions are broadcast through.
+    pub fn send_rpc(&self) -> &str {
+        self.send_rpc_url.as_deref().unwrap_or(&self.rpc_url)
     }
 }
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+use anyhow::{Result, bail};
+use futures_util::future::try_join_all;
+
+use crate::adapters::book::OrderBook;
+use crate::adapters::{bybit, gateio};
+use crate::arbitrage::{ArbDirection, ArbProfit};
+use crate::dex::router::{DexRouter, router_for};
+use crate::normalize::Normalizer;
+use crate::settings::Settings;
+
+// 1 / golden ratio
+const INV_PHI: f64 = 0.618_033_988_749_895;
+
+/// The size an opportunity nets the most at, with what each leg averages
+/// there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimalSize {
+    pub size: f64,
+    pub net: f64,
+    pub cex_price: f64,
+    pub dex_price: f64,
+}
+
+/// What a trade costs besides the prices it crosses at: the CEX fee on
+/// its notional, margin interest per base unit and gas once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeCosts {
+    pub cex_fee_bps: u32,
+    pub borrow_per_unit: f64,
+    pub gas_cost: f64,
+}
+
+impl TradeCosts {
+    /// The costs behind a per-unit `profit`, in quote.
+    pub fn of(cex_fee_bps: u32, profit: &ArbProfit) -> Self {
+        Self { cex_fee_bps, borrow_per_unit: profit.borrow_cost, gas_cost: profit.gas_cost }
+    }
+}
+
+/// Quote amounts of DEX trades at a ladder of sizes, received when selling
+/// base (`BuyCex`) or paid when buying it. Sizes between two quotes are
+/// interpolated, below the first at its average price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DexCurve {
+    points: Vec<(f64, f64)>,
+}
+
+impl DexCurve {
+    /// From `(size, quote amount)` points, in any order.
+    pub fn new(mut points: Vec<(f64, f64)>) -> Self {
+        points.retain(|(size, amount)| *size > 0.0 && *amount > 0.0);
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { points }
+    }
+
+    pub fn amount(&self, size: f64) -> Option<f64> {
+        let first = *self.points.first()?;
+        if size <= first.0 {
+            return Some(first.1 / first.0 * size);
+        }
+        let i = self.points.partition_point(|(s, _)| *s < size).min(self.points.len() - 1);
+        let ((s0, a0), (s1, a1)) = (self.points[i.saturating_sub(1)], self.points[i]);
+        if s1 <= s0 {
+            return Some(a1 / s1 * size);
+        }
+        Some(a0 + (a1 - a0) * (size - s0) / (s1 - s0))
+    }
+}
+
+/// Net profit of trading `size` base in `direction`, the CEX leg at
+/// `cex_price` and the DEX leg for `dex_amount` quote.
+pub fn net_at(direction: &ArbDirection, size: f64, cex_price: f64, dex_amount: f64, costs: &TradeCosts) -> f64 {
+    let notional = size * cex_price;
+    let fee = notional * costs.cex_fee_bps as f64 / 10000.0;
+    let gross = match direction {
+        ArbDirection::BuyCex => dex_amount - notional,
+        ArbDirection::BuyDex => notional - dex_amount,
+    };
+    gross - fee - size * costs.borrow_per_unit - costs.gas_cost
+}
+
+/// Where `f` peaks on `[lo, hi]`, narrowed to `tolerance` by golden-section
+/// search. Finds the maximum of a unimodal `f`, a local one otherwise.
+pub fn golden_section(mut lo: f64, mut hi: f64, tolerance: f64, f: impl Fn(f64) -> f64) -> f64 {
+    let mut a = hi - (hi - lo) * INV_PHI;
+    let mut b = lo + (hi - lo) * INV_PHI;
+    let (mut fa, mut fb) = (f(a), f(b));
+    while hi - lo > tolerance.max(f64::EPSILON) {
+        if fa < fb {
+            lo = a;
+            (a, fa) = (b, fb);
+            b = lo + (hi - lo) * INV_PHI;
+            fb = f(b);
+        } else {
+            hi = b;
+            (b, fb) = (a, fa);
+            a = hi - (hi - lo) * INV_PHI;
+            fa = f(a);
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Searches the size maximizing net profit between `sizing_min_size` and
+/// `sizing_max_size`. The net curve is not unimodal in general, gas pulls
+/// small sizes down and a thin level can kink it, so the best of a
+/// geometric ladder of `sizing_steps` sizes picks the bracket that
+/// golden-section search narrows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeSolver {
+    pub min_size: f64,
+    pub max_size: f64,
+    pub steps: usize,
+    pub tolerance: f64,
+}
+
+impl SizeSolver {
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self {
+            min_size: cfg.sizing_min_size,
+            max_size: cfg.sizing_max_size.max(cfg.sizing_min_size),
+            steps: cfg.sizing_steps.max(2),
+            tolerance: cfg.sizing_tolerance,
+        }
+    }
+
+    /// The sizes the DEX is quoted at, from `min_size` to `max_size`.
+    pub fn ladder(&self) -> Vec<f64> {
+        let ratio = self.max_size / self.min_size;
+        (0..self.steps)
+            .map(|i| self.min_size * ratio.powf(i as f64 / (self.steps - 1) as f64))
+            .collect()
+    }
+
+    /// The best size against `dex` and `book`, `None` when either side
+    /// cannot price it.
+    pub fn solve(&self, direction: &ArbDirection, dex: &DexCurve, book: &OrderBook, costs: &TradeCosts) -> Option<OptimalSize> {
+        let cex_buy = *direction == ArbDirection::BuyCex;
+        let at = |size: f64| -> Option<OptimalSize> {
+            let cex_price = book.vwap(cex_buy, size)?;
+            let dex_amount = dex.amount(size)?;
+            let net = net_at(direction, size, cex_price, dex_amount, costs);
+            Some(OptimalSize { size, net, cex_price, dex_price: dex_amount / size })
+        };
+
+        let ladder = self.ladder();
+        let (i, best) = ladder
+            .iter()
+            .enumerate()
+            .filter_map(|(i, size)| Some((i, at(*size)?)))
+            .max_by(|(_, a), (_, b)| a.net.total_cmp(&b.net))?;
+        let (lo, hi) = (ladder[i.saturating_sub(1)], ladder[(i + 1).min(ladder.len() - 1)]);
+        let size = golden_section(lo, hi, self.tolerance, |size| at(size).map_or(f64::NEG_INFINITY, |s| s.net));
+        match at(size) {
+            Some(refined) if refined.net > best.net => Some(refined),
+            _ => Some(best),
+        }
+    }
+}
+
+/// `levels` a side of `venue`'s book from its REST API.
+pub async fn fetch_depth(cfg: &Settings, venue: &str, levels: u32) -> Result<OrderBook> {
+    match venue {
+        "bybit" if !cfg.is_inverse(&cfg.bybit_ticker) => bybit::fetch_book(cfg, levels).await,
+        "gateio" if !cfg.is_inverse(&cfg.gateio_ticker) => gateio::fetch_book(cfg, levels).await,
+        venue => bail!("no book depth to size {} trades against", venue),
+    }
+}
+
+/// Sizes opportunities of the configured pair on one CEX venue: quotes the
+/// DEX along the solver's ladder and fetches the venue's depth each time,
+/// then re-quotes the DEX at the size found.
+pub struct Sizer {
+    cfg: Settings,
+    venue: String,
+    solver: SizeSolver,
+    router: Arc<dyn DexRouter>,
+    provider: Arc<dyn Provider>,
+}
+
+impl Sizer {
+    pub fn new(cfg: &Settings, venue: &str, provider: Arc<dyn Provider>) -> Result<Self> {
+        if cfg.sizing_min_size <= 0.0 {
+            bail!("sizing_min_size must be positive");
+        }
+        Ok(Self {
+            cfg: cfg.clone(),
+            venue: venue.to_string(),
+            solver: SizeSolver::from_config(cfg),
+            router: router_for(cfg)?,
+            provider,
+        })
+    }
+
+    /// The size of an opportunity trading `direction` at the per-unit
+    /// `profit` that nets the most, `None` when no size can be priced.
+    /// `profit` and the result are in USD when `peg` is set.
+    pub async fn size(&self, direction: &ArbDirection, profit: &ArbProfit, peg: Option<f64>) -> Result<Option<OptimalSize>> {
+        let norm = &Normalizer::new(&self.cfg);
+        let quote = |size: f64| async move {
+            let amount = self.router.quote(&*self.provider, direction, norm.base_units(size)).await?;
+            Ok::<_, anyhow::Error>((size, norm.quote(U256::from(amount))))
+        };
+        let (points, book) = tokio::try_join!(
+            try_join_all(self.solver.ladder().into_iter().map(quote)),
+            fetch_depth(&self.cfg, &self.venue, self.cfg.sizing_book_depth),
+        )?;
+
+        let rate = peg.unwrap_or(1.0);
+        let costs = TradeCosts::of(self.cfg.cex_fee_bps, &profit.denominated(1.0 / rate));
+        let Some(found) = self.solver.solve(direction, &DexCurve::new(points), &book, &costs) else {
+            return Ok(None);
+        };
+        // the curve is interpolated between quotes, the size found is quoted
+        let (size, dex_amount) = quote(found.size).await?;
+        Ok(Some(OptimalSize {
+            net: net_at(direction, size, found.cex_price, dex_amount, &costs) * rate,
+            dex_price: dex_amount / size,
+            ..found
+        }))
+    }
+}
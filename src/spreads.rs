@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arbitrage::ArbDirection;
+use crate::settings::Settings;
+
+/// The percentiles a summary reports.
+pub const PERCENTILES: [f64; 5] = [50.0, 75.0, 90.0, 95.0, 99.0];
+
+/// Counts of observed net spreads in buckets `bucket` wide. Buckets are
+/// kept sparse, so the range needs no bound and negative spreads count
+/// like positive ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpreadHistogram {
+    bucket: f64,
+    counts: BTreeMap<i64, u64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl SpreadHistogram {
+    pub fn new(bucket: f64) -> Self {
+        Self { bucket, counts: BTreeMap::new(), count: 0, min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+
+    pub fn record(&mut self, net: f64) {
+        if !net.is_finite() {
+            return;
+        }
+        *self.counts.entry((net / self.bucket).floor() as i64).or_default() += 1;
+        self.count += 1;
+        self.min = self.min.min(net);
+        self.max = self.max.max(net);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The spread `p` percent of observations are at or below, read as the
+    /// upper edge of its bucket and capped at the largest seen. `None`
+    /// before the first observation.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in &self.counts {
+            seen += count;
+            if seen >= rank {
+                return Some(((*bucket + 1) as f64 * self.bucket).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    pub fn summary(&self, pair: &str, direction: &ArbDirection) -> SpreadSummary {
+        SpreadSummary {
+            pair: pair.to_string(),
+            direction: direction.clone(),
+            count: self.count,
+            min: (self.count > 0).then_some(self.min),
+            max: (self.count > 0).then_some(self.max),
+            percentiles: PERCENTILES.iter().filter_map(|p| Some((*p, self.percentile(*p)?))).collect(),
+        }
+    }
+}
+
+/// Percentiles of the net spread of one pair and direction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpreadSummary {
+    pub pair: String,
+    pub direction: ArbDirection,
+    pub count: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    // (percentile, net spread at it)
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl SpreadSummary {
+    /// The net spread at percentile `p` when it is one of `PERCENTILES`.
+    pub fn at(&self, p: f64) -> Option<f64> {
+        self.percentiles.iter().find(|(q, _)| *q == p).map(|(_, net)| *net)
+    }
+}
+
+type Histograms = BTreeMap<(String, ArbDirection), SpreadHistogram>;
+
+/// Net spread histograms per (pair, direction), shared by the scanner
+/// recording every candidate it prices and whatever reads them, e.g. the
+/// `spreads` control command. A historical percentile is a better
+/// `exec_min_profit` than a guess.
+#[derive(Debug, Clone)]
+pub struct Spreads {
+    bucket: f64,
+    histograms: Arc<Mutex<Histograms>>,
+}
+
+impl Spreads {
+    pub fn new(bucket: f64) -> Self {
+        Self { bucket: bucket.max(f64::EPSILON), histograms: Arc::default() }
+    }
+
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self::new(cfg.spread_bucket)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Histograms> {
+        // a histogram is consistent across a panicking holder
+        self.histograms.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn record(&self, pair: &str, direction: &ArbDirection, net: f64) {
+        self.lock()
+            .entry((pair.to_string(), direction.clone()))
+            .or_insert_with(|| SpreadHistogram::new(self.bucket))
+            .record(net);
+    }
+
+    pub fn percentile(&self, pair: &str, direction: &ArbDirection, p: f64) -> Option<f64> {
+        self.lock().get(&(pair.to_string(), direction.clone()))?.percentile(p)
+    }
+
+    /// A summary per (pair, direction) seen, ordered by pair.
+    pub fn summaries(&self) -> Vec<SpreadSummary> {
+        self.lock()
+            .iter()
+            .map(|((pair, direction), histogram)| histogram.summary(pair, direction))
+            .collect()
+    }
+
+    /// Drops the histograms of a pair no longer traded.
+    pub fn forget(&self, pair: &str) {
+        self.lock().retain(|(p, _), _| p != pair);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::TxHash;
+use alloy::providers::Provider;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::arbitrage::PriceData;
+use crate::execution::accounts::AccountBalance;
+use crate::execution::hyperliquid::HyperliquidClient;
+use crate::inventory::{CoreInventory, EvmInventory, core_inventory, evm_inventory};
+use crate::settings::Settings;
+use crate::strategy::market_making::Side;
+
+/// Last price of a feed and when it was seen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPrice {
+    pub bid: f64,
+    pub ask: f64,
+    pub at_ms: u64,
+}
+
+/// A market-maker quote resting on HyperCore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedOrder {
+    pub side: Side,
+    pub oid: u64,
+    pub px: f64,
+    pub sz: f64,
+    pub filled: f64,
+}
+
+/// What the bot knew when it last saved, to pick up from after a restart.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WarmState {
+    pub saved_at_ms: u64,
+    pub prices: BTreeMap<String, SavedPrice>,
+    pub evm_inventory: Option<EvmInventory>,
+    pub core_inventory: Option<CoreInventory>,
+    pub open_orders: Vec<SavedOrder>,
+    // sent but without a receipt yet
+    pub in_flight: Vec<TxHash>,
+    // known balances of the CEX sub-accounts, by account name
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountBalance>,
+}
+
+impl WarmState {
+    /// The state saved at `path`, `None` if nothing was saved yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(Some(serde_json::from_str(&json).with_context(|| format!("failed to parse {}", path.display()))?))
+    }
+
+    /// Writes to a temporary file renamed over `path`, so a crash mid-write
+    /// leaves the previous state intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?).with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Prices at most `max_age` old at `now_ms`, safe to start the engines on.
+    pub fn fresh_prices(&self, now_ms: u64, max_age: Duration) -> Vec<(String, PriceData)> {
+        self.prices
+            .iter()
+            .filter(|(_, p)| now_ms.saturating_sub(p.at_ms) <= max_age.as_millis() as u64)
+            .map(|(feed, p)| (feed.clone(), PriceData::new(p.bid, p.ask)))
+            .collect()
+    }
+}
+
+/// The live copy of `WarmState` components write into and a saver task
+/// persists. Clones share it.
+#[derive(Clone, Default)]
+pub struct StateStore {
+    state: Arc<Mutex<WarmState>>,
+}
+
+impl StateStore {
+    /// Starts from `state`, usually the reconciled one.
+    pub fn new(state: WarmState) -> Self {
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    pub fn snapshot(&self) -> WarmState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut WarmState)) {
+        if let Ok(mut state) = self.state.lock() {
+            f(&mut state);
+        }
+    }
+
+    pub fn record_price(&self, feed: &str, price: &PriceData, at_ms: u64) {
+        self.update(|s| {
+            s.prices.insert(feed.to_string(), SavedPrice { bid: price.bid, ask: price.ask, at_ms });
+        });
+    }
+
+    pub fn set_inventory(&self, evm: Option<EvmInventory>, core: Option<CoreInventory>) {
+        self.update(|s| {
+            s.evm_inventory = evm;
+            s.core_inventory = core;
+        });
+    }
+
+    pub fn set_account_balance(&self, account: &str, balance: AccountBalance) {
+        self.update(|s| {
+            s.accounts.insert(account.to_string(), balance);
+        });
+    }
+
+    pub fn open_orders(&self) -> Vec<SavedOrder> {
+        self.snapshot().open_orders
+    }
+
+    pub fn set_open_orders(&self, orders: Vec<SavedOrder>) {
+        self.update(|s| s.open_orders = orders);
+    }
+
+    pub fn track_tx(&self, hash: TxHash) {
+        self.update(|s| {
+            if !s.in_flight.contains(&hash) {
+                s.in_flight.push(hash);
+            }
+        });
+    }
+
+    pub fn untrack_tx(&self, hash: TxHash) {
+        self.update(|s| s.in_flight.retain(|h| *h != hash));
+    }
+
+    /// Forgets in-flight transactions that got a receipt, returning how
+    /// many are still pending.
+    pub async fn prune_landed(&self, provider: &(impl Provider + ?Sized)) -> usize {
+        let in_flight = self.snapshot().in_flight;
+        let mut pending = in_flight.len();
+        for hash in in_flight {
+            if let Ok(Some(_)) = provider.get_transaction_receipt(hash).await {
+                self.untrack_tx(hash);
+                pending -= 1;
+            }
+        }
+        pending
+    }
+
+    /// Saves the current state to `path`, stamped with the time.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut state = self.snapshot();
+        state.saved_at_ms = now_ms();
+        state.save(path)
+    }
+}
+
+/// Saved state checked against the exchanges and the chain.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reconciled {
+    pub state: WarmState,
+    pub fresh_prices: Vec<(String, PriceData)>,
+    // orders that filled or were cancelled while the bot was down
+    pub closed_orders: Vec<SavedOrder>,
+    pub landed_txs: Vec<TxHash>,
+    pub dropped_txs: Vec<TxHash>,
+}
+
+/// Brings `saved` up to date: keeps prices no older than `feed_stale_secs`,
+/// reports how inventory moved, keeps only orders still resting and
+/// transactions still pending. Without `client` saved orders cannot be
+/// checked and are dropped with a warning.
+pub async fn reconcile(
+    cfg: &Settings,
+    saved: WarmState,
+    provider: &(impl Provider + ?Sized),
+    client: Option<&HyperliquidClient>,
+    now_ms: u64,
+) -> Result<Reconciled> {
+    let fresh_prices = saved.fresh_prices(now_ms, Duration::from_secs(cfg.feed_stale_secs));
+    let mut state = WarmState { saved_at_ms: now_ms, ..Default::default() };
+
+    if saved.evm_inventory.is_some() {
+        let now = evm_inventory(cfg, provider, cfg.self_addr).await?;
+        if saved.evm_inventory.as_ref() != Some(&now) {
+            warn!("HyperEVM inventory changed while down: {:?} -> {:?}", saved.evm_inventory, now);
+        }
+        state.evm_inventory = Some(now);
+    }
+    if let (Some(before), Some(client)) = (&saved.core_inventory, client) {
+        let now = core_inventory(cfg, client).await?;
+        if *before != now {
+            warn!("HyperCore inventory changed while down: {:?} -> {:?}", before, now);
+        }
+        state.core_inventory = Some(now);
+    }
+
+    // nothing to check sub-account balances against, they are carried over
+    // as the bot last booked them
+    for (account, balance) in &saved.accounts {
+        info!("📒 account {} restored with {:.4} base, {:.2} quote", account, balance.base, balance.quote);
+    }
+    state.accounts = saved.accounts;
+
+    let mut closed_orders = Vec::new();
+    for mut order in saved.open_orders {
+        let Some(client) = client else {
+            warn!("cannot check {:?} order {} without hyperliquid_private_key", order.side, order.oid);
+            continue;
+        };
+        match client.order_status(client.account(), order.oid).await? {
+            Some(status) if status.status == "open" => {
+                order.filled = status.filled_sz();
+                info!("📌 {:?} quote {} still resting, {:.4} filled", order.side, order.oid, order.filled);
+                state.open_orders.push(order);
+            }
+            status => {
+                warn!("{:?} quote {} closed while down: {:?}", order.side, order.oid, status);
+                closed_orders.push(order);
+            }
+        }
+    }
+
+    let (mut landed_txs, mut dropped_txs) = (Vec::new(), Vec::new());
+    for hash in saved.in_flight {
+        if let Some(receipt) = provider.get_transaction_receipt(hash).await? {
+            info!("tx {} landed while down in block {:?}, success {}", hash, receipt.block_number, receipt.status());
+            landed_txs.push(hash);
+        } else if provider.get_transaction_by_hash(hash).await?.is_some() {
+            info!("tx {} still pending", hash);
+            state.in_flight.push(hash);
+        } else {
+            warn!("tx {} was dropped while down", hash);
+            dropped_txs.push(hash);
+        }
+    }
+
+    Ok(Reconciled { state, fresh_prices, closed_orders, landed_txs, dropped_txs })
+}
+
+/// Loads the state saved at `path` and reconciles it; a fresh state when
+/// nothing was saved. The bot enables execution only after this returned.
+pub async fn warm_start(
+    cfg: &Settings,
+    path: &str,
+    provider: &(impl Provider + ?Sized),
+    client: Option<&HyperliquidClient>,
+) -> Result<Reconciled> {
+    let Some(saved) = WarmState::load(path)? else {
+        info!("no saved state at {}, starting cold", path);
+        return Ok(Reconciled::default());
+    };
+
+    info!("♻️ restoring state saved at {} ms from {}...", saved.saved_at_ms, path);
+    let reconciled = reconcile(cfg, saved, provider, client, now_ms()).await?;
+    info!(
+        "♻️ restored {} prices, {} resting orders, {} pending txs",
+        reconciled.fresh_prices.len(),
+        reconciled.state.open_orders.len(),
+        reconciled.state.in_flight.len()
+    );
+    Ok(reconciled)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Saves `store` to `path` every `every`, after recording the latest price
+/// of each feed, refreshing inventory (HyperCore's only with `client`) and
+/// forgetting transactions that got a receipt.
+pub async fn run_state_saver(
+    cfg: Settings,
+    store: StateStore,
+    path: String,
+    mut feeds: Vec<(String, watch::Receiver<Option<PriceData>>)>,
+    provider: Arc<dyn Provider>,
+    client: Option<HyperliquidClient>,
+    every: Duration,
+) {
+    let mut tick = interval(every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tick.tick().await;
+        let now = now_ms();
+
+        for (name, feed) in feeds.iter_mut() {
+            if feed.has_changed().unwrap_or(false)
+                && let Some(price) = feed.borrow_and_update().clone()
+            {
+                store.record_price(name, &price, now);
+            }
+        }
+
+        let evm = evm_inventory(&cfg, &*provider, cfg.self_addr).await;
+        let core = match &client {
+            Some(client) => Some(core_inventory(&cfg, client).await),
+            None => None,
+        };
+        match (evm, core.transpose()) {
+            (Ok(evm), Ok(core)) => store.set_inventory(Some(evm), core),
+            (Err(e), _) | (_, Err(e)) => warn!("inventory refresh failed: {:#}", e),
+        }
+
+        store.prune_landed(&*provider).await;
+        if let Err(e) = store.save(&path) {
+            warn!("failed to save state to {}: {:#}", path, e);
+        }
+    }
+}
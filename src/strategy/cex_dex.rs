@@ -0,0 +1,68 @@
+use log::info;
+
+use crate::arbitrage::{ArbDirection, ArbProfit, GasToken, PriceData, arb_profit_in};
+use crate::settings::Settings;
+use crate::strategy::Strategy;
+
+/// Crosses the CEX and DEX books directly: buy on one venue at its ask and
+/// sell on the other at its bid, net of the CEX fee and DEX gas.
+pub struct CexDexSpread {
+    cex_fee_bps: u32,
+    dex_gas_used: u64,
+    gas_token: GasToken,
+}
+
+impl CexDexSpread {
+    pub fn new(cfg: &Settings) -> Self {
+        Self {
+            cex_fee_bps: cfg.cex_fee_bps,
+            dex_gas_used: cfg.dex_gas_used,
+            gas_token: cfg.gas_token(),
+        }
+    }
+
+    pub fn calculate_arbitrage(
+        &self,
+        buy_price: f64,
+        sell_price: f64,
+        direction: ArbDirection,
+        gas_price_wei: u128,
+    ) -> ArbProfit {
+        let profit = arb_profit_in(
+            self.gas_token,
+            self.cex_fee_bps,
+            self.dex_gas_used,
+            buy_price,
+            sell_price,
+            &direction,
+            gas_price_wei,
+        );
+
+        if profit.net <= 0.0 {
+            info!(
+                "🔴 NO ARB: buy ${:.4}, sell ${:.4}, net ${:.4}, cex fee: ${:.4}, gas: ${:.4}",
+                buy_price, sell_price, profit.net, profit.cex_fee, profit.gas_cost
+            );
+        } else {
+            info!(
+                "🟢 ARB: buy ${:.4}, sell ${:.4}, net ${:.4}, cex fee: ${:.4}, gas: ${:.4}",
+                buy_price, sell_price, profit.net, profit.cex_fee, profit.gas_cost
+            );
+        }
+
+        profit
+    }
+}
+
+impl Strategy for CexDexSpread {
+    fn name(&self) -> &'static str {
+        "cex_dex"
+    }
+
+    fn evaluate(&mut self, cex: &PriceData, dex: &PriceData, gas_price_wei: u128) -> Vec<(ArbDirection, ArbProfit)> {
+        let buy_cex = self.calculate_arbitrage(cex.ask, dex.bid, ArbDirection::BuyCex, gas_price_wei);
+        let buy_dex = self.calculate_arbitrage(dex.ask, cex.bid, ArbDirection::BuyDex, gas_price_wei);
+
+        vec![(ArbDirection::BuyCex, buy_cex), (ArbDirection::BuyDex, buy_dex)]
+    }
+}
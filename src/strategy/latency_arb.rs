@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use tokio::time::Instant;
+
+use crate::arbitrage::{ArbDirection, ArbProfit, GasToken, PriceData, arb_profit_in};
+use crate::clock::{Clock, SystemClock};
+use crate::pnl::TradeRecord;
+use crate::settings::Settings;
+use crate::strategy::Strategy;
+
+/// A DEX leg fired on a CEX lead that has not filled yet.
+#[derive(Debug, Clone)]
+pub struct Exposure {
+    pub direction: ArbDirection,
+    pub opened_at: Instant,
+    // CEX mid the entry was priced against
+    pub cex_mid: f64,
+}
+
+/// Latency arbitrage: the CEX moves first and the pool only reprices once
+/// someone trades it. A CEX mid move of at least `latency_lead_bps` within
+/// `latency_lookback_ms` that the DEX has not followed fires the DEX leg at
+/// the stale pool price. The executor sends that lead leg first and the CEX
+/// hedge once it filled, or once `latency_max_exposure_ms` passed without
+/// it confirming. No new entry is taken until the lead filled or its
+/// exposure ran out.
+pub struct LatencyArb {
+    cex_fee_bps: u32,
+    dex_gas_used: u64,
+    gas_token: GasToken,
+    lead_bps: f64,
+    lookback: Duration,
+    max_exposure: Duration,
+    // recent CEX mids, oldest first
+    cex_mids: VecDeque<(Instant, f64)>,
+    exposure: Option<Exposure>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LatencyArb {
+    pub fn new(cfg: &Settings) -> Self {
+        Self {
+            cex_fee_bps: cfg.cex_fee_bps,
+            dex_gas_used: cfg.dex_gas_used,
+            gas_token: cfg.gas_token(),
+            lead_bps: cfg.latency_lead_bps as f64,
+            lookback: Duration::from_millis(cfg.latency_lookback_ms),
+            max_exposure: Duration::from_millis(cfg.latency_max_exposure_ms),
+            cex_mids: VecDeque::new(),
+            exposure: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Times the lookback and exposure on `clock` rather than the wall
+    /// clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn exposure(&self) -> Option<&Exposure> {
+        self.exposure.as_ref()
+    }
+
+    /// CEX mid move over the lookback window in bps, positive when it rose.
+    pub fn lead_bps(&self) -> Option<f64> {
+        let (_, first) = self.cex_mids.front()?;
+        let (_, last) = self.cex_mids.back()?;
+        Some((last - first) / first * 10000.0)
+    }
+}
+
+fn mid(price: &PriceData) -> f64 {
+    (price.bid + price.ask) / 2.0
+}
+
+impl Strategy for LatencyArb {
+    fn name(&self) -> &'static str {
+        "latency_arb"
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    fn on_price_update(&mut self, cex: &PriceData, _dex: &PriceData) {
+        let now = self.clock.now();
+        let cex_mid = mid(cex);
+        if self.cex_mids.back().is_none_or(|(_, last)| *last != cex_mid) {
+            self.cex_mids.push_back((now, cex_mid));
+        }
+        while self.cex_mids.len() > 1
+            && self.cex_mids.front().is_some_and(|(at, _)| now.duration_since(*at) > self.lookback)
+        {
+            self.cex_mids.pop_front();
+        }
+    }
+
+    /// An entry is priced as crossing both books at the current prices, the
+    /// CEX side being where the hedge is expected to land.
+    fn evaluate(&mut self, cex: &PriceData, dex: &PriceData, gas_price_wei: u128) -> Vec<(ArbDirection, ArbProfit)> {
+        let (cex_mid, dex_mid) = (mid(cex), mid(dex));
+
+        if let Some(exposure) = &self.exposure {
+            let open_for = self.clock.now().duration_since(exposure.opened_at);
+            if open_for < self.max_exposure {
+                return Vec::new();
+            }
+            // the executor hedged by now, or the lead never went out
+            warn!(
+                "⏱️ LEAD {:?} not filled after {:.0?}, CEX mid ${:.4} (entry ${:.4})",
+                exposure.direction, open_for, cex_mid, exposure.cex_mid
+            );
+            self.exposure = None;
+            return Vec::new();
+        }
+
+        let Some(lead) = self.lead_bps() else {
+            return Vec::new();
+        };
+        if lead.abs() < self.lead_bps {
+            return Vec::new();
+        }
+
+        // CEX rallied: the pool is still cheap, buy it; the reverse on a drop
+        let (direction, buy, sell, gap_bps) = if lead > 0.0 {
+            (ArbDirection::BuyDex, dex.ask, cex.bid, (cex_mid - dex_mid) / cex_mid * 10000.0)
+        } else {
+            (ArbDirection::BuyCex, cex.ask, dex.bid, (dex_mid - cex_mid) / cex_mid * 10000.0)
+        };
+        // the DEX already followed
+        if gap_bps < self.lead_bps {
+            return Vec::new();
+        }
+
+        let profit = arb_profit_in(self.gas_token, self.cex_fee_bps, self.dex_gas_used, buy, sell, &direction, gas_price_wei);
+        if profit.net <= 0.0 {
+            return Vec::new();
+        }
+
+        info!(
+            "⚡ LEAD {:?}: CEX moved {:.1} bps, DEX {:.1} bps behind, expected net ${:.4}",
+            direction, lead, gap_bps, profit.net
+        );
+        self.exposure = Some(Exposure { direction: direction.clone(), opened_at: self.clock.now(), cex_mid });
+        vec![(direction, profit)]
+    }
+
+    fn on_fill(&mut self, _trade: &TradeRecord) {
+        // both legs settled, nothing left to hedge
+        self.exposure = None;
+    }
+}
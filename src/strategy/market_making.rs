@@ -0,0 +1,567 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{TxHash, U256};
+use alloy::providers::Provider;
+use anyhow::{Result, anyhow, bail};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use tokio::time::{Instant, MissedTickBehavior, interval};
+
+use crate::arbitrage::{ArbDirection, PriceData};
+use crate::audit::Decision;
+use crate::capital::{self, Capital, Reservation};
+use crate::dex::router::{DexRouter, router_for};
+use crate::execution::dex::DexSwap;
+use crate::execution::hyperliquid::{HyperliquidClient, OrderStatus, SPOT_ASSET_OFFSET, Tif};
+use crate::execution::confirmations::{ConfirmationTracker, TxState, TxUpdate};
+use crate::execution::sender::{PendingTx, TxOverrides, TxSender};
+use crate::normalize::Normalizer;
+use crate::risk::RiskHandle;
+use crate::scanner::DEFAULT_PAIR;
+use crate::settings::Settings;
+use crate::state::{SavedOrder, StateStore};
+use crate::telemetry::Tracer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl Side {
+    fn index(self) -> usize {
+        match self {
+            Side::Bid => 0,
+            Side::Ask => 1,
+        }
+    }
+}
+
+/// One of our orders resting on the CEX book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestingQuote {
+    pub oid: u64,
+    pub px: f64,
+    pub sz: f64,
+    // filled so far, as last polled
+    pub filled: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuoteAction {
+    Place { side: Side, px: f64, sz: f64 },
+    Cancel { side: Side, oid: u64 },
+}
+
+/// Quote bookkeeping of the market maker: where each side should rest
+/// given the DEX price, when a resting quote is stale enough to replace,
+/// and how much filled inventory still waits for its DEX hedge. Holds no
+/// connections, the caller executes what it plans and reports back.
+pub struct QuoteManager {
+    edge_bps: f64,
+    requote_bps: f64,
+    min_requote: Duration,
+    size: f64,
+    hedge_min: f64,
+    quotes: [Option<RestingQuote>; 2],
+    // last place or cancel per side, for throttling
+    last_change: [Option<Instant>; 2],
+    // base bought on the CEX minus base sold there, not yet offset on the DEX
+    unhedged: f64,
+    // a hedge sent and not confirmed yet
+    hedging: bool,
+    // hedges failed in a row, and when the next may go out
+    hedge_failures: u32,
+    hedge_retry_at: Option<Instant>,
+}
+
+impl QuoteManager {
+    pub fn new(cfg: &Settings) -> Self {
+        Self {
+            edge_bps: cfg.mm_edge_bps as f64,
+            requote_bps: cfg.mm_requote_bps as f64,
+            min_requote: Duration::from_millis(cfg.mm_min_requote_ms),
+            size: cfg.mm_quote_size,
+            hedge_min: cfg.mm_hedge_min_size,
+            quotes: [None, None],
+            last_change: [None, None],
+            unhedged: 0.0,
+            hedging: false,
+            hedge_failures: 0,
+            hedge_retry_at: None,
+        }
+    }
+
+    /// Where each side should rest: the DEX price we can hedge at, widened
+    /// by the edge and rounded away from the mid to 5 significant figures.
+    pub fn targets(&self, dex: &PriceData) -> (f64, f64) {
+        let edge = self.edge_bps / 10000.0;
+        (round_px(dex.bid * (1.0 - edge), false), round_px(dex.ask * (1.0 + edge), true))
+    }
+
+    /// Places missing sides and cancel/replaces those that drifted more than
+    /// `mm_requote_bps` off target. A side changes at most once per
+    /// `mm_min_requote_ms`, so a busy pool does not turn into order spam.
+    pub fn plan(&self, dex: &PriceData, now: Instant) -> Vec<QuoteAction> {
+        let (bid, ask) = self.targets(dex);
+        let mut actions = Vec::new();
+
+        for (side, target) in [(Side::Bid, bid), (Side::Ask, ask)] {
+            let throttled = self.last_change[side.index()].is_some_and(|at| now.duration_since(at) < self.min_requote);
+            if throttled {
+                continue;
+            }
+
+            match &self.quotes[side.index()] {
+                None => actions.push(QuoteAction::Place { side, px: target, sz: self.size }),
+                Some(quote) if (target - quote.px).abs() / quote.px * 10000.0 > self.requote_bps => {
+                    actions.push(QuoteAction::Cancel { side, oid: quote.oid });
+                    actions.push(QuoteAction::Place { side, px: target, sz: self.size - quote.filled });
+                }
+                Some(_) => {}
+            }
+        }
+
+        actions
+    }
+
+    /// Cancels for every resting side, e.g. while trading is paused.
+    pub fn cancel_all(&self) -> Vec<QuoteAction> {
+        [Side::Bid, Side::Ask]
+            .into_iter()
+            .filter_map(|side| Some(QuoteAction::Cancel { side, oid: self.quote(side)?.oid }))
+            .collect()
+    }
+
+    pub fn quote(&self, side: Side) -> Option<&RestingQuote> {
+        self.quotes[side.index()].as_ref()
+    }
+
+    pub fn on_placed(&mut self, side: Side, oid: u64, px: f64, sz: f64, now: Instant) {
+        self.quotes[side.index()] = Some(RestingQuote { oid, px, sz, filled: 0.0 });
+        self.last_change[side.index()] = Some(now);
+    }
+
+    /// Takes over a quote left resting by a previous run. What it filled
+    /// back then is not counted as unhedged, that run hedged or lost it.
+    pub fn adopt(&mut self, order: &SavedOrder, now: Instant) {
+        let quote = RestingQuote { oid: order.oid, px: order.px, sz: order.sz, filled: order.filled };
+        self.quotes[order.side.index()] = Some(quote);
+        self.last_change[order.side.index()] = Some(now);
+    }
+
+    /// Resting quotes, for saving across restarts.
+    pub fn saved_orders(&self) -> Vec<SavedOrder> {
+        [Side::Bid, Side::Ask]
+            .into_iter()
+            .filter_map(|side| {
+                let q = self.quote(side)?;
+                Some(SavedOrder { side, oid: q.oid, px: q.px, sz: q.sz, filled: q.filled })
+            })
+            .collect()
+    }
+
+    /// The quote of `side` left the book, cancelled or filled in full.
+    pub fn on_closed(&mut self, side: Side, now: Instant) {
+        self.quotes[side.index()] = None;
+        self.last_change[side.index()] = Some(now);
+    }
+
+    /// Records the cumulative fill of the quote resting on `side` and
+    /// returns the newly filled size.
+    pub fn on_fill(&mut self, side: Side, filled: f64) -> f64 {
+        let Some(quote) = self.quotes[side.index()].as_mut() else {
+            return 0.0;
+        };
+
+        let delta = (filled - quote.filled).max(0.0);
+        quote.filled = quote.filled.max(filled);
+        match side {
+            Side::Bid => self.unhedged += delta,
+            Side::Ask => self.unhedged -= delta,
+        }
+        delta
+    }
+
+    pub fn unhedged(&self) -> f64 {
+        self.unhedged
+    }
+
+    /// The DEX trade offsetting unhedged fills once they reach
+    /// `mm_hedge_min_size`: bought on the CEX sells on the DEX and the
+    /// reverse, which is the second leg of `BuyCex`/`BuyDex`.
+    pub fn hedge(&self) -> Option<(ArbDirection, f64)> {
+        if self.unhedged >= self.hedge_min {
+            Some((ArbDirection::BuyCex, self.unhedged))
+        } else if -self.unhedged >= self.hedge_min {
+            Some((ArbDirection::BuyDex, -self.unhedged))
+        } else {
+            None
+        }
+    }
+
+    /// `hedge`, unless a hedge is still in flight or the last one failed
+    /// within its backoff.
+    pub fn hedge_at(&self, now: Instant) -> Option<(ArbDirection, f64)> {
+        if self.hedging || self.hedge_retry_at.is_some_and(|at| now < at) {
+            return None;
+        }
+        self.hedge()
+    }
+
+    pub fn on_hedge_sent(&mut self) {
+        self.hedging = true;
+    }
+
+    /// Offsets the unhedged inventory once a hedge confirmed.
+    pub fn on_hedged(&mut self, direction: &ArbDirection, size: f64) {
+        match direction {
+            ArbDirection::BuyCex => self.unhedged -= size,
+            ArbDirection::BuyDex => self.unhedged += size,
+        }
+        self.hedging = false;
+        self.hedge_failures = 0;
+        self.hedge_retry_at = None;
+    }
+
+    /// Holds hedging back after a failure, twice as long as after the one
+    /// before up to `HEDGE_BACKOFF_MAX`. Returns how long.
+    pub fn on_hedge_failed(&mut self, now: Instant) -> Duration {
+        let backoff = (HEDGE_BACKOFF_MIN * 2u32.saturating_pow(self.hedge_failures)).min(HEDGE_BACKOFF_MAX);
+        self.hedging = false;
+        self.hedge_failures += 1;
+        self.hedge_retry_at = Some(now + backoff);
+        backoff
+    }
+}
+
+const HEDGE_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const HEDGE_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+// hyperliquid accepts at most 5 significant figures
+pub(crate) fn round_px(px: f64, up: bool) -> f64 {
+    if px <= 0.0 {
+        return 0.0;
+    }
+    let scale = 10f64.powi(4 - px.log10().floor() as i32);
+    let scaled = px * scale;
+    // snap float noise first so an exact price does not move a tick
+    let scaled = if (scaled - scaled.round()).abs() < 1e-6 { scaled.round() } else { scaled };
+    (if up { scaled.ceil() } else { scaled.floor() }) / scale
+}
+
+/// Market-making mode: keeps a two-sided post-only quote on the HyperCore
+/// spot book around the DEX price, re-quoting as the pool moves, and hedges
+/// fills on the DEX once enough inventory built up. Quotes are pulled while
+/// trading is paused or outside the trading windows. Starts from the quotes
+/// `state` holds and keeps it current with its quotes and hedge
+/// transactions. With `capital`, each resting quote holds its notional of
+/// the `market_making/default` budget.
+pub async fn run_market_maker(
+    cfg: Settings,
+    mut dex_rx: watch::Receiver<Option<PriceData>>,
+    mut risk: RiskHandle,
+    provider: Arc<dyn Provider>,
+    tracer: Tracer,
+    state: StateStore,
+    capital: Option<Capital>,
+) -> Result<()> {
+    let client = HyperliquidClient::new(&cfg)?;
+    // hedges are followed by a tracker of the market maker's own wallet
+    let sender = Arc::new(TxSender::new(&cfg).await?);
+    let (tracker, mut tx_updates) = ConfirmationTracker::new(&cfg, sender.clone());
+    let hedges = tracker.handle();
+    tokio::spawn(tracker.run(risk.events.subscribe()));
+    let mut hedging: Option<Hedge> = None;
+    let asset = SPOT_ASSET_OFFSET + cfg.mm_spot_pair_index;
+    let router = router_for(&cfg)?;
+    let mut quotes = QuoteManager::new(&cfg);
+    let book = capital::book("market_making", DEFAULT_PAIR);
+    let mut reserved: [Option<Reservation>; 2] = [None, None];
+    if let Some(capital) = &capital {
+        capital.register(&book);
+    }
+    for order in state.open_orders() {
+        quotes.adopt(&order, Instant::now());
+        if let Some(capital) = &capital {
+            // adopted quotes rest whether or not the budget has room
+            reserved[order.side.index()] = capital.reserve(&book, order.px * (order.sz - order.filled)).ok();
+        }
+    }
+
+    let mut poll = interval(Duration::from_millis(cfg.mm_fill_poll_ms));
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    info!("starting market maker on spot asset {}...", asset);
+
+    loop {
+        tokio::select! {
+            _ = dex_rx.changed() => {}
+            _ = risk.trading.changed() => {}
+            _ = poll.tick() => {
+                poll_fills(&client, &mut quotes).await;
+            }
+            update = tx_updates.recv(), if hedging.is_some() => {
+                match update {
+                    Ok(update) => on_hedge_update(&risk, &state, &mut quotes, &mut hedging, update),
+                    Err(RecvError::Lagged(n)) => warn!("hedge confirmation lagged, {} tx updates missed", n),
+                    Err(RecvError::Closed) => bail!("hedge confirmation tracker stopped"),
+                }
+            }
+        }
+
+        if let Some((direction, size)) = quotes.hedge_at(Instant::now()) {
+            let mut span = tracer.span("hedge");
+            span.set("direction", format!("{:?}", direction));
+            span.set("size", size);
+            let dex = dex_rx.borrow().clone();
+            let inputs = json!({
+                "direction": format!("{:?}", direction),
+                "size": size,
+                "dex_bid": dex.as_ref().map(|p| p.bid),
+                "dex_ask": dex.as_ref().map(|p| p.ask),
+                "hedge_min_size": cfg.mm_hedge_min_size,
+                "slippage_bps": cfg.dex_slippage_bps,
+            });
+            let result = match dex {
+                Some(dex) => {
+                    let base = if direction == ArbDirection::BuyDex { size } else { -size };
+                    let deltas = balance_deltas(base, (dex.bid + dex.ask) / 2.0);
+                    risk.check_exposure(&deltas).and_then(|_| risk.throttle(DEFAULT_PAIR)).map(|_| deltas)
+                }
+                None => Err(anyhow!("no DEX price to size the hedge")),
+            };
+            let result = match result {
+                Ok(deltas) => match send_hedge(&cfg, &*provider, &sender, &*router, &direction, size).await {
+                    Ok(tx) => {
+                        let hash = tx.hash;
+                        hedges.track(tx).map(|_| (hash, deltas))
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok((hash, deltas)) => {
+                    risk.audit.record(Decision::taken(AUDIT_SOURCE, "hedge", with_tx(inputs, hash)));
+                    state.track_tx(hash);
+                    risk.exposure.begin_leg(hash, &deltas);
+                    quotes.on_hedge_sent();
+                    hedging = Some(Hedge { direction, size, live: HashSet::from([hash]) });
+                }
+                Err(e) => {
+                    risk.audit.record(Decision::skipped(AUDIT_SOURCE, "hedge", format!("{:#}", e), inputs));
+                    span.set("error", format!("{:#}", e));
+                    let backoff = quotes.on_hedge_failed(Instant::now());
+                    warn!("hedge {:?} {:.4} failed, retrying in {:?}: {:#}", direction, size, backoff, e);
+                }
+            }
+        }
+
+        let dex = dex_rx.borrow().clone();
+        let actions = if !risk.can_execute() {
+            let actions = quotes.cancel_all();
+            if !actions.is_empty() {
+                let reason = if risk.is_paused() { "trading paused" } else { "outside trading windows" };
+                risk.audit.record(Decision::skipped(AUDIT_SOURCE, "quote", reason, json!({ "pulled": actions.len() })));
+            }
+            actions
+        } else {
+            match &dex {
+                Some(dex) => quotes.plan(dex, Instant::now()),
+                None => Vec::new(),
+            }
+        };
+
+        for action in actions {
+            if let QuoteAction::Place { side, px, sz } = &action {
+                let inputs = json!({
+                    "side": format!("{:?}", side),
+                    "px": px,
+                    "sz": sz,
+                    "dex_bid": dex.as_ref().map(|p| p.bid),
+                    "dex_ask": dex.as_ref().map(|p| p.ask),
+                    "edge_bps": cfg.mm_edge_bps,
+                    "requote_bps": cfg.mm_requote_bps,
+                });
+                // a resting quote counts as if it filled
+                let base = if *side == Side::Bid { *sz } else { -sz };
+                if let Err(e) = risk.check_exposure(&balance_deltas(base, *px)) {
+                    risk.audit.record(Decision::skipped(AUDIT_SOURCE, "quote", format!("{:#}", e), inputs));
+                    warn!("not quoting {:?}: {:#}", side, e);
+                    continue;
+                }
+                if let Some(capital) = &capital {
+                    // a replaced quote's capital goes to its successor
+                    reserved[side.index()] = None;
+                    match capital.reserve(&book, px * sz) {
+                        Ok(reservation) => reserved[side.index()] = Some(reservation),
+                        Err(e) => {
+                            risk.audit.record(Decision::skipped(AUDIT_SOURCE, "quote", format!("{:#}", e), inputs));
+                            warn!("not quoting {:?}: {:#}", side, e);
+                            continue;
+                        }
+                    }
+                }
+                risk.audit.record(Decision::taken(AUDIT_SOURCE, "quote", inputs));
+            }
+            let mut span = tracer.span("quote_action");
+            span.set("action", format!("{:?}", action));
+            execute(&client, asset, &mut quotes, action).await;
+        }
+        // capital of quotes that left the book is free again
+        for side in [Side::Bid, Side::Ask] {
+            if quotes.quote(side).is_none() {
+                reserved[side.index()] = None;
+            }
+        }
+        state.set_open_orders(quotes.saved_orders());
+        let resting = |side| quotes.quote(side).map(|q| (q.sz - q.filled, q.px)).unwrap_or_default();
+        let ((bid_sz, bid_px), (ask_sz, ask_px)) = (resting(Side::Bid), resting(Side::Ask));
+        risk.exposure.set_orders("base", bid_sz, ask_sz);
+        risk.exposure.set_orders("quote", ask_sz * ask_px, bid_sz * bid_px);
+    }
+}
+
+const AUDIT_SOURCE: &str = "market_maker";
+
+// a hedge sent and not confirmed yet, with its hash and those of its
+// replacements
+struct Hedge {
+    direction: ArbDirection,
+    size: f64,
+    live: HashSet<TxHash>,
+}
+
+// offsets the inventory once the hedge confirmed, and backs off when it
+// reverted or every transaction of it dropped
+fn on_hedge_update(risk: &RiskHandle, state: &StateStore, quotes: &mut QuoteManager, hedging: &mut Option<Hedge>, update: TxUpdate) {
+    let Some(hedge) = hedging.as_mut().filter(|h| h.live.contains(&update.hash)) else {
+        return;
+    };
+    let failed = match update.state {
+        TxState::Confirmed { success, .. } => {
+            for hash in &hedge.live {
+                state.untrack_tx(*hash);
+            }
+            if success {
+                info!("🛡️ HEDGE {:?} {:.4} confirmed: {}", hedge.direction, hedge.size, update.hash);
+                quotes.on_hedged(&hedge.direction, hedge.size);
+                *hedging = None;
+                return;
+            }
+            format!("reverted: {}", update.hash)
+        }
+        TxState::Replaced { by } => {
+            risk.exposure.rekey_leg(update.hash, by);
+            state.track_tx(by);
+            hedge.live.insert(by);
+            return;
+        }
+        TxState::Dropped { reason } => {
+            state.untrack_tx(update.hash);
+            hedge.live.remove(&update.hash);
+            if let Some(other) = hedge.live.iter().next() {
+                risk.exposure.rekey_leg(update.hash, *other);
+                return;
+            }
+            format!("dropped: {:?}", reason)
+        }
+        TxState::Pending | TxState::Included { .. } => return,
+    };
+
+    risk.exposure.end_leg(update.hash);
+    let backoff = quotes.on_hedge_failed(Instant::now());
+    warn!("hedge {:?} {:.4} {}, retrying in {:?}", hedge.direction, hedge.size, failed, backoff);
+    *hedging = None;
+}
+
+fn with_tx(mut inputs: Value, hash: TxHash) -> Value {
+    inputs["tx"] = json!(hash);
+    inputs
+}
+
+// base and quote balance changes of buying (positive) or selling `base` at `px`
+fn balance_deltas(base: f64, px: f64) -> [(&'static str, f64); 2] {
+    [("base", base), ("quote", -base * px)]
+}
+
+async fn execute(client: &HyperliquidClient, asset: u32, quotes: &mut QuoteManager, action: QuoteAction) {
+    match action {
+        QuoteAction::Place { side, px, sz } => {
+            match client.place_order(asset, side == Side::Bid, px, sz, Tif::Alo, None).await {
+                Ok(OrderStatus::Resting { oid }) => {
+                    info!("📌 QUOTE {:?} {} @ {}", side, sz, px);
+                    quotes.on_placed(side, oid, px, sz, Instant::now());
+                }
+                Ok(status) => warn!("quote {:?} @ {} not resting: {:?}", side, px, status),
+                Err(e) => warn!("quote {:?} @ {} failed: {:#}", side, px, e),
+            }
+        }
+        QuoteAction::Cancel { side, oid } => {
+            // a cancel that lost the race against a fill still closes the
+            // quote, the fill is picked up first
+            if let Err(e) = client.cancel_order(asset, oid).await {
+                warn!("cancel of {:?} quote {} failed: {:#}", side, oid, e);
+                poll_fill(client, quotes, side).await;
+            }
+            quotes.on_closed(side, Instant::now());
+        }
+    }
+}
+
+async fn poll_fills(client: &HyperliquidClient, quotes: &mut QuoteManager) {
+    for side in [Side::Bid, Side::Ask] {
+        poll_fill(client, quotes, side).await;
+    }
+}
+
+async fn poll_fill(client: &HyperliquidClient, quotes: &mut QuoteManager, side: Side) {
+    let Some(oid) = quotes.quote(side).map(|q| q.oid) else {
+        return;
+    };
+
+    match client.order_status(client.account(), oid).await {
+        Ok(Some(state)) => {
+            let filled = quotes.on_fill(side, state.filled_sz());
+            if filled > 0.0 {
+                info!("💰 FILL {:?} {:.4}, unhedged {:.4}", side, filled, quotes.unhedged());
+            }
+            if state.status != "open" {
+                quotes.on_closed(side, Instant::now());
+            }
+        }
+        Ok(None) => quotes.on_closed(side, Instant::now()),
+        Err(e) => warn!("order status of {} failed: {:#}", oid, e),
+    }
+}
+
+async fn send_hedge(
+    cfg: &Settings,
+    provider: &dyn Provider,
+    sender: &TxSender,
+    router: &dyn DexRouter,
+    direction: &ArbDirection,
+    size: f64,
+) -> Result<PendingTx> {
+    let norm = Normalizer::new(cfg);
+    let base_amount = norm.base_units(size);
+    let quoted = router.quote(provider, direction, base_amount).await?;
+    if quoted == 0 {
+        bail!("no DEX liquidity for {:.4}", size);
+    }
+    let per_unit = norm.dex_price(quoted, base_amount);
+
+    let deadline = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 60;
+    let swap = DexSwap::for_direction(cfg, direction, base_amount, U256::from(quoted));
+    let calldata = router.swap_calldata(&swap, sender.address(), U256::from(deadline)).await?;
+    let tx = sender.send_swap(calldata, TxOverrides::default()).await?;
+
+    info!("🛡️ HEDGE {:?} {:.4} @ ${:.4}: {}", direction, size, per_unit, tx.hash);
+    Ok(tx)
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use log::info;
+
+use crate::arbitrage::{ArbDirection, ArbProfit, GasToken, PriceData, arb_profit_in};
+use crate::pnl::TradeRecord;
+use crate::settings::Settings;
+use crate::strategy::Strategy;
+
+/// Statistical arbitrage on the CEX–DEX mid spread. Keeps a rolling mean
+/// and standard deviation of the spread in bps, enters when it sits more
+/// than `zscore_entry` sigmas from the mean (buying the cheap venue) and
+/// unwinds once it reverts to within `zscore_exit` sigmas. The position
+/// only moves with fills, so a proposal that never executes is proposed
+/// again.
+pub struct MeanReversion {
+    cex_fee_bps: u32,
+    dex_gas_used: u64,
+    gas_token: GasToken,
+    window: usize,
+    entry: f64,
+    exit: f64,
+    spreads: VecDeque<f64>,
+    // direction of the entry leg while a position is open, from the fills
+    position: Option<ArbDirection>,
+}
+
+impl MeanReversion {
+    pub fn new(cfg: &Settings) -> Self {
+        Self {
+            cex_fee_bps: cfg.cex_fee_bps,
+            dex_gas_used: cfg.dex_gas_used,
+            gas_token: cfg.gas_token(),
+            window: cfg.zscore_window.max(2),
+            entry: cfg.zscore_entry,
+            exit: cfg.zscore_exit,
+            spreads: VecDeque::with_capacity(cfg.zscore_window),
+            position: None,
+        }
+    }
+
+    /// Z-score of the latest spread against the window, `None` while the
+    /// window is filling or flat.
+    pub fn zscore(&self) -> Option<f64> {
+        if self.spreads.len() < self.window {
+            return None;
+        }
+
+        let n = self.spreads.len() as f64;
+        let mean = self.spreads.iter().sum::<f64>() / n;
+        let var = self.spreads.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        let std = var.sqrt();
+        if std <= f64::EPSILON {
+            return None;
+        }
+
+        Some((self.spreads.back()? - mean) / std)
+    }
+
+    fn mean(&self) -> f64 {
+        self.spreads.iter().sum::<f64>() / self.spreads.len() as f64
+    }
+
+    fn profit(&self, cex: &PriceData, dex: &PriceData, direction: &ArbDirection, gas_price_wei: u128) -> ArbProfit {
+        let (buy, sell) = match direction {
+            ArbDirection::BuyCex => (cex.ask, dex.bid),
+            ArbDirection::BuyDex => (dex.ask, cex.bid),
+        };
+        arb_profit_in(self.gas_token, self.cex_fee_bps, self.dex_gas_used, buy, sell, direction, gas_price_wei)
+    }
+}
+
+fn spread_bps(cex: &PriceData, dex: &PriceData) -> f64 {
+    let cex_mid = (cex.bid + cex.ask) / 2.0;
+    let dex_mid = (dex.bid + dex.ask) / 2.0;
+    (dex_mid - cex_mid) / cex_mid * 10000.0
+}
+
+impl Strategy for MeanReversion {
+    fn name(&self) -> &'static str {
+        "mean_reversion"
+    }
+
+    fn on_price_update(&mut self, cex: &PriceData, dex: &PriceData) {
+        if self.spreads.len() == self.window {
+            self.spreads.pop_front();
+        }
+        self.spreads.push_back(spread_bps(cex, dex));
+    }
+
+    /// An entry is priced at what reverting to the mean would capture, less
+    /// crossing both books and the fees of entering and exiting. An exit is
+    /// priced at the cost of unwinding at the current prices.
+    fn evaluate(&mut self, cex: &PriceData, dex: &PriceData, gas_price_wei: u128) -> Vec<(ArbDirection, ArbProfit)> {
+        let Some(z) = self.zscore() else {
+            return Vec::new();
+        };
+
+        match self.position.clone() {
+            None if z.abs() >= self.entry => {
+                // DEX rich against its usual spread: buy the CEX, sell the DEX
+                let direction = if z > 0.0 { ArbDirection::BuyCex } else { ArbDirection::BuyDex };
+
+                let cex_mid = (cex.bid + cex.ask) / 2.0;
+                let deviation = (spread_bps(cex, dex) - self.mean()).abs() / 10000.0 * cex_mid;
+                let crossing = (cex.ask - cex.bid) + (dex.ask - dex.bid);
+                let leg = self.profit(cex, dex, &direction, gas_price_wei);
+
+                let gross = deviation - crossing;
+                let (cex_fee, gas_cost) = (2.0 * leg.cex_fee, 2.0 * leg.gas_cost);
+                let profit = ArbProfit { gross, cex_fee, gas_cost, borrow_cost: 0.0, net: gross - cex_fee - gas_cost };
+
+                info!("📐 ENTER {:?}: z {:.2}, expected net ${:.4}", direction, z, profit.net);
+                vec![(direction, profit)]
+            }
+            Some(entered) if z.abs() <= self.exit || z.signum() != zsign(&entered) => {
+                let direction = match entered {
+                    ArbDirection::BuyCex => ArbDirection::BuyDex,
+                    ArbDirection::BuyDex => ArbDirection::BuyCex,
+                };
+                let profit = self.profit(cex, dex, &direction, gas_price_wei);
+
+                info!("📐 EXIT {:?}: z {:.2}, unwind net ${:.4}", direction, z, profit.net);
+                vec![(direction, profit)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn closes(&self, direction: &ArbDirection) -> bool {
+        self.position.as_ref().is_some_and(|entered| entered != direction)
+    }
+
+    // a fill against the entry closes the position, any other opens or
+    // adds to it
+    fn on_fill(&mut self, trade: &TradeRecord) {
+        self.position = match self.position.take() {
+            Some(entered) if entered != trade.direction => None,
+            _ => Some(trade.direction.clone()),
+        };
+    }
+}
+
+// sign of the z-score an entry in `direction` was taken on
+fn zsign(direction: &ArbDirection) -> f64 {
+    match direction {
+        ArbDirection::BuyCex => 1.0,
+        ArbDirection::BuyDex => -1.0,
+    }
+}
@@ -0,0 +1,49 @@
+pub mod cex_dex;
+pub mod latency_arb;
+pub mod market_making;
+pub mod mean_reversion;
+
+use std::sync::Arc;
+
+use crate::arbitrage::{ArbDirection, ArbProfit, PriceData};
+use crate::clock::Clock;
+use crate::pnl::TradeRecord;
+use crate::settings::{Settings, StrategyKind};
+
+/// Decision logic of an `ArbEngine`. The engine owns the feeds, risk checks
+/// and gas price; a strategy only turns prices into candidate trades, so
+/// alternatives can be swapped in through `strategy` in the config.
+pub trait Strategy: Send {
+    fn name(&self) -> &'static str;
+
+    /// Times the strategy on `clock`, for strategies that keep time.
+    fn set_clock(&mut self, _clock: Arc<dyn Clock>) {}
+
+    /// Every change of either feed once both have a price, including
+    /// while trading is paused, for strategies that keep state.
+    fn on_price_update(&mut self, _cex: &PriceData, _dex: &PriceData) {}
+
+    /// Candidate trades on the latest prices, each with its expected profit
+    /// per base unit.
+    fn evaluate(&mut self, cex: &PriceData, dex: &PriceData, gas_price_wei: u128) -> Vec<(ArbDirection, ArbProfit)>;
+
+    /// Whether a trade in `direction` closes a position the strategy holds.
+    /// Those go out whatever they net, as holding on costs more.
+    fn closes(&self, _direction: &ArbDirection) -> bool {
+        false
+    }
+
+    /// A trade this strategy proposed settled.
+    fn on_fill(&mut self, _trade: &TradeRecord) {}
+}
+
+pub fn from_config(cfg: &Settings) -> Box<dyn Strategy> {
+    match cfg.strategy {
+        StrategyKind::CexDex => Box::new(cex_dex::CexDexSpread::new(cfg)),
+        StrategyKind::MeanReversion => Box::new(mean_reversion::MeanReversion::new(cfg)),
+        // quoting runs in `market_making::run_market_maker`, the engines
+        // only report taker spreads
+        StrategyKind::LatencyArb => Box::new(latency_arb::LatencyArb::new(cfg)),
+        StrategyKind::MarketMaking => Box::new(cex_dex::CexDexSpread::new(cfg)),
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::info;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Instant, MissedTickBehavior, interval};
+
+use crate::arbitrage::PriceData;
+use crate::notify::{Notification, Notifier};
+use crate::pnl::{AccountPnl, GasTotals};
+use crate::schedule::civil_date;
+
+const DAY_MS: u64 = 86_400_000;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VenueSummary {
+    pub opportunities: u64,
+    pub trades: u64,
+    pub pnl: f64,
+}
+
+/// How long a price feed had a fresh price over the day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedUptime {
+    pub samples: u64,
+    pub up_samples: u64,
+}
+
+impl FeedUptime {
+    pub fn ratio(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.up_samples as f64 / self.samples as f64 }
+    }
+}
+
+/// What one UTC day looked like, aggregated from the notifications the
+/// engines raised (every opportunity, not only those above
+/// `webhook_min_profit`) and the feeds sampled once a second.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DailySummary {
+    // YYYY-MM-DD
+    pub date: String,
+    pub opportunities: u64,
+    pub best_net: f64,
+    pub trades: u64,
+    pub volume_base: f64,
+    pub pnl: f64,
+    pub estimated_pnl: f64,
+    pub cex_fees: f64,
+    // spent over the day, `None` without a gas ledger
+    pub gas: Option<GasTotals>,
+    pub venues: BTreeMap<String, VenueSummary>,
+    // trades by CEX sub-account (the venue when on its own keys) and by
+    // the wallet that sent the swap
+    pub accounts: BTreeMap<String, AccountPnl>,
+    pub wallets: BTreeMap<String, AccountPnl>,
+    pub feeds: BTreeMap<String, FeedUptime>,
+}
+
+impl DailySummary {
+    pub fn new(day_start_ms: u64) -> Self {
+        Self { date: format_date(day_start_ms), ..Default::default() }
+    }
+
+    pub fn record(&mut self, notification: &Notification) {
+        match notification {
+            Notification::Opportunity { venue, net, .. } => {
+                self.best_net = if self.opportunities == 0 { *net } else { self.best_net.max(*net) };
+                self.opportunities += 1;
+                self.venues.entry(venue.clone()).or_default().opportunities += 1;
+            }
+            Notification::Trade { venue, account, wallet, base_amount, cex_fee, pnl, estimated_pnl, .. } => {
+                self.trades += 1;
+                self.volume_base += base_amount;
+                self.pnl += pnl;
+                self.estimated_pnl += estimated_pnl;
+                self.cex_fees += cex_fee;
+                let account = account.clone().unwrap_or_else(|| venue.clone());
+                self.accounts.entry(account).or_default().add(*base_amount, *pnl);
+                if let Some(wallet) = wallet {
+                    self.wallets.entry(wallet.to_string()).or_default().add(*base_amount, *pnl);
+                }
+                let venue = self.venues.entry(venue.clone()).or_default();
+                venue.trades += 1;
+                venue.pnl += pnl;
+            }
+            Notification::Summary { .. } => {}
+        }
+    }
+
+    pub fn sample_feed(&mut self, feed: &str, up: bool) {
+        let uptime = self.feeds.entry(feed.to_string()).or_default();
+        uptime.samples += 1;
+        uptime.up_samples += up as u64;
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "📊 Daily summary {}", self.date);
+        let _ = writeln!(out, "opportunities: {} (best net ${:.4})", self.opportunities, self.best_net);
+        let _ = writeln!(
+            out,
+            "trades: {}, volume {:.4} base, pnl ${:.4} (estimated ${:.4})",
+            self.trades, self.volume_base, self.pnl, self.estimated_pnl
+        );
+        let _ = writeln!(out, "cex fees: ${:.4}", self.cex_fees);
+        match &self.gas {
+            Some(gas) => {
+                let _ = writeln!(
+                    out,
+                    "gas: {} txs ({} reverted), {:.6} native, ${:.4}",
+                    gas.txs, gas.failed_txs, gas.native, gas.usd
+                );
+            }
+            None => {
+                let _ = writeln!(out, "gas: n/a");
+            }
+        }
+        for (venue, s) in &self.venues {
+            let _ = writeln!(out, "{}: {} opportunities, {} trades, pnl ${:.4}", venue, s.opportunities, s.trades, s.pnl);
+        }
+        for (kind, breakdown) in self.breakdowns() {
+            for (name, s) in breakdown {
+                let _ = writeln!(out, "{} {}: {} trades, {:.4} base, pnl ${:.4}", kind, name, s.trades, s.volume_base, s.pnl);
+            }
+        }
+        for (feed, uptime) in &self.feeds {
+            let _ = writeln!(out, "{} feed uptime: {:.2}%", feed, uptime.ratio() * 100.0);
+        }
+        out
+    }
+
+    pub fn render_html(&self) -> String {
+        let row = |k: &str, v: String| format!("<tr><th>{}</th><td>{}</td></tr>", escape(k), escape(&v));
+
+        let mut rows = vec![
+            row("opportunities", format!("{} (best net ${:.4})", self.opportunities, self.best_net)),
+            row("trades", self.trades.to_string()),
+            row("volume", format!("{:.4} base", self.volume_base)),
+            row("pnl", format!("${:.4} (estimated ${:.4})", self.pnl, self.estimated_pnl)),
+            row("cex fees", format!("${:.4}", self.cex_fees)),
+            row(
+                "gas",
+                match &self.gas {
+                    Some(gas) => format!("{} txs ({} reverted), {:.6} native, ${:.4}", gas.txs, gas.failed_txs, gas.native, gas.usd),
+                    None => "n/a".to_string(),
+                },
+            ),
+        ];
+        for (venue, s) in &self.venues {
+            rows.push(row(venue, format!("{} opportunities, {} trades, pnl ${:.4}", s.opportunities, s.trades, s.pnl)));
+        }
+        for (kind, breakdown) in self.breakdowns() {
+            for (name, s) in breakdown {
+                let value = format!("{} trades, {:.4} base, pnl ${:.4}", s.trades, s.volume_base, s.pnl);
+                rows.push(row(&format!("{} {}", kind, name), value));
+            }
+        }
+        for (feed, uptime) in &self.feeds {
+            rows.push(row(&format!("{} feed uptime", feed), format!("{:.2}%", uptime.ratio() * 100.0)));
+        }
+
+        format!("<h2>Daily summary {}</h2>\n<table>\n{}\n</table>\n", escape(&self.date), rows.join("\n"))
+    }
+
+    // only worth a line each when trades were spread over more than one
+    fn breakdowns(&self) -> Vec<(&'static str, &BTreeMap<String, AccountPnl>)> {
+        [("account", &self.accounts), ("wallet", &self.wallets)].into_iter().filter(|(_, b)| b.len() > 1).collect()
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// `YYYY-MM-DD` (UTC) of a unix ms timestamp.
+pub fn format_date(ms: u64) -> String {
+    let (y, m, d) = civil_date(ms);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Aggregates the day's activity and, once the UTC day is over, sends its
+/// summary as text and HTML through `notifier`. A feed counts as up while
+/// its last price is at most `stale` old.
+pub async fn run_daily_summary(
+    mut rx: mpsc::Receiver<Notification>,
+    mut feeds: Vec<(String, watch::Receiver<Option<PriceData>>)>,
+    gas_rx: Option<watch::Receiver<GasTotals>>,
+    stale: Duration,
+    notifier: Notifier,
+) {
+    let mut day = now_ms() / DAY_MS;
+    let mut summary = DailySummary::new(day * DAY_MS);
+    let gas_at = |rx: &Option<watch::Receiver<GasTotals>>| rx.as_ref().map(|rx| rx.borrow().clone());
+    let mut gas_start = gas_at(&gas_rx);
+    let mut last_update: Vec<Option<Instant>> = vec![None; feeds.len()];
+
+    let mut tick = interval(Duration::from_secs(1));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            notification = rx.recv() => match notification {
+                Some(notification) => summary.record(&notification),
+                None => return,
+            },
+            _ = tick.tick() => {
+                let now = Instant::now();
+                for ((name, feed), last) in feeds.iter_mut().zip(last_update.iter_mut()) {
+                    if feed.has_changed().unwrap_or(false) && feed.borrow_and_update().is_some() {
+                        *last = Some(now);
+                    }
+                    summary.sample_feed(name, last.is_some_and(|at| now.duration_since(at) <= stale));
+                }
+
+                let today = now_ms() / DAY_MS;
+                if today == day {
+                    continue;
+                }
+
+                let gas_end = gas_at(&gas_rx);
+                summary.gas = gas_end.as_ref().zip(gas_start.as_ref()).map(|(end, start)| gas_delta(end, start));
+
+                let text = summary.render_text();
+                info!("{}", text.trim_end());
+                notifier.summary(&summary.date, text, summary.render_html());
+
+                day = today;
+                summary = DailySummary::new(day * DAY_MS);
+                gas_start = gas_end;
+            }
+        }
+    }
+}
+
+fn gas_delta(end: &GasTotals, start: &GasTotals) -> GasTotals {
+    GasTotals {
+        txs: end.txs - start.txs,
+        failed_txs: end.failed_txs - start.failed_txs,
+        native: end.native - start.native,
+        usd: end.usd - start.usd,
+        failed_native: end.failed_native - start.failed_native,
+        failed_usd: end.failed_usd - start.failed_usd,
+        by_kind: end
+            .by_kind
+            .iter()
+            .map(|(kind, native)| (*kind, native - start.by_kind.get(kind).copied().unwrap_or_default()))
+            .collect(),
+    }
+}
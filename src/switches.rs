@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Result, bail};
+
+use crate::arbitrage::ArbDirection;
+use crate::settings::{Settings, VenueTrading};
+
+// an entry for every venue
+const ALL_VENUES: &str = "*";
+
+/// Which venues and directions may execute, for staged rollouts: a venue
+/// can stay detection-only while another trades, or a direction can be
+/// held back everywhere. Opportunities are still found and scored either
+/// way, only their execution is refused.
+///
+/// A trade needs every entry naming its venue, and the `*` one, to allow
+/// its direction; venues without entries trade both. On top of that a
+/// venue can be halted while it is in maintenance, see `venue_status`;
+/// clones share the halts.
+#[derive(Debug, Clone, Default)]
+pub struct TradingSwitches {
+    entries: Vec<VenueTrading>,
+    // venue -> why it is halted
+    halted: Arc<RwLock<BTreeMap<String, String>>>,
+}
+
+impl TradingSwitches {
+    pub fn new(entries: Vec<VenueTrading>) -> Self {
+        Self { entries, halted: Arc::default() }
+    }
+
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self::new(cfg.venue_trading.clone())
+    }
+
+    pub fn allows(&self, venue: &str, direction: &ArbDirection) -> bool {
+        self.entries
+            .iter()
+            .filter(|e| e.venue == venue || e.venue == ALL_VENUES)
+            .all(|e| e.directions.contains(direction))
+    }
+
+    /// Stops all execution on `venue` until `resume`, false when it was
+    /// halted already.
+    pub fn halt(&self, venue: &str, reason: &str) -> bool {
+        self.halted.write().unwrap().insert(venue.to_string(), reason.to_string()).is_none()
+    }
+
+    /// Lifts a `halt` of `venue`, false when it was not halted.
+    pub fn resume(&self, venue: &str) -> bool {
+        self.halted.write().unwrap().remove(venue).is_some()
+    }
+
+    /// Why `venue` is halted, if it is.
+    pub fn halted(&self, venue: &str) -> Option<String> {
+        self.halted.read().unwrap().get(venue).cloned()
+    }
+
+    /// Fails when `venue` may not execute `direction`.
+    pub fn check(&self, venue: &str, direction: &ArbDirection) -> Result<()> {
+        if let Some(reason) = self.halted(venue) {
+            bail!("execution on {} is halted: {}", venue, reason);
+        }
+        if !self.allows(venue, direction) {
+            bail!("{:?} execution on {} is switched off", direction, venue);
+        }
+        Ok(())
+    }
+
+    /// The venues of `venues` that may not execute at all.
+    pub fn detection_only<'a>(&self, venues: &'a [String]) -> Vec<&'a str> {
+        venues
+            .iter()
+            .filter(|v| !self.allows(v, &ArbDirection::BuyCex) && !self.allows(v, &ArbDirection::BuyDex))
+            .map(|v| v.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,286 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{B64, B128, B256, keccak256};
+use anyhow::Result;
+use log::{info, warn};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio::time::interval;
+
+use crate::notify::http_client;
+use crate::settings::Settings;
+
+// flush early once this many spans are buffered
+const MAX_BATCH: usize = 512;
+// finished spans queued for the exporter before further ones are dropped
+const QUEUE_CAPACITY: usize = 4 * MAX_BATCH;
+
+// ids only need to be unique: hash a per-process seed with a counter
+static ID_SEED: LazyLock<(u64, u32)> = LazyLock::new(|| (now_ns(), std::process::id()));
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn next_id() -> B256 {
+    let (seed, pid) = *ID_SEED;
+    let n = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    keccak256([seed.to_be_bytes().as_slice(), &pid.to_be_bytes(), &n.to_be_bytes()].concat())
+}
+
+/// Attribute value of a span, the OTLP `AnyValue` kinds we use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl From<&str> for AttrValue {
+    fn from(v: &str) -> Self {
+        Self::Str(v.to_string())
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(v: String) -> Self {
+        Self::Str(v)
+    }
+}
+
+impl From<i64> for AttrValue {
+    fn from(v: i64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<usize> for AttrValue {
+    fn from(v: usize) -> Self {
+        Self::Int(v as i64)
+    }
+}
+
+impl From<f64> for AttrValue {
+    fn from(v: f64) -> Self {
+        Self::Double(v)
+    }
+}
+
+impl From<bool> for AttrValue {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+/// A finished span as exported.
+#[derive(Debug, Clone)]
+pub struct SpanData {
+    pub trace_id: B128,
+    pub span_id: B64,
+    pub parent_span_id: Option<B64>,
+    pub name: String,
+    pub start_ns: u64,
+    pub end_ns: u64,
+    pub attributes: Vec<(&'static str, AttrValue)>,
+}
+
+/// Starts spans and hands finished ones to the OTLP exporter. Cheap to
+/// clone; the default tracer is disabled and its spans cost next to nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+    tx: Option<Arc<mpsc::Sender<SpanData>>>,
+}
+
+impl Tracer {
+    /// Tracer for `otlp_endpoint` and the exporter task feeding it, `None`
+    /// for the task when tracing is off.
+    pub fn from_config(cfg: &Settings) -> (Self, Option<OtlpExporter>) {
+        let Some(endpoint) = cfg.otlp_endpoint.clone() else {
+            return (Self::default(), None);
+        };
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let exporter = OtlpExporter {
+            http: http_client(),
+            url: format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+            service_name: cfg.otel_service_name.clone(),
+            interval: Duration::from_millis(cfg.otlp_export_interval_ms),
+            rx,
+        };
+        (Self { tx: Some(Arc::new(tx)) }, Some(exporter))
+    }
+
+    /// Tracer exporting into `tx` instead of over OTLP, e.g. for tests.
+    pub fn with_sink(tx: mpsc::Sender<SpanData>) -> Self {
+        Self { tx: Some(Arc::new(tx)) }
+    }
+
+    /// Root span of a new trace.
+    pub fn span(&self, name: &str) -> Span {
+        self.start(name, B128::from_slice(&next_id()[..16]), None)
+    }
+
+    fn start(&self, name: &str, trace_id: B128, parent_span_id: Option<B64>) -> Span {
+        let data = self.tx.as_ref().map(|_| SpanData {
+            trace_id,
+            span_id: B64::from_slice(&next_id()[..8]),
+            parent_span_id,
+            name: name.to_string(),
+            start_ns: now_ns(),
+            end_ns: 0,
+            attributes: Vec::new(),
+        });
+        Span { tracer: self.clone(), data }
+    }
+}
+
+/// An open span, exported when dropped or `end`ed.
+pub struct Span {
+    tracer: Tracer,
+    data: Option<SpanData>,
+}
+
+impl Span {
+    pub fn child(&self, name: &str) -> Span {
+        match &self.data {
+            Some(data) => self.tracer.start(name, data.trace_id, Some(data.span_id)),
+            None => Span { tracer: Tracer::default(), data: None },
+        }
+    }
+
+    pub fn set(&mut self, key: &'static str, value: impl Into<AttrValue>) {
+        if let Some(data) = self.data.as_mut() {
+            data.attributes.push((key, value.into()));
+        }
+    }
+
+    /// W3C `traceparent` header value continuing this trace in another
+    /// process, `None` while tracing is off.
+    pub fn traceparent(&self) -> Option<String> {
+        let data = self.data.as_ref()?;
+        Some(format!("00-{:x}-{:x}-01", data.trace_id, data.span_id))
+    }
+
+    pub fn end(self) {}
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let (Some(mut data), Some(tx)) = (self.data.take(), self.tracer.tx.as_ref()) else {
+            return;
+        };
+        data.end_ns = now_ns();
+        // a span the exporter has no room for is dropped
+        let _ = tx.try_send(data);
+    }
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or_default()
+}
+
+/// Batches finished spans and POSTs them as OTLP/HTTP JSON to
+/// `{otlp_endpoint}/v1/traces` every `otlp_export_interval_ms`. A failed
+/// export is logged and its batch dropped.
+pub struct OtlpExporter {
+    http: reqwest::Client,
+    url: String,
+    service_name: String,
+    interval: Duration,
+    rx: mpsc::Receiver<SpanData>,
+}
+
+impl OtlpExporter {
+    pub async fn run(mut self) {
+        info!("exporting traces to {}", self.url);
+        let mut tick = interval(self.interval);
+        let mut batch = Vec::new();
+
+        loop {
+            tokio::select! {
+                span = self.rx.recv() => match span {
+                    Some(span) => {
+                        batch.push(span);
+                        if batch.len() < MAX_BATCH {
+                            continue;
+                        }
+                    }
+                    None => {
+                        self.flush(&mut batch).await;
+                        return;
+                    }
+                },
+                _ = tick.tick() => {}
+            }
+            self.flush(&mut batch).await;
+        }
+    }
+
+    async fn flush(&self, batch: &mut Vec<SpanData>) {
+        if batch.is_empty() {
+            return;
+        }
+        let body = otlp_json(&self.service_name, batch);
+        batch.clear();
+
+        if let Err(e) = self.post(&body).await {
+            warn!("trace export failed: {:#}", e);
+        }
+    }
+
+    async fn post(&self, body: &Value) -> Result<()> {
+        self.http.post(&self.url).json(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// `ExportTraceServiceRequest` in the OTLP JSON encoding: ids as hex,
+/// 64-bit integers as strings.
+pub fn otlp_json(service_name: &str, spans: &[SpanData]) -> Value {
+    let attributes = |attrs: &[(&str, AttrValue)]| -> Vec<Value> {
+        attrs
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    AttrValue::Str(v) => json!({ "stringValue": v }),
+                    AttrValue::Int(v) => json!({ "intValue": v.to_string() }),
+                    AttrValue::Double(v) => json!({ "doubleValue": v }),
+                    AttrValue::Bool(v) => json!({ "boolValue": v }),
+                };
+                json!({ "key": key, "value": value })
+            })
+            .collect()
+    };
+
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": format!("{:x}", span.trace_id),
+                "spanId": format!("{:x}", span.span_id),
+                "name": span.name,
+                // SPAN_KIND_INTERNAL
+                "kind": 1,
+                "startTimeUnixNano": span.start_ns.to_string(),
+                "endTimeUnixNano": span.end_ns.to_string(),
+                "attributes": attributes(&span.attributes),
+            });
+            if let Some(parent) = span.parent_span_id {
+                value["parentSpanId"] = json!(format!("{:x}", parent));
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": attributes(&[("service.name", AttrValue::from(service_name))]),
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
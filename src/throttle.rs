@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use tokio::time::Instant;
+
+use crate::settings::Settings;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Executions allowed per rolling minute and hour, `None` for no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateLimit {
+    pub per_minute: Option<u32>,
+    pub per_hour: Option<u32>,
+}
+
+impl RateLimit {
+    pub(crate) fn check(&self, times: &VecDeque<Instant>, now: Instant, scope: &str) -> Result<()> {
+        let within = |window: Duration| times.iter().filter(|t| now.duration_since(**t) < window).count() as u32;
+        if let Some(max) = self.per_minute
+            && within(MINUTE) >= max
+        {
+            bail!("{} reached its limit of {} executions per minute", scope, max);
+        }
+        if let Some(max) = self.per_hour
+            && within(HOUR) >= max
+        {
+            bail!("{} reached its limit of {} executions per hour", scope, max);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    global: VecDeque<Instant>,
+    pairs: BTreeMap<String, VecDeque<Instant>>,
+}
+
+/// Caps executions per pair and across all pairs, a blunt safety net
+/// against a runaway signal such as a broken feed showing a permanent
+/// spread. Clones share the counts.
+#[derive(Debug, Clone, Default)]
+pub struct TradeThrottle {
+    global: RateLimit,
+    per_pair: RateLimit,
+    counts: Arc<Mutex<Counts>>,
+}
+
+impl TradeThrottle {
+    pub fn new(global: RateLimit, per_pair: RateLimit) -> Self {
+        Self { global, per_pair, counts: Arc::default() }
+    }
+
+    pub fn from_config(cfg: &Settings) -> Self {
+        Self::new(
+            RateLimit { per_minute: cfg.max_trades_per_minute, per_hour: cfg.max_trades_per_hour },
+            RateLimit { per_minute: cfg.max_pair_trades_per_minute, per_hour: cfg.max_pair_trades_per_hour },
+        )
+    }
+
+    /// Counts an execution on `pair` if both limits leave room for it.
+    pub fn acquire(&self, pair: &str) -> Result<()> {
+        self.acquire_at(pair, Instant::now())
+    }
+
+    pub fn acquire_at(&self, pair: &str, now: Instant) -> Result<()> {
+        let Ok(mut counts) = self.counts.lock() else {
+            bail!("trade throttle lock poisoned");
+        };
+        let Counts { global, pairs } = &mut *counts;
+        let pair_times = pairs.entry(pair.to_string()).or_default();
+        for times in [&mut *global, &mut *pair_times] {
+            while times.front().is_some_and(|t| now.duration_since(*t) >= HOUR) {
+                times.pop_front();
+            }
+        }
+
+        self.global.check(global, now, "all pairs")?;
+        self.per_pair.check(pair_times, now, pair)?;
+        global.push_back(now);
+        pair_times.push_back(now);
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use log::{info, warn};
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::adapters::{bybit, gateio};
+use crate::settings::Settings;
+use crate::switches::TradingSwitches;
+
+/// A maintenance period a venue announced, in unix ms. Without an end it
+/// lasts until the venue stops reporting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    pub title: String,
+    pub begin_ms: u64,
+    pub end_ms: Option<u64>,
+}
+
+impl MaintenanceWindow {
+    /// Whether `now_ms` falls inside the window widened by `margin_ms` on
+    /// both sides.
+    pub fn covers(&self, now_ms: u64, margin_ms: u64) -> bool {
+        let begun = now_ms >= self.begin_ms.saturating_sub(margin_ms);
+        let ended = self.end_ms.is_some_and(|end| now_ms >= end.saturating_add(margin_ms));
+        begun && !ended
+    }
+}
+
+/// The window of `windows` covering `now_ms`, see `MaintenanceWindow::covers`.
+pub fn active_window(windows: &[MaintenanceWindow], now_ms: u64, margin_ms: u64) -> Option<&MaintenanceWindow> {
+    windows.iter().find(|w| w.covers(now_ms, margin_ms))
+}
+
+/// Maintenance `venue` reports, empty for venues without a status endpoint.
+/// Gate.io announces no windows, so a pair that is not tradable counts as
+/// maintenance until it is again.
+pub async fn fetch_maintenance(cfg: &Settings, venue: &str, now_ms: u64) -> Result<Vec<MaintenanceWindow>> {
+    match venue {
+        "bybit" => bybit::fetch_maintenance(cfg).await,
+        "gateio" => {
+            let status = gateio::fetch_pair_status(cfg).await?;
+            Ok(match status.as_str() {
+                "tradable" => Vec::new(),
+                _ => vec![MaintenanceWindow {
+                    title: format!("{} is {}", cfg.gateio_ticker, status),
+                    begin_ms: now_ms,
+                    end_ms: None,
+                }],
+            })
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Applies the maintenance windows of `venue` at `now_ms` to `switches`:
+/// halted inside one, resumed outside. Returns whether that changed
+/// anything.
+pub fn apply(
+    switches: &TradingSwitches,
+    venue: &str,
+    windows: &[MaintenanceWindow],
+    now_ms: u64,
+    margin_ms: u64,
+) -> bool {
+    match active_window(windows, now_ms, margin_ms) {
+        Some(window) => {
+            let halted = switches.halt(venue, &window.title);
+            if halted {
+                info!("🛠️ {} maintenance ({}), execution on it paused", venue, window.title);
+            }
+            halted
+        }
+        None => {
+            let resumed = switches.resume(venue);
+            if resumed {
+                info!("✅ {} maintenance over, execution on it resumed", venue);
+            }
+            resumed
+        }
+    }
+}
+
+/// Polls the status of every CEX venue every `venue_status_poll_secs` and
+/// halts execution on a venue around its maintenance, see `apply`. A
+/// failed poll keeps the windows known from the last one.
+pub async fn run_venue_status(cfg: Settings, switches: TradingSwitches) {
+    let mut tick = interval(Duration::from_secs(cfg.venue_status_poll_secs.max(1)));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let margin_ms = cfg.venue_maintenance_margin_secs * 1000;
+    let mut known: BTreeMap<String, Vec<MaintenanceWindow>> = BTreeMap::new();
+
+    loop {
+        tick.tick().await;
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+        for venue in &cfg.cex_venues {
+            match fetch_maintenance(&cfg, venue, now_ms).await {
+                Ok(windows) => {
+                    known.insert(venue.clone(), windows);
+                }
+                Err(e) => warn!("status of {} unknown: {:#}", venue, e),
+            }
+            apply(&switches, venue, known.get(venue).map(Vec::as_slice).unwrap_or_default(), now_ms, margin_ms);
+        }
+    }
+}
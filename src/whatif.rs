@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::arbitrage::{ArbInputs, price_candidates};
+use crate::audit::{AuditEntry, entries};
+use crate::borrow::BorrowCost;
+use crate::execution::executor::expected_net;
+use crate::settings::Settings;
+use crate::strategy::{self, Strategy};
+
+const FEE_PIPS: f64 = 1_000_000.0;
+
+/// Hypothetical fee, gas and size parameters to re-price past engine
+/// evaluations with; anything left `None` stays as configured.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WhatIf {
+    pub cex_fee_bps: Option<u32>,
+    // pool fee tier in pips, e.g. 500 for 0.05%
+    pub dex_fee_tier: Option<u32>,
+    pub dex_gas_used: Option<u64>,
+    pub gas_price_gwei: Option<f64>,
+    // base per trade instead of `exec_base_size`
+    pub size: Option<f64>,
+}
+
+impl WhatIf {
+    /// `cfg` with the hypothetical parameters in place.
+    pub fn settings(&self, cfg: &Settings) -> Settings {
+        let mut cfg = cfg.clone();
+        cfg.cex_fee_bps = self.cex_fee_bps.unwrap_or(cfg.cex_fee_bps);
+        cfg.dex_fee_tier = self.dex_fee_tier.unwrap_or(cfg.dex_fee_tier);
+        cfg.dex_gas_used = self.dex_gas_used.unwrap_or(cfg.dex_gas_used);
+        cfg.exec_base_size = self.size.unwrap_or(cfg.exec_base_size);
+        cfg
+    }
+
+    /// Recorded inputs as they would have been: the DEX quotes re-fee'd from
+    /// the configured tier to the hypothetical one, price impact aside, and
+    /// the gas price replaced.
+    pub fn inputs(&self, cfg: &Settings, inputs: &ArbInputs) -> ArbInputs {
+        let mut inputs = inputs.clone();
+        if let Some(tier) = self.dex_fee_tier {
+            // the pool pays out less of a sale and takes more for a purchase
+            let kept = (1.0 - tier as f64 / FEE_PIPS) / (1.0 - cfg.dex_fee_tier as f64 / FEE_PIPS);
+            inputs.dex_bid *= kept;
+            inputs.dex_ask /= kept;
+        }
+        if let Some(gwei) = self.gas_price_gwei {
+            inputs.gas_price_wei = (gwei * 1e9) as u128;
+        }
+        inputs
+    }
+}
+
+/// How past evaluations would have paid at one set of parameters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Outcome {
+    pub size: f64,
+    // evaluations whose best candidate nets `exec_min_profit` at `size`
+    pub profitable: u64,
+    // summed net of those best candidates
+    pub net: f64,
+}
+
+impl Outcome {
+    fn add(&mut self, best: Option<f64>, min_profit: f64) {
+        if let Some(net) = best.filter(|net| *net >= min_profit) {
+            self.profitable += 1;
+            self.net += net;
+        }
+    }
+}
+
+/// The configured parameters against the hypothetical ones over the same
+/// recorded evaluations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WhatIfReport {
+    pub evaluations: u64,
+    pub current: Outcome,
+    pub what_if: Outcome,
+}
+
+type Engines = BTreeMap<String, (Box<dyn Strategy>, BorrowCost)>;
+
+// the best expected net of an evaluation at `cfg`'s trade size
+fn best_net(cfg: &Settings, engines: &mut Engines, venue: &str, inputs: &ArbInputs) -> Option<f64> {
+    let (strategy, borrow) = engines
+        .entry(venue.to_string())
+        .or_insert_with(|| (strategy::from_config(cfg), BorrowCost::new(cfg, venue)));
+    let prices = inputs.prices();
+    strategy.on_price_update(&prices.cex, &prices.dex);
+    price_candidates(strategy.as_mut(), borrow, &prices, inputs.gas_price_wei)
+        .iter()
+        .map(|(_, profit)| expected_net(profit, cfg.exec_base_size))
+        .reduce(f64::max)
+}
+
+/// Re-prices every recorded engine evaluation twice, through the strategy
+/// of `cfg` and through the same with `what_if` applied, counting what
+/// would have cleared `exec_min_profit` each way.
+pub fn what_if(cfg: &Settings, entries: &[AuditEntry], what_if: &WhatIf) -> Result<WhatIfReport> {
+    let hypothetical = what_if.settings(cfg);
+    let mut current_engines = Engines::new();
+    let mut what_if_engines = Engines::new();
+    let mut report = WhatIfReport {
+        current: Outcome { size: cfg.exec_base_size, ..Default::default() },
+        what_if: Outcome { size: hypothetical.exec_base_size, ..Default::default() },
+        ..Default::default()
+    };
+
+    for entry in entries {
+        let decision = &entry.decision;
+        let Some(venue) = decision.source.strip_prefix("engine:").filter(|_| decision.action == "arb") else {
+            continue;
+        };
+        let inputs: ArbInputs = serde_json::from_value(decision.inputs.clone())?;
+
+        report.evaluations += 1;
+        report.current.add(best_net(cfg, &mut current_engines, venue, &inputs), cfg.exec_min_profit);
+        let changed = what_if.inputs(cfg, &inputs);
+        report.what_if.add(best_net(&hypothetical, &mut what_if_engines, venue, &changed), cfg.exec_min_profit);
+    }
+    Ok(report)
+}
+
+/// `what_if` over the log at `path` once its chain checks out.
+pub fn what_if_file(cfg: &Settings, path: impl AsRef<Path>, what_if: &WhatIf) -> Result<WhatIfReport> {
+    self::what_if(cfg, &entries(path)?, what_if)
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_arb_bot::arbitrage::Leg;
+use rust_arb_bot::execution::accounts::{Account, AccountBalance, AccountRouter};
+use rust_arb_bot::execution::cex::{CexFill, CexOrders, OrderPolicy};
+use rust_arb_bot::execution::client_order::ClientOrderId;
+use rust_arb_bot::opportunity::OpportunityId;
+use rust_arb_bot::state::{StateStore, WarmState};
+use rust_arb_bot::throttle::RateLimit;
+use tokio::time::Instant;
+
+struct Fills(&'static str);
+
+#[async_trait]
+impl CexOrders for Fills {
+    fn venue(&self) -> &'static str {
+        self.0
+    }
+
+    async fn market_order(&self, _is_buy: bool, base: f64, price: f64, _id: ClientOrderId, _policy: &OrderPolicy) -> Result<CexFill> {
+        Ok(CexFill { base, price })
+    }
+}
+
+fn router() -> AccountRouter {
+    let mut router = AccountRouter::default();
+    router.add(
+        Account::new("bybit-mm", Arc::new(Fills("bybit")))
+            .with_books(vec!["cex_dex/default".to_string()])
+            .with_limit(RateLimit { per_minute: Some(1), per_hour: None }),
+    );
+    router.add(Account::new("bybit", Arc::new(Fills("bybit"))));
+    router
+}
+
+#[test]
+fn routes_to_the_first_account_with_room() {
+    let router = router();
+    let now = Instant::now();
+    let route = |book: &str, now: Instant| router.route_at("bybit", "default", book, true, (40.0, 1.0), now).map(|a| a.name.clone());
+
+    assert_eq!(route("cex_dex/default", now).unwrap(), "bybit-mm");
+    // over its rate limit the next account takes the order
+    assert_eq!(route("cex_dex/default", now).unwrap(), "bybit");
+    assert_eq!(route("cex_dex/default", now + Duration::from_secs(61)).unwrap(), "bybit-mm");
+    // other books only go to accounts that take any
+    assert_eq!(route("latency_arb/default", now + Duration::from_secs(122)).unwrap(), "bybit");
+
+    let err = router.route_at("gateio", "default", "cex_dex/default", true, (40.0, 1.0), now).map(|a| a.name.clone()).unwrap_err();
+    assert!(err.to_string().contains("no order client for gateio"));
+}
+
+#[tokio::test]
+async fn known_balances_gate_orders_and_follow_fills() {
+    let router = router();
+    let mm = router.get("bybit-mm").unwrap();
+    mm.set_balance(AccountBalance { base: 0.5, quote: 100.0 });
+    let now = Instant::now();
+
+    // selling 1 base needs more than the account holds
+    let account = router.route_at("bybit", "default", "cex_dex/default", false, (40.0, 1.0), now).unwrap();
+    assert_eq!(account.name, "bybit");
+
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    mm.market_order(true, 2.0, 40.0, id, &OrderPolicy { attempts: 1, ack_timeout: Duration::ZERO, fill_timeout: Duration::ZERO })
+        .await
+        .unwrap();
+    assert_eq!(mm.balance(), Some(AccountBalance { base: 2.5, quote: 20.0 }));
+    let account = router.route_at("bybit", "default", "cex_dex/default", false, (40.0, 2.0), now).unwrap();
+    assert_eq!(account.name, "bybit-mm");
+    let account = router.route_at("bybit", "default", "cex_dex/default", true, (40.0, 1.0), now).unwrap();
+    assert_eq!(account.name, "bybit");
+}
+
+#[test]
+fn saves_balances_to_the_state_across_restarts() {
+    let state = StateStore::new(WarmState::default());
+    let account = Account::new("bybit-mm", Arc::new(Fills("bybit"))).with_state(state.clone());
+    account.set_balance(AccountBalance { base: 1.0, quote: 100.0 });
+    account.record_fill(false, &CexFill { base: 0.5, price: 40.0 });
+    assert_eq!(state.snapshot().accounts["bybit-mm"], AccountBalance { base: 0.5, quote: 120.0 });
+
+    // a restarted account picks up where it left off
+    let restarted = Account::new("bybit-mm", Arc::new(Fills("bybit"))).with_state(state);
+    assert_eq!(restarted.balance(), Some(AccountBalance { base: 0.5, quote: 120.0 }));
+}
@@ -0,0 +1,281 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use rust_arb_bot::adapters::declarative::{Condition, DeclarativeAdapter, FieldPath};
+use rust_arb_bot::adapters::gateio;
+use rust_arb_bot::adapters::{AdapterRegistry, Decoder, ExchangeAdapter, run_labeled_listener, run_listener};
+use rust_arb_bot::arbitrage::{PriceData, QuoteSource};
+use rust_arb_bot::risk::{RiskEvent, risk_channel};
+use rust_arb_bot::settings::{Settings, WsVenue};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::time::timeout;
+use tokio_tungstenite::{accept_async, tungstenite::protocol::Message};
+
+/// A venue sending `{"bid": .., "ask": ..}` after a `{"sub": ticker}`,
+/// pinging with "ping" after `keepalive` of silence.
+struct Toy {
+    url: String,
+    keepalive: Option<Duration>,
+}
+
+impl ExchangeAdapter for Toy {
+    fn venue(&self) -> &'static str {
+        "toy"
+    }
+
+    fn ticker(&self, _cfg: &Settings) -> String {
+        "TOYUSD".to_string()
+    }
+
+    fn endpoint(&self, _cfg: &Settings) -> String {
+        self.url.clone()
+    }
+
+    fn subscribe(&self, _cfg: &Settings, ticker: &str) -> Vec<Value> {
+        vec![json!({ "sub": ticker })]
+    }
+
+    fn decoder(&self, _cfg: &Settings, _ticker: &str) -> Decoder {
+        Box::new(|text| {
+            // keepalive answers are not json
+            let Ok(v) = serde_json::from_str::<Value>(text) else {
+                return Ok(None);
+            };
+            Ok(v["bid"].as_f64().zip(v["ask"].as_f64()).map(|(bid, ask)| PriceData::new(bid, ask)))
+        })
+    }
+
+    fn keepalive(&self) -> Option<(Duration, &'static str)> {
+        self.keepalive.map(|after| (after, "ping"))
+    }
+}
+
+#[test]
+fn registry_resolves_configured_venues_in_order() {
+    let mut cfg = common::settings();
+    let registry = AdapterRegistry::builtin();
+
+    cfg.cex_venues = vec!["gateio".to_string(), "bybit".to_string()];
+    let venues: Vec<_> = registry.from_config(&cfg).unwrap().iter().map(|a| a.venue()).collect();
+    assert_eq!(venues, ["gateio", "bybit"]);
+    assert_eq!(registry.get("bybit").unwrap().ticker(&cfg), cfg.bybit_ticker);
+
+    cfg.cex_venues.push("toy".to_string());
+    let e = registry.from_config(&cfg).err().unwrap().to_string();
+    assert!(e.contains("\"toy\"") && e.contains("bybit, gateio"), "{}", e);
+    assert!(registry.with(Toy { url: String::new(), keepalive: None }).from_config(&cfg).is_ok());
+}
+
+#[test]
+fn bybit_decoder_keeps_the_book_across_messages() {
+    let cfg = common::settings();
+    let bybit = AdapterRegistry::builtin().get("bybit").unwrap();
+    assert_eq!(bybit.subscribe(&cfg, "HYPEUSDT")[0]["args"][0], format!("orderbook.{}.HYPEUSDT", cfg.bybit_depth("HYPEUSDT")));
+
+    let mut decode = bybit.decoder(&cfg, "HYPEUSDT");
+    let snapshot = r#"{"topic":"orderbook.50.HYPEUSDT","type":"snapshot","data":{"b":[["40.0","1"],["39.9","2"]],"a":[["40.1","1"]]}}"#;
+    assert_eq!(decode(snapshot).unwrap(), Some(PriceData::new(40.0, 40.1)));
+    // the best bid is pulled, the next level shows through
+    let delta = r#"{"topic":"orderbook.50.HYPEUSDT","type":"delta","data":{"b":[["40.0","0"]],"a":[]}}"#;
+    assert_eq!(decode(delta).unwrap(), Some(PriceData::new(39.9, 40.1)));
+    assert_eq!(decode(r#"{"op":"subscribe","success":true}"#).unwrap(), None);
+}
+
+#[test]
+fn bybit_decoder_prices_the_trade_size_and_drops_out_of_sequence_books() {
+    let mut cfg = common::settings();
+    cfg.exec_base_size = 4.0;
+    let bybit = AdapterRegistry::builtin().get("bybit").unwrap();
+
+    let mut decode = bybit.decoder(&cfg, "HYPEUSDT");
+    let snapshot = r#"{"topic":"orderbook.50.HYPEUSDT","type":"snapshot","data":{"b":[["40.0","2"],["39.5","5"]],"a":[["40.25","2"],["40.75","5"]],"u":7}}"#;
+    assert_eq!(decode(snapshot).unwrap(), Some(PriceData::new(39.75, 40.5)));
+    // dated by the exchange's `ts`
+    let delta = r#"{"topic":"orderbook.50.HYPEUSDT","ts":1700000000123,"type":"delta","data":{"b":[],"a":[["40.25","4"]],"u":8}}"#;
+    let stamped = PriceData::new(39.75, 40.25).with_source(Some(QuoteSource::Exchange(1_700_000_000_123)));
+    assert_eq!(decode(delta).unwrap(), Some(stamped));
+
+    // update 9 got lost
+    let gap = r#"{"topic":"orderbook.50.HYPEUSDT","type":"delta","data":{"b":[],"a":[["40.25","0"]],"u":10}}"#;
+    assert!(decode(gap).unwrap_err().to_string().contains("does not follow 8"));
+}
+
+#[tokio::test]
+async fn a_new_venue_is_one_adapter() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(socket).await.unwrap();
+        let Some(Ok(Message::Text(sub))) = ws.next().await else {
+            panic!("expected a subscription");
+        };
+        assert_eq!(serde_json::from_str::<Value>(&sub).unwrap(), json!({ "sub": "TOYUSD" }));
+        ws.send(Message::Text(r#"{"bid": 2.0, "ask": 2.5}"#.to_string())).await.unwrap();
+        // hold the connection open
+        let _ = ws.next().await;
+    });
+
+    let adapter = AdapterRegistry::empty().with(Toy { url, keepalive: None }).get("toy").unwrap();
+    let (tx, mut rx) = watch::channel(None);
+    let (risk_tx, _risk_rx) = risk_channel();
+    let cfg = common::settings();
+    let ticker = adapter.ticker(&cfg);
+    tokio::spawn(run_listener(Arc::clone(&adapter), cfg, ticker, tx, risk_tx));
+
+    timeout(Duration::from_secs(5), rx.changed()).await.unwrap().unwrap();
+    assert_eq!(*rx.borrow(), Some(PriceData::new(2.0, 2.5)));
+}
+
+#[test]
+fn okx_decoder_reads_books5_snapshots() {
+    let mut cfg = common::settings();
+    cfg.exec_base_size = 2.0;
+    let okx = AdapterRegistry::builtin().get("okx").unwrap();
+    assert_eq!(okx.subscribe(&cfg, "HYPE-USDT")[0]["args"][0], json!({ "channel": "books5", "instId": "HYPE-USDT" }));
+    assert!(okx.keepalive().is_some());
+
+    let mut decode = okx.decoder(&cfg, "HYPE-USDT");
+    let push = r#"{"arg":{"channel":"books5","instId":"HYPE-USDT"},"data":[{"asks":[["40.25","1","0","2"],["40.75","5","0","1"]],"bids":[["40.0","3","0","4"]],"instId":"HYPE-USDT","ts":"1700000000000"}]}"#;
+    let stamped = PriceData::new(40.0, 40.5).with_source(Some(QuoteSource::Exchange(1_700_000_000_000)));
+    assert_eq!(decode(push).unwrap(), Some(stamped));
+    assert_eq!(decode("pong").unwrap(), None);
+    assert_eq!(decode(r#"{"event":"subscribe","arg":{"channel":"books5","instId":"HYPE-USDT"}}"#).unwrap(), None);
+    let error = r#"{"event":"error","code":"60018","msg":"Wrong URL or channel"}"#;
+    assert!(decode(error).unwrap_err().to_string().contains("60018"));
+}
+
+#[test]
+fn hyperliquid_decoder_reads_l2_books_of_the_spot_pair() {
+    let mut cfg = common::settings();
+    cfg.exec_base_size = 2.0;
+    cfg.mm_spot_pair_index = 107;
+    let hyperliquid = AdapterRegistry::builtin().get("hyperliquid").unwrap();
+    let coin = hyperliquid.ticker(&cfg);
+    assert_eq!(coin, "@107");
+    assert_eq!(hyperliquid.subscribe(&cfg, &coin)[0]["subscription"], json!({ "type": "l2Book", "coin": "@107" }));
+
+    let mut decode = hyperliquid.decoder(&cfg, &coin);
+    let push = r#"{"channel":"l2Book","data":{"coin":"@107","time":1700000000000,"levels":[[{"px":"40.0","sz":"3","n":4}],[{"px":"40.25","sz":"1","n":2},{"px":"40.75","sz":"5","n":1}]]}}"#;
+    let stamped = PriceData::new(40.0, 40.5).with_source(Some(QuoteSource::Exchange(1_700_000_000_000)));
+    assert_eq!(decode(push).unwrap(), Some(stamped));
+    assert_eq!(decode(&push.replace("@107", "@108")).unwrap(), None);
+    assert_eq!(decode(r#"{"channel":"pong"}"#).unwrap(), None);
+    assert_eq!(decode(r#"{"channel":"subscriptionResponse","data":{"method":"subscribe"}}"#).unwrap(), None);
+    assert!(decode(r#"{"channel":"error","data":"Invalid subscription"}"#).is_err());
+}
+
+#[tokio::test]
+async fn a_second_feed_on_a_venue_reports_halts_under_its_label() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(socket).await.unwrap();
+        let _subscription = ws.next().await;
+        ws.send(Message::Text(r#"{"bid": 0.0, "ask": 0.0}"#.to_string())).await.unwrap();
+        let _ = ws.next().await;
+    });
+
+    let adapter = AdapterRegistry::empty().with(Toy { url, keepalive: None }).get("toy").unwrap();
+    let (tx, _rx) = watch::channel(None);
+    let (risk_tx, mut risk_rx) = risk_channel();
+    let cfg = common::settings();
+    let ticker = adapter.ticker(&cfg);
+    tokio::spawn(run_labeled_listener(adapter, "toy:bridge".to_string(), cfg, ticker, tx, risk_tx));
+
+    let event = timeout(Duration::from_secs(5), risk_rx.recv()).await.unwrap().unwrap();
+    assert!(matches!(event, RiskEvent::CexHalt { venue, halted: true } if venue == "toy:bridge"));
+}
+
+#[tokio::test]
+async fn text_keepalives_hold_a_quiet_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(socket).await.unwrap();
+        let _subscription = ws.next().await;
+        // quiet until pinged, then answers and sends a price
+        let Some(Ok(Message::Text(ping))) = ws.next().await else {
+            panic!("expected a keepalive");
+        };
+        assert_eq!(ping, "ping");
+        ws.send(Message::Text("pong".to_string())).await.unwrap();
+        ws.send(Message::Text(r#"{"bid": 3.0, "ask": 3.5}"#.to_string())).await.unwrap();
+        let _ = ws.next().await;
+    });
+
+    let adapter = AdapterRegistry::empty()
+        .with(Toy { url, keepalive: Some(Duration::from_millis(50)) })
+        .get("toy")
+        .unwrap();
+    let (tx, mut rx) = watch::channel(None);
+    let (risk_tx, _risk_rx) = risk_channel();
+    let cfg = common::settings();
+    let ticker = adapter.ticker(&cfg);
+    tokio::spawn(run_listener(adapter, cfg, ticker, tx, risk_tx));
+
+    timeout(Duration::from_secs(5), rx.changed()).await.unwrap().unwrap();
+    assert_eq!(*rx.borrow(), Some(PriceData::new(3.0, 3.5)));
+}
+
+fn gateio_spec() -> WsVenue {
+    WsVenue {
+        name: "gate-declared".to_string(),
+        endpoint: "wss://api.gateio.ws/ws/v4/".to_string(),
+        ticker: "HYPE_USDT".to_string(),
+        subscribe: vec![r#"{"time": {time}, "channel": "spot.tickers", "event": "subscribe", "payload": ["{ticker}"]}"#.to_string()],
+        only_when: vec!["channel=spot.tickers".to_string(), "event=update".to_string(), "result.currency_pair={ticker}".to_string()],
+        error_when: vec!["error.code=2".to_string()],
+        bid: "result.highest_bid".to_string(),
+        ask: "$.result.lowest_ask".to_string(),
+        timestamp: Some("time_ms".to_string()),
+        keepalive_secs: None,
+        keepalive_message: None,
+    }
+}
+
+#[test]
+fn a_venue_described_in_config_reads_like_its_adapter() {
+    let cfg = common::settings();
+    let adapter = DeclarativeAdapter::new(&gateio_spec()).unwrap();
+    let subscription = &adapter.subscribe(&cfg, "HYPE_USDT")[0];
+    assert_eq!(subscription["payload"], json!(["HYPE_USDT"]));
+    assert!(subscription["time"].as_u64().unwrap() > 0);
+
+    let mut decode = adapter.decoder(&cfg, "HYPE_USDT");
+    let update = r#"{"time":1700000000,"time_ms":1700000000123,"channel":"spot.tickers","event":"update","result":{"currency_pair":"HYPE_USDT","highest_bid":"40.0","lowest_ask":"40.1"}}"#;
+    let emptied = update.replace(r#""40.1""#, r#""""#);
+    let ack = r#"{"time":1700000000,"channel":"spot.tickers","event":"subscribe","result":{"status":"success"}}"#;
+    for message in [update, emptied.as_str(), ack, "pong"] {
+        assert_eq!(decode(message).unwrap(), gateio::parse_message(message), "{}", message);
+    }
+    assert_eq!(decode(&update.replace("HYPE_USDT", "PURR_USDT")).unwrap(), None);
+    assert!(decode(r#"{"error":{"code":2,"message":"unknown currency pair"}}"#).is_err());
+}
+
+#[test]
+fn configured_venues_are_checked_and_registered() {
+    assert_eq!(FieldPath::parse("data[0].b[1][0]").unwrap().get(&json!({ "data": [{ "b": [[1], ["2.5", 3]] }] })), Some(&json!("2.5")));
+    for bad in ["", "data..bid", "data[x]", "data[0"] {
+        assert!(FieldPath::parse(bad).is_err(), "{}", bad);
+    }
+    assert!(Condition::parse("data.bid").is_err());
+    assert!(DeclarativeAdapter::new(&WsVenue { subscribe: vec!["{ticker}".to_string()], ..gateio_spec() }).is_err());
+
+    let mut cfg = common::settings();
+    cfg.ws_venues = vec![gateio_spec()];
+    cfg.cex_venues = vec!["bybit".to_string(), "gate-declared".to_string()];
+    let registry = AdapterRegistry::configured(&cfg).unwrap();
+    let venues: Vec<_> = registry.from_config(&cfg).unwrap().iter().map(|a| (a.venue(), a.ticker(&cfg))).collect();
+    assert_eq!(venues, [("bybit", cfg.bybit_ticker.clone()), ("gate-declared", "HYPE_USDT".to_string())]);
+
+    cfg.ws_venues.push(WsVenue { name: "bybit".to_string(), ..gateio_spec() });
+    assert!(AdapterRegistry::configured(&cfg).err().unwrap().to_string().contains("already defined"));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rust_arb_bot::alerts::{AlertLimiter, ChatAlerts, format_alert};
+use rust_arb_bot::arbitrage::{ArbDirection, ArbProfit};
+use rust_arb_bot::notify::{Notification, Notifier, OpportunityDetails};
+use rust_arb_bot::opportunity::Opportunity;
+use rust_arb_bot::settings::Settings;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::time::Instant;
+
+use common::capture_request;
+
+fn details() -> OpportunityDetails {
+    OpportunityDetails { pair: "default".to_string(), size: 10.0, expected_net: 12.0, cex_price: 40.0, dex_price: 40.2 }
+}
+
+fn profit(net: f64) -> ArbProfit {
+    ArbProfit { gross: net + 0.5, cex_fee: 0.3, gas_cost: 0.2, borrow_cost: 0.0, net }
+}
+
+#[test]
+fn holds_back_repeats_and_caps_the_hour() {
+    let mut limiter = AlertLimiter::new(Duration::from_secs(300), Some(3));
+    let now = Instant::now();
+
+    assert_eq!(limiter.admit("bybit/default/BuyCex", now), Some(0));
+    assert_eq!(limiter.admit("bybit/default/BuyCex", now + Duration::from_secs(10)), None);
+    assert_eq!(limiter.admit("bybit/default/BuyCex", now + Duration::from_secs(20)), None);
+    // other keys are not held back by the cooldown
+    assert_eq!(limiter.admit("gateio/default/BuyCex", now), Some(0));
+    assert_eq!(limiter.admit("bybit/default/BuyCex", now + Duration::from_secs(300)), Some(2));
+    // the hourly cap of 3 is reached
+    assert_eq!(limiter.admit("bybit/default/BuyDex", now + Duration::from_secs(400)), None);
+    assert_eq!(limiter.admit("bybit/default/BuyDex", now + Duration::from_secs(3600)), Some(1));
+
+    let opportunity = Opportunity::new(ArbDirection::BuyDex, profit(1.2));
+    let text = format_alert(&Notification::opportunity("bybit", &opportunity, details()), 2).unwrap();
+    assert!(text.starts_with("💡 default BuyDex on bybit\nsize 10.0000, net $12.0000 ($1.2000/unit)\ncex 40.000000, dex 40.200000"));
+    assert!(text.ends_with("2 more alerts held back"));
+}
+
+#[tokio::test]
+async fn pushes_opportunities_above_the_threshold_to_telegram() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let api_url = format!("http://{}", listener.local_addr().unwrap());
+    let captured = tokio::spawn(capture_request(listener));
+
+    let env = HashMap::from([
+        ("RPC_URL".to_string(), "http://mock.invalid".to_string()),
+        ("TELEGRAM_BOT_TOKEN".to_string(), "123:abc".to_string()),
+        ("TELEGRAM_CHAT_ID".to_string(), "-100".to_string()),
+        ("TELEGRAM_API_URL".to_string(), api_url),
+    ]);
+    let cfg = Settings::load_from(&["config/default.toml"], Some(env)).unwrap();
+    let (chat_tx, chat) = ChatAlerts::from_config(&cfg).unwrap();
+    tokio::spawn(chat.run());
+    let notifier = Notifier::default().with_chat(chat_tx, cfg.chat_min_profit);
+
+    // below chat_min_profit, never pushed
+    notifier.opportunity("bybit", &Opportunity::new(ArbDirection::BuyCex, profit(cfg.chat_min_profit / 2.0)), details());
+    notifier.opportunity("bybit", &Opportunity::new(ArbDirection::BuyDex, profit(cfg.chat_min_profit * 2.0)), details());
+
+    let (head, body) = captured.await.unwrap();
+    assert!(head.starts_with("POST /bot123:abc/sendMessage "));
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["chat_id"], "-100");
+    assert!(json["text"].as_str().unwrap().starts_with("💡 default BuyDex on bybit"));
+}
@@ -0,0 +1,214 @@
+use alloy::primitives::{Address, U256};
+use proptest::prelude::*;
+
+use rust_arb_bot::arbitrage::{ArbDirection, GasToken, PriceData, arb_profit, arb_profit_in};
+use rust_arb_bot::execution::dex::apply_slippage;
+use rust_arb_bot::execution::split::{PoolRoute, QuoteLadder, allocate};
+use rust_arb_bot::settings::QuoterKind;
+
+const EPS: f64 = 1e-9;
+
+fn direction() -> impl Strategy<Value = ArbDirection> {
+    prop_oneof![Just(ArbDirection::BuyCex), Just(ArbDirection::BuyDex)]
+}
+
+fn price() -> impl Strategy<Value = f64> {
+    0.01f64..100_000.0
+}
+
+// up to 1000 bps fee, 100 gwei and 2M gas, so costs stay below one unit
+fn fee_bps() -> impl Strategy<Value = u32> {
+    0u32..1000
+}
+
+fn gas_price() -> impl Strategy<Value = u128> {
+    0u128..100_000_000_000
+}
+
+fn gas_used() -> impl Strategy<Value = u64> {
+    0u64..2_000_000
+}
+
+fn ladder(pool: u8, buying: bool) -> impl Strategy<Value = QuoteLadder> {
+    prop::collection::vec(1u128..1_000_000_000, 1..12).prop_map(move |mut marginals| {
+        // quotes are concave: each slice sells for less, or costs more
+        marginals.sort_unstable();
+        if !buying {
+            marginals.reverse();
+        }
+
+        let mut amounts = vec![0u128];
+        for marginal in marginals {
+            amounts.push(amounts.last().unwrap() + marginal);
+        }
+
+        QuoteLadder {
+            route: PoolRoute {
+                pool_addr: Address::with_last_byte(pool),
+                quoter_addr: Address::ZERO,
+                quoter_kind: QuoterKind::V2,
+                router_addr: Address::ZERO,
+                fee_tier: 3000,
+            },
+            step: U256::from(1),
+            amounts,
+        }
+    })
+}
+
+fn ladders() -> impl Strategy<Value = (ArbDirection, Vec<QuoteLadder>)> {
+    direction().prop_flat_map(|direction| {
+        let buying = matches!(direction, ArbDirection::BuyDex);
+        let ladders = (ladder(1, buying), ladder(2, buying), ladder(3, buying))
+            .prop_map(|(a, b, c)| vec![a, b, c]);
+        (Just(direction), ladders)
+    })
+}
+
+fn ladder_total(ladders: &[QuoteLadder], filled: &[usize]) -> u128 {
+    ladders.iter().zip(filled).map(|(l, &k)| l.amounts[k]).sum()
+}
+
+proptest! {
+    #[test]
+    fn net_profit_is_monotonic_in_spread(
+        direction in direction(),
+        buy in price(),
+        sell in price(),
+        widen in 0.0f64..1000.0,
+        fee in fee_bps(),
+        gas in gas_used(),
+        gas_price in gas_price(),
+    ) {
+        let narrow = arb_profit(fee, gas, buy, sell, &direction, gas_price);
+        let wide = arb_profit(fee, gas, buy, sell + widen, &direction, gas_price);
+
+        prop_assert!(wide.net + EPS * wide.net.abs().max(1.0) >= narrow.net);
+    }
+
+    #[test]
+    fn mirrored_directions_cost_the_same(
+        a in price(),
+        b in price(),
+        fee in fee_bps(),
+        gas in gas_used(),
+        gas_price in gas_price(),
+    ) {
+        // buying on the CEX at a and selling on the DEX at b touches the
+        // same venues at the same prices as buying on the DEX at b and
+        // selling on the CEX at a
+        let buy_cex = arb_profit(fee, gas, a, b, &ArbDirection::BuyCex, gas_price);
+        let buy_dex = arb_profit(fee, gas, b, a, &ArbDirection::BuyDex, gas_price);
+
+        prop_assert_eq!(buy_cex.gross, -buy_dex.gross);
+        prop_assert_eq!(buy_cex.cex_fee, buy_dex.cex_fee);
+        prop_assert_eq!(buy_cex.gas_cost, buy_dex.gas_cost);
+    }
+
+    #[test]
+    fn equal_prices_never_profit(
+        direction in direction(),
+        p in price(),
+        fee in fee_bps(),
+        gas in gas_used(),
+        gas_price in gas_price(),
+    ) {
+        let profit = arb_profit(fee, gas, p, p, &direction, gas_price);
+
+        prop_assert_eq!(profit.gross, 0.0);
+        prop_assert!(profit.net <= 0.0);
+    }
+
+    #[test]
+    fn fees_and_gas_never_increase_net_profit(
+        direction in direction(),
+        buy in price(),
+        sell in price(),
+        fee in fee_bps(),
+        extra_fee in 0u32..1000,
+        gas in gas_used(),
+        gas_price in gas_price(),
+        extra_gas_price in gas_price(),
+    ) {
+        let base = arb_profit(fee, gas, buy, sell, &direction, gas_price);
+        let more_fee = arb_profit(fee + extra_fee, gas, buy, sell, &direction, gas_price);
+        let more_gas = arb_profit(fee, gas, buy, sell, &direction, gas_price + extra_gas_price);
+
+        prop_assert!(base.net <= base.gross);
+        prop_assert!(base.cex_fee >= 0.0 && base.gas_cost >= 0.0);
+        prop_assert!(more_fee.net <= base.net);
+        prop_assert!(more_gas.net <= base.net);
+    }
+
+    #[test]
+    fn inverting_a_book_keeps_it_crossed_the_right_way(bid in price(), spread in 0.0f64..100.0) {
+        let book = PriceData::new(bid, bid + spread);
+        let inverse = book.inverted();
+
+        prop_assert!(inverse.bid <= inverse.ask);
+        let back = inverse.inverted();
+        prop_assert!((back.bid - book.bid).abs() <= EPS * book.bid);
+        prop_assert!((back.ask - book.ask).abs() <= EPS * book.ask);
+    }
+
+    #[test]
+    fn gas_in_quote_costs_the_same_at_any_price(
+        direction in direction(),
+        buy in price(),
+        sell in price(),
+        gas in gas_used(),
+        gas_price in gas_price(),
+    ) {
+        let at = |buy, sell| arb_profit_in(GasToken::Quote, 0, gas, buy, sell, &direction, gas_price);
+        prop_assert_eq!(at(buy, sell).gas_cost, at(buy * 2.0, sell * 2.0).gas_cost);
+        prop_assert_eq!(at(buy, sell).gas_cost, (gas_price * gas as u128) as f64 / 1e18);
+    }
+
+    #[test]
+    fn slippage_limits_bracket_the_quote(amount in 0u128..u128::MAX / 20_000, bps in 0u32..=10_000) {
+        let amount = U256::from(amount);
+
+        prop_assert!(apply_slippage(amount, bps, false) <= amount);
+        prop_assert!(apply_slippage(amount, bps, true) >= amount);
+        prop_assert_eq!(apply_slippage(amount, 0, true), amount);
+        prop_assert_eq!(apply_slippage(amount, 0, false), amount);
+    }
+
+    #[test]
+    fn allocation_hands_out_every_step((direction, ladders) in ladders(), steps in 0usize..30) {
+        let capacity: usize = ladders.iter().map(|l| l.amounts.len() - 1).sum();
+        let filled = allocate(&ladders, steps, &direction);
+
+        if steps > capacity {
+            prop_assert!(filled.is_err());
+        } else {
+            let filled = filled.unwrap();
+            prop_assert_eq!(filled.iter().sum::<usize>(), steps);
+            for (l, k) in ladders.iter().zip(&filled) {
+                prop_assert!(*k < l.amounts.len());
+            }
+        }
+    }
+
+    #[test]
+    fn allocation_beats_any_single_pool((direction, ladders) in ladders(), steps in 1usize..12) {
+        let Ok(filled) = allocate(&ladders, steps, &direction) else {
+            return Ok(());
+        };
+        let split = ladder_total(&ladders, &filled);
+
+        for (i, l) in ladders.iter().enumerate() {
+            if l.amounts.len() <= steps {
+                continue;
+            }
+            let mut single = vec![0; ladders.len()];
+            single[i] = steps;
+            let single = ladder_total(&ladders, &single);
+
+            match direction {
+                ArbDirection::BuyCex => prop_assert!(split >= single),
+                ArbDirection::BuyDex => prop_assert!(split <= single),
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use std::fs;
+
+use rust_arb_bot::arbitrage::{ArbInputs, PriceData, TickPrices, arb_decision, price_candidates};
+use rust_arb_bot::audit::{AuditLog, Decision, GENESIS, verify};
+use rust_arb_bot::borrow::BorrowCost;
+use rust_arb_bot::opportunity::OpportunityId;
+use rust_arb_bot::replay::replay_file;
+use rust_arb_bot::strategy;
+use rust_arb_bot::whatif::{WhatIf, what_if_file};
+use serde_json::json;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust-arb-bot-audit-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join("audit.jsonl")
+}
+
+#[test]
+fn chains_entries_across_reopens() {
+    let path = temp_path("chain");
+    let mut log = AuditLog::open(&path).unwrap();
+    let first = log.append(Decision::taken("market_maker", "hedge", json!({ "size": 1.5, "dex_bid": 40.1 }))).unwrap();
+    assert_eq!((first.seq, first.prev.as_str()), (0, GENESIS));
+    drop(log);
+
+    let mut log = AuditLog::open(&path).unwrap();
+    let second = log
+        .append(Decision::skipped("market_maker", "quote", "trading paused", json!({ "pulled": 2 })))
+        .unwrap();
+    assert_eq!((second.seq, &second.prev), (1, &first.hash));
+    assert_eq!(verify(&path).unwrap(), (2, second.hash));
+}
+
+#[test]
+fn detects_edited_and_dropped_lines() {
+    let path = temp_path("tamper");
+    let mut log = AuditLog::open(&path).unwrap();
+    for size in [1.0, 2.0, 3.0] {
+        log.append(Decision::taken("market_maker", "hedge", json!({ "size": size }))).unwrap();
+    }
+    let original = fs::read_to_string(&path).unwrap();
+
+    fs::write(&path, original.replace("\"size\":2.0", "\"size\":20.0")).unwrap();
+    assert!(verify(&path).unwrap_err().to_string().contains("line 2"));
+
+    let dropped: Vec<&str> = original.lines().enumerate().filter(|(i, _)| *i != 1).map(|(_, l)| l).collect();
+    fs::write(&path, dropped.join("\n")).unwrap();
+    assert!(verify(&path).unwrap_err().to_string().contains("chain broken"));
+}
+
+#[test]
+fn replays_engine_decisions_against_changed_config() {
+    let path = temp_path("replay");
+    let cfg = common::settings();
+    let mut strategy = strategy::from_config(&cfg);
+    let borrow = BorrowCost::new(&cfg, "bybit");
+
+    let mut log = AuditLog::open(&path).unwrap();
+    log.append(Decision::taken("market_maker", "hedge", json!({ "size": 1.0 }))).unwrap();
+    // dex 0.5% above the cex: worth it at 10 bps, not at 60
+    for dex in [40.0, 40.2] {
+        let prices = TickPrices {
+            cex: PriceData::new(39.99, 40.0),
+            dex: PriceData::new(dex, dex + 0.01),
+            peg: None,
+            age_weight: None,
+        };
+        let found = price_candidates(strategy.as_mut(), &borrow, &prices, 1_000_000_000);
+        let inputs = ArbInputs::new(strategy.name(), &prices, 1_000_000_000);
+        let ids = found.iter().map(|_| OpportunityId::new()).collect();
+        log.append(arb_decision("bybit", &inputs, &found).with_opportunities(ids)).unwrap();
+    }
+
+    let report = replay_file(&cfg, &path).unwrap();
+    assert_eq!((report.replayed, report.skipped, report.diffs.len()), (2, 1, 0));
+
+    let costlier = rust_arb_bot::settings::Settings { cex_fee_bps: 60, ..cfg };
+    let report = replay_file(&costlier, &path).unwrap();
+    assert_eq!((report.diffs.len(), report.flipped()), (2, 1));
+    assert_eq!(report.diffs[1].seq, 2);
+    assert!(report.diffs[1].recorded.taken && !report.diffs[1].replayed.taken);
+    assert!(report.net_change() < 0.0);
+}
+
+#[test]
+fn what_if_reprices_recorded_evaluations() {
+    let path = temp_path("what-if");
+    let mut cfg = common::settings();
+    cfg.exec_min_profit = 0.0;
+    assert_eq!(cfg.dex_fee_tier, 3000);
+    let mut strategy = strategy::from_config(&cfg);
+    let borrow = BorrowCost::new(&cfg, "bybit");
+
+    let mut log = AuditLog::open(&path).unwrap();
+    // dex level with the cex, then 0.5% above it
+    for dex in [40.0, 40.2] {
+        let prices = TickPrices {
+            cex: PriceData::new(39.99, 40.0),
+            dex: PriceData::new(dex, dex + 0.01),
+            peg: None,
+            age_weight: None,
+        };
+        let found = price_candidates(strategy.as_mut(), &borrow, &prices, 1_000_000_000);
+        log.append(arb_decision("bybit", &ArbInputs::new(strategy.name(), &prices, 1_000_000_000), &found)).unwrap();
+    }
+
+    let unchanged = what_if_file(&cfg, &path, &WhatIf::default()).unwrap();
+    assert_eq!(unchanged.evaluations, 2);
+    assert_eq!(unchanged.current, unchanged.what_if);
+    assert_eq!(unchanged.current.profitable, 1);
+
+    // a 60 bps CEX fee eats the 0.5%
+    let costlier = what_if_file(&cfg, &path, &WhatIf { cex_fee_bps: Some(60), ..Default::default() }).unwrap();
+    assert_eq!(costlier.what_if.profitable, 0);
+
+    // a 0.05% pool gives back 0.25% on the level quote too
+    let cheaper = what_if_file(&cfg, &path, &WhatIf { dex_fee_tier: Some(500), ..Default::default() }).unwrap();
+    assert_eq!(cheaper.what_if.profitable, 2);
+    assert!(cheaper.what_if.net > cheaper.current.net);
+
+    // twice the size nets more than twice, gas is paid once
+    let bigger = what_if_file(&cfg, &path, &WhatIf { size: Some(cfg.exec_base_size * 2.0), ..Default::default() }).unwrap();
+    assert!(bigger.what_if.net > bigger.current.net * 2.0);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::path::Path;
+
+use rust_arb_bot::backtest::{BacktestParams, Tick, TickWriter, load_ticks, parse_date_ms, run_backtest, run_engine_backtest};
+use rust_arb_bot::clock::{Clock, SimClock};
+use rust_arb_bot::settings::Settings;
+
+const TICKS: &str = "tests/fixtures/backtest/ticks.jsonl";
+
+// no fees or gas, so fills are the raw price differences
+fn cfg() -> Settings {
+    let mut cfg = common::settings();
+    cfg.cex_fee_bps = 0;
+    cfg.dex_gas_used = 0;
+    cfg.max_spread_bps = 500;
+    cfg
+}
+
+fn params() -> BacktestParams {
+    BacktestParams { from_ms: None, to_ms: None, size: 1.0, min_profit: 0.0, latency_ms: 100, gas_price_wei: 0 }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+}
+
+#[test]
+fn ticks_load_in_timestamp_order() {
+    let ticks = load_ticks(Path::new(TICKS)).unwrap();
+
+    let ts: Vec<u64> = ticks.iter().map(|t| t.ts_ms).collect();
+    assert_eq!(ts, vec![1000, 1010, 1050, 1200, 1400]);
+    assert_eq!(ticks[0].gas_price_wei, Some(0));
+}
+
+#[test]
+fn opportunities_fill_after_latency() {
+    let ticks = load_ticks(Path::new(TICKS)).unwrap();
+    let report = run_backtest(&cfg(), &params(), &ticks);
+
+    // taken at 1010 and 1200; the first fills after bybit moved against it
+    assert_eq!(report.ticks, 5);
+    assert_eq!(report.opportunities, 2);
+    assert_eq!(report.opportunities_by_route["bybit:BuyCex"], 2);
+    assert_close(report.gross_pnl, 0.0);
+    assert_close(report.net_pnl, 0.0);
+    assert_close(report.max_drawdown, 0.1);
+    assert_eq!((report.trades, report.wins), (2, 1));
+    assert_eq!(report.parameters["latency_ms"], "100");
+}
+
+#[test]
+fn date_range_bounds_the_replay() {
+    let ticks = load_ticks(Path::new(TICKS)).unwrap();
+    let params = BacktestParams { to_ms: Some(1100), ..params() };
+    let report = run_backtest(&cfg(), &params, &ticks);
+
+    // the one opportunity still in flight fills at the last prices in range
+    assert_eq!(report.ticks, 3);
+    assert_eq!(report.last_ts_ms, Some(1050));
+    assert_eq!(report.opportunities, 1);
+    assert_close(report.net_pnl, -0.1);
+
+    assert_eq!(parse_date_ms("1970-01-02").unwrap(), 86_400_000);
+    assert_eq!(parse_date_ms("2024-03-01").unwrap(), 1_709_251_200_000);
+    assert!(parse_date_ms("2024-13-01").is_err());
+}
+
+#[tokio::test]
+async fn replays_through_the_engine_one_trade_at_a_time() {
+    let ticks = load_ticks(Path::new(TICKS)).unwrap();
+    let report = run_engine_backtest(&cfg(), &params(), &ticks, None).await.unwrap();
+
+    // every profitable evaluation counts, at 1010, 1200 and 1400; each is
+    // taken as the previous one filled 100ms after it
+    assert_eq!(report.ticks, 5);
+    assert_eq!(report.opportunities, 3);
+    assert_eq!((report.trades, report.wins), (3, 2));
+    assert_close(report.hit_rate, 2.0 / 3.0);
+    assert_close(report.net_pnl, -0.1 + 0.1 + 0.15);
+}
+
+#[test]
+fn recorded_ticks_load_back_across_days() {
+    let dir = std::env::temp_dir().join(format!("rust-arb-bot-record-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let clock = SimClock::new(86_399_990);
+    let tick = |venue: &str, gas_price_wei| Tick { ts_ms: clock.now_ms(), venue: venue.to_string(), bid: 10.0, ask: 10.1, gas_price_wei };
+
+    let mut writer = TickWriter::new(&dir).unwrap();
+    writer.write(&tick("hyperswap", Some(1_000_000_000))).unwrap();
+    clock.set(86_400_005);
+    writer.write(&tick("bybit", None)).unwrap();
+    // the clock never runs backwards
+    clock.set(0);
+    writer.write(&tick("gateio", None)).unwrap();
+
+    assert!(dir.join("ticks-1970-01-01.jsonl").exists());
+    let ticks = load_ticks(&dir).unwrap();
+    let venues: Vec<_> = ticks.iter().map(|t| (t.ts_ms, t.venue.as_str())).collect();
+    assert_eq!(venues, vec![(86_399_990, "hyperswap"), (86_400_005, "bybit"), (86_400_005, "gateio")]);
+    assert_eq!(ticks[0].gas_price_wei, Some(1_000_000_000));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
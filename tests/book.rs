@@ -0,0 +1,53 @@
+use rust_arb_bot::adapters::book::OrderBook;
+use rust_arb_bot::adapters::bybit::parse_book_update;
+use rust_arb_bot::arbitrage::PriceData;
+
+#[test]
+fn keeps_a_bybit_depth_book_across_deltas() {
+    let mut book = OrderBook::default();
+    let messages = [
+        r#"{"topic":"orderbook.50.HYPEUSDT","type":"snapshot","data":{"s":"HYPEUSDT","b":[["40.10","5"],["40.05","8"]],"a":[["40.20","3"],["40.25","9"]],"u":1}}"#,
+        // best bid taken out, a better ask joins
+        r#"{"topic":"orderbook.50.HYPEUSDT","type":"delta","data":{"s":"HYPEUSDT","b":[["40.10","0"]],"a":[["40.15","1.5"]],"u":2}}"#,
+    ];
+    let mut tops = Vec::new();
+    for text in messages {
+        let update = parse_book_update(text).unwrap();
+        if update.snapshot {
+            book.apply_snapshot(&update.bids, &update.asks);
+        } else {
+            book.apply_delta(&update.bids, &update.asks);
+        }
+        tops.push(book.top());
+    }
+
+    assert_eq!(tops[0], PriceData::new(40.10, 40.20));
+    assert_eq!(tops[1], PriceData::new(40.05, 40.15));
+    assert_eq!(book.asks().copied().collect::<Vec<_>>(), vec![(40.15, 1.5), (40.20, 3.0), (40.25, 9.0)]);
+
+    // a later snapshot replaces everything
+    let reset = parse_book_update(r#"{"topic":"orderbook.50.HYPEUSDT","type":"snapshot","data":{"b":[],"a":[["41","1"]]}}"#).unwrap();
+    book.apply_snapshot(&reset.bids, &reset.asks);
+    assert_eq!(book.top(), PriceData::new(0.0, 41.0));
+    assert!(parse_book_update(r#"{"op":"subscribe","success":true}"#).is_none());
+}
+
+#[test]
+fn walks_the_book_for_the_size_taken() {
+    let mut book = OrderBook::default();
+    book.apply_update(true, Some(1), &[(40.0, 1.0), (39.0, 1.0)], &[(41.0, 1.0), (43.0, 1.0)]).unwrap();
+
+    assert_eq!(book.vwap(true, 0.5), Some(41.0));
+    assert_eq!(book.vwap(true, 2.0), Some(42.0));
+    assert_eq!(book.vwap(false, 2.0), Some(39.5));
+    // more than the book holds
+    assert_eq!(book.vwap(false, 4.0), None);
+    assert_eq!(book.executable(2.0), PriceData::new(39.5, 42.0));
+    assert_eq!(book.executable(4.0), PriceData::new(39.25, 42.5).with_thin(true));
+    assert_eq!(OrderBook::default().vwap(true, 1.0), None);
+
+    // deltas follow the last update one by one
+    assert!(book.apply_update(false, Some(2), &[(40.0, 0.0)], &[]).is_ok());
+    assert!(book.apply_update(false, Some(4), &[], &[]).unwrap_err().to_string().contains("does not follow 2"));
+    assert!(OrderBook::default().apply_update(false, Some(3), &[], &[]).is_err());
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+use rust_arb_bot::arbitrage::PriceData;
+use rust_arb_bot::dex::cadence::RefreshCadence;
+
+fn price(mid: f64) -> Option<PriceData> {
+    Some(PriceData::new(mid - 0.01, mid + 0.01))
+}
+
+#[test]
+fn backs_off_while_the_dex_price_stands_still() {
+    let ms = Duration::from_millis;
+    let mut cadence = RefreshCadence::new(ms(500), ms(3000), 5.0);
+    assert_eq!(cadence.wait(), ms(500));
+
+    let waits: Vec<_> = (0..4)
+        .map(|_| {
+            cadence.refreshed(false);
+            cadence.wait()
+        })
+        .collect();
+    assert_eq!(waits, vec![ms(1000), ms(2000), ms(3000), ms(3000)]);
+
+    cadence.refreshed(true);
+    assert_eq!(cadence.wait(), ms(500));
+    cadence.refreshed(false);
+    cadence.wake();
+    assert_eq!(cadence.wait(), ms(500));
+}
+
+#[tokio::test]
+async fn a_cex_move_past_the_threshold_cuts_the_wait_short() {
+    let (cex_tx, cex_rx) = watch::channel(price(40.0));
+    let mut cadence =
+        RefreshCadence::new(Duration::from_secs(30), Duration::from_secs(60), 5.0).with_cex_feeds(vec![cex_rx]);
+    cadence.refreshed(false);
+
+    // 2.5 bps off the last quote is noise
+    cex_tx.send(price(40.01)).unwrap();
+    assert!(timeout(Duration::from_millis(100), cadence.tick()).await.is_err());
+
+    // 7.5 bps is not
+    cex_tx.send(price(40.03)).unwrap();
+    timeout(Duration::from_secs(5), cadence.tick()).await.expect("no wake-up on the move");
+    assert_eq!(cadence.wait(), Duration::from_secs(30));
+
+    // a feed that went away stops counting, the timer still runs
+    drop(cex_tx);
+    assert!(timeout(Duration::from_millis(100), cadence.tick()).await.is_err());
+}
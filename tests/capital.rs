@@ -0,0 +1,82 @@
+use rust_arb_bot::capital::{Capital, CapitalAllocator, book};
+use rust_arb_bot::exposure::Exposure;
+use rust_arb_bot::inventory::{InFlightTransfer, TransferKind};
+
+#[test]
+fn splits_evenly_then_tilts_toward_profit() {
+    let mut capital = CapitalAllocator::new(1000.0, 0.5, 0.25);
+    capital.register(&book("cex_dex", "default"));
+    capital.register(&book("market_making", "default"));
+    assert_eq!(capital.book("cex_dex/default").unwrap().budget, 500.0);
+
+    capital.record_pnl("cex_dex/default", 30.0);
+    capital.record_pnl("market_making/default", -10.0);
+    capital.rebalance();
+    // weights 1.5 and 0.5
+    assert_eq!(capital.book("cex_dex/default").unwrap().budget, 750.0);
+    assert_eq!(capital.book("market_making/default").unwrap().budget, 250.0);
+
+    // the floor keeps a losing book in the game
+    let mut capital = CapitalAllocator::new(1000.0, 1.0, 0.25);
+    capital.register("a");
+    capital.register("b");
+    capital.record_pnl("a", 5.0);
+    capital.rebalance();
+    assert!((capital.book("a").unwrap().budget - 2.0 / 2.25 * 1000.0).abs() < 1e-9);
+    assert!((capital.book("b").unwrap().budget - 0.25 / 2.25 * 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn reservations_stay_within_book_and_total() {
+    let mut capital = CapitalAllocator::new(1000.0, 1.0, 0.25);
+    capital.register("a");
+    capital.register("b");
+    capital.reserve("a", 400.0).unwrap();
+    assert!(capital.reserve("a", 200.0).is_err());
+    assert!(capital.reserve("unknown", 1.0).is_err());
+
+    // "a" now holds more than its shrunk budget, "b" cannot take its share
+    capital.record_pnl("b", 1.0);
+    capital.rebalance();
+    assert!(capital.reserve("b", 700.0).is_err());
+    capital.reserve("b", 600.0).unwrap();
+    assert_eq!(capital.in_use(), 1000.0);
+    assert!(capital.unregister("a").is_err());
+}
+
+#[test]
+fn dropping_a_reservation_releases_it() {
+    let capital = Capital::new(CapitalAllocator::new(100.0, 0.5, 0.25));
+    capital.register("a");
+    let reservation = capital.reserve("a", 80.0).unwrap();
+    assert!(capital.reserve("a", 30.0).is_err());
+    drop(reservation);
+    assert_eq!(capital.lock().in_use(), 0.0);
+    capital.reserve("a", 30.0).unwrap();
+}
+
+#[test]
+fn transfers_in_flight_are_not_free_capital() {
+    let capital = Capital::new(CapitalAllocator::new(1000.0, 0.0, 0.25));
+    capital.register("a");
+    let exposure = Exposure::default();
+    exposure.begin_transfer(InFlightTransfer {
+        id: "bridge-1".to_string(),
+        kind: TransferKind::Bridge,
+        asset: "base".to_string(),
+        amount: 10.0,
+        from: "hyperevm".to_string(),
+        to: "hypercore".to_string(),
+        started_ms: 0,
+    });
+
+    assert_eq!(capital.track_in_flight(&exposure, 40.0), 400.0);
+    assert!(capital.reserve("a", 700.0).is_err());
+    capital.lock().rebalance();
+    assert_eq!(capital.lock().book("a").unwrap().budget, 600.0);
+
+    exposure.end_transfer("bridge-1");
+    capital.track_in_flight(&exposure, 40.0);
+    capital.lock().rebalance();
+    capital.reserve("a", 700.0).unwrap();
+}
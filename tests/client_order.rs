@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use rust_arb_bot::arbitrage::Leg;
+use rust_arb_bot::execution::client_order::{ClientOrderId, Submitted, acked, is_ambiguous, submit};
+use rust_arb_bot::opportunity::OpportunityId;
+use tokio::net::TcpListener;
+
+// a request whose connection drops before any answer
+async fn lost_answer() -> anyhow::Error {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/exchange", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        drop(socket);
+    });
+    reqwest::Client::new().post(url).send().await.unwrap_err().into()
+}
+
+#[test]
+fn ids_are_fixed_per_opportunity_and_leg() {
+    let (a, b) = (OpportunityId::new(), OpportunityId::new());
+    assert_eq!(ClientOrderId::new(a, Leg::Cex), ClientOrderId::new(a, Leg::Cex));
+    assert_ne!(ClientOrderId::new(a, Leg::Cex), ClientOrderId::new(a, Leg::Dex));
+    assert_ne!(ClientOrderId::new(a, Leg::Cex), ClientOrderId::new(b, Leg::Cex));
+
+    let id = ClientOrderId::new(a, Leg::Cex);
+    assert_eq!(id.to_string().len(), 32);
+    assert_eq!(id.cloid().to_string(), format!("0x{}", id));
+    // Gate.io takes at most 28 characters after `t-`
+    let text = id.gateio_text();
+    assert_eq!(text.len(), 30);
+    assert!(text.starts_with("t-") && id.to_string().starts_with(&text[2..]));
+}
+
+#[tokio::test]
+async fn only_lost_answers_are_ambiguous() {
+    // also behind context
+    assert!(is_ambiguous(&lost_answer().await.context("placing order")));
+    assert!(!is_ambiguous(&anyhow!("hyperliquid rejected action: insufficient margin")));
+}
+
+#[tokio::test]
+async fn looks_up_a_lost_order_instead_of_sending_it_again() {
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let placed = AtomicU32::new(0);
+
+    let result: Result<Submitted<(), &str>> = submit(
+        id,
+        3,
+        || async {
+            placed.fetch_add(1, Ordering::Relaxed);
+            Err(lost_answer().await)
+        },
+        || async { Ok(Some("filled")) },
+    )
+    .await;
+
+    assert_eq!(result.unwrap(), Submitted::Found("filled"));
+    assert_eq!(placed.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn resends_only_what_the_venue_never_got() {
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let (placed, looked_up) = (AtomicU32::new(0), AtomicU32::new(0));
+
+    let result: Result<Submitted<u32, ()>> = submit(
+        id,
+        3,
+        || async {
+            match placed.fetch_add(1, Ordering::Relaxed) {
+                0 => Err(lost_answer().await),
+                n => Ok(n),
+            }
+        },
+        || async {
+            looked_up.fetch_add(1, Ordering::Relaxed);
+            Ok(None)
+        },
+    )
+    .await;
+    assert_eq!(result.unwrap(), Submitted::Placed(1));
+    assert_eq!(looked_up.load(Ordering::Relaxed), 1);
+
+    // gives up after the last attempt
+    let result: Result<Submitted<(), ()>> = submit(id, 2, || async { Err(lost_answer().await) }, || async { Ok(None) }).await;
+    assert!(result.unwrap_err().to_string().contains("not placed after 2 attempts"));
+}
+
+#[tokio::test]
+async fn never_retries_a_rejection() {
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let placed = AtomicU32::new(0);
+
+    let result: Result<Submitted<(), ()>> = submit(
+        id,
+        3,
+        || async {
+            placed.fetch_add(1, Ordering::Relaxed);
+            Err(anyhow!("order rejected"))
+        },
+        || async { panic!("a rejected order is not looked up") },
+    )
+    .await;
+    assert!(result.is_err());
+    assert_eq!(placed.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn an_answer_past_the_ack_timeout_is_lost() {
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let limit = Duration::from_millis(20);
+
+    let result: Result<Submitted<(), &str>> = submit(
+        id,
+        3,
+        || acked(limit, std::future::pending()),
+        || acked(limit, async { Ok(Some("filled")) }),
+    )
+    .await;
+    assert_eq!(result.unwrap(), Submitted::Found("filled"));
+
+    let e = acked::<()>(limit, std::future::pending()).await.unwrap_err();
+    assert!(is_ambiguous(&e));
+}
@@ -0,0 +1,88 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rust_arb_bot::clock::{
+    BYBIT_MAX_SKEW_MS, ClockReport, ClockSample, check_signing, ntp_offset, parse_ntp_reply, query_ntp, request_offset,
+    set_skew, skew_ms,
+};
+use tokio::net::UdpSocket;
+
+const NTP_EPOCH_OFFSET_SECS: f64 = 2_208_988_800.0;
+
+fn ntp_timestamp(unix_secs: f64) -> [u8; 8] {
+    let t = unix_secs + NTP_EPOCH_OFFSET_SECS;
+    let secs = t.trunc() as u32;
+    let frac = (t.fract() * 4_294_967_296.0) as u32;
+    let mut out = [0u8; 8];
+    out[..4].copy_from_slice(&secs.to_be_bytes());
+    out[4..].copy_from_slice(&frac.to_be_bytes());
+    out
+}
+
+fn sample(source: &str, offset_ms: i64) -> ClockSample {
+    ClockSample { source: source.to_string(), offset_ms, rtt_ms: 10 }
+}
+
+#[test]
+fn offsets_assume_the_answer_came_halfway() {
+    // sent at 1000, back at 1100, server said 1550: local is 500ms behind
+    assert_eq!(request_offset(1_000, 1_100, 1_550), 500);
+    assert_eq!(request_offset(1_000, 1_100, 1_000), -50);
+
+    // server 2s ahead, 0.1s each way, 0.01s to answer
+    assert!((ntp_offset(10.0, 12.1, 12.11, 10.21) - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn skew_is_the_median_of_the_sources() {
+    assert_eq!(ClockReport::default().skew_ms(), None);
+    let report = ClockReport { samples: vec![sample("ntp", 40), sample("bybit", 9_000), sample("gateio", 60)], ..Default::default() };
+    assert_eq!(report.skew_ms(), Some(60));
+    let report = ClockReport { samples: vec![sample("ntp", 40), sample("gateio", 60)], ..Default::default() };
+    assert_eq!(report.skew_ms(), Some(50));
+}
+
+#[test]
+fn parses_ntp_receive_and_transmit_times() {
+    let mut reply = [0u8; 48];
+    reply[32..40].copy_from_slice(&ntp_timestamp(1_700_000_000.25));
+    reply[40..48].copy_from_slice(&ntp_timestamp(1_700_000_000.5));
+    let (t1, t2) = parse_ntp_reply(&reply).unwrap();
+    assert!((t1 - 1_700_000_000.25).abs() < 1e-6);
+    assert!((t2 - 1_700_000_000.5).abs() < 1e-6);
+    assert!(parse_ntp_reply(&reply[..40]).is_err());
+}
+
+#[tokio::test]
+async fn measures_against_an_ntp_server() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    // a server running 3s ahead
+    tokio::spawn(async move {
+        let mut request = [0u8; 48];
+        let (_, from) = server.recv_from(&mut request).await.unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64() + 3.0;
+        let mut reply = [0u8; 48];
+        reply[0] = 0x24;
+        reply[32..40].copy_from_slice(&ntp_timestamp(now));
+        reply[40..48].copy_from_slice(&ntp_timestamp(now));
+        server.send_to(&reply, from).await.unwrap();
+    });
+
+    let sample = query_ntp(&addr).await.unwrap();
+    assert!((sample.offset_ms - 3_000).abs() < 100, "offset {}", sample.offset_ms);
+}
+
+#[test]
+fn refuses_signing_beyond_the_venue_tolerance() {
+    // nothing measured yet
+    assert_eq!(skew_ms(), None);
+    assert!(check_signing("bybit", BYBIT_MAX_SKEW_MS).is_ok());
+
+    set_skew(-(BYBIT_MAX_SKEW_MS + 1));
+    let e = check_signing("bybit", BYBIT_MAX_SKEW_MS).unwrap_err();
+    assert!(e.to_string().contains("bybit"));
+    assert!(check_signing("gateio", 50_000).is_ok());
+
+    set_skew(120);
+    assert!(check_signing("bybit", BYBIT_MAX_SKEW_MS).is_ok());
+}
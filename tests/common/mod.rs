@@ -0,0 +1,476 @@
+// shared by several test crates, not all of which use every fixture
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::{
+    consensus,
+    primitives::{Address, B256, Bytes, U256, keccak256},
+    providers::RootProvider,
+    rpc::{
+        client::RpcClient,
+        json_rpc::{ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest},
+        types::{Block, Header},
+    },
+    transports::{TransportError, TransportFut},
+};
+use serde::Serialize;
+use serde_json::{Value, value::RawValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+
+use rust_arb_bot::arbitrage::{ArbEngine, PriceData};
+use rust_arb_bot::risk::{RiskEvent, RiskHandle, TradingState, risk_channel};
+use rust_arb_bot::settings::Settings;
+
+/// Canned chain state served over JSON-RPC without any network. Clones
+/// share state, so a test can keep one handle and change what the provider
+/// sees between calls.
+#[derive(Clone, Default)]
+pub struct MockRpc {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    gas_price: u128,
+    head: u64,
+    head_timestamp: u64,
+    code: HashMap<Address, Bytes>,
+    storage: HashMap<(Address, U256), U256>,
+    calls: HashMap<Address, Bytes>,
+    // transactions to these fail gas estimation, as if they would revert
+    rejected: Vec<Address>,
+    // methods failing outright, as on a timeout or a pruned block
+    failing: Vec<String>,
+    // successful receipts by tx hash, in the given block or else the head
+    receipts: HashMap<B256, Option<u64>>,
+    // transactions the mempool holds, sent through the mock or not
+    mempool: Vec<B256>,
+    // method of every request served, in order
+    requests: Vec<String>,
+}
+
+impl MockRpc {
+    /// A chain at block 1000 whose head was just produced.
+    pub fn new() -> Self {
+        let mock = Self::default();
+        mock.set_head(1000, now());
+        mock
+    }
+
+    pub fn provider(&self) -> RootProvider {
+        RootProvider::new(self.client())
+    }
+
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new(self.clone(), true)
+    }
+
+    pub fn with_gas_price(self, gas_price: u128) -> Self {
+        self.state.lock().unwrap().gas_price = gas_price;
+        self
+    }
+
+    pub fn with_code(self, address: Address, code: Bytes) -> Self {
+        self.state.lock().unwrap().code.insert(address, code);
+        self
+    }
+
+    pub fn with_storage(self, address: Address, slot: U256, value: U256) -> Self {
+        self.state.lock().unwrap().storage.insert((address, slot), value);
+        self
+    }
+
+    /// Every `eth_call` to `to` returns `output`, whatever the calldata.
+    pub fn with_call(self, to: Address, output: Bytes) -> Self {
+        self.state.lock().unwrap().calls.insert(to, output);
+        self
+    }
+
+    /// Transactions to `to` fail gas estimation, so they are never sent.
+    pub fn with_rejected(self, to: Address) -> Self {
+        self.state.lock().unwrap().rejected.push(to);
+        self
+    }
+
+    /// Fails every `method` request, e.g. `eth_getStorageAt` as a node
+    /// that pruned the block would.
+    pub fn with_failing(self, method: &str) -> Self {
+        self.state.lock().unwrap().failing.push(method.to_string());
+        self
+    }
+
+    /// `hash` was included at the current head and succeeded. Other
+    /// transactions are unknown to the node.
+    pub fn with_receipt(self, hash: B256) -> Self {
+        self.state.lock().unwrap().receipts.insert(hash, None);
+        self
+    }
+
+    /// `hash` was included in `block` and succeeded.
+    pub fn include(&self, hash: B256, block: u64) {
+        self.state.lock().unwrap().receipts.insert(hash, Some(block));
+    }
+
+    /// `hash` sits in the mempool, not yet included.
+    pub fn hold(&self, hash: B256) {
+        self.state.lock().unwrap().mempool.push(hash);
+    }
+
+    /// Hashes in the mempool, held or sent through the mock, in order.
+    pub fn sent(&self) -> Vec<B256> {
+        self.state.lock().unwrap().mempool.clone()
+    }
+
+    /// `hash` is no longer included, as after a reorg.
+    pub fn uninclude(&self, hash: B256) {
+        self.state.lock().unwrap().receipts.remove(&hash);
+    }
+
+    pub fn set_head(&self, number: u64, timestamp: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.head = number;
+        state.head_timestamp = timestamp;
+    }
+
+    pub fn requests(&self, method: &str) -> usize {
+        self.state.lock().unwrap().requests.iter().filter(|m| *m == method).count()
+    }
+
+    fn respond(&self, req: &SerializedRequest) -> Response {
+        let params: Vec<Value> = req
+            .params()
+            .and_then(|p| serde_json::from_str(p.get()).ok())
+            .unwrap_or_default();
+        let param = |i: usize| params.get(i).cloned().unwrap_or(Value::Null);
+        let address = |i: usize| serde_json::from_value::<Address>(param(i)).unwrap_or_default();
+
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(req.method().to_string());
+
+        let result = match req.method() {
+            method if state.failing.iter().any(|m| m == method) => Err(format!("{method} failed")),
+            "eth_chainId" => json(U256::from(999)),
+            "eth_gasPrice" => json(U256::from(state.gas_price)),
+            "eth_blockNumber" => json(U256::from(state.head)),
+            "eth_getBlockByNumber" => {
+                let number = match param(0).as_str() {
+                    Some("latest") | None => state.head,
+                    Some(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap_or_default(),
+                };
+                let timestamp = state.head_timestamp.saturating_sub(state.head.saturating_sub(number));
+                json(block(number, timestamp))
+            }
+            "eth_getBalance" | "eth_getTransactionCount" => json(U256::ZERO),
+            "eth_getCode" => json(state.code.get(&address(0)).cloned().unwrap_or_default()),
+            "eth_getStorageAt" => {
+                let slot = serde_json::from_value::<U256>(param(1)).unwrap_or_default();
+                json(state.storage.get(&(address(0), slot)).copied().unwrap_or_default())
+            }
+            "eth_call" => {
+                let to = param(0).get("to").cloned().unwrap_or(Value::Null);
+                let to = serde_json::from_value::<Address>(to).unwrap_or_default();
+                match state.calls.get(&to) {
+                    Some(output) => json(output),
+                    None => Err(format!("no canned eth_call response for {to}")),
+                }
+            }
+            "eth_estimateGas" => {
+                let to = param(0).get("to").cloned().unwrap_or(Value::Null);
+                let to = serde_json::from_value::<Address>(to).unwrap_or_default();
+                match state.rejected.contains(&to) {
+                    true => Err("execution reverted".to_string()),
+                    false => json(U256::from(200_000)),
+                }
+            }
+            "eth_feeHistory" => json(serde_json::json!({
+                "oldestBlock": U256::from(state.head),
+                "baseFeePerGas": ["0x1", "0x1"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x1"]],
+            })),
+            "eth_getTransactionReceipt" => {
+                let hash = serde_json::from_value::<B256>(param(0)).unwrap_or_default();
+                match state.receipts.get(&hash) {
+                    Some(block) => json(receipt(hash, block.unwrap_or(state.head))),
+                    None => json(Value::Null),
+                }
+            }
+            "eth_getTransactionByHash" => {
+                let hash = serde_json::from_value::<B256>(param(0)).unwrap_or_default();
+                match state.mempool.contains(&hash) {
+                    true => json(transaction(hash)),
+                    false => json(Value::Null),
+                }
+            }
+            "eth_sendRawTransaction" => {
+                let raw = serde_json::from_value::<Bytes>(param(0)).unwrap_or_default();
+                let hash = keccak256(&raw);
+                state.mempool.push(hash);
+                json(hash)
+            }
+            method => Err(format!("{method} is not mocked")),
+        };
+
+        let payload = match result {
+            Ok(result) => ResponsePayload::Success(result),
+            Err(message) => ResponsePayload::Failure(ErrorPayload::internal_error_message(message.into())),
+        };
+        Response { id: req.id().clone(), payload }
+    }
+}
+
+impl tower::Service<RequestPacket> for MockRpc {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let packet = match req {
+            RequestPacket::Single(req) => ResponsePacket::Single(self.respond(&req)),
+            RequestPacket::Batch(reqs) => ResponsePacket::Batch(reqs.iter().map(|r| self.respond(r)).collect()),
+        };
+        Box::pin(async move { Ok(packet) })
+    }
+}
+
+fn json(value: impl Serialize) -> Result<Box<RawValue>, String> {
+    serde_json::value::to_raw_value(&value).map_err(|e| e.to_string())
+}
+
+fn block_hash(number: u64) -> B256 {
+    keccak256(number.to_be_bytes())
+}
+
+fn block(number: u64, timestamp: u64) -> Block {
+    let inner = consensus::Header {
+        number,
+        timestamp,
+        parent_hash: block_hash(number.saturating_sub(1)),
+        ..Default::default()
+    };
+    Block {
+        header: Header { hash: block_hash(number), inner, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+/// The header of block `number` as a newHeads subscription delivers it.
+pub fn head(number: u64, timestamp: u64) -> Header {
+    block(number, timestamp).header
+}
+
+fn receipt(hash: B256, block: u64) -> Value {
+    serde_json::json!({
+        "type": "0x2",
+        "status": "0x1",
+        "cumulativeGasUsed": "0x5208",
+        "logs": [],
+        "logsBloom": format!("0x{}", "0".repeat(512)),
+        "transactionHash": hash,
+        "transactionIndex": "0x0",
+        "blockHash": block_hash(block),
+        "blockNumber": format!("{:#x}", block),
+        "gasUsed": "0x5208",
+        "effectiveGasPrice": "0x1",
+        "from": Address::ZERO,
+        "to": Address::ZERO,
+        "contractAddress": null,
+    })
+}
+
+// a pending transfer, as the node returns it
+fn transaction(hash: B256) -> Value {
+    serde_json::json!({
+        "type": "0x2",
+        "chainId": "0x3e7",
+        "nonce": "0x0",
+        "gas": "0x5208",
+        "maxFeePerGas": "0x1",
+        "maxPriorityFeePerGas": "0x1",
+        "to": Address::ZERO,
+        "value": "0x0",
+        "accessList": [],
+        "input": "0x",
+        "r": "0x1",
+        "s": "0x1",
+        "yParity": "0x0",
+        "v": "0x0",
+        "hash": hash,
+        "blockHash": null,
+        "blockNumber": null,
+        "transactionIndex": null,
+        "from": Address::ZERO,
+    })
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Quoter that answers exact-output calls (selector `output_selector`) with
+/// `ask` and everything else with `bid`, padded to the four words QuoterV2
+/// returns.
+pub fn quoter_bytecode(output_selector: &[u8], bid: U256, ask: U256) -> Bytes {
+    let answer = |amount: U256| {
+        let mut code = vec![0x7f]; // PUSH32 amount
+        code.extend_from_slice(&amount.to_be_bytes::<32>());
+        // MSTORE at 0, RETURN 0x80 bytes from 0
+        code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x80, 0x60, 0x00, 0xf3]);
+        code
+    };
+
+    // selector = calldataload(0) >> 224
+    let mut code = vec![0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63];
+    code.extend_from_slice(&output_selector[..4]);
+    // EQ, PUSH1 <ask>, JUMPI
+    code.extend_from_slice(&[0x14, 0x60, 0x00, 0x57]);
+    let jump_arg = code.len() - 2;
+
+    code.extend(answer(bid));
+    code[jump_arg] = code.len() as u8;
+    code.push(0x5b); // JUMPDEST
+    code.extend(answer(ask));
+
+    code.into()
+}
+
+/// The shipped config with the RPC pointed nowhere, unaffected by `.env`.
+pub fn settings() -> Settings {
+    let env = HashMap::from([("RPC_URL".to_string(), "http://mock.invalid".to_string())]);
+    Settings::load_from(&["config/default.toml"], Some(env)).unwrap()
+}
+
+/// The channels an `ArbEngine` reads from and writes to, held by the test.
+pub struct EngineFixture {
+    pub engine: ArbEngine,
+    pub cex_tx: watch::Sender<Option<PriceData>>,
+    pub dex_tx: watch::Sender<Option<PriceData>>,
+    pub trading_tx: watch::Sender<TradingState>,
+    pub risk_rx: broadcast::Receiver<RiskEvent>,
+}
+
+impl EngineFixture {
+    pub fn new(cfg: Settings, rpc: &MockRpc) -> Self {
+        let (cex_tx, cex_rx) = watch::channel(None);
+        let (dex_tx, dex_rx) = watch::channel(None);
+        let (trading_tx, trading) = watch::channel(TradingState::default());
+        let (events, risk_rx) = risk_channel();
+
+        let engine = ArbEngine::new(
+            cfg,
+            "bybit",
+            cex_rx,
+            dex_rx,
+            Arc::new(rpc.provider()),
+            RiskHandle { events, trading, exposure: Default::default(), throttle: Default::default(), audit: Default::default(), markets: Default::default(), switches: Default::default() },
+        );
+
+        Self { engine, cex_tx, dex_tx, trading_tx, risk_rx }
+    }
+
+    pub fn prices(&self, cex: (f64, f64), dex: (f64, f64)) {
+        self.cex_tx.send_replace(Some(PriceData::new(cex.0, cex.1)));
+        self.dex_tx.send_replace(Some(PriceData::new(dex.0, dex.1)));
+    }
+}
+
+/// Accepts one HTTP request, answers 200 and returns its raw headers and
+/// body.
+pub async fn capture_request(listener: TcpListener) -> (String, Vec<u8>) {
+    capture_requests(listener, 1).await.remove(0)
+}
+
+/// The first `n` requests made to `listener`, each answered 200 on the
+/// connection it came on.
+pub async fn capture_requests(listener: TcpListener, n: usize) -> Vec<(String, Vec<u8>)> {
+    let mut requests = Vec::new();
+    while requests.len() < n {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut raw = Vec::new();
+        let mut buf = [0u8; 4096];
+
+        while requests.len() < n {
+            if let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&raw[..end]).to_string();
+                let length: usize = head
+                    .lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap();
+                if raw.len() >= end + 4 + length {
+                    socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+                    requests.push((head, raw[end + 4..end + 4 + length].to_vec()));
+                    raw.drain(..end + 4 + length);
+                    continue;
+                }
+            }
+            let read = socket.read(&mut buf).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            raw.extend_from_slice(&buf[..read]);
+        }
+    }
+    requests
+}
+
+/// Requests `serve_json` was sent, as path and JSON body, in order.
+pub type Served = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Answers every HTTP request to `listener` with the JSON `answer` gives
+/// for its path and body, on any number of connections, until the test
+/// ends. A request without a JSON body is passed `null`.
+pub fn serve_json(listener: TcpListener, answer: impl Fn(&str, &Value) -> Value + Send + Sync + 'static) -> Served {
+    let served = Served::default();
+    let answer = Arc::new(answer);
+    let log = served.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (answer, log) = (answer.clone(), log.clone());
+            tokio::spawn(async move {
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    if let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&raw[..end]).to_string();
+                        let length: usize = head
+                            .lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                            .unwrap_or(0);
+                        if raw.len() >= end + 4 + length {
+                            let path = head.split_whitespace().nth(1).unwrap_or_default().to_string();
+                            let body = serde_json::from_slice(&raw[end + 4..end + 4 + length]).unwrap_or(Value::Null);
+                            let response = answer(&path, &body).to_string();
+                            log.lock().unwrap().push((path, body));
+                            let reply = format!(
+                                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                                response.len(),
+                                response
+                            );
+                            if socket.write_all(reply.as_bytes()).await.is_err() {
+                                return;
+                            }
+                            raw.drain(..end + 4 + length);
+                            continue;
+                        }
+                    }
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => raw.extend_from_slice(&buf[..read]),
+                    }
+                }
+            });
+        }
+    });
+    served
+}
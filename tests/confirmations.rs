@@ -0,0 +1,150 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use alloy::primitives::{Address, B256, Bytes, U256};
+use alloy::signers::local::PrivateKeySigner;
+use tokio::sync::broadcast;
+
+use rust_arb_bot::execution::confirmations::{ConfirmationTracker, DropReason, REORG_HORIZON_BLOCKS, TxState, TxUpdate};
+use rust_arb_bot::execution::sender::{PendingTx, TxSender};
+
+use common::{MockRpc, settings};
+
+fn tracker(rpc: &MockRpc, inclusion_timeout_secs: u64) -> (ConfirmationTracker, broadcast::Receiver<TxUpdate>) {
+    let mut cfg = settings();
+    cfg.confirmation_depth = 2;
+    cfg.inclusion_timeout_secs = inclusion_timeout_secs;
+    let sender = TxSender::on_client(rpc.client(), PrivateKeySigner::random(), Address::ZERO);
+    ConfirmationTracker::new(&cfg, Arc::new(sender))
+}
+
+fn pending(byte: u8, nonce: u64) -> PendingTx {
+    PendingTx {
+        hash: B256::repeat_byte(byte),
+        nonce,
+        to: Address::ZERO,
+        calldata: Bytes::new(),
+        value: U256::ZERO,
+        gas_limit: 21_000,
+        max_fee_per_gas: 1,
+        max_priority_fee_per_gas: 1,
+        sent_at: Instant::now(),
+    }
+}
+
+fn states(updates: &mut broadcast::Receiver<TxUpdate>) -> Vec<(B256, TxState)> {
+    std::iter::from_fn(|| updates.try_recv().ok()).map(|u| (u.hash, u.state)).collect()
+}
+
+#[tokio::test]
+async fn confirmed_transactions_are_verified_again_after_a_reorg() {
+    let rpc = MockRpc::new();
+    let (mut tracker, mut updates) = tracker(&rpc, 10);
+    let tx = pending(1, 0);
+    tracker.track(tx.clone());
+
+    rpc.include(tx.hash, 1000);
+    tracker.poll().await.unwrap();
+    rpc.set_head(1001, common::now());
+    tracker.poll().await.unwrap();
+    let confirmed = TxState::Confirmed { block_number: 1000, success: true, gas_used: 21_000, effective_gas_price: 1 };
+    assert!(matches!(states(&mut updates).as_slice(), [
+        (_, TxState::Pending),
+        (_, TxState::Included { block_number: 1000, confirmations: 1, .. }),
+        (_, state),
+    ] if *state == confirmed));
+
+    // still followed while a reorg could undo it
+    rpc.set_head(1010, common::now());
+    tracker.poll().await.unwrap();
+    assert!(tracker.is_tracked(tx.hash));
+
+    rpc.uninclude(tx.hash);
+    tracker.on_reorg(999);
+    tracker.poll().await.unwrap();
+    assert_eq!(states(&mut updates), vec![(tx.hash, TxState::Pending)]);
+
+    rpc.include(tx.hash, 1011);
+    rpc.set_head(1012, common::now());
+    tracker.poll().await.unwrap();
+    assert!(matches!(states(&mut updates).as_slice(), [(_, TxState::Confirmed { block_number: 1011, .. })]));
+
+    rpc.set_head(1011 + REORG_HORIZON_BLOCKS, common::now());
+    tracker.poll().await.unwrap();
+    assert!(!tracker.is_tracked(tx.hash));
+}
+
+#[tokio::test]
+async fn a_transaction_whose_nonce_landed_in_another_is_superseded() {
+    let rpc = MockRpc::new();
+    let (mut tracker, mut updates) = tracker(&rpc, 10);
+    let (stuck, landed) = (pending(1, 0), pending(2, 0));
+    tracker.track(stuck.clone());
+    tracker.track(landed.clone());
+    states(&mut updates);
+
+    rpc.include(landed.hash, 1000);
+    tracker.poll().await.unwrap();
+
+    let updates = states(&mut updates);
+    assert!(updates.contains(&(stuck.hash, TxState::Dropped { reason: DropReason::Superseded { by: landed.hash } })));
+    assert!(!tracker.is_tracked(stuck.hash));
+    assert!(tracker.is_tracked(landed.hash));
+}
+
+#[tokio::test]
+async fn a_timed_out_transaction_the_node_forgot_is_evicted() {
+    let rpc = MockRpc::new();
+    let (mut tracker, mut updates) = tracker(&rpc, 0);
+    let tx = pending(1, 0);
+    tracker.track(tx.clone());
+    states(&mut updates);
+
+    tracker.poll().await.unwrap();
+
+    assert_eq!(states(&mut updates), vec![(tx.hash, TxState::Dropped { reason: DropReason::Evicted })]);
+    assert!(!tracker.is_tracked(tx.hash));
+}
+
+#[tokio::test]
+async fn a_transaction_out_of_replacements_is_given_up_and_cancelled() {
+    let rpc = MockRpc::new();
+    let mut cfg = settings();
+    cfg.inclusion_timeout_secs = 0;
+    cfg.max_replacements = 0;
+    let sender = Arc::new(TxSender::on_client(rpc.client(), PrivateKeySigner::random(), Address::ZERO));
+    let (mut tracker, mut updates) = ConfirmationTracker::new(&cfg, sender.clone());
+    let tx = pending(1, 0);
+    rpc.hold(tx.hash);
+    tracker.track(tx.clone());
+    states(&mut updates);
+
+    tracker.poll().await.unwrap();
+
+    // the cancellation is an empty self-transfer under the same nonce
+    let cancel = *rpc.sent().last().unwrap();
+    assert_ne!(cancel, tx.hash);
+    let updates: Vec<_> = std::iter::from_fn(|| updates.try_recv().ok()).collect();
+    assert!(updates.iter().any(|u| u.hash == cancel && u.to == sender.address() && u.nonce == 0 && u.state == TxState::Pending));
+    assert!(updates.iter().any(|u| u.hash == tx.hash && u.state == TxState::Dropped { reason: DropReason::GaveUp }));
+    assert!(tracker.is_tracked(cancel));
+}
+
+#[tokio::test]
+async fn handles_feed_the_tracker_and_share_its_updates() {
+    let rpc = MockRpc::new();
+    let (tracker, _) = tracker(&rpc, 10);
+    let (shared, mut updates) = broadcast::channel(16);
+    let tracker = tracker.with_updates(shared);
+    let handle = tracker.handle();
+    let (_risk_tx, risk_rx) = broadcast::channel(1);
+    tokio::spawn(tracker.run(risk_rx));
+
+    let tx = pending(1, 0);
+    handle.track(tx.clone()).unwrap();
+
+    let update = updates.recv().await.unwrap();
+    assert_eq!((update.hash, update.state), (tx.hash, TxState::Pending));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::{Address, Bytes, address};
+use alloy::sol_types::SolCall;
+
+use rust_arb_bot::dex::profile::{expected_selectors, missing_functions, pushed_selectors, verify_interface, verify_interfaces};
+use rust_arb_bot::execution::split::PoolRoute;
+use rust_arb_bot::helpers::abi::{quoteExactInputSingleCall, quoteExactOutputSingleCall};
+use rust_arb_bot::settings::{QuoterKind, Settings};
+
+use common::{MockRpc, settings};
+
+const HYPERSWAP_QUOTER: Address = address!("0x03A918028f22D9E1473B7959C927AD7425A45C7C");
+const HYPERSWAP_ROUTER: Address = address!("0x4E2960a8cd19B467b82d26D83fAcb0fAE26b094D");
+
+fn load(files: &[&str], vars: &[(&str, &str)]) -> anyhow::Result<Settings> {
+    let env: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    Settings::load_from(files, Some(env))
+}
+
+// a dispatcher comparing the selector against each of `selectors`
+fn dispatcher(selectors: &[[u8; 4]]) -> Bytes {
+    let mut code = vec![0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c];
+    for selector in selectors {
+        // DUP1, PUSH4 selector, EQ
+        code.extend_from_slice(&[0x80, 0x63]);
+        code.extend_from_slice(selector);
+        code.push(0x14);
+    }
+    code.push(0x00);
+    code.into()
+}
+
+#[test]
+fn shipped_config_resolves_the_hyperswap_profile() {
+    let cfg = settings();
+
+    assert_eq!(cfg.dex_profile.as_deref(), Some("hyperswap"));
+    assert_eq!(cfg.quoter_v2_addr, HYPERSWAP_QUOTER);
+    assert_eq!(cfg.router_addr, HYPERSWAP_ROUTER);
+    assert_eq!(cfg.quoter_kind, QuoterKind::V2);
+}
+
+#[test]
+fn explicit_addresses_override_the_profile() {
+    let router = "0x3333333333333333333333333333333333333333";
+    let cfg = load(&["config/default.toml"], &[("ROUTER_ADDR", router)]).unwrap();
+
+    assert_eq!(cfg.router_addr, router.parse::<Address>().unwrap());
+    assert_eq!(cfg.quoter_v2_addr, HYPERSWAP_QUOTER);
+}
+
+#[test]
+fn unknown_profile_is_rejected() {
+    let err = load(&["config/default.toml"], &[("DEX_PROFILE", "nope")]).unwrap_err();
+
+    assert!(format!("{:#}", err).contains("unknown dex_profile \"nope\", known: hyperswap"), "{:#}", err);
+}
+
+#[test]
+fn extra_pools_take_the_quoter_and_router_of_their_profile() {
+    let cfg = load(&["config/default.toml", "tests/fixtures/settings/dex_profile.toml"], &[]).unwrap();
+    let pool = &cfg.extra_pools[0];
+
+    assert_eq!(pool.quoter_addr, Some(address!("0x1111111111111111111111111111111111111111")));
+    assert_eq!(pool.quoter_kind, QuoterKind::V1);
+    assert_eq!(pool.router_addr, Some(address!("0x2222222222222222222222222222222222222222")));
+    // the pair itself stays on hyperswap
+    assert_eq!(cfg.quoter_v2_addr, HYPERSWAP_QUOTER);
+    assert_eq!(cfg.router_addr, HYPERSWAP_ROUTER);
+
+    let routes = PoolRoute::all(&cfg);
+    assert_eq!(routes[0].router_addr, HYPERSWAP_ROUTER);
+    assert_eq!(routes[1].router_addr, address!("0x2222222222222222222222222222222222222222"));
+}
+
+#[test]
+fn selectors_in_push_data_do_not_count() {
+    let selector = quoteExactInputSingleCall::SELECTOR;
+    // PUSH5 carrying the selector, then PUSH3 of a selector with a zero
+    // first byte as the optimizer emits it
+    let mut code = vec![0x64, 0xff];
+    code.extend_from_slice(&selector);
+    code.extend_from_slice(&[0x62, 0xab, 0xcd, 0xef]);
+
+    assert_eq!(pushed_selectors(&code), vec![[0x00, 0xab, 0xcd, 0xef]]);
+    assert_eq!(missing_functions(&code, &expected_selectors(QuoterKind::V2)), vec!["quoteExactInputSingle", "quoteExactOutputSingle"]);
+}
+
+#[tokio::test]
+async fn verifies_the_deployed_interfaces() {
+    let cfg = settings();
+    let v2 = dispatcher(&[quoteExactInputSingleCall::SELECTOR, quoteExactOutputSingleCall::SELECTOR]);
+    let rpc = MockRpc::new().with_code(cfg.quoter_v2_addr, v2.clone());
+
+    verify_interface(&rpc.provider(), cfg.quoter_v2_addr, &expected_selectors(QuoterKind::V2)).await.unwrap();
+    let err = verify_interface(&rpc.provider(), cfg.quoter_v2_addr, &expected_selectors(QuoterKind::V1)).await.unwrap_err();
+    assert!(err.to_string().contains("does not dispatch quoteExactInputSingle, quoteExactOutputSingle"), "{}", err);
+
+    // the router is not deployed
+    let err = verify_interfaces(&cfg, &rpc.provider()).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("router_addr failed verification: no contract at"), "{:#}", err);
+}
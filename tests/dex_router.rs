@@ -0,0 +1,117 @@
+mod common;
+
+use alloy::primitives::{Address, U256, address};
+use alloy::sol_types::{SolCall, SolValue};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use rust_arb_bot::arbitrage::ArbDirection;
+use rust_arb_bot::dex::router::{DexRouter, UniswapV2, router_for};
+use rust_arb_bot::execution::dex::DexSwap;
+use rust_arb_bot::helpers::abi::{IUniswapV2Router, ONE_ETHER};
+use rust_arb_bot::settings::{DexKind, Settings};
+
+use common::{MockRpc, settings};
+
+const RECIPIENT: Address = address!("0x00000000000000000000000000000000000000aa");
+
+// answers every request with `body`, handing back the request line
+async fn swap_api(body: serde_json::Value) -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = vec![0u8; 4096];
+        let n = socket.read(&mut request).await.unwrap();
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request[..n]).lines().next().unwrap_or_default().to_string()
+    });
+    (url, server)
+}
+
+fn aggregator(url: &str) -> Settings {
+    Settings { dex_kind: DexKind::Aggregator, aggregator_url: Some(url.to_string()), ..settings() }
+}
+
+#[tokio::test]
+async fn v2_quotes_along_the_direct_path() {
+    let cfg = Settings { dex_kind: DexKind::V2, ..settings() };
+    let amounts = vec![ONE_ETHER, U256::from(41_500_000)];
+    let rpc = MockRpc::new().with_call(cfg.router_addr, amounts.abi_encode().into());
+    let router = router_for(&cfg).unwrap();
+
+    // the last amount is what selling yields, the first what buying costs
+    assert_eq!(router.quote(&rpc.provider(), &ArbDirection::BuyCex, ONE_ETHER).await.unwrap(), 41_500_000);
+    assert_eq!(
+        router.quote(&rpc.provider(), &ArbDirection::BuyDex, U256::from(41_500_000)).await.unwrap(),
+        u128::try_from(ONE_ETHER).unwrap()
+    );
+    assert_eq!(router.fee_bps(), 30.0);
+}
+
+#[tokio::test]
+async fn v2_swaps_keep_the_legs_base_amount() {
+    let cfg = settings();
+    let router = UniswapV2::new(&cfg);
+
+    let sell = DexSwap::for_direction(&cfg, &ArbDirection::BuyCex, ONE_ETHER, U256::from(41_500_000));
+    let calldata = router.swap_calldata(&sell, RECIPIENT, U256::from(60)).await.unwrap();
+    let call = IUniswapV2Router::swapExactTokensForTokensCall::abi_decode(&calldata).unwrap();
+    assert_eq!((call.amountIn, call.amountOutMin), (ONE_ETHER, sell.limit));
+    assert_eq!((call.path, call.to), (vec![cfg.weth_addr, cfg.usdt_addr], RECIPIENT));
+
+    let buy = DexSwap::for_direction(&cfg, &ArbDirection::BuyDex, ONE_ETHER, U256::from(41_700_000));
+    let calldata = router.swap_calldata(&buy, RECIPIENT, U256::from(60)).await.unwrap();
+    let call = IUniswapV2Router::swapTokensForExactTokensCall::abi_decode(&calldata).unwrap();
+    assert_eq!((call.amountOut, call.amountInMax), (ONE_ETHER, buy.limit));
+    assert_eq!(call.path, vec![cfg.usdt_addr, cfg.weth_addr]);
+}
+
+#[tokio::test]
+async fn v3_swaps_are_the_single_pool_router_calls() {
+    let cfg = settings();
+    let router = router_for(&cfg).unwrap();
+    let swap = DexSwap::for_direction(&cfg, &ArbDirection::BuyCex, ONE_ETHER, U256::from(41_500_000));
+
+    let calldata = router.swap_calldata(&swap, RECIPIENT, U256::from(60)).await.unwrap();
+    assert_eq!(calldata, swap.calldata(RECIPIENT, U256::from(60)));
+    assert_eq!(router.fee_bps(), cfg.dex_fee_tier as f64 / 100.0);
+}
+
+#[tokio::test]
+async fn aggregator_quotes_through_the_swap_api() {
+    let (url, server) = swap_api(json!({ "sellAmount": "1000000000000000000", "buyAmount": "41500000" })).await;
+    let router = router_for(&aggregator(&url)).unwrap();
+
+    let quoted = router.quote(&MockRpc::new().provider(), &ArbDirection::BuyCex, ONE_ETHER).await.unwrap();
+    assert_eq!(quoted, 41_500_000);
+    let request = server.await.unwrap();
+    assert!(request.starts_with("GET /swap/v1/quote?"), "{}", request);
+    assert!(request.contains("sellAmount=1000000000000000000"), "{}", request);
+}
+
+#[tokio::test]
+async fn aggregator_swaps_must_settle_through_the_router() {
+    let cfg = settings();
+    let swap = DexSwap::for_direction(&cfg, &ArbDirection::BuyCex, ONE_ETHER, U256::from(41_500_000));
+    let quote = |to: Address| {
+        json!({ "buyAmount": "41500000", "sellAmount": "1000000000000000000", "to": to.to_string(), "data": "0xdeadbeef" })
+    };
+
+    let (url, _) = swap_api(quote(cfg.router_addr)).await;
+    let router = router_for(&aggregator(&url)).unwrap();
+    let calldata = router.swap_calldata(&swap, RECIPIENT, U256::ZERO).await.unwrap();
+    assert_eq!(calldata.to_vec(), vec![0xde, 0xad, 0xbe, 0xef]);
+
+    let (url, _) = swap_api(quote(RECIPIENT)).await;
+    let router = router_for(&aggregator(&url)).unwrap();
+    let err = router.swap_calldata(&swap, RECIPIENT, U256::ZERO).await.unwrap_err();
+    assert!(err.to_string().contains("not router_addr"), "{}", err);
+}
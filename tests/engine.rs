@@ -0,0 +1,336 @@
+mod common;
+
+use std::sync::Arc;
+
+use rust_arb_bot::arbitrage::{ArbDirection, ArbEngine, Leg, PriceData, QuoteSource, VenueId, age_weight, arb_profit};
+use rust_arb_bot::borrow::BorrowCost;
+use rust_arb_bot::clock::SimClock;
+use rust_arb_bot::opportunity::Opportunity;
+use rust_arb_bot::risk::{RiskEvent, RiskHandle, TradingState, risk_channel};
+use rust_arb_bot::scanner::{MatrixScanner, best};
+use rust_arb_bot::settings::{BorrowRate, Settings};
+use tokio::sync::watch;
+
+use common::{EngineFixture, MockRpc, settings};
+
+const GWEI: u128 = 1_000_000_000;
+
+#[tokio::test]
+async fn charges_margin_interest_on_the_borrowed_asset() {
+    let mut cfg = settings();
+    cfg.borrow_hold_secs = 7200;
+    cfg.borrow_rates = vec![
+        BorrowRate { venue: "bybit".to_string(), asset: "USDT".to_string(), hourly_bps: 0.5 },
+        BorrowRate { venue: "gateio".to_string(), asset: "HYPE".to_string(), hourly_bps: 9.0 },
+    ];
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(cfg.clone(), &rpc);
+
+    fx.prices((40.0, 40.1), (41.0, 41.2));
+    let found = fx.engine.check_for_opportunity().await.unwrap();
+
+    // buying on bybit borrows USDT for two hours, selling borrows nothing there
+    let buy_cex = arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.1, 41.0, &ArbDirection::BuyCex, GWEI);
+    let interest = 40.1 * 0.5 / 10000.0 * 2.0;
+    assert!((found[0].profit.borrow_cost - interest).abs() < 1e-12);
+    assert!((found[0].profit.net - (buy_cex.net - interest)).abs() < 1e-12);
+    assert_eq!(found[1].profit.borrow_cost, 0.0);
+
+    let gateio = BorrowCost::new(&cfg, "gateio:other");
+    assert_eq!(gateio.cost(&ArbDirection::BuyCex, 1.0, 40.0), 0.0);
+    assert!((gateio.cost(&ArbDirection::BuyDex, 3.0, 40.0) - 3.0 * 40.0 * 9.0 / 10000.0 * 2.0).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn waits_for_both_feeds() {
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(settings(), &rpc);
+
+    assert!(fx.engine.check_for_opportunity().await.unwrap().is_empty());
+
+    fx.cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    assert!(fx.engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(rpc.requests("eth_gasPrice"), 0);
+}
+
+#[tokio::test]
+async fn stays_cold_until_every_feed_delivers_a_valid_price() {
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    // prices restored from a saved state are there before any update
+    let (cex_tx, cex_rx) = watch::channel(Some(PriceData::new(40.0, 40.1)));
+    let (dex_tx, dex_rx) = watch::channel(Some(PriceData::new(41.0, 41.2)));
+    let (_trading_tx, trading) = watch::channel(TradingState::default());
+    let (events, _risk_rx) = risk_channel();
+    let mut engine = ArbEngine::new(
+        settings(),
+        "bybit",
+        cex_rx,
+        dex_rx,
+        Arc::new(rpc.provider()),
+        RiskHandle { events, trading, exposure: Default::default(), throttle: Default::default(), audit: Default::default(), markets: Default::default(), switches: Default::default() },
+    );
+
+    assert!(engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(engine.warmup().unwrap().waiting(), ["cex", "dex"]);
+
+    // a crossed book is no sign of life
+    cex_tx.send_replace(Some(PriceData::new(40.2, 40.1)));
+    dex_tx.send_replace(Some(PriceData::new(41.0, 41.2)));
+    assert!(engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(engine.warmup().unwrap().waiting(), ["cex"]);
+    assert_eq!(rpc.requests("eth_gasPrice"), 0);
+
+    cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    assert!(!engine.check_for_opportunity().await.unwrap().is_empty());
+    assert!(engine.warmup().is_none());
+}
+
+#[tokio::test]
+async fn prices_both_directions_at_the_node_gas_price() {
+    let cfg = settings();
+    let rpc = MockRpc::new().with_gas_price(2 * GWEI);
+    let mut fx = EngineFixture::new(cfg.clone(), &rpc);
+
+    // DEX bids well above the CEX ask
+    fx.prices((40.0, 40.1), (41.0, 41.2));
+    let found = fx.engine.check_for_opportunity().await.unwrap();
+
+    let [
+        Opportunity { direction: ArbDirection::BuyCex, profit: buy_cex, .. },
+        Opportunity { direction: ArbDirection::BuyDex, profit: buy_dex, .. },
+    ] = found.as_slice()
+    else {
+        panic!("expected both directions, got {:?}", found);
+    };
+    assert_eq!(
+        *buy_cex,
+        arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.1, 41.0, &ArbDirection::BuyCex, 2 * GWEI)
+    );
+    assert_eq!(
+        *buy_dex,
+        arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 41.2, 40.0, &ArbDirection::BuyDex, 2 * GWEI)
+    );
+
+    // gas is valued in HYPE at the DEX-side price
+    let gas_hype = (cfg.dex_gas_used as u128 * 2 * GWEI) as f64 / 1e18;
+    assert!((buy_cex.gas_cost - gas_hype * 41.0).abs() < 1e-12);
+    assert!(buy_cex.net > 0.0);
+    assert!(buy_dex.net < 0.0);
+    assert_eq!(rpc.requests("eth_gasPrice"), 1);
+}
+
+#[tokio::test]
+async fn paused_trading_skips_evaluation() {
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(settings(), &rpc);
+
+    fx.trading_tx.send_replace(TradingState { paused: true, ..Default::default() });
+    fx.prices((40.0, 40.1), (41.0, 41.2));
+
+    assert!(fx.engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(rpc.requests("eth_gasPrice"), 0);
+}
+
+#[tokio::test]
+async fn a_book_too_thin_for_the_trade_size_skips_evaluation() {
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(settings(), &rpc);
+
+    fx.prices((40.0, 40.1), (41.0, 41.2));
+    fx.cex_tx.send_replace(Some(PriceData::new(40.0, 40.1).with_thin(true)));
+
+    assert!(fx.engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(rpc.requests("eth_gasPrice"), 0);
+}
+
+#[tokio::test]
+async fn reports_spread_out_of_bounds_once_until_normal() {
+    let cfg = settings();
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(cfg.clone(), &rpc);
+
+    // twice the limit apart
+    let far = 40.0 * (1.0 + 2.0 * cfg.max_spread_bps as f64 / 10000.0);
+    fx.prices((40.0, 40.0), (far, far));
+    fx.engine.check_for_opportunity().await.unwrap();
+    fx.engine.check_for_opportunity().await.unwrap();
+
+    match fx.risk_rx.try_recv().unwrap() {
+        RiskEvent::SpreadOutOfBounds { venue, spread_bps } => {
+            assert_eq!(venue, "bybit");
+            assert!(spread_bps > cfg.max_spread_bps as f64);
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+    assert!(fx.risk_rx.try_recv().is_err());
+
+    fx.prices((40.0, 40.0), (40.1, 40.1));
+    fx.engine.check_for_opportunity().await.unwrap();
+    assert!(matches!(fx.risk_rx.try_recv().unwrap(), RiskEvent::SpreadNormal { venue } if venue == "bybit"));
+}
+
+#[tokio::test]
+async fn bridge_converts_dex_prices_into_the_cex_quote() {
+    let cfg = settings();
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let EngineFixture { engine, cex_tx, dex_tx, .. } = EngineFixture::new(cfg.clone(), &rpc);
+    let (bridge_tx, bridge_rx) = tokio::sync::watch::channel(None);
+    let mut engine = engine.with_dex_bridge(bridge_rx);
+
+    // HYPE/USDC on the DEX, HYPE/USDT on the CEX
+    cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    dex_tx.send_replace(Some(PriceData::new(40.5, 40.6)));
+    assert!(engine.check_for_opportunity().await.unwrap().is_empty());
+
+    // USDC/USDT
+    bridge_tx.send_replace(Some(PriceData::new(0.99, 1.01)));
+    let found = engine.check_for_opportunity().await.unwrap();
+
+    let [
+        Opportunity { direction: ArbDirection::BuyCex, profit: buy_cex, .. },
+        Opportunity { direction: ArbDirection::BuyDex, profit: buy_dex, .. },
+    ] = found.as_slice()
+    else {
+        panic!("expected both directions, got {:?}", found);
+    };
+    assert_eq!(*buy_cex, arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.1, 40.5 * 0.99, &ArbDirection::BuyCex, GWEI));
+    assert_eq!(*buy_dex, arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.6 * 1.01, 40.0, &ArbDirection::BuyDex, GWEI));
+}
+
+#[tokio::test]
+async fn peg_redenominates_profits_in_the_reference() {
+    let cfg = settings();
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let EngineFixture { engine, cex_tx, dex_tx, .. } = EngineFixture::new(cfg.clone(), &rpc);
+    let (peg_tx, peg_rx) = tokio::sync::watch::channel(None);
+    let mut engine = engine.with_quote_peg(peg_rx);
+
+    cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    dex_tx.send_replace(Some(PriceData::new(41.0, 41.2)));
+    assert!(engine.check_for_opportunity().await.unwrap().is_empty());
+
+    // USDC at 1.25 USDT: one USDT is worth 0.8 USD
+    peg_tx.send_replace(Some(PriceData::new(1.25, 1.25)));
+    let found = engine.check_for_opportunity().await.unwrap();
+
+    let in_quote = arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.1, 41.0, &ArbDirection::BuyCex, GWEI);
+    let [Opportunity { direction: ArbDirection::BuyCex, profit: buy_cex, .. }, _] = found.as_slice() else {
+        panic!("expected both directions, got {:?}", found);
+    };
+    assert!((buy_cex.net - in_quote.net * 0.8).abs() < 1e-12);
+    assert!((buy_cex.gross - in_quote.gross * 0.8).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn discounts_edges_by_how_far_apart_the_legs_were_quoted() {
+    assert_eq!(age_weight(3_000, 3_000, 500), 1.0);
+    assert_eq!(age_weight(0, 1_000, 500), 0.25);
+
+    let mut cfg = settings();
+    cfg.quote_age_half_life_ms = Some(500);
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let EngineFixture { engine, cex_tx, dex_tx, .. } = EngineFixture::new(cfg.clone(), &rpc);
+    let clock = Arc::new(SimClock::new(10_000));
+    let mut engine = engine.with_clock(clock.clone());
+    let buy_cex = arb_profit(cfg.cex_fee_bps, cfg.dex_gas_used, 40.1, 41.0, &ArbDirection::BuyCex, GWEI);
+    let cex = |ts_ms| Some(PriceData::new(40.0, 40.1).with_source(Some(QuoteSource::Exchange(ts_ms))));
+    let dex = Some(PriceData::new(41.0, 41.2).with_source(Some(QuoteSource::Block(7))));
+
+    // the CEX book a second older than the block just quoted
+    cex_tx.send_replace(cex(9_000));
+    dex_tx.send_replace(dex.clone());
+    let found = engine.check_for_opportunity().await.unwrap();
+    assert!((found[0].profit.gross - buy_cex.gross * 0.25).abs() < 1e-12);
+    assert!((found[0].profit.net - (buy_cex.net - buy_cex.gross * 0.75)).abs() < 1e-12);
+
+    // a fresh book against block 7 still being the latest, 500ms on
+    clock.set(10_500);
+    cex_tx.send_replace(cex(10_500));
+    dex_tx.send_replace(dex);
+    let found = engine.check_for_opportunity().await.unwrap();
+    assert!((found[0].profit.gross - buy_cex.gross * 0.5).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn monitors_only_while_a_feed_is_silent() {
+    let mut cfg = settings();
+    cfg.feed_stale_secs = 1;
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let mut fx = EngineFixture::new(cfg, &rpc);
+
+    fx.prices((40.0, 40.1), (41.0, 41.2));
+    assert!(!fx.engine.check_for_opportunity().await.unwrap().is_empty());
+
+    // the CEX keeps ticking, the pool price freezes
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    fx.cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    assert!(fx.engine.check_for_opportunity().await.unwrap().is_empty());
+    assert_eq!(fx.engine.degraded().map(|d| d.leg), Some(Leg::Dex));
+    assert_eq!(rpc.requests("eth_gasPrice"), 1);
+
+    fx.dex_tx.send_replace(Some(PriceData::new(41.0, 41.2)));
+    assert!(!fx.engine.check_for_opportunity().await.unwrap().is_empty());
+    assert!(fx.engine.degraded().is_none());
+}
+
+#[tokio::test]
+async fn scanner_reads_gas_once_and_ranks_all_pairs() {
+    let rpc = MockRpc::new().with_gas_price(GWEI);
+    let (bybit_tx, bybit_rx) = watch::channel(None);
+    let (gateio_tx, gateio_rx) = watch::channel(None);
+    let (dex_tx, dex_rx) = watch::channel(None);
+    let (_trading_tx, trading) = watch::channel(TradingState::default());
+    let (events, _risk_rx) = risk_channel();
+
+    let mut scanner = MatrixScanner::new(
+        &settings(),
+        vec![
+            (VenueId::Cex("bybit".to_string()), bybit_rx),
+            (VenueId::Cex("gateio".to_string()), gateio_rx),
+            (VenueId::Dex("hyperswap".to_string()), dex_rx),
+        ],
+        Arc::new(rpc.provider()),
+        RiskHandle { events, trading, exposure: Default::default(), throttle: Default::default(), audit: Default::default(), markets: Default::default(), switches: Default::default() },
+    );
+    // bybit and gateio against the pool, and against each other
+    assert_eq!(scanner.len(), 3);
+    assert!(scanner.scan().await.unwrap().is_empty());
+    assert_eq!(rpc.requests("eth_gasPrice"), 0);
+
+    // gateio is the cheaper place to buy against the pool bid
+    bybit_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    gateio_tx.send_replace(Some(PriceData::new(39.8, 39.9)));
+    dex_tx.send_replace(Some(PriceData::new(41.0, 41.2)));
+    let found = scanner.scan().await.unwrap();
+
+    assert_eq!(rpc.requests("eth_gasPrice"), 1);
+    assert_eq!(found.len(), 6);
+    assert!(found.windows(2).all(|w| w[0].profit.net >= w[1].profit.net));
+    let cross: Vec<_> = found.iter().filter(|c| c.cross_cex).collect();
+    assert_eq!(cross.len(), 2);
+    assert!(cross.iter().all(|c| (c.venue.as_str(), c.dex_venue.as_str()) == ("bybit", "gateio")));
+    let buy_cex = best(&found, &ArbDirection::BuyCex).unwrap();
+    assert_eq!((buy_cex.venue.as_str(), buy_cex.dex_venue.as_str()), ("gateio", "hyperswap"));
+    assert_eq!(best(&found, &ArbDirection::BuyDex).unwrap().venue, "bybit");
+}
+
+#[test]
+fn compares_two_cexes_at_both_taker_fees_and_no_gas() {
+    let rpc = MockRpc::new();
+    let (_bybit_tx, bybit_rx) = watch::channel(None);
+    let (_gateio_tx, gateio_rx) = watch::channel(None);
+    let (_trading_tx, trading) = watch::channel(TradingState::default());
+    let (events, _risk_rx) = risk_channel();
+    let risk = RiskHandle { events, trading, exposure: Default::default(), throttle: Default::default(), audit: Default::default(), markets: Default::default(), switches: Default::default() };
+    let feeds = vec![(VenueId::Cex("bybit".to_string()), bybit_rx), (VenueId::Cex("gateio".to_string()), gateio_rx)];
+
+    let cfg = settings();
+    let engines = ArbEngine::across(&cfg, &feeds, Arc::new(rpc.provider()), risk.clone());
+    assert_eq!(engines.len(), 1);
+    assert_eq!((engines[0].venue.as_str(), &engines[0].counter), ("bybit", &VenueId::Cex("gateio".to_string())));
+    assert_eq!((engines[0].config.cex_fee_bps, engines[0].config.dex_gas_used), (cfg.cex_fee_bps * 2, 0));
+    assert!(engines[0].sizer().is_none());
+
+    let cfg = Settings { scan_cross_cex: false, ..settings() };
+    assert!(ArbEngine::across(&cfg, &feeds, Arc::new(rpc.provider()), risk).is_empty());
+}
@@ -0,0 +1,575 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolValue;
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_arb_bot::adapters::bybit::market_meta;
+use rust_arb_bot::arbitrage::{ArbDirection, ArbProfit, Leg};
+use rust_arb_bot::execution::cex::{CexFill, CexHoldings, CexOrders, OrderPolicy};
+use rust_arb_bot::execution::client_order::ClientOrderId;
+use rust_arb_bot::execution::confirmations::ConfirmationTracker;
+use rust_arb_bot::execution::margin::{MarginAccount, MarginLoan};
+use rust_arb_bot::execution::executor::{ArbExecutor, Execution, cex_orders, check_gas_ceiling, expected_net};
+use rust_arb_bot::execution::hyperliquid::HyperliquidClient;
+use rust_arb_bot::execution::sender::TxSender;
+use rust_arb_bot::execution::short::{ShortBook, ShortSeller};
+use rust_arb_bot::execution::split::PoolRoute;
+use rust_arb_bot::execution::{bybit, gateio};
+use rust_arb_bot::helpers::abi::quote_exact_output_calldata_for;
+use rust_arb_bot::markets::Markets;
+use rust_arb_bot::opportunity::OpportunityId;
+use rust_arb_bot::risk::{RiskHandle, TradingState, risk_channel};
+use rust_arb_bot::scanner::{Candidate, DEFAULT_PAIR};
+use rust_arb_bot::settings::{BorrowRate, ExecutionMode, PoolConfig, Secret, Settings, StrategyKind, VenueTrading};
+use rust_arb_bot::sizing::OptimalSize;
+use rust_arb_bot::switches::TradingSwitches;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use common::{MockRpc, Served, quoter_bytecode, serve_json, settings};
+
+#[derive(Default)]
+struct FakeOrders {
+    orders: Mutex<Vec<(bool, f64, f64)>>,
+    holdings: CexHoldings,
+}
+
+#[async_trait]
+impl CexOrders for FakeOrders {
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+
+    async fn market_order(&self, is_buy: bool, base: f64, price: f64, _id: ClientOrderId, _policy: &OrderPolicy) -> Result<CexFill> {
+        self.orders.lock().unwrap().push((is_buy, base, price));
+        Ok(CexFill { base, price })
+    }
+
+    async fn holdings(&self) -> Result<CexHoldings> {
+        Ok(self.holdings)
+    }
+}
+
+// loans per asset
+#[derive(Default)]
+struct FakeMargin {
+    loans: Mutex<BTreeMap<String, f64>>,
+}
+
+#[async_trait]
+impl MarginAccount for FakeMargin {
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+
+    async fn borrow(&self, asset: &str, amount: f64) -> Result<()> {
+        *self.loans.lock().unwrap().entry(asset.to_string()).or_default() += amount;
+        Ok(())
+    }
+
+    async fn repay(&self, asset: &str, amount: f64) -> Result<()> {
+        *self.loans.lock().unwrap().entry(asset.to_string()).or_default() -= amount;
+        Ok(())
+    }
+
+    async fn loan(&self, asset: &str) -> Result<MarginLoan> {
+        let borrowed = self.loans.lock().unwrap().get(asset).copied().unwrap_or_default();
+        Ok(MarginLoan { borrowed, hourly_rate: 0.0 })
+    }
+}
+
+fn risk(paused: bool) -> (RiskHandle, watch::Sender<TradingState>) {
+    let (events, _) = risk_channel();
+    let (trading_tx, trading) = watch::channel(TradingState { paused, ..Default::default() });
+    let meta = market_meta(&json!({
+        "symbol": "HYPEUSDT",
+        "priceFilter": { "tickSize": "0.001" },
+        "lotSizeFilter": { "basePrecision": "0.01", "minOrderQty": "0.1", "minOrderAmt": "5" }
+    }))
+    .unwrap();
+    let markets = Markets::new(BTreeMap::from([("bybit".to_string(), meta)]));
+    let risk = RiskHandle {
+        events,
+        trading,
+        exposure: Default::default(),
+        throttle: Default::default(),
+        audit: Default::default(),
+        markets,
+        switches: Default::default(),
+    };
+    (risk, trading_tx)
+}
+
+// HyperCore holding no spot inventory, with $1000 of free perp margin,
+// filling every IOC order in full at 40.1
+fn hyperliquid_api(listener: TcpListener) -> Served {
+    serve_json(listener, |path, body| match (path, body["type"].as_str()) {
+        ("/info", Some("spotMeta")) => json!({
+            "universe": [{ "name": "@107", "tokens": [150, 0], "index": 107 }],
+            "tokens": [{ "name": "USDC", "szDecimals": 8, "index": 0 }, { "name": "HYPE", "szDecimals": 2, "index": 150 }]
+        }),
+        ("/info", Some("spotClearinghouseState")) => json!({ "balances": [] }),
+        ("/info", Some("clearinghouseState")) => {
+            json!({ "marginSummary": { "accountValue": "1000" }, "withdrawable": "1000" })
+        }
+        ("/exchange", _) => {
+            let sz = body["action"]["orders"][0]["s"].clone();
+            let statuses = json!([{ "filled": { "totalSz": sz, "avgPx": "40.1", "oid": 1 } }]);
+            json!({ "status": "ok", "response": { "type": "order", "data": { "statuses": statuses } } })
+        }
+        _ => Value::Null,
+    })
+}
+
+// live on hyperliquid, every exchange API served from `url`
+fn hyperliquid_settings(url: &str) -> Settings {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Live;
+    cfg.hyperliquid_api_url = url.to_string();
+    cfg.bybit_rest_endpoint = url.to_string();
+    cfg.gateio_rest_endpoint = url.to_string();
+    cfg.hyperliquid_private_key = Some(Secret::new(PrivateKeySigner::random().to_bytes().to_string()));
+    cfg
+}
+
+// the orders `served` placed, as asset, whether a buy, price, size and
+// whether reduce-only
+fn placed_orders(served: &Served) -> Vec<(u64, bool, String, String, bool)> {
+    let served = served.lock().unwrap();
+    let orders = served.iter().filter(|(path, _)| path == "/exchange").map(|(_, body)| &body["action"]["orders"][0]);
+    orders
+        .map(|o| (o["a"].as_u64().unwrap(), o["b"] == true, o["p"].as_str().unwrap().to_string(), o["s"].as_str().unwrap().to_string(), o["r"] == true))
+        .collect()
+}
+
+fn new_executor(cfg: &Settings, risk: RiskHandle) -> ArbExecutor {
+    ArbExecutor::new(cfg, Arc::new(MockRpc::new().provider()), risk).unwrap()
+}
+
+// buying 1 HYPE on bybit at 40 nets `net` per unit before 0.5 of gas
+fn candidate(net: f64) -> Candidate {
+    Candidate {
+        pair: DEFAULT_PAIR.to_string(),
+        venue: "bybit".to_string(),
+        dex_venue: "hyperswap".to_string(),
+        id: OpportunityId::new(),
+        direction: ArbDirection::BuyCex,
+        profit: ArbProfit { gross: net + 0.5, cex_fee: 0.0, gas_cost: 0.5, borrow_cost: 0.0, net },
+        cex_price: 40.0,
+        dex_price: 40.0 + net + 0.5,
+        book: "cex_dex/default".to_string(),
+        size: None,
+        cross_cex: false,
+        closing: false,
+    }
+}
+
+#[test]
+fn counts_gas_once_at_any_size() {
+    let profit = ArbProfit { gross: 1.2, cex_fee: 0.2, gas_cost: 0.5, borrow_cost: 0.0, net: 0.5 };
+    assert_eq!(expected_net(&profit, 1.0), 0.5);
+    assert_eq!(expected_net(&profit, 3.0), 2.5);
+}
+
+#[tokio::test]
+async fn dry_run_only_takes_opportunities_above_the_threshold() {
+    let cfg = settings();
+    assert_eq!(cfg.execution_mode, ExecutionMode::Log);
+    let (risk, _trading) = risk(false);
+    let executor = new_executor(&cfg, risk);
+
+    assert_eq!(executor.execute(&candidate(cfg.exec_min_profit / 2.0)).await, Execution::BelowThreshold);
+    assert_eq!(executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await, Execution::DryRun);
+    // closing a position goes out at a loss too
+    let mut closing = candidate(-cfg.exec_min_profit);
+    closing.closing = true;
+    assert_eq!(executor.execute(&closing).await, Execution::DryRun);
+
+    let mut other_pair = candidate(cfg.exec_min_profit * 2.0);
+    other_pair.pair = "PURR/USDC".to_string();
+    assert!(matches!(executor.execute(&other_pair).await, Execution::Skipped(reason) if reason.contains("only the default pair")));
+}
+
+#[tokio::test]
+async fn a_sized_candidate_goes_by_its_own_net() {
+    let cfg = settings();
+    let (risk, _trading) = risk(false);
+    let executor = new_executor(&cfg, risk);
+
+    let mut sized = candidate(cfg.exec_min_profit * 2.0);
+    sized.size = Some(OptimalSize { size: 5.0, net: cfg.exec_min_profit / 2.0, cex_price: 40.1, dex_price: 40.3 });
+    assert_eq!(executor.execute(&sized).await, Execution::BelowThreshold);
+
+    sized.size = Some(OptimalSize { size: 5.0, net: cfg.exec_min_profit * 3.0, cex_price: 40.1, dex_price: 40.3 });
+    assert_eq!(executor.execute(&sized).await, Execution::DryRun);
+}
+
+#[tokio::test]
+async fn paper_fills_move_virtual_balances() {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Paper;
+    cfg.exec_base_size = 1.0;
+    cfg.cex_fee_bps = 10;
+    cfg.paper_base_balance = 1.5;
+    cfg.paper_quote_balance = 100.0;
+    let (risk, _trading) = risk(false);
+    let executor = new_executor(&cfg, risk);
+
+    // bought at 40 on bybit, sold into the pool, less 0.04 of fee and 0.5 of gas
+    let net = cfg.exec_min_profit * 2.0;
+    let Execution::Paper(fill) = executor.execute(&candidate(net)).await else {
+        panic!("not filled on paper");
+    };
+    assert!((fill.pnl - (net - 0.04)).abs() < 1e-9);
+    assert_eq!(fill.cumulative_pnl, fill.pnl);
+    let bybit = executor.paper().balance("bybit");
+    assert_eq!(bybit.base, 2.5);
+    assert!((bybit.quote - (100.0 - 40.04)).abs() < 1e-9);
+    assert_eq!(executor.paper().balance("hyperswap").base, 0.5);
+
+    // the pool side holds too little base for another
+    let execution = executor.execute(&candidate(net)).await;
+    assert!(matches!(execution, Execution::Skipped(reason) if reason.contains("paper balance on hyperswap")));
+    assert_eq!(executor.paper().totals(), (1, fill.pnl));
+}
+
+#[test]
+fn gas_ceilings_bound_price_and_cost() {
+    let mut cfg = settings();
+    cfg.dex_gas_used = 200_000;
+    let gwei = 1_000_000_000u128;
+    assert!(check_gas_ceiling(&cfg, 500 * gwei, 40.0).is_ok());
+
+    cfg.max_gas_price_gwei = Some(10.0);
+    assert!(check_gas_ceiling(&cfg, 10 * gwei, 40.0).is_ok());
+    assert!(check_gas_ceiling(&cfg, 11 * gwei, 40.0).unwrap_err().to_string().contains("max_gas_price_gwei"));
+
+    // 200k gas at 10 gwei is 0.002 of a $40 token
+    cfg.max_gas_cost_usd = Some(0.05);
+    assert!(check_gas_ceiling(&cfg, 10 * gwei, 40.0).unwrap_err().to_string().contains("max_gas_cost_usd"));
+    assert!(check_gas_ceiling(&cfg, 5 * gwei, 40.0).is_ok());
+}
+
+#[tokio::test]
+async fn skips_when_gas_spiked_since_the_quote() {
+    let mut cfg = settings();
+    cfg.max_gas_price_gwei = Some(10.0);
+    let (risk, _trading) = risk(false);
+    let rpc = MockRpc::new().with_gas_price(50_000_000_000);
+    let executor = ArbExecutor::new(&cfg, Arc::new(rpc.provider()), risk).unwrap();
+
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Skipped(reason) if reason.contains("gas price 50.00 gwei")));
+}
+
+#[tokio::test]
+async fn refuses_while_trading_is_paused() {
+    let cfg = settings();
+    let (risk, _trading) = risk(true);
+    let executor = new_executor(&cfg, risk);
+
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Skipped(reason) if reason.contains("paused")));
+}
+
+#[tokio::test]
+async fn switched_off_venues_and_directions_are_not_executed() {
+    let cfg = settings();
+    let (mut risk, _trading) = risk(false);
+    risk.switches = TradingSwitches::new(vec![
+        VenueTrading { venue: "gateio".to_string(), directions: vec![] },
+        VenueTrading { venue: "*".to_string(), directions: vec![ArbDirection::BuyCex] },
+    ]);
+    let executor = new_executor(&cfg, risk);
+
+    assert_eq!(executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await, Execution::DryRun);
+
+    let mut buy_dex = candidate(cfg.exec_min_profit * 2.0);
+    buy_dex.direction = ArbDirection::BuyDex;
+    assert!(matches!(executor.execute(&buy_dex).await, Execution::Skipped(reason) if reason.contains("BuyDex execution on bybit is switched off")));
+
+    let mut gateio = candidate(cfg.exec_min_profit * 2.0);
+    gateio.venue = "gateio".to_string();
+    assert!(matches!(executor.execute(&gateio).await, Execution::Skipped(reason) if reason.contains("switched off")));
+}
+
+#[tokio::test]
+async fn live_needs_an_order_client_and_a_wallet() {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Live;
+    let (risk, _trading) = risk(false);
+    let executor = new_executor(&cfg, risk.clone());
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Skipped(reason) if reason.contains("no order client for bybit")));
+
+    // the CEX leg goes out alongside the DEX leg, rounded onto the grid,
+    // and is sold back once the swap cannot go out
+    let orders = Arc::new(FakeOrders::default());
+    let executor = new_executor(&cfg, risk).with_venue(orders.clone());
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Unwound(reason) if reason.contains("dex leg: no wallet")));
+    assert_eq!(*orders.orders.lock().unwrap(), vec![(true, cfg.exec_base_size, 40.0), (false, cfg.exec_base_size, 40.0)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_split_leg_that_does_not_go_out_is_unwound_on_the_cex_alone() {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Live;
+    cfg.confirmation_depth = 1;
+    cfg.split_ladder_steps = 2;
+    let second_router = Address::repeat_byte(0x22);
+    cfg.extra_pools = vec![PoolConfig {
+        pool_addr: Address::repeat_byte(0x11),
+        fee_tier: 500,
+        quoter_addr: None,
+        quoter_kind: cfg.quoter_kind,
+        router_addr: Some(second_router),
+        dex_profile: None,
+    }];
+    // both pools quote the same, so each takes half; the second one's swap
+    // fails to go out after the first was sent
+    let output_selector =
+        quote_exact_output_calldata_for(cfg.quoter_kind, cfg.usdt_addr, cfg.weth_addr, U256::from(1), 500, cfg.self_addr);
+    let rpc = MockRpc::new()
+        .with_code(cfg.quoter_v2_addr, quoter_bytecode(&output_selector, U256::from(40_000_000), U256::from(40_000_000)))
+        .with_rejected(second_router);
+
+    let sender = Arc::new(TxSender::on_client(rpc.client(), PrivateKeySigner::random(), cfg.router_addr));
+    let (tracker, _) = ConfirmationTracker::new(&cfg, sender.clone());
+    let handle = tracker.handle();
+    let (risk, _trading) = risk(false);
+    tokio::spawn(tracker.run(risk.events.subscribe()));
+    // every swap lands as soon as it is sent
+    let chain = rpc.clone();
+    tokio::spawn(async move {
+        loop {
+            chain.sent().into_iter().for_each(|hash| chain.include(hash, 1000));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+
+    let orders = Arc::new(FakeOrders::default());
+    let executor = ArbExecutor::new(&cfg, Arc::new(rpc.provider()), risk)
+        .unwrap()
+        .with_venue(orders.clone())
+        .with_sender(sender, handle);
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+
+    assert!(matches!(execution, Execution::Unwound(reason) if reason.contains("filled 50%")));
+    assert_eq!(rpc.sent().len(), 1);
+    // only the half the DEX did not fill is sold back
+    assert_eq!(*orders.orders.lock().unwrap(), vec![(true, 1.0, 40.0), (false, 0.5, 40.0)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn trades_hypercore_spot_on_its_own_market_rules() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let served = hyperliquid_api(listener);
+    let mut cfg = hyperliquid_settings(&url);
+    cfg.confirmation_depth = 1;
+    cfg.exec_base_size = 1.234567;
+
+    let (mut risk, _trading) = risk(false);
+    risk.markets = Markets::fetch(&cfg).await;
+    let meta = risk.markets.get("hyperliquid").unwrap();
+    assert_eq!((meta.lot_size, meta.qty_precision, meta.price_precision), (0.01, 2, 6));
+
+    // the pool's quoter takes the base for 49 USDT
+    let quoted = (U256::from(49_000_000), U256::ZERO, 0u32, U256::ZERO).abi_encode_params();
+    let rpc = MockRpc::new().with_call(PoolRoute::all(&cfg)[0].quoter_addr, quoted.into());
+    let sender = Arc::new(TxSender::on_client(rpc.client(), PrivateKeySigner::random(), cfg.router_addr));
+    let (tracker, _) = ConfirmationTracker::new(&cfg, sender.clone());
+    let handle = tracker.handle();
+    tokio::spawn(tracker.run(risk.events.subscribe()));
+    let chain = rpc.clone();
+    tokio::spawn(async move {
+        loop {
+            chain.sent().into_iter().for_each(|hash| chain.include(hash, 1000));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+
+    let spot = cex_orders(&cfg, "hyperliquid").unwrap();
+    let executor = ArbExecutor::new(&cfg, Arc::new(rpc.provider()), risk).unwrap().with_venue(spot).with_sender(sender, handle);
+    let mut candidate = candidate(cfg.exec_min_profit * 2.0);
+    candidate.venue = "hyperliquid".to_string();
+    candidate.cex_price = 40.12345;
+    let execution = executor.execute(&candidate).await;
+
+    assert!(matches!(&execution, Execution::Sent { account, fill, .. } if account == "hyperliquid" && *fill == CexFill { base: 1.23, price: 40.1 }));
+    assert_eq!(rpc.sent().len(), 1);
+    // 40.123 on the 5 significant figures, through 20 bps of slippage
+    assert_eq!(placed_orders(&served), vec![(10107, true, "40.204".to_string(), "1.23".to_string(), false)]);
+}
+
+#[tokio::test]
+async fn hypercore_orders_go_out_in_whole_size_steps() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let served = hyperliquid_api(listener);
+    let cfg = hyperliquid_settings(&url);
+    let spot = cex_orders(&cfg, "hyperliquid").unwrap();
+    let policy = OrderPolicy::from_config(&cfg);
+
+    // as a golden-section size comes out
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    assert_eq!(spot.market_order(false, 1.2345678912, 40.0, id, &policy).await.unwrap(), CexFill { base: 1.23, price: 40.1 });
+    assert!(spot.market_order(false, 0.009, 40.0, id.unwind(), &policy).await.unwrap_err().to_string().contains("rounds to nothing"));
+    assert_eq!(placed_orders(&served), vec![(10107, false, "39.92".to_string(), "1.23".to_string(), false)]);
+    // szDecimals are looked up once
+    let spot_metas = served.lock().unwrap().iter().filter(|(_, body)| body["type"] == "spotMeta").count();
+    assert_eq!(spot_metas, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_buy_dex_beyond_the_hypercore_inventory_sells_short() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let served = hyperliquid_api(listener);
+    let mut cfg = hyperliquid_settings(&url);
+    cfg.confirmation_depth = 1;
+    cfg.short_selling = true;
+
+    let (mut risk, _trading) = risk(false);
+    risk.markets = Markets::fetch(&cfg).await;
+    let book = ShortBook::default();
+    let seller = || ShortSeller::new(cfg.clone(), HyperliquidClient::new(&cfg).unwrap(), book.clone());
+    let spot = cex_orders(&cfg, "hyperliquid").unwrap();
+    let mut buy_dex = candidate(cfg.exec_min_profit * 2.0);
+    buy_dex.venue = "hyperliquid".to_string();
+    buy_dex.direction = ArbDirection::BuyDex;
+
+    // the pool's quoter asks 40 USDT for the base
+    let quoted = (U256::from(40_000_000), U256::ZERO, 0u32, U256::ZERO).abi_encode_params();
+    let rpc = MockRpc::new().with_call(PoolRoute::all(&cfg)[0].quoter_addr, quoted.into());
+    let sender = Arc::new(TxSender::on_client(rpc.client(), PrivateKeySigner::random(), cfg.router_addr));
+    let (tracker, _) = ConfirmationTracker::new(&cfg, sender.clone());
+    let handle = tracker.handle();
+    tokio::spawn(tracker.run(risk.events.subscribe()));
+    let chain = rpc.clone();
+    tokio::spawn(async move {
+        loop {
+            chain.sent().into_iter().for_each(|hash| chain.include(hash, 1000));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+
+    // no spot base is held, so the perp is sold and stays short until its
+    // hold time once the swap landed
+    let executor = ArbExecutor::new(&cfg, Arc::new(rpc.provider()), risk.clone())
+        .unwrap()
+        .with_venue(spot.clone())
+        .with_short_seller(seller())
+        .with_sender(sender, handle);
+    let execution = executor.execute(&buy_dex).await;
+    assert!(matches!(&execution, Execution::Sent { account, fill, .. } if account == "hyperliquid" && *fill == CexFill { base: 1.0, price: 40.1 }));
+    assert_eq!(book.total_size(), 1.0);
+
+    // a swap that cannot go out has the short bought back right away
+    let executor = new_executor(&cfg, risk).with_venue(spot).with_short_seller(seller());
+    buy_dex.id = OpportunityId::new();
+    let execution = executor.execute(&buy_dex).await;
+    assert!(matches!(execution, Execution::Unwound(reason) if reason.contains("dex leg: no wallet")));
+    assert_eq!(book.all().len(), 1);
+
+    let perp = cfg.oracle_perp_index as u64;
+    let short = (perp, false, "39.92".to_string(), "1".to_string(), false);
+    let cover = (perp, true, "40.181".to_string(), "1".to_string(), true);
+    assert_eq!(placed_orders(&served), vec![short.clone(), short, cover]);
+}
+
+#[tokio::test]
+async fn latency_arb_hedges_only_once_its_lead_leg_filled() {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Live;
+    cfg.strategy = StrategyKind::LatencyArb;
+    let (risk, _trading) = risk(false);
+    let orders = Arc::new(FakeOrders::default());
+    let executor = new_executor(&cfg, risk).with_venue(orders.clone());
+
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Skipped(reason) if reason.contains("lead leg failed, not hedged")));
+    assert!(orders.orders.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn legs_borrow_what_the_account_lacks_and_repay_from_what_comes_in() {
+    let mut cfg = settings();
+    cfg.execution_mode = ExecutionMode::Live;
+    cfg.borrow_rates = ["USDT", "HYPE"]
+        .map(|asset| BorrowRate { venue: "bybit".to_string(), asset: asset.to_string(), hourly_bps: 1.0 })
+        .to_vec();
+    let (risk, _trading) = risk(false);
+    let holdings = CexHoldings { base: 5.0, quote: 20.0, bids_quote: 5.0, ..Default::default() };
+    let orders = Arc::new(FakeOrders { holdings, ..Default::default() });
+    let margin = Arc::new(FakeMargin::default());
+    let executor = new_executor(&cfg, risk).with_venue(orders.clone()).with_margin(margin.clone());
+
+    let execution = executor.execute(&candidate(cfg.exec_min_profit * 2.0)).await;
+    assert!(matches!(execution, Execution::Unwound(_)));
+    // the buy lacked 25 of its 40 USDT, the unwinding sell paid back the
+    // 15 free; base was never short
+    assert_eq!(cfg.exec_base_size, 1.0);
+    assert_eq!(*margin.loans.lock().unwrap(), BTreeMap::from([("USDT".to_string(), 10.0)]));
+}
+
+#[test]
+fn unwinds_under_an_id_of_its_own() {
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    assert_eq!(id.unwind(), id.unwind());
+    assert_ne!(id.unwind(), id);
+    assert_ne!(id.unwind(), ClientOrderId::new(OpportunityId::new(), Leg::Cex).unwind());
+}
+
+#[test]
+fn reads_fills_of_done_orders() {
+    let working = json!({ "orderLinkId": "a", "orderStatus": "New", "cumExecQty": "0", "avgPrice": "" });
+    assert_eq!(bybit::order_fill(&working).unwrap(), None);
+    let filled = json!({ "orderLinkId": "a", "orderStatus": "Filled", "cumExecQty": "1.5", "avgPrice": "40.1" });
+    assert_eq!(bybit::order_fill(&filled).unwrap(), Some(CexFill { base: 1.5, price: 40.1 }));
+    let rejected = json!({ "orderLinkId": "a", "orderStatus": "Rejected", "cumExecQty": "0", "avgPrice": "" });
+    assert!(bybit::order_fill(&rejected).is_err());
+
+    let order = json!({ "text": "t-a", "finish_as": "filled", "filled_total": "80.2", "avg_deal_price": "40.1" });
+    assert_eq!(gateio::order_fill(&order).unwrap(), CexFill { base: 2.0, price: 40.1 });
+
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let text = id.gateio_text();
+    let trades = json!([
+        { "text": text, "amount": "1", "price": "40" },
+        { "text": "t-other", "amount": "5", "price": "39" },
+        { "text": text, "amount": "3", "price": "40.4" },
+    ]);
+    assert_eq!(gateio::trades_fill(&trades, id).unwrap(), Some(CexFill { base: 4.0, price: 40.3 }));
+    assert_eq!(gateio::trades_fill(&json!([]), id).unwrap(), None);
+}
+
+#[test]
+fn reads_pair_holdings_and_open_orders() {
+    let wallet = json!({ "list": [{ "coin": [
+        { "coin": "HYPE", "walletBalance": "12.5" },
+        { "coin": "BTC", "walletBalance": "1" },
+    ] }] });
+    let orders = json!({ "list": [
+        { "side": "Buy", "qty": "2", "cumExecQty": "0.5", "price": "40" },
+        { "side": "Sell", "qty": "1", "cumExecQty": "0", "price": "42" },
+    ] });
+    let holdings = bybit::holdings(&wallet, &orders, "HYPE", "USDT").unwrap();
+    assert_eq!(holdings, CexHoldings { base: 12.5, quote: 0.0, bids: 1.5, asks: 1.0, bids_quote: 60.0, asks_quote: 42.0 });
+
+    let accounts = json!([
+        { "currency": "HYPE", "available": "3", "locked": "1" },
+        { "currency": "USDT", "available": "100", "locked": "0" },
+    ]);
+    let orders = json!([{ "side": "sell", "left": "1", "price": "41" }]);
+    let holdings = gateio::holdings(&accounts, &orders, "HYPE", "USDT").unwrap();
+    assert_eq!(holdings, CexHoldings { base: 4.0, quote: 100.0, asks: 1.0, asks_quote: 41.0, ..Default::default() });
+}
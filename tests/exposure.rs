@@ -0,0 +1,88 @@
+use alloy::primitives::TxHash;
+use rust_arb_bot::execution::cex::CexHoldings;
+use rust_arb_bot::exposure::{AssetExposure, Exposure, ExposureSource};
+use rust_arb_bot::inventory::{AssetBalance, InFlightTransfer, TransferKind};
+use rust_arb_bot::settings::ExposureLimit;
+
+fn limit(max_net: f64, max_gross: f64) -> ExposureLimit {
+    ExposureLimit { asset: "base".to_string(), max_net: Some(max_net), max_gross: Some(max_gross) }
+}
+
+#[test]
+fn counts_balances_orders_and_in_flight_legs_together() {
+    let exposure = Exposure::new(vec![limit(100.0, 150.0)]);
+    exposure.set_balance(ExposureSource::OnChain, "base", 60.0);
+    exposure.set_balance(ExposureSource::Cex, "base", -20.0);
+    exposure.set_orders("base", 10.0, 5.0);
+    exposure.begin_leg(TxHash::ZERO, &[("base", 15.0), ("quote", -600.0)]);
+    assert_eq!(exposure.exposure("base"), AssetExposure { net: 60.0, gross: 110.0 });
+
+    // within the net limit but over the gross one
+    assert!(exposure.check(&[("base", 30.0)]).is_ok());
+    assert!(exposure.check(&[("base", 45.0)]).is_err());
+    // other assets have no limit
+    assert!(exposure.check(&[("quote", -1e9)]).is_ok());
+
+    exposure.end_leg(TxHash::ZERO);
+    assert_eq!(exposure.exposure("base"), AssetExposure { net: 45.0, gross: 95.0 });
+}
+
+#[test]
+fn lets_trades_back_under_a_breached_limit() {
+    let exposure = Exposure::new(vec![limit(50.0, 1000.0)]);
+    exposure.set_balance(ExposureSource::OnChain, "base", 80.0);
+
+    assert!(exposure.check(&[("base", 1.0)]).is_err());
+    assert!(exposure.check(&[("base", -10.0)]).is_ok());
+    assert!(Exposure::default().check(&[("base", 1e9)]).is_ok());
+}
+
+#[test]
+fn transfers_count_toward_equity_but_are_not_tradable() {
+    let exposure = Exposure::new(vec![limit(100.0, 1000.0)]);
+    exposure.set_balance(ExposureSource::OnChain, "base", 60.0);
+    exposure.set_orders("base", 5.0, 0.0);
+    exposure.begin_transfer(InFlightTransfer {
+        id: "w1".to_string(),
+        kind: TransferKind::Withdrawal,
+        asset: "base".to_string(),
+        amount: 30.0,
+        from: "bybit".to_string(),
+        to: "hyperevm".to_string(),
+        started_ms: 0,
+    });
+
+    let balance = exposure.balance("base");
+    assert_eq!(balance, AssetBalance { tradable: 60.0, in_flight: 30.0 });
+    assert_eq!(balance.equity(), 90.0);
+    assert_eq!(exposure.balance("quote"), AssetBalance::default());
+    // the limit sees the withdrawal before it lands
+    assert!(exposure.check(&[("base", 10.0)]).is_err());
+
+    assert_eq!(exposure.end_transfer("w1").map(|t| t.amount), Some(30.0));
+    assert!(exposure.transfers().is_empty());
+    assert!(exposure.check(&[("base", 10.0)]).is_ok());
+}
+
+#[test]
+fn cex_accounts_are_counted_apart_and_replaced_legs_keep_counting() {
+    let exposure = Exposure::new(vec![limit(100.0, 1000.0)]);
+    exposure.set_balance(ExposureSource::Cex, "base", 10.0);
+    exposure.set_cex("bybit", &CexHoldings { base: 20.0, quote: 800.0, bids: 2.0, asks: 1.0, bids_quote: 80.0, asks_quote: 41.0 });
+    exposure.set_cex("sub", &CexHoldings { base: 5.0, ..Default::default() });
+    assert_eq!(exposure.balance("base"), AssetBalance { tradable: 35.0, in_flight: 0.0 });
+    assert_eq!(exposure.exposure("base"), AssetExposure { net: 36.0, gross: 38.0 });
+    assert_eq!(exposure.exposure("quote"), AssetExposure { net: 761.0, gross: 921.0 });
+
+    // a later read of one account replaces only its own holdings
+    exposure.set_cex("bybit", &CexHoldings::default());
+    assert_eq!(exposure.exposure("base"), AssetExposure { net: 15.0, gross: 15.0 });
+
+    let (sent, replacement) = (TxHash::repeat_byte(1), TxHash::repeat_byte(2));
+    exposure.begin_leg(sent, &[("base", 4.0)]);
+    exposure.rekey_leg(sent, replacement);
+    assert_eq!(exposure.in_flight(), vec![replacement]);
+    assert_eq!(exposure.exposure("base").net, 19.0);
+    exposure.end_leg(replacement);
+    assert!(exposure.in_flight().is_empty());
+}
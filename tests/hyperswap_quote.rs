@@ -0,0 +1,148 @@
+mod common;
+
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Bytes, U256},
+    sol_types::{SolCall, SolValue},
+};
+use tokio::sync::watch;
+
+use rust_arb_bot::adapters::hyperswap::{fetch_quote, fetch_quote_revm};
+use rust_arb_bot::arbitrage::{PriceData, QuoteSource};
+use rust_arb_bot::helpers::abi::{IMulticall3, ONE_ETHER, quote_exact_output_calldata_for};
+use rust_arb_bot::helpers::revm::{BlockTracker, init_cache_db};
+use rust_arb_bot::risk::{RiskEvent, risk_channel};
+use rust_arb_bot::settings::Settings;
+
+use common::{MockRpc, head, now, quoter_bytecode, settings};
+
+// USDT has 6 decimals
+const BID: u64 = 41_500_000;
+const ASK: u64 = 41_700_000;
+
+fn quoting_rpc(cfg: &Settings) -> MockRpc {
+    let output_selector = quote_exact_output_calldata_for(
+        cfg.quoter_kind,
+        cfg.usdt_addr,
+        cfg.weth_addr,
+        ONE_ETHER,
+        cfg.dex_fee_tier,
+        cfg.self_addr,
+    );
+
+    MockRpc::new()
+        .with_code(cfg.quoter_v2_addr, quoter_bytecode(&output_selector, U256::from(BID), U256::from(ASK)))
+        .with_storage(cfg.pool_addr, U256::ZERO, U256::from(1) << 96)
+}
+
+// the quoted book, dated to the block it was quoted on
+fn quoted_at(block: u64) -> PriceData {
+    PriceData::new(41.5, 41.7).with_source(Some(QuoteSource::Block(block)))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn revm_quote_publishes_bid_and_ask() {
+    let cfg = settings();
+    let rpc = quoting_rpc(&cfg);
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let mut tracker = BlockTracker::new(vec![cfg.pool_addr]).with_max_lag(cfg.max_chain_lag_secs);
+    let (price_tx, price_rx) = watch::channel(None);
+
+    fetch_quote_revm(&cfg, provider, &price_tx, &mut cache_db, &mut tracker, None).await.unwrap();
+
+    assert_eq!(*price_rx.borrow(), Some(quoted_at(1000)));
+    assert_eq!(tracker.block_number, Some(1000));
+    assert_eq!(cache_db.cache.accounts[&cfg.pool_addr].storage[&U256::ZERO], U256::from(1) << 96);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lagging_chain_withdraws_the_price() {
+    let cfg = settings();
+    let rpc = quoting_rpc(&cfg);
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let (risk_tx, mut risk_rx) = risk_channel();
+    let mut tracker = BlockTracker::new(vec![cfg.pool_addr])
+        .with_max_lag(cfg.max_chain_lag_secs)
+        .with_risk_events(risk_tx);
+    let (price_tx, price_rx) = watch::channel(None);
+
+    fetch_quote_revm(&cfg, provider.clone(), &price_tx, &mut cache_db, &mut tracker, None).await.unwrap();
+    assert!(price_rx.borrow().is_some());
+
+    // the next head is far older than the lag limit
+    rpc.set_head(1001, now() - 10 * cfg.max_chain_lag_secs);
+    fetch_quote_revm(&cfg, provider, &price_tx, &mut cache_db, &mut tracker, None).await.unwrap();
+
+    assert!(price_rx.borrow().is_none());
+    assert!(tracker.is_lagging());
+    assert!(matches!(risk_rx.try_recv().unwrap(), RiskEvent::ChainLag { head: 1001, .. }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn announced_head_is_quoted_without_asking_the_node() {
+    let cfg = settings();
+    let rpc = quoting_rpc(&cfg);
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let mut tracker = BlockTracker::new(vec![cfg.pool_addr]);
+    let (price_tx, price_rx) = watch::channel(None);
+
+    tracker.announce(head(1001, now()));
+    fetch_quote_revm(&cfg, provider.clone(), &price_tx, &mut cache_db, &mut tracker, None).await.unwrap();
+
+    assert_eq!(*price_rx.borrow(), Some(quoted_at(1001)));
+    assert_eq!(tracker.block_number, Some(1001));
+    assert_eq!(rpc.requests("eth_getBlockByNumber"), 0);
+
+    // without an announced head the node is asked again
+    tracker.unannounce();
+    rpc.set_head(1002, now());
+    fetch_quote_revm(&cfg, provider, &price_tx, &mut cache_db, &mut tracker, None).await.unwrap();
+    assert_eq!(rpc.requests("eth_getBlockByNumber"), 1);
+    assert_eq!(tracker.block_number, Some(1002));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn quote_off_the_oracle_is_rejected() {
+    let cfg = settings();
+    let rpc = quoting_rpc(&cfg);
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let mut tracker = BlockTracker::new(vec![cfg.pool_addr]);
+    let (price_tx, price_rx) = watch::channel(Some(PriceData::new(41.0, 41.0)));
+
+    let near = PriceData::new(41.6, 41.6);
+    fetch_quote_revm(&cfg, provider.clone(), &price_tx, &mut cache_db, &mut tracker, Some(&near)).await.unwrap();
+    assert_eq!(*price_rx.borrow(), Some(quoted_at(1000)));
+
+    let far = PriceData::new(30.0, 30.0);
+    let result = fetch_quote_revm(&cfg, provider, &price_tx, &mut cache_db, &mut tracker, Some(&far)).await;
+    assert!(result.is_err());
+    assert!(price_rx.borrow().is_none());
+}
+
+#[tokio::test]
+async fn rpc_quote_decodes_the_multicall_batch() {
+    let cfg = settings();
+
+    // QuoterV2 returns (amount, sqrtPriceX96After, ticksCrossed, gasEstimate)
+    let quote = |amount: u64| (U256::from(amount), U256::ZERO, 0u32, U256::ZERO).abi_encode_params();
+    let results = vec![
+        IMulticall3::Result { success: true, returnData: Bytes::from(quote(BID)) },
+        IMulticall3::Result { success: true, returnData: Bytes::from(quote(ASK)) },
+        IMulticall3::Result { success: true, returnData: Bytes::from(U256::from(100_000_000u64).abi_encode()) },
+    ];
+    let rpc = MockRpc::new().with_call(
+        cfg.multicall3_addr,
+        IMulticall3::aggregate3Call::abi_encode_returns(&results).into(),
+    );
+    let (price_tx, price_rx) = watch::channel(None);
+
+    fetch_quote(&cfg, &Arc::new(rpc.provider()), &price_tx).await.unwrap();
+
+    assert_eq!(*price_rx.borrow(), Some(PriceData::new(41.5, 41.7)));
+    assert_eq!(rpc.requests("eth_call"), 1);
+}
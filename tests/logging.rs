@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use log::{Level, LevelFilter, Record};
+use rust_arb_bot::control::{Control, run_control_socket, send_command};
+use rust_arb_bot::logging::{self, RotatingFile, json_line, parse_filter};
+use rust_arb_bot::settings::{LogRotation, Settings};
+use serde_json::Value;
+
+const HOUR_MS: u64 = 3_600_000;
+
+fn temp_log(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arb-logging-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join("bot.jsonl")
+}
+
+fn lines(path: &PathBuf) -> Vec<String> {
+    fs::read_to_string(path).unwrap().lines().map(str::to_string).collect()
+}
+
+#[test]
+fn formats_records_as_json() {
+    let line = json_line(
+        &Record::builder()
+            .level(Level::Warn)
+            .target("rust_arb_bot::risk")
+            .args(format_args!("paused: {}", "spread"))
+            .build(),
+        1_752_000_000_000,
+    );
+
+    let json: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(json["level"], "WARN");
+    assert_eq!(json["target"], "rust_arb_bot::risk");
+    assert_eq!(json["msg"], "paused: spread");
+    assert_eq!(json["ts_ms"], 1_752_000_000_000u64);
+}
+
+#[test]
+fn rotates_by_size_keeping_the_newest_files() {
+    let path = temp_log("size");
+    let mut file = RotatingFile::open(&path, LogRotation::Never, 20, 2).unwrap();
+
+    // 10 bytes per line with the newline: two fit in a file
+    for i in 0..7 {
+        file.write_line(&format!("line {:04}", i), 0).unwrap();
+    }
+
+    assert_eq!(lines(&path), ["line 0006"]);
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    assert_eq!(lines(&numbered(1)), ["line 0004", "line 0005"]);
+    assert_eq!(lines(&numbered(2)), ["line 0002", "line 0003"]);
+    assert!(!numbered(3).exists());
+}
+
+#[test]
+fn rotates_when_the_period_ends() {
+    let path = temp_log("period");
+    let mut file = RotatingFile::open(&path, LogRotation::Hourly, 0, 3).unwrap();
+
+    file.write_line("first", 10 * HOUR_MS).unwrap();
+    file.write_line("same hour", 10 * HOUR_MS + 1000).unwrap();
+    file.write_line("next hour", 11 * HOUR_MS).unwrap();
+
+    assert_eq!(lines(&path), ["next hour"]);
+    assert_eq!(lines(&PathBuf::from(format!("{}.1", path.display()))), ["first", "same hour"]);
+}
+
+#[test]
+fn rejects_malformed_filters() {
+    let filter = parse_filter("info,rust_arb_bot::adapters::hyperswap=warn").unwrap();
+    assert_eq!(filter.filter(), LevelFilter::Info);
+
+    assert!(parse_filter("rust_arb_bot=loud").is_err());
+    assert!(parse_filter("a/b/c").is_err());
+}
+
+// the only test installing the process logger
+#[tokio::test]
+async fn changes_the_filter_over_the_control_socket() {
+    let env = HashMap::from([("RPC_URL".to_string(), "http://mock.invalid".to_string())]);
+    let cfg = Settings::load_from(&["config/default.toml"], Some(env)).unwrap();
+    logging::init(&cfg).unwrap();
+
+    let path = std::env::temp_dir().join(format!("arb-control-{}.sock", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    tokio::spawn(run_control_socket(path.clone(), Control::default()));
+    while !std::path::Path::new(&path).exists() {
+        tokio::task::yield_now().await;
+    }
+
+    let initial = send_command(&path, "log").await.unwrap();
+    assert_eq!(Some(initial), logging::current_filter());
+
+    let reply = send_command(&path, "log info,rust_arb_bot::adapters::hyperswap=warn").await.unwrap();
+    assert_eq!(reply, "info,rust_arb_bot::adapters::hyperswap=warn");
+    assert_eq!(log::max_level(), LevelFilter::Info);
+    assert!(log::log_enabled!(target: "rust_arb_bot::arbitrage", Level::Info));
+    assert!(!log::log_enabled!(target: "rust_arb_bot::adapters::hyperswap", Level::Info));
+
+    // a bad filter leaves the current one in place
+    assert!(send_command(&path, "log rust_arb_bot=loud").await.is_err());
+    assert!(send_command(&path, "restart").await.is_err());
+    assert_eq!(send_command(&path, "log").await.unwrap(), "info,rust_arb_bot::adapters::hyperswap=warn");
+}
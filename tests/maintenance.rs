@@ -0,0 +1,79 @@
+mod common;
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+
+use alloy::primitives::B256;
+use tokio::sync::mpsc;
+
+use rust_arb_bot::adapters::hyperswap::PoolCommand;
+use rust_arb_bot::control::{Control, execute};
+use rust_arb_bot::lifecycle::{Failure, drain, exit_code};
+use rust_arb_bot::maintenance::{Maintenance, MaintenancePhase};
+use rust_arb_bot::risk::{RiskEvent, risk_channel};
+use rust_arb_bot::state::{StateStore, WarmState};
+
+use common::{MockRpc, settings};
+
+#[tokio::test]
+async fn winds_down_and_back_up_on_command() {
+    let path = std::env::temp_dir().join(format!("rust-arb-bot-maintenance-{}.json", std::process::id()));
+    let mut cfg = settings();
+    cfg.state_file = Some(path.display().to_string());
+
+    let rpc = MockRpc::new().with_receipt(B256::repeat_byte(1));
+    let store = StateStore::new(WarmState { in_flight: vec![B256::repeat_byte(1)], ..Default::default() });
+    let (risk_tx, mut risk_rx) = risk_channel();
+    let (pool_tx, mut pool_rx) = mpsc::unbounded_channel();
+
+    let (maintenance, mut handle) =
+        Maintenance::new(&cfg, risk_tx, Arc::new(rpc.provider()), store.clone()).unwrap();
+    tokio::spawn(maintenance.with_pool_commands(pool_tx).run());
+    let control = Control { maintenance: Some(handle.clone()), ..Default::default() };
+
+    assert_eq!(execute("maintenance", &control).await, "ok off");
+    assert!(execute("maintenance on", &control).await.starts_with("ok"));
+    handle.wait_for(MaintenancePhase::Idle).await.unwrap();
+
+    // executions stopped first, then the landed tx was settled and saved
+    assert_eq!(risk_rx.recv().await.unwrap(), RiskEvent::Maintenance { active: true });
+    assert!(store.snapshot().in_flight.is_empty());
+    assert!(WarmState::load(&path).unwrap().unwrap().in_flight.is_empty());
+    assert!(matches!(pool_rx.recv().await, Some(PoolCommand::Idle(true))));
+    assert_eq!(execute("maintenance", &control).await, "ok idle");
+
+    assert_eq!(execute("maintenance off", &control).await, "ok idle");
+    handle.wait_for(MaintenancePhase::Off).await.unwrap();
+    assert!(matches!(pool_rx.recv().await, Some(PoolCommand::Idle(false))));
+    assert_eq!(risk_rx.recv().await.unwrap(), RiskEvent::Maintenance { active: false });
+    assert!(execute("maintenance later", &control).await.starts_with("error:"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn drains_on_shutdown_within_the_deadline() {
+    let (risk_tx, _risk_rx) = risk_channel();
+    let rpc = MockRpc::new();
+    let (maintenance, handle) =
+        Maintenance::new(&settings(), risk_tx.clone(), Arc::new(rpc.provider()), StateStore::default()).unwrap();
+    tokio::spawn(maintenance.run());
+    drain(handle.clone(), Duration::from_secs(5)).await.unwrap();
+    assert_eq!(handle.phase(), MaintenancePhase::Idle);
+
+    // nothing runs this one, so it never settles
+    let (_stuck, handle) = Maintenance::new(&settings(), risk_tx, Arc::new(rpc.provider()), StateStore::default()).unwrap();
+    let err = drain(handle, Duration::from_millis(50)).await.unwrap_err();
+    assert_eq!(exit_code(&err), ExitCode::from(75));
+}
+
+#[test]
+fn exit_codes_follow_the_failure_class() {
+    let config = Err::<(), _>(anyhow!("missing field `rpc_url`")).context(Failure::Config).context("loading settings");
+    assert_eq!(exit_code(&config.unwrap_err()), ExitCode::from(78));
+    assert_eq!(exit_code(&anyhow!(Failure::Crashed)), ExitCode::from(70));
+    assert_eq!(exit_code(&anyhow!("unclassified")), ExitCode::from(1));
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_arb_bot::execution::margin::{MarginAccount, MarginLoan};
+use rust_arb_bot::execution::{bybit, gateio};
+use rust_arb_bot::inventory::{borrow_shortfall, repay_loan};
+
+#[derive(Default)]
+struct FakeMargin {
+    borrowed: Mutex<f64>,
+}
+
+#[async_trait]
+impl MarginAccount for FakeMargin {
+    fn venue(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn borrow(&self, _asset: &str, amount: f64) -> Result<()> {
+        *self.borrowed.lock().unwrap() += amount;
+        Ok(())
+    }
+
+    async fn repay(&self, _asset: &str, amount: f64) -> Result<()> {
+        *self.borrowed.lock().unwrap() -= amount;
+        Ok(())
+    }
+
+    async fn loan(&self, _asset: &str) -> Result<MarginLoan> {
+        Ok(MarginLoan { borrowed: *self.borrowed.lock().unwrap(), hourly_rate: 0.00001 })
+    }
+}
+
+#[tokio::test]
+async fn borrows_the_shortfall_and_repays_from_free_balance() {
+    let account = FakeMargin::default();
+
+    assert_eq!(borrow_shortfall(&account, "HYPE", 5.0, 8.0).await.unwrap(), 0.0);
+    assert_eq!(borrow_shortfall(&account, "HYPE", 5.0, 2.0).await.unwrap(), 3.0);
+    assert_eq!(*account.borrowed.lock().unwrap(), 3.0);
+
+    // never more than is owed, nor more than is free
+    assert_eq!(repay_loan(&account, "HYPE", 1.0).await.unwrap(), 1.0);
+    assert_eq!(repay_loan(&account, "HYPE", 10.0).await.unwrap(), 2.0);
+    assert_eq!(repay_loan(&account, "HYPE", 10.0).await.unwrap(), 0.0);
+}
+
+#[test]
+fn signs_requests_the_way_the_venues_verify_them() {
+    assert_eq!(
+        bybit::sign("secret", "1700000000000key5000coin=USDT"),
+        "fe3c4f237285af8dd8bcbe0738020dbe08146bc4c24f3253cd9ef251df7962f0"
+    );
+    assert_eq!(
+        gateio::sign("secret", "GET", "/api/v4/margin/uni/loans", "currency=USDT", "", "1700000000"),
+        "9556e970ec86560d63a2cdcdc56e26d891feba24ade4f06c12e6965f2e581d7631ca881fa888798c607168cdda5c3b6d48ea77c93b872ff09d2bfb206b779e29"
+    );
+}
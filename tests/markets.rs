@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use rust_arb_bot::adapters::{bybit, gateio, hyperliquid};
+use rust_arb_bot::markets::Markets;
+use serde_json::json;
+
+#[test]
+fn parses_bybit_and_gateio_metadata() {
+    let bybit = bybit::market_meta(&json!({
+        "symbol": "HYPEUSDT",
+        "priceFilter": { "tickSize": "0.001" },
+        "lotSizeFilter": { "basePrecision": "0.01", "minOrderQty": "0.1", "minOrderAmt": "5" }
+    }))
+    .unwrap();
+    assert_eq!((bybit.tick_size, bybit.lot_size, bybit.min_qty, bybit.min_notional), (0.001, 0.01, 0.1, 5.0));
+    assert_eq!((bybit.price_precision, bybit.qty_precision), (3, 2));
+
+    let gateio = gateio::market_meta(&json!({
+        "id": "HYPE_USDT",
+        "precision": 4,
+        "amount_precision": 2,
+        "min_quote_amount": "3"
+    }))
+    .unwrap();
+    assert_eq!((gateio.tick_size, gateio.lot_size, gateio.min_qty, gateio.min_notional), (0.0001, 0.01, 0.0, 3.0));
+}
+
+#[test]
+fn rounds_orders_onto_the_grid_and_enforces_minimums() {
+    let meta = bybit::market_meta(&json!({
+        "symbol": "HYPEUSDT",
+        "priceFilter": { "tickSize": "0.001" },
+        "lotSizeFilter": { "basePrecision": "0.01", "minOrderQty": "0.1", "minOrderAmt": "5" }
+    }))
+    .unwrap();
+    let markets = Markets::new(BTreeMap::from([("bybit".to_string(), meta)]));
+
+    assert_eq!(markets.round_order("bybit", true, 40.12345, 1.239).unwrap(), (40.123, 1.23));
+    assert_eq!(markets.round_order("bybit", false, 40.12345, 1.239).unwrap(), (40.124, 1.23));
+    assert_eq!(markets.round_order("bybit", true, 40.1, 0.5).unwrap(), (40.1, 0.5));
+
+    assert!(markets.round_order("bybit", true, 40.0, 0.05).unwrap_err().to_string().contains("minimum of 0.1"));
+    assert!(markets.round_order("bybit", true, 40.0, 0.12).unwrap_err().to_string().contains("notional"));
+    assert!(markets.round_order("gateio", true, 40.0, 1.0).is_err());
+}
+
+#[test]
+fn rounds_hypercore_prices_to_five_significant_figures() {
+    let spot_meta = json!({
+        "universe": [{ "name": "@107", "tokens": [150, 0], "index": 107 }],
+        "tokens": [{ "name": "USDC", "szDecimals": 8, "index": 0 }, { "name": "HYPE", "szDecimals": 2, "index": 150 }]
+    });
+    let meta = hyperliquid::market_meta(&spot_meta, 107).unwrap();
+    assert_eq!((meta.symbol.as_str(), meta.lot_size, meta.min_notional), ("@107", 0.01, 10.0));
+    assert!(hyperliquid::market_meta(&spot_meta, 108).is_err());
+
+    let markets = Markets::new(BTreeMap::from([("hyperliquid".to_string(), meta)]));
+    assert_eq!(markets.round_order("hyperliquid", true, 40.12345, 1.239).unwrap(), (40.123, 1.23));
+    assert_eq!(markets.round_order("hyperliquid", false, 40.12345, 1.239).unwrap(), (40.124, 1.23));
+    // the tick grows with the price, down to the 6 decimals left by szDecimals
+    assert_eq!(markets.round_order("hyperliquid", true, 123_456.7, 0.5).unwrap(), (123_450.0, 0.5));
+    assert_eq!(markets.round_order("hyperliquid", true, 0.012345678, 1000.0).unwrap(), (0.012345, 1000.0));
+    assert!(markets.round_order("hyperliquid", true, 40.0, 0.2).unwrap_err().to_string().contains("notional"));
+}
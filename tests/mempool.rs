@@ -0,0 +1,106 @@
+mod common;
+
+use alloy::primitives::{Address, B256, U256};
+use rust_arb_bot::adapters::mempool::{PendingSwap, PendingSwaps, decode_pending_swap, predict_price};
+use rust_arb_bot::arbitrage::PriceData;
+use rust_arb_bot::dex::univ3_math::PoolPrice;
+use rust_arb_bot::helpers::abi::{exact_input_single_calldata, exact_output_single_calldata};
+
+use common::settings;
+
+// price 1.0 in raw units
+fn pool() -> PoolPrice {
+    PoolPrice { sqrt_price_x96: U256::from(1) << 96usize, liquidity: 1_000_000_000_000_000_000_000_000 }
+}
+
+#[test]
+fn decodes_router_swaps_of_the_pair_only() {
+    let cfg = settings();
+    let amount = U256::from(10).pow(U256::from(18));
+
+    let sell = exact_input_single_calldata(
+        cfg.weth_addr,
+        cfg.usdt_addr,
+        cfg.dex_fee_tier,
+        Address::ZERO,
+        U256::MAX,
+        amount,
+        U256::ZERO,
+    );
+    assert_eq!(
+        decode_pending_swap(&cfg, &sell),
+        Some(PendingSwap { zero_for_one: cfg.weth_addr < cfg.usdt_addr, exact_input: true, amount })
+    );
+
+    let buy = exact_output_single_calldata(
+        cfg.usdt_addr,
+        cfg.weth_addr,
+        cfg.dex_fee_tier,
+        Address::ZERO,
+        U256::MAX,
+        amount,
+        U256::MAX,
+    );
+    assert!(matches!(decode_pending_swap(&cfg, &buy), Some(PendingSwap { exact_input: false, .. })));
+
+    // another fee tier is another pool
+    let other_pool =
+        exact_input_single_calldata(cfg.weth_addr, cfg.usdt_addr, 500, Address::ZERO, U256::MAX, amount, U256::ZERO);
+    assert_eq!(decode_pending_swap(&cfg, &other_pool), None);
+    assert_eq!(decode_pending_swap(&cfg, &[0xde, 0xad, 0xbe, 0xef]), None);
+}
+
+#[test]
+fn swaps_move_the_pool_price_the_right_way() {
+    let pool = pool();
+    let amount = U256::from(10).pow(U256::from(21));
+
+    // token0 in makes token0 cheaper, token1 in dearer
+    assert!(pool.after_swap(true, true, amount, 3000).price() < pool.price());
+    assert!(pool.after_swap(false, true, amount, 3000).price() > pool.price());
+
+    // exact output of token1 needs about as much token0 as the fee-free input
+    let out = pool.after_swap(true, false, amount, 3000);
+    let back = pool.after_swap(true, true, amount, 0);
+    assert!((out.price() / back.price() - 1.0).abs() < 0.01);
+
+    // 1000 of 1e24 liquidity moves the price by about 2 * 0.1%
+    let moved = pool.after_swap(false, true, amount, 0).price();
+    assert!((moved - 1.002001).abs() < 1e-6);
+}
+
+#[test]
+fn predicted_price_follows_the_base_token() {
+    let dex = PriceData::new(40.0, 40.1);
+    let before = pool();
+    let after = before.after_swap(false, true, U256::from(10).pow(U256::from(22)), 0);
+
+    let as_token0 = predict_price(&dex, &before, &after, true);
+    assert!(as_token0.bid > dex.bid && as_token0.ask > dex.ask);
+
+    let as_token1 = predict_price(&dex, &before, &after, false);
+    assert!(as_token1.bid < dex.bid && as_token1.ask < dex.ask);
+    assert_eq!(predict_price(&dex, &before, &before, true), dex);
+}
+
+#[test]
+fn pending_swaps_leave_once_replaced_mined_or_stale() {
+    let swap = |amount: u64| PendingSwap { zero_for_one: true, exact_input: true, amount: U256::from(amount) };
+    let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    let mut pending = PendingSwaps::new(3);
+
+    pending.insert(B256::repeat_byte(1), alice, 7, swap(1), 10);
+    pending.insert(B256::repeat_byte(2), bob, 3, swap(2), 10);
+    // a fee bump of alice's swap takes its place
+    pending.insert(B256::repeat_byte(3), alice, 7, swap(3), 11);
+    assert_eq!(pending.swaps().cloned().collect::<Vec<_>>(), vec![swap(2), swap(3)]);
+
+    // whatever used up bob's nonce, his swap will not land
+    pending.on_block(11, &[(bob, 3)]);
+    assert_eq!(pending.swaps().cloned().collect::<Vec<_>>(), vec![swap(3)]);
+
+    pending.on_block(13, &[]);
+    assert_eq!(pending.len(), 1);
+    pending.on_block(14, &[]);
+    assert!(pending.is_empty());
+}
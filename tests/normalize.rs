@@ -0,0 +1,31 @@
+mod common;
+
+use alloy::primitives::U256;
+use rust_arb_bot::arbitrage::PriceData;
+use rust_arb_bot::normalize::Normalizer;
+
+use common::settings;
+
+#[test]
+fn scales_by_the_configured_decimals() {
+    let norm = Normalizer::new(&settings());
+    assert_eq!(norm.denomination.to_string(), "HYPE/USDT");
+    assert_eq!(norm.one_base(), U256::from(10u64).pow(U256::from(18)));
+
+    // 2 base sold for 81.2 USDT, bought for 81.4
+    let two = norm.base_units(2.0);
+    assert_eq!(norm.dex_book(81_200_000, 81_400_000, two), PriceData::new(40.6, 40.7));
+    assert_eq!(norm.quote_units(40.6), U256::from(40_600_000u64));
+    assert_eq!(norm.quote(U256::from(1_500_000u64)), 1.5);
+}
+
+#[test]
+fn prices_tokens_of_any_decimals_alike() {
+    // an 8-decimal base against an 18-decimal quote
+    let norm = Normalizer { base_decimals: 8, quote_decimals: 18, ..Normalizer::new(&settings()) };
+    let one = norm.one_base();
+    assert_eq!(one, U256::from(100_000_000u64));
+    let book = norm.dex_book(40_600_000_000_000_000_000, 40_700_000_000_000_000_000, one);
+    assert_eq!(book, PriceData::new(40.6, 40.7));
+    assert_eq!(norm.dex_price(1, U256::ZERO), 0.0);
+}
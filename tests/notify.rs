@@ -0,0 +1,90 @@
+mod common;
+
+use std::collections::HashMap;
+
+use rust_arb_bot::arbitrage::{ArbDirection, ArbProfit};
+use rust_arb_bot::notify::{Notifier, OpportunityDetails, SIGNATURE_HEADER, sign};
+use rust_arb_bot::opportunity::Opportunity;
+use rust_arb_bot::settings::Settings;
+use serde_json::Value;
+use tokio::net::TcpListener;
+
+use common::{capture_request, capture_requests};
+
+fn details() -> OpportunityDetails {
+    OpportunityDetails { pair: "default".to_string(), size: 10.0, expected_net: 12.0, cex_price: 40.0, dex_price: 40.2 }
+}
+
+#[test]
+fn signs_like_rfc_4231() {
+    assert_eq!(
+        sign(b"Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[tokio::test]
+async fn posts_signed_opportunities_above_the_threshold() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let captured = tokio::spawn(capture_request(listener));
+
+    let env = HashMap::from([
+        ("RPC_URL".to_string(), "http://mock.invalid".to_string()),
+        ("WEBHOOK_URL".to_string(), url),
+        ("WEBHOOK_SECRET".to_string(), "hunter2".to_string()),
+    ]);
+    let cfg = Settings::load_from(&["config/default.toml"], Some(env)).unwrap();
+    let (notifier, sink) = Notifier::from_config(&cfg);
+    tokio::spawn(sink.unwrap().run());
+
+    let profit = |net: f64| ArbProfit { gross: net + 0.5, cex_fee: 0.3, gas_cost: 0.2, borrow_cost: 0.0, net };
+    // below webhook_min_profit, never sent
+    notifier.opportunity("bybit", &Opportunity::new(ArbDirection::BuyCex, profit(cfg.webhook_min_profit / 2.0)), details());
+    let sent = Opportunity::new(ArbDirection::BuyDex, profit(cfg.webhook_min_profit * 2.0));
+    notifier.opportunity("bybit", &sent, details());
+
+    let (head, body) = captured.await.unwrap();
+    let signature = head
+        .lines()
+        .find_map(|l| l.strip_prefix(&format!("{}: ", SIGNATURE_HEADER.to_lowercase())))
+        .expect("signature header");
+    assert_eq!(signature, format!("sha256={}", sign(b"hunter2", &body)));
+
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["type"], "opportunity");
+    assert_eq!(json["direction"], "BuyDex");
+    assert_eq!(json["venue"], "bybit");
+    assert_eq!(json["id"], sent.id.to_string());
+    assert_eq!(json["pair"], "default");
+    assert_eq!(json["dex_price"], 40.2);
+}
+
+#[tokio::test]
+async fn a_persistent_opportunity_goes_out_once_per_cooldown() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let captured = tokio::spawn(capture_requests(listener, 2));
+
+    let env = HashMap::from([("RPC_URL".to_string(), "http://mock.invalid".to_string()), ("WEBHOOK_URL".to_string(), url)]);
+    let cfg = Settings::load_from(&["config/default.toml"], Some(env)).unwrap();
+    let (notifier, sink) = Notifier::from_config(&cfg);
+    tokio::spawn(sink.unwrap().run());
+
+    let net = cfg.webhook_min_profit * 2.0;
+    let profit = ArbProfit { gross: net + 0.5, cex_fee: 0.3, gas_cost: 0.2, borrow_cost: 0.0, net };
+    let first = Opportunity::new(ArbDirection::BuyDex, profit);
+    notifier.opportunity("bybit", &first, details());
+    // the same spread again, held back
+    notifier.opportunity("bybit", &Opportunity::new(ArbDirection::BuyDex, profit), details());
+    let other = Opportunity::new(ArbDirection::BuyCex, profit);
+    notifier.opportunity("bybit", &other, details());
+
+    let ids: Vec<String> = captured
+        .await
+        .unwrap()
+        .iter()
+        .map(|(_, body)| serde_json::from_slice::<Value>(body).unwrap()["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids, vec![first.id.to_string(), other.id.to_string()]);
+}
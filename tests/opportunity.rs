@@ -0,0 +1,37 @@
+use std::collections::BTreeSet;
+
+use rust_arb_bot::arbitrage::{ArbDirection, ArbProfit};
+use rust_arb_bot::opportunity::{Opportunity, OpportunityId};
+
+#[test]
+fn ids_are_unique_version_4_uuids() {
+    let ids: BTreeSet<_> = (0..1000).map(|_| OpportunityId::new()).collect();
+    assert_eq!(ids.len(), 1000);
+
+    for id in ids.iter().take(10) {
+        let text = id.to_string();
+        let groups: Vec<_> = text.split('-').map(str::len).collect();
+        assert_eq!(groups, [8, 4, 4, 4, 12]);
+        assert_eq!(&text[14..15], "4");
+        assert!("89ab".contains(&text[19..20]));
+    }
+}
+
+#[test]
+fn ids_round_trip_through_text_and_json() {
+    let id = OpportunityId::new();
+    assert_eq!(id.to_string().parse::<OpportunityId>().unwrap(), id);
+    assert!("not-a-uuid".parse::<OpportunityId>().is_err());
+
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, format!("\"{}\"", id));
+    assert_eq!(serde_json::from_str::<OpportunityId>(&json).unwrap(), id);
+}
+
+#[test]
+fn every_opportunity_gets_its_own_id() {
+    let profit = ArbProfit { gross: 1.0, cex_fee: 0.1, gas_cost: 0.1, borrow_cost: 0.0, net: 0.8 };
+    let a = Opportunity::new(ArbDirection::BuyCex, profit);
+    let b = Opportunity::new(ArbDirection::BuyCex, profit);
+    assert_ne!(a.id, b.id);
+}
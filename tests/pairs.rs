@@ -0,0 +1,155 @@
+mod common;
+
+use std::sync::Arc;
+
+use alloy::primitives::address;
+use rust_arb_bot::adapters::hyperswap::PoolCommand;
+use rust_arb_bot::arbitrage::{ArbEngine, PriceData, VenueId};
+use rust_arb_bot::control::{Control, execute};
+use rust_arb_bot::pairs::{PairManager, PairSpec};
+use rust_arb_bot::risk::{RiskHandle, TradingState, risk_channel};
+use rust_arb_bot::scanner::{MatrixScanner, ScannerCommand};
+use tokio::sync::{mpsc, watch};
+
+use common::{MockRpc, settings};
+
+fn purr() -> PairSpec {
+    PairSpec {
+        name: "PURR".to_string(),
+        base_addr: address!("0x9b498c3c8a0b8cd8ba1d9851d40d186f1872b44e"),
+        quote_addr: address!("0xb8ce59fc3717ada4c02eadf9682a9e934f625ebb"),
+        pool_addr: address!("0x1111111111111111111111111111111111111111"),
+        fee_tier: 3000,
+        bybit_ticker: Some("PURRUSDT".to_string()),
+        gateio_ticker: None,
+        base_decimals: None,
+        quote_decimals: None,
+        dex_profile: None,
+    }
+}
+
+fn risk() -> RiskHandle {
+    let (_, trading) = watch::channel(TradingState::default());
+    let (events, _) = risk_channel();
+    RiskHandle { events, trading, exposure: Default::default(), throttle: Default::default(), audit: Default::default(), markets: Default::default(), switches: Default::default() }
+}
+
+#[test]
+fn pair_settings_replace_the_configured_pair() {
+    let cfg = settings();
+    let spec = purr();
+    let pair_cfg = spec.settings(&cfg).unwrap();
+
+    assert_eq!(pair_cfg.weth_addr, spec.base_addr);
+    assert_eq!(pair_cfg.usdt_addr, spec.quote_addr);
+    assert_eq!(pair_cfg.pool_addr, spec.pool_addr);
+    assert_eq!(pair_cfg.bybit_ticker, "PURRUSDT");
+    assert_eq!(pair_cfg.gateio_ticker, cfg.gateio_ticker);
+    assert!(pair_cfg.extra_pools.is_empty());
+    assert_eq!((pair_cfg.base_decimals, pair_cfg.quote_decimals), (18, 6));
+
+    let eight = PairSpec { base_decimals: Some(8), ..purr() }.settings(&cfg).unwrap();
+    assert_eq!((eight.base_decimals, eight.quote_decimals), (8, 6));
+}
+
+#[test]
+fn pair_settings_take_the_contracts_of_their_dex_profile() {
+    let cfg = settings();
+    let hyperswap = cfg.find_dex_profile("hyperswap").unwrap().clone();
+    let pair_cfg = PairSpec { dex_profile: Some("hyperswap".to_string()), ..purr() }.settings(&cfg).unwrap();
+
+    assert_eq!((pair_cfg.quoter_v2_addr, pair_cfg.router_addr), (hyperswap.quoter_addr, hyperswap.router_addr));
+    assert!(PairSpec { dex_profile: Some("nope".to_string()), ..purr() }.settings(&cfg).is_err());
+}
+
+#[tokio::test]
+async fn adds_and_removes_pairs_through_the_control_commands() {
+    let mut cfg = settings();
+    cfg.screen_tokens = false;
+    let rpc = MockRpc::new();
+    let (pool_tx, mut pool_rx) = mpsc::unbounded_channel();
+    let (scanner_tx, mut scanner_rx) = mpsc::unbounded_channel();
+    let pairs = PairManager::new(cfg, Arc::new(rpc.provider()), risk(), pool_tx, scanner_tx);
+    let control = Control { pairs: Some(Arc::new(pairs)), ..Default::default() };
+
+    assert!(execute("pair list", &Control::default()).await.starts_with("error:"));
+    let add = format!("pair add {}", serde_json::to_string(&purr()).unwrap());
+    assert_eq!(execute(&add, &control).await, "ok");
+    assert!(execute(&add, &control).await.contains("already trading"));
+    assert!(execute("pair add {\"name\": \"PURR\"}", &control).await.starts_with("error:"));
+
+    let Some(PoolCommand::Add { name, cfg, .. }) = pool_rx.recv().await else {
+        panic!("expected the pool to be added");
+    };
+    assert_eq!((name.as_str(), cfg.pool_addr), ("PURR", purr().pool_addr));
+    let Some(ScannerCommand::AddPair { pair, engines, feeds }) = scanner_rx.recv().await else {
+        panic!("expected the pair's engines");
+    };
+    assert_eq!(pair, "PURR");
+    assert_eq!(engines[0].venue, "bybit:PURR");
+    assert_eq!(engines[0].counter, VenueId::Dex("hyperswap".to_string()));
+    assert_eq!(feeds.len(), 2);
+
+    let listed: Vec<PairSpec> = serde_json::from_str(execute("pair list", &control).await.trim_start_matches("ok ")).unwrap();
+    assert_eq!(listed, vec![purr()]);
+
+    assert_eq!(execute("pair remove PURR", &control).await, "ok");
+    assert!(matches!(scanner_rx.recv().await, Some(ScannerCommand::RemovePair { .. })));
+    assert!(matches!(pool_rx.recv().await, Some(PoolCommand::Remove { .. })));
+    assert!(execute("pair remove PURR", &control).await.starts_with("error:"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_pairs_whose_tokens_fail_screening() {
+    // the pool holds none of the tokens, nothing can be moved through it
+    let rpc = MockRpc::new();
+    let (pool_tx, mut pool_rx) = mpsc::unbounded_channel();
+    let (scanner_tx, mut scanner_rx) = mpsc::unbounded_channel();
+    let pairs = PairManager::new(settings(), Arc::new(rpc.provider()), risk(), pool_tx, scanner_tx);
+    assert!(settings().screen_tokens);
+
+    let e = pairs.add(purr()).await.unwrap_err();
+    assert!(format!("{:#}", e).contains("pair PURR refused"));
+    assert!(format!("{:#}", e).contains("failed transfer screening"));
+    assert!(pairs.list().is_empty());
+    assert!(pool_rx.try_recv().is_err());
+    assert!(scanner_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn scanner_takes_pairs_while_running() {
+    let cfg = settings();
+    let rpc = MockRpc::new().with_gas_price(1_000_000_000);
+    let (cex_tx, cex_rx) = watch::channel(None);
+    let (dex_tx, dex_rx) = watch::channel(None);
+    let mut scanner = MatrixScanner::new(
+        &cfg,
+        vec![(VenueId::Cex("bybit".to_string()), cex_rx.clone()), (VenueId::Dex("hyperswap".to_string()), dex_rx.clone())],
+        Arc::new(rpc.provider()),
+        risk(),
+    );
+
+    let engine = ArbEngine::new(
+        purr().settings(&cfg).unwrap(),
+        "bybit:PURR",
+        cex_rx.clone(),
+        dex_rx.clone(),
+        Arc::new(rpc.provider()),
+        risk(),
+    );
+    scanner.apply(ScannerCommand::AddPair {
+        pair: "PURR".to_string(),
+        engines: vec![engine],
+        feeds: vec![cex_rx, dex_rx],
+    });
+    assert_eq!(scanner.pairs(), vec!["default", "PURR"]);
+
+    cex_tx.send_replace(Some(PriceData::new(40.0, 40.1)));
+    dex_tx.send_replace(Some(PriceData::new(41.0, 41.2)));
+    let found = scanner.scan().await.unwrap();
+    assert!(found.iter().any(|c| c.pair == "PURR" && c.venue == "bybit:PURR"));
+
+    scanner.apply(ScannerCommand::RemovePair { pair: "PURR".to_string() });
+    assert_eq!(scanner.pairs(), vec!["default"]);
+    assert!(scanner.scan().await.unwrap().iter().all(|c| c.pair == "default"));
+}
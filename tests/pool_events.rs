@@ -0,0 +1,104 @@
+mod common;
+
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, I256, U160, U256, aliases::I24},
+    rpc::types::Log,
+    sol_types::SolEvent,
+};
+
+use rust_arb_bot::dex::pool_events::{PoolEvent, apply_pool_event};
+use rust_arb_bot::helpers::abi::{Mint, Swap};
+use rust_arb_bot::helpers::revm::{BlockTracker, POOL_LIQUIDITY, POOL_SLOT0, init_cache_db};
+
+use common::{MockRpc, head, now};
+
+fn log(pool: Address, event: &impl SolEvent) -> Log {
+    Log { inner: alloy::primitives::Log { address: pool, data: event.encode_log_data() }, ..Default::default() }
+}
+
+fn swap(sqrt_price_x96: u64, liquidity: u128, tick: i32) -> Swap {
+    Swap {
+        sender: Address::ZERO,
+        recipient: Address::ZERO,
+        amount0: I256::ZERO,
+        amount1: I256::ZERO,
+        sqrtPriceX96: U160::from(sqrt_price_x96),
+        liquidity,
+        tick: I24::try_from(tick).unwrap(),
+    }
+}
+
+#[test]
+fn decodes_swaps_and_liquidity_changes() {
+    let pool = Address::repeat_byte(7);
+    assert_eq!(
+        PoolEvent::decode(&log(pool, &swap(1 << 40, 5000, -3))),
+        Some(PoolEvent::Swap { pool, sqrt_price_x96: U256::from(1u64 << 40), liquidity: 5000, tick: -3 })
+    );
+
+    let mint = Mint {
+        sender: Address::ZERO,
+        owner: Address::ZERO,
+        tickLower: I24::try_from(-60).unwrap(),
+        tickUpper: I24::try_from(60).unwrap(),
+        amount: 10,
+        amount0: U256::ZERO,
+        amount1: U256::ZERO,
+    };
+    assert_eq!(PoolEvent::decode(&log(pool, &mint)), Some(PoolEvent::Liquidity { pool }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn swaps_are_written_into_cached_slot0() {
+    let pool = Address::repeat_byte(7);
+    let rpc = MockRpc::new();
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let mut tracker = BlockTracker::new(vec![pool]);
+    tracker.followed = vec![pool];
+
+    // unlocked, with an observation index above price and tick
+    let upper = (U256::from(1) << 240) | (U256::from(9) << 184);
+    cache_db.insert_account_storage(pool, U256::from(POOL_SLOT0), upper | U256::from(1u64 << 50)).unwrap();
+
+    let event = PoolEvent::decode(&log(pool, &swap(1 << 40, 5000, -3))).unwrap();
+    assert!(apply_pool_event(&mut cache_db, &mut tracker, &event));
+
+    let storage = &cache_db.cache.accounts[&pool].storage;
+    let tick = U256::from(0xff_fffd_u32) << 160;
+    assert_eq!(storage[&U256::from(POOL_SLOT0)], upper | tick | U256::from(1u64 << 40));
+    assert_eq!(storage[&U256::from(POOL_LIQUIDITY)], U256::from(5000));
+    assert!(tracker.recheck.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unappliable_events_reread_the_pool_at_the_next_block() {
+    let pool = Address::repeat_byte(7);
+    let rpc = MockRpc::new();
+    let provider = Arc::new(rpc.provider());
+    let mut cache_db = init_cache_db(provider.clone());
+    let mut tracker = BlockTracker::new(vec![pool]);
+    tracker.followed = vec![pool];
+    tracker.announce(head(1000, now()));
+    tracker.advance(&mut cache_db, &provider).await.unwrap();
+
+    let slot0 = U256::from(1) << 96;
+    cache_db.insert_account_storage(pool, U256::from(POOL_SLOT0), slot0).unwrap();
+
+    // a followed pool outlives a new block
+    tracker.announce(head(1001, now()));
+    tracker.advance(&mut cache_db, &provider).await.unwrap();
+    assert_eq!(cache_db.cache.accounts[&pool].storage[&U256::from(POOL_SLOT0)], slot0);
+
+    // a mint drops it now, and what was read meanwhile at the next block
+    assert!(!apply_pool_event(&mut cache_db, &mut tracker, &PoolEvent::Liquidity { pool }));
+    assert!(cache_db.cache.accounts[&pool].storage.is_empty());
+    cache_db.insert_account_storage(pool, U256::from(POOL_SLOT0), slot0).unwrap();
+
+    tracker.announce(head(1002, now()));
+    tracker.advance(&mut cache_db, &provider).await.unwrap();
+    assert!(cache_db.cache.accounts[&pool].storage.is_empty());
+    assert!(tracker.recheck.is_empty());
+}
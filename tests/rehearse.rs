@@ -0,0 +1,30 @@
+mod common;
+
+use rust_arb_bot::arbitrage::Leg;
+use rust_arb_bot::cli::rehearse::MockBybit;
+use rust_arb_bot::execution::bybit::BybitClient;
+use rust_arb_bot::execution::cex::{CexFill, CexOrders, OrderPolicy};
+use rust_arb_bot::execution::client_order::ClientOrderId;
+use rust_arb_bot::opportunity::OpportunityId;
+use rust_arb_bot::settings::Secret;
+
+#[tokio::test]
+async fn mock_exchange_fills_bybit_market_orders() {
+    let exchange = MockBybit::start(40.25).await.unwrap();
+    let mut cfg = common::settings();
+    cfg.bybit_rest_endpoint = exchange.url.clone();
+    cfg.bybit_api_key = Some("key".to_string());
+    cfg.bybit_api_secret = Some(Secret::new("secret"));
+
+    let client = BybitClient::new(&cfg).unwrap();
+    let id = ClientOrderId::new(OpportunityId::new(), Leg::Cex);
+    let fill = client.market_order(true, 1.5, 40.0, id, &OrderPolicy::from_config(&cfg)).await.unwrap();
+    assert_eq!(fill, CexFill { base: 1.5, price: 40.25 });
+
+    let orders = exchange.orders.lock().unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0]["side"], "Buy");
+    assert_eq!(orders[0]["orderType"], "Market");
+    assert_eq!(orders[0]["marketUnit"], "baseCoin");
+    assert_eq!(orders[0]["orderLinkId"], id.to_string());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::sync::Arc;
+
+use alloy::primitives::{Address, Bytes, I256, bytes};
+use alloy::sol_types::{Revert, SolError, SolValue};
+use rust_arb_bot::helpers::revert::{RevertKind, RevmCallError, decode_quoter_deltas, decode_revert};
+use rust_arb_bot::helpers::revm::{init_cache_db, revm_call};
+
+use common::MockRpc;
+
+// what CustomQuoter's swap callback reverts with for 1 WHYPE in and
+// 41.512345 USDT out: `revert(string(abi.encode(amount0Delta, amount1Delta)))`
+const QUOTER_REVERT: Bytes = bytes!(
+    "08c379a0"
+    "0000000000000000000000000000000000000000000000000000000000000020"
+    "0000000000000000000000000000000000000000000000000000000000000040"
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffd869267"
+    "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+);
+
+// reverts with the `data` appended to it
+fn reverting_code(data: &[u8]) -> Bytes {
+    let len = data.len() as u8;
+    // CODECOPY(0, 12, len), REVERT(0, len)
+    let mut code = vec![0x60, len, 0x60, 12, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xfd];
+    code.extend_from_slice(data);
+    code.into()
+}
+
+// returns storage slot 0
+const SLOAD_CODE: [u8; 11] = [0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_failed_state_fetch_is_an_error_not_a_panic() {
+    let contract = Address::repeat_byte(0x42);
+    let rpc = MockRpc::new().with_code(contract, Bytes::from_static(&SLOAD_CODE)).with_failing("eth_getStorageAt");
+    let mut cache_db = init_cache_db(Arc::new(rpc.provider()));
+
+    let error = revm_call(Address::ZERO, contract, Bytes::new(), &mut cache_db).unwrap_err();
+    assert!(format!("{:#}", error).contains("eth_getStorageAt failed"), "{:#}", error);
+}
+
+#[test]
+fn decodes_the_quoter_deltas_from_its_revert() {
+    let (amount0, amount1) = decode_quoter_deltas(&QUOTER_REVERT).unwrap();
+    assert_eq!(amount0, I256::try_from(-41_512_345).unwrap());
+    assert_eq!(amount1, I256::try_from(10u64.pow(18)).unwrap());
+
+    // a reason that happens to be 64 bytes long is still a reason
+    let reason = Revert::from("a".repeat(64)).abi_encode().into();
+    assert_eq!(decode_quoter_deltas(&reason), None);
+    assert_eq!(decode_revert(&reason).kind, RevertKind::Other);
+}
+
+#[test]
+fn classifies_pool_reverts() {
+    let spl = Revert::from("SPL").abi_encode().into();
+    assert_eq!(decode_revert(&spl).kind, RevertKind::NoLiquidity);
+    assert_eq!(decode_revert(&Bytes::new()).kind, RevertKind::BadCalldata);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_quoter_revert_is_the_quote() {
+    let quoter = Address::repeat_byte(0x51);
+    let other = Address::repeat_byte(0x52);
+    let spl: Bytes = Revert::from("SPL").abi_encode().into();
+    let rpc = MockRpc::new().with_code(quoter, reverting_code(&QUOTER_REVERT)).with_code(other, reverting_code(&spl));
+    let mut cache_db = init_cache_db(Arc::new(rpc.provider()));
+
+    let output = revm_call(Address::ZERO, quoter, Bytes::new(), &mut cache_db).unwrap();
+    let deltas = <(I256, I256)>::abi_decode_params(&output).unwrap();
+    assert_eq!(Some(deltas), decode_quoter_deltas(&QUOTER_REVERT));
+
+    let error = revm_call(Address::ZERO, other, Bytes::new(), &mut cache_db).unwrap_err();
+    assert_eq!(error.downcast_ref::<RevmCallError>().unwrap().kind, RevertKind::NoLiquidity);
+}
@@ -0,0 +1,38 @@
+use rust_arb_bot::backtest::parse_date_ms;
+use rust_arb_bot::schedule::{CronWindow, civil_date};
+
+const MINUTE_MS: u64 = 60_000;
+const HOUR_MS: u64 = 3_600_000;
+
+#[test]
+fn matches_utc_minutes_of_a_cron_window() {
+    // weekdays, except the 08:00 UTC hour and every :00-:04
+    let window = CronWindow::parse("5-59 0-7,9-23 * * 1-5").unwrap();
+    // a Wednesday
+    let day = parse_date_ms("2025-01-15").unwrap();
+
+    assert!(window.matches(day + 7 * HOUR_MS + 30 * MINUTE_MS));
+    assert!(!window.matches(day + 8 * HOUR_MS + 30 * MINUTE_MS));
+    assert!(!window.matches(day + 9 * HOUR_MS + 4 * MINUTE_MS));
+    assert!(window.matches(day + 9 * HOUR_MS + 5 * MINUTE_MS));
+    // the Saturday after
+    assert!(!window.matches(day + 3 * 24 * HOUR_MS + 12 * HOUR_MS));
+
+    let quarter_hours = CronWindow::parse("*/15 * 1 1,6 *").unwrap();
+    assert!(quarter_hours.matches(parse_date_ms("2025-06-01").unwrap() + 45 * MINUTE_MS));
+    assert!(!quarter_hours.matches(parse_date_ms("2025-06-01").unwrap() + 46 * MINUTE_MS));
+    assert!(!quarter_hours.matches(parse_date_ms("2025-06-02").unwrap()));
+}
+
+#[test]
+fn rejects_malformed_windows() {
+    for expr in ["* * * *", "60 * * * *", "* 5-3 * * *", "* * 0 * *", "*/0 * * * *", "a * * * *"] {
+        assert!(CronWindow::parse(expr).is_err(), "{expr}");
+    }
+}
+
+#[test]
+fn converts_timestamps_to_civil_dates() {
+    assert_eq!(civil_date(0), (1970, 1, 1));
+    assert_eq!(civil_date(parse_date_ms("2024-02-29").unwrap() + HOUR_MS), (2024, 2, 29));
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+
+use rust_arb_bot::arbitrage::GasToken;
+use rust_arb_bot::settings::Settings;
+
+// set UPDATE_SNAPSHOTS=1 to accept a deliberate schema change
+fn assert_snapshot(name: &str, settings: &Settings) {
+    let path = format!("tests/snapshots/settings_{}.snap", name);
+    let actual = format!("{:#?}\n", settings);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| panic!("missing snapshot {}", path));
+    assert_eq!(actual, expected, "settings {} changed, see {}", name, path);
+}
+
+fn env(vars: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+    Some(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+}
+
+#[test]
+fn shipped_config() {
+    let settings = Settings::load_from(&["config/default.toml"], env(&[])).unwrap();
+    assert_snapshot("default", &settings);
+}
+
+#[test]
+fn minimal_config_fills_optional_keys() {
+    let settings = Settings::load_from(
+        &["tests/fixtures/settings/minimal.toml"],
+        env(&[("RPC_URL", "https://rpc.example/evm")]),
+    )
+    .unwrap();
+
+    assert_eq!(settings.quote_rpc(), "https://rpc.example/evm");
+    assert!(settings.extra_pools.is_empty());
+    assert_snapshot("minimal", &settings);
+}
+
+#[test]
+fn multi_pool_overlay() {
+    let settings = Settings::load_from(
+        &["config/default.toml", "tests/fixtures/settings/multi_pool.toml"],
+        env(&[]),
+    )
+    .unwrap();
+
+    assert_eq!(settings.extra_pools.len(), 2);
+    assert_snapshot("multi_pool", &settings);
+}
+
+#[test]
+fn env_overrides_file() {
+    let settings = Settings::load_from(
+        &["config/default.toml"],
+        env(&[
+            ("RPC_URL", "https://rpc.example/evm"),
+            ("SEND_RPC_URL", "https://send.example/evm"),
+            ("QUOTER_KIND", "v1"),
+            ("MAX_SPREAD_BPS", "250"),
+            ("QUOTE_CORE_EXTRA_WEI_DECIMALS", "-8"),
+            ("PRIVATE_KEY", "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"),
+            ("HYPERLIQUID_VAULT_ADDR", "0x000000000000000000000000000000000000beef"),
+        ]),
+    )
+    .unwrap();
+
+    assert_eq!(settings.send_rpc(), "https://send.example/evm");
+    assert_eq!(settings.quote_rpc(), "https://rpc.example/evm");
+    assert_snapshot("env_overrides", &settings);
+}
+
+#[test]
+fn missing_required_key_is_an_error() {
+    assert!(Settings::load_from(&["tests/fixtures/settings/minimal.toml"], env(&[])).is_err());
+}
+
+#[test]
+fn bybit_depth_must_be_offered() {
+    let cfg = Settings::load_from(&["config/default.toml"], env(&[("BYBIT_ORDERBOOK_DEPTH", "50")])).unwrap();
+    assert_eq!(cfg.bybit_depth("HYPEUSDT"), 50);
+
+    let err = Settings::load_from(&["config/default.toml"], env(&[("BYBIT_ORDERBOOK_DEPTH", "20")])).unwrap_err();
+    assert!(err.to_string().contains("depth 20"));
+}
+
+#[test]
+fn slippage_must_stay_below_the_whole_amount() {
+    let cfg = Settings::load_from(&["config/default.toml"], env(&[("DEX_SLIPPAGE_BPS", "9999")])).unwrap();
+    assert_eq!(cfg.dex_slippage_bps, 9999);
+
+    let err = Settings::load_from(&["config/default.toml"], env(&[("DEX_SLIPPAGE_BPS", "10000")])).unwrap_err();
+    assert!(err.to_string().contains("dex_slippage_bps 10000"));
+}
+
+#[test]
+fn native_token_must_be_part_of_the_pair() {
+    let inverse = Settings::load_from(
+        &["config/default.toml"],
+        env(&[("PAIR_BASE", "USDT"), ("PAIR_QUOTE", "HYPE")]),
+    )
+    .unwrap();
+    assert_eq!(inverse.gas_token(), GasToken::Quote);
+    assert_eq!(inverse.denomination().to_string(), "USDT/HYPE");
+
+    let err = Settings::load_from(&["config/default.toml"], env(&[("NATIVE_TOKEN", "ETH")])).unwrap_err();
+    assert!(err.to_string().contains("neither side of HYPE/USDT"));
+}
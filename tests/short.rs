@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use rust_arb_bot::execution::hyperliquid::PerpMargin;
+use rust_arb_bot::execution::short::{CexSell, OpenShort, ShortBook, check_margin, plan_cex_sell};
+
+#[test]
+fn shorts_only_what_inventory_cannot_cover() {
+    assert_eq!(plan_cex_sell(5.0, 5.0), CexSell::FromInventory);
+    // never half from inventory, half short
+    assert_eq!(plan_cex_sell(4.9, 5.0), CexSell::Short);
+    assert_eq!(plan_cex_sell(0.0, 1.0), CexSell::Short);
+
+    let margin = PerpMargin { account_value: 1500.0, withdrawable: 1000.0 };
+    assert!(check_margin(&margin, 2000.0, 2.0).is_ok());
+    assert!(check_margin(&margin, 2000.01, 2.0).is_err());
+    assert!(check_margin(&PerpMargin { account_value: 0.0, withdrawable: -5.0 }, 1.0, 3.0).is_err());
+}
+
+#[test]
+fn buys_back_shorts_past_their_hold_time() {
+    let book = ShortBook::default();
+    book.open(OpenShort { size: 1.0, entry_px: 40.0, opened_ms: 1_000 });
+    book.open(OpenShort { size: 2.0, entry_px: 41.0, opened_ms: 5_000 });
+    assert_eq!(book.total_size(), 3.0);
+
+    let due = book.take_due(4_000, Duration::from_secs(3));
+    assert_eq!(due, vec![OpenShort { size: 1.0, entry_px: 40.0, opened_ms: 1_000 }]);
+    assert_eq!(book.total_size(), 2.0);
+    assert!(book.take_due(7_999, Duration::from_secs(3)).is_empty());
+    assert_eq!(book.take_due(8_000, Duration::from_secs(3)).len(), 1);
+}
+
+#[test]
+fn closing_early_takes_from_the_newest_shorts() {
+    let book = ShortBook::default();
+    book.open(OpenShort { size: 1.0, entry_px: 40.0, opened_ms: 1_000 });
+    book.open(OpenShort { size: 2.0, entry_px: 41.0, opened_ms: 5_000 });
+
+    book.close(2.5);
+    assert_eq!(book.all(), vec![OpenShort { size: 0.5, entry_px: 40.0, opened_ms: 1_000 }]);
+    book.close(1.0);
+    assert!(book.all().is_empty());
+}
@@ -0,0 +1,78 @@
+use rust_arb_bot::adapters::book::OrderBook;
+use rust_arb_bot::arbitrage::ArbDirection;
+use rust_arb_bot::sizing::{DexCurve, SizeSolver, TradeCosts, golden_section, net_at};
+use serde_json::json;
+
+fn solver() -> SizeSolver {
+    SizeSolver { min_size: 0.5, max_size: 100.0, steps: 8, tolerance: 0.01 }
+}
+
+// selling base on the DEX from 41 down, 0.05 lower per base sold
+fn dex_curve(sizes: &[f64]) -> DexCurve {
+    DexCurve::new(sizes.iter().map(|s| (*s, s * (41.0 - 0.05 * s))).collect())
+}
+
+#[test]
+fn golden_section_finds_the_peak() {
+    let peak = golden_section(0.0, 10.0, 1e-6, |x| -(x - 3.7) * (x - 3.7));
+    assert!((peak - 3.7).abs() < 1e-5);
+}
+
+#[test]
+fn interpolates_between_quotes() {
+    let curve = DexCurve::new(vec![(10.0, 390.0), (1.0, 40.0), (0.0, 0.0)]);
+    assert_eq!(curve.amount(0.5), Some(20.0));
+    assert_eq!(curve.amount(5.5), Some(215.0));
+    assert_eq!(curve.amount(10.0), Some(390.0));
+    assert_eq!(DexCurve::new(Vec::new()).amount(1.0), None);
+}
+
+#[test]
+fn counts_fees_borrow_and_gas() {
+    let costs = TradeCosts { cex_fee_bps: 10, borrow_per_unit: 0.01, gas_cost: 0.5 };
+    // buy 2 at 40 on the CEX, sell them on the DEX for 82
+    let net = net_at(&ArbDirection::BuyCex, 2.0, 40.0, 82.0, &costs);
+    assert!((net - (2.0 - 0.08 - 0.02 - 0.5)).abs() < 1e-9);
+    let net = net_at(&ArbDirection::BuyDex, 2.0, 41.0, 80.0, &costs);
+    assert!((net - (2.0 - 0.082 - 0.02 - 0.5)).abs() < 1e-9);
+}
+
+#[test]
+fn trades_up_to_where_slippage_eats_the_edge() {
+    let solver = solver();
+    let curve = dex_curve(&solver.ladder());
+    let mut book = OrderBook::default();
+    book.apply_snapshot(&[(39.9, 1000.0)], &[(40.0, 1000.0)]);
+    let costs = TradeCosts { cex_fee_bps: 0, borrow_per_unit: 0.0, gas_cost: 0.5 };
+
+    // size * (1 - 0.05 * size) - 0.5 peaks at 10, the ladder interpolated
+    // around it reads a little lower
+    let found = solver.solve(&ArbDirection::BuyCex, &curve, &book, &costs).unwrap();
+    let brute = (1..=10000)
+        .map(|i| i as f64 * 0.01)
+        .map(|size| net_at(&ArbDirection::BuyCex, size, 40.0, curve.amount(size).unwrap(), &costs))
+        .fold(f64::NEG_INFINITY, f64::max);
+    assert!((found.net - brute).abs() < 0.01, "{} vs {}", found.net, brute);
+    assert!(found.size > 5.0 && found.size < 15.0);
+    assert_eq!(found.cex_price, 40.0);
+
+    // a thin book caps it sooner
+    book.apply_snapshot(&[(39.9, 1000.0)], &[(40.0, 2.0), (40.5, 1000.0)]);
+    let thin = solver.solve(&ArbDirection::BuyCex, &curve, &book, &costs).unwrap();
+    assert!(thin.size < found.size && thin.net < found.net);
+
+    // nothing to take
+    assert_eq!(solver.solve(&ArbDirection::BuyCex, &curve, &OrderBook::default(), &costs), None);
+}
+
+#[test]
+fn reads_rest_book_snapshots() {
+    let bybit = json!({ "b": [["39.9", "2"], ["39.8", "5"]], "a": [["40.1", "3"]] });
+    let book = OrderBook::from_json(&bybit, "b", "a");
+    assert_eq!(book.bids().copied().collect::<Vec<_>>(), vec![(39.9, 2.0), (39.8, 5.0)]);
+    assert_eq!(book.asks().copied().collect::<Vec<_>>(), vec![(40.1, 3.0)]);
+
+    let gateio = json!({ "bids": [["39.9", "2"]], "asks": [["40.1", "3"], ["40.2", "1"]] });
+    let book = OrderBook::from_json(&gateio, "bids", "asks");
+    assert_eq!(book.vwap(true, 4.0), Some(40.125));
+}
@@ -0,0 +1,62 @@
+use rust_arb_bot::arbitrage::ArbDirection;
+use rust_arb_bot::control::{Control, execute};
+use rust_arb_bot::spreads::{SpreadHistogram, SpreadSummary, Spreads};
+
+#[test]
+fn percentiles_read_the_upper_edge_of_their_bucket() {
+    let mut histogram = SpreadHistogram::new(0.1);
+    assert_eq!(histogram.percentile(50.0), None);
+
+    // -0.95, -0.85, ..., 8.95: a hundred spreads, mostly losing money
+    for i in 0..100 {
+        histogram.record(-0.95 + i as f64 * 0.1);
+    }
+    histogram.record(f64::NAN);
+    assert_eq!(histogram.count(), 100);
+
+    let near = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9;
+    assert!(near(histogram.percentile(0.0), -0.9));
+    assert!(near(histogram.percentile(50.0), 4.0));
+    assert!(near(histogram.percentile(95.0), 8.5));
+    // never past the largest spread seen
+    assert!(near(histogram.percentile(100.0), 8.95));
+}
+
+#[test]
+fn keeps_a_histogram_per_pair_and_direction() {
+    let spreads = Spreads::new(0.01);
+    for net in [0.105, 0.205, 0.305] {
+        spreads.record("default", &ArbDirection::BuyCex, net);
+    }
+    spreads.record("default", &ArbDirection::BuyDex, -1.0);
+    spreads.record("PURR", &ArbDirection::BuyCex, 5.0);
+
+    let summaries = spreads.summaries();
+    assert_eq!(summaries.len(), 3);
+    let buy_cex = summaries.iter().find(|s| s.pair == "default" && s.direction == ArbDirection::BuyCex).unwrap();
+    assert_eq!(buy_cex.count, 3);
+    assert_eq!((buy_cex.min, buy_cex.max), (Some(0.105), Some(0.305)));
+    assert!((buy_cex.at(50.0).unwrap() - 0.21).abs() < 1e-9);
+    assert_eq!(spreads.percentile("default", &ArbDirection::BuyDex, 95.0), Some(-1.0));
+
+    spreads.forget("PURR");
+    assert_eq!(spreads.percentile("PURR", &ArbDirection::BuyCex, 50.0), None);
+}
+
+#[tokio::test]
+async fn control_socket_replies_with_summaries() {
+    assert!(execute("spreads", &Control::default()).await.starts_with("error:"));
+
+    let spreads = Spreads::new(0.01);
+    spreads.record("default", &ArbDirection::BuyCex, 0.5);
+    spreads.record("PURR", &ArbDirection::BuyDex, 1.5);
+    let control = Control { spreads: Some(spreads), ..Default::default() };
+
+    let all: Vec<SpreadSummary> = serde_json::from_str(execute("spreads", &control).await.trim_start_matches("ok ")).unwrap();
+    assert_eq!(all.len(), 2);
+    let purr: Vec<SpreadSummary> =
+        serde_json::from_str(execute("spreads PURR", &control).await.trim_start_matches("ok ")).unwrap();
+    assert_eq!(purr.len(), 1);
+    assert_eq!(purr[0].direction, ArbDirection::BuyDex);
+    assert_eq!(purr[0].max, Some(1.5));
+}
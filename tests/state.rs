@@ -0,0 +1,65 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use alloy::primitives::B256;
+use rust_arb_bot::arbitrage::PriceData;
+use rust_arb_bot::execution::accounts::AccountBalance;
+use rust_arb_bot::state::{SavedOrder, SavedPrice, StateStore, WarmState, reconcile};
+use rust_arb_bot::strategy::market_making::Side;
+
+use common::{MockRpc, settings};
+
+fn saved_state() -> WarmState {
+    WarmState {
+        saved_at_ms: 1_000_000,
+        prices: BTreeMap::from([
+            ("bybit".to_string(), SavedPrice { bid: 40.0, ask: 40.1, at_ms: 995_000 }),
+            ("gateio".to_string(), SavedPrice { bid: 40.2, ask: 40.3, at_ms: 900_000 }),
+        ]),
+        in_flight: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn saves_and_restores_state_across_restarts() {
+    let dir = std::env::temp_dir().join(format!("rust-arb-bot-state-{}", std::process::id()));
+    let path = dir.join("state.json");
+    assert_eq!(WarmState::load(&path).unwrap(), None);
+
+    let store = StateStore::new(saved_state());
+    store.record_price("hyperswap", &PriceData::new(39.9, 40.0), 1_000_000);
+    store.untrack_tx(B256::repeat_byte(2));
+    store.snapshot().save(&path).unwrap();
+
+    let restored = WarmState::load(&path).unwrap().unwrap();
+    assert_eq!(restored, store.snapshot());
+    assert_eq!(restored.in_flight, vec![B256::repeat_byte(1)]);
+
+    // gateio's price is too old to trade on
+    let fresh: Vec<_> = restored.fresh_prices(1_010_000, Duration::from_secs(30)).into_iter().map(|(f, _)| f).collect();
+    assert_eq!(fresh, vec!["bybit", "hyperswap"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn reconciles_in_flight_txs_against_the_chain() {
+    let rpc = MockRpc::new().with_receipt(B256::repeat_byte(1));
+    let mut state = saved_state();
+    state.open_orders = vec![SavedOrder { side: Side::Bid, oid: 7, px: 40.0, sz: 1.0, filled: 0.0 }];
+    state.accounts = BTreeMap::from([("bybit-mm".to_string(), AccountBalance { base: 1.5, quote: 250.0 })]);
+
+    let reconciled = reconcile(&settings(), state, &rpc.provider(), None, 1_010_000).await.unwrap();
+
+    assert_eq!(reconciled.landed_txs, vec![B256::repeat_byte(1)]);
+    assert_eq!(reconciled.dropped_txs, vec![B256::repeat_byte(2)]);
+    assert!(reconciled.state.in_flight.is_empty());
+    // orders cannot be checked without a HyperCore client
+    assert!(reconciled.state.open_orders.is_empty());
+    assert_eq!(reconciled.fresh_prices, vec![("bybit".to_string(), PriceData::new(40.0, 40.1))]);
+    // sub-account balances carry over as last booked
+    assert_eq!(reconciled.state.accounts["bybit-mm"], AccountBalance { base: 1.5, quote: 250.0 });
+}
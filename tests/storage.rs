@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use rust_arb_bot::arbitrage::{ArbDirection, ArbProfit, PriceData};
+use rust_arb_bot::notify::{Notification, Notifier, OpportunityDetails, QUEUE_CAPACITY};
+use rust_arb_bot::opportunity::Opportunity;
+use rust_arb_bot::settings::Settings;
+use rust_arb_bot::storage::{Row, Storage, StorageDb};
+use sqlx::Row as _;
+
+fn temp_url(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("rust-arb-bot-storage-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    format!("sqlite://{}?mode=rwc", dir.join("arb.db").display())
+}
+
+fn settings(url: &str) -> Settings {
+    let env = HashMap::from([
+        ("RPC_URL".to_string(), "http://mock.invalid".to_string()),
+        ("STORAGE_URL".to_string(), url.to_string()),
+        ("STORAGE_FLUSH_MS".to_string(), "10".to_string()),
+    ]);
+    Settings::load_from(&["config/default.toml"], Some(env)).unwrap()
+}
+
+fn opportunity() -> (Opportunity, OpportunityDetails) {
+    let profit = ArbProfit { gross: 1.5, cex_fee: 0.3, gas_cost: 0.2, borrow_cost: f64::NAN, net: 1.0 };
+    let details = OpportunityDetails { pair: "o'hype".to_string(), size: 10.0, expected_net: 10.0, cex_price: 40.0, dex_price: 40.2 };
+    (Opportunity::new(ArbDirection::BuyDex, profit), details)
+}
+
+#[tokio::test]
+async fn writes_rows_in_transactions() {
+    let url = temp_url("db");
+    let db = StorageDb::connect(&url).await.unwrap();
+    let (opp, details) = opportunity();
+    let rows = [
+        Row::Price { ts_ms: 1_700_000_000_000, feed: "bybit".to_string(), price: PriceData::new(40.0, f64::INFINITY) },
+        Row::opportunity(&Notification::opportunity("bybit", &opp, details)).unwrap(),
+    ];
+    db.append(&rows).await.unwrap();
+    // reconnecting keeps what is there
+    let db = StorageDb::connect(&url).await.unwrap();
+
+    let price = sqlx::query("SELECT ts_ms, feed, bid, ask FROM prices").fetch_one(db.pool()).await.unwrap();
+    assert_eq!(price.get::<i64, _>("ts_ms"), 1_700_000_000_000);
+    assert_eq!(price.get::<String, _>("feed"), "bybit");
+    assert_eq!(price.get::<Option<f64>, _>("bid"), Some(40.0));
+    // what no column holds is stored as NULL
+    assert_eq!(price.get::<Option<f64>, _>("ask"), None);
+
+    let row = sqlx::query("SELECT id, pair, direction, size, borrow_cost, expected_net FROM opportunities")
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("id"), opp.id.to_string());
+    assert_eq!(row.get::<String, _>("pair"), "o'hype");
+    assert_eq!(row.get::<String, _>("direction"), "BuyDex");
+    assert_eq!(row.get::<Option<f64>, _>("size"), Some(10.0));
+    assert_eq!(row.get::<Option<f64>, _>("borrow_cost"), None);
+    assert_eq!(row.get::<Option<f64>, _>("expected_net"), Some(10.0));
+
+    let summary = Notification::Summary { ts_ms: 0, date: "2025-08-02".to_string(), text: String::new(), html: String::new() };
+    assert!(Row::opportunity(&summary).is_none());
+}
+
+#[tokio::test]
+async fn stores_every_opportunity_the_notifier_sees() {
+    let url = temp_url("notifier");
+    let (storage, writer) = Storage::from_config(&settings(&url)).await.unwrap();
+    let writer = tokio::spawn(writer.unwrap().run());
+
+    // without a webhook, below any threshold, still stored
+    let notifier = Notifier::default().with_storage(storage.clone());
+    let (opp, details) = opportunity();
+    notifier.opportunity("gateio", &opp, details);
+    storage.record_price(1, "hyperswap", &PriceData::new(39.9, 40.1));
+    drop((notifier, storage));
+    tokio::time::timeout(Duration::from_secs(5), writer).await.unwrap().unwrap();
+
+    let db = StorageDb::connect(&url).await.unwrap();
+    let price = sqlx::query("SELECT feed, bid, ask FROM prices WHERE ts_ms = 1").fetch_one(db.pool()).await.unwrap();
+    assert_eq!(price.get::<String, _>("feed"), "hyperswap");
+    assert_eq!((price.get::<f64, _>("bid"), price.get::<f64, _>("ask")), (39.9, 40.1));
+    let row = sqlx::query("SELECT venue, pair FROM opportunities WHERE id = $1")
+        .bind(opp.id.to_string())
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+    assert_eq!((row.get::<String, _>("venue"), row.get::<String, _>("pair")), ("gateio".to_string(), "o'hype".to_string()));
+}
+
+#[tokio::test]
+async fn drops_rows_once_the_writer_falls_behind() {
+    let url = temp_url("queue");
+    let (storage, writer) = Storage::from_config(&settings(&url)).await.unwrap();
+
+    // nothing is written until the writer runs
+    for ts_ms in 0..QUEUE_CAPACITY as u64 + 10 {
+        storage.record_price(ts_ms, "bybit", &PriceData::new(40.0, 40.1));
+    }
+    drop(storage);
+    tokio::time::timeout(Duration::from_secs(5), writer.unwrap().run()).await.unwrap();
+
+    let db = StorageDb::connect(&url).await.unwrap();
+    let stored: i64 = sqlx::query("SELECT COUNT(*) AS n FROM prices").fetch_one(db.pool()).await.unwrap().get("n");
+    assert_eq!(stored, QUEUE_CAPACITY as i64);
+}
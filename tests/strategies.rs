@@ -0,0 +1,252 @@
+mod common;
+
+use alloy::primitives::TxHash;
+use rust_arb_bot::arbitrage::{ArbDirection, PriceData};
+use rust_arb_bot::pnl::TradeRecord;
+use rust_arb_bot::strategy::Strategy;
+use rust_arb_bot::strategy::latency_arb::LatencyArb;
+use rust_arb_bot::strategy::market_making::{QuoteAction, QuoteManager, Side};
+use rust_arb_bot::strategy::mean_reversion::MeanReversion;
+
+use common::settings;
+use rust_arb_bot::clock::SimClock;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const GWEI: u128 = 1_000_000_000;
+
+fn book(mid: f64) -> PriceData {
+    PriceData::new(mid - 0.001, mid + 0.001)
+}
+
+fn mean_reversion() -> MeanReversion {
+    let mut cfg = settings();
+    cfg.zscore_window = 20;
+    cfg.zscore_entry = 2.0;
+    cfg.zscore_exit = 0.5;
+    MeanReversion::new(&cfg)
+}
+
+fn filled(direction: ArbDirection) -> TradeRecord {
+    TradeRecord {
+        opportunity_id: None,
+        dex_tx: TxHash::ZERO,
+        account: None,
+        wallet: None,
+        direction,
+        base_amount: 1.0,
+        dex_quote_amount: 40.0,
+        estimated_dex_quote_amount: 40.0,
+        cex_price: 40.0,
+        cex_fee: 0.0,
+        borrow_cost: 0.0,
+        pnl: 0.0,
+        estimated_pnl: 0.0,
+    }
+}
+
+#[test]
+fn mean_reversion_waits_for_a_full_window() {
+    let mut strategy = mean_reversion();
+    let cex = book(40.0);
+
+    for i in 0..19 {
+        strategy.on_price_update(&cex, &book(40.0 + 0.2 * (i % 2) as f64));
+        assert!(strategy.zscore().is_none());
+        assert!(strategy.evaluate(&cex, &book(40.2), GWEI).is_empty());
+    }
+
+    strategy.on_price_update(&cex, &book(40.0));
+    assert!(strategy.zscore().is_some());
+}
+
+#[test]
+fn mean_reversion_enters_on_a_spike_and_exits_on_reversion() {
+    let mut strategy = mean_reversion();
+    let cex = book(40.0);
+
+    // spread flips between 0 and 1 bps, then the DEX jumps 50 bps rich
+    for i in 0..19 {
+        strategy.on_price_update(&cex, &book(40.0 + 0.004 * (i % 2) as f64));
+        assert!(strategy.evaluate(&cex, &book(40.0), GWEI).is_empty());
+    }
+    let rich = book(40.2);
+    strategy.on_price_update(&cex, &rich);
+    assert!(strategy.zscore().unwrap() > 2.0);
+
+    let entry = strategy.evaluate(&cex, &rich, GWEI);
+    let [(ArbDirection::BuyCex, profit)] = entry.as_slice() else {
+        panic!("expected a BuyCex entry, got {:?}", entry);
+    };
+    assert!(profit.gross > 0.0);
+    assert!((profit.net - (profit.gross - profit.cex_fee - profit.gas_cost)).abs() < 1e-12);
+
+    // an entry that did not execute is proposed again
+    assert_eq!(strategy.evaluate(&cex, &rich, GWEI).len(), 1);
+    assert!(!strategy.closes(&ArbDirection::BuyDex));
+
+    // still rich once filled: hold
+    strategy.on_fill(&filled(ArbDirection::BuyCex));
+    assert!(strategy.evaluate(&cex, &rich, GWEI).is_empty());
+    assert!(strategy.closes(&ArbDirection::BuyDex));
+    assert!(!strategy.closes(&ArbDirection::BuyCex));
+
+    strategy.on_price_update(&cex, &book(40.0));
+    for _ in 0..2 {
+        let exit = strategy.evaluate(&cex, &book(40.0), GWEI);
+        assert!(matches!(exit.as_slice(), [(ArbDirection::BuyDex, _)]), "expected an exit, got {:?}", exit);
+    }
+    strategy.on_fill(&filled(ArbDirection::BuyDex));
+    assert!(strategy.evaluate(&cex, &book(40.0), GWEI).is_empty());
+    assert!(!strategy.closes(&ArbDirection::BuyDex));
+}
+
+fn latency_arb(max_exposure_ms: u64, clock: &Arc<SimClock>) -> LatencyArb {
+    let mut cfg = settings();
+    cfg.latency_lead_bps = 15;
+    cfg.latency_lookback_ms = 60_000;
+    cfg.latency_max_exposure_ms = max_exposure_ms;
+    LatencyArb::new(&cfg).with_clock(clock.clone())
+}
+
+#[test]
+fn latency_arb_fires_the_dex_leg_on_a_cex_lead() {
+    let mut strategy = latency_arb(60_000, &Arc::new(SimClock::new(0)));
+    let dex = book(40.0);
+
+    // a move the DEX followed is no lead
+    strategy.on_price_update(&book(40.0), &dex);
+    strategy.on_price_update(&book(40.1), &book(40.1));
+    assert!(strategy.evaluate(&book(40.1), &book(40.1), GWEI).is_empty());
+
+    // CEX rallies 50 bps from the start of the window, the pool lags
+    let cex = book(40.2);
+    strategy.on_price_update(&cex, &dex);
+    assert!(strategy.lead_bps().unwrap() > 15.0);
+    let entry = strategy.evaluate(&cex, &dex, GWEI);
+    let [(ArbDirection::BuyDex, profit)] = entry.as_slice() else {
+        panic!("expected a BuyDex entry, got {:?}", entry);
+    };
+    assert!(profit.net > 0.0);
+    assert!(strategy.exposure().is_some());
+
+    // exposed: no second entry until the lead filled
+    assert!(strategy.evaluate(&cex, &dex, GWEI).is_empty());
+    assert!(strategy.exposure().is_some());
+    strategy.on_fill(&filled(ArbDirection::BuyDex));
+    assert!(strategy.exposure().is_none());
+}
+
+#[test]
+fn latency_arb_lets_an_unfilled_lead_go_after_max_exposure() {
+    let clock = Arc::new(SimClock::new(0));
+    let mut strategy = latency_arb(1_000, &clock);
+    let dex = book(40.0);
+
+    strategy.on_price_update(&book(40.0), &dex);
+    strategy.on_price_update(&book(39.8), &dex);
+    let entry = strategy.evaluate(&book(39.8), &dex, GWEI);
+    assert!(matches!(entry.as_slice(), [(ArbDirection::BuyCex, _)]), "expected a BuyCex entry, got {:?}", entry);
+
+    // no fill came back, and the exposure runs out on the strategy's clock
+    clock.set(999);
+    assert!(strategy.evaluate(&book(39.8), &dex, GWEI).is_empty());
+    assert!(strategy.exposure().is_some());
+    clock.set(1_000);
+    assert!(strategy.evaluate(&book(39.8), &dex, GWEI).is_empty());
+    assert!(strategy.exposure().is_none());
+}
+
+fn quote_manager() -> QuoteManager {
+    let mut cfg = settings();
+    cfg.mm_edge_bps = 20;
+    cfg.mm_requote_bps = 5;
+    cfg.mm_min_requote_ms = 500;
+    cfg.mm_quote_size = 1.0;
+    cfg.mm_hedge_min_size = 0.5;
+    QuoteManager::new(&cfg)
+}
+
+#[test]
+fn market_maker_requotes_on_drift_but_throttled() {
+    let mut quotes = quote_manager();
+    let start = Instant::now();
+    let dex = PriceData::new(40.0, 40.1);
+
+    assert_eq!(quotes.targets(&dex), (39.92, 40.181));
+    let actions = quotes.plan(&dex, start);
+    assert_eq!(
+        actions,
+        vec![
+            QuoteAction::Place { side: Side::Bid, px: 39.92, sz: 1.0 },
+            QuoteAction::Place { side: Side::Ask, px: 40.181, sz: 1.0 },
+        ]
+    );
+    quotes.on_placed(Side::Bid, 1, 39.92, 1.0, start);
+    quotes.on_placed(Side::Ask, 2, 40.181, 1.0, start);
+
+    // within the requote band nothing changes
+    let nudged = PriceData::new(40.01, 40.11);
+    assert!(quotes.plan(&nudged, start + Duration::from_secs(1)).is_empty());
+
+    // a real move is held back until the side may change again
+    let moved = PriceData::new(40.2, 40.3);
+    assert!(quotes.plan(&moved, start + Duration::from_millis(100)).is_empty());
+    let actions = quotes.plan(&moved, start + Duration::from_millis(600));
+    assert_eq!(actions.len(), 4);
+    assert_eq!(actions[0], QuoteAction::Cancel { side: Side::Bid, oid: 1 });
+    assert_eq!(actions[2], QuoteAction::Cancel { side: Side::Ask, oid: 2 });
+}
+
+#[test]
+fn market_maker_hedges_net_fills() {
+    let mut quotes = quote_manager();
+    let now = Instant::now();
+    quotes.on_placed(Side::Bid, 1, 39.92, 1.0, now);
+    quotes.on_placed(Side::Ask, 2, 40.18, 1.0, now);
+
+    assert_eq!(quotes.on_fill(Side::Bid, 0.3), 0.3);
+    assert!(quotes.hedge().is_none());
+
+    // cumulative fills only count once
+    assert_eq!(quotes.on_fill(Side::Bid, 0.8), 0.5);
+    assert_eq!(quotes.on_fill(Side::Bid, 0.8), 0.0);
+    let (direction, size) = quotes.hedge().unwrap();
+    assert!(matches!(direction, ArbDirection::BuyCex));
+    assert!((size - 0.8).abs() < 1e-9);
+
+    quotes.on_hedged(&direction, size);
+    quotes.on_fill(Side::Ask, 0.6);
+    let (direction, size) = quotes.hedge().unwrap();
+    assert!(matches!(direction, ArbDirection::BuyDex));
+    assert!((size - 0.6).abs() < 1e-9);
+}
+
+#[test]
+fn market_maker_waits_on_a_hedge_in_flight_and_backs_off_failures() {
+    let mut quotes = quote_manager();
+    let now = Instant::now();
+    quotes.on_placed(Side::Bid, 1, 39.92, 1.0, now);
+    quotes.on_fill(Side::Bid, 0.8);
+    let (direction, size) = quotes.hedge_at(now).unwrap();
+
+    // nothing offsets the inventory until the hedge confirmed
+    quotes.on_hedge_sent();
+    assert!(quotes.hedge_at(now).is_none());
+    assert_eq!(quotes.on_hedge_failed(now), Duration::from_secs(1));
+    assert!(quotes.hedge_at(now + Duration::from_millis(999)).is_none());
+    assert!(quotes.hedge_at(now + Duration::from_secs(1)).is_some());
+    assert_eq!(quotes.on_hedge_failed(now), Duration::from_secs(2));
+    for _ in 0..10 {
+        quotes.on_hedge_failed(now);
+    }
+    assert_eq!(quotes.on_hedge_failed(now), Duration::from_secs(60));
+
+    // a confirmed hedge clears the backoff
+    quotes.on_hedged(&direction, size);
+    assert!(quotes.unhedged().abs() < 1e-9);
+    quotes.on_placed(Side::Ask, 2, 40.18, 1.0, now);
+    quotes.on_fill(Side::Ask, 0.6);
+    assert!(matches!(quotes.hedge_at(now), Some((ArbDirection::BuyDex, _))));
+}
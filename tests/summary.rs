@@ -0,0 +1,132 @@
+use alloy::primitives::{Address, TxHash};
+use rust_arb_bot::arbitrage::{ArbDirection, PriceData};
+use rust_arb_bot::backtest::parse_date_ms;
+use rust_arb_bot::execution::confirmations::{TxState, TxUpdate};
+use rust_arb_bot::notify::Notification;
+use rust_arb_bot::opportunity::OpportunityId;
+use rust_arb_bot::pnl::{GasTotals, run_gas_ledger};
+use rust_arb_bot::summary::{DailySummary, format_date};
+use tokio::sync::{broadcast, watch};
+
+#[test]
+fn formats_dates_as_parsed_by_the_backtest() {
+    for date in ["1970-01-01", "2000-02-29", "2024-12-31", "2026-03-01"] {
+        let ms = parse_date_ms(date).unwrap();
+        assert_eq!(format_date(ms), date);
+        assert_eq!(format_date(ms + 86_399_999), date);
+    }
+}
+
+#[test]
+fn aggregates_opportunities_trades_and_uptime() {
+    let mut summary = DailySummary::new(parse_date_ms("2026-10-14").unwrap());
+
+    let opportunity = |venue: &str, net: f64| Notification::Opportunity {
+        ts_ms: 0,
+        id: OpportunityId::new(),
+        venue: venue.to_string(),
+        direction: ArbDirection::BuyDex,
+        gross: net + 0.5,
+        cex_fee: 0.3,
+        gas_cost: 0.2,
+        borrow_cost: 0.0,
+        net,
+        details: Default::default(),
+    };
+    summary.record(&opportunity("bybit", -0.5));
+    summary.record(&opportunity("bybit", 1.5));
+    summary.record(&opportunity("gateio", 0.7));
+    summary.record(&Notification::Trade {
+        ts_ms: 0,
+        opportunity_id: None,
+        venue: "bybit".to_string(),
+        account: None,
+        wallet: None,
+        dex_tx: TxHash::ZERO,
+        direction: ArbDirection::BuyDex,
+        base_amount: 2.0,
+        cex_price: 40.0,
+        cex_fee: 0.08,
+        borrow_cost: 0.0,
+        pnl: 1.2,
+        estimated_pnl: 1.5,
+    });
+    for up in [true, true, true, false] {
+        summary.sample_feed("bybit", up);
+    }
+
+    assert_eq!(summary.opportunities, 3);
+    assert_eq!(summary.best_net, 1.5);
+    assert_eq!(summary.trades, 1);
+    assert_eq!(summary.venues["bybit"].opportunities, 2);
+    assert_eq!(summary.venues["bybit"].pnl, 1.2);
+    assert_eq!(summary.venues["gateio"].trades, 0);
+    assert_eq!(summary.feeds["bybit"].ratio(), 0.75);
+
+    let text = summary.render_text();
+    assert!(text.contains("Daily summary 2026-10-14"));
+    assert!(text.contains("pnl $1.2000 (estimated $1.5000)"));
+    assert!(text.contains("bybit feed uptime: 75.00%"));
+    assert!(text.contains("gas: n/a"));
+
+    let html = summary.render_html();
+    assert!(html.contains("<tr><th>cex fees</th><td>$0.0800</td></tr>"));
+}
+
+#[test]
+fn breaks_trades_down_by_account_and_wallet() {
+    let mut summary = DailySummary::new(parse_date_ms("2026-10-14").unwrap());
+    let wallet = Address::repeat_byte(0x11);
+    let trade = |venue: &str, account: Option<&str>, pnl: f64| Notification::Trade {
+        ts_ms: 0,
+        opportunity_id: None,
+        venue: venue.to_string(),
+        account: account.map(str::to_string),
+        wallet: Some(wallet),
+        dex_tx: TxHash::ZERO,
+        direction: ArbDirection::BuyCex,
+        base_amount: 1.0,
+        cex_price: 40.0,
+        cex_fee: 0.04,
+        borrow_cost: 0.0,
+        pnl,
+        estimated_pnl: pnl,
+    };
+    summary.record(&trade("bybit", Some("bybit-mm"), 0.5));
+    summary.record(&trade("bybit", Some("bybit-mm"), 0.25));
+    // on the venue's own keys
+    summary.record(&trade("bybit", None, -0.1));
+
+    assert_eq!(summary.accounts["bybit-mm"].trades, 2);
+    assert_eq!(summary.accounts["bybit-mm"].pnl, 0.75);
+    assert_eq!(summary.accounts["bybit"].pnl, -0.1);
+    assert_eq!(summary.wallets[&wallet.to_string()].trades, 3);
+
+    let text = summary.render_text();
+    assert!(text.contains("account bybit-mm: 2 trades, 2.0000 base, pnl $0.7500"));
+    // a single wallet adds nothing to the totals
+    assert!(!text.contains("wallet "));
+}
+
+#[tokio::test]
+async fn books_gas_of_a_transaction_confirmed_again_after_a_reorg_once() {
+    let (updates_tx, updates_rx) = broadcast::channel(8);
+    let (_price_tx, price_rx) = watch::channel(Some(PriceData::new(40.0, 40.0)));
+    let (totals_tx, mut totals_rx) = watch::channel(GasTotals::default());
+    tokio::spawn(run_gas_ledger(updates_rx, price_rx, totals_tx));
+
+    let confirmed = |hash: u8, block_number: u64, gas_used: u64| TxUpdate {
+        hash: TxHash::repeat_byte(hash),
+        nonce: 0,
+        to: Address::ZERO,
+        selector: None,
+        state: TxState::Confirmed { block_number, success: true, gas_used, effective_gas_price: 10_000_000_000 },
+    };
+    for update in [confirmed(1, 1000, 100_000), confirmed(1, 1001, 100_000), confirmed(2, 1001, 300_000)] {
+        updates_tx.send(update).unwrap();
+    }
+
+    let totals = totals_rx.wait_for(|t| t.txs == 2).await.unwrap().clone();
+    assert!((totals.native - 0.004).abs() < 1e-12);
+    assert!((totals.usd - 0.16).abs() < 1e-9);
+}
@@ -0,0 +1,59 @@
+use rust_arb_bot::telemetry::{AttrValue, Tracer, otlp_json};
+use tokio::sync::mpsc;
+
+#[test]
+fn child_spans_join_the_parent_trace() {
+    let (tx, mut rx) = mpsc::channel(16);
+    let tracer = Tracer::with_sink(tx);
+
+    let mut tick = tracer.span("tick");
+    tick.set("venue", "bybit");
+    let traceparent = tick.traceparent().unwrap();
+    let mut evaluate = tick.child("evaluate");
+    evaluate.set("candidates", 2usize);
+    evaluate.end();
+    tick.end();
+
+    let (child, root) = (rx.try_recv().unwrap(), rx.try_recv().unwrap());
+    assert_eq!(child.name, "evaluate");
+    assert_eq!(child.trace_id, root.trace_id);
+    assert_eq!(child.parent_span_id, Some(root.span_id));
+    assert_eq!(root.parent_span_id, None);
+    assert!(root.end_ns >= child.end_ns && child.start_ns >= root.start_ns);
+    assert_eq!(root.attributes, vec![("venue", AttrValue::Str("bybit".to_string()))]);
+
+    // 00-<32 hex trace id>-<16 hex span id>-01
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    assert_eq!(parts, ["00", &format!("{:x}", root.trace_id), &format!("{:x}", root.span_id), "01"]);
+    assert_eq!((parts[1].len(), parts[2].len()), (32, 16));
+}
+
+#[test]
+fn disabled_tracer_exports_nothing() {
+    let mut span = Tracer::default().span("tick");
+    span.set("venue", "bybit");
+    assert!(span.traceparent().is_none());
+    assert!(span.child("evaluate").traceparent().is_none());
+}
+
+#[test]
+fn encodes_spans_as_otlp_json() {
+    let (tx, mut rx) = mpsc::channel(16);
+    let tracer = Tracer::with_sink(tx);
+    let mut span = tracer.span("tick");
+    span.set("candidates", 2usize);
+    span.child("gas_price").end();
+    span.end();
+    let spans = vec![rx.try_recv().unwrap(), rx.try_recv().unwrap()];
+
+    let body = otlp_json("arb-test", &spans);
+    let resource = &body["resourceSpans"][0];
+    assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "arb-test");
+
+    let exported = &resource["scopeSpans"][0]["spans"];
+    assert_eq!(exported[0]["name"], "gas_price");
+    assert_eq!(exported[0]["parentSpanId"], exported[1]["spanId"]);
+    assert!(exported[1].get("parentSpanId").is_none());
+    assert_eq!(exported[1]["attributes"][0]["value"]["intValue"], "2");
+    assert!(exported[1]["startTimeUnixNano"].as_str().unwrap().parse::<u64>().is_ok());
+}
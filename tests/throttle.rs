@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use rust_arb_bot::throttle::{RateLimit, TradeThrottle};
+use tokio::time::Instant;
+
+#[test]
+fn caps_executions_per_pair_and_overall() {
+    let throttle = TradeThrottle::new(
+        RateLimit { per_minute: Some(3), per_hour: None },
+        RateLimit { per_minute: Some(2), per_hour: Some(3) },
+    );
+    let start = Instant::now();
+    let at = |secs: u64| start + Duration::from_secs(secs);
+
+    throttle.acquire_at("default", at(0)).unwrap();
+    throttle.acquire_at("default", at(1)).unwrap();
+    assert!(throttle.acquire_at("default", at(2)).is_err());
+    // the pair limit leaves room elsewhere, until the global one is hit
+    throttle.acquire_at("kHYPE", at(3)).unwrap();
+    assert!(throttle.acquire_at("kHYPE", at(4)).is_err());
+
+    // a minute later only the hourly pair limit is left to hit
+    throttle.acquire_at("default", at(61)).unwrap();
+    assert!(throttle.acquire_at("default", at(62)).unwrap_err().to_string().contains("per hour"));
+    throttle.acquire_at("default", at(3601)).unwrap();
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use alloy::primitives::{I256, U256, uint};
+use anyhow::Result;
+
+use rust_arb_bot::dex::univ3_math::{
+    MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK, PoolState, TickData, amount0_delta, amount1_delta,
+    sqrt_ratio_at_tick, swap,
+};
+
+// bitmap words and liquidityNet of a pool's initialized ticks
+#[derive(Default)]
+struct Ticks {
+    words: HashMap<i16, U256>,
+    nets: HashMap<i32, i128>,
+}
+
+impl Ticks {
+    // a position of `liquidity` over [lower, upper)
+    fn with_position(mut self, lower: i32, upper: i32, spacing: i32, liquidity: i128) -> Self {
+        for (tick, net) in [(lower, liquidity), (upper, -liquidity)] {
+            let compressed = tick.div_euclid(spacing);
+            *self.words.entry((compressed >> 8) as i16).or_default() |= U256::from(1) << (compressed & 0xff) as usize;
+            *self.nets.entry(tick).or_default() += net;
+        }
+        self
+    }
+}
+
+impl TickData for Ticks {
+    fn bitmap_word(&mut self, word: i16) -> Result<U256> {
+        Ok(self.words.get(&word).copied().unwrap_or_default())
+    }
+
+    fn liquidity_net(&mut self, tick: i32) -> Result<i128> {
+        Ok(self.nets[&tick])
+    }
+}
+
+fn at_tick(tick: i32, liquidity: u128) -> PoolState {
+    PoolState { sqrt_price_x96: sqrt_ratio_at_tick(tick), tick, liquidity }
+}
+
+#[test]
+fn sqrt_ratios_match_tick_math() {
+    assert_eq!(sqrt_ratio_at_tick(0), U256::from(1) << 96);
+    assert_eq!(sqrt_ratio_at_tick(MIN_TICK), MIN_SQRT_RATIO);
+    assert_eq!(sqrt_ratio_at_tick(MAX_TICK), MAX_SQRT_RATIO);
+    assert_eq!(sqrt_ratio_at_tick(-1), uint!(79224201403219477170569942574_U256));
+    assert_eq!(sqrt_ratio_at_tick(1), uint!(79232123823359799118286999568_U256));
+
+    // every bit of the tick against floating point
+    for bit in 0..20 {
+        for tick in [1 << bit, -(1 << bit)] {
+            let exact = f64::from(sqrt_ratio_at_tick(tick)) / 2f64.powi(96);
+            let approx = 1.0001f64.powf(tick as f64 / 2.0);
+            assert!((exact / approx - 1.0).abs() < 1e-9, "tick {tick}: {exact} vs {approx}");
+        }
+    }
+}
+
+#[test]
+fn a_swap_within_range_moves_along_the_curve() {
+    let spacing = 60;
+    let liquidity = 10u128.pow(24);
+    let mut ticks = Ticks::default().with_position(-6000, 6000, spacing, liquidity as i128);
+    let pool = at_tick(0, liquidity);
+    let amount = U256::from(10u64.pow(18));
+
+    let sold = swap(pool, &mut ticks, spacing, 3000, true, I256::from_raw(amount)).unwrap();
+    assert_eq!(sold.amount_in, amount);
+    // the output is what the curve gives between the prices, less the fee
+    assert_eq!(sold.amount_out, amount1_delta(sold.sqrt_price_x96, pool.sqrt_price_x96, liquidity, false));
+    assert!(sold.amount_out < amount * U256::from(997) / U256::from(1000));
+
+    // an exact output pays the curve's input plus the fee on top
+    let bought = swap(pool, &mut ticks, spacing, 3000, false, -I256::from_raw(amount)).unwrap();
+    assert_eq!(bought.amount_out, amount);
+    assert_eq!(bought.amount_in, with_fee(amount1_delta(pool.sqrt_price_x96, bought.sqrt_price_x96, liquidity, true)));
+}
+
+// amount in plus the 0.3% fee, rounded up like the pool
+fn with_fee(amount: U256) -> U256 {
+    let fee = amount * U256::from(3000);
+    amount + fee / U256::from(997_000) + U256::from(!(fee % U256::from(997_000)).is_zero() as u8)
+}
+
+#[test]
+fn swaps_cross_initialized_ticks() {
+    let spacing = 60;
+    let inner = 10u128.pow(20);
+    let outer = 10u128.pow(22);
+    // a thin range around the price inside a deep one
+    let mut ticks = Ticks::default()
+        .with_position(-120, 120, spacing, inner as i128)
+        .with_position(-60000, 60000, spacing, outer as i128);
+    let pool = at_tick(0, inner + outer);
+
+    // selling token0 past tick -120 leaves only the deep range
+    let to_edge = amount0_delta(sqrt_ratio_at_tick(-120), pool.sqrt_price_x96, inner + outer, true);
+    let amount = to_edge * U256::from(3);
+    let sold = swap(pool, &mut ticks, spacing, 500, true, I256::from_raw(amount)).unwrap();
+    assert!(sold.sqrt_price_x96 < sqrt_ratio_at_tick(-120));
+    assert_eq!(sold.amount_in, amount);
+
+    // the same amount with the thin range spread everywhere moves less
+    let mut flat = Ticks::default().with_position(-60000, 60000, spacing, (inner + outer) as i128);
+    let deep = swap(pool, &mut flat, spacing, 500, true, I256::from_raw(amount)).unwrap();
+    assert!(deep.sqrt_price_x96 > sold.sqrt_price_x96);
+    assert!(deep.amount_out > sold.amount_out);
+
+    // an exact output beyond every range fails like the quoter
+    let reserves = amount1_delta(sqrt_ratio_at_tick(-60000), pool.sqrt_price_x96, outer, false);
+    assert!(swap(pool, &mut ticks, spacing, 500, true, -I256::from_raw(reserves * U256::from(2))).is_err());
+}
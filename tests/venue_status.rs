@@ -0,0 +1,50 @@
+use rust_arb_bot::adapters::bybit::maintenance_windows;
+use rust_arb_bot::arbitrage::ArbDirection;
+use rust_arb_bot::switches::TradingSwitches;
+use rust_arb_bot::venue_status::{MaintenanceWindow, active_window, apply};
+use serde_json::json;
+
+#[test]
+fn parses_announced_bybit_maintenance() {
+    let status = json!({
+        "list": [
+            {"id": "a", "title": "System upgrade", "state": "scheduled", "begin": "1000000", "end": "2000000"},
+            {"id": "b", "title": "Old upgrade", "state": "completed", "begin": "100", "end": "200"},
+            {"id": "c", "title": "Hotfix", "state": "ongoing", "begin": "500", "end": ""},
+        ]
+    });
+
+    let windows = maintenance_windows(&status);
+    assert_eq!(
+        windows,
+        vec![
+            MaintenanceWindow { title: "System upgrade".to_string(), begin_ms: 1_000_000, end_ms: Some(2_000_000) },
+            MaintenanceWindow { title: "Hotfix".to_string(), begin_ms: 500, end_ms: None },
+        ]
+    );
+    assert!(maintenance_windows(&json!({})).is_empty());
+}
+
+#[test]
+fn halts_one_venue_around_its_window() {
+    let window = MaintenanceWindow { title: "System upgrade".to_string(), begin_ms: 100_000, end_ms: Some(200_000) };
+    let windows = [window.clone()];
+    let margin = 10_000;
+    assert!(active_window(&windows, 89_999, margin).is_none());
+    assert_eq!(active_window(&windows, 90_000, margin), Some(&window));
+    assert!(active_window(&windows, 210_000, margin).is_none());
+
+    let switches = TradingSwitches::default();
+    let shared = switches.clone();
+    assert!(!apply(&switches, "bybit", &windows, 50_000, margin));
+    assert!(apply(&switches, "bybit", &windows, 95_000, margin));
+    // already halted
+    assert!(!apply(&switches, "bybit", &windows, 150_000, margin));
+
+    let refused = shared.check("bybit", &ArbDirection::BuyDex).unwrap_err();
+    assert!(refused.to_string().contains("System upgrade"));
+    shared.check("gateio", &ArbDirection::BuyDex).unwrap();
+
+    assert!(apply(&switches, "bybit", &windows, 210_000, margin));
+    shared.check("bybit", &ArbDirection::BuyDex).unwrap();
+}
@@ -0,0 +1,30 @@
+use alloy::primitives::U256;
+
+use rust_arb_bot::execution::dex::SwapMode;
+use rust_arb_bot::execution::verify::VerifiedFill;
+
+fn fill(amount_in: u64, amount_out: u64, quoted_out: u64, gas_used: u64, gas_price: u128) -> VerifiedFill {
+    VerifiedFill {
+        amount_in: U256::from(amount_in),
+        amount_out: U256::from(amount_out),
+        quoted_in: U256::from(amount_in),
+        quoted_out: U256::from(quoted_out),
+        shortfall_bps: 0.0,
+        gas_used,
+        effective_gas_price: gas_price,
+    }
+}
+
+#[test]
+fn split_legs_combine_into_one_fill() {
+    let legs = [fill(600, 5_970, 6_000, 100_000, 2), fill(400, 4_000, 4_000, 300_000, 4)];
+
+    let combined = VerifiedFill::combine(SwapMode::ExactInput, &legs).unwrap();
+
+    assert_eq!((combined.amount_in, combined.amount_out), (U256::from(1_000), U256::from(9_970)));
+    assert_eq!(combined.quoted_out, U256::from(10_000));
+    assert!((combined.shortfall_bps - 30.0).abs() < 1e-9);
+    // gas priced at what all legs paid together
+    assert_eq!((combined.gas_used, combined.effective_gas_price), (400_000, 3));
+    assert!(VerifiedFill::combine(SwapMode::ExactInput, &[]).is_none());
+}
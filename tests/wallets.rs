@@ -0,0 +1,56 @@
+mod common;
+
+use std::sync::Arc;
+
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use rust_arb_bot::execution::confirmations::ConfirmationTracker;
+use rust_arb_bot::execution::sender::TxSender;
+use rust_arb_bot::execution::wallets::WalletPool;
+use rust_arb_bot::settings::WalletSelection;
+
+use common::settings;
+
+async fn pool(wallets: usize) -> (WalletPool, Vec<Address>) {
+    let mut pool = WalletPool::new(WalletSelection::RoundRobin);
+    for _ in 0..wallets {
+        // nothing is sent, the endpoint is never dialed
+        let sender = Arc::new(TxSender::connect("http://127.0.0.1:1", PrivateKeySigner::random(), Address::ZERO).await.unwrap());
+        let (tracker, _) = ConfirmationTracker::new(&settings(), sender.clone());
+        pool.add(sender, tracker.handle());
+    }
+    let addresses = pool.addresses();
+    (pool, addresses)
+}
+
+#[tokio::test]
+async fn rotates_through_idle_wallets() {
+    let (pool, wallets) = pool(3).await;
+
+    let first = pool.lease(None).unwrap();
+    let second = pool.lease(None).unwrap();
+    assert_eq!((first.address(), second.address()), (wallets[0], wallets[1]));
+    drop(first);
+    // the next in turn, then back to the first as the second is still busy
+    assert_eq!(pool.lease(None).unwrap().address(), wallets[2]);
+    assert_eq!(pool.lease(None).unwrap().address(), wallets[0]);
+
+    let (_a, _b, _c) = (pool.lease(None).unwrap(), pool.lease(None).unwrap(), second);
+    let err = pool.lease(None).map(|w| w.address()).unwrap_err();
+    assert!(err.to_string().contains("all 3 wallets have a swap in flight"));
+
+    let (empty, _) = self::pool(0).await;
+    assert!(empty.lease(None).is_err());
+}
+
+#[tokio::test]
+async fn picks_the_idle_wallet_holding_the_most() {
+    let (pool, wallets) = pool(3).await;
+    let balances = [U256::from(5), U256::from(9), U256::from(7)];
+
+    let richest = pool.lease(Some(&balances)).unwrap();
+    assert_eq!(richest.address(), wallets[1]);
+    assert_eq!(pool.lease(Some(&balances)).unwrap().address(), wallets[2]);
+    drop(richest);
+    assert_eq!(pool.lease(Some(&balances)).unwrap().address(), wallets[1]);
+}